@@ -1,3 +1,5 @@
+import { execFile } from "node:child_process";
+
 function isObject(value) {
   return Boolean(value) && typeof value === "object" && !Array.isArray(value);
 }
@@ -272,6 +274,67 @@ function compactToolResultMessage(message, toolName, pluginConfig) {
   return { ...message, content: nextContent, details };
 }
 
+function resolveRecallConfig(pluginConfig) {
+  const recallCfg = isObject(pluginConfig.recall) ? pluginConfig.recall : {};
+  return {
+    enabled: recallCfg.enabled !== false,
+    moonBin:
+      typeof recallCfg.moonBin === "string" && recallCfg.moonBin.trim()
+        ? recallCfg.moonBin.trim()
+        : "moon",
+    timeoutMs: clampInt(recallCfg.timeoutMs, 8000, 100, 30000),
+    maxBytes: clampInt(recallCfg.maxBytes, 16384, 1024, 262144),
+    maxResults: clampInt(recallCfg.maxResults, 5, 1, 20),
+  };
+}
+
+// One request per call over `moon recall --rpc` (newline-delimited JSON on stdin/stdout).
+// Runs asynchronously so a slow recall never blocks the gateway's event loop; the child is
+// killed once the timeout passes.
+function runMoonRecall(params, recallCfg) {
+  const request = {
+    id: 1,
+    query: String((params && params.query) || ""),
+    channel_key: params && params.channelKey ? String(params.channelKey) : undefined,
+    collection: params && params.collection ? String(params.collection) : undefined,
+    scope: params && params.scope ? String(params.scope) : undefined,
+    max_results: recallCfg.maxResults,
+    max_bytes: recallCfg.maxBytes,
+    timeout_ms: recallCfg.timeoutMs,
+  };
+  const timeoutMs = recallCfg.timeoutMs + 2000;
+  return new Promise((resolve) => {
+    const child = execFile(
+      recallCfg.moonBin,
+      ["--allow-out-of-bounds", "recall", "--rpc"],
+      { encoding: "utf8", timeout: timeoutMs, killSignal: "SIGKILL" },
+      (error, stdout, stderr) => {
+        const line = String(stdout || "")
+          .split("\n")
+          .find((value) => value.trim());
+        if (!line) {
+          let reason = `moon recall returned no response: ${String(stderr || "").trim()}`;
+          if (error && error.killed) {
+            reason = `moon recall timed out after ${timeoutMs}ms`;
+          } else if (error && typeof error.code === "string") {
+            reason = `moon recall failed: ${error.message}`;
+          }
+          resolve({ ok: false, error: reason, matches: [] });
+          return;
+        }
+        try {
+          resolve(JSON.parse(line));
+        } catch {
+          resolve({ ok: false, error: "moon recall returned invalid JSON", matches: [] });
+        }
+      },
+    );
+    // moon may exit before reading stdin (e.g. a bad binary); that surfaces via the callback.
+    child.stdin.on("error", () => {});
+    child.stdin.end(`${JSON.stringify(request)}\n`);
+  });
+}
+
 export default {
   id: "moon",
   register(api) {
@@ -281,5 +344,37 @@ export default {
       const next = compactToolResultMessage(event.message, toolName, pluginCfg);
       return { message: next };
     });
+
+    const pluginCfg = isObject(api && api.pluginConfig) ? api.pluginConfig : {};
+    const recallCfg = resolveRecallConfig(pluginCfg);
+    if (!recallCfg.enabled || typeof api.registerTool !== "function") {
+      return;
+    }
+    api.registerTool({
+      name: "moon_recall",
+      description:
+        "Search moon session archives for past conversations, decisions and pre-compaction context.",
+      parameters: {
+        type: "object",
+        additionalProperties: false,
+        properties: {
+          query: { type: "string", description: "What to look up in archived history." },
+          channelKey: { type: "string", description: "Optional session key to pin its latest archive." },
+          collection: { type: "string", description: "Optional qmd collection (default: routed from channel_key via moon [collections])." },
+          scope: {
+            type: "string",
+            enum: ["archives", "memory", "all"],
+            description: "archives (default): raw session history; memory: distilled daily summaries; all: both.",
+          },
+        },
+        required: ["query"],
+      },
+      async execute(_toolCallId, params) {
+        const response = await runMoonRecall(params, recallCfg);
+        return {
+          content: [{ type: "text", text: JSON.stringify(response, null, 2) }],
+        };
+      },
+    });
   },
 };
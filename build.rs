@@ -65,6 +65,30 @@ fn write_generated_allowlist() -> std::io::Result<()> {
     Ok(())
 }
 
+/// Short commit of the source tree, or `unknown` outside a git checkout (e.g. crates.io).
+fn git_sha() -> String {
+    std::process::Command::new("git")
+        .args(["rev-parse", "--short=12", "HEAD"])
+        .output()
+        .ok()
+        .filter(|out| out.status.success())
+        .map(|out| String::from_utf8_lossy(&out.stdout).trim().to_string())
+        .filter(|sha| !sha.is_empty())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Cargo features enabled for this build, comma-separated.
+fn enabled_features() -> String {
+    let mut features: Vec<String> = env::vars()
+        .filter_map(|(key, _)| {
+            key.strip_prefix("CARGO_FEATURE_")
+                .map(|name| name.to_ascii_lowercase().replace('_', "-"))
+        })
+        .collect();
+    features.sort();
+    features.join(",")
+}
+
 fn main() {
     write_generated_allowlist().expect("failed to generate MOON env allowlist");
 
@@ -77,6 +101,9 @@ fn main() {
     let build_id = format!("{:x}-{:x}", now.as_secs(), now.subsec_nanos());
 
     println!("cargo:rustc-env=BUILD_UUID={}", build_id);
+    println!("cargo:rustc-env=BUILD_GIT_SHA={}", git_sha());
+    println!("cargo:rustc-env=BUILD_FEATURES={}", enabled_features());
     println!("cargo:rerun-if-changed=build.rs");
     println!("cargo:rerun-if-changed=src");
+    println!("cargo:rerun-if-changed=.git/HEAD");
 }
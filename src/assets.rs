@@ -16,6 +16,31 @@ pub fn plugin_asset_contents() -> [(&'static str, &'static str); 4] {
     ]
 }
 
+/// `version` from a plugin `package.json` payload.
+pub fn package_version(raw: &str) -> Option<String> {
+    serde_json::from_str::<serde_json::Value>(raw)
+        .ok()?
+        .get("version")?
+        .as_str()
+        .map(str::to_string)
+}
+
+/// Plugin version shipped inside this binary.
+pub fn embedded_plugin_version() -> String {
+    package_version(PACKAGE_JSON).unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Plugin version currently installed in `plugin_dir`, if its `package.json` is readable.
+pub fn installed_plugin_version(plugin_dir: &Path) -> Option<String> {
+    package_version(&fs::read_to_string(plugin_dir.join("package.json")).ok()?)
+}
+
+/// Installed asset content equals the embedded copy, ignoring CRLF line endings that Windows
+/// checkouts and editors introduce.
+pub fn asset_matches(current: &str, expected: &str) -> bool {
+    current == expected || current.replace("\r\n", "\n") == expected.replace("\r\n", "\n")
+}
+
 pub fn write_plugin_assets(target_dir: &Path) -> Result<()> {
     fs::create_dir_all(target_dir)?;
     for (name, content) in plugin_asset_contents() {
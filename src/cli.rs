@@ -28,14 +28,35 @@ pub enum Command {
     Stop,
     Restart,
     Snapshot(MoonSnapshotArgs),
+    Compact(MoonCompactArgs),
+    Sessions,
+    /// Per-session usage with its recent trend, flagging sessions the next cycle will trigger.
+    Usage,
     Index(MoonIndexArgs),
     Watch(MoonWatchArgs),
     Embed(MoonEmbedArgs),
     Recall(MoonRecallArgs),
+    Memory(MoonMemoryArgs),
+    Graph(MoonGraphArgs),
+    Ledger(MoonLedgerArgs),
+    Gc(MoonGcArgs),
+    Report(MoonReportArgs),
+    /// Query the audit log by phase, cycle or structured detail fields.
+    Audit(MoonAuditArgs),
+    Continuity(MoonContinuityArgs),
+    /// Merge projection timelines across sessions for a day and/or channel.
+    Timeline(MoonTimelineArgs),
+    /// Show the openclaw config changes of the prune safety profile; `--apply` writes them,
+    /// `restore --from <backup>` rolls the config back to a saved copy.
+    Prune(MoonPruneArgs),
     #[command(name = "distill")]
     Distill(DistillArgs),
     Config(ConfigArgs),
-    Health,
+    Health(HealthArgs),
+    Bench(MoonBenchArgs),
+    Init(MoonInitArgs),
+    /// Build metadata: version, git sha, BUILD_UUID, features and providers.
+    Version,
 }
 
 #[derive(Debug, Args)]
@@ -46,12 +67,30 @@ pub struct InstallArgs {
     pub dry_run: bool,
     #[arg(long, default_value_t = true, action = clap::ArgAction::Set)]
     pub apply: bool,
+    /// Install the plugin under this directory instead of `<openclaw state>/extensions`.
+    #[arg(long, value_name = "DIR")]
+    pub prefix: Option<PathBuf>,
+    /// Write Homebrew, scoop and deb packaging metadata here and exit.
+    #[arg(long, value_name = "DIR")]
+    pub packaging_dir: Option<PathBuf>,
+    /// Keep the plugin at this asset version across binary upgrades.
+    #[arg(long, value_name = "VERSION", conflicts_with = "unpin")]
+    pub pin: Option<String>,
+    /// Drop a recorded pin so the embedded assets are installed again.
+    #[arg(long)]
+    pub unpin: bool,
+    /// Upgrade channel: `stable` (default) or `beta` (accepts prerelease assets).
+    #[arg(long)]
+    pub channel: Option<String>,
 }
 
 #[derive(Debug, Args, Default)]
 pub struct VerifyArgs {
     #[arg(long)]
     pub strict: bool,
+    /// Limit checks to `plugin`, `config` and/or `moon` (repeatable or comma-separated).
+    #[arg(long, value_delimiter = ',')]
+    pub only: Vec<String>,
 }
 
 #[derive(Debug, Args, Default)]
@@ -68,16 +107,32 @@ pub struct MoonSnapshotArgs {
     pub dry_run: bool,
 }
 
+#[derive(Debug, Args)]
+pub struct MoonCompactArgs {
+    pub session_key: String,
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
 #[derive(Debug, Args)]
 pub struct MoonIndexArgs {
-    #[arg(long, default_value = "history")]
-    pub name: String,
+    #[arg(long)]
+    pub name: Option<String>,
+    #[arg(long)]
+    pub reindex_all: bool,
+    #[arg(long)]
+    pub limit: Option<usize>,
+    #[arg(long, requires = "reindex_all")]
+    pub keep_prev: bool,
     #[arg(long)]
     pub dry_run: bool,
 }
 
 #[derive(Debug, Args, Default)]
+#[command(args_conflicts_with_subcommands = true)]
 pub struct MoonWatchArgs {
+    #[command(subcommand)]
+    pub command: Option<MoonWatchCommand>,
     #[arg(long)]
     pub once: bool,
     #[arg(long)]
@@ -86,26 +141,273 @@ pub struct MoonWatchArgs {
     pub dry_run: bool,
 }
 
+#[derive(Debug, Subcommand)]
+pub enum MoonWatchCommand {
+    /// Keep collecting usage and heartbeats but skip every mutating action until `resume`.
+    Pause(MoonWatchPauseArgs),
+    Resume,
+}
+
+#[derive(Debug, Args)]
+pub struct MoonWatchPauseArgs {
+    #[arg(long)]
+    pub reason: Option<String>,
+}
+
 #[derive(Debug, Args)]
 pub struct MoonRecallArgs {
+    #[arg(long, required_unless_present_any = ["rpc", "day"])]
+    pub query: Option<String>,
+    /// Daily memory day (`YYYY-MM-DD`) whose distilled sections come before archive matches.
+    #[arg(long, conflicts_with = "rpc")]
+    pub day: Option<String>,
     #[arg(long)]
-    pub query: String,
-    #[arg(long, default_value = "history")]
-    pub name: String,
+    pub name: Option<String>,
     #[arg(long)]
     pub channel_key: Option<String>,
+    #[arg(long, default_value = "archives")]
+    pub scope: String,
+    #[arg(long, conflicts_with = "query")]
+    pub rpc: bool,
+    #[arg(long, conflicts_with = "rpc")]
+    pub open: Option<usize>,
+    #[arg(long, default_value_t = 8, requires = "open")]
+    pub context: usize,
+    #[arg(long, requires = "open")]
+    pub export: Option<PathBuf>,
+    #[arg(long, conflicts_with_all = ["rpc", "open"])]
+    pub max_tokens: Option<usize>,
+    #[arg(long, conflicts_with_all = ["rpc", "open"])]
+    pub fields: Option<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct MoonMemoryArgs {
+    #[command(subcommand)]
+    pub command: MoonMemoryCommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum MoonMemoryCommand {
+    Diff(MoonMemoryDiffArgs),
+    Inject(MoonMemoryInjectArgs),
+    Export(MoonMemoryExportArgs),
+    Import(MoonMemoryImportArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct MoonMemoryDiffArgs {
+    #[arg(long, default_value = "7d")]
+    pub since: String,
+}
+
+#[derive(Debug, Args)]
+pub struct MoonMemoryInjectArgs {
+    pub session_key: String,
+    #[arg(long)]
+    pub max_tokens: Option<u64>,
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct MoonMemoryExportArgs {
+    #[arg(long, default_value = "json")]
+    pub format: String,
+    #[arg(long)]
+    pub output: Option<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct MoonMemoryImportArgs {
+    pub path: String,
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct MoonLedgerArgs {
+    #[command(subcommand)]
+    pub command: MoonLedgerCommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum MoonLedgerCommand {
+    Compact(MoonLedgerCompactArgs),
+    Decrypt(MoonLedgerDecryptArgs),
+    SkipDistill(MoonLedgerSkipDistillArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct MoonLedgerCompactArgs {
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct MoonLedgerDecryptArgs {
+    #[arg(long)]
+    pub archive: PathBuf,
+    #[arg(long)]
+    pub output: PathBuf,
+}
+
+#[derive(Debug, Args)]
+pub struct MoonLedgerSkipDistillArgs {
+    #[arg(long)]
+    pub archive: String,
+    /// Distill the archive again.
+    #[arg(long)]
+    pub clear: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct MoonGcArgs {
+    #[command(subcommand)]
+    pub command: MoonGcCommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum MoonGcCommand {
+    Purge(MoonGcPurgeArgs),
+    Restore(MoonGcRestoreArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct MoonGcPurgeArgs {
+    #[arg(long)]
+    pub all: bool,
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct MoonGcRestoreArgs {
+    pub path: String,
+}
+
+#[derive(Debug, Args)]
+pub struct MoonGraphArgs {
+    #[command(subcommand)]
+    pub command: MoonGraphCommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum MoonGraphCommand {
+    Query(MoonGraphQueryArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct MoonGraphQueryArgs {
+    pub entity: String,
+    #[arg(long, default_value_t = 50)]
+    pub limit: usize,
+}
+
+#[derive(Debug, Args)]
+pub struct MoonAuditArgs {
+    #[arg(long)]
+    pub phase: Option<String>,
+    #[arg(long)]
+    pub status: Option<String>,
+    #[arg(long = "cycle", value_name = "CYCLE_ID")]
+    pub cycle_id: Option<String>,
+    /// Match a detail field, e.g. `--where session=abc`; repeatable, all must match.
+    #[arg(long = "where", value_name = "KEY=VALUE")]
+    pub filters: Vec<String>,
+    #[arg(long, default_value_t = 50)]
+    pub limit: usize,
+}
+
+#[derive(Debug, Args)]
+pub struct MoonReportArgs {
+    #[command(subcommand)]
+    pub command: MoonReportCommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum MoonReportCommand {
+    Daily(MoonReportDailyArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct MoonReportDailyArgs {
+    #[arg(long)]
+    pub day: Option<String>,
+    #[arg(long)]
+    pub notify: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct MoonContinuityArgs {
+    #[command(subcommand)]
+    pub command: MoonContinuityCommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum MoonContinuityCommand {
+    Show(MoonContinuityShowArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct MoonContinuityShowArgs {
+    pub channel: String,
+}
+
+#[derive(Debug, Args)]
+#[command(args_conflicts_with_subcommands = true)]
+pub struct MoonPruneArgs {
+    #[command(subcommand)]
+    pub command: Option<MoonPruneCommand>,
+    /// Write the planned changes; without it only the diff is printed.
+    #[arg(long)]
+    pub apply: bool,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum MoonPruneCommand {
+    /// Replace the openclaw config with a backup from `MOON_HOME/backups`.
+    Restore(MoonPruneRestoreArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct MoonPruneRestoreArgs {
+    /// Backup file name in `MOON_HOME/backups`, or a path to a backup file.
+    #[arg(long, value_name = "BACKUP")]
+    pub from: String,
+}
+
+#[derive(Debug, Args)]
+pub struct MoonTimelineArgs {
+    /// Residential day (YYYY-MM-DD); defaults to today unless `--channel` is given.
+    #[arg(long)]
+    pub day: Option<String>,
+    /// Only archives mapped to this channel key, past sessions included.
+    #[arg(long)]
+    pub channel: Option<String>,
+    #[arg(long, default_value = "markdown")]
+    pub format: String,
+    #[arg(long)]
+    pub output: Option<String>,
+    /// Entries to print in the report; the output file always has all of them.
+    #[arg(long, default_value_t = 50)]
+    pub limit: usize,
 }
 
 #[derive(Debug, Args)]
 pub struct MoonEmbedArgs {
-    #[arg(long, default_value = "history")]
-    pub name: String,
+    #[arg(long)]
+    pub name: Option<String>,
     #[arg(long, default_value_t = 25)]
     pub max_docs: usize,
     #[arg(long)]
     pub dry_run: bool,
     #[arg(long)]
     pub watcher_trigger: bool,
+    #[arg(long, conflicts_with_all = ["verify", "watcher_trigger"])]
+    pub rebuild: bool,
+    #[arg(long, conflicts_with_all = ["dry_run", "watcher_trigger"])]
+    pub verify: bool,
 }
 
 #[derive(Debug, Args)]
@@ -122,12 +424,118 @@ pub struct DistillArgs {
     pub dry_run: bool,
 }
 
+#[derive(Debug, Args)]
+pub struct MoonInitArgs {
+    /// Workspace root to set up (default: `MOON_HOME`, else the home directory).
+    #[arg(long, value_name = "DIR")]
+    pub moon_home: Option<PathBuf>,
+    /// Accept defaults without prompting.
+    #[arg(long, short = 'y')]
+    pub yes: bool,
+    /// Also install the OpenClaw plugin and watcher service.
+    #[arg(long)]
+    pub install: bool,
+    /// Overwrite existing `moon.toml` and `.env`.
+    #[arg(long)]
+    pub force: bool,
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct MoonBenchArgs {
+    #[arg(long, default_value_t = 16)]
+    pub archive_mb: u64,
+    #[arg(long, default_value_t = 10_000)]
+    pub ledger_records: usize,
+    #[arg(long, default_value_t = 3)]
+    pub iterations: usize,
+    #[arg(long)]
+    pub scratch_dir: Option<PathBuf>,
+    #[arg(long)]
+    pub keep: bool,
+}
+
+#[derive(Debug, Args, Default)]
+pub struct HealthArgs {
+    /// Drop or repoint dangling ledger, channel map, and state references.
+    #[arg(long)]
+    pub repair: bool,
+}
+
 #[derive(Debug, Args, Default)]
 pub struct ConfigArgs {
+    #[command(subcommand)]
+    pub command: Option<MoonConfigCommand>,
     #[arg(long)]
     pub show: bool,
 }
 
+#[derive(Debug, Subcommand)]
+pub enum MoonConfigCommand {
+    /// Make one minimal authenticated call per configured provider key and report its status.
+    CheckKeys,
+}
+
+impl Command {
+    /// Operation name to refuse under `MOON_READ_ONLY`; `None` for commands
+    /// that only read MOON_HOME.
+    fn mutating_operation(&self) -> Option<&'static str> {
+        match self {
+            Command::Status
+            | Command::Health(HealthArgs { repair: false })
+            | Command::Version
+            | Command::Verify(_)
+            | Command::Sessions
+            | Command::Usage
+            | Command::Recall(_)
+            | Command::Graph(_)
+            | Command::Continuity(_)
+            | Command::Timeline(_)
+            | Command::Audit(_)
+            | Command::Config(_) => None,
+            Command::Embed(args) if args.verify => None,
+            Command::Prune(args) => match &args.command {
+                Some(MoonPruneCommand::Restore(_)) => Some("prune restore"),
+                None if args.apply => Some("prune --apply"),
+                None => None,
+            },
+            Command::Health(_) => Some("health --repair"),
+            Command::Memory(args) => match &args.command {
+                MoonMemoryCommand::Diff(_) | MoonMemoryCommand::Export(_) => None,
+                MoonMemoryCommand::Inject(_) => Some("memory inject"),
+                MoonMemoryCommand::Import(_) => Some("memory import"),
+            },
+            Command::Install(_) => Some("install"),
+            Command::Repair(_) => Some("repair"),
+            Command::Stop => Some("stop"),
+            Command::Restart => Some("restart"),
+            Command::Snapshot(_) => Some("snapshot"),
+            Command::Compact(_) => Some("compact"),
+            Command::Index(_) => Some("index"),
+            Command::Watch(args) => match &args.command {
+                Some(MoonWatchCommand::Pause(_)) => Some("watch pause"),
+                Some(MoonWatchCommand::Resume) => Some("watch resume"),
+                None => Some("watch"),
+            },
+            Command::Embed(_) => Some("embed"),
+            Command::Ledger(args) => match &args.command {
+                MoonLedgerCommand::Compact(_) => Some("ledger compact"),
+                MoonLedgerCommand::Decrypt(_) => None,
+                MoonLedgerCommand::SkipDistill(_) => Some("ledger skip-distill"),
+            },
+            Command::Gc(args) => match &args.command {
+                MoonGcCommand::Purge(_) => Some("gc purge"),
+                MoonGcCommand::Restore(_) => Some("gc restore"),
+            },
+            Command::Report(_) => Some("report daily"),
+            Command::Distill(_) => Some("distill"),
+            Command::Bench(_) => Some("bench"),
+            Command::Init(_) => Some("init"),
+        }
+    }
+}
+
 fn print_report(report: &commands::CommandReport, as_json: bool) -> Result<()> {
     if as_json {
         println!("{}", serde_json::to_string_pretty(report)?);
@@ -146,6 +554,21 @@ fn print_report(report: &commands::CommandReport, as_json: bool) -> Result<()> {
         println!("issues:");
         for issue in &report.issues {
             println!("- {issue}");
+            if let Some(hint) = &issue.hint {
+                println!("  hint: {hint}");
+            }
+        }
+    }
+    if !report.warnings.is_empty() {
+        println!("warnings:");
+        for warning in &report.warnings {
+            println!("- {warning}");
+        }
+    }
+    if !report.info.is_empty() {
+        println!("info:");
+        for info in &report.info {
+            println!("- {info}");
         }
     }
     Ok(())
@@ -186,22 +609,56 @@ pub fn run() -> Result<()> {
 
     // Every command validates CWD except diagnostics.
     match &cli.command {
-        Command::Status | Command::Health | Command::Verify(_) | Command::Config(_) => {
-            // Diagnostics are exempt from CWD enforcement.
+        Command::Status
+        | Command::Health(_)
+        | Command::Version
+        | Command::Verify(_)
+        | Command::Config(_)
+        | Command::Bench(_)
+        | Command::Init(_) => {
+            // Diagnostics, bench (scratch dir only) and init (first run) are exempt from CWD enforcement.
         }
         _ => {
             commands::validate_cwd(&paths, cli.allow_out_of_bounds)?;
         }
     }
 
+    if let Some(operation) = cli.command.mutating_operation() {
+        crate::moon::util::ensure_writable(operation)?;
+    }
+
+    // `version --json` prints the build metadata object itself rather than a command report.
+    if let Command::Version = &cli.command
+        && cli.json
+    {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&crate::moon::build_info::BuildInfo::current())?
+        );
+        return Ok(());
+    }
+
+    // RPC mode owns stdout for newline-delimited JSON, so it bypasses the command report.
+    if let Command::Recall(args) = &cli.command
+        && args.rpc
+    {
+        return commands::moon_recall::run_rpc(args.name.as_deref());
+    }
+
     let report = match &cli.command {
         Command::Install(args) => commands::install::run(&commands::install::InstallOptions {
             force: args.force,
             dry_run: args.dry_run,
             apply: args.apply,
+            prefix: args.prefix.clone(),
+            packaging_dir: args.packaging_dir.clone(),
+            pin: args.pin.clone(),
+            unpin: args.unpin,
+            channel: args.channel.clone(),
         })?,
         Command::Verify(args) => commands::verify::run(&commands::verify::VerifyOptions {
             strict: args.strict,
+            only: args.only.clone(),
         })?,
         Command::Repair(args) => {
             commands::repair::run(&commands::repair::RepairOptions { force: args.force })?
@@ -215,34 +672,170 @@ pub fn run() -> Result<()> {
                 dry_run: args.dry_run,
             })?
         }
+        Command::Sessions => commands::moon_sessions::run()?,
+        Command::Usage => commands::moon_usage::run()?,
+        Command::Compact(args) => {
+            commands::moon_compact::run(&commands::moon_compact::MoonCompactOptions {
+                session_key: args.session_key.clone(),
+                dry_run: args.dry_run,
+            })?
+        }
         Command::Index(args) => {
             commands::moon_index::run(&commands::moon_index::MoonIndexOptions {
                 collection_name: args.name.clone(),
+                reindex_all: args.reindex_all,
+                limit: args.limit,
+                keep_prev: args.keep_prev,
                 dry_run: args.dry_run,
             })?
         }
-        Command::Watch(args) => {
-            commands::moon_watch::run(&commands::moon_watch::MoonWatchOptions {
+        Command::Watch(args) => match &args.command {
+            Some(MoonWatchCommand::Pause(pause)) => {
+                commands::moon_watch::run_pause(&commands::moon_watch::MoonWatchPauseOptions {
+                    reason: pause.reason.clone(),
+                })?
+            }
+            Some(MoonWatchCommand::Resume) => commands::moon_watch::run_resume()?,
+            None => commands::moon_watch::run(&commands::moon_watch::MoonWatchOptions {
                 once: args.once,
                 daemon: args.daemon,
                 dry_run: args.dry_run,
-            })?
-        }
+            })?,
+        },
         Command::Embed(args) => {
             commands::moon_embed::run(&commands::moon_embed::MoonEmbedOptions {
                 collection_name: args.name.clone(),
                 max_docs: args.max_docs,
                 dry_run: args.dry_run,
                 watcher_trigger: args.watcher_trigger,
+                rebuild: args.rebuild,
+                verify: args.verify,
             })?
         }
         Command::Recall(args) => {
             commands::moon_recall::run(&commands::moon_recall::MoonRecallOptions {
-                query: args.query.clone(),
+                query: args.query.clone().unwrap_or_default(),
+                day: args.day.clone(),
                 collection_name: args.name.clone(),
                 channel_key: args.channel_key.clone(),
+                scope: args.scope.clone(),
+                open: args.open,
+                context: args.context,
+                export: args.export.clone(),
+                max_tokens: args.max_tokens,
+                fields: args.fields.clone(),
+            })?
+        }
+        Command::Memory(args) => {
+            let action = match &args.command {
+                MoonMemoryCommand::Diff(diff) => commands::moon_memory::MoonMemoryAction::Diff {
+                    since: diff.since.clone(),
+                },
+                MoonMemoryCommand::Inject(inject) => {
+                    commands::moon_memory::MoonMemoryAction::Inject {
+                        session_key: inject.session_key.clone(),
+                        max_tokens: inject.max_tokens,
+                        dry_run: inject.dry_run,
+                    }
+                }
+                MoonMemoryCommand::Export(export) => {
+                    commands::moon_memory::MoonMemoryAction::Export {
+                        format: export.format.clone(),
+                        output: export.output.clone(),
+                    }
+                }
+                MoonMemoryCommand::Import(import) => {
+                    commands::moon_memory::MoonMemoryAction::Import {
+                        path: import.path.clone(),
+                        dry_run: import.dry_run,
+                    }
+                }
+            };
+            commands::moon_memory::run(&commands::moon_memory::MoonMemoryOptions { action })?
+        }
+        Command::Graph(args) => match &args.command {
+            MoonGraphCommand::Query(query) => {
+                commands::moon_graph::run_query(&commands::moon_graph::MoonGraphQueryOptions {
+                    entity: query.entity.clone(),
+                    limit: query.limit,
+                })?
+            }
+        },
+        Command::Ledger(args) => match &args.command {
+            MoonLedgerCommand::Compact(compact) => commands::moon_ledger::run_compact(
+                &commands::moon_ledger::MoonLedgerCompactOptions {
+                    dry_run: compact.dry_run,
+                },
+            )?,
+            MoonLedgerCommand::Decrypt(decrypt) => commands::moon_ledger::run_decrypt(
+                &commands::moon_ledger::MoonLedgerDecryptOptions {
+                    archive: decrypt.archive.clone(),
+                    output: decrypt.output.clone(),
+                },
+            )?,
+            MoonLedgerCommand::SkipDistill(skip) => commands::moon_ledger::run_skip_distill(
+                &commands::moon_ledger::MoonLedgerSkipDistillOptions {
+                    archive: skip.archive.clone(),
+                    clear: skip.clear,
+                },
+            )?,
+        },
+        Command::Gc(args) => match &args.command {
+            MoonGcCommand::Purge(purge) => {
+                commands::moon_gc::run_purge(&commands::moon_gc::MoonGcPurgeOptions {
+                    all: purge.all,
+                    dry_run: purge.dry_run,
+                })?
+            }
+            MoonGcCommand::Restore(restore) => {
+                commands::moon_gc::run_restore(&commands::moon_gc::MoonGcRestoreOptions {
+                    path: restore.path.clone(),
+                })?
+            }
+        },
+        Command::Report(args) => match &args.command {
+            MoonReportCommand::Daily(daily) => {
+                commands::moon_report::run_daily(&commands::moon_report::MoonReportDailyOptions {
+                    day: daily.day.clone(),
+                    notify: daily.notify,
+                })?
+            }
+        },
+        Command::Audit(args) => {
+            commands::moon_audit::run(&commands::moon_audit::MoonAuditOptions {
+                phase: args.phase.clone(),
+                status: args.status.clone(),
+                cycle_id: args.cycle_id.clone(),
+                filters: args.filters.clone(),
+                limit: args.limit,
+            })?
+        }
+        Command::Continuity(args) => match &args.command {
+            MoonContinuityCommand::Show(show) => commands::moon_continuity::run_show(
+                &commands::moon_continuity::MoonContinuityShowOptions {
+                    channel: show.channel.clone(),
+                },
+            )?,
+        },
+        Command::Timeline(args) => {
+            commands::moon_timeline::run(&commands::moon_timeline::MoonTimelineOptions {
+                day: args.day.clone(),
+                channel: args.channel.clone(),
+                format: args.format.clone(),
+                output: args.output.clone(),
+                limit: args.limit,
             })?
         }
+        Command::Prune(args) => match &args.command {
+            Some(MoonPruneCommand::Restore(restore)) => {
+                commands::moon_prune::run_restore(&commands::moon_prune::MoonPruneRestoreOptions {
+                    from: restore.from.clone(),
+                })?
+            }
+            None => commands::moon_prune::run(&commands::moon_prune::MoonPruneOptions {
+                apply: args.apply,
+            })?,
+        },
         Command::Distill(args) => {
             commands::moon_distill::run(&commands::moon_distill::MoonDistillOptions {
                 mode: args.mode.clone(),
@@ -252,12 +845,34 @@ pub fn run() -> Result<()> {
                 dry_run: args.dry_run,
             })?
         }
-        Command::Config(args) => {
-            commands::moon_config::run(&commands::moon_config::MoonConfigOptions {
+        Command::Config(args) => match &args.command {
+            Some(MoonConfigCommand::CheckKeys) => commands::moon_config::run_check_keys()?,
+            None => commands::moon_config::run(&commands::moon_config::MoonConfigOptions {
                 show: args.show,
+            })?,
+        },
+        Command::Health(args) => {
+            commands::moon_health::run(&commands::moon_health::MoonHealthOptions {
+                repair: args.repair,
+            })?
+        }
+        Command::Version => commands::moon_version::run(),
+        Command::Init(args) => commands::moon_init::run(&commands::moon_init::MoonInitOptions {
+            moon_home: args.moon_home.clone(),
+            yes: args.yes,
+            install: args.install,
+            force: args.force,
+            dry_run: args.dry_run,
+        })?,
+        Command::Bench(args) => {
+            commands::moon_bench::run(&commands::moon_bench::MoonBenchOptions {
+                archive_mb: args.archive_mb,
+                ledger_records: args.ledger_records,
+                iterations: args.iterations,
+                scratch_dir: args.scratch_dir.clone(),
+                keep: args.keep,
             })?
         }
-        Command::Health => commands::moon_health::run()?,
     };
 
     print_report(&report, cli.json)?;
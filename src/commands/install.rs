@@ -12,35 +12,117 @@ use std::path::Path;
 #[cfg(target_os = "macos")]
 use std::process::Command;
 
+use crate::assets::{embedded_plugin_version, installed_plugin_version};
 use crate::commands::CommandReport;
 use crate::commands::moon_stop;
 use crate::moon::config::load_context_policy_if_explicit_env;
 use crate::openclaw::config::{
-    ConfigPatchOptions, apply_config_patches, ensure_plugin_enabled, ensure_plugin_install_record,
-    read_config_value, write_config_atomic,
+    ConfigPatchOptions, PluginVersionRecord, apply_config_patches, ensure_plugin_enabled,
+    ensure_plugin_install_record, ensure_plugin_version_record, read_config_value,
+    read_plugin_version_record, write_config_atomic,
 };
-use crate::openclaw::paths::resolve_paths;
-use crate::openclaw::plugin_install;
+use crate::openclaw::paths::resolve_paths_with_prefix;
+use crate::openclaw::plugin_install::{self, AssetDecision, CHANNEL_BETA, CHANNEL_STABLE};
+use crate::packaging;
+use std::path::PathBuf;
 
 #[derive(Debug, Clone)]
 pub struct InstallOptions {
     pub force: bool,
     pub dry_run: bool,
     pub apply: bool,
+    pub prefix: Option<PathBuf>,
+    pub packaging_dir: Option<PathBuf>,
+    pub pin: Option<String>,
+    pub unpin: bool,
+    pub channel: Option<String>,
+}
+
+fn validate_pin(pin: &str) -> Result<String> {
+    let pin = pin.trim().trim_start_matches('v');
+    if pin.is_empty()
+        || !pin
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '+'))
+    {
+        anyhow::bail!("invalid --pin `{pin}`: expected a plugin version such as 0.1.0");
+    }
+    Ok(pin.to_string())
+}
+
+fn validate_channel(channel: &str) -> Result<String> {
+    match channel.trim() {
+        CHANNEL_STABLE | CHANNEL_BETA => Ok(channel.trim().to_string()),
+        other => anyhow::bail!("invalid --channel `{other}`: expected stable or beta"),
+    }
 }
 
 pub fn run(opts: &InstallOptions) -> Result<CommandReport> {
-    let paths = resolve_paths()?;
+    if let Some(dir) = &opts.packaging_dir {
+        return write_packaging(dir);
+    }
+
+    let paths = resolve_paths_with_prefix(opts.prefix.as_deref())?;
     let mut report = CommandReport::new("install");
 
     report.detail("preflight: stopping watcher daemon and clearing lock".to_string());
     report.merge(moon_stop::run()?);
 
-    let plugin = plugin_install::install_plugin(&paths, opts.dry_run)?;
-    report.detail(format!("plugin_dir={}", plugin.path));
-    report.detail(format!("plugin_changed={}", plugin.changed));
-
     let mut cfg = read_config_value(&paths)?;
+    let recorded = read_plugin_version_record(&cfg, &paths.plugin_id);
+    let pin = match (&opts.pin, opts.unpin) {
+        (_, true) => None,
+        (Some(pin), false) => Some(validate_pin(pin)?),
+        (None, false) => recorded.pinned_version.clone(),
+    };
+    let channel = match opts.channel.as_deref().or(recorded.channel.as_deref()) {
+        Some(channel) => validate_channel(channel)?,
+        None => CHANNEL_STABLE.to_string(),
+    };
+    let embedded = embedded_plugin_version();
+    let installed_before = installed_plugin_version(&paths.plugin_dir);
+    report.detail(format!("plugin_version.embedded={embedded}"));
+    report.detail(format!("plugin_channel={channel}"));
+    if let Some(pin) = &pin {
+        report.detail(format!("plugin_pin={pin}"));
+    }
+
+    let (plugin_changed, installed_version) = match plugin_install::decide_assets(
+        &embedded,
+        installed_before.as_deref(),
+        pin.as_deref(),
+        &channel,
+    ) {
+        AssetDecision::Refresh => {
+            let plugin = plugin_install::install_plugin(&paths, opts.dry_run)?;
+            report.detail(format!("plugin_dir={}", plugin.path));
+            report.detail(format!("plugin_changed={}", plugin.changed));
+            let installed = if opts.dry_run {
+                installed_before
+            } else {
+                Some(embedded.clone())
+            };
+            (plugin.changed, installed)
+        }
+        AssetDecision::Keep { reason } => {
+            report.detail(format!("plugin_dir={}", paths.plugin_dir.display()));
+            report.detail("plugin_changed=false".to_string());
+            report.detail(format!("plugin_assets=kept {reason}"));
+            (false, installed_before)
+        }
+        AssetDecision::Blocked { reason } => {
+            report.issue(format!("plugin assets not installed: {reason}"));
+            (false, installed_before)
+        }
+    };
+    if let Some(prefix) = &opts.prefix {
+        report.detail(format!(
+            "plugin_prefix={} hint=set MOON_PLUGIN_PREFIX={} so status/verify/repair use the same directory",
+            prefix.display(),
+            prefix.display()
+        ));
+    }
+
     let context_policy = load_context_policy_if_explicit_env()?;
     if let Some(policy) = &context_policy {
         report.detail(format!(
@@ -64,6 +146,15 @@ pub fn run(opts: &InstallOptions) -> Result<CommandReport> {
     let plugin_patch = ensure_plugin_enabled(&mut cfg, &paths.plugin_id);
     let install_record_patch =
         ensure_plugin_install_record(&mut cfg, &paths.plugin_id, &paths.plugin_dir);
+    let version_patch = ensure_plugin_version_record(
+        &mut cfg,
+        &paths.plugin_id,
+        &PluginVersionRecord {
+            version: installed_version,
+            pinned_version: pin,
+            channel: Some(channel),
+        },
+    );
 
     for key in patch.inserted_paths {
         report.detail(format!("inserted {key}"));
@@ -86,9 +177,21 @@ pub fn run(opts: &InstallOptions) -> Result<CommandReport> {
     for key in install_record_patch.forced_paths {
         report.detail(format!("forced {key}"));
     }
+    for key in version_patch.inserted_paths {
+        report.detail(format!("inserted {key}"));
+    }
+    for key in version_patch.forced_paths {
+        report.detail(format!("forced {key}"));
+    }
+    for key in version_patch.removed_paths {
+        report.detail(format!("removed {key}"));
+    }
 
-    let changed =
-        patch.changed || plugin_patch.changed || install_record_patch.changed || plugin.changed;
+    let changed = patch.changed
+        || plugin_patch.changed
+        || install_record_patch.changed
+        || version_patch.changed
+        || plugin_changed;
     if changed && opts.apply && !opts.dry_run {
         let path_written = write_config_atomic(&paths, &cfg)?;
         report.detail(format!("updated config: {path_written}"));
@@ -105,7 +208,30 @@ pub fn run(opts: &InstallOptions) -> Result<CommandReport> {
     Ok(report)
 }
 
-#[cfg(not(target_os = "macos"))]
+fn write_packaging(dir: &std::path::Path) -> Result<CommandReport> {
+    let mut report = CommandReport::new("install");
+    for path in packaging::write_packaging_metadata(dir)? {
+        report.detail(format!("packaging.file={}", path.display()));
+    }
+    report.detail(format!(
+        "packaging.hint=replace {} with the release artifact sha256 before publishing",
+        packaging::SHA256_PLACEHOLDER
+    ));
+    Ok(report)
+}
+
+#[cfg(target_os = "windows")]
+fn ensure_default_autostart(opts: &InstallOptions, report: &mut CommandReport) -> Result<()> {
+    let _ = opts;
+    report.detail("autostart=skipped reason=unsupported_platform".to_string());
+    report.detail(
+        "autostart.hint=create a Task Scheduler logon task running `moon watch --daemon`"
+            .to_string(),
+    );
+    Ok(())
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
 fn ensure_default_autostart(opts: &InstallOptions, report: &mut CommandReport) -> Result<()> {
     let _ = opts;
     report.detail("autostart=skipped reason=unsupported_platform".to_string());
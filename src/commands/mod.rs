@@ -1,19 +1,35 @@
 pub mod install;
+pub mod moon_audit;
+pub mod moon_bench;
+pub mod moon_compact;
 pub mod moon_config;
+pub mod moon_continuity;
 pub mod moon_distill;
 pub mod moon_embed;
+pub mod moon_gc;
+pub mod moon_graph;
 pub mod moon_health;
 pub mod moon_index;
+pub mod moon_init;
+pub mod moon_ledger;
+pub mod moon_memory;
+pub mod moon_prune;
 pub mod moon_recall;
+pub mod moon_report;
 pub mod moon_restart;
+pub mod moon_sessions;
 pub mod moon_snapshot;
 pub mod moon_status;
 pub mod moon_stop;
+pub mod moon_timeline;
+pub mod moon_usage;
+pub mod moon_version;
 pub mod moon_watch;
 pub mod repair;
 pub mod status;
 pub mod verify;
 
+use crate::error::MoonErrorCode;
 use anyhow::{Context, Result};
 use serde::Serialize;
 use std::path::PathBuf;
@@ -23,7 +39,41 @@ pub struct CommandReport {
     pub command: String,
     pub ok: bool,
     pub details: Vec<String>,
-    pub issues: Vec<String>,
+    /// Error-severity problems; any entry clears `ok` (exit code 2).
+    pub issues: Vec<ReportIssue>,
+    /// Warning-severity problems, reported without failing the command.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+    /// Info-severity findings such as repair hints.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub info: Vec<String>,
+}
+
+/// One error-severity problem, optionally classified with a stable code and a remediation hint.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportIssue {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<&'static str>,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hint: Option<String>,
+}
+
+impl ReportIssue {
+    /// Replaces the code's default hint with one specific to this issue.
+    pub fn with_hint(&mut self, hint: impl Into<String>) -> &mut Self {
+        self.hint = Some(hint.into());
+        self
+    }
+}
+
+impl std::fmt::Display for ReportIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(code) = self.code {
+            write!(f, "[{code}] ")?;
+        }
+        f.write_str(&self.message)
+    }
 }
 
 impl CommandReport {
@@ -33,6 +83,8 @@ impl CommandReport {
             ok: true,
             details: Vec::new(),
             issues: Vec::new(),
+            warnings: Vec::new(),
+            info: Vec::new(),
         }
     }
 
@@ -42,13 +94,44 @@ impl CommandReport {
 
     pub fn issue(&mut self, text: impl Into<String>) {
         self.ok = false;
-        self.issues.push(text.into());
+        self.issues.push(ReportIssue {
+            code: None,
+            message: text.into(),
+            hint: None,
+        });
+    }
+
+    /// An issue tagged with `code`, carrying the code's default hint.
+    pub fn coded_issue(
+        &mut self,
+        code: MoonErrorCode,
+        text: impl Into<String>,
+    ) -> &mut ReportIssue {
+        self.ok = false;
+        self.issues.push(ReportIssue {
+            code: Some(code.as_str()),
+            message: text.into(),
+            hint: Some(code.hint().to_string()),
+        });
+        self.issues.last_mut().expect("issue just pushed")
+    }
+
+    /// A problem worth fixing that does not fail the command.
+    pub fn warning(&mut self, text: impl Into<String>) {
+        self.warnings.push(text.into());
+    }
+
+    /// An advisory finding, e.g. a repair hint.
+    pub fn info(&mut self, text: impl Into<String>) {
+        self.info.push(text.into());
     }
 
     pub fn merge(&mut self, mut other: CommandReport) {
         self.ok &= other.ok;
         self.details.append(&mut other.details);
         self.issues.append(&mut other.issues);
+        self.warnings.append(&mut other.warnings);
+        self.info.append(&mut other.info);
     }
 }
 
@@ -57,7 +140,10 @@ pub fn ensure_openclaw_available(report: &mut CommandReport) -> bool {
         return true;
     }
 
-    report.issue("openclaw binary unavailable; set OPENCLAW_BIN or ensure openclaw is on PATH");
+    report.coded_issue(
+        MoonErrorCode::E011OpenClawMissing,
+        "openclaw binary unavailable",
+    );
     false
 }
 
@@ -75,6 +161,35 @@ pub fn restart_gateway_with_fallback(report: &mut CommandReport) {
         report.detail("gateway restart succeeded");
     }
 }
+pub fn report_archive_plan(
+    report: &mut CommandReport,
+    prefix: &str,
+    plan: &crate::moon::archive::ArchivePlan,
+) {
+    report.detail(format!("{prefix}.source={}", plan.source_path.display()));
+    report.detail(format!("{prefix}.source_bytes={}", plan.source_bytes));
+    report.detail(format!("{prefix}.deduped={}", plan.deduped));
+    report.detail(format!(
+        "{prefix}.archive_path={}",
+        plan.archive_path.display()
+    ));
+    report.detail(format!(
+        "{prefix}.projection_path={}",
+        plan.projection_path.display()
+    ));
+    if let Some(bytes) = plan.projection_bytes_estimate {
+        report.detail(format!("{prefix}.projection_bytes_estimate={bytes}"));
+    }
+    if let Some(err) = &plan.projection_error {
+        report.detail(format!("{prefix}.projection_error={err}"));
+    }
+    report.detail(format!("{prefix}.qmd_operation={}", plan.qmd_operation));
+    report.detail(format!(
+        "{prefix}.ledger_path={}",
+        plan.ledger_path.display()
+    ));
+}
+
 fn canonicalize_or_original(path: PathBuf) -> PathBuf {
     std::fs::canonicalize(&path).unwrap_or(path)
 }
@@ -118,7 +233,7 @@ pub fn validate_cwd(
 
     anyhow::bail!(
         "code={} cwd={} expected_workspace={} hint=run from the workspace tree or pass --allow-out-of-bounds",
-        crate::error::MoonErrorCode::E004CwdInvalid.as_str(),
+        MoonErrorCode::E004CwdInvalid.as_str(),
         cwd.display(),
         expected_workspace.display()
     );
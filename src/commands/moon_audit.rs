@@ -0,0 +1,87 @@
+use anyhow::Result;
+use serde_json::Value;
+
+use crate::commands::CommandReport;
+use crate::error::MoonErrorCode;
+use crate::moon::audit::{self, AuditEvent};
+use crate::moon::paths::resolve_paths;
+
+#[derive(Debug, Clone, Default)]
+pub struct MoonAuditOptions {
+    pub phase: Option<String>,
+    pub status: Option<String>,
+    pub cycle_id: Option<String>,
+    /// `key=value` detail filters; every one must match.
+    pub filters: Vec<String>,
+    pub limit: usize,
+}
+
+pub fn run(opts: &MoonAuditOptions) -> Result<CommandReport> {
+    let paths = resolve_paths()?;
+    let mut report = CommandReport::new("audit");
+
+    let mut filters = Vec::with_capacity(opts.filters.len());
+    for raw in &opts.filters {
+        match raw.split_once('=') {
+            Some((key, value)) if !key.trim().is_empty() => {
+                filters.push((key.trim(), value.trim()));
+            }
+            _ => {
+                report.coded_issue(
+                    MoonErrorCode::E013InvalidArgument,
+                    format!("invalid --where `{raw}`: expected KEY=VALUE"),
+                );
+            }
+        }
+    }
+    if !report.ok {
+        return Ok(report);
+    }
+
+    let events = audit::read_events(&paths)?;
+    let matched = events
+        .iter()
+        .filter(|event| {
+            opts.phase
+                .as_deref()
+                .is_none_or(|phase| event.phase == phase)
+                && opts
+                    .status
+                    .as_deref()
+                    .is_none_or(|status| event.status == status)
+                && opts
+                    .cycle_id
+                    .as_deref()
+                    .is_none_or(|cycle| event.cycle_id.as_deref() == Some(cycle))
+                && filters
+                    .iter()
+                    .all(|(key, value)| event.field(key).as_deref() == Some(*value))
+        })
+        .collect::<Vec<_>>();
+
+    report.detail(format!("schema_version={}", audit::AUDIT_SCHEMA_VERSION));
+    report.detail(format!("events_scanned={}", events.len()));
+    report.detail(format!("events_matched={}", matched.len()));
+    let skip = matched.len().saturating_sub(opts.limit);
+    for event in &matched[skip..] {
+        report.detail(event_line(event));
+    }
+    Ok(report)
+}
+
+fn event_line(event: &AuditEvent) -> String {
+    let mut line = format!(
+        "event at_epoch_secs={} phase={} status={} schema_version={}",
+        event.at_epoch_secs, event.phase, event.status, event.schema_version
+    );
+    if let Some(cycle_id) = &event.cycle_id {
+        line.push_str(&format!(" cycle_id={cycle_id}"));
+    }
+    match &event.details {
+        Value::Object(map) if !map.is_empty() => {
+            line.push_str(&format!(" details={}", event.details));
+        }
+        _ => line.push_str(&format!(" message={}", event.message)),
+    }
+    line
+}
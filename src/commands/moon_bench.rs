@@ -0,0 +1,46 @@
+use anyhow::Result;
+use std::path::PathBuf;
+
+use crate::commands::CommandReport;
+use crate::moon::bench::{BenchOptions, run_bench};
+
+#[derive(Debug, Clone)]
+pub struct MoonBenchOptions {
+    pub archive_mb: u64,
+    pub ledger_records: usize,
+    pub iterations: usize,
+    pub scratch_dir: Option<PathBuf>,
+    pub keep: bool,
+}
+
+pub fn run(opts: &MoonBenchOptions) -> Result<CommandReport> {
+    let mut report = CommandReport::new("bench");
+    let bench = run_bench(&BenchOptions {
+        archive_mb: opts.archive_mb,
+        ledger_records: opts.ledger_records,
+        iterations: opts.iterations,
+        scratch_root: opts.scratch_dir.clone().unwrap_or_else(std::env::temp_dir),
+        keep: opts.keep,
+    })?;
+
+    report.detail(format!("bench.version={}", env!("CARGO_PKG_VERSION")));
+    report.detail(format!("bench.archive_bytes={}", bench.archive_bytes));
+    report.detail(format!("bench.archive_lines={}", bench.archive_lines));
+    report.detail(format!("bench.ledger_records={}", opts.ledger_records));
+    report.detail(format!("bench.iterations={}", opts.iterations.max(1)));
+    for stage in &bench.stages {
+        report.detail(format!(
+            "bench.{}: best_ms={:.2} mean_ms={:.2} items={} mb_per_sec={:.1} items_per_sec={:.0}",
+            stage.name,
+            stage.best.as_secs_f64() * 1000.0,
+            stage.mean.as_secs_f64() * 1000.0,
+            stage.items,
+            stage.mb_per_sec(),
+            stage.items_per_sec()
+        ));
+    }
+    if opts.keep {
+        report.detail(format!("bench.scratch_dir={}", bench.scratch_dir.display()));
+    }
+    Ok(report)
+}
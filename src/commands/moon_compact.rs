@@ -0,0 +1,146 @@
+use anyhow::Result;
+
+use crate::commands::{CommandReport, ensure_openclaw_available, report_archive_plan};
+use crate::moon::archive::plan_archive_and_index;
+use crate::moon::audit;
+use crate::moon::channel_archive_map;
+use crate::moon::config::load_config;
+use crate::moon::paths::resolve_paths;
+use crate::moon::session_usage::collect_openclaw_usage_batch;
+use crate::moon::snapshot::is_snapshot_excluded;
+use crate::moon::watcher::{archive_and_compact_session, load_session_source_map};
+
+#[derive(Debug, Clone, Default)]
+pub struct MoonCompactOptions {
+    pub session_key: String,
+    pub dry_run: bool,
+}
+
+fn session_usage_ratio(session_key: &str) -> Option<f64> {
+    let batch = collect_openclaw_usage_batch().ok()?;
+    batch
+        .sessions
+        .into_iter()
+        .find(|session| session.session_id == session_key)
+        .map(|session| session.usage_ratio)
+}
+
+pub fn run(opts: &MoonCompactOptions) -> Result<CommandReport> {
+    let paths = resolve_paths()?;
+    let cfg = load_config()?;
+    let mut report = CommandReport::new("compact");
+
+    let session_key = opts.session_key.trim();
+    if session_key.is_empty() {
+        report.issue("session key cannot be empty");
+        return Ok(report);
+    }
+    report.detail(format!("session_key={session_key}"));
+
+    let source_map = load_session_source_map(&paths.openclaw_sessions_dir)?;
+    let Some(source_path) = source_map.get(session_key) else {
+        report.issue(format!(
+            "no session file for key `{session_key}` in {}",
+            paths.openclaw_sessions_dir.join("sessions.json").display()
+        ));
+        return Ok(report);
+    };
+    if is_snapshot_excluded(source_path, &cfg.snapshot.exclude) {
+        report.issue(format!(
+            "source {} matches snapshot.exclude; compaction would drop unarchived history",
+            source_path.display()
+        ));
+        return Ok(report);
+    }
+    if !cfg.sessions.allows_archive(session_key) {
+        report.issue(format!(
+            "session {session_key} is excluded by [sessions] archive; compaction would drop unarchived history"
+        ));
+        return Ok(report);
+    }
+    report.detail(format!("source={}", source_path.display()));
+    let collection = cfg.collections.for_session(Some(session_key));
+    report.detail(format!("collection={collection}"));
+    let strategy = cfg.compaction.for_session(session_key);
+    report.detail(format!("compaction_strategy={}", strategy.label()));
+
+    if opts.dry_run {
+        report.detail("dry-run: archive and compaction planned but not run".to_string());
+        let plan = plan_archive_and_index(&paths, source_path, collection)?;
+        report_archive_plan(&mut report, "plan", &plan);
+        return Ok(report);
+    }
+
+    if !ensure_openclaw_available(&mut report) {
+        return Ok(report);
+    }
+
+    let usage_before = session_usage_ratio(session_key);
+    let compacted = match archive_and_compact_session(
+        &paths,
+        session_key,
+        source_path,
+        collection,
+        strategy,
+        // An explicit `moon compact` always sends; only watcher retriggers are deduplicated.
+        0,
+    ) {
+        Ok(compacted) => compacted,
+        Err(failure) => {
+            report.issue(format!("compaction aborted: {failure}"));
+            let _ = audit::append_event(
+                &paths,
+                "compaction",
+                "degraded",
+                &format!("manual failed key={session_key} {failure}"),
+                serde_json::json!({
+                    "trigger": "manual",
+                    "session": session_key,
+                    "error": failure.to_string(),
+                }),
+            );
+            return Ok(report);
+        }
+    };
+
+    report.detail(format!("archive_path={}", compacted.archive_path));
+    if let Some(projection) = &compacted.projection_path {
+        report.detail(format!("projection_path={projection}"));
+    }
+    report.detail(format!("compact={}", compacted.compact_summary));
+    report.detail(format!("index_note={}", compacted.index_note));
+
+    match channel_archive_map::get(&paths, session_key)? {
+        Some(record) if record.archive_path == compacted.archive_path => {
+            report.detail("verify.channel_archive_map=ok".to_string());
+        }
+        _ => report.issue(format!(
+            "verify failed: channel archive map does not point `{session_key}` at {}",
+            compacted.archive_path
+        )),
+    }
+    let usage_after = session_usage_ratio(session_key);
+    if let Some(ratio) = usage_before {
+        report.detail(format!("verify.usage_ratio_before={ratio:.4}"));
+    }
+    match usage_after {
+        Some(ratio) => report.detail(format!("verify.usage_ratio_after={ratio:.4}")),
+        None => report.detail("verify.usage_ratio_after=unavailable".to_string()),
+    }
+
+    let status = if report.ok { "ok" } else { "degraded" };
+    let _ = audit::append_event(
+        &paths,
+        "compaction",
+        status,
+        &format!("manual ok key={session_key} {}", compacted.detail()),
+        serde_json::json!({
+            "trigger": "manual",
+            "session": session_key,
+            "archive": compacted.archive_path,
+            "projection": compacted.projection_path,
+        }),
+    );
+
+    Ok(report)
+}
@@ -1,5 +1,11 @@
 use crate::commands::CommandReport;
-use crate::moon::config::{SECRET_ENV_KEYS, load_config, masked_env_secret, resolve_config_path};
+use crate::moon::config::{
+    SECRET_ENV_KEYS, load_config, mask_secret, masked_env_secret, resolve_config_path,
+};
+use crate::moon::distill::{KeyStatus, check_distill_keys};
+use crate::moon::hooks::HookEvent;
+use crate::moon::privacy;
+use crate::moon::vectors::check_embed_key;
 use anyhow::Result;
 
 #[derive(Debug, Clone)]
@@ -32,6 +38,14 @@ pub fn run(opts: &MoonConfigOptions) -> Result<CommandReport> {
             "thresholds.trigger_ratio={}",
             cfg.thresholds.trigger_ratio
         ));
+        report.detail(format!(
+            "thresholds.archive_ratio={}",
+            cfg.thresholds.archive_ratio
+        ));
+        report.detail(format!(
+            "thresholds.archive_ratio_trigger_enabled={}",
+            cfg.thresholds.archive_ratio_trigger_enabled
+        ));
         report.detail(format!(
             "watcher.poll_interval_secs={}",
             cfg.watcher.poll_interval_secs
@@ -40,6 +54,14 @@ pub fn run(opts: &MoonConfigOptions) -> Result<CommandReport> {
             "watcher.cooldown_secs={}",
             cfg.watcher.cooldown_secs
         ));
+        report.detail(format!(
+            "watcher.max_cycle_secs={}",
+            cfg.watcher.max_cycle_secs
+        ));
+        report.detail(format!(
+            "watcher.predictive_trigger={}",
+            cfg.watcher.predictive_trigger
+        ));
         report.detail(format!(
             "inbound_watch.enabled={}",
             cfg.inbound_watch.enabled
@@ -52,6 +74,10 @@ pub fn run(opts: &MoonConfigOptions) -> Result<CommandReport> {
             "inbound_watch.event_mode={}",
             cfg.inbound_watch.event_mode
         ));
+        report.detail(format!(
+            "inbound_watch.event_format={}",
+            cfg.inbound_watch.event_format
+        ));
         report.detail(format!(
             "inbound_watch.watch_paths={:?}",
             cfg.inbound_watch.watch_paths
@@ -68,18 +94,49 @@ pub fn run(opts: &MoonConfigOptions) -> Result<CommandReport> {
             "distill.topic_discovery={}",
             cfg.distill.topic_discovery
         ));
+        report.detail(format!(
+            "distill.graph_extraction={}",
+            cfg.distill.graph_extraction
+        ));
         report.detail(format!("distill.chunk_bytes={:?}", cfg.distill.chunk_bytes));
         report.detail(format!("distill.max_chunks={:?}", cfg.distill.max_chunks));
         report.detail(format!(
             "distill.model_context_tokens={:?}",
             cfg.distill.model_context_tokens
         ));
+        report.detail(format!(
+            "distill.model_limits_cache_secs={}",
+            cfg.distill.model_limits_cache_secs
+        ));
+        report.detail(format!(
+            "distill.daily_token_budget={}",
+            cfg.distill.daily_token_budget
+        ));
+        report.detail(format!("distill.mode={}", cfg.distill.mode));
+        report.detail(format!("distill.idle_secs={}", cfg.distill.idle_secs));
+        report.detail(format!(
+            "distill.cooldown_secs={}",
+            cfg.distill
+                .cooldown_secs
+                .map_or_else(|| "watcher".to_string(), |secs| secs.to_string())
+        ));
+        report.detail(format!("distill.concurrency={}", cfg.distill.concurrency));
+        report.detail(format!(
+            "distill.rollup_threshold_chunks={}",
+            cfg.distill.rollup_threshold_chunks
+        ));
+        report.detail(format!(
+            "distill.cost_per_million_tokens={}",
+            cfg.distill.cost_per_million_tokens
+        ));
         report.detail(format!(
             "retention.active_days={}",
             cfg.retention.active_days
         ));
         report.detail(format!("retention.warm_days={}", cfg.retention.warm_days));
         report.detail(format!("retention.cold_days={}", cfg.retention.cold_days));
+        report.detail(format!("retention.force={}", cfg.retention.force));
+        report.detail(format!("retention.trash_days={}", cfg.retention.trash_days));
         report.detail(format!("embed.mode={}", cfg.embed.mode));
         report.detail(format!("embed.idle_secs={}", cfg.embed.idle_secs));
         report.detail(format!("embed.cooldown_secs={}", cfg.embed.cooldown_secs));
@@ -92,6 +149,134 @@ pub fn run(opts: &MoonConfigOptions) -> Result<CommandReport> {
             cfg.embed.min_pending_docs
         ));
         report.detail(format!("embed.max_cycle_secs={}", cfg.embed.max_cycle_secs));
+        report.detail(format!("embed.provider={}", cfg.embed.provider));
+        report.detail(format!("embed.model={}", cfg.embed.model));
+        report.detail(format!("embed.base_url={}", cfg.embed.base_url));
+        report.detail(format!("embed.batch_size={}", cfg.embed.batch_size));
+        report.detail(format!(
+            "embed.requests_per_minute={}",
+            cfg.embed.requests_per_minute
+        ));
+        report.detail(format!("embed.max_retries={}", cfg.embed.max_retries));
+        report.detail(format!(
+            "memory.inject_on_new_session={}",
+            cfg.memory.inject_on_new_session
+        ));
+        report.detail(format!(
+            "memory.primer_max_tokens={}",
+            cfg.memory.primer_max_tokens
+        ));
+        report.detail(format!("collections.default={}", cfg.collections.default));
+        for (prefix, name) in &cfg.collections.channels {
+            report.detail(format!(
+                "collections.channel prefix={prefix} collection={name}"
+            ));
+        }
+        report.detail(format!(
+            "compaction.default={}",
+            cfg.compaction.default.label()
+        ));
+        for (prefix, strategy) in &cfg.compaction.channels {
+            report.detail(format!(
+                "compaction.channel prefix={prefix} strategy={}",
+                strategy.label()
+            ));
+        }
+        report.detail(format!("snapshot.exclude={:?}", cfg.snapshot.exclude));
+        for (stage, filter) in cfg.sessions.filters() {
+            report.detail(format!(
+                "sessions.{stage} include={} exclude={:?}",
+                filter
+                    .include
+                    .as_ref()
+                    .map(|include| format!("{include:?}"))
+                    .unwrap_or_else(|| "default".to_string()),
+                filter.exclude
+            ));
+        }
+        report.detail(format!(
+            "projection.max_scan_bytes={}",
+            cfg.projection.max_scan_bytes
+        ));
+        report.detail(format!(
+            "projection.max_scan_lines={}",
+            cfg.projection.max_scan_lines
+        ));
+        report.detail(format!(
+            "projection.max_entries={}",
+            cfg.projection.max_entries
+        ));
+        report.detail(format!("projection.full_scan={}", cfg.projection.full_scan));
+        report.detail(format!(
+            "projection.json_sidecar={}",
+            cfg.projection.json_sidecar
+        ));
+        report.detail(format!(
+            "projection.duplicate_detection={}",
+            cfg.projection.duplicate_detection
+        ));
+        report.detail(format!(
+            "projection.duplicate_max_distance={}",
+            cfg.projection.duplicate_max_distance
+        ));
+        report.detail(format!("report.daily={}", cfg.report.daily));
+        report.detail(format!("report.notify={}", cfg.report.notify));
+        report.detail(format!("recall.deadline_ms={}", cfg.recall.deadline_ms));
+        report.detail(format!("usage.schema={}", cfg.usage.schema));
+        report.detail(format!(
+            "notify.discord_webhook_url={}",
+            mask_secret(&cfg.notify.discord_webhook_url)
+        ));
+        report.detail(format!(
+            "notify.slack_webhook_url={}",
+            mask_secret(&cfg.notify.slack_webhook_url)
+        ));
+        report.detail(format!(
+            "notify.distill_failure_threshold={}",
+            cfg.notify.distill_failure_threshold
+        ));
+        for (event, sinks) in &cfg.notify.routes {
+            report.detail(format!("notify.routes.{event}={}", sinks.join(",")));
+        }
+        for event in HookEvent::ALL {
+            report.detail(format!(
+                "hooks.{}={}",
+                event.as_str(),
+                event.command(&cfg.hooks)
+            ));
+        }
+        report.detail(format!("hooks.timeout_secs={}", cfg.hooks.timeout_secs));
+        report.detail(format!("policy.script={}", cfg.policy.script));
+        report.detail(format!(
+            "privacy.channels={}",
+            cfg.privacy.channels.join(",")
+        ));
+        report.detail(format!("privacy.encrypt={}", cfg.privacy.encrypt));
+        report.detail(format!(
+            "privacy.key={}",
+            match std::env::var("MOON_PRIVACY_KEY") {
+                Ok(key) if key.trim().is_empty() => "unset",
+                Ok(key) if privacy::parse_privacy_key(&key).is_err() => "invalid",
+                Ok(_) => "set",
+                Err(_) => "unset",
+            }
+        ));
+        report.detail(format!(
+            "tool_priority.high_boost={}",
+            cfg.tool_priority.high_boost
+        ));
+        report.detail(format!(
+            "tool_priority.normal_boost={}",
+            cfg.tool_priority.normal_boost
+        ));
+        for rule in &cfg.tool_priority.rules {
+            report.detail(format!(
+                "tool_priority.rule tool={} priority={:?} boost={}",
+                rule.tool,
+                rule.priority,
+                cfg.tool_priority.boost_for_rule(rule)
+            ));
+        }
 
         if let Some(context) = &cfg.context {
             report.detail(format!("context.window_mode={:?}", context.window_mode));
@@ -118,3 +303,50 @@ pub fn run(opts: &MoonConfigOptions) -> Result<CommandReport> {
 
     Ok(report)
 }
+
+/// Checks every configured distill, wisdom and embedding key with one minimal call each;
+/// keys that are rejected, out of quota or unreachable are issues.
+pub fn run_check_keys() -> Result<CommandReport> {
+    let mut report = CommandReport::new("config check-keys");
+    let cfg = load_config()?;
+
+    let mut checks = check_distill_keys();
+    if let Some(check) = check_embed_key(&cfg.embed) {
+        checks.push(("embed", check));
+    }
+    if checks.is_empty() {
+        report.detail("keys=none (no remote distill, wisdom or embedding provider configured)");
+        return Ok(report);
+    }
+
+    let mut valid = 0usize;
+    for (role, check) in &checks {
+        let check = match check {
+            Ok(check) => check,
+            Err(err) => {
+                report.issue(format!("key.{role} status=misconfigured error={err:#}"));
+                continue;
+            }
+        };
+        let mut line = format!(
+            "key.{role} provider={} model={} key={} status={} duration_ms={}",
+            check.provider,
+            check.model,
+            check.masked_key,
+            check.status.as_str(),
+            check.duration_ms
+        );
+        if check.status == KeyStatus::Valid {
+            valid += 1;
+            report.detail(line);
+            continue;
+        }
+        if let Some(error) = &check.error {
+            line.push_str(&format!(" error={error}"));
+        }
+        report.issue(line);
+    }
+    report.detail(format!("keys.checked={}", checks.len()));
+    report.detail(format!("keys.valid={valid}"));
+    Ok(report)
+}
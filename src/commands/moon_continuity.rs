@@ -0,0 +1,65 @@
+use anyhow::Result;
+use chrono::{TimeZone, Utc};
+
+use crate::commands::CommandReport;
+use crate::moon::channel_archive_map;
+use crate::moon::continuity::{read_records, records_path};
+use crate::moon::paths::resolve_paths;
+
+#[derive(Debug, Clone)]
+pub struct MoonContinuityShowOptions {
+    pub channel: String,
+}
+
+fn format_epoch(epoch_secs: u64) -> String {
+    Utc.timestamp_opt(epoch_secs as i64, 0)
+        .single()
+        .map(|dt| dt.to_rfc3339())
+        .unwrap_or_else(|| epoch_secs.to_string())
+}
+
+pub fn run_show(opts: &MoonContinuityShowOptions) -> Result<CommandReport> {
+    let paths = resolve_paths()?;
+    let mut report = CommandReport::new("continuity show");
+
+    let channel = opts.channel.trim();
+    if channel.is_empty() {
+        report.issue("channel key cannot be empty");
+        return Ok(report);
+    }
+    report.detail(format!("channel={channel}"));
+    report.detail(format!("records_path={}", records_path(&paths).display()));
+
+    let mapped = channel_archive_map::get(&paths, channel)?;
+    if let Some(record) = &mapped {
+        report.detail(format!("mapped_archive={}", record.archive_path));
+        report.detail(format!("mapped_source={}", record.source_path));
+        if let Some(retired) = &record.retired_archive_path {
+            report.detail(format!("mapped_retired_archive={retired}"));
+        }
+        report.detail(format!(
+            "mapped_at={}",
+            format_epoch(record.updated_at_epoch_secs)
+        ));
+    }
+
+    let records = read_records(&paths, channel)?;
+    report.detail(format!("records={}", records.len()));
+    if records.is_empty() && mapped.is_none() {
+        report.issue(format!("no continuity recorded for channel `{channel}`"));
+        return Ok(report);
+    }
+    for (idx, record) in records.iter().rev().enumerate() {
+        report.detail(format!(
+            "record[{idx}] at={} reason={} {} -> {} archive={} summary={}",
+            format_epoch(record.recorded_at_epoch_secs),
+            record.reason,
+            record.source_session_id,
+            record.target_session_id,
+            record.archive_path.as_deref().unwrap_or("none"),
+            record.summary_path.as_deref().unwrap_or("none")
+        ));
+    }
+
+    Ok(report)
+}
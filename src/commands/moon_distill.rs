@@ -1,10 +1,15 @@
 use anyhow::{Context, Result};
+use std::borrow::Cow;
 use std::fs;
 use std::path::Path;
 use std::path::PathBuf;
 
 use crate::commands::CommandReport;
-use crate::moon::archive::{ArchiveRecord, projection_path_for_archive, read_ledger_records};
+use crate::moon::archive::{
+    ArchiveRecord, DistillProvenance, projection_path_for_archive, read_ledger_records,
+    record_distill_provenance,
+};
+use crate::moon::config::load_config;
 use crate::moon::distill::{
     DistillInput, WisdomDistillInput, archive_file_size, run_distillation, run_wisdom_distillation,
 };
@@ -161,6 +166,7 @@ pub fn run(opts: &MoonDistillOptions) -> Result<CommandReport> {
         if opts.dry_run {
             report.detail("distill.dry_run=true".to_string());
         }
+        let distill_cfg = load_config().map(|cfg| cfg.distill).unwrap_or_default();
         let out = match run_wisdom_distillation(
             &paths,
             &WisdomDistillInput {
@@ -168,6 +174,8 @@ pub fn run(opts: &MoonDistillOptions) -> Result<CommandReport> {
                 day_epoch_secs: None,
                 source_paths: opts.files.clone(),
                 dry_run: opts.dry_run,
+                concurrency: distill_cfg.chunk_workers(),
+                rollup_threshold_chunks: distill_cfg.rollup_threshold_chunks as usize,
             },
         ) {
             Ok(out) => out,
@@ -193,6 +201,40 @@ pub fn run(opts: &MoonDistillOptions) -> Result<CommandReport> {
         report.detail(format!("provider={}", out.provider));
         report.detail(format!("summary_path={}", out.summary_path));
         report.detail(format!("audit_log_path={}", out.audit_log_path));
+        report.detail(format!("memory_conflicts={}", out.memory_conflicts.len()));
+        report.detail(format!("memory_expired={}", out.expired_memory.len()));
+        report.detail(format!("remote_tokens={}", out.remote_tokens));
+        if !out.chunk_durations_ms.is_empty() {
+            report.detail(format!(
+                "chunk_durations_ms={}",
+                out.chunk_durations_ms
+                    .iter()
+                    .map(u64::to_string)
+                    .collect::<Vec<_>>()
+                    .join(",")
+            ));
+        }
+        if out.rollup_levels > 0 {
+            report.detail(format!("rollup_levels={}", out.rollup_levels));
+        }
+        if let Some(fallback) = &out.provider_fallback {
+            report.detail(format!("provider_fallback {}", fallback.detail()));
+            report.warning(format!(
+                "{} failed for {} chunk(s) (error_class={}); summary built from the remaining output",
+                fallback.provider,
+                fallback.failed_chunks,
+                fallback.error_class.as_str()
+            ));
+        }
+        for bullet in &out.expired_memory {
+            report.detail(format!("memory_expired_bullet=\"{bullet}\""));
+        }
+        for conflict in &out.memory_conflicts {
+            report.detail(format!(
+                "memory_conflict new=\"{}\" earlier=\"{}\" similarity={:.2} model_confirmed={}",
+                conflict.incoming, conflict.existing, conflict.similarity, conflict.model_confirmed
+            ));
+        }
         return Ok(report);
     }
 
@@ -240,7 +282,7 @@ pub fn run(opts: &MoonDistillOptions) -> Result<CommandReport> {
         &DistillInput {
             session_id,
             archive_path: pending_projection_path,
-            archive_text: String::new(),
+            archive_text: Cow::Borrowed(""),
             archive_epoch_secs,
         },
     )?;
@@ -250,6 +292,15 @@ pub fn run(opts: &MoonDistillOptions) -> Result<CommandReport> {
     report.detail(format!("summary_path={}", out.summary_path));
     report.detail(format!("audit_log_path={}", out.audit_log_path));
     report.detail(format!("archive_size_bytes={archive_size}"));
+    match record_distill_provenance(
+        &paths,
+        &pending_record.archive_path,
+        &DistillProvenance::from_output(&out),
+    ) {
+        Ok(true) => report.detail("ledger.distill_provenance=recorded".to_string()),
+        Ok(false) => report.detail("ledger.distill_provenance=no-ledger-row".to_string()),
+        Err(err) => report.warning(format!("failed to record distill provenance: {err:#}")),
+    }
 
     Ok(report)
 }
@@ -9,17 +9,91 @@ use crate::moon::state;
 
 #[derive(Debug, Clone)]
 pub struct MoonEmbedOptions {
-    pub collection_name: String,
+    /// Collection to embed; `None` uses `collections.default` from config.
+    pub collection_name: Option<String>,
     pub max_docs: usize,
     pub dry_run: bool,
     pub watcher_trigger: bool,
+    /// Drop the remote vector store and re-embed every projection.
+    pub rebuild: bool,
+    /// Report vector coverage of ledger projections instead of embedding.
+    pub verify: bool,
+}
+
+const VERIFY_LIST_LIMIT: usize = 10;
+
+fn push_path_list(report: &mut CommandReport, key: &str, paths: &[String]) {
+    for path in paths.iter().take(VERIFY_LIST_LIMIT) {
+        report.detail(format!("verify.{key}={path}"));
+    }
+    if paths.len() > VERIFY_LIST_LIMIT {
+        report.detail(format!(
+            "verify.{key}_more={}",
+            paths.len() - VERIFY_LIST_LIMIT
+        ));
+    }
+}
+
+fn run_verify() -> Result<CommandReport> {
+    let paths = resolve_paths()?;
+    let cfg = load_config()?;
+    let state = state::load(&paths)?;
+    let mut report = CommandReport::new("embed verify");
+
+    let out = embed::verify(&paths, &state, &cfg.embed)?;
+    report.detail(format!("verify.provider={}", out.provider));
+    if out.provider != "qmd" {
+        report.detail(format!("verify.model={}", out.model));
+        report.detail(format!("verify.vectors={}", out.vectors));
+    }
+    report.detail(format!("verify.source={}", out.source));
+    report.detail(format!("verify.ledger_archives={}", out.ledger_archives));
+    report.detail(format!("verify.projections={}", out.projections));
+    report.detail(format!("verify.embedded={}", out.embedded));
+    report.detail(format!("verify.missing={}", out.missing.len()));
+    report.detail(format!("verify.stale={}", out.stale.len()));
+    report.detail(format!("verify.orphaned={}", out.orphaned.len()));
+    report.detail(format!("verify.coverage={:.1}%", out.coverage_pct()));
+    push_path_list(&mut report, "missing_path", &out.missing);
+    push_path_list(&mut report, "stale_path", &out.stale);
+    push_path_list(&mut report, "orphaned_path", &out.orphaned);
+
+    if !out.missing.is_empty() || !out.stale.is_empty() {
+        report.issue(format!(
+            "{} of {} ledger projection(s) lack current vectors (missing={} stale={}); run `moon embed`",
+            out.missing.len() + out.stale.len(),
+            out.projections,
+            out.missing.len(),
+            out.stale.len()
+        ));
+    }
+    if !out.orphaned.is_empty() {
+        report.issue(format!(
+            "{} projection(s) in the vector store are not in the ledger",
+            out.orphaned.len()
+        ));
+    }
+    if out.model_mismatch > 0 || out.dimension_mismatch > 0 {
+        report.issue(format!(
+            "vector store mixes models or dimensions (model_mismatch={} dimension_mismatch={}); run `moon embed --rebuild`",
+            out.model_mismatch, out.dimension_mismatch
+        ));
+    }
+    Ok(report)
 }
 
 pub fn run(opts: &MoonEmbedOptions) -> Result<CommandReport> {
+    if opts.verify {
+        return run_verify();
+    }
     let paths = resolve_paths()?;
     let cfg = load_config()?;
     let mut state = state::load(&paths)?;
-    let mut report = CommandReport::new("embed");
+    let mut report = CommandReport::new(if opts.rebuild {
+        "embed rebuild"
+    } else {
+        "embed"
+    });
 
     let caller = if opts.watcher_trigger {
         EmbedCaller::Watcher
@@ -27,14 +101,22 @@ pub fn run(opts: &MoonEmbedOptions) -> Result<CommandReport> {
         EmbedCaller::Manual
     };
     let run_opts = EmbedRunOptions {
-        collection_name: opts.collection_name.clone(),
+        collection_name: opts
+            .collection_name
+            .clone()
+            .unwrap_or_else(|| cfg.collections.default.clone()),
         max_docs: opts.max_docs,
         dry_run: opts.dry_run,
         caller,
         max_cycle_secs: Some(300), // Default 300s for manual/command-line runs
     };
 
-    let run_result = embed::run(&paths, &mut state, &cfg.embed, &run_opts);
+    let run_result = if opts.rebuild {
+        embed::rebuild(&paths, &mut state, &cfg.embed, &run_opts)
+    } else {
+        embed::run(&paths, &mut state, &cfg.embed, &run_opts)
+    };
+    report.detail(format!("embed.rebuild={}", opts.rebuild));
     let state_file = state::save(&paths, &state)?;
     report.detail(format!("state_file={}", state_file.display()));
 
@@ -54,6 +136,13 @@ pub fn run(opts: &MoonEmbedOptions) -> Result<CommandReport> {
             report.detail(format!("embed.elapsed_ms={}", summary.elapsed_ms));
             report.detail(format!("embed.degraded={}", summary.degraded));
             report.detail(format!("embed.skip_reason={}", summary.skip_reason));
+            report.detail(format!("embed.provider={}", summary.provider));
+            if summary.provider != "qmd" {
+                report.detail(format!("embed.model={}", summary.model));
+                report.detail(format!("embed.model_changed={}", summary.model_changed));
+                report.detail(format!("embed.requests={}", summary.requests));
+                report.detail(format!("embed.vectors={}", summary.vectors));
+            }
 
             let status = if summary.degraded { "degraded" } else { "ok" };
             let _ = audit::append_event(
@@ -61,9 +150,10 @@ pub fn run(opts: &MoonEmbedOptions) -> Result<CommandReport> {
                 "embed",
                 status,
                 &format!(
-                    "mode={} collection={} capability={} selected={} embedded={} pending_before={} pending_after={} skip_reason={}",
+                    "mode={} collection={} provider={} capability={} selected={} embedded={} pending_before={} pending_after={} skip_reason={}",
                     summary.mode,
                     summary.collection,
+                    summary.provider,
                     summary.capability,
                     summary.selected_docs,
                     summary.embedded_docs,
@@ -71,6 +161,7 @@ pub fn run(opts: &MoonEmbedOptions) -> Result<CommandReport> {
                     summary.pending_after,
                     summary.skip_reason
                 ),
+                serde_json::to_value(&summary).unwrap_or_default(),
             );
         }
         Err(err) => {
@@ -86,8 +177,13 @@ pub fn run(opts: &MoonEmbedOptions) -> Result<CommandReport> {
                 &format!(
                     "mode={} collection={} error={err_text}",
                     caller.as_str(),
-                    opts.collection_name
+                    run_opts.collection_name
                 ),
+                serde_json::json!({
+                    "mode": caller.as_str(),
+                    "collection": run_opts.collection_name,
+                    "error": err_text,
+                }),
             );
 
             if opts.watcher_trigger
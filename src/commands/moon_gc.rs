@@ -0,0 +1,152 @@
+use anyhow::Result;
+
+use crate::commands::CommandReport;
+use crate::moon::archive::restore_ledger_record;
+use crate::moon::audit;
+use crate::moon::channel_archive_map;
+use crate::moon::config::load_config;
+use crate::moon::paths::resolve_paths;
+use crate::moon::qmd;
+use crate::moon::state;
+use crate::moon::trash::{purge_trash, restore_from_trash, trash_dir};
+use crate::moon::util::now_epoch_secs;
+
+#[derive(Debug, Clone, Default)]
+pub struct MoonGcPurgeOptions {
+    pub all: bool,
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MoonGcRestoreOptions {
+    pub path: String,
+}
+
+pub fn run_purge(opts: &MoonGcPurgeOptions) -> Result<CommandReport> {
+    let paths = resolve_paths()?;
+    let cfg = load_config()?;
+    let mut report = CommandReport::new("gc purge");
+
+    let older_than = if opts.all {
+        None
+    } else {
+        Some(cfg.retention.trash_days.saturating_mul(86_400))
+    };
+    let out = purge_trash(&paths, older_than, now_epoch_secs()?, opts.dry_run)?;
+    report.detail(format!("trash_dir={}", trash_dir(&paths).display()));
+    report.detail(format!(
+        "older_than_days={}",
+        if opts.all {
+            "any".to_string()
+        } else {
+            cfg.retention.trash_days.to_string()
+        }
+    ));
+    report.detail(format!("purged={}", out.purged));
+    report.detail(format!("purged_bytes={}", out.bytes));
+    report.detail(format!("kept={}", out.kept));
+    report.detail(format!("missing={}", out.missing));
+    if out.failed > 0 {
+        report.issue(format!("failed to purge {} trashed file(s)", out.failed));
+    }
+
+    if opts.dry_run {
+        report.detail("dry-run: trash not purged".to_string());
+        return Ok(report);
+    }
+    if out.purged > 0 || out.failed > 0 {
+        let _ = audit::append_event(
+            &paths,
+            "gc",
+            if out.failed > 0 { "degraded" } else { "ok" },
+            &format!(
+                "purge purged={} bytes={} kept={} missing={} failed={}",
+                out.purged, out.bytes, out.kept, out.missing, out.failed
+            ),
+            serde_json::json!({
+                "action": "purge",
+                "purged": out.purged,
+                "bytes": out.bytes,
+                "kept": out.kept,
+                "missing": out.missing,
+                "failed": out.failed,
+            }),
+        );
+    }
+
+    Ok(report)
+}
+
+pub fn run_restore(opts: &MoonGcRestoreOptions) -> Result<CommandReport> {
+    let paths = resolve_paths()?;
+    let mut report = CommandReport::new("gc restore");
+
+    let out = match restore_from_trash(&paths, &opts.path) {
+        Ok(out) => out,
+        Err(err) => {
+            report.issue(format!("{err:#}"));
+            return Ok(report);
+        }
+    };
+    report.detail(format!("archive_path={}", out.archive_path));
+    if out.restored.is_empty() {
+        report.issue(format!(
+            "trashed files for {} are no longer on disk",
+            out.archive_path
+        ));
+        return Ok(report);
+    }
+    for entry in &out.restored {
+        report.detail(format!("restored={}", entry.original_path));
+    }
+
+    let record = out.restored.iter().find_map(|e| e.record.as_ref());
+    let ledger_restored = match record {
+        Some(record) => restore_ledger_record(&paths, record)?,
+        None => false,
+    };
+    report.detail(format!("ledger_restored={ledger_restored}"));
+    let map_restored =
+        channel_archive_map::restore_retired_archive_path(&paths, &out.archive_path)?;
+    report.detail(format!("map_restored={map_restored}"));
+
+    // Keep the distill marker so the watcher does not distill the restored archive twice.
+    if let Some(distilled_at) = out.restored.iter().find_map(|e| e.distilled_at_epoch_secs) {
+        let mut moon_state = state::load(&paths)?;
+        moon_state
+            .distilled_archives
+            .insert(out.archive_path.clone(), distilled_at);
+        moon_state
+            .retention_protected_archives
+            .remove(&out.archive_path);
+        state::save(&paths, &moon_state)?;
+        report.detail("distill_marker_restored=true".to_string());
+    }
+
+    if ledger_restored {
+        match qmd::update(&paths.qmd_bin) {
+            Ok(()) => report.detail("qmd_updated=true".to_string()),
+            Err(err) => report.issue(format!("qmd update failed: {err:#}")),
+        }
+    }
+
+    let _ = audit::append_event(
+        &paths,
+        "gc",
+        "ok",
+        &format!(
+            "restore archive={} files={} ledger_restored={}",
+            out.archive_path,
+            out.restored.len(),
+            ledger_restored
+        ),
+        serde_json::json!({
+            "action": "restore",
+            "archive": out.archive_path,
+            "files": out.restored.len(),
+            "ledger_restored": ledger_restored,
+        }),
+    );
+
+    Ok(report)
+}
@@ -0,0 +1,47 @@
+use anyhow::Result;
+
+use crate::commands::CommandReport;
+use crate::moon::graph::{self, graph_edges_path};
+use crate::moon::paths::resolve_paths;
+
+#[derive(Debug, Clone)]
+pub struct MoonGraphQueryOptions {
+    pub entity: String,
+    pub limit: usize,
+}
+
+pub fn run_query(opts: &MoonGraphQueryOptions) -> Result<CommandReport> {
+    let paths = resolve_paths()?;
+    let mut report = CommandReport::new("graph query");
+
+    if opts.entity.trim().is_empty() {
+        report.issue("entity cannot be empty");
+        return Ok(report);
+    }
+    let edges_path = graph_edges_path(&paths);
+    report.detail(format!("graph_path={}", edges_path.display()));
+    if !edges_path.exists() {
+        report.detail(
+            "graph is empty; enable distill.graph_extraction (MOON_GRAPH_EXTRACTION=true) and run norm distillation"
+                .to_string(),
+        );
+        return Ok(report);
+    }
+
+    let edges = graph::query(&paths, &opts.entity, opts.limit)?;
+    report.detail(format!("entity={}", opts.entity.trim()));
+    report.detail(format!("edges={}", edges.len()));
+    for (idx, edge) in edges.iter().enumerate() {
+        report.detail(format!(
+            "edge[{idx}] {}({}) -[{}]-> {}({}) session={} source={}",
+            edge.subject,
+            edge.subject_kind.as_str(),
+            edge.relation,
+            edge.object,
+            edge.object_kind.as_str(),
+            edge.session_id,
+            edge.source_path
+        ));
+    }
+    Ok(report)
+}
@@ -1,13 +1,29 @@
 use crate::commands::CommandReport;
+use crate::env_loader;
+use crate::error::MoonErrorCode;
+use crate::moon::archive::read_ledger_records;
+use crate::moon::audit;
+use crate::moon::build_info::BuildInfo;
+use crate::moon::config::load_config;
+use crate::moon::consistency::{self, ConsistencyFinding};
 use crate::moon::daemon_lock::{daemon_lock_path, read_daemon_lock_payload};
-use crate::moon::paths::resolve_paths;
+use crate::moon::paths::{MoonPaths, resolve_paths};
+use crate::moon::qmd;
 use crate::moon::state::{self, MoonState};
-use crate::moon::util::now_epoch_secs;
+use crate::moon::util::{now_epoch_secs, read_only_mode};
 use anyhow::Result;
 use std::fs;
 use std::io::Write;
 
 const DEFAULT_MAX_CYCLE_AGE_SECS: u64 = 600;
+/// Timestamps this far ahead of the local clock are treated as clock anomalies.
+const CLOCK_SKEW_TOLERANCE_SECS: u64 = 300;
+
+#[derive(Debug, Clone, Default)]
+pub struct MoonHealthOptions {
+    /// Drop or repoint the dangling references the consistency check finds.
+    pub repair: bool,
+}
 
 #[derive(Debug, Clone, Copy, Default)]
 struct HeartbeatStatus {
@@ -37,30 +53,35 @@ fn check_state_file(
 
     let state_exists = state_path.exists();
 
-    if let Some(parent) = state_path.parent() {
-        if let Err(err) = fs::create_dir_all(parent) {
-            report.issue(format!("state.dir=unwritable ({err})"));
-            return heartbeat;
-        }
-        if state_exists {
-            let writable = fs::OpenOptions::new()
-                .append(true)
-                .open(&state_path)
-                .and_then(|mut f| f.write_all(b""));
-            if let Err(err) = writable {
-                report.issue(format!("state.file=unwritable ({err})"));
-                return heartbeat;
-            }
-        } else {
-            let probe = parent.join(".moon-health-write-probe");
-            let writable = fs::write(&probe, b"probe").and_then(|_| fs::remove_file(&probe));
-            if let Err(err) = writable {
+    if read_only_mode() {
+        // A read-only host must not leave probe files in a shared MOON_HOME.
+        report.detail("state.file=read-only (write probe skipped)".to_string());
+    } else {
+        if let Some(parent) = state_path.parent() {
+            if let Err(err) = fs::create_dir_all(parent) {
                 report.issue(format!("state.dir=unwritable ({err})"));
                 return heartbeat;
             }
+            if state_exists {
+                let writable = fs::OpenOptions::new()
+                    .append(true)
+                    .open(&state_path)
+                    .and_then(|mut f| f.write_all(b""));
+                if let Err(err) = writable {
+                    report.issue(format!("state.file=unwritable ({err})"));
+                    return heartbeat;
+                }
+            } else {
+                let probe = parent.join(".moon-health-write-probe");
+                let writable = fs::write(&probe, b"probe").and_then(|_| fs::remove_file(&probe));
+                if let Err(err) = writable {
+                    report.issue(format!("state.dir=unwritable ({err})"));
+                    return heartbeat;
+                }
+            }
         }
+        report.detail("state.file=writable".to_string());
     }
-    report.detail("state.file=writable".to_string());
 
     if !state_exists {
         report.detail("state.file=not_found (will be created on first cycle)".to_string());
@@ -70,22 +91,37 @@ fn check_state_file(
     let raw = match fs::read_to_string(&state_path) {
         Ok(raw) => raw,
         Err(err) => {
-            report.issue(format!("state.file=unreadable ({err})"));
+            report.coded_issue(
+                MoonErrorCode::E007StateCorrupt,
+                format!("state.file=unreadable ({err})"),
+            );
             return heartbeat;
         }
     };
 
-    let parsed: MoonState = match serde_json::from_str(&raw) {
+    let parsed = match state::parse(&raw) {
         Ok(state) => state,
         Err(err) => {
-            report.issue(format!("state.file=corrupt ({err})"));
+            report.coded_issue(
+                MoonErrorCode::E007StateCorrupt,
+                format!("state.file=corrupt ({err})"),
+            );
             return heartbeat;
         }
     };
 
     report.detail("state.file=parse_ok".to_string());
+    if let Some(build) = &parsed.build {
+        report.detail(format!("state.heartbeat_build={}", build.summary()));
+    }
+    if let Ok(now) = now_epoch_secs() {
+        check_state_clock(&parsed, now, report);
+    }
     if parsed.last_heartbeat_epoch_secs == 0 {
-        report.issue("state.last_heartbeat=missing".to_string());
+        report.coded_issue(
+            MoonErrorCode::E008HeartbeatStale,
+            "state.last_heartbeat=missing",
+        );
         return heartbeat;
     }
 
@@ -95,10 +131,13 @@ fn check_state_file(
     report.detail(format!("state.last_heartbeat_age_secs={age}"));
 
     if age > heartbeat.max_age_secs {
-        report.issue(format!(
-            "state.last_heartbeat=stale age_secs={age} max_allowed_secs={}",
-            heartbeat.max_age_secs
-        ));
+        report.coded_issue(
+            MoonErrorCode::E008HeartbeatStale,
+            format!(
+                "state.last_heartbeat=stale age_secs={age} max_allowed_secs={}",
+                heartbeat.max_age_secs
+            ),
+        );
     } else {
         heartbeat.is_fresh = true;
         report.detail(format!(
@@ -110,9 +149,276 @@ fn check_state_file(
     heartbeat
 }
 
-pub fn run() -> Result<CommandReport> {
+/// State timestamps newer than `now` (beyond the tolerance), newest per field.
+fn future_state_timestamps(state: &MoonState, now: u64) -> Vec<(&'static str, u64)> {
+    let newest = |map: &std::collections::BTreeMap<String, u64>| map.values().copied().max();
+    [
+        ("last_heartbeat", Some(state.last_heartbeat_epoch_secs)),
+        ("last_archive_trigger", state.last_archive_epoch_secs()),
+        (
+            "last_compaction_trigger",
+            state.last_compaction_epoch_secs(),
+        ),
+        ("last_distill_trigger", state.last_distill_epoch_secs()),
+        ("last_syns_trigger", state.last_syns_trigger_epoch_secs),
+        ("last_embed_trigger", state.last_embed_trigger_epoch_secs),
+        ("distilled_archives", newest(&state.distilled_archives)),
+        ("embedded_projections", newest(&state.embedded_projections)),
+        (
+            "compaction_hysteresis_active",
+            newest(&state.compaction_hysteresis_active),
+        ),
+        ("inbound_seen_files", newest(&state.inbound_seen_files)),
+    ]
+    .into_iter()
+    .filter_map(|(field, epoch)| {
+        epoch
+            .filter(|epoch| *epoch > now.saturating_add(CLOCK_SKEW_TOLERANCE_SECS))
+            .map(|epoch| (field, epoch))
+    })
+    .collect()
+}
+
+/// Cooldown and grace windows use `now - last`, which saturates to zero for future timestamps,
+/// so a clock that jumped backwards silently suppresses triggers until it catches up.
+fn check_state_clock(state: &MoonState, now: u64, report: &mut CommandReport) {
+    let future = future_state_timestamps(state, now);
+    if future.is_empty() {
+        report.detail("clock.state=ok".to_string());
+        return;
+    }
+    for (field, epoch) in future {
+        report.issue(format!(
+            "clock.state.{field}=future by {}s (epoch {epoch}, now {now}); check the system clock, cooldowns stay suppressed until it catches up",
+            epoch - now
+        ));
+    }
+}
+
+/// Ledger rows or archive files dated after now point at a clock that was ahead when they were
+/// written (or is behind now); retention and grace periods will treat them as brand new.
+fn check_archive_clock(paths: &MoonPaths, report: &mut CommandReport) {
+    let Ok(now) = now_epoch_secs() else {
+        report.issue("clock.system=before_unix_epoch".to_string());
+        return;
+    };
+    report.detail(format!("clock.now_epoch_secs={now}"));
+    let Ok(records) = read_ledger_records(paths) else {
+        return;
+    };
+    let limit = now.saturating_add(CLOCK_SKEW_TOLERANCE_SECS);
+    let future_records = records
+        .iter()
+        .filter(|record| record.created_at_epoch_secs > limit)
+        .count();
+    let future_files = records
+        .iter()
+        .filter_map(|record| fs::metadata(&record.archive_path).ok()?.modified().ok())
+        .filter_map(|modified| modified.duration_since(std::time::UNIX_EPOCH).ok())
+        .filter(|modified| modified.as_secs() > limit)
+        .count();
+    if future_records == 0 && future_files == 0 {
+        report.detail("clock.archives=ok".to_string());
+        return;
+    }
+    report.issue(format!(
+        "clock.archives=future ledger_records={future_records} archive_files={future_files} (now {now}); check the system clock before retention or grace logic runs"
+    ));
+}
+
+/// Reports each consistency finding as an issue; missing projections point at `moon index`.
+pub fn report_consistency_findings<'a>(
+    report: &mut CommandReport,
+    findings: impl IntoIterator<Item = &'a ConsistencyFinding>,
+) {
+    for finding in findings {
+        let issue = report.coded_issue(
+            MoonErrorCode::E015DanglingReference,
+            format!("consistency {}", finding.detail()),
+        );
+        if !finding.kind.repairable() {
+            issue.with_hint("run `moon index` to rebuild missing projections");
+        }
+    }
+}
+
+/// Flags a loaded `.env` that holds provider keys but is group/world accessible; with
+/// `repair`, restricts it to `0600`.
+fn check_env_permissions(repair: bool, report: &mut CommandReport) {
+    let Some(path) = env_loader::loaded_dotenv_path() else {
+        report.detail("env.file=not_loaded".to_string());
+        return;
+    };
+    report.detail(format!("env.file={}", path.display()));
+    let permissions = match env_loader::check_permissions(path) {
+        Ok(Some(permissions)) => permissions,
+        Ok(None) => {
+            report.detail("env.permissions=unchecked (no unix permissions)".to_string());
+            return;
+        }
+        Err(err) => {
+            report.warning(format!("env.permissions=unreadable ({err:#})"));
+            return;
+        }
+    };
+    if !permissions.is_exposed() {
+        report.detail(format!("env.permissions=ok ({:04o})", permissions.mode));
+        return;
+    }
+    if repair {
+        match env_loader::restrict_permissions(path) {
+            Ok(()) => report.detail(format!(
+                "env.permissions=restricted ({:04o} -> 0600)",
+                permissions.mode
+            )),
+            Err(err) => report.issue(format!("env.permissions=repair_failed ({err:#})")),
+        }
+        return;
+    }
+    report.warning(format!(
+        "env.permissions=exposed ({:04o}): {} holds provider keys but other users can read it; run `chmod 600` or `moon health --repair`",
+        permissions.mode,
+        path.display()
+    ));
+}
+
+/// Cross-references state, ledger, channel archive map, and the files on disk; with `repair`,
+/// drops or repoints what no longer resolves.
+fn check_consistency(paths: &MoonPaths, repair: bool, report: &mut CommandReport) -> Result<()> {
+    let Ok(mut moon_state) = state::load(paths) else {
+        report.detail("consistency=skipped (state unreadable)".to_string());
+        return Ok(());
+    };
+    let findings = match consistency::check(paths, &moon_state) {
+        Ok(findings) => findings,
+        Err(err) => {
+            report.issue(format!("consistency=failed ({err:#})"));
+            return Ok(());
+        }
+    };
+    report.detail(format!(
+        "consistency.findings={}",
+        consistency::summarize(&findings)
+    ));
+    if !repair || findings.iter().all(|finding| !finding.kind.repairable()) {
+        report_consistency_findings(report, &findings);
+        return Ok(());
+    }
+
+    let outcome = consistency::repair(paths, &mut moon_state, &findings)?;
+    if outcome.state_removed > 0 {
+        state::save(paths, &moon_state)?;
+    }
+    let summary = outcome.summary();
+    audit::append_event(
+        paths,
+        "consistency",
+        "repaired",
+        &summary,
+        serde_json::json!({
+            "findings": consistency::kind_counts(&findings)
+                .into_iter()
+                .collect::<std::collections::BTreeMap<_, _>>(),
+            "repaired": outcome,
+        }),
+    )?;
+    report.detail(format!("consistency.repair={summary}"));
+    report_consistency_findings(
+        report,
+        findings.iter().filter(|finding| !finding.kind.repairable()),
+    );
+    Ok(())
+}
+
+/// qmd binary, index database, and archive collections. Before anything has been archived the
+/// database and collections legitimately do not exist yet, so their absence is only a detail.
+fn check_qmd(paths: &MoonPaths, report: &mut CommandReport) {
+    match qmd::version(&paths.qmd_bin) {
+        Ok(version) => report.detail(format!("qmd.version={version}")),
+        Err(err) => {
+            report.coded_issue(
+                MoonErrorCode::E009QmdUnavailable,
+                format!("qmd.binary=unusable ({err:#})"),
+            );
+            return;
+        }
+    }
+    let has_archives = read_ledger_records(paths).is_ok_and(|records| !records.is_empty());
+    let flag = |report: &mut CommandReport, message: String, hint: String| {
+        if has_archives {
+            report
+                .coded_issue(MoonErrorCode::E010QmdCollection, message)
+                .with_hint(hint);
+        } else {
+            report.detail(format!("{message} (nothing archived yet)"));
+        }
+    };
+
+    match fs::File::open(&paths.qmd_db) {
+        Ok(_) => report.detail(format!("qmd.db=ok ({})", paths.qmd_db.display())),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => flag(
+            report,
+            format!("qmd.db=missing ({})", paths.qmd_db.display()),
+            "run `moon index` to rebuild the qmd index".to_string(),
+        ),
+        Err(err) => {
+            report.coded_issue(
+                MoonErrorCode::E009QmdUnavailable,
+                format!("qmd.db=unreadable ({}: {err})", paths.qmd_db.display()),
+            );
+        }
+    }
+
+    let listed = match qmd::collection_list(&paths.qmd_bin) {
+        Ok(listed) => listed,
+        Err(err) => {
+            report.coded_issue(
+                MoonErrorCode::E009QmdUnavailable,
+                format!("qmd.collections=unavailable ({err:#})"),
+            );
+            return;
+        }
+    };
+    let collections = load_config().map(|cfg| cfg.collections).unwrap_or_default();
+    for name in collections.names() {
+        let mask = qmd::archive_collection_mask(collections.subdir(&name));
+        let Some(info) = listed.iter().find(|info| info.name == name) else {
+            flag(
+                report,
+                format!("qmd.collection.{name}=missing"),
+                format!("run `moon index --name {name}`"),
+            );
+            continue;
+        };
+        match info.pattern.as_deref() {
+            Some(pattern) if pattern == mask.as_str() => {
+                report.detail(format!("qmd.collection.{name}.mask=ok"))
+            }
+            other => {
+                report
+                    .coded_issue(
+                        MoonErrorCode::E010QmdCollection,
+                        format!(
+                            "qmd.collection.{name}.mask=unexpected (found {} expected {mask})",
+                            other.unwrap_or("none")
+                        ),
+                    )
+                    .with_hint(format!("run `moon index --name {name}`"));
+            }
+        }
+        match info.documents {
+            Some(count) => report.detail(format!("qmd.collection.{name}.documents={count}")),
+            None => report.detail(format!("qmd.collection.{name}.documents=unknown")),
+        }
+    }
+}
+
+pub fn run(opts: &MoonHealthOptions) -> Result<CommandReport> {
+    run_for(&resolve_paths()?, opts)
+}
+
+pub fn run_for(paths: &MoonPaths, opts: &MoonHealthOptions) -> Result<CommandReport> {
     let mut report = CommandReport::new("health");
-    let paths = resolve_paths()?;
 
     report.detail(format!("moon_home={}", paths.moon_home.display()));
 
@@ -124,16 +430,23 @@ pub fn run() -> Result<CommandReport> {
         if path.exists() {
             report.detail(format!("path.{name}=ok"));
         } else {
-            report.issue(format!("path.{name}=missing ({})", path.display()));
+            report.coded_issue(
+                MoonErrorCode::E014PathMissing,
+                format!("path.{name}=missing ({})", path.display()),
+            );
         }
     }
 
-    let heartbeat = check_state_file(&paths, &mut report);
+    let heartbeat = check_state_file(paths, &mut report);
+    check_archive_clock(paths, &mut report);
+    check_qmd(paths, &mut report);
+    check_env_permissions(opts.repair, &mut report);
+    check_consistency(paths, opts.repair, &mut report)?;
 
     // Check daemon lock
-    let lock_path = daemon_lock_path(&paths);
+    let lock_path = daemon_lock_path(paths);
     if lock_path.exists() {
-        match read_daemon_lock_payload(&paths) {
+        match read_daemon_lock_payload(paths) {
             Ok(Some(payload)) => {
                 report.detail("daemon.lock=found".to_string());
                 report.detail(format!("daemon.pid={}", payload.pid));
@@ -150,28 +463,53 @@ pub fn run() -> Result<CommandReport> {
                 if crate::moon::util::pid_alive(payload.pid) {
                     report.detail("daemon.process=alive".to_string());
                 } else {
-                    report.issue("daemon.process=dead (stale lock)".to_string());
+                    report.coded_issue(
+                        MoonErrorCode::E001Locked,
+                        "daemon.process=dead (stale lock)",
+                    );
                 }
 
+                if let Some(build) = &payload.build {
+                    report.detail(format!("daemon.build={}", build.summary()));
+                }
                 if !payload.build_uuid.trim().is_empty() {
-                    let current_uuid = env!("BUILD_UUID");
-                    if payload.build_uuid == current_uuid {
+                    let current = BuildInfo::current();
+                    if payload.build_uuid == current.build_uuid {
                         report.detail("daemon.build_match=ok".to_string());
                     } else {
-                        report.issue(format!(
-                            "daemon.build_mismatch=found (lock={} current={})",
-                            payload.build_uuid, current_uuid
-                        ));
+                        let context = match &payload.build {
+                            Some(build) => format!(
+                                "daemon version={} git_sha={}, current version={} git_sha={}",
+                                build.version, build.git_sha, current.version, current.git_sha
+                            ),
+                            None => format!(
+                                "daemon predates build metadata, current version={} git_sha={}",
+                                current.version, current.git_sha
+                            ),
+                        };
+                        report.coded_issue(
+                            MoonErrorCode::E002StaleBuild,
+                            format!(
+                                "daemon.build_mismatch=found (lock={} current={}; {context})",
+                                payload.build_uuid, current.build_uuid
+                            ),
+                        );
                     }
                 } else {
-                    report.issue("daemon.build_uuid=missing".to_string());
+                    report.coded_issue(MoonErrorCode::E002StaleBuild, "daemon.build_uuid=missing");
                 }
             }
             Ok(None) => {
-                report.issue("daemon.lock=corrupt (empty payload)".to_string());
+                report.coded_issue(
+                    MoonErrorCode::E001Locked,
+                    "daemon.lock=corrupt (empty payload)",
+                );
             }
             Err(err) => {
-                report.issue(format!("daemon.lock=corrupt ({err})"));
+                report.coded_issue(
+                    MoonErrorCode::E001Locked,
+                    format!("daemon.lock=corrupt ({err})"),
+                );
             }
         }
     } else if heartbeat.is_fresh {
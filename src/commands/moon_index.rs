@@ -1,41 +1,103 @@
 use anyhow::Result;
 
 use crate::commands::CommandReport;
-use crate::moon::archive::{backfill_archive_projections, normalize_archive_layout};
+use crate::error::MoonErrorCode;
+use crate::moon::archive::{
+    MigrationRunOptions, backfill_archive_projections, normalize_archive_layout,
+};
+use crate::moon::audit;
 use crate::moon::channel_archive_map;
-use crate::moon::paths::resolve_paths;
+use crate::moon::config::load_config;
+use crate::moon::paths::{MoonPaths, resolve_paths};
 use crate::moon::qmd;
 use crate::moon::qmd::CollectionSyncResult;
 use crate::moon::state;
 
 #[derive(Debug, Clone)]
 pub struct MoonIndexOptions {
-    pub collection_name: String,
+    /// Collection to sync; `None` syncs every collection configured in `[collections]`.
+    pub collection_name: Option<String>,
+    /// Drop and recreate the collections and rebuild every projection from its raw archive.
+    pub reindex_all: bool,
+    /// Process at most this many ledger records per pass; later runs resume from the cursor.
+    pub limit: Option<usize>,
+    /// With `reindex_all`, keep each overwritten projection as `mlib-prev/<name>.prev.md`.
+    pub keep_prev: bool,
     pub dry_run: bool,
 }
 
+const PROJECTION_DIFF_DETAIL_LIMIT: usize = 20;
+
+const REINDEX_STEPS: usize = 3;
+
+fn cursor_detail(next_cursor: Option<usize>) -> String {
+    next_cursor
+        .map(|idx| idx.to_string())
+        .unwrap_or_else(|| "done".to_string())
+}
+
+fn reindex_progress(report: &mut CommandReport, step: usize, message: String) {
+    eprintln!("moon index: reindex [{step}/{REINDEX_STEPS}] {message}");
+    report.detail(format!("reindex.step[{step}/{REINDEX_STEPS}]={message}"));
+}
+
 pub fn run(opts: &MoonIndexOptions) -> Result<CommandReport> {
     let paths = resolve_paths()?;
     let mut report = CommandReport::new("index");
+    let collections_cfg = load_config()?.collections;
+    let collection_names = match &opts.collection_name {
+        Some(name) => vec![name.clone()],
+        None => collections_cfg.names(),
+    };
 
     report.detail(format!("archives_dir={}", paths.archives_dir.display()));
     report.detail(format!("qmd_bin={}", paths.qmd_bin.display()));
-    report.detail(format!("collection_name={}", opts.collection_name));
+    report.detail(format!("collection_name={}", collection_names.join(",")));
 
     if !paths.archives_dir.exists() {
-        report.issue("archives dir does not exist");
+        report.coded_issue(
+            MoonErrorCode::E014PathMissing,
+            "archives dir does not exist",
+        );
         return Ok(report);
     }
+    if opts.limit == Some(0) {
+        report.coded_issue(MoonErrorCode::E013InvalidArgument, "--limit must be >= 1");
+        return Ok(report);
+    }
+
+    report.detail(format!("reindex_all={}", opts.reindex_all));
 
     if opts.dry_run {
-        report.detail(
-            "dry-run: qmd collection add planned (with update fallback on existing collection)"
-                .to_string(),
-        );
+        if opts.reindex_all {
+            report.detail(format!(
+                "dry-run: reindex planned: remove collection(s) {names}, reproject every ledger archive, re-add {names}",
+                names = collection_names.join(",")
+            ));
+        } else {
+            report.detail(
+                "dry-run: qmd collection add planned (with update fallback on existing collection)"
+                    .to_string(),
+            );
+        }
         return Ok(report);
     }
 
-    let layout = normalize_archive_layout(&paths)?;
+    let run_opts = MigrationRunOptions {
+        limit: opts.limit,
+        keep_previous: opts.keep_prev,
+    };
+    let layout = normalize_archive_layout(&paths, &run_opts, &mut |done, total| {
+        eprintln!("moon index: layout_migration {done}/{total}");
+    })?;
+    report.detail(format!(
+        "layout_migration.resumed_from={}",
+        layout.resumed_from
+    ));
+    report.detail(format!(
+        "layout_migration.next_cursor={}",
+        cursor_detail(layout.next_cursor)
+    ));
     report.detail(format!("layout_migration.scanned={}", layout.scanned));
     report.detail(format!("layout_migration.moved={}", layout.moved));
     report.detail(format!("layout_migration.missing={}", layout.missing));
@@ -61,27 +123,122 @@ pub fn run(opts: &MoonIndexOptions) -> Result<CommandReport> {
         report.detail(format!("layout_migration.state_updates={}", state_updates));
     }
 
-    let backfill = backfill_archive_projections(&paths, false)?;
+    if layout.next_cursor.is_some() {
+        report.detail(
+            "projection_backfill=skipped reason=layout-migration-incomplete (rerun to continue)"
+                .to_string(),
+        );
+    } else {
+        run_projection_backfill(&paths, opts, &run_opts, &collection_names, &mut report)?;
+    }
+
+    for collection_name in &collection_names {
+        if opts.reindex_all {
+            reindex_progress(&mut report, 3, format!("add collection={collection_name}"));
+        }
+        match qmd::collection_add_or_update(
+            &paths.qmd_bin,
+            &paths.archives_dir,
+            collection_name,
+            collections_cfg.subdir(collection_name),
+        )? {
+            CollectionSyncResult::Added => report.detail(format!(
+                "qmd collection add completed collection={collection_name}"
+            )),
+            CollectionSyncResult::Updated => report.detail(format!(
+                "qmd update completed (collection already existed) collection={collection_name}"
+            )),
+            CollectionSyncResult::Recreated => report.detail(format!(
+                "qmd collection recreated with latest archive projection mask collection={collection_name}"
+            )),
+        }
+    }
+
+    Ok(report)
+}
+
+fn run_projection_backfill(
+    paths: &MoonPaths,
+    opts: &MoonIndexOptions,
+    run_opts: &MigrationRunOptions,
+    collection_names: &[String],
+    report: &mut CommandReport,
+) -> Result<()> {
+    if opts.reindex_all {
+        for collection_name in collection_names {
+            let removed = qmd::collection_remove(&paths.qmd_bin, collection_name)?;
+            reindex_progress(
+                report,
+                1,
+                format!(
+                    "remove collection={collection_name} {}",
+                    if removed { "removed" } else { "absent" }
+                ),
+            );
+        }
+    }
+
+    let backfill =
+        backfill_archive_projections(paths, opts.reindex_all, run_opts, &mut |done, total| {
+            eprintln!("moon index: projection_backfill {done}/{total}");
+        })?;
+    if opts.reindex_all {
+        reindex_progress(
+            report,
+            2,
+            format!(
+                "reproject scanned={} created={} failed={}",
+                backfill.scanned, backfill.created, backfill.failed
+            ),
+        );
+    }
+    report.detail(format!(
+        "projection_backfill.resumed_from={}",
+        backfill.resumed_from
+    ));
+    report.detail(format!(
+        "projection_backfill.next_cursor={}",
+        cursor_detail(backfill.next_cursor)
+    ));
     report.detail(format!("projection_backfill.scanned={}", backfill.scanned));
     report.detail(format!("projection_backfill.created={}", backfill.created));
     report.detail(format!("projection_backfill.failed={}", backfill.failed));
+    report.detail(format!(
+        "projection_backfill.duplicates_marked={}",
+        backfill.duplicates_marked
+    ));
     report.detail(format!(
         "projection_backfill.ledger_updated={}",
         backfill.ledger_updated
     ));
+    if opts.reindex_all {
+        report.detail(format!(
+            "projection_diff.changed={} projection_diff.unchanged={}",
+            backfill.diffs.len(),
+            backfill.unchanged
+        ));
+        for diff in backfill.diffs.iter().take(PROJECTION_DIFF_DETAIL_LIMIT) {
+            report.detail(format!("projection_diff {}", diff.summary()));
+        }
+        if backfill.diffs.len() > PROJECTION_DIFF_DETAIL_LIMIT {
+            report.detail(format!(
+                "projection_diff.more={}",
+                backfill.diffs.len() - PROJECTION_DIFF_DETAIL_LIMIT
+            ));
+        }
+        for diff in &backfill.diffs {
+            let _ = audit::append_event(
+                paths,
+                "reproject",
+                "ok",
+                &diff.summary(),
+                serde_json::to_value(diff).unwrap_or_default(),
+            );
+        }
+    }
     if backfill.failed > 0 {
         report.issue("some archive projections failed to build; check archive readability");
     }
 
-    match qmd::collection_add_or_update(&paths.qmd_bin, &paths.archives_dir, &opts.collection_name)?
-    {
-        CollectionSyncResult::Added => report.detail("qmd collection add completed".to_string()),
-        CollectionSyncResult::Updated => {
-            report.detail("qmd update completed (collection already existed)".to_string())
-        }
-        CollectionSyncResult::Recreated => report
-            .detail("qmd collection recreated with latest archive projection mask".to_string()),
-    }
-
-    Ok(report)
+    Ok(())
 }
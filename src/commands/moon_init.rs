@@ -0,0 +1,285 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::io::{BufRead, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+
+use crate::commands::install::{self, InstallOptions};
+use crate::commands::{CommandReport, moon_health, verify};
+use crate::moon::paths::{MoonPaths, resolve_paths_with_home};
+use crate::moon::qmd;
+use crate::openclaw::gateway;
+
+const MOON_TOML_TEMPLATE: &str = include_str!("../../moon.toml.example");
+const ENV_TEMPLATE: &str = include_str!("../../.env.example");
+
+#[derive(Debug, Clone, Default)]
+pub struct MoonInitOptions {
+    pub moon_home: Option<PathBuf>,
+    /// Accept defaults without prompting even on a terminal.
+    pub yes: bool,
+    /// Install the OpenClaw plugin (and the watcher service where supported).
+    pub install: bool,
+    /// Overwrite an existing `moon.toml` / `.env`.
+    pub force: bool,
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Detected {
+    openclaw_bin: Option<PathBuf>,
+    qmd_bin: Option<PathBuf>,
+}
+
+pub fn run(opts: &MoonInitOptions) -> Result<CommandReport> {
+    let mut report = CommandReport::new("init");
+    let mut opts = opts.clone();
+    if !opts.yes && std::io::stdin().is_terminal() {
+        prompt_choices(&mut opts)?;
+    }
+
+    let paths = resolve_paths_with_home(opts.moon_home.as_deref())?;
+    report.detail(format!("moon_home={}", paths.moon_home.display()));
+
+    let detected = detect_tools(&paths, &mut report);
+
+    for dir in init_dirs(&paths) {
+        if dir.exists() {
+            report.detail(format!("dir={} status=exists", dir.display()));
+        } else if opts.dry_run {
+            report.detail(format!("dir={} status=planned", dir.display()));
+        } else {
+            fs::create_dir_all(&dir)
+                .with_context(|| format!("failed to create {}", dir.display()))?;
+            report.detail(format!("dir={} status=created", dir.display()));
+        }
+    }
+
+    let moon_dir = paths.moon_home.join("moon");
+    write_template(
+        &moon_dir.join("moon.toml"),
+        MOON_TOML_TEMPLATE,
+        &opts,
+        &mut report,
+    )?;
+    let env_payload = render_env(ENV_TEMPLATE, &paths, &detected);
+    let env_path = moon_dir.join(".env");
+    write_template(&env_path, &env_payload, &opts, &mut report)?;
+    #[cfg(unix)]
+    if !opts.dry_run && env_path.exists() {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&env_path, fs::Permissions::from_mode(0o600))
+            .with_context(|| format!("failed to restrict {}", env_path.display()))?;
+    }
+
+    if opts.install {
+        if detected.openclaw_bin.is_some() {
+            report.merge(install::run(&InstallOptions {
+                force: false,
+                dry_run: opts.dry_run,
+                apply: true,
+                prefix: None,
+                packaging_dir: None,
+                pin: None,
+                unpin: false,
+                channel: None,
+            })?);
+        } else {
+            report.warning("install=skipped reason=openclaw_not_found");
+        }
+    } else {
+        report.detail("install=skipped (rerun with --install or run `moon install`)".to_string());
+    }
+
+    // Doctor pass. A fresh MOON_HOME has no daemon state yet, so health findings are advisory.
+    let health = moon_health::run_for(&paths, &moon_health::MoonHealthOptions::default())?;
+    report.details.extend(health.details);
+    for issue in health.issues {
+        report.warning(format!("doctor: {issue}"));
+    }
+    if opts.install && detected.openclaw_bin.is_some() && !opts.dry_run {
+        report.merge(verify::run(&verify::VerifyOptions::default())?);
+    }
+
+    if paths.moon_home_is_explicit && std::env::var_os("MOON_HOME").is_none() {
+        report.info(format!(
+            "export MOON_HOME={} so later commands find this workspace and load {}",
+            paths.moon_home.display(),
+            env_path.display()
+        ));
+    }
+
+    Ok(report)
+}
+
+fn prompt_choices(opts: &mut MoonInitOptions) -> Result<()> {
+    let stdin = std::io::stdin();
+    let mut lines = stdin.lock().lines();
+    let mut ask = |question: &str| -> Result<String> {
+        eprint!("{question}");
+        std::io::stderr().flush()?;
+        Ok(lines
+            .next()
+            .transpose()?
+            .unwrap_or_default()
+            .trim()
+            .to_string())
+    };
+
+    if opts.moon_home.is_none() {
+        let current = resolve_paths_with_home(None)?.moon_home;
+        let answer = ask(&format!("MOON_HOME [{}]: ", current.display()))?;
+        if !answer.is_empty() {
+            opts.moon_home = Some(PathBuf::from(answer));
+        }
+    }
+    if !opts.install {
+        let answer = ask("Install the OpenClaw plugin and watcher service now? [y/N]: ")?;
+        opts.install = matches!(answer.to_ascii_lowercase().as_str(), "y" | "yes");
+    }
+    Ok(())
+}
+
+fn detect_tools(paths: &MoonPaths, report: &mut CommandReport) -> Detected {
+    let mut detected = Detected::default();
+    match gateway::resolve_openclaw_bin_path() {
+        Ok(bin) => {
+            report.detail(format!("detect.openclaw={}", bin.display()));
+            detected.openclaw_bin = Some(bin);
+        }
+        Err(err) => report.warning(format!("detect.openclaw=missing ({err:#})")),
+    }
+
+    let qmd_bin = if paths.qmd_bin.is_file() {
+        Some(paths.qmd_bin.clone())
+    } else {
+        which::which("qmd").ok()
+    };
+    match qmd_bin {
+        Some(bin) => {
+            let version = qmd::version(&bin).unwrap_or_else(|_| "unknown".to_string());
+            report.detail(format!("detect.qmd={} version={version}", bin.display()));
+            detected.qmd_bin = Some(bin);
+        }
+        None => report.warning(format!(
+            "detect.qmd=missing (install qmd or set QMD_BIN; looked at {})",
+            paths.qmd_bin.display()
+        )),
+    }
+    detected
+}
+
+fn init_dirs(paths: &MoonPaths) -> Vec<PathBuf> {
+    vec![
+        paths.archives_dir.clone(),
+        paths.memory_dir.clone(),
+        paths.logs_dir.clone(),
+        paths.moon_home.join("moon").join("state"),
+        paths.moon_home.join("continuity"),
+    ]
+}
+
+fn write_template(
+    path: &Path,
+    payload: &str,
+    opts: &MoonInitOptions,
+    report: &mut CommandReport,
+) -> Result<()> {
+    let exists = path.exists();
+    let status = match (exists, opts.force, opts.dry_run) {
+        (true, false, _) => "kept",
+        (_, _, true) => "planned",
+        (true, true, false) => "overwritten",
+        (false, _, false) => "written",
+    };
+    if matches!(status, "written" | "overwritten") {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create {}", parent.display()))?;
+        }
+        fs::write(path, payload).with_context(|| format!("failed to write {}", path.display()))?;
+    }
+    report.detail(format!("file={} status={status}", path.display()));
+    Ok(())
+}
+
+/// The `.env.example` template with the workspace and detected binaries filled in.
+fn render_env(template: &str, paths: &MoonPaths, detected: &Detected) -> String {
+    // An undetected OpenClaw stays commented out rather than pointing at the placeholder path.
+    let values = [
+        ("MOON_HOME", Some(paths.moon_home.display().to_string())),
+        (
+            "OPENCLAW_BIN",
+            detected
+                .openclaw_bin
+                .as_ref()
+                .map(|bin| bin.display().to_string()),
+        ),
+        (
+            "QMD_BIN",
+            detected
+                .qmd_bin
+                .as_ref()
+                .map(|bin| bin.display().to_string()),
+        ),
+    ];
+
+    let mut out = String::with_capacity(template.len());
+    for line in template.lines() {
+        let replaced = values.iter().find_map(|(key, value)| {
+            let rest = line
+                .strip_prefix(key)
+                .filter(|rest| rest.starts_with('='))?;
+            Some(match value {
+                Some(value) => format!("{key}={value}"),
+                None if *key == "OPENCLAW_BIN" => format!("# {key}{rest}"),
+                None => line.to_string(),
+            })
+        });
+        out.push_str(replaced.as_deref().unwrap_or(line));
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_env_comments_out_undetected_openclaw() {
+        let out = render_env(
+            "OPENCLAW_BIN=/absolute/path/to/openclaw\n",
+            &test_paths(),
+            &Detected::default(),
+        );
+        assert_eq!(out, "# OPENCLAW_BIN=/absolute/path/to/openclaw\n");
+    }
+
+    #[test]
+    fn bundled_moon_toml_template_parses() {
+        toml::from_str::<toml::Value>(MOON_TOML_TEMPLATE).expect("moon.toml.example is valid toml");
+    }
+
+    fn test_paths() -> MoonPaths {
+        let root = PathBuf::from("/work");
+        MoonPaths::for_test(&root)
+    }
+
+    #[test]
+    fn render_env_fills_detected_paths_only_for_exact_keys() {
+        let paths = test_paths();
+        let detected = Detected {
+            openclaw_bin: Some(PathBuf::from("/usr/bin/openclaw")),
+            qmd_bin: None,
+        };
+        let out = render_env(
+            "OPENCLAW_BIN=/x\nMOON_HOME=$HOME\nMOON_HOME_EXTRA=1\nQMD_BIN=q\n",
+            &paths,
+            &detected,
+        );
+        assert_eq!(
+            out,
+            "OPENCLAW_BIN=/usr/bin/openclaw\nMOON_HOME=/work\nMOON_HOME_EXTRA=1\nQMD_BIN=q\n"
+        );
+    }
+}
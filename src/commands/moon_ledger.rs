@@ -0,0 +1,130 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::commands::CommandReport;
+use crate::error::MoonErrorCode;
+use crate::moon::archive::{compact_ledger, portable_path_str, set_distill_skip};
+use crate::moon::audit;
+use crate::moon::paths::resolve_paths;
+use crate::moon::privacy;
+
+#[derive(Debug, Clone, Default)]
+pub struct MoonLedgerCompactOptions {
+    pub dry_run: bool,
+}
+
+pub fn run_compact(opts: &MoonLedgerCompactOptions) -> Result<CommandReport> {
+    let paths = resolve_paths()?;
+    let mut report = CommandReport::new("ledger compact");
+
+    let out = compact_ledger(&paths, opts.dry_run)?;
+    report.detail(format!("records_before={}", out.before));
+    report.detail(format!("records_kept={}", out.kept));
+    report.detail(format!("dropped_missing={}", out.missing));
+    report.detail(format!("dropped_superseded={}", out.superseded));
+    report.detail(format!("history_path={}", out.history_path.display()));
+
+    if opts.dry_run {
+        report.detail("dry-run: ledger not rewritten".to_string());
+        return Ok(report);
+    }
+    let dropped = out.missing + out.superseded;
+    if dropped > 0 {
+        let _ = audit::append_event(
+            &paths,
+            "ledger",
+            "ok",
+            &format!(
+                "compact before={} kept={} missing={} superseded={}",
+                out.before, out.kept, out.missing, out.superseded
+            ),
+            serde_json::json!({
+                "action": "compact",
+                "before": out.before,
+                "kept": out.kept,
+                "missing": out.missing,
+                "superseded": out.superseded,
+            }),
+        );
+    }
+
+    Ok(report)
+}
+
+#[derive(Debug, Clone)]
+pub struct MoonLedgerSkipDistillOptions {
+    pub archive: String,
+    pub clear: bool,
+}
+
+/// Flags an archive so the watcher never distills it (e.g. log dumps); `clear` undoes it.
+pub fn run_skip_distill(opts: &MoonLedgerSkipDistillOptions) -> Result<CommandReport> {
+    let paths = resolve_paths()?;
+    let mut report = CommandReport::new("ledger skip-distill");
+    let archive = portable_path_str(opts.archive.trim());
+    let skip = !opts.clear;
+
+    if !set_distill_skip(&paths, &archive, skip)? {
+        report.coded_issue(
+            MoonErrorCode::E013InvalidArgument,
+            format!("no ledger record for archive {archive}"),
+        );
+        return Ok(report);
+    }
+    report.detail(format!("archive={archive}"));
+    report.detail(format!("distill_skip={skip}"));
+    let _ = audit::append_event(
+        &paths,
+        "ledger",
+        "ok",
+        &format!("skip-distill archive={archive} distill_skip={skip}"),
+        serde_json::json!({
+            "action": "skip-distill",
+            "archive": archive,
+            "distill_skip": skip,
+        }),
+    );
+    Ok(report)
+}
+
+#[derive(Debug, Clone)]
+pub struct MoonLedgerDecryptOptions {
+    pub archive: PathBuf,
+    pub output: PathBuf,
+}
+
+/// Writes the plaintext of a `[privacy] encrypt` archive to `output`; MOON_HOME is untouched.
+pub fn run_decrypt(opts: &MoonLedgerDecryptOptions) -> Result<CommandReport> {
+    let mut report = CommandReport::new("ledger decrypt");
+    let raw = fs::read(&opts.archive)
+        .with_context(|| format!("failed to read {}", opts.archive.display()))?;
+    if !privacy::is_encrypted(&raw) {
+        report.issue(format!(
+            "{} is not an encrypted archive",
+            opts.archive.display()
+        ));
+        return Ok(report);
+    }
+    let plain = match privacy::decrypt_bytes(&raw) {
+        Ok(plain) => plain,
+        Err(err) => {
+            report.issue(format!("{err:#}"));
+            return Ok(report);
+        }
+    };
+    if opts.output == opts.archive {
+        report.issue("--output must differ from --archive".to_string());
+        return Ok(report);
+    }
+    if let Some(parent) = opts.output.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+    fs::write(&opts.output, &plain)
+        .with_context(|| format!("failed to write {}", opts.output.display()))?;
+    report.detail(format!("archive={}", opts.archive.display()));
+    report.detail(format!("output={}", opts.output.display()));
+    report.detail(format!("bytes={}", plain.len()));
+    Ok(report)
+}
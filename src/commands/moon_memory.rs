@@ -0,0 +1,294 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::commands::{CommandReport, ensure_openclaw_available};
+use crate::error::MoonErrorCode;
+use crate::moon::audit;
+use crate::moon::config::load_config;
+use crate::moon::distill::{acquire_memory_lock, atomic_write_file};
+use crate::moon::memory::{
+    MemoryExport, MemoryRecord, build_memory_primer, diff_memory_since, ensure_memory_baseline,
+    export_memory_records, is_memory_file_record, merge_memory_records, parse_since_window,
+    record_memory_snapshot, render_memory_export_yaml,
+};
+use crate::moon::paths::resolve_paths;
+use crate::moon::util::now_epoch_secs;
+use crate::openclaw::gateway;
+
+#[derive(Debug, Clone)]
+pub enum MoonMemoryAction {
+    Diff {
+        since: String,
+    },
+    Inject {
+        session_key: String,
+        max_tokens: Option<u64>,
+        dry_run: bool,
+    },
+    Export {
+        format: String,
+        output: Option<String>,
+    },
+    Import {
+        path: String,
+        dry_run: bool,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct MoonMemoryOptions {
+    pub action: MoonMemoryAction,
+}
+
+fn run_diff(since: &str) -> Result<CommandReport> {
+    let paths = resolve_paths()?;
+    let mut report = CommandReport::new("memory diff");
+
+    let window_secs = match parse_since_window(since) {
+        Ok(secs) => secs,
+        Err(err) => {
+            report.issue(format!("{err:#}"));
+            return Ok(report);
+        }
+    };
+    let since_epoch = now_epoch_secs()?.saturating_sub(window_secs);
+    let diff = diff_memory_since(&paths, since_epoch)?;
+
+    report.detail(format!("since={since} since_epoch_secs={since_epoch}"));
+    report.detail(format!("memory_file={}", paths.memory_file.display()));
+    match &diff.baseline_path {
+        Some(path) => report.detail(format!("baseline={}", path.display())),
+        None => report.detail("baseline=none (no memory history before window)".to_string()),
+    }
+    report.detail(format!(
+        "summary added={} modified={} consolidated={} removed={}",
+        diff.added.len(),
+        diff.modified.len(),
+        diff.consolidated.len(),
+        diff.removed.len()
+    ));
+    for bullet in &diff.added {
+        report.detail(format!("+ {bullet}"));
+    }
+    for (old, new) in &diff.modified {
+        report.detail(format!("~ {old} -> {new}"));
+    }
+    for (olds, new) in &diff.consolidated {
+        report.detail(format!("= {} -> {new}", olds.join(" | ")));
+    }
+    for bullet in &diff.removed {
+        report.detail(format!("- {bullet}"));
+    }
+    for daily in &diff.daily_files {
+        report.detail(format!(
+            "daily day={} sessions={} lines={} path={}",
+            daily.day_key,
+            daily.sessions,
+            daily.lines,
+            daily.path.display()
+        ));
+    }
+
+    Ok(report)
+}
+
+fn run_inject(session_key: &str, max_tokens: Option<u64>, dry_run: bool) -> Result<CommandReport> {
+    let paths = resolve_paths()?;
+    let cfg = load_config()?;
+    let mut report = CommandReport::new("memory inject");
+
+    let session_key = session_key.trim();
+    if session_key.is_empty() {
+        report.issue("session key cannot be empty");
+        return Ok(report);
+    }
+    let max_tokens = max_tokens.unwrap_or(cfg.memory.primer_max_tokens);
+    if max_tokens == 0 {
+        report.coded_issue(
+            MoonErrorCode::E013InvalidArgument,
+            "--max-tokens must be >= 1",
+        );
+        return Ok(report);
+    }
+
+    let memory = fs::read_to_string(&paths.memory_file).unwrap_or_default();
+    let Some(primer) = build_memory_primer(&memory, max_tokens) else {
+        report.issue(format!(
+            "no memory bullets to inject from {}",
+            paths.memory_file.display()
+        ));
+        return Ok(report);
+    };
+
+    report.detail(format!("session_key={session_key}"));
+    report.detail(format!("max_tokens={max_tokens}"));
+    report.detail(format!("primer_bytes={}", primer.len()));
+    if dry_run {
+        report.detail("dry_run=true".to_string());
+        for line in primer.lines() {
+            report.detail(format!("primer: {line}"));
+        }
+        return Ok(report);
+    }
+
+    if !ensure_openclaw_available(&mut report) {
+        return Ok(report);
+    }
+    match gateway::run_sessions_memory_primer(session_key, &primer) {
+        Ok(result) => {
+            report.detail(format!("inject={result}"));
+            let _ = audit::append_event(
+                &paths,
+                "memory-inject",
+                "ok",
+                &format!("session_key={session_key} primer_bytes={}", primer.len()),
+                serde_json::json!({"session": session_key, "primer_bytes": primer.len()}),
+            );
+        }
+        Err(err) => report.issue(format!("memory primer injection failed: {err:#}")),
+    }
+
+    Ok(report)
+}
+
+fn run_export(format: &str, output: Option<&str>) -> Result<CommandReport> {
+    let paths = resolve_paths()?;
+    let mut report = CommandReport::new("memory export");
+
+    let format = format.trim().to_ascii_lowercase();
+    let extension = match format.as_str() {
+        "json" => "json",
+        "yaml" | "yml" => "yaml",
+        other => {
+            report.coded_issue(
+                MoonErrorCode::E013InvalidArgument,
+                format!("invalid --format `{other}`; use json or yaml"),
+            );
+            return Ok(report);
+        }
+    };
+
+    let now = now_epoch_secs()?;
+    let export = export_memory_records(&paths, now)?;
+    let rendered = if extension == "json" {
+        let mut out = serde_json::to_string_pretty(&export)?;
+        out.push('\n');
+        out
+    } else {
+        render_memory_export_yaml(&export)?
+    };
+
+    let output_path = match output.map(str::trim).filter(|v| !v.is_empty()) {
+        Some(path) => PathBuf::from(path),
+        None => paths
+            .moon_home
+            .join("moon")
+            .join("exports")
+            .join(format!("memory-export-{now}.{extension}")),
+    };
+    if let Some(parent) = output_path.parent()
+        && !parent.as_os_str().is_empty()
+    {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+    fs::write(&output_path, rendered)
+        .with_context(|| format!("failed to write {}", output_path.display()))?;
+
+    let memory_records = export
+        .records
+        .iter()
+        .filter(|record| is_memory_file_record(&paths, record))
+        .count();
+    report.detail(format!("format={extension}"));
+    report.detail(format!("output={}", output_path.display()));
+    report.detail(format!("records={}", export.records.len()));
+    report.detail(format!("memory_records={memory_records}"));
+    report.detail(format!(
+        "daily_records={}",
+        export.records.len() - memory_records
+    ));
+    Ok(report)
+}
+
+fn run_import(path: &str, dry_run: bool) -> Result<CommandReport> {
+    let paths = resolve_paths()?;
+    let mut report = CommandReport::new("memory import");
+
+    let raw = fs::read_to_string(path).with_context(|| format!("failed to read {path}"))?;
+    let records = match serde_json::from_str::<MemoryExport>(&raw) {
+        Ok(export) => export.records,
+        Err(_) => match serde_json::from_str::<Vec<MemoryRecord>>(&raw) {
+            Ok(records) => records,
+            Err(err) => {
+                report.issue(format!(
+                    "import expects a `moon memory export --format json` document or a JSON array of records: {err}"
+                ));
+                return Ok(report);
+            }
+        },
+    };
+
+    let (memory_records, daily_records): (Vec<_>, Vec<_>) = records
+        .into_iter()
+        .partition(|record| is_memory_file_record(&paths, record));
+    let previous = fs::read_to_string(&paths.memory_file).unwrap_or_default();
+    let (_, added) = merge_memory_records(&previous, &memory_records);
+
+    report.detail(format!("source={path}"));
+    report.detail(format!("memory_file={}", paths.memory_file.display()));
+    report.detail(format!("memory_records={}", memory_records.len()));
+    report.detail(format!("added={}", added.len()));
+    report.detail(format!(
+        "skipped_existing={}",
+        memory_records.len() - added.len()
+    ));
+    // Daily files are rebuilt from archives by norm distillation, so they are never overwritten.
+    report.detail(format!("skipped_daily={}", daily_records.len()));
+    for bullet in &added {
+        report.detail(format!("+ {bullet}"));
+    }
+    if dry_run {
+        report.detail("dry_run=true".to_string());
+        return Ok(report);
+    }
+    if added.is_empty() {
+        return Ok(report);
+    }
+
+    // Synthesis may have rewritten memory.md since the preview; merge against what is there
+    // now, under the same lock synthesis holds.
+    let _lock_file = acquire_memory_lock(&paths)?;
+    let previous = fs::read_to_string(&paths.memory_file).unwrap_or_default();
+    let (merged, added) = merge_memory_records(&previous, &memory_records);
+    if added.is_empty() {
+        return Ok(report);
+    }
+    let now = now_epoch_secs()?;
+    ensure_memory_baseline(&paths, &previous)?;
+    atomic_write_file(&paths.memory_file, &merged)?;
+    record_memory_snapshot(&paths, &merged, now)?;
+    let _ = audit::append_event(
+        &paths,
+        "memory-import",
+        "ok",
+        &format!("source={path} added={}", added.len()),
+        serde_json::json!({"source": path, "added": added.len()}),
+    );
+
+    Ok(report)
+}
+
+pub fn run(opts: &MoonMemoryOptions) -> Result<CommandReport> {
+    match &opts.action {
+        MoonMemoryAction::Diff { since } => run_diff(since),
+        MoonMemoryAction::Inject {
+            session_key,
+            max_tokens,
+            dry_run,
+        } => run_inject(session_key, *max_tokens, *dry_run),
+        MoonMemoryAction::Export { format, output } => run_export(format, output.as_deref()),
+        MoonMemoryAction::Import { path, dry_run } => run_import(path, *dry_run),
+    }
+}
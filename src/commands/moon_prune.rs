@@ -0,0 +1,119 @@
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+
+use crate::commands::CommandReport;
+use crate::error::MoonErrorCode;
+use crate::moon::audit;
+use crate::moon::paths::resolve_paths;
+use crate::moon::prune::apply_aggressive_profile;
+use crate::openclaw::config::{
+    config_backups_dir, list_config_backups, read_config_backup, write_config_atomic,
+};
+use crate::openclaw::paths::resolve_paths as resolve_openclaw_paths;
+
+#[derive(Debug, Clone, Default)]
+pub struct MoonPruneOptions {
+    pub apply: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MoonPruneRestoreOptions {
+    pub from: String,
+}
+
+pub fn run(opts: &MoonPruneOptions) -> Result<CommandReport> {
+    let paths = resolve_paths()?;
+    let oc_paths = resolve_openclaw_paths()?;
+    let mut report = CommandReport::new("prune");
+
+    let outcome = apply_aggressive_profile(&paths, &oc_paths.plugin_id, opts.apply)?;
+    report.detail(format!("config_path={}", outcome.config_path.display()));
+    report.detail(format!("changes={}", outcome.changes.len()));
+    for change in &outcome.changes {
+        report.detail(format!(
+            "change {}: {} -> {}",
+            change.path, change.old, change.new
+        ));
+    }
+    report.detail(format!("diff={}", outcome.diff_json()));
+
+    if outcome.changes.is_empty() {
+        report.detail("unchanged (safety floors already satisfied)".to_string());
+    } else if outcome.applied {
+        report.detail(format!("updated config: {}", outcome.config_path.display()));
+        let _ = audit::append_event(
+            &paths,
+            "prune",
+            "ok",
+            &format!(
+                "applied changes={} config={}",
+                outcome.changes.len(),
+                outcome.config_path.display()
+            ),
+            serde_json::json!({
+                "action": "applied",
+                "changes": outcome.changes.len(),
+                "config": outcome.config_path.display().to_string(),
+            }),
+        );
+    } else if let Some(skipped) = &outcome.skipped {
+        report.detail(format!("config changes planned but not applied: {skipped}"));
+    }
+    Ok(report)
+}
+
+/// A bare file name is looked up in the backups dir first, anything else is taken as a path.
+fn resolve_backup(backups_dir: &Path, from: &str) -> PathBuf {
+    let candidate = Path::new(from);
+    if candidate.components().count() == 1 {
+        let in_dir = backups_dir.join(candidate);
+        if in_dir.exists() {
+            return in_dir;
+        }
+    }
+    candidate.to_path_buf()
+}
+
+pub fn run_restore(opts: &MoonPruneRestoreOptions) -> Result<CommandReport> {
+    let paths = resolve_paths()?;
+    let oc_paths = resolve_openclaw_paths()?;
+    let mut report = CommandReport::new("prune restore");
+
+    let backups_dir = config_backups_dir()?;
+    let backup = resolve_backup(&backups_dir, opts.from.trim());
+    if !backup.is_file() {
+        report.coded_issue(
+            MoonErrorCode::E014PathMissing,
+            format!("backup not found: {}", backup.display()),
+        );
+        for available in list_config_backups(&backups_dir)?.iter().rev() {
+            report.detail(format!("available={}", available.display()));
+        }
+        return Ok(report);
+    }
+
+    let value = match read_config_backup(&backup) {
+        Ok(value) => value,
+        Err(err) => {
+            report.coded_issue(MoonErrorCode::E013InvalidArgument, format!("{err:#}"));
+            return Ok(report);
+        }
+    };
+
+    // The write backs up the config being replaced, so a restore can itself be undone.
+    let written = write_config_atomic(&oc_paths, &value)?;
+    report.detail(format!("restored_from={}", backup.display()));
+    report.detail(format!("updated config: {written}"));
+    let _ = audit::append_event(
+        &paths,
+        "prune",
+        "ok",
+        &format!("restored from={} config={written}", backup.display()),
+        serde_json::json!({
+            "action": "restored",
+            "from": backup.display().to_string(),
+            "config": written,
+        }),
+    );
+    Ok(report)
+}
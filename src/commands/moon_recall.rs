@@ -1,47 +1,622 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::io::{BufRead, Write};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
 
 use crate::commands::CommandReport;
-use crate::moon::paths::resolve_paths;
-use crate::moon::recall;
+use crate::error::MoonErrorCode;
+use crate::moon::config::{MoonCollectionsConfig, load_config, resolve_residential_tz};
+use crate::moon::memory::{daily_memory_file, parse_daily_memory_sections};
+use crate::moon::paths::{MoonPaths, resolve_paths};
+use crate::moon::recall::{self, RecallHydration, RecallScope};
+use crate::moon::util::truncate_with_ellipsis;
+
+const RPC_DEFAULT_MAX_RESULTS: usize = 5;
+const RPC_MAX_RESULTS_CAP: usize = 20;
+const RPC_DEFAULT_MAX_BYTES: usize = 16 * 1024;
+const RPC_MAX_BYTES_CAP: usize = 256 * 1024;
+const RPC_DEFAULT_TIMEOUT_MS: u64 = 8_000;
+const RPC_TIMEOUT_CAP_MS: u64 = 30_000;
+const RPC_SNIPPET_MAX_CHARS: usize = 1_000;
 
 #[derive(Debug, Clone)]
 pub struct MoonRecallOptions {
+    /// May be empty when `day` is set, which then prints only that day's memory.
     pub query: String,
-    pub collection_name: String,
+    /// `YYYY-MM-DD` whose daily memory sections are printed ahead of archive matches.
+    pub day: Option<String>,
+    /// Explicit collection; `None` routes by `channel_key` through `[collections]`.
+    pub collection_name: Option<String>,
     pub channel_key: Option<String>,
+    /// `archives`, `memory` or `all`; see [`RecallScope`].
+    pub scope: String,
+    /// `match[N]` index to hydrate from its raw archive.
+    pub open: Option<usize>,
+    /// Raw entries kept on each side of the opened match.
+    pub context: usize,
+    /// Writes the opened slice as markdown instead of printing it.
+    pub export: Option<PathBuf>,
+    /// Token budget for the printed snippets combined; lower-ranked matches are cut first.
+    pub max_tokens: Option<usize>,
+    /// Comma-separated per-match fields to print; `None` prints [`RecallField::DEFAULT`].
+    pub fields: Option<String>,
+}
+
+/// Per-match output fields selectable with `--fields` (or `fields` in an RPC request).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecallField {
+    ArchivePath,
+    Score,
+    Snippet,
+    Anchor,
+    /// The raw qmd result object; never printed unless asked for.
+    Metadata,
+}
+
+impl RecallField {
+    const DEFAULT: [Self; 4] = [Self::Score, Self::ArchivePath, Self::Anchor, Self::Snippet];
+    const RPC_DEFAULT: [Self; 3] = [Self::ArchivePath, Self::Snippet, Self::Score];
+
+    fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "archive_path" | "archive" => Some(Self::ArchivePath),
+            "score" => Some(Self::Score),
+            "snippet" => Some(Self::Snippet),
+            "anchor" => Some(Self::Anchor),
+            "metadata" => Some(Self::Metadata),
+            _ => None,
+        }
+    }
+}
+
+/// Resolves a field list; `None` keeps `default` and an unknown name is returned as `Err`.
+fn parse_fields<'a>(
+    names: Option<impl IntoIterator<Item = &'a str>>,
+    default: &[RecallField],
+) -> std::result::Result<Vec<RecallField>, String> {
+    let Some(names) = names else {
+        return Ok(default.to_vec());
+    };
+    let mut fields = Vec::new();
+    for name in names.into_iter().filter(|name| !name.trim().is_empty()) {
+        let field = RecallField::parse(name).ok_or_else(|| name.trim().to_string())?;
+        if !fields.contains(&field) {
+            fields.push(field);
+        }
+    }
+    if fields.is_empty() {
+        return Err(String::new());
+    }
+    Ok(fields)
+}
+
+const RECALL_FIELD_NAMES: &str = "archive_path, score, snippet, anchor, metadata";
+
+fn match_anchor(m: &recall::RecallMatch) -> Option<String> {
+    let anchor = recall::projection_anchor_for_snippet(&m.archive_path, &m.snippet)?;
+    let line = anchor.line()?;
+    Some(match anchor.byte_offset() {
+        Some(offset) => format!("L{line}@{offset}"),
+        None => format!("L{line}"),
+    })
+}
+
+fn format_entry_time(epoch: Option<u64>) -> String {
+    let tz = resolve_residential_tz();
+    epoch
+        .and_then(|secs| chrono::DateTime::from_timestamp(secs as i64, 0))
+        .map(|dt| {
+            dt.with_timezone(&tz)
+                .format("%Y-%m-%d %H:%M:%S %Z")
+                .to_string()
+        })
+        .unwrap_or_else(|| "unknown-time".to_string())
+}
+
+fn render_hydration_markdown(query: &str, idx: usize, hydration: &RecallHydration) -> String {
+    let mut out = format!("# Recall match[{idx}]\n\n");
+    out.push_str(&format!("- query: {query}\n"));
+    out.push_str(&format!("- archive: {}\n", hydration.archive_path));
+    out.push_str(&format!(
+        "- anchor_line: {} ({})\n",
+        hydration.anchor_line, hydration.anchor_method
+    ));
+    for entry in &hydration.entries {
+        let marker = if entry.line == hydration.anchor_line {
+            " (match)"
+        } else {
+            ""
+        };
+        out.push_str(&format!(
+            "\n## L{} · {} · {}{marker}\n\n{}\n",
+            entry.line,
+            format_entry_time(entry.timestamp_epoch),
+            entry.role,
+            entry.text
+        ));
+    }
+    out
+}
+
+fn resolve_collection(
+    collections: &MoonCollectionsConfig,
+    explicit: Option<&str>,
+    channel_key: Option<&str>,
+) -> String {
+    explicit
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(ToOwned::to_owned)
+        .unwrap_or_else(|| collections.for_session(channel_key).to_string())
 }
 
 pub fn run(opts: &MoonRecallOptions) -> Result<CommandReport> {
     let paths = resolve_paths()?;
     let mut report = CommandReport::new("recall");
 
-    if opts.query.trim().is_empty() {
+    let day = opts.day.as_deref().map(str::trim).filter(|v| !v.is_empty());
+    if day.is_none() && opts.query.trim().is_empty() {
         report.issue("query cannot be empty");
         return Ok(report);
     }
+    if let Some(day) = day {
+        if NaiveDate::parse_from_str(day, "%Y-%m-%d").is_err() {
+            report.coded_issue(
+                MoonErrorCode::E013InvalidArgument,
+                format!("invalid --day `{day}`: expected YYYY-MM-DD"),
+            );
+            return Ok(report);
+        }
+        push_day_sections(&mut report, &paths, day)?;
+        if opts.query.trim().is_empty() {
+            return Ok(report);
+        }
+    }
+    let Some(scope) = RecallScope::parse(&opts.scope) else {
+        report.coded_issue(
+            MoonErrorCode::E013InvalidArgument,
+            format!(
+                "invalid --scope `{}`: expected archives, memory or all",
+                opts.scope
+            ),
+        );
+        return Ok(report);
+    };
+    let fields = match parse_fields(
+        opts.fields.as_deref().map(|raw| raw.split(',')),
+        &RecallField::DEFAULT,
+    ) {
+        Ok(fields) => fields,
+        Err(name) => {
+            report.coded_issue(
+                MoonErrorCode::E013InvalidArgument,
+                format!(
+                    "invalid --fields `{name}`: expected a comma-separated list of {RECALL_FIELD_NAMES}"
+                ),
+            );
+            return Ok(report);
+        }
+    };
 
+    let cfg = load_config()?;
+    let collection = resolve_collection(
+        &cfg.collections,
+        opts.collection_name.as_deref(),
+        opts.channel_key.as_deref(),
+    );
+    let deadline_ms = cfg.recall.deadline_ms;
     let result = recall::recall(
         &paths,
         &opts.query,
-        &opts.collection_name,
+        &collection,
         opts.channel_key.as_deref(),
+        scope,
+        (deadline_ms > 0).then(|| Duration::from_millis(deadline_ms)),
     )?;
     report.detail(format!("query={}", result.query));
-    report.detail(format!("collection={}", opts.collection_name));
+    report.detail(format!("scope={}", scope.as_str()));
+    report.detail(format!("collection={collection}"));
     if let Some(key) = &opts.channel_key {
         report.detail(format!("channel_key={key}"));
     }
+    if result.degraded {
+        report.detail("degraded=true".to_string());
+        report.warning(format!(
+            "recall search exceeded deadline_ms={deadline_ms}; showing deterministic and cached matches only"
+        ));
+    }
     report.detail(format!("match_count={}", result.matches.len()));
-    for (idx, m) in result.matches.iter().take(5).enumerate() {
-        report.detail(format!("match[{idx}].score={:.4}", m.score));
-        report.detail(format!("match[{idx}].archive={}", m.archive_path));
-        if !m.snippet.is_empty() {
-            report.detail(format!(
-                "match[{idx}].snippet={}",
-                m.snippet.replace('\n', " ")
+    if let Some(idx) = opts.open {
+        let Some(m) = result.matches.get(idx) else {
+            report.issue(format!(
+                "--open {idx} is out of range: recall returned {} match(es)",
+                result.matches.len()
             ));
+            return Ok(report);
+        };
+        let hydration = match recall::hydrate_match(m, opts.context) {
+            Ok(hydration) => hydration,
+            Err(err) => {
+                report.issue(format!("failed to open match[{idx}]: {err:#}"));
+                return Ok(report);
+            }
+        };
+        report.detail(format!("open.match={idx}"));
+        report.detail(format!("open.archive={}", hydration.archive_path));
+        report.detail(format!(
+            "open.anchor_line={} method={}",
+            hydration.anchor_line, hydration.anchor_method
+        ));
+        if let (Some(first), Some(last)) = (hydration.entries.first(), hydration.entries.last()) {
+            report.detail(format!("open.lines={}-{}", first.line, last.line));
+        }
+        if let Some(path) = &opts.export {
+            if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+                fs::create_dir_all(parent)
+                    .with_context(|| format!("failed to create {}", parent.display()))?;
+            }
+            fs::write(
+                path,
+                render_hydration_markdown(&result.query, idx, &hydration),
+            )
+            .with_context(|| format!("failed to write {}", path.display()))?;
+            report.detail(format!("open.export={}", path.display()));
+        } else {
+            for entry in &hydration.entries {
+                report.detail(format!(
+                    "open.line[{}] {} [{}] {}",
+                    entry.line,
+                    format_entry_time(entry.timestamp_epoch),
+                    entry.role,
+                    entry.text.replace('\n', " ")
+                ));
+            }
+        }
+        return Ok(report);
+    }
+    let mut shown: Vec<_> = result.matches.into_iter().take(5).collect();
+    if let Some(max_tokens) = opts.max_tokens {
+        let outcome = recall::fit_snippets_to_token_budget(&mut shown, max_tokens);
+        report.detail(format!(
+            "token_budget max_tokens={max_tokens} used_tokens={} truncated={} dropped={}",
+            outcome.used_tokens,
+            outcome.truncated.len(),
+            outcome.dropped
+        ));
+        for idx in outcome.truncated {
+            report.detail(format!("match[{idx}].truncated=true"));
+        }
+    }
+    let groups = recall::group_matches_by_session(&paths, &shown);
+    report.detail(format!("session_count={}", groups.len()));
+    for (group_idx, group) in groups.iter().enumerate() {
+        report.detail(format!(
+            "session[{group_idx}] id={} channel={} time={} topics={} matches={}",
+            group.session_id,
+            group.channel_key.as_deref().unwrap_or("-"),
+            group.time_range.as_deref().unwrap_or("unknown"),
+            if group.topics.is_empty() {
+                "-".to_string()
+            } else {
+                group.topics.join(", ")
+            },
+            group
+                .match_indexes
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(",")
+        ));
+        for &idx in &group.match_indexes {
+            push_match_details(&mut report, idx, &shown[idx], &fields);
         }
     }
 
     Ok(report)
 }
+
+/// Prints the `##` sections of `memory/<day>.md`, the distilled record of that day.
+fn push_day_sections(report: &mut CommandReport, paths: &MoonPaths, day: &str) -> Result<()> {
+    let path = daily_memory_file(paths, day);
+    report.detail(format!("day={day}"));
+    report.detail(format!("day.file={}", path.display()));
+    if !path.exists() {
+        report.detail("day.section_count=0".to_string());
+        report.warning(format!("no daily memory for {day} ({})", path.display()));
+        return Ok(());
+    }
+    let raw =
+        fs::read_to_string(&path).with_context(|| format!("failed to read {}", path.display()))?;
+    let sections = parse_daily_memory_sections(&raw);
+    report.detail(format!("day.section_count={}", sections.len()));
+    for (idx, section) in sections.iter().enumerate() {
+        report.detail(format!("day.section[{idx}].title={}", section.title));
+        if !section.lines.is_empty() {
+            report.detail(format!(
+                "day.section[{idx}].text={}",
+                section.lines.join(" ")
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn push_match_details(
+    report: &mut CommandReport,
+    idx: usize,
+    m: &recall::RecallMatch,
+    fields: &[RecallField],
+) {
+    for field in fields {
+        match field {
+            RecallField::Score => report.detail(format!("match[{idx}].score={:.4}", m.score)),
+            RecallField::ArchivePath => {
+                report.detail(format!("match[{idx}].archive={}", m.archive_path))
+            }
+            RecallField::Anchor => {
+                if let Some(anchor) = match_anchor(m) {
+                    report.detail(format!("match[{idx}].anchor={anchor}"));
+                }
+            }
+            RecallField::Snippet => {
+                if !m.snippet.is_empty() {
+                    report.detail(format!(
+                        "match[{idx}].snippet={}",
+                        m.snippet.replace('\n', " ")
+                    ));
+                }
+            }
+            RecallField::Metadata => {
+                report.detail(format!("match[{idx}].metadata={}", m.metadata));
+            }
+        }
+    }
+}
+
+/// One line of `recall --rpc` input.
+#[derive(Debug, Deserialize)]
+struct RecallRpcRequest {
+    #[serde(default)]
+    id: Value,
+    query: String,
+    #[serde(default)]
+    collection: Option<String>,
+    #[serde(default)]
+    channel_key: Option<String>,
+    #[serde(default)]
+    scope: Option<String>,
+    #[serde(default)]
+    fields: Option<Vec<String>>,
+    #[serde(default)]
+    max_results: Option<usize>,
+    #[serde(default)]
+    max_bytes: Option<usize>,
+    #[serde(default)]
+    timeout_ms: Option<u64>,
+    #[serde(default)]
+    max_tokens: Option<usize>,
+}
+
+/// A match trimmed to the request's `fields`; unselected fields are omitted from the JSON.
+#[derive(Debug, Serialize)]
+struct RecallRpcMatch {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    archive_path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    snippet: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    score: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    anchor: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    metadata: Option<Value>,
+}
+
+impl RecallRpcMatch {
+    fn project(m: &recall::RecallMatch, fields: &[RecallField]) -> Self {
+        let wants = |field| fields.contains(&field);
+        Self {
+            archive_path: wants(RecallField::ArchivePath).then(|| m.archive_path.clone()),
+            snippet: wants(RecallField::Snippet).then(|| {
+                truncate_with_ellipsis(&m.snippet.replace('\n', " "), RPC_SNIPPET_MAX_CHARS)
+            }),
+            score: wants(RecallField::Score).then_some(m.score),
+            anchor: wants(RecallField::Anchor)
+                .then(|| match_anchor(m))
+                .flatten(),
+            metadata: wants(RecallField::Metadata).then(|| m.metadata.clone()),
+        }
+    }
+}
+
+/// One line of `recall --rpc` output; `matches` never exceeds the request's byte budget.
+#[derive(Debug, Serialize)]
+struct RecallRpcResponse {
+    id: Value,
+    ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    matches: Vec<RecallRpcMatch>,
+    /// Sessions the returned matches came from; `matches` holds indexes into `matches`.
+    sessions: Vec<RecallRpcSession>,
+    truncated: bool,
+    /// The search missed `timeout_ms`; `matches` holds only deterministic and cached hits.
+    degraded: bool,
+    elapsed_ms: u64,
+}
+
+#[derive(Debug, Serialize)]
+struct RecallRpcSession {
+    session_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    channel_key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    time_range: Option<String>,
+    topics: Vec<String>,
+    matches: Vec<usize>,
+}
+
+impl RecallRpcResponse {
+    fn failed(id: Value, error: String, started: Instant) -> Self {
+        Self {
+            id,
+            ok: false,
+            error: Some(error),
+            matches: Vec::new(),
+            sessions: Vec::new(),
+            truncated: false,
+            degraded: false,
+            elapsed_ms: started.elapsed().as_millis() as u64,
+        }
+    }
+}
+
+fn answer_rpc_request(
+    paths: &MoonPaths,
+    collections: &MoonCollectionsConfig,
+    collection_override: Option<&str>,
+    line: &str,
+) -> RecallRpcResponse {
+    let started = Instant::now();
+    let request: RecallRpcRequest = match serde_json::from_str(line) {
+        Ok(request) => request,
+        Err(err) => {
+            return RecallRpcResponse::failed(
+                Value::Null,
+                format!("invalid request: {err}"),
+                started,
+            );
+        }
+    };
+    if request.query.trim().is_empty() {
+        return RecallRpcResponse::failed(request.id, "query cannot be empty".to_string(), started);
+    }
+    let Some(scope) = RecallScope::parse(request.scope.as_deref().unwrap_or_default()) else {
+        return RecallRpcResponse::failed(
+            request.id,
+            "invalid scope: expected archives, memory or all".to_string(),
+            started,
+        );
+    };
+    let fields = match parse_fields(
+        request
+            .fields
+            .as_ref()
+            .map(|names| names.iter().map(String::as_str)),
+        &RecallField::RPC_DEFAULT,
+    ) {
+        Ok(fields) => fields,
+        Err(name) => {
+            return RecallRpcResponse::failed(
+                request.id,
+                format!("invalid fields `{name}`: expected {RECALL_FIELD_NAMES}"),
+                started,
+            );
+        }
+    };
+
+    let max_results = request
+        .max_results
+        .unwrap_or(RPC_DEFAULT_MAX_RESULTS)
+        .clamp(1, RPC_MAX_RESULTS_CAP);
+    let max_bytes = request
+        .max_bytes
+        .unwrap_or(RPC_DEFAULT_MAX_BYTES)
+        .clamp(1, RPC_MAX_BYTES_CAP);
+    let timeout = Duration::from_millis(
+        request
+            .timeout_ms
+            .unwrap_or(RPC_DEFAULT_TIMEOUT_MS)
+            .clamp(1, RPC_TIMEOUT_CAP_MS),
+    );
+    let collection = resolve_collection(
+        collections,
+        request
+            .collection
+            .as_deref()
+            .filter(|name| !name.trim().is_empty())
+            .or(collection_override),
+        request.channel_key.as_deref(),
+    );
+
+    // A slow qmd call degrades the response to deterministic and cached matches instead of
+    // holding the caller past its deadline.
+    let result = match recall::recall(
+        paths,
+        &request.query,
+        &collection,
+        request.channel_key.as_deref(),
+        scope,
+        Some(timeout),
+    ) {
+        Ok(result) => result,
+        Err(err) => {
+            return RecallRpcResponse::failed(request.id, format!("{err:#}"), started);
+        }
+    };
+
+    let mut matches = Vec::new();
+    let mut used_bytes = 0usize;
+    let mut truncated = result.matches.len() > max_results;
+    let mut ranked: Vec<_> = result.matches.into_iter().take(max_results).collect();
+    if let Some(max_tokens) = request.max_tokens {
+        let outcome = recall::fit_snippets_to_token_budget(&mut ranked, max_tokens);
+        truncated |= outcome.dropped > 0 || !outcome.truncated.is_empty();
+    }
+    let mut kept = Vec::new();
+    for m in ranked {
+        let item = RecallRpcMatch::project(&m, &fields);
+        let item_bytes = serde_json::to_string(&item).map(|v| v.len()).unwrap_or(0);
+        if used_bytes + item_bytes > max_bytes {
+            truncated = true;
+            break;
+        }
+        used_bytes += item_bytes;
+        matches.push(item);
+        kept.push(m);
+    }
+    let sessions = recall::group_matches_by_session(paths, &kept)
+        .into_iter()
+        .map(|group| RecallRpcSession {
+            session_id: group.session_id,
+            channel_key: group.channel_key,
+            time_range: group.time_range,
+            topics: group.topics,
+            matches: group.match_indexes,
+        })
+        .collect();
+
+    RecallRpcResponse {
+        id: request.id,
+        ok: true,
+        error: None,
+        matches,
+        sessions,
+        truncated,
+        degraded: result.degraded,
+        elapsed_ms: started.elapsed().as_millis() as u64,
+    }
+}
+
+/// Serves newline-delimited JSON recall requests from stdin until EOF, one response line each.
+pub fn run_rpc(collection_override: Option<&str>) -> Result<()> {
+    let paths = resolve_paths()?;
+    let collections = load_config()?.collections;
+    let stdin = std::io::stdin();
+    let mut stdout = std::io::stdout().lock();
+    for line in stdin.lock().lines() {
+        let line = line.context("failed to read recall rpc request")?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = answer_rpc_request(&paths, &collections, collection_override, &line);
+        writeln!(stdout, "{}", serde_json::to_string(&response)?)
+            .context("failed to write recall rpc response")?;
+        stdout.flush()?;
+    }
+    Ok(())
+}
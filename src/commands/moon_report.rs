@@ -0,0 +1,77 @@
+use anyhow::Result;
+use chrono::Utc;
+
+use crate::commands::{CommandReport, ensure_openclaw_available};
+use crate::moon::audit;
+use crate::moon::config::{load_config, resolve_residential_tz};
+use crate::moon::paths::resolve_paths;
+use crate::moon::report::{build_daily_report, daily_report_event_text, write_daily_report};
+use crate::openclaw::gateway;
+
+#[derive(Debug, Clone)]
+pub struct MoonReportDailyOptions {
+    pub day: Option<String>,
+    pub notify: bool,
+}
+
+pub fn run_daily(opts: &MoonReportDailyOptions) -> Result<CommandReport> {
+    let paths = resolve_paths()?;
+    let mut report = CommandReport::new("report daily");
+
+    let tz = resolve_residential_tz();
+    let day_key = match opts.day.as_deref().map(str::trim).filter(|v| !v.is_empty()) {
+        Some(day) => day.to_string(),
+        None => Utc::now().with_timezone(&tz).format("%Y-%m-%d").to_string(),
+    };
+    let cost_per_million_tokens = load_config()
+        .map(|cfg| cfg.distill.cost_per_million_tokens)
+        .unwrap_or(0.0);
+    let digest = match build_daily_report(&paths, &day_key, tz, cost_per_million_tokens) {
+        Ok(digest) => digest,
+        Err(err) => {
+            report.issue(format!("{err:#}"));
+            return Ok(report);
+        }
+    };
+    let path = write_daily_report(&paths, &digest)?;
+
+    report.detail(format!("day={day_key}"));
+    report.detail(format!("timezone={}", digest.timezone));
+    report.detail(format!("report_path={}", path.display()));
+    report.detail(format!("archived={}", digest.archived.len()));
+    report.detail(format!("compactions={}", digest.compactions.len()));
+    report.detail(format!("decisions={}", digest.decisions.len()));
+    report.detail(format!("open_tasks={}", digest.open_tasks.len()));
+    report.detail(format!(
+        "distill_runs={}",
+        digest.distill_runs.values().sum::<usize>()
+    ));
+    report.detail(format!("distill_tokens={}", digest.distill_tokens));
+    if let Some(cost) = digest.distill_cost {
+        report.detail(format!("distill_cost_estimate={cost:.4}"));
+    }
+    report.detail(format!("warnings={}", digest.warnings.len()));
+
+    if opts.notify {
+        if !ensure_openclaw_available(&mut report) {
+            return Ok(report);
+        }
+        match gateway::run_system_event(&daily_report_event_text(&digest, &path), "now") {
+            Ok(()) => report.detail("notify=sent".to_string()),
+            Err(err) => report.issue(format!("daily report notification failed: {err:#}")),
+        }
+    }
+    let _ = audit::append_event(
+        &paths,
+        "report",
+        "ok",
+        &format!("daily day={day_key} path={}", path.display()),
+        serde_json::json!({
+            "kind": "daily",
+            "day": day_key,
+            "path": path.display().to_string(),
+        }),
+    );
+
+    Ok(report)
+}
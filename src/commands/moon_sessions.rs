@@ -0,0 +1,93 @@
+use anyhow::Result;
+use chrono::{TimeZone, Utc};
+use std::path::Path;
+
+use crate::commands::{CommandReport, ensure_openclaw_available};
+use crate::moon::archive::{ArchiveRecord, portable_path_string, read_ledger_records};
+use crate::moon::config::load_config;
+use crate::moon::paths::resolve_paths;
+use crate::moon::session_usage::collect_openclaw_usage_batch;
+use crate::moon::state;
+use crate::moon::watcher::{effective_compaction_start_ratio, load_session_source_map};
+
+fn format_epoch(epoch_secs: u64) -> String {
+    Utc.timestamp_opt(epoch_secs as i64, 0)
+        .single()
+        .map(|dt| dt.to_rfc3339())
+        .unwrap_or_else(|| epoch_secs.to_string())
+}
+
+/// Newest ledger record archived from `source`.
+fn latest_archive_for_source<'a>(
+    records: &'a [ArchiveRecord],
+    source: &Path,
+) -> Option<&'a ArchiveRecord> {
+    let source = portable_path_string(source);
+    records
+        .iter()
+        .filter(|record| record.source_path == source)
+        .max_by_key(|record| record.created_at_epoch_secs)
+}
+
+pub fn run() -> Result<CommandReport> {
+    let paths = resolve_paths()?;
+    let cfg = load_config()?;
+    let mut report = CommandReport::new("sessions");
+
+    if !ensure_openclaw_available(&mut report) {
+        return Ok(report);
+    }
+    let batch = match collect_openclaw_usage_batch() {
+        Ok(batch) => batch,
+        Err(err) => {
+            report.issue(format!("failed to collect openclaw session usage: {err:#}"));
+            return Ok(report);
+        }
+    };
+    let source_map = load_session_source_map(&paths.openclaw_sessions_dir).unwrap_or_default();
+    let records = read_ledger_records(&paths)?;
+    let moon_state = state::load(&paths)?;
+    let start_ratio = effective_compaction_start_ratio(&cfg, cfg.context.as_ref());
+
+    report.detail(format!("sessions={}", batch.sessions.len()));
+    report.detail(format!("current={}", batch.current.session_id));
+    report.detail(format!("compaction_start_ratio={start_ratio:.4}"));
+
+    for (idx, session) in batch.sessions.iter().enumerate() {
+        let channel = if cfg.sessions.allows_compaction(&session.session_id) {
+            "compaction-eligible"
+        } else {
+            "not-eligible"
+        };
+        let source = source_map.get(&session.session_id);
+        let latest = source.and_then(|path| latest_archive_for_source(&records, path));
+        let last_archive = latest
+            .map(|record| format_epoch(record.created_at_epoch_secs))
+            .unwrap_or_else(|| "none".to_string());
+        let distilled = source.is_some_and(|path| {
+            let source = portable_path_string(path);
+            records.iter().any(|record| {
+                record.source_path == source
+                    && moon_state
+                        .distilled_archives
+                        .contains_key(&record.archive_path)
+            })
+        });
+        report.detail(format!(
+            "session[{idx}] key={} ratio={:.4} used={} max={} channel={} over_threshold={} last_archive={} distilled={} source={}",
+            session.session_id,
+            session.usage_ratio,
+            session.used_tokens,
+            session.max_tokens,
+            channel,
+            session.usage_ratio >= start_ratio,
+            last_archive,
+            distilled,
+            source
+                .map(|path| path.display().to_string())
+                .unwrap_or_else(|| "unmapped".to_string())
+        ));
+    }
+
+    Ok(report)
+}
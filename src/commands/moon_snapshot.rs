@@ -1,9 +1,15 @@
 use anyhow::Result;
 use std::path::PathBuf;
 
-use crate::commands::CommandReport;
+use crate::commands::{CommandReport, report_archive_plan};
+use crate::moon::archive::plan_archive_and_index;
+use crate::moon::config::load_config;
 use crate::moon::paths::resolve_paths;
-use crate::moon::snapshot::{latest_session_file, write_snapshot};
+use crate::moon::privacy;
+use crate::moon::snapshot::{
+    is_snapshot_excluded, latest_session_file, raw_archive_dir, write_snapshot,
+};
+use crate::moon::watcher::{collection_for_source, load_session_source_map};
 
 #[derive(Debug, Clone, Default)]
 pub struct MoonSnapshotOptions {
@@ -13,12 +19,24 @@ pub struct MoonSnapshotOptions {
 
 pub fn run(opts: &MoonSnapshotOptions) -> Result<CommandReport> {
     let paths = resolve_paths()?;
+    let cfg = load_config()?;
     let mut report = CommandReport::new("snapshot");
 
     let source = match &opts.source {
-        Some(path) => path.clone(),
+        Some(path) => {
+            if is_snapshot_excluded(path, &cfg.snapshot.exclude) {
+                report.issue(format!(
+                    "source {} matches snapshot.exclude; remove the pattern or MOON_SNAPSHOT_EXCLUDE entry to archive it",
+                    path.display()
+                ));
+                return Ok(report);
+            }
+            path.clone()
+        }
         None => {
-            let Some(path) = latest_session_file(&paths.openclaw_sessions_dir)? else {
+            let Some(path) =
+                latest_session_file(&paths.openclaw_sessions_dir, &cfg.snapshot.exclude)?
+            else {
                 report.issue("no source session file found in openclaw sessions dir");
                 return Ok(report);
             };
@@ -26,21 +44,50 @@ pub fn run(opts: &MoonSnapshotOptions) -> Result<CommandReport> {
         }
     };
 
+    let source_map = load_session_source_map(&paths.openclaw_sessions_dir).unwrap_or_default();
+    if let Some((session_key, _)) = source_map.iter().find(|(_, path)| **path == source)
+        && !cfg.sessions.allows_snapshot(session_key)
+    {
+        report.issue(format!(
+            "session {session_key} is excluded by [sessions] snapshot; adjust its include/exclude patterns to snapshot it"
+        ));
+        return Ok(report);
+    }
+
     report.detail(format!("source={}", source.display()));
     report.detail(format!("archives_dir={}", paths.archives_dir.display()));
 
+    let collection = collection_for_source(&paths, &cfg.collections, &source);
     if opts.dry_run {
         report.detail("dry-run: snapshot planned but not written".to_string());
+        let plan = plan_archive_and_index(&paths, &source, collection)?;
+        report_archive_plan(&mut report, "plan", &plan);
         return Ok(report);
     }
 
-    let outcome = write_snapshot(&paths.archives_dir, &source)?;
+    let private_pattern = privacy::private_pattern_for_source(&paths, &cfg.privacy, &source)?;
+    let outcome = write_snapshot(
+        &raw_archive_dir(&paths.archives_dir, cfg.collections.subdir(collection)),
+        &source,
+    )?;
     report.detail(format!(
         "source_confirmed={}",
         outcome.source_path.display()
     ));
     report.detail(format!("archive={}", outcome.archive_path.display()));
     report.detail(format!("bytes={}", outcome.bytes));
+    if let Some(pattern) = private_pattern {
+        report.detail(format!("private_channel={pattern}"));
+        if cfg.privacy.encrypt {
+            if let Err(err) = privacy::encrypt_file_in_place(&outcome.archive_path) {
+                // Same rule as the archive pipeline: no unsealed copy of a private channel.
+                let _ = std::fs::remove_file(&outcome.archive_path);
+                report.issue(format!("private snapshot removed: {err:#}"));
+                return Ok(report);
+            }
+            report.detail("encrypted=true".to_string());
+        }
+    }
 
     Ok(report)
 }
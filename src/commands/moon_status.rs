@@ -1,9 +1,20 @@
 use anyhow::Result;
 
 use crate::commands::CommandReport;
-use crate::moon::config::{SECRET_ENV_KEYS, masked_env_secret};
+use crate::error::MoonErrorCode;
+use crate::moon::audit;
+use crate::moon::budget;
+use crate::moon::config::{
+    SECRET_ENV_KEYS, load_config, masked_env_secret, resolve_residential_tz,
+};
 use crate::moon::paths::resolve_paths;
-use crate::moon::state::state_file_path;
+use crate::moon::pause::read_pause;
+use crate::moon::report::DistillFallbackStats;
+use crate::moon::state::{self, state_file_path};
+use crate::moon::util::{now_epoch_secs, read_only_mode};
+use crate::moon::watcher::intentional_distill_skips;
+
+const DISTILL_FALLBACK_WINDOW_DAYS: u64 = 7;
 
 pub fn run() -> Result<CommandReport> {
     let paths = resolve_paths()?;
@@ -21,42 +32,143 @@ pub fn run() -> Result<CommandReport> {
     ));
     report.detail(format!("qmd_bin={}", paths.qmd_bin.display()));
     report.detail(format!("qmd_db={}", paths.qmd_db.display()));
+    report.detail(format!("read_only={}", read_only_mode()));
+    match read_pause(&paths) {
+        Some(pause) => report.detail(format!("watch.paused=true {}", pause.detail())),
+        None => report.detail("watch.paused=false".to_string()),
+    }
     for key in SECRET_ENV_KEYS {
         report.detail(format!("secret.{key}={}", masked_env_secret(key)));
     }
 
+    let budget_limit = load_config()
+        .map(|cfg| cfg.distill.daily_token_budget)
+        .unwrap_or(0);
+    let budget_day_key = budget::day_key_for_epoch(now_epoch_secs()?, resolve_residential_tz());
+    match budget::load_for_day(&paths, &budget_day_key) {
+        Ok(spent) => {
+            report.detail(format!("distill_budget.day={}", spent.day_key));
+            report.detail(format!("distill_budget.tokens_used={}", spent.tokens_used));
+            report.detail(format!("distill_budget.limit={budget_limit}"));
+            report.detail(format!(
+                "distill_budget.exhausted={}",
+                spent.is_exhausted(budget_limit)
+            ));
+        }
+        Err(err) => report.issue(format!("failed to read distill budget: {err:#}")),
+    }
+
+    let window_start = now_epoch_secs()?.saturating_sub(DISTILL_FALLBACK_WINDOW_DAYS * 86_400);
+    match audit::read_events(&paths) {
+        Ok(events) => {
+            let mut fallbacks = DistillFallbackStats::default();
+            for event in events.iter().filter(|e| e.at_epoch_secs >= window_start) {
+                fallbacks.record(event);
+            }
+            report.detail(format!(
+                "distill_fallback.window_days={DISTILL_FALLBACK_WINDOW_DAYS}"
+            ));
+            report.detail(format!("distill_fallback.runs={}", fallbacks.runs));
+            report.detail(format!(
+                "distill_fallback.fallbacks={}",
+                fallbacks.fallbacks()
+            ));
+            report.detail(format!("distill_fallback.rate={:.2}", fallbacks.rate()));
+            if fallbacks.fallbacks() > 0 {
+                report.detail(format!(
+                    "distill_fallback.classes={}",
+                    fallbacks.class_summary()
+                ));
+                report.warning(format!(
+                    "distill provider fell back in {} of {} run(s) over {DISTILL_FALLBACK_WINDOW_DAYS} days ({}); check provider API keys and quota",
+                    fallbacks.fallbacks(),
+                    fallbacks.runs,
+                    fallbacks.class_summary()
+                ));
+            }
+        }
+        Err(err) => report.issue(format!("failed to read audit log: {err:#}")),
+    }
+
+    match state::load(&paths) {
+        Ok(state) => {
+            let sessions = load_config().map(|cfg| cfg.sessions).unwrap_or_default();
+            match intentional_distill_skips(&paths, &state, &sessions) {
+                Ok(skips) => {
+                    report.detail(format!("distill_skipped.archives={}", skips.total()));
+                    report.detail(format!("distill_skipped.flagged={}", skips.flagged));
+                    report.detail(format!(
+                        "distill_skipped.session_filter={}",
+                        skips.session_filter
+                    ));
+                }
+                Err(err) => report.issue(format!("failed to read archive ledger: {err:#}")),
+            }
+            report.detail(format!("channels={}", state.channels.len()));
+            let epoch = |value: Option<u64>| value.map_or("-".to_string(), |v| v.to_string());
+            for (key, channel) in &state.channels {
+                report.detail(format!(
+                    "channel.{key} last_archive={} last_compaction={} last_distill={} failures=archive:{},compaction:{},distill:{}",
+                    epoch(channel.last_archive_epoch_secs),
+                    epoch(channel.last_compaction_epoch_secs),
+                    epoch(channel.last_distill_epoch_secs),
+                    channel.archive_failures,
+                    channel.compaction_failures,
+                    channel.distill_failures
+                ));
+            }
+        }
+        Err(err) => {
+            report.coded_issue(
+                MoonErrorCode::E007StateCorrupt,
+                format!("failed to read state: {err:#}"),
+            );
+        }
+    }
+
     if !paths.archives_dir.exists() {
-        report.issue(format!(
-            "missing archives dir ({})",
-            paths.archives_dir.display()
-        ));
+        report.coded_issue(
+            MoonErrorCode::E014PathMissing,
+            format!("missing archives dir ({})", paths.archives_dir.display()),
+        );
     }
     if !paths.memory_dir.exists() {
-        report.issue(format!(
-            "missing daily memory dir ({})",
-            paths.memory_dir.display()
-        ));
+        report.coded_issue(
+            MoonErrorCode::E014PathMissing,
+            format!("missing daily memory dir ({})", paths.memory_dir.display()),
+        );
     }
     if !paths.logs_dir.exists() {
-        report.issue(format!(
-            "missing moon log dir ({})",
-            paths.logs_dir.display()
-        ));
+        report.coded_issue(
+            MoonErrorCode::E014PathMissing,
+            format!("missing moon log dir ({})", paths.logs_dir.display()),
+        );
     }
     if !paths.memory_file.exists() {
-        report.issue(format!(
-            "missing long-term memory file ({})",
-            paths.memory_file.display()
-        ));
+        report.coded_issue(
+            MoonErrorCode::E014PathMissing,
+            format!(
+                "missing long-term memory file ({})",
+                paths.memory_file.display()
+            ),
+        );
     }
     if !paths.openclaw_sessions_dir.exists() {
-        report.issue(format!(
-            "missing OpenClaw sessions dir ({})",
-            paths.openclaw_sessions_dir.display()
-        ));
+        report
+            .coded_issue(
+                MoonErrorCode::E014PathMissing,
+                format!(
+                    "missing OpenClaw sessions dir ({})",
+                    paths.openclaw_sessions_dir.display()
+                ),
+            )
+            .with_hint("set OPENCLAW_SESSIONS_DIR to the OpenClaw sessions directory");
     }
     if !paths.qmd_bin.exists() {
-        report.issue(format!("missing qmd binary ({})", paths.qmd_bin.display()));
+        report.coded_issue(
+            MoonErrorCode::E009QmdUnavailable,
+            format!("missing qmd binary ({})", paths.qmd_bin.display()),
+        );
     }
 
     Ok(report)
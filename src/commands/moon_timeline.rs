@@ -0,0 +1,144 @@
+use anyhow::{Context, Result};
+use chrono::Utc;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::commands::CommandReport;
+use crate::error::MoonErrorCode;
+use crate::moon::config::resolve_residential_tz;
+use crate::moon::paths::resolve_paths;
+use crate::moon::timeline::{TimelineFilter, build_timeline, render_timeline_markdown};
+
+#[derive(Debug, Clone)]
+pub struct MoonTimelineOptions {
+    pub day: Option<String>,
+    pub channel: Option<String>,
+    pub format: String,
+    pub output: Option<String>,
+    pub limit: usize,
+}
+
+fn file_label(filter: &TimelineFilter) -> String {
+    let channel = filter.channel_key.as_deref().map(|key| {
+        key.chars()
+            .map(|ch| if ch.is_ascii_alphanumeric() { ch } else { '-' })
+            .collect::<String>()
+    });
+    match (filter.day_key.as_deref(), channel) {
+        (Some(day), Some(channel)) => format!("{day}-{channel}"),
+        (Some(day), None) => day.to_string(),
+        (None, Some(channel)) => channel,
+        (None, None) => "all".to_string(),
+    }
+}
+
+pub fn run(opts: &MoonTimelineOptions) -> Result<CommandReport> {
+    let paths = resolve_paths()?;
+    let mut report = CommandReport::new("timeline");
+
+    let format = opts.format.trim().to_ascii_lowercase();
+    let extension = match format.as_str() {
+        "markdown" | "md" => "md",
+        "json" => "json",
+        other => {
+            report.coded_issue(
+                MoonErrorCode::E013InvalidArgument,
+                format!("invalid --format `{other}`; use markdown or json"),
+            );
+            return Ok(report);
+        }
+    };
+
+    let tz = resolve_residential_tz();
+    let non_empty = |value: &Option<String>| {
+        value
+            .as_deref()
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(ToOwned::to_owned)
+    };
+    let channel_key = non_empty(&opts.channel);
+    // Without a channel the timeline covers one day, today unless `--day` says otherwise.
+    let day_key = non_empty(&opts.day).or_else(|| {
+        channel_key
+            .is_none()
+            .then(|| Utc::now().with_timezone(&tz).format("%Y-%m-%d").to_string())
+    });
+    let filter = TimelineFilter {
+        day_key,
+        channel_key,
+    };
+    let timeline = match build_timeline(&paths, &filter, tz) {
+        Ok(timeline) => timeline,
+        Err(err) => {
+            report.coded_issue(MoonErrorCode::E013InvalidArgument, format!("{err:#}"));
+            return Ok(report);
+        }
+    };
+
+    let rendered = if extension == "json" {
+        let mut out = serde_json::to_string_pretty(&timeline)?;
+        out.push('\n');
+        out
+    } else {
+        render_timeline_markdown(&timeline)
+    };
+    let output_path = match opts
+        .output
+        .as_deref()
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+    {
+        Some(path) => PathBuf::from(path),
+        None => paths
+            .moon_home
+            .join("moon")
+            .join("exports")
+            .join(format!("timeline-{}.{extension}", file_label(&filter))),
+    };
+    if let Some(parent) = output_path.parent()
+        && !parent.as_os_str().is_empty()
+    {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+    fs::write(&output_path, rendered)
+        .with_context(|| format!("failed to write {}", output_path.display()))?;
+
+    if let Some(day) = &filter.day_key {
+        report.detail(format!("day={day}"));
+    }
+    if let Some(channel) = &filter.channel_key {
+        report.detail(format!("channel={channel}"));
+    }
+    report.detail(format!("timezone={}", timeline.timezone));
+    report.detail(format!("format={extension}"));
+    report.detail(format!("output={}", output_path.display()));
+    report.detail(format!("archives={}", timeline.archives));
+    report.detail(format!("sessions={}", timeline.sessions));
+    report.detail(format!("entries={}", timeline.entries.len()));
+    report.detail(format!(
+        "skipped_archives={}",
+        timeline.skipped_archives.len()
+    ));
+    for archive in &timeline.skipped_archives {
+        report.warning(format!("skipped unreadable archive {archive}"));
+    }
+    for (idx, entry) in timeline.entries.iter().take(opts.limit).enumerate() {
+        report.detail(format!(
+            "entry[{idx}] {} channel={} session={} role={} {}",
+            entry.time_local,
+            entry.channel_key.as_deref().unwrap_or("-"),
+            entry.session_id,
+            entry.role,
+            entry.text
+        ));
+    }
+    if timeline.entries.len() > opts.limit {
+        report.detail(format!(
+            "entries_shown={} (see output for the rest)",
+            opts.limit
+        ));
+    }
+    Ok(report)
+}
@@ -0,0 +1,219 @@
+use anyhow::Result;
+
+use crate::commands::{CommandReport, ensure_openclaw_available};
+use crate::moon::config::{MoonConfig, MoonContextCompactionAuthority, load_config};
+use crate::moon::paths::resolve_paths;
+use crate::moon::session_usage::{SessionUsageSnapshot, collect_openclaw_usage_batch};
+use crate::moon::state::{self, MoonState, UsageSample};
+use crate::moon::thresholds::{
+    compaction_cooldown_epoch_secs, evaluate_context_compaction_candidate,
+    predicts_threshold_crossing,
+};
+use crate::moon::watcher::{effective_compaction_start_ratio, is_cooldown_ready};
+
+const SPARK_LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// One character per sample on an absolute 0..=1 ratio scale, so sessions compare at a glance.
+fn sparkline(samples: &[UsageSample]) -> String {
+    samples
+        .iter()
+        .map(|sample| {
+            let level = (sample.ratio.clamp(0.0, 1.0) * (SPARK_LEVELS.len() - 1) as f64).round();
+            SPARK_LEVELS[level as usize]
+        })
+        .collect()
+}
+
+/// What the watcher's next cycle would do for `session`, mirroring its trigger selection:
+/// `compaction`, `cooldown` (over the start ratio but held back), `predictive-archive`, or `none`.
+fn next_cycle_trigger(
+    cfg: &MoonConfig,
+    state: &MoonState,
+    session: &SessionUsageSnapshot,
+    now_epoch_secs: u64,
+) -> &'static str {
+    if !cfg.sessions.allows_compaction(&session.session_id) {
+        return "none";
+    }
+    let policy = cfg.context.as_ref();
+    let start_ratio = effective_compaction_start_ratio(cfg, policy);
+    let cooldown_ready = is_cooldown_ready(
+        compaction_cooldown_epoch_secs(cfg, state),
+        now_epoch_secs,
+        cfg.watcher.cooldown_secs,
+    );
+    match policy {
+        Some(policy)
+            if matches!(
+                policy.compaction_authority,
+                MoonContextCompactionAuthority::Openclaw
+            ) => {}
+        Some(policy) => {
+            let decision = evaluate_context_compaction_candidate(
+                session.usage_ratio,
+                policy.compaction_start_ratio,
+                policy.compaction_emergency_ratio,
+                cooldown_ready,
+            );
+            if decision.should_compact {
+                return "compaction";
+            }
+            if session.usage_ratio >= start_ratio {
+                return "cooldown";
+            }
+        }
+        None if session.usage_ratio >= start_ratio => {
+            return if cooldown_ready {
+                "compaction"
+            } else {
+                "cooldown"
+            };
+        }
+        None => {}
+    }
+    let Some(trend) = state.usage_trends.get(&session.session_id) else {
+        return "none";
+    };
+    if cfg.watcher.predictive_trigger
+        && is_cooldown_ready(
+            trend.last_predictive_archive_epoch_secs,
+            now_epoch_secs,
+            cfg.watcher.cooldown_secs,
+        )
+        && predicts_threshold_crossing(&trend.samples, start_ratio, cfg.watcher.poll_interval_secs)
+    {
+        return "predictive-archive";
+    }
+    "none"
+}
+
+pub fn run() -> Result<CommandReport> {
+    let paths = resolve_paths()?;
+    let cfg = load_config()?;
+    let mut report = CommandReport::new("usage");
+
+    if !ensure_openclaw_available(&mut report) {
+        return Ok(report);
+    }
+    let batch = match collect_openclaw_usage_batch() {
+        Ok(batch) => batch,
+        Err(err) => {
+            report.issue(format!("failed to collect openclaw session usage: {err:#}"));
+            return Ok(report);
+        }
+    };
+    let moon_state = state::load(&paths)?;
+    let start_ratio = effective_compaction_start_ratio(&cfg, cfg.context.as_ref());
+
+    let schema = batch.schema;
+    let mut sessions = batch.sessions;
+    sessions.sort_by(|a, b| {
+        b.usage_ratio
+            .total_cmp(&a.usage_ratio)
+            .then_with(|| a.session_id.cmp(&b.session_id))
+    });
+
+    report.detail(format!("schema={}", schema.as_str()));
+    report.detail(format!("sessions={}", sessions.len()));
+    report.detail(format!("compaction_start_ratio={start_ratio:.4}"));
+    report.detail(format!(
+        "predictive_trigger={}",
+        cfg.watcher.predictive_trigger
+    ));
+    let mut triggering = 0usize;
+    for (idx, session) in sessions.iter().enumerate() {
+        let samples = moon_state
+            .usage_trends
+            .get(&session.session_id)
+            .map(|trend| trend.samples.as_slice())
+            .unwrap_or_default();
+        let trend = if samples.is_empty() {
+            "none".to_string()
+        } else {
+            sparkline(samples)
+        };
+        let next_cycle =
+            next_cycle_trigger(&cfg, &moon_state, session, session.captured_at_epoch_secs);
+        if matches!(next_cycle, "compaction" | "predictive-archive") {
+            triggering += 1;
+        }
+        report.detail(format!(
+            "session[{idx}] key={} ratio={:.4} used={} max={} trend={trend} samples={} next_cycle={next_cycle}",
+            session.session_id,
+            session.usage_ratio,
+            session.used_tokens,
+            session.max_tokens,
+            samples.len()
+        ));
+    }
+    report.detail(format!("next_cycle.triggering={triggering}"));
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{next_cycle_trigger, sparkline};
+    use crate::moon::config::MoonConfig;
+    use crate::moon::session_usage::SessionUsageSnapshot;
+    use crate::moon::state::{MoonState, UsageSample, record_usage_sample};
+
+    fn snapshot(session_id: &str, usage_ratio: f64) -> SessionUsageSnapshot {
+        SessionUsageSnapshot {
+            session_id: session_id.to_string(),
+            used_tokens: 0,
+            max_tokens: 0,
+            usage_ratio,
+            captured_at_epoch_secs: 1_000,
+            provider: "openclaw".to_string(),
+        }
+    }
+
+    #[test]
+    fn sparkline_scales_ratios_to_eight_levels() {
+        let samples = [0.0, 0.5, 1.0, 1.4]
+            .iter()
+            .enumerate()
+            .map(|(idx, ratio)| UsageSample {
+                epoch_secs: idx as u64,
+                ratio: *ratio,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(sparkline(&samples), "▁▅██");
+        assert_eq!(sparkline(&[]), "");
+    }
+
+    #[test]
+    fn next_cycle_trigger_flags_compaction_and_predictive_sessions() {
+        let mut cfg = MoonConfig {
+            context: None,
+            ..MoonConfig::default()
+        };
+        cfg.thresholds.trigger_ratio = 0.8;
+        cfg.watcher.predictive_trigger = true;
+        cfg.watcher.poll_interval_secs = 200;
+        let mut state = MoonState::default();
+        let channel = "agent:main:discord:channel:ops";
+
+        assert_eq!(
+            next_cycle_trigger(&cfg, &state, &snapshot(channel, 0.9), 1_000),
+            "compaction"
+        );
+        assert_eq!(
+            next_cycle_trigger(&cfg, &state, &snapshot("agent:main:main", 0.9), 1_000),
+            "none"
+        );
+
+        record_usage_sample(&mut state, channel, 800, 0.5);
+        record_usage_sample(&mut state, channel, 1_000, 0.7);
+        assert_eq!(
+            next_cycle_trigger(&cfg, &state, &snapshot(channel, 0.7), 1_000),
+            "predictive-archive"
+        );
+        cfg.watcher.predictive_trigger = false;
+        assert_eq!(
+            next_cycle_trigger(&cfg, &state, &snapshot(channel, 0.7), 1_000),
+            "none"
+        );
+    }
+}
@@ -0,0 +1,27 @@
+use crate::commands::CommandReport;
+use crate::moon::build_info::BuildInfo;
+
+pub fn run() -> CommandReport {
+    let info = BuildInfo::current();
+    let mut report = CommandReport::new("version");
+    report.detail(format!("version={}", info.version));
+    report.detail(format!("git_sha={}", info.git_sha));
+    report.detail(format!("build_uuid={}", info.build_uuid));
+    report.detail(format!(
+        "features={}",
+        if info.features.is_empty() {
+            "none".to_string()
+        } else {
+            info.features.join(",")
+        }
+    ));
+    report.detail(format!(
+        "distill_providers={}",
+        info.distill_providers.join(",")
+    ));
+    report.detail(format!(
+        "embed_providers={}",
+        info.embed_providers.join(",")
+    ));
+    report
+}
@@ -1,6 +1,11 @@
 use anyhow::Result;
 
-use crate::commands::CommandReport;
+use crate::commands::{CommandReport, report_archive_plan};
+use crate::error::MoonErrorCode;
+use crate::moon::audit;
+use crate::moon::paths::resolve_paths;
+use crate::moon::pause;
+use crate::moon::util::now_epoch_secs;
 use crate::moon::watcher;
 
 #[derive(Debug, Clone, Default)]
@@ -10,15 +15,66 @@ pub struct MoonWatchOptions {
     pub dry_run: bool,
 }
 
+#[derive(Debug, Clone, Default)]
+pub struct MoonWatchPauseOptions {
+    pub reason: Option<String>,
+}
+
+pub fn run_pause(opts: &MoonWatchPauseOptions) -> Result<CommandReport> {
+    let mut report = CommandReport::new("watch pause");
+    let paths = resolve_paths()?;
+    let record = pause::pause(&paths, opts.reason.as_deref(), now_epoch_secs()?)?;
+    report.detail("watcher paused: cycles record usage and heartbeat only".to_string());
+    report.detail(format!(
+        "pause_file={}",
+        pause::pause_file_path(&paths).display()
+    ));
+    report.detail(record.detail());
+    let _ = audit::append_event(
+        &paths,
+        "watch",
+        "ok",
+        &format!("paused {}", record.detail()),
+        serde_json::to_value(&record).unwrap_or_default(),
+    );
+    Ok(report)
+}
+
+pub fn run_resume() -> Result<CommandReport> {
+    let mut report = CommandReport::new("watch resume");
+    let paths = resolve_paths()?;
+    match pause::resume(&paths)? {
+        Some(record) => {
+            report.detail("watcher resumed".to_string());
+            report.detail(record.detail());
+            let _ = audit::append_event(
+                &paths,
+                "watch",
+                "ok",
+                &format!("resumed {}", record.detail()),
+                serde_json::to_value(&record).unwrap_or_default(),
+            );
+        }
+        None => report.detail("watcher was not paused".to_string()),
+    }
+    Ok(report)
+}
+
 pub fn run(opts: &MoonWatchOptions) -> Result<CommandReport> {
     let mut report = CommandReport::new("watch");
 
     if opts.once && opts.daemon {
-        report.issue("invalid flags: use only one of --once or --daemon");
+        report.coded_issue(
+            MoonErrorCode::E013InvalidArgument,
+            "invalid flags: use only one of --once or --daemon",
+        );
         return Ok(report);
     }
     if opts.daemon && opts.dry_run {
-        report.issue("invalid flags: --dry-run is only valid with --once");
+        report.coded_issue(
+            MoonErrorCode::E013InvalidArgument,
+            "invalid flags: --dry-run is only valid with --once",
+        );
         return Ok(report);
     }
 
@@ -31,14 +87,14 @@ pub fn run(opts: &MoonWatchOptions) -> Result<CommandReport> {
             || exe_str.contains("target\\debug")
             || exe_str.contains("target\\release")
         {
-            report.issue(
-                "CRITICAL: Running the background daemon via `cargo run` is disabled for stability.",
-            );
-            report.issue(
-                "Cargo run holds file locks and causes severe CPU/IO spikes when the daemon restarts.",
-            );
-            report.issue("Please install the binary to your path first: `cargo install --path .`");
-            report.issue("Then start the daemon using the compiled binary: `moon watch --daemon`");
+            report
+                .coded_issue(
+                    MoonErrorCode::E003BinaryMismatch,
+                    "CRITICAL: Running the background daemon via `cargo run` is disabled for stability. Cargo run holds file locks and causes severe CPU/IO spikes when the daemon restarts.",
+                )
+                .with_hint(
+                    "install the binary to your path with `cargo install --path .`, then run `moon watch --daemon` from it",
+                );
             return Ok(report);
         }
     }
@@ -61,13 +117,27 @@ pub fn run(opts: &MoonWatchOptions) -> Result<CommandReport> {
     if opts.dry_run {
         report.detail("dry_run=true".to_string());
     }
+    report.detail(format!("cycle_id={}", cycle.cycle_id));
     report.detail(format!("state_file={}", cycle.state_file));
+    if let Some(paused) = &cycle.paused {
+        report.detail(format!("paused=true {}", paused.detail()));
+        report.detail("mutating actions skipped until `moon watch resume`".to_string());
+    }
     report.detail(format!(
         "heartbeat_epoch_secs={}",
         cycle.heartbeat_epoch_secs
     ));
     report.detail(format!("poll_interval_secs={}", cycle.poll_interval_secs));
-    report.detail(format!("threshold.trigger={}", cycle.trigger_threshold));
+    report.detail(format!("threshold.trigger={}", cycle.compaction_threshold));
+    report.detail(format!("threshold.archive={}", cycle.archive_threshold));
+    report.detail(format!(
+        "threshold.compaction={}",
+        cycle.compaction_threshold
+    ));
+    report.detail(format!(
+        "threshold.archive_trigger_enabled={}",
+        cycle.archive_trigger_enabled
+    ));
     report.detail(format!(
         "compaction.authority={}",
         cycle.compaction_authority
@@ -82,6 +152,11 @@ pub fn run(opts: &MoonWatchOptions) -> Result<CommandReport> {
         "distill.max_per_cycle={}",
         cycle.distill_max_per_cycle
     ));
+    report.detail(format!("distill.mode={}", cycle.distill_mode));
+    report.detail(format!("distill.trigger={}", cycle.distill_trigger));
+    if let Some(selection) = &cycle.distill_selection {
+        report.detail(format!("distill.selection={selection}"));
+    }
     report.detail(format!("embed.mode={}", cycle.embed_mode));
     report.detail(format!("embed.idle_secs={}", cycle.embed_idle_secs));
     report.detail(format!(
@@ -127,6 +202,9 @@ pub fn run(opts: &MoonWatchOptions) -> Result<CommandReport> {
         ));
     }
 
+    for (idx, plan) in cycle.archive_plans.iter().enumerate() {
+        report_archive_plan(&mut report, &format!("archive.plan[{idx}]"), plan);
+    }
     if let Some(archive) = cycle.archive {
         report.detail(format!("archive.path={}", archive.record.archive_path));
         if let Some(projection_path) = &archive.record.projection_path {
@@ -139,24 +217,54 @@ pub fn run(opts: &MoonWatchOptions) -> Result<CommandReport> {
         }
         report.detail(format!("archive.indexed={}", archive.record.indexed));
         report.detail(format!("archive.deduped={}", archive.deduped));
+        if let Some(original) = &archive.record.duplicate_of {
+            report.detail(format!("archive.duplicate_of={original}"));
+        }
         report.detail(format!(
             "archive.ledger_path={}",
             archive.ledger_path.display()
         ));
     }
+    if let Some(result) = cycle.compaction_anchor_result {
+        report.detail(format!("compaction_anchors.result={result}"));
+    }
     if let Some(result) = cycle.compaction_result {
         report.detail(format!("compaction.result={result}"));
     }
+    if let Some(sessions_dir) = cycle.sessions_dir {
+        report.detail(format!("sessions_dir={sessions_dir}"));
+    }
+    if let Some(skipped) = cycle.archive_skipped {
+        report.detail(format!("archive.skipped={skipped}"));
+    }
+    if let Some(result) = cycle.predictive_archive_result {
+        report.detail(format!("predictive_archive.result={result}"));
+    }
+    if let Some(result) = cycle.idle_archive_result {
+        report.detail(format!("idle_archive.result={result}"));
+    }
     if let Some(distill) = cycle.distill {
         report.detail(format!("distill.provider={}", distill.provider));
         report.detail(format!("distill.summary_path={}", distill.summary_path));
     }
+    if let Some(result) = cycle.qmd_sync_result {
+        report.detail(format!("qmd_sync.result={result}"));
+    }
     if let Some(result) = cycle.embed_result {
         report.detail(format!("embed.result={result}"));
     }
     if let Some(result) = cycle.archive_retention_result {
         report.detail(format!("archive_retention.result={result}"));
     }
+    if let Some(result) = cycle.consistency_result {
+        report.detail(format!("consistency.result={result}"));
+    }
+    if let Some(result) = cycle.daily_report_result {
+        report.detail(format!("daily_report.result={result}"));
+    }
+    if let Some(result) = cycle.memory_primer_result {
+        report.detail(format!("memory_primer.result={result}"));
+    }
     if let Some(continuity) = cycle.continuity {
         report.detail(format!("continuity.map_path={}", continuity.map_path));
         report.detail(format!(
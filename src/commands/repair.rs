@@ -23,9 +23,17 @@ pub fn run(opts: &RepairOptions) -> Result<CommandReport> {
         force: true,
         dry_run: false,
         apply: true,
+        prefix: None,
+        packaging_dir: None,
+        pin: None,
+        unpin: false,
+        channel: None,
     })?);
     restart_gateway_with_fallback(&mut report);
-    report.merge(verify::run(&VerifyOptions { strict: true })?);
+    report.merge(verify::run(&VerifyOptions {
+        strict: true,
+        only: Vec::new(),
+    })?);
 
     Ok(report)
 }
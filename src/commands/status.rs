@@ -1,14 +1,16 @@
 use anyhow::Result;
 use serde_json::Value;
 
+use crate::assets::embedded_plugin_version;
 use crate::commands::CommandReport;
+use crate::error::MoonErrorCode;
 use crate::moon::config::{
     MoonContextCompactionAuthority, MoonContextPruneMode, MoonContextWindowMode,
     load_context_policy_if_explicit_env,
 };
 use crate::openclaw::config;
 use crate::openclaw::gateway;
-use crate::openclaw::paths::resolve_paths;
+use crate::openclaw::paths::{OpenClawPaths, resolve_paths};
 use crate::openclaw::plugin_verify;
 
 #[derive(Debug, Clone, Default)]
@@ -120,23 +122,45 @@ pub fn config_snapshot(root: &Value, plugin_id: &str) -> StatusSnapshot {
     }
 }
 
-pub fn run() -> Result<CommandReport> {
+/// Which OpenClaw-side checks `verify` runs (see `verify --only`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StatusScope {
+    pub plugin: bool,
+    pub config: bool,
+}
+
+pub fn run(scope: StatusScope) -> Result<CommandReport> {
     let paths = resolve_paths()?;
     let mut report = CommandReport::new("status");
 
     let cfg = config::read_config_value(&paths)?;
     let snapshot = config_snapshot(&cfg, &paths.plugin_id);
-    let install_snapshot = install_record_snapshot(&cfg, &paths.plugin_id);
-    let context_policy = load_context_policy_if_explicit_env()?;
-    let verify = plugin_verify::verify_plugin(&paths)?;
 
-    let state_dir_disp = paths.state_dir.display().to_string();
-    let config_path_disp = paths.config_path.display().to_string();
-    let plugin_dir_disp = paths.plugin_dir.display().to_string();
+    report.detail(format!("state_dir={}", paths.state_dir.display()));
+    report.detail(format!("config_path={}", paths.config_path.display()));
+
+    if scope.plugin {
+        check_plugin(&paths, &cfg, &snapshot, &mut report)?;
+    }
+    if scope.config {
+        check_config(&paths, &cfg, &snapshot, &mut report)?;
+    }
+
+    Ok(report)
+}
+
+fn check_plugin(
+    paths: &OpenClawPaths,
+    cfg: &Value,
+    snapshot: &StatusSnapshot,
+    report: &mut CommandReport,
+) -> Result<()> {
+    let install_snapshot = install_record_snapshot(cfg, &paths.plugin_id);
+    let verify = plugin_verify::verify_plugin(paths)?;
+    let version_record = config::read_plugin_version_record(cfg, &paths.plugin_id);
+    let embedded_version = embedded_plugin_version();
 
-    report.detail(format!("state_dir={}", state_dir_disp.trim()));
-    report.detail(format!("config_path={}", config_path_disp.trim()));
-    report.detail(format!("plugin_dir={}", plugin_dir_disp.trim()));
+    report.detail(format!("plugin_dir={}", paths.plugin_dir.display()));
 
     report.detail(format!("plugin_present_on_disk={}", verify.present_on_disk));
     report.detail(format!(
@@ -152,6 +176,20 @@ pub fn run() -> Result<CommandReport> {
         verify.assets_match_local
     ));
     report.detail(format!("plugin_enabled={}", snapshot.plugin_enabled));
+    report.detail(format!("plugin_version.embedded={embedded_version}"));
+    report.detail(format!(
+        "plugin_version.installed={}",
+        verify.installed_version.as_deref().unwrap_or("<missing>")
+    ));
+    if let Some(v) = &version_record.version {
+        report.detail(format!("install_record.version={v}"));
+    }
+    if let Some(v) = &version_record.pinned_version {
+        report.detail(format!("install_record.pinnedVersion={v}"));
+    }
+    if let Some(v) = &version_record.channel {
+        report.detail(format!("install_record.channel={v}"));
+    }
 
     if let Some(s) = &install_snapshot.source {
         report.detail(format!("install_record.source={}", s.trim()));
@@ -163,8 +201,120 @@ pub fn run() -> Result<CommandReport> {
         report.detail(format!("install_record.installPath={}", s.trim()));
     }
 
+    if !verify.present_on_disk {
+        report.coded_issue(
+            MoonErrorCode::E012PluginNotLoaded,
+            "plugin files missing on disk",
+        );
+    }
+    let pinned_installed = version_record.pinned_version.is_some()
+        && version_record.pinned_version == verify.installed_version;
+    if let Some(installed) = &verify.installed_version
+        && installed != &embedded_version
+    {
+        if pinned_installed {
+            report.info(format!(
+                "plugin_version.drift=pinned installed={installed} embedded={embedded_version}"
+            ));
+        } else {
+            report.warning(format!(
+                "plugin version drift: installed {installed}, embedded {embedded_version} (run `moon install` or pin with `moon install --pin {installed}`)"
+            ));
+        }
+    }
+    if !verify.assets_match_local && !pinned_installed {
+        report.warning("installed plugin assets drift from local package assets");
+    }
+    if gateway::openclaw_available() && !verify.listed_by_openclaw {
+        report.coded_issue(
+            MoonErrorCode::E012PluginNotLoaded,
+            "plugin not listed by `openclaw plugins list --json`",
+        );
+    }
+    if gateway::openclaw_available() && !verify.loaded_by_openclaw {
+        report.coded_issue(
+            MoonErrorCode::E012PluginNotLoaded,
+            "plugin is listed but not loaded",
+        );
+    }
+    if gateway::openclaw_available() && verify.provenance_warning_detected {
+        report.issue(
+            "plugin loaded without install/load-path provenance per `openclaw plugins list --json` diagnostics",
+        );
+    }
+
+    let expected_plugin_dir = paths.plugin_dir_record();
+    let mut install_record_reasons = Vec::new();
+    if install_snapshot.source.as_deref() != Some("path") {
+        install_record_reasons.push(format!(
+            "plugins.installs.{}.source expected \"path\", found {}",
+            paths.plugin_id,
+            install_snapshot.source.as_deref().unwrap_or("<missing>")
+        ));
+    }
+    if install_snapshot.source_path.as_deref() != Some(expected_plugin_dir.as_str()) {
+        install_record_reasons.push(format!(
+            "plugins.installs.{}.sourcePath expected {}, found {}",
+            paths.plugin_id,
+            expected_plugin_dir,
+            install_snapshot
+                .source_path
+                .as_deref()
+                .unwrap_or("<missing>")
+        ));
+    }
+    if install_snapshot.install_path.as_deref() != Some(expected_plugin_dir.as_str()) {
+        install_record_reasons.push(format!(
+            "plugins.installs.{}.installPath expected {}, found {}",
+            paths.plugin_id,
+            expected_plugin_dir,
+            install_snapshot
+                .install_path
+                .as_deref()
+                .unwrap_or("<missing>")
+        ));
+    }
+    if let (Some(recorded), Some(installed)) = (&version_record.version, &verify.installed_version)
+        && recorded != installed
+    {
+        install_record_reasons.push(format!(
+            "plugins.installs.{}.version expected {installed}, found {recorded}",
+            paths.plugin_id
+        ));
+    }
+    if !install_record_reasons.is_empty() {
+        if verify.provenance_warning_detected {
+            report.issue(format!(
+                "install record drift: {}",
+                install_record_reasons.join("; ")
+            ));
+        } else {
+            report.info(format!(
+                "provenance repair hint: {}",
+                install_record_reasons.join("; ")
+            ));
+        }
+    }
+    if !snapshot.plugin_enabled {
+        report.coded_issue(
+            MoonErrorCode::E012PluginNotLoaded,
+            "plugin entry is not enabled in config",
+        );
+    }
+
+    Ok(())
+}
+
+fn check_config(
+    paths: &OpenClawPaths,
+    cfg: &Value,
+    snapshot: &StatusSnapshot,
+    report: &mut CommandReport,
+) -> Result<()> {
+    let context_policy = load_context_policy_if_explicit_env()?;
+
     if let Some(v) = path_value(
-        &cfg,
+        cfg,
         &[
             "plugins",
             "entries",
@@ -176,13 +326,13 @@ pub fn run() -> Result<CommandReport> {
         report.detail(format!("plugin_config.maxTokens={}", v.to_string().trim()));
     }
     if let Some(v) = path_value(
-        &cfg,
+        cfg,
         &["plugins", "entries", &paths.plugin_id, "config", "maxChars"],
     ) {
         report.detail(format!("plugin_config.maxChars={}", v.to_string().trim()));
     }
     if let Some(v) = path_value(
-        &cfg,
+        cfg,
         &[
             "plugins",
             "entries",
@@ -196,13 +346,13 @@ pub fn run() -> Result<CommandReport> {
             v.to_string().trim()
         ));
     }
-    if let Some(v) = path_value(&cfg, &["agents", "defaults", "contextTokens"]) {
+    if let Some(v) = path_value(cfg, &["agents", "defaults", "contextTokens"]) {
         report.detail(format!(
             "agents.defaults.contextTokens={}",
             v.to_string().trim()
         ));
     }
-    if let Some(v) = path_value(&cfg, &["agents", "defaults", "compaction", "mode"]) {
+    if let Some(v) = path_value(cfg, &["agents", "defaults", "compaction", "mode"]) {
         report.detail(format!(
             "agents.defaults.compaction.mode={}",
             v.to_string().trim()
@@ -225,13 +375,14 @@ pub fn run() -> Result<CommandReport> {
         );
     }
 
-    let context_tokens = path_u64(&cfg, &["agents", "defaults", "contextTokens"]);
-    let compaction_mode = path_string(&cfg, &["agents", "defaults", "compaction", "mode"]);
+    let context_tokens = path_u64(cfg, &["agents", "defaults", "contextTokens"]);
+    let compaction_mode = path_string(cfg, &["agents", "defaults", "compaction", "mode"]);
     if let Some(policy) = &context_policy {
         match policy.prune_mode {
             MoonContextPruneMode::Disabled => {
                 if snapshot.context_pruning_mode {
-                    report.issue(
+                    report.coded_issue(
+                        MoonErrorCode::E005ConfigMissing,
                         "context policy drift: agents.defaults.contextPruning must be disabled"
                             .to_string(),
                     );
@@ -239,10 +390,16 @@ pub fn run() -> Result<CommandReport> {
             }
             MoonContextPruneMode::Guarded => {
                 if !snapshot.context_pruning_mode {
-                    report.issue("missing agents.defaults.contextPruning.mode");
+                    report.coded_issue(
+                        MoonErrorCode::E005ConfigMissing,
+                        "missing agents.defaults.contextPruning.mode",
+                    );
                 }
                 if !snapshot.context_pruning_soft_trim {
-                    report.issue("missing agents.defaults.contextPruning.softTrim.maxChars");
+                    report.coded_issue(
+                        MoonErrorCode::E005ConfigMissing,
+                        "missing agents.defaults.contextPruning.softTrim.maxChars",
+                    );
                 }
             }
         }
@@ -250,7 +407,8 @@ pub fn run() -> Result<CommandReport> {
         match policy.window_mode {
             MoonContextWindowMode::Inherit => {
                 if context_tokens.is_some() {
-                    report.issue(
+                    report.coded_issue(
+                        MoonErrorCode::E005ConfigMissing,
                         "context policy drift: agents.defaults.contextTokens must be unset when window_mode=inherit"
                             .to_string(),
                     );
@@ -266,7 +424,7 @@ pub fn run() -> Result<CommandReport> {
                     .window_tokens
                     .unwrap_or(config::MIN_AGENT_CONTEXT_TOKENS);
                 if context_tokens != Some(expected) {
-                    report.issue(format!(
+                    report.coded_issue(MoonErrorCode::E005ConfigMissing, format!(
                         "context policy drift: agents.defaults.contextTokens expected {expected}, found {}",
                         context_tokens
                             .map(|v| v.to_string())
@@ -285,17 +443,23 @@ pub fn run() -> Result<CommandReport> {
                 MoonContextCompactionAuthority::Moon => "moon",
                 MoonContextCompactionAuthority::Openclaw => "openclaw",
             };
-            report.issue(format!(
+            report.coded_issue(MoonErrorCode::E005ConfigMissing, format!(
                 "context policy drift: agents.defaults.compaction.mode expected {expected_compaction_mode} when compaction_authority={auth}, found {}",
                 compaction_mode.unwrap_or_else(|| "<missing>".to_string())
             ));
         }
     } else {
         if !snapshot.context_pruning_mode {
-            report.issue("missing agents.defaults.contextPruning.mode");
+            report.coded_issue(
+                MoonErrorCode::E005ConfigMissing,
+                "missing agents.defaults.contextPruning.mode",
+            );
         }
         if !snapshot.context_pruning_soft_trim {
-            report.issue("missing agents.defaults.contextPruning.softTrim.maxChars");
+            report.coded_issue(
+                MoonErrorCode::E005ConfigMissing,
+                "missing agents.defaults.contextPruning.softTrim.maxChars",
+            );
         }
         if context_tokens.is_none() {
             report.detail(
@@ -312,82 +476,17 @@ pub fn run() -> Result<CommandReport> {
     }
 
     if !snapshot.plugin_max_tokens {
-        report.issue("missing plugins.entries.moon.config.maxTokens");
+        report.warning("missing plugins.entries.moon.config.maxTokens");
     }
     if !snapshot.plugin_max_chars {
-        report.issue("missing plugins.entries.moon.config.maxChars");
+        report.warning("missing plugins.entries.moon.config.maxChars");
     }
     if !snapshot.plugin_max_retained_bytes {
-        report.issue("missing plugins.entries.moon.config.maxRetainedBytes");
+        report.warning("missing plugins.entries.moon.config.maxRetainedBytes");
     }
     if !snapshot.plugin_read_profile_tokens {
-        report.issue("missing plugins.entries.moon.config.tools.read.maxTokens");
-    }
-    if !verify.present_on_disk {
-        report.issue("plugin files missing on disk");
-    }
-    if !verify.assets_match_local {
-        report.issue("installed plugin assets drift from local package assets");
-    }
-    if gateway::openclaw_available() && !verify.listed_by_openclaw {
-        report.issue("plugin not listed by `openclaw plugins list --json`");
-    }
-    if gateway::openclaw_available() && !verify.loaded_by_openclaw {
-        report.issue("plugin is listed but not loaded");
-    }
-    if gateway::openclaw_available() && verify.provenance_warning_detected {
-        report.issue(
-            "plugin loaded without install/load-path provenance per `openclaw plugins list --json` diagnostics",
-        );
-    }
-
-    let expected_plugin_dir = paths.plugin_dir.display().to_string();
-    let mut install_record_reasons = Vec::new();
-    if install_snapshot.source.as_deref() != Some("path") {
-        install_record_reasons.push(format!(
-            "plugins.installs.{}.source expected \"path\", found {}",
-            paths.plugin_id,
-            install_snapshot.source.as_deref().unwrap_or("<missing>")
-        ));
-    }
-    if install_snapshot.source_path.as_deref() != Some(expected_plugin_dir.as_str()) {
-        install_record_reasons.push(format!(
-            "plugins.installs.{}.sourcePath expected {}, found {}",
-            paths.plugin_id,
-            expected_plugin_dir,
-            install_snapshot
-                .source_path
-                .as_deref()
-                .unwrap_or("<missing>")
-        ));
-    }
-    if install_snapshot.install_path.as_deref() != Some(expected_plugin_dir.as_str()) {
-        install_record_reasons.push(format!(
-            "plugins.installs.{}.installPath expected {}, found {}",
-            paths.plugin_id,
-            expected_plugin_dir,
-            install_snapshot
-                .install_path
-                .as_deref()
-                .unwrap_or("<missing>")
-        ));
-    }
-    if !install_record_reasons.is_empty() {
-        if verify.provenance_warning_detected {
-            report.issue(format!(
-                "install record drift: {}",
-                install_record_reasons.join("; ")
-            ));
-        } else {
-            report.detail(format!(
-                "provenance repair hint: {}",
-                install_record_reasons.join("; ")
-            ));
-        }
-    }
-    if !snapshot.plugin_enabled {
-        report.issue("plugin entry is not enabled in config");
+        report.warning("missing plugins.entries.moon.config.tools.read.maxTokens");
     }
 
-    Ok(report)
+    Ok(())
 }
@@ -1,31 +1,126 @@
 use anyhow::Result;
 
-use crate::commands::status;
-use crate::commands::{CommandReport, ensure_openclaw_available};
+use crate::commands::status::{self, StatusScope};
+use crate::commands::{CommandReport, ensure_openclaw_available, moon_health};
 use crate::openclaw::doctor;
 
+pub const SCOPE_PLUGIN: &str = "plugin";
+pub const SCOPE_CONFIG: &str = "config";
+pub const SCOPE_MOON: &str = "moon";
+
 #[derive(Debug, Clone, Default)]
 pub struct VerifyOptions {
     pub strict: bool,
+    /// `plugin`, `config` and/or `moon`; empty means `plugin` + `config`.
+    pub only: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct VerifyScope {
+    plugin: bool,
+    config: bool,
+    moon: bool,
+}
+
+impl VerifyScope {
+    fn parse(only: &[String]) -> Result<Self> {
+        if only.is_empty() {
+            return Ok(Self {
+                plugin: true,
+                config: true,
+                moon: false,
+            });
+        }
+        let mut scope = Self {
+            plugin: false,
+            config: false,
+            moon: false,
+        };
+        for raw in only {
+            match raw.trim() {
+                SCOPE_PLUGIN => scope.plugin = true,
+                SCOPE_CONFIG => scope.config = true,
+                SCOPE_MOON => scope.moon = true,
+                other => anyhow::bail!(
+                    "invalid --only `{other}`: expected {SCOPE_PLUGIN}, {SCOPE_CONFIG} or {SCOPE_MOON}"
+                ),
+            }
+        }
+        Ok(scope)
+    }
+
+    fn openclaw(self) -> bool {
+        self.plugin || self.config
+    }
+
+    fn label(self) -> String {
+        [
+            (self.plugin, SCOPE_PLUGIN),
+            (self.config, SCOPE_CONFIG),
+            (self.moon, SCOPE_MOON),
+        ]
+        .iter()
+        .filter(|(enabled, _)| *enabled)
+        .map(|(_, name)| *name)
+        .collect::<Vec<_>>()
+        .join(",")
+    }
 }
 
 pub fn run(opts: &VerifyOptions) -> Result<CommandReport> {
+    let scope = VerifyScope::parse(&opts.only)?;
     let mut report = CommandReport::new("verify");
+    report.detail(format!("verify.scope={}", scope.label()));
 
-    let openclaw_ready = ensure_openclaw_available(&mut report);
-    if openclaw_ready {
-        if let Err(err) = doctor::run_full_doctor() {
-            report.issue(format!("doctor failed: {err}"));
-        } else {
-            report.detail("doctor: ok".to_string());
+    if scope.openclaw() {
+        let openclaw_ready = ensure_openclaw_available(&mut report);
+        if openclaw_ready {
+            if let Err(err) = doctor::run_full_doctor() {
+                report.issue(format!("doctor failed: {err}"));
+            } else {
+                report.detail("doctor: ok".to_string());
+            }
         }
-    }
 
-    report.merge(status::run()?);
+        report.merge(status::run(StatusScope {
+            plugin: scope.plugin,
+            config: scope.config,
+        })?);
+    }
+    if scope.moon {
+        report.merge(moon_health::run(&moon_health::MoonHealthOptions::default())?);
+    }
 
+    report.detail(format!(
+        "verify.errors={} verify.warnings={} verify.info={}",
+        report.issues.len(),
+        report.warnings.len(),
+        report.info.len()
+    ));
+    // Strict mode fails on error-severity issues only; warnings and info never fail verify.
     if opts.strict && !report.ok {
-        report.issue("strict verify failed");
+        report.issue(format!(
+            "strict verify failed: {} error(s)",
+            report.issues.len()
+        ));
     }
 
     Ok(report)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scope_defaults_to_openclaw_checks_and_rejects_unknown_names() {
+        let default = VerifyScope::parse(&[]).expect("default");
+        assert_eq!(default.label(), "plugin,config");
+
+        let moon_only = VerifyScope::parse(&["moon".to_string()]).expect("moon");
+        assert!(!moon_only.openclaw());
+        assert_eq!(moon_only.label(), "moon");
+
+        assert!(VerifyScope::parse(&["gateway".to_string()]).is_err());
+    }
+}
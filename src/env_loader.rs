@@ -1,5 +1,15 @@
+use anyhow::{Context, Result};
 use std::env;
-use std::path::PathBuf;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::moon::config::SECRET_ENV_KEYS;
+
+/// Keys besides the provider API keys whose presence makes a `.env` a secret file.
+const EXTRA_SECRET_KEYS: [&str; 1] = ["MOON_PRIVACY_KEY"];
+
+static LOADED_DOTENV: OnceLock<PathBuf> = OnceLock::new();
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum DotenvLoadOutcome {
@@ -14,7 +24,8 @@ fn fallback_dotenv_path(moon_home: Option<PathBuf>, home_dir: Option<PathBuf>) -
 }
 
 pub fn load_dotenv() -> DotenvLoadOutcome {
-    if dotenvy::dotenv().is_ok() {
+    if let Ok(path) = dotenvy::dotenv() {
+        let _ = LOADED_DOTENV.set(path);
         return DotenvLoadOutcome::LoadedDefault;
     }
 
@@ -27,16 +38,113 @@ pub fn load_dotenv() -> DotenvLoadOutcome {
         return DotenvLoadOutcome::Missing;
     };
     if path.is_file() && dotenvy::from_path(&path).is_ok() {
+        let _ = LOADED_DOTENV.set(path.clone());
         return DotenvLoadOutcome::LoadedFallback(path);
     }
 
     DotenvLoadOutcome::Missing
 }
 
+/// The `.env` file [`load_dotenv`] read, if any.
+pub fn loaded_dotenv_path() -> Option<&'static Path> {
+    LOADED_DOTENV.get().map(PathBuf::as_path)
+}
+
+/// Permission bits of a `.env` and whether it sets a provider key or `MOON_PRIVACY_KEY`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DotenvPermissions {
+    pub mode: u32,
+    pub holds_secrets: bool,
+}
+
+impl DotenvPermissions {
+    /// Group or other users can read (or write) a file that holds secrets.
+    pub fn is_exposed(&self) -> bool {
+        self.holds_secrets && self.mode & 0o077 != 0
+    }
+}
+
+fn holds_secrets(path: &Path) -> bool {
+    let Ok(entries) = dotenvy::from_path_iter(path) else {
+        return false;
+    };
+    entries.flatten().any(|(key, value)| {
+        !value.trim().is_empty()
+            && (SECRET_ENV_KEYS.contains(&key.as_str())
+                || EXTRA_SECRET_KEYS.contains(&key.as_str()))
+    })
+}
+
+/// `None` on platforms without Unix permission bits.
+pub fn check_permissions(path: &Path) -> Result<Option<DotenvPermissions>> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let meta =
+            fs::metadata(path).with_context(|| format!("failed to stat {}", path.display()))?;
+        Ok(Some(DotenvPermissions {
+            mode: meta.permissions().mode() & 0o777,
+            holds_secrets: holds_secrets(path),
+        }))
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = path;
+        Ok(None)
+    }
+}
+
+pub fn restrict_permissions(path: &Path) -> Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o600))
+            .with_context(|| format!("failed to restrict {}", path.display()))?;
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+    Ok(())
+}
+
+/// Warns on stderr when the loaded `.env` holds secrets but is group/world accessible, or
+/// restricts it to `0600` when `MOON_ENV_FIX_PERMISSIONS` is true.
+pub fn harden_loaded_dotenv() {
+    let Some(path) = loaded_dotenv_path() else {
+        return;
+    };
+    let Ok(Some(permissions)) = check_permissions(path) else {
+        return;
+    };
+    if !permissions.is_exposed() {
+        return;
+    }
+    let fix = env::var("MOON_ENV_FIX_PERMISSIONS")
+        .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+        .unwrap_or(false);
+    if fix {
+        match restrict_permissions(path) {
+            Ok(()) => eprintln!(
+                "NOTE: restricted `{}` to mode 0600 (was {:04o}); it holds provider keys.",
+                path.display(),
+                permissions.mode
+            ),
+            Err(err) => eprintln!("WARN: {err:#}"),
+        }
+    } else {
+        eprintln!(
+            "WARN: `{}` holds provider keys but is readable by other users (mode {:04o}); run `chmod 600` on it or set MOON_ENV_FIX_PERMISSIONS=true.",
+            path.display(),
+            permissions.mode
+        );
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::fallback_dotenv_path;
+    use super::{DotenvPermissions, fallback_dotenv_path, holds_secrets};
+    use std::fs;
     use std::path::PathBuf;
+    use tempfile::tempdir;
 
     #[test]
     fn fallback_always_uses_moon_repo_subdir_for_moon_home() {
@@ -55,4 +163,31 @@ mod tests {
         let want = Some(PathBuf::from("/home/alice/moon/.env"));
         assert_eq!(got, want);
     }
+
+    #[test]
+    fn only_env_files_setting_a_secret_count_as_secret_files() {
+        let tmp = tempdir().expect("tempdir");
+        let with_key = tmp.path().join("with-key.env");
+        fs::write(&with_key, "MOON_HOME=/srv/moon\nOPENAI_API_KEY=sk-test\n").expect("write");
+        let blank_key = tmp.path().join("blank-key.env");
+        fs::write(&blank_key, "MOON_HOME=/srv/moon\nOPENAI_API_KEY=\n").expect("write");
+
+        assert!(holds_secrets(&with_key));
+        assert!(!holds_secrets(&blank_key));
+    }
+
+    #[test]
+    fn exposure_needs_secrets_and_group_or_world_bits() {
+        let exposed = |mode, holds_secrets| {
+            DotenvPermissions {
+                mode,
+                holds_secrets,
+            }
+            .is_exposed()
+        };
+        assert!(exposed(0o644, true));
+        assert!(exposed(0o620, true));
+        assert!(!exposed(0o600, true));
+        assert!(!exposed(0o644, false));
+    }
 }
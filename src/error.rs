@@ -21,6 +21,14 @@ pub enum MoonErrorCode {
     E005ConfigMissing,
     E006DaemonPanic,
     E007StateCorrupt,
+    E008HeartbeatStale,
+    E009QmdUnavailable,
+    E010QmdCollection,
+    E011OpenClawMissing,
+    E012PluginNotLoaded,
+    E013InvalidArgument,
+    E014PathMissing,
+    E015DanglingReference,
 }
 
 impl MoonErrorCode {
@@ -33,6 +41,43 @@ impl MoonErrorCode {
             Self::E005ConfigMissing => "E005_CONFIG_MISSING",
             Self::E006DaemonPanic => "E006_DAEMON_PANIC",
             Self::E007StateCorrupt => "E007_STATE_CORRUPT",
+            Self::E008HeartbeatStale => "E008_HEARTBEAT_STALE",
+            Self::E009QmdUnavailable => "E009_QMD_UNAVAILABLE",
+            Self::E010QmdCollection => "E010_QMD_COLLECTION",
+            Self::E011OpenClawMissing => "E011_OPENCLAW_MISSING",
+            Self::E012PluginNotLoaded => "E012_PLUGIN_NOT_LOADED",
+            Self::E013InvalidArgument => "E013_INVALID_ARGUMENT",
+            Self::E014PathMissing => "E014_PATH_MISSING",
+            Self::E015DanglingReference => "E015_DANGLING_REFERENCE",
+        }
+    }
+
+    /// Default remediation shown next to issues carrying this code.
+    pub fn hint(self) -> &'static str {
+        match self {
+            Self::E001Locked => {
+                "run `moon stop` to clear the daemon lock, then `moon watch --daemon`"
+            }
+            Self::E002StaleBuild => "run `moon restart` so the daemon runs this build",
+            Self::E003BinaryMismatch => {
+                "install the binary with `cargo install --path .` and start the daemon from it"
+            }
+            Self::E004CwdInvalid => "run from the workspace tree or pass --allow-out-of-bounds",
+            Self::E005ConfigMissing => "run `moon repair` to restore the expected OpenClaw config",
+            Self::E006DaemonPanic => "check `moon audit --phase daemon`, then run `moon restart`",
+            Self::E007StateCorrupt => {
+                "fix or move aside the state file; the next watch cycle recreates it"
+            }
+            Self::E008HeartbeatStale => "start the watcher with `moon watch --daemon`",
+            Self::E009QmdUnavailable => "install qmd or point QMD_BIN at it",
+            Self::E010QmdCollection => "run `moon index` to rebuild the collection",
+            Self::E011OpenClawMissing => "set OPENCLAW_BIN or ensure openclaw is on PATH",
+            Self::E012PluginNotLoaded => "run `moon install`, then restart the OpenClaw gateway",
+            Self::E013InvalidArgument => "see `moon help` for the accepted values",
+            Self::E014PathMissing => "run `moon init` to create the MOON_HOME layout",
+            Self::E015DanglingReference => {
+                "run `moon health --repair` to drop or repoint dangling references"
+            }
         }
     }
 }
@@ -6,6 +6,7 @@ mod error;
 mod logging;
 mod moon;
 mod openclaw;
+mod packaging;
 
 fn main() {
     if matches!(
@@ -14,6 +15,7 @@ fn main() {
     ) {
         eprintln!("WARN: `.env` not found — distill/embed features will be unavailable.");
     }
+    env_loader::harden_loaded_dotenv();
 
     if let Err(err) = cli::run() {
         eprintln!("error: {err:#}");
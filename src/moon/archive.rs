@@ -1,9 +1,19 @@
-use crate::moon::distill::{ProjectionData, extract_projection_data};
+use crate::moon::audit;
+use crate::moon::config::{MoonPrivacyConfig, load_config, resolve_residential_tz};
+use crate::moon::distill::{
+    CompactionAnchor, DistillOutput, ProjectionData, extract_projection_data,
+};
+use crate::moon::hooks::{self, HookEvent};
+use crate::moon::lease;
 use crate::moon::paths::MoonPaths;
+use crate::moon::privacy;
 use crate::moon::qmd;
-use crate::moon::snapshot::write_snapshot;
+use crate::moon::session_file::{read_session_bytes, session_file_stem};
+use crate::moon::simhash;
+use crate::moon::snapshot::{planned_snapshot_path, raw_archive_dir, write_snapshot};
 use crate::moon::warn::{self, WarnEvent};
 use anyhow::{Context, Result};
+use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use std::collections::{BTreeMap, BTreeSet};
@@ -24,6 +34,58 @@ pub struct ArchiveRecord {
     pub created_at_epoch_secs: u64,
     pub indexed_collection: String,
     pub indexed: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_bytes: Option<u64>,
+    /// SHA-256 of the archive prefix at every `PREFIX_HASH_STRIDE` bytes, oldest first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub prefix_hashes: Vec<String>,
+    /// Newer archive of the same session whose content starts with this one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub superseded_by: Option<String>,
+    /// Archived from a `[privacy]` channel: never projected, indexed, or recalled.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub private: bool,
+    /// Raw archive sealed with `MOON_PRIVACY_KEY`; hashes above are of the plaintext.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub encrypted: bool,
+    /// Latest distillation of this archive, from the watcher or `moon distill -mode norm`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub distill: Option<DistillProvenance>,
+    /// Summaries OpenClaw reported through the gateway for the compaction that followed this
+    /// archive; rendered into the projection's compaction notes.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub compaction_anchors: Vec<CompactionAnchor>,
+    /// Simhash of the projected conversation (16 hex digits); unset for short conversations.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub projection_simhash: Option<String>,
+    /// Earlier archive of another session holding essentially the same conversation, e.g.
+    /// after OpenClaw renamed the session; recall reports that archive instead.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duplicate_of: Option<String>,
+    /// Left out of the watcher's distillation on purpose (`moon ledger skip-distill`); newer
+    /// archives superseding this one inherit the flag.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub distill_skip: bool,
+}
+
+/// Which provider distilled an archive, when, and into which summary file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DistillProvenance {
+    pub distilled_at_epoch_secs: u64,
+    pub provider: String,
+    pub summary_path: String,
+    pub chunk_count: usize,
+}
+
+impl DistillProvenance {
+    pub fn from_output(out: &DistillOutput) -> Self {
+        Self {
+            distilled_at_epoch_secs: out.created_at_epoch_secs,
+            provider: out.provider.clone(),
+            summary_path: out.summary_path.clone(),
+            chunk_count: out.chunk_count,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -33,12 +95,35 @@ pub struct ArchivePipelineOutcome {
     pub ledger_path: PathBuf,
 }
 
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone)]
+pub struct ArchivePlan {
+    pub source_path: PathBuf,
+    pub source_bytes: u64,
+    pub deduped: bool,
+    pub archive_path: PathBuf,
+    pub projection_path: PathBuf,
+    pub projection_bytes_estimate: Option<usize>,
+    pub projection_error: Option<String>,
+    pub qmd_operation: String,
+    pub ledger_path: PathBuf,
+}
+
+#[derive(Debug, Clone, Default)]
 pub struct ProjectionBackfillOutcome {
     pub scanned: usize,
     pub created: usize,
     pub failed: usize,
     pub ledger_updated: bool,
+    /// Reprojected files whose rendered markdown changed.
+    pub diffs: Vec<ProjectionDiff>,
+    /// Reprojected files that rendered byte-identical to the previous version.
+    pub unchanged: usize,
+    pub total: usize,
+    pub resumed_from: usize,
+    /// Ledger index the next run resumes from; `None` once the pass completed.
+    pub next_cursor: Option<usize>,
+    /// Rows newly marked `duplicate_of` an earlier session's archive once the pass completed.
+    pub duplicates_marked: usize,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -49,6 +134,157 @@ pub struct ArchiveLayoutMigrationOutcome {
     pub failed: usize,
     pub ledger_updated: bool,
     pub path_rewrites: BTreeMap<String, String>,
+    pub total: usize,
+    pub resumed_from: usize,
+    /// Ledger index the next run resumes from; `None` once the pass completed.
+    pub next_cursor: Option<usize>,
+}
+
+/// Ledger records processed between ledger/cursor checkpoints and progress callbacks.
+pub const MIGRATION_CHUNK_SIZE: usize = 200;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MigrationRunOptions {
+    /// Stop after this many ledger records; the cursor lets the next run continue.
+    pub limit: Option<usize>,
+    /// When reprojecting, copy each overwritten projection to `mlib-prev/<name>.prev.md`.
+    pub keep_previous: bool,
+}
+
+/// Summary of how a reprojection changed one projection file.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ProjectionDiff {
+    pub projection_path: String,
+    pub sections_added: Vec<String>,
+    pub sections_removed: Vec<String>,
+    pub message_count_delta: i64,
+    pub bytes_delta: i64,
+    pub previous_path: Option<String>,
+}
+
+impl ProjectionDiff {
+    pub fn summary(&self) -> String {
+        let list = |sections: &[String]| {
+            if sections.is_empty() {
+                "none".to_string()
+            } else {
+                sections.join("|")
+            }
+        };
+        format!(
+            "path={} sections_added={} sections_removed={} message_count_delta={:+} bytes_delta={:+} prev={}",
+            self.projection_path,
+            list(&self.sections_added),
+            list(&self.sections_removed),
+            self.message_count_delta,
+            self.bytes_delta,
+            self.previous_path.as_deref().unwrap_or("none")
+        )
+    }
+}
+
+fn projection_sections(markdown: &str) -> Vec<String> {
+    markdown
+        .lines()
+        .filter_map(|line| line.strip_prefix("## "))
+        .map(|title| title.trim().to_string())
+        .collect()
+}
+
+fn projection_message_count(markdown: &str) -> i64 {
+    markdown
+        .lines()
+        .find_map(|line| line.strip_prefix("message_count:"))
+        .and_then(|value| value.trim().parse::<i64>().ok())
+        .unwrap_or(0)
+}
+
+/// Section and message-count changes between two renders of the same projection.
+pub fn diff_projection_markdown(
+    projection_path: &Path,
+    previous: &str,
+    current: &str,
+) -> ProjectionDiff {
+    let before = projection_sections(previous);
+    let after = projection_sections(current);
+    ProjectionDiff {
+        projection_path: projection_path.display().to_string(),
+        sections_added: after
+            .iter()
+            .filter(|section| !before.contains(section))
+            .cloned()
+            .collect(),
+        sections_removed: before
+            .iter()
+            .filter(|section| !after.contains(section))
+            .cloned()
+            .collect(),
+        message_count_delta: projection_message_count(current) - projection_message_count(previous),
+        bytes_delta: current.len() as i64 - previous.len() as i64,
+        previous_path: None,
+    }
+}
+
+/// Kept outside `mlib/` so previous versions never match the qmd collection mask.
+fn previous_projection_path(paths: &MoonPaths, projection_path: &Path) -> PathBuf {
+    let stem = projection_path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("projection");
+    paths
+        .archives_dir
+        .join("mlib-prev")
+        .join(format!("{stem}.prev.md"))
+}
+
+/// Resume points for the layout migration and projection backfill passes. The layout pass runs
+/// first; once it completes it is skipped until the backfill pass also completes and the cursor
+/// resets.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct MigrationCursor {
+    pub layout_next: usize,
+    pub layout_done: bool,
+    pub projection_next: usize,
+    /// The projection cursor only resumes a pass run with the same reproject mode.
+    pub projection_reproject: bool,
+}
+
+pub fn migration_cursor_path(paths: &MoonPaths) -> PathBuf {
+    paths.archives_dir.join("migration-cursor.json")
+}
+
+pub fn load_migration_cursor(paths: &MoonPaths) -> Result<MigrationCursor> {
+    let path = migration_cursor_path(paths);
+    if !path.exists() {
+        return Ok(MigrationCursor::default());
+    }
+    let raw =
+        fs::read_to_string(&path).with_context(|| format!("failed to read {}", path.display()))?;
+    serde_json::from_str(&raw).with_context(|| format!("failed to parse {}", path.display()))
+}
+
+fn save_migration_cursor(paths: &MoonPaths, cursor: &MigrationCursor) -> Result<()> {
+    let path = migration_cursor_path(paths);
+    if *cursor == MigrationCursor::default() {
+        return match fs::remove_file(&path) {
+            Ok(()) => Ok(()),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(err) => Err(err).with_context(|| format!("failed to remove {}", path.display())),
+        };
+    }
+    fs::write(&path, serde_json::to_string_pretty(cursor)?)
+        .with_context(|| format!("failed to write {}", path.display()))
+}
+
+/// `[start, end)` ledger window for a pass resuming at `cursor` over `total` records.
+fn migration_window(cursor: usize, total: usize, opts: &MigrationRunOptions) -> (usize, usize) {
+    let start = if cursor < total { cursor } else { 0 };
+    let end = opts
+        .limit
+        .map(|limit| start.saturating_add(limit).min(total))
+        .unwrap_or(total);
+    (start, end)
 }
 
 fn epoch_now() -> Result<u64> {
@@ -62,17 +298,32 @@ fn ledger_path(paths: &MoonPaths) -> PathBuf {
     paths.archives_dir.join("ledger.jsonl")
 }
 
+fn is_raw_dir_name(dir: &Path) -> bool {
+    dir.file_name()
+        .and_then(|v| v.to_str())
+        .is_some_and(|name| name == "raw" || (cfg!(windows) && name.eq_ignore_ascii_case("raw")))
+}
+
+/// `raw/<name>` projects to `mlib/<name>.md` and a collection's `raw/<sub>/<name>` to
+/// `mlib/<sub>/<name>.md`; anything else gets its projection alongside.
 pub fn projection_path_for_archive_path(archive_path: &Path) -> PathBuf {
-    if let (Some(parent), Some(file_name)) = (archive_path.parent(), archive_path.file_name())
-        && parent
-            .file_name()
-            .and_then(|v| v.to_str())
-            .is_some_and(|name| name == "raw")
-        && let Some(archives_root) = parent.parent()
-    {
+    if let (Some(parent), Some(file_name)) = (archive_path.parent(), archive_path.file_name()) {
         let mut projection_name = PathBuf::from(file_name);
         projection_name.set_extension("md");
-        return archives_root.join("mlib").join(projection_name);
+        if is_raw_dir_name(parent)
+            && let Some(archives_root) = parent.parent()
+        {
+            return archives_root.join("mlib").join(projection_name);
+        }
+        if let (Some(subdir), Some(raw_dir)) = (parent.file_name(), parent.parent())
+            && is_raw_dir_name(raw_dir)
+            && let Some(archives_root) = raw_dir.parent()
+        {
+            return archives_root
+                .join("mlib")
+                .join(subdir)
+                .join(projection_name);
+        }
     }
     archive_path.with_extension("md")
 }
@@ -81,6 +332,24 @@ pub fn projection_path_for_archive(archive_path: &str) -> PathBuf {
     projection_path_for_archive_path(Path::new(archive_path))
 }
 
+/// `<stem>.projection.json` next to the projection; the `.md` qmd mask never indexes it, and the
+/// double extension keeps it apart from whole-document `.json` archives in the legacy layout.
+pub fn projection_sidecar_path(projection_path: &Path) -> PathBuf {
+    projection_path.with_extension("projection.json")
+}
+
+/// The `[projection] json_sidecar` file: the extracted [`ProjectionData`] with the archive
+/// identity it was rendered for, so tools can read a session without reparsing the raw archive.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectionSidecar {
+    pub session_id: String,
+    pub source_path: String,
+    pub archive_path: String,
+    pub content_hash: String,
+    pub created_at_epoch_secs: u64,
+    pub projection: ProjectionData,
+}
+
 fn raw_archives_dir(paths: &MoonPaths) -> PathBuf {
     paths.archives_dir.join("raw")
 }
@@ -110,7 +379,13 @@ fn legacy_lib_projection_path_for_archive_path(archive_path: &Path) -> Option<Pa
     None
 }
 
-fn move_file(from: &Path, to: &Path) -> Result<()> {
+const MOVE_RETRY_ATTEMPTS: u32 = 3;
+const MOVE_RETRY_DELAY: std::time::Duration = std::time::Duration::from_millis(100);
+
+/// Renames `from` to `to`, falling back to copy + remove when a rename cannot work: across
+/// devices or volumes, or when Windows keeps refusing because another process (indexer,
+/// antivirus, sync client) holds the file open.
+pub(crate) fn move_file(from: &Path, to: &Path) -> Result<()> {
     if from == to {
         return Ok(());
     }
@@ -119,30 +394,274 @@ fn move_file(from: &Path, to: &Path) -> Result<()> {
             .with_context(|| format!("failed to create {}", parent.display()))?;
     }
 
-    match fs::rename(from, to) {
-        Ok(_) => Ok(()),
-        Err(rename_err) => {
-            if matches!(
-                rename_err.kind(),
-                ErrorKind::CrossesDevices | ErrorKind::PermissionDenied
-            ) {
-                fs::copy(from, to).with_context(|| {
-                    format!("failed to copy {} to {}", from.display(), to.display())
-                })?;
-                fs::remove_file(from)
-                    .with_context(|| format!("failed to remove {}", from.display()))?;
-                Ok(())
-            } else {
-                Err(rename_err).with_context(|| {
-                    format!("failed to move {} to {}", from.display(), to.display())
-                })
+    let mut attempt = 0;
+    let rename_err = loop {
+        match fs::rename(from, to) {
+            Ok(()) => return Ok(()),
+            Err(err) if is_transient_rename_error(&err) && attempt < MOVE_RETRY_ATTEMPTS => {
+                attempt += 1;
+                std::thread::sleep(MOVE_RETRY_DELAY);
+            }
+            Err(err) => break err,
+        }
+    };
+    if !rename_needs_copy_fallback(&rename_err) {
+        return Err(rename_err)
+            .with_context(|| format!("failed to move {} to {}", from.display(), to.display()));
+    }
+    copy_then_remove(from, to)
+}
+
+/// `ErrorKind::CrossesDevices` is not reported consistently across platforms and toolchains,
+/// so the raw cross-device codes (`EXDEV`, `ERROR_NOT_SAME_DEVICE`) are checked too.
+fn rename_needs_copy_fallback(err: &std::io::Error) -> bool {
+    const EXDEV: i32 = 18;
+    const ERROR_NOT_SAME_DEVICE: i32 = 17;
+    let cross_device_code = if cfg!(windows) {
+        ERROR_NOT_SAME_DEVICE
+    } else {
+        EXDEV
+    };
+    matches!(
+        err.kind(),
+        ErrorKind::CrossesDevices | ErrorKind::PermissionDenied
+    ) || err.raw_os_error() == Some(cross_device_code)
+}
+
+/// Windows reports a file held open elsewhere as access denied or a sharing/lock violation;
+/// those usually clear within moments, so the rename is retried before copying.
+fn is_transient_rename_error(err: &std::io::Error) -> bool {
+    const ERROR_SHARING_VIOLATION: i32 = 32;
+    const ERROR_LOCK_VIOLATION: i32 = 33;
+    cfg!(windows)
+        && (err.kind() == ErrorKind::PermissionDenied
+            || matches!(
+                err.raw_os_error(),
+                Some(ERROR_SHARING_VIOLATION | ERROR_LOCK_VIOLATION)
+            ))
+}
+
+fn copy_then_remove(from: &Path, to: &Path) -> Result<()> {
+    let existed = to.exists();
+    if let Err(err) = fs::copy(from, to) {
+        if !existed {
+            // Never leave a partial copy that a later run could mistake for the moved file.
+            let _ = fs::remove_file(to);
+        }
+        return Err(err)
+            .with_context(|| format!("failed to copy {} to {}", from.display(), to.display()));
+    }
+    match fs::remove_file(from) {
+        Ok(()) => Ok(()),
+        #[cfg(windows)]
+        Err(err) if err.kind() == ErrorKind::PermissionDenied => {
+            // Windows refuses to delete read-only files; clear the attribute and retry once.
+            let mut perms = fs::metadata(from)
+                .with_context(|| format!("failed to stat {}", from.display()))?
+                .permissions();
+            perms.set_readonly(false);
+            fs::set_permissions(from, perms)
+                .and_then(|_| fs::remove_file(from))
+                .with_context(|| format!("failed to remove {}", from.display()))
+        }
+        Err(err) => Err(err).with_context(|| format!("failed to remove {}", from.display())),
+    }
+}
+
+/// Ledger form of a path: `/` separators and no Windows verbatim prefix, so rows written on
+/// Windows compare equal to paths computed later and stay readable by sync peers. POSIX paths
+/// are returned unchanged.
+pub fn portable_path_str(raw: &str) -> String {
+    let raw = if let Some(rest) = raw.strip_prefix(r"\\?\UNC\") {
+        format!(r"\\{rest}")
+    } else {
+        raw.strip_prefix(r"\\?\").unwrap_or(raw).to_string()
+    };
+    let bytes = raw.as_bytes();
+    let windows_style = cfg!(windows)
+        || raw.starts_with(r"\\")
+        || (bytes.len() >= 3
+            && bytes[0].is_ascii_alphabetic()
+            && bytes[1] == b':'
+            && matches!(bytes[2], b'\\' | b'/'));
+    if windows_style {
+        raw.replace('\\', "/")
+    } else {
+        raw
+    }
+}
+
+pub fn portable_path_string(path: &Path) -> String {
+    portable_path_str(&path.display().to_string())
+}
+
+fn normalize_record_paths(record: &mut ArchiveRecord) {
+    record.source_path = portable_path_str(&record.source_path);
+    record.archive_path = portable_path_str(&record.archive_path);
+    if let Some(projection) = record.projection_path.as_mut() {
+        *projection = portable_path_str(projection);
+    }
+    if let Some(newer) = record.superseded_by.as_mut() {
+        *newer = portable_path_str(newer);
+    }
+    if let Some(original) = record.duplicate_of.as_mut() {
+        *original = portable_path_str(original);
+    }
+}
+
+pub const PREFIX_HASH_STRIDE: u64 = 64 * 1024;
+
+#[derive(Debug, Clone, Default)]
+struct PrefixHashes {
+    total_bytes: u64,
+    stride: Vec<String>,
+    /// Hash of the prefix ending at each requested length that fits in the file.
+    at_lengths: BTreeMap<u64, String>,
+}
+
+/// Streams `path` once through a rolling SHA-256, snapshotting the digest at every stride
+/// boundary and at each of `lengths`.
+fn rolling_prefix_hashes(path: &Path, lengths: &BTreeSet<u64>) -> Result<PrefixHashes> {
+    use std::io::Read;
+    let mut file =
+        fs::File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+    let mut cuts = lengths.iter().copied().filter(|len| *len > 0).peekable();
+    let mut hasher = Sha256::new();
+    let mut out = PrefixHashes::default();
+    let mut next_stride = PREFIX_HASH_STRIDE;
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        let read = file
+            .read(&mut buf)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        if read == 0 {
+            break;
+        }
+        let mut chunk = &buf[..read];
+        while !chunk.is_empty() {
+            let next_cut = cuts.peek().copied().unwrap_or(u64::MAX).min(next_stride);
+            let take = (next_cut - out.total_bytes).min(chunk.len() as u64) as usize;
+            hasher.update(&chunk[..take]);
+            out.total_bytes += take as u64;
+            chunk = &chunk[take..];
+            if out.total_bytes == next_stride {
+                out.stride.push(format!("{:x}", hasher.clone().finalize()));
+                next_stride += PREFIX_HASH_STRIDE;
+            }
+            while cuts.peek() == Some(&out.total_bytes) {
+                out.at_lengths
+                    .insert(out.total_bytes, format!("{:x}", hasher.clone().finalize()));
+                cuts.next();
             }
         }
     }
+    Ok(out)
 }
 
+/// Whether `candidate` (an older archive of the same session) is a strict prefix of the
+/// snapshot described by `current`; stride hashes reject mismatches before the exact check.
+fn is_superseded_by(
+    candidate: &ArchiveRecord,
+    candidate_bytes: u64,
+    current: &PrefixHashes,
+) -> bool {
+    if candidate_bytes == 0 || candidate_bytes >= current.total_bytes {
+        return false;
+    }
+    let shared = candidate.prefix_hashes.len().min(current.stride.len());
+    if candidate.prefix_hashes[..shared] != current.stride[..shared] {
+        return false;
+    }
+    current
+        .at_lengths
+        .get(&candidate_bytes)
+        .is_some_and(|hash| *hash == candidate.content_hash)
+}
+
+/// Follows `superseded_by` links to the newest complete archive of `archive_path`.
+pub fn newest_archive_version(records: &[ArchiveRecord], archive_path: &str) -> String {
+    let by_path = records
+        .iter()
+        .map(|r| (r.archive_path.as_str(), r))
+        .collect::<BTreeMap<_, _>>();
+    let mut current = archive_path;
+    let mut seen = BTreeSet::new();
+    while seen.insert(current)
+        && let Some(next) = by_path
+            .get(current)
+            .and_then(|r| r.superseded_by.as_deref())
+    {
+        current = next;
+    }
+    current.to_string()
+}
+
+/// Earliest archive of another session whose conversation simhash is within `max_distance`
+/// bits of `hash`. Rows already marked as duplicates are skipped so chains stay one hop.
+fn find_duplicate_archive<'a>(
+    records: &'a [ArchiveRecord],
+    session_id: &str,
+    hash: u64,
+    max_distance: u32,
+) -> Option<&'a ArchiveRecord> {
+    records
+        .iter()
+        .filter(|r| r.session_id != session_id && !r.private && r.duplicate_of.is_none())
+        .filter_map(|r| {
+            let other = simhash::parse_simhash(r.projection_simhash.as_deref()?)?;
+            let distance = simhash::distance(hash, other);
+            (distance <= max_distance).then_some((distance, r.created_at_epoch_secs, r))
+        })
+        .min_by_key(|(distance, created_at, _)| (*distance, *created_at))
+        .map(|(_, _, r)| r)
+}
+
+/// Sets `duplicate_of` on rows whose conversation repeats an earlier row from another
+/// session; returns how many rows were marked.
+fn mark_duplicate_archives(records: &mut [ArchiveRecord], max_distance: u32) -> usize {
+    let mut marked = 0;
+    for idx in 0..records.len() {
+        let record = &records[idx];
+        if record.duplicate_of.is_some() || record.private {
+            continue;
+        }
+        let Some(hash) = record
+            .projection_simhash
+            .as_deref()
+            .and_then(simhash::parse_simhash)
+        else {
+            continue;
+        };
+        if let Some(original) =
+            find_duplicate_archive(&records[..idx], &record.session_id, hash, max_distance)
+                .map(|r| r.archive_path.clone())
+        {
+            records[idx].duplicate_of = Some(original);
+            marked += 1;
+        }
+    }
+    marked
+}
+
+/// The archive a `duplicate_of` row collapses into, resolved to its newest version; `None`
+/// when `archive_path` is not a duplicate or its original has left the ledger.
+pub fn duplicate_canonical_archive(
+    records: &[ArchiveRecord],
+    archive_path: &str,
+) -> Option<String> {
+    let original = records
+        .iter()
+        .filter(|r| r.archive_path == archive_path)
+        .find_map(|r| r.duplicate_of.as_deref())?;
+    records
+        .iter()
+        .any(|r| r.archive_path == original && !r.private)
+        .then(|| newest_archive_version(records, original))
+}
+
+/// SHA-256 of the file's contents; gzip session files hash decompressed, like their archive.
 fn file_hash(path: &Path) -> Result<String> {
-    let bytes = fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+    let bytes = read_session_bytes(path)?;
     let mut hasher = Sha256::new();
     hasher.update(&bytes);
     Ok(format!("{:x}", hasher.finalize()))
@@ -210,8 +729,9 @@ fn read_ledger(path: &Path) -> Result<Vec<ArchiveRecord>> {
         if trimmed.is_empty() {
             continue;
         }
-        let entry: ArchiveRecord = serde_json::from_str(trimmed)
+        let mut entry: ArchiveRecord = serde_json::from_str(trimmed)
             .with_context(|| format!("failed to parse ledger line in {}", path.display()))?;
+        normalize_record_paths(&mut entry);
         out.push(entry);
     }
     Ok(out)
@@ -232,6 +752,48 @@ fn truncate_preview(text: &str, max: usize) -> String {
     }
 }
 
+/// Raw-archive position of one timeline row or search capsule: `[line, byte_offset]`, with
+/// `null` parts when the archive could not be located (legacy or whole-document sources).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProjectionLineAnchor(pub Option<usize>, pub Option<u64>);
+
+impl ProjectionLineAnchor {
+    fn for_entry(entry: &crate::moon::distill::ProjectionEntry) -> Self {
+        Self(entry.source_line, entry.source_byte_offset)
+    }
+
+    pub fn line(&self) -> Option<usize> {
+        self.0
+    }
+
+    pub fn byte_offset(&self) -> Option<u64> {
+        self.1
+    }
+}
+
+/// `line_anchors` frontmatter: `timeline[i]` anchors timeline row `i + 1`, `capsules[i]` the
+/// `i`-th bullet under `## Search Capsules`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProjectionLineAnchors {
+    #[serde(default)]
+    pub timeline: Vec<ProjectionLineAnchor>,
+    #[serde(default)]
+    pub capsules: Vec<ProjectionLineAnchor>,
+}
+
+/// Reads `line_anchors` from projection frontmatter; `None` for projections written before
+/// anchors were recorded.
+pub fn parse_projection_line_anchors(markdown: &str) -> Option<ProjectionLineAnchors> {
+    let mut lines = markdown.lines();
+    if lines.next()?.trim() != "---" {
+        return None;
+    }
+    lines
+        .take_while(|line| line.trim() != "---")
+        .find_map(|line| line.strip_prefix("line_anchors:"))
+        .and_then(|raw| serde_json::from_str(raw.trim()).ok())
+}
+
 fn render_search_capsule(entry: &crate::moon::distill::ProjectionEntry) -> Option<String> {
     let mut parts = Vec::new();
     if !entry.content.trim().is_empty() {
@@ -266,6 +828,48 @@ fn render_search_capsule(entry: &crate::moon::distill::ProjectionEntry) -> Optio
     }
 }
 
+/// A time range in UTC and in the residential timezone, in the projection's dual format.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimeRangeLabels {
+    pub utc: String,
+    pub local: String,
+    pub timezone: String,
+}
+
+impl TimeRangeLabels {
+    pub fn new(
+        start_utc: chrono::DateTime<chrono::Utc>,
+        end_utc: chrono::DateTime<chrono::Utc>,
+        tz: Tz,
+    ) -> Self {
+        let start_local = start_utc.with_timezone(&tz);
+        let end_local = end_utc.with_timezone(&tz);
+        Self {
+            utc: format!(
+                "{} — {}",
+                start_utc.format("%Y-%m-%dT%H:%M:%SZ"),
+                end_utc.format("%Y-%m-%dT%H:%M:%SZ")
+            ),
+            local: format!(
+                "{} — {}",
+                start_local.format("%Y-%m-%dT%H:%M:%S%:z"),
+                end_local.format("%Y-%m-%dT%H:%M:%S%:z")
+            ),
+            timezone: tz.name().to_string(),
+        }
+    }
+
+    /// `None` when `start_epoch_secs` is out of chrono's range; a missing end collapses to the start.
+    pub fn from_epochs(start_epoch_secs: u64, end_epoch_secs: Option<u64>, tz: Tz) -> Option<Self> {
+        use chrono::{TimeZone, Utc};
+        let start = Utc.timestamp_opt(start_epoch_secs as i64, 0).single()?;
+        let end = end_epoch_secs
+            .and_then(|t| Utc.timestamp_opt(t as i64, 0).single())
+            .unwrap_or(start);
+        Some(Self::new(start, end, tz))
+    }
+}
+
 fn render_projection_markdown_v2(
     session_id: &str,
     source_path: &Path,
@@ -273,8 +877,9 @@ fn render_projection_markdown_v2(
     content_hash: &str,
     created_at_epoch_secs: u64,
     data: &ProjectionData,
+    tz: Tz,
 ) -> String {
-    use chrono::{DateTime, Local, TimeZone, Utc};
+    use chrono::{DateTime, TimeZone, Utc};
     const TIMELINE_ENTRY_LIMIT: usize = 400;
     const SEARCH_CAPSULE_LIMIT: usize = 1_600;
 
@@ -306,24 +911,46 @@ fn render_projection_markdown_v2(
         .and_then(|t| Utc.timestamp_opt(t as i64, 0).single())
         .unwrap_or(start_utc);
 
-    let local_offset =
-        std::env::var("MOON_LOCAL_TIMEZONE").unwrap_or_else(|_| Local::now().offset().to_string());
-
-    let start_local: DateTime<Local> = start_utc.with_timezone(&Local);
-    let end_local: DateTime<Local> = end_utc.with_timezone(&Local);
+    let start_local: DateTime<Tz> = start_utc.with_timezone(&tz);
+    let end_local: DateTime<Tz> = end_utc.with_timezone(&tz);
+    let range = TimeRangeLabels::new(start_utc, end_utc, tz);
 
+    out.push_str(&format!("time_range_utc: \"{}\"\n", range.utc));
+    out.push_str(&format!("time_range_local: \"{}\"\n", range.local));
     out.push_str(&format!(
-        "time_range_utc: \"{} — {}\"\n",
-        start_utc.format("%Y-%m-%dT%H:%M:%SZ"),
-        end_utc.format("%Y-%m-%dT%H:%M:%SZ")
-    ));
-    out.push_str(&format!(
-        "time_range_local: \"{} — {}\"\n",
-        start_local.format("%Y-%m-%dT%H:%M:%S%:z"),
-        end_local.format("%Y-%m-%dT%H:%M:%S%:z")
+        "local_timezone: {}\n",
+        yaml_quote(&range.timezone)
     ));
-    out.push_str(&format!("local_timezone: {}\n", yaml_quote(&local_offset)));
-    out.push_str(&format!("message_count: {}\n", data.entries.len()));
+    out.push_str(&format!("message_count: {}\n", data.message_count));
+    if data.sample_stride > 1 {
+        out.push_str(&format!(
+            "projection_sample_stride: {}\n",
+            data.sample_stride
+        ));
+    }
+    let capsules = data
+        .entries
+        .iter()
+        .filter_map(|entry| render_search_capsule(entry).map(|line| (line, entry)))
+        .take(SEARCH_CAPSULE_LIMIT)
+        .collect::<Vec<_>>();
+    if data.entries.iter().any(|entry| entry.source_line.is_some()) {
+        let anchors = ProjectionLineAnchors {
+            timeline: data
+                .entries
+                .iter()
+                .take(TIMELINE_ENTRY_LIMIT)
+                .map(ProjectionLineAnchor::for_entry)
+                .collect(),
+            capsules: capsules
+                .iter()
+                .map(|(_, entry)| ProjectionLineAnchor::for_entry(entry))
+                .collect(),
+        };
+        if let Ok(encoded) = serde_json::to_string(&anchors) {
+            out.push_str(&format!("line_anchors: {encoded}\n"));
+        }
+    }
     out.push_str(&format!(
         "filtered_noise_count: {}\n",
         data.filtered_noise_count
@@ -345,13 +972,13 @@ fn render_projection_markdown_v2(
         "> Session: {}–{} {} ({}–{} UTC)\n",
         start_local.format("%Y-%m-%d %H:%M"),
         end_local.format("%H:%M"),
-        local_offset,
+        range.timezone,
         start_utc.format("%Y-%m-%d %H:%M"),
         end_utc.format("%H:%M")
     ));
     out.push_str(&format!(
         "> Messages: {} | Noise filtered: {} | Timeline rows: up to {} | Tools used: {}\n\n",
-        data.entries.len(),
+        data.message_count,
         data.filtered_noise_count,
         TIMELINE_ENTRY_LIMIT,
         data.tool_calls.join(", ")
@@ -373,7 +1000,7 @@ fn render_projection_markdown_v2(
             .and_then(|t| Utc.timestamp_opt(t as i64, 0).single())
             .unwrap_or(last_known_ts_utc);
         last_known_ts_utc = ts_utc;
-        let ts_local: DateTime<Local> = ts_utc.with_timezone(&Local);
+        let ts_local: DateTime<Tz> = ts_utc.with_timezone(&tz);
         let time_str_utc = ts_utc.format("%H:%M:%SZ").to_string();
         let time_str_local = ts_local.format("%H:%M:%S").to_string();
 
@@ -457,19 +1084,13 @@ fn render_projection_markdown_v2(
 
     out.push_str("## Search Capsules\n");
     out.push_str("<!-- High-recall lexical anchors for QMD exact/keyword retrieval -->\n");
-    let mut capsule_count = 0usize;
-    for entry in &data.entries {
-        let Some(line) = render_search_capsule(entry) else {
-            continue;
-        };
-        out.push_str(&line);
-        capsule_count += 1;
-        if capsule_count >= SEARCH_CAPSULE_LIMIT {
-            out.push_str("- [search capsules truncated]\n");
-            break;
-        }
+    for (line, _) in &capsules {
+        out.push_str(line);
     }
-    if capsule_count == 0 {
+    if capsules.len() >= SEARCH_CAPSULE_LIMIT {
+        out.push_str("- [search capsules truncated]\n");
+    }
+    if capsules.is_empty() {
         out.push_str("- None\n");
     }
     out.push('\n');
@@ -497,6 +1118,7 @@ fn render_projection_markdown_v2(
 struct ProjectionWriteOutcome {
     path: PathBuf,
     filtered_noise_count: usize,
+    simhash: Option<u64>,
 }
 
 fn write_archive_projection(
@@ -505,15 +1127,26 @@ fn write_archive_projection(
     archive_path: &Path,
     content_hash: &str,
     created_at_epoch_secs: u64,
+    recorded_anchors: &[CompactionAnchor],
+    tz: Tz,
 ) -> Result<ProjectionWriteOutcome> {
     let projection_path = projection_path_for_archive_path(archive_path);
     let archive_path_str = archive_path.display().to_string();
-    let proj_data = extract_projection_data(&archive_path_str).with_context(|| {
+    let mut proj_data = extract_projection_data(&archive_path_str).with_context(|| {
         format!(
             "failed to extract projection data from {}",
             archive_path.display()
         )
     })?;
+    for anchor in recorded_anchors {
+        if !proj_data
+            .compaction_anchors
+            .iter()
+            .any(|existing| existing.note == anchor.note)
+        {
+            proj_data.compaction_anchors.push(anchor.clone());
+        }
+    }
 
     let markdown = render_projection_markdown_v2(
         session_id,
@@ -522,6 +1155,7 @@ fn write_archive_projection(
         content_hash,
         created_at_epoch_secs,
         &proj_data,
+        tz,
     );
 
     if let Some(parent) = projection_path.parent() {
@@ -530,9 +1164,25 @@ fn write_archive_projection(
     }
     fs::write(&projection_path, markdown)
         .with_context(|| format!("failed to write {}", projection_path.display()))?;
+    let filtered_noise_count = proj_data.filtered_noise_count;
+    let simhash = simhash::projection_simhash(&proj_data);
+    if load_config().is_ok_and(|cfg| cfg.projection.json_sidecar) {
+        let sidecar_path = projection_sidecar_path(&projection_path);
+        let sidecar = ProjectionSidecar {
+            session_id: session_id.to_string(),
+            source_path: portable_path_string(source_path),
+            archive_path: portable_path_string(archive_path),
+            content_hash: content_hash.to_string(),
+            created_at_epoch_secs,
+            projection: proj_data,
+        };
+        fs::write(&sidecar_path, serde_json::to_string_pretty(&sidecar)?)
+            .with_context(|| format!("failed to write {}", sidecar_path.display()))?;
+    }
     Ok(ProjectionWriteOutcome {
         path: projection_path,
-        filtered_noise_count: proj_data.filtered_noise_count,
+        filtered_noise_count,
+        simhash,
     })
 }
 
@@ -570,12 +1220,19 @@ fn write_ledger(path: &Path, records: &[ArchiveRecord]) -> Result<()> {
     Ok(())
 }
 
-pub fn normalize_archive_layout(paths: &MoonPaths) -> Result<ArchiveLayoutMigrationOutcome> {
+/// Moves ledger archives into `raw/` and projections into `mlib/`, resuming from the persisted
+/// cursor; `progress(done, total)` fires at every chunk checkpoint and once at the end.
+pub fn normalize_archive_layout(
+    paths: &MoonPaths,
+    opts: &MigrationRunOptions,
+    progress: &mut dyn FnMut(usize, usize),
+) -> Result<ArchiveLayoutMigrationOutcome> {
     let ledger = ledger_path(paths);
     if !ledger.exists() {
         return Ok(ArchiveLayoutMigrationOutcome::default());
     }
 
+    let _lease = lease::acquire(&ledger, "archive layout migration")?;
     let mut records = read_ledger(&ledger)?;
     if records.is_empty() {
         return Ok(ArchiveLayoutMigrationOutcome::default());
@@ -590,8 +1247,28 @@ pub fn normalize_archive_layout(paths: &MoonPaths) -> Result<ArchiveLayoutMigrat
 
     let mut out = ArchiveLayoutMigrationOutcome::default();
     let mut changed = false;
-
-    for record in &mut records {
+    let mut cursor = load_migration_cursor(paths)?;
+    let total = records.len();
+    out.total = total;
+    if cursor.layout_done {
+        out.resumed_from = total;
+        return Ok(out);
+    }
+    let (start, end) = migration_window(cursor.layout_next, total, opts);
+    out.resumed_from = start;
+
+    for idx in start..end {
+        if idx > start && (idx - start).is_multiple_of(MIGRATION_CHUNK_SIZE) {
+            if changed {
+                write_ledger(&ledger, &records)?;
+                out.ledger_updated = true;
+                changed = false;
+            }
+            cursor.layout_next = idx;
+            save_migration_cursor(paths, &cursor)?;
+            progress(idx, total);
+        }
+        let record = &mut records[idx];
         out.scanned += 1;
 
         let old_archive = PathBuf::from(&record.archive_path);
@@ -605,7 +1282,12 @@ pub fn normalize_archive_layout(paths: &MoonPaths) -> Result<ArchiveLayoutMigrat
             continue;
         }
 
-        let target_archive = raw_dir.join(file_name);
+        // A collection's archives already sit in their own `raw/<collection>/`.
+        let target_archive = if old_archive.starts_with(&raw_dir) {
+            old_archive.clone()
+        } else {
+            raw_dir.join(file_name)
+        };
         if target_archive != old_archive {
             if target_archive.exists() {
                 let from_hash = file_hash(&old_archive)?;
@@ -622,7 +1304,7 @@ pub fn normalize_archive_layout(paths: &MoonPaths) -> Result<ArchiveLayoutMigrat
             }
 
             let old_archive_str = record.archive_path.clone();
-            let new_archive_str = target_archive.display().to_string();
+            let new_archive_str = portable_path_string(&target_archive);
             if old_archive_str != new_archive_str {
                 record.archive_path = new_archive_str.clone();
                 out.path_rewrites.insert(old_archive_str, new_archive_str);
@@ -648,11 +1330,15 @@ pub fn normalize_archive_layout(paths: &MoonPaths) -> Result<ArchiveLayoutMigrat
 
         if let Some(old_projection) = old_projection {
             if old_projection != new_projection {
+                let old_sidecar = projection_sidecar_path(&old_projection);
+                if old_sidecar.exists() {
+                    move_projection_file(&old_sidecar, &projection_sidecar_path(&new_projection))?;
+                }
                 move_projection_file(&old_projection, &new_projection)?;
                 out.moved += 1;
             }
 
-            let projection_str = new_projection.display().to_string();
+            let projection_str = portable_path_string(&new_projection);
             if record.projection_path.as_deref() != Some(projection_str.as_str()) {
                 record.projection_path = Some(projection_str);
                 changed = true;
@@ -663,6 +1349,21 @@ pub fn normalize_archive_layout(paths: &MoonPaths) -> Result<ArchiveLayoutMigrat
         }
     }
 
+    progress(end, total);
+    if changed {
+        write_ledger(&ledger, &records)?;
+        out.ledger_updated = true;
+    }
+    if end < total {
+        cursor.layout_next = end;
+        save_migration_cursor(paths, &cursor)?;
+        out.next_cursor = Some(end);
+        return Ok(out);
+    }
+    cursor.layout_next = 0;
+    cursor.layout_done = true;
+    save_migration_cursor(paths, &cursor)?;
+
     if raw_dir.exists() {
         for entry in fs::read_dir(&raw_dir)? {
             let path = entry?.path();
@@ -711,23 +1412,23 @@ pub fn normalize_archive_layout(paths: &MoonPaths) -> Result<ArchiveLayoutMigrat
         }
     }
 
-    if changed {
-        write_ledger(&ledger, &records)?;
-        out.ledger_updated = true;
-    }
-
     Ok(out)
 }
 
+/// Builds missing projections (or rebuilds all with `reproject`), resuming from the persisted
+/// cursor; `progress(done, total)` fires at every chunk checkpoint and once at the end.
 pub fn backfill_archive_projections(
     paths: &MoonPaths,
     reproject: bool,
+    opts: &MigrationRunOptions,
+    progress: &mut dyn FnMut(usize, usize),
 ) -> Result<ProjectionBackfillOutcome> {
     let ledger = ledger_path(paths);
     if !ledger.exists() {
         return Ok(ProjectionBackfillOutcome::default());
     }
 
+    let _lease = lease::acquire(&ledger, "projection backfill")?;
     let mut records = read_ledger(&ledger)?;
     if records.is_empty() {
         return Ok(ProjectionBackfillOutcome::default());
@@ -735,18 +1436,40 @@ pub fn backfill_archive_projections(
 
     let mut out = ProjectionBackfillOutcome::default();
     let mut changed = false;
+    let tz = resolve_residential_tz();
 
-    let mut tracked_archives = BTreeSet::new();
     let mlib_dir = mlib_archives_dir(paths);
     fs::create_dir_all(&mlib_dir)
         .with_context(|| format!("failed to create {}", mlib_dir.display()))?;
 
-    for record in &mut records {
+    let mut cursor = load_migration_cursor(paths)?;
+    let resume_at = if cursor.projection_reproject == reproject {
+        cursor.projection_next
+    } else {
+        0
+    };
+    let total = records.len();
+    let (start, end) = migration_window(resume_at, total, opts);
+    out.total = total;
+    out.resumed_from = start;
+    cursor.projection_reproject = reproject;
+
+    for idx in start..end {
+        if idx > start && (idx - start).is_multiple_of(MIGRATION_CHUNK_SIZE) {
+            if changed {
+                write_ledger(&ledger, &records)?;
+                out.ledger_updated = true;
+                changed = false;
+            }
+            cursor.projection_next = idx;
+            save_migration_cursor(paths, &cursor)?;
+            progress(idx, total);
+        }
+        let record = &mut records[idx];
         out.scanned += 1;
-        tracked_archives.insert(record.archive_path.clone());
 
         let archive_path = Path::new(&record.archive_path);
-        if !archive_path.exists() {
+        if record.private || !archive_path.exists() {
             continue;
         }
         let expected_projection = projection_path_for_archive_path(archive_path);
@@ -782,7 +1505,7 @@ pub fn backfill_archive_projections(
                         move_file(&existing, &expected_projection)?;
                     }
                 }
-                let normalized = expected_projection.display().to_string();
+                let normalized = portable_path_string(&expected_projection);
                 if record.projection_path.as_deref() != Some(normalized.as_str()) {
                     record.projection_path = Some(normalized);
                     changed = true;
@@ -791,17 +1514,46 @@ pub fn backfill_archive_projections(
             }
         }
 
+        let previous = if reproject {
+            fs::read_to_string(&expected_projection).ok()
+        } else {
+            None
+        };
         match write_archive_projection(
             &record.session_id,
             Path::new(&record.source_path),
             archive_path,
             &record.content_hash,
             record.created_at_epoch_secs,
+            &record.compaction_anchors,
+            tz,
         ) {
             Ok(outcome) => {
                 out.created += 1;
-                record.projection_path = Some(outcome.path.display().to_string());
+                if let Some(previous) = previous {
+                    let current = fs::read_to_string(&outcome.path).unwrap_or_default();
+                    if current == previous {
+                        out.unchanged += 1;
+                    } else {
+                        let mut diff = diff_projection_markdown(&outcome.path, &previous, &current);
+                        if opts.keep_previous {
+                            let prev_path = previous_projection_path(paths, &outcome.path);
+                            if let Some(parent) = prev_path.parent() {
+                                fs::create_dir_all(parent).with_context(|| {
+                                    format!("failed to create {}", parent.display())
+                                })?;
+                            }
+                            fs::write(&prev_path, &previous).with_context(|| {
+                                format!("failed to write {}", prev_path.display())
+                            })?;
+                            diff.previous_path = Some(prev_path.display().to_string());
+                        }
+                        out.diffs.push(diff);
+                    }
+                }
+                record.projection_path = Some(portable_path_string(&outcome.path));
                 record.projection_filtered_noise_count = Some(outcome.filtered_noise_count);
+                record.projection_simhash = outcome.simhash.map(simhash::format_simhash);
                 changed = true;
             }
             Err(_) => {
@@ -810,14 +1562,34 @@ pub fn backfill_archive_projections(
         }
     }
 
+    progress(end, total);
+    if end == total
+        && let Ok(cfg) = load_config()
+        && cfg.projection.duplicate_detection
+    {
+        out.duplicates_marked =
+            mark_duplicate_archives(&mut records, cfg.projection.duplicate_max_distance as u32);
+        changed |= out.duplicates_marked > 0;
+    }
+    if changed {
+        write_ledger(&ledger, &records)?;
+        out.ledger_updated = true;
+    }
+    if end < total {
+        cursor.projection_next = end;
+        save_migration_cursor(paths, &cursor)?;
+        out.next_cursor = Some(end);
+        return Ok(out);
+    }
+    save_migration_cursor(paths, &MigrationCursor::default())?;
+
+    let tracked_archives = records
+        .iter()
+        .map(|record| record.archive_path.clone())
+        .collect::<BTreeSet<_>>();
     let raw_dir = raw_archives_dir(paths);
     if raw_dir.exists() {
-        for entry in fs::read_dir(&raw_dir)? {
-            let path = entry?.path();
-            if !path.is_file() {
-                continue;
-            }
-
+        for path in raw_archive_files(&raw_dir)? {
             let Some(ext) = path.extension().and_then(|v| v.to_str()) else {
                 continue;
             };
@@ -825,7 +1597,7 @@ pub fn backfill_archive_projections(
                 continue;
             }
 
-            let archive_path = path.display().to_string();
+            let archive_path = portable_path_string(&path);
             if tracked_archives.contains(&archive_path) {
                 continue;
             }
@@ -856,6 +1628,8 @@ pub fn backfill_archive_projections(
                 &path,
                 &content_hash,
                 created_at_epoch_secs,
+                &[],
+                tz,
             ) {
                 Ok(_) => {
                     out.created += 1;
@@ -867,14 +1641,28 @@ pub fn backfill_archive_projections(
         }
     }
 
-    if changed {
-        write_ledger(&ledger, &records)?;
-        out.ledger_updated = true;
-    }
-
     Ok(out)
 }
 
+/// Files in `raw_dir` and in its per-collection subdirectories.
+fn raw_archive_files(raw_dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(raw_dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            for entry in fs::read_dir(&path)? {
+                let path = entry?.path();
+                if path.is_file() {
+                    files.push(path);
+                }
+            }
+        } else if path.is_file() {
+            files.push(path);
+        }
+    }
+    Ok(files)
+}
+
 pub fn remove_ledger_records(paths: &MoonPaths, archive_paths: &BTreeSet<String>) -> Result<usize> {
     if archive_paths.is_empty() {
         return Ok(0);
@@ -885,6 +1673,7 @@ pub fn remove_ledger_records(paths: &MoonPaths, archive_paths: &BTreeSet<String>
         return Ok(0);
     }
 
+    let _lease = lease::acquire(&ledger, "ledger remove")?;
     let existing = read_ledger(&ledger)?;
     let existing_len = existing.len();
     let kept = existing
@@ -900,10 +1689,377 @@ pub fn remove_ledger_records(paths: &MoonPaths, archive_paths: &BTreeSet<String>
     Ok(removed)
 }
 
+/// Stamps `provenance` on every ledger row for `archive_path`; `false` when none matched.
+pub fn record_distill_provenance(
+    paths: &MoonPaths,
+    archive_path: &str,
+    provenance: &DistillProvenance,
+) -> Result<bool> {
+    let ledger = ledger_path(paths);
+    if !ledger.exists() {
+        return Ok(false);
+    }
+    let _lease = lease::acquire(&ledger, "ledger distill")?;
+    let mut records = read_ledger(&ledger)?;
+    let mut matched = false;
+    for record in records
+        .iter_mut()
+        .filter(|record| record.archive_path == archive_path)
+    {
+        record.distill = Some(provenance.clone());
+        matched = true;
+    }
+    if matched {
+        write_ledger(&ledger, &records)?;
+    }
+    Ok(matched)
+}
+
+/// Sets or clears `distill_skip` on every ledger row for `archive_path`; `false` when none
+/// matched.
+pub fn set_distill_skip(paths: &MoonPaths, archive_path: &str, skip: bool) -> Result<bool> {
+    let ledger = ledger_path(paths);
+    if !ledger.exists() {
+        return Ok(false);
+    }
+    let _lease = lease::acquire(&ledger, "ledger skip-distill")?;
+    let mut records = read_ledger(&ledger)?;
+    let mut matched = false;
+    for record in records
+        .iter_mut()
+        .filter(|record| record.archive_path == archive_path)
+    {
+        record.distill_skip = skip;
+        matched = true;
+    }
+    if matched {
+        write_ledger(&ledger, &records)?;
+    }
+    Ok(matched)
+}
+
+/// Adds gateway-reported compaction summaries to every ledger row for `archive_path` and
+/// re-renders its projection with them. Returns the updated row, or `None` when no row matched
+/// or every anchor was already recorded.
+pub fn record_compaction_anchors(
+    paths: &MoonPaths,
+    archive_path: &str,
+    anchors: &[CompactionAnchor],
+) -> Result<Option<ArchiveRecord>> {
+    let ledger = ledger_path(paths);
+    if !ledger.exists() {
+        return Ok(None);
+    }
+    let _lease = lease::acquire(&ledger, "ledger compaction anchors")?;
+    let mut records = read_ledger(&ledger)?;
+    let mut updated = None;
+    for record in records
+        .iter_mut()
+        .filter(|record| record.archive_path == archive_path)
+    {
+        let mut added = false;
+        for anchor in anchors {
+            if !record
+                .compaction_anchors
+                .iter()
+                .any(|existing| existing.note == anchor.note)
+            {
+                record.compaction_anchors.push(anchor.clone());
+                added = true;
+            }
+        }
+        if added {
+            updated = Some(record.clone());
+        }
+    }
+    let Some(record) = updated else {
+        return Ok(None);
+    };
+    write_ledger(&ledger, &records)?;
+    if record.projection_path.is_some() && !record.private {
+        write_archive_projection(
+            &record.session_id,
+            Path::new(&record.source_path),
+            Path::new(&record.archive_path),
+            &record.content_hash,
+            record.created_at_epoch_secs,
+            &record.compaction_anchors,
+            resolve_residential_tz(),
+        )?;
+    }
+    Ok(Some(record))
+}
+
+/// Re-appends `record` unless the ledger already tracks its archive path.
+pub fn restore_ledger_record(paths: &MoonPaths, record: &ArchiveRecord) -> Result<bool> {
+    let ledger = ledger_path(paths);
+    let _lease = lease::acquire(&ledger, "ledger restore")?;
+    if ledger.exists()
+        && read_ledger(&ledger)?
+            .iter()
+            .any(|r| r.archive_path == record.archive_path)
+    {
+        return Ok(false);
+    }
+    append_ledger(&ledger, record)?;
+    Ok(true)
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct LedgerCompactOutcome {
+    pub before: usize,
+    pub kept: usize,
+    /// Records whose raw archive no longer exists.
+    pub missing: usize,
+    /// Earlier rows superseded by a later row for the same archive path.
+    pub superseded: usize,
+    pub history_path: PathBuf,
+}
+
+pub fn ledger_history_path(paths: &MoonPaths) -> PathBuf {
+    paths.archives_dir.join("ledger-history.jsonl")
+}
+
+/// Rewrites the ledger keeping only the latest row per archive whose file still exists; dropped
+/// rows are appended to [`ledger_history_path`] so nothing is discarded outright.
+pub fn compact_ledger(paths: &MoonPaths, dry_run: bool) -> Result<LedgerCompactOutcome> {
+    let ledger = ledger_path(paths);
+    let mut out = LedgerCompactOutcome {
+        history_path: ledger_history_path(paths),
+        ..LedgerCompactOutcome::default()
+    };
+    // Read, partition and rewrite under one lease so no concurrent ledger rewrite is lost.
+    let _lease = if dry_run {
+        None
+    } else {
+        Some(lease::acquire(&ledger, "ledger compact")?)
+    };
+    let records = read_ledger(&ledger)?;
+    out.before = records.len();
+
+    let mut latest_by_archive = BTreeMap::new();
+    for (idx, record) in records.iter().enumerate() {
+        latest_by_archive.insert(record.archive_path.clone(), idx);
+    }
+    let mut kept = Vec::new();
+    let mut dropped = Vec::new();
+    for (idx, record) in records.into_iter().enumerate() {
+        if latest_by_archive.get(&record.archive_path) != Some(&idx) {
+            out.superseded += 1;
+            dropped.push(record);
+        } else if !Path::new(&record.archive_path).exists() {
+            out.missing += 1;
+            dropped.push(record);
+        } else {
+            kept.push(record);
+        }
+    }
+    out.kept = kept.len();
+    if dry_run || dropped.is_empty() {
+        return Ok(out);
+    }
+
+    for record in &dropped {
+        append_ledger(&out.history_path, record)?;
+    }
+    write_ledger(&ledger, &kept)?;
+    Ok(out)
+}
+
+/// Appends `record`, first marking the `superseded` rows of `existing` as replaced by it.
+fn commit_ledger_record(
+    ledger: &Path,
+    record: &ArchiveRecord,
+    existing: &[ArchiveRecord],
+    superseded: &BTreeSet<usize>,
+) -> Result<()> {
+    let _lease = lease::acquire(ledger, "archive")?;
+    if !superseded.is_empty() {
+        let mut rewritten = read_ledger(ledger)?;
+        for older in superseded {
+            let older_path = &existing[*older].archive_path;
+            for row in rewritten
+                .iter_mut()
+                .filter(|r| &r.archive_path == older_path)
+            {
+                row.superseded_by = Some(record.archive_path.clone());
+            }
+        }
+        write_ledger(ledger, &rewritten)?;
+    }
+    append_ledger(ledger, record)
+}
+
+/// Finishes archiving a `[privacy]` channel: the raw copy is kept (sealed when configured) but
+/// no projection is written and qmd is not updated, so nothing reaches search or recall.
+fn archive_private(
+    paths: &MoonPaths,
+    privacy_cfg: &MoonPrivacyConfig,
+    pattern: &str,
+    mut record: ArchiveRecord,
+    existing: &[ArchiveRecord],
+    superseded: &BTreeSet<usize>,
+) -> Result<ArchivePipelineOutcome> {
+    let archive_path = PathBuf::from(&record.archive_path);
+    if privacy_cfg.encrypt {
+        if let Err(err) = privacy::encrypt_file_in_place(&archive_path) {
+            // Never leave a private channel readable on disk when sealing was requested.
+            let _ = fs::remove_file(&archive_path);
+            return Err(err.context(format!(
+                "private archive for session {} was not stored",
+                record.session_id
+            )));
+        }
+        record.encrypted = true;
+    }
+
+    let ledger = ledger_path(paths);
+    commit_ledger_record(&ledger, &record, existing, superseded)?;
+    let _ = audit::append_event(
+        paths,
+        "privacy",
+        "ok",
+        &format!(
+            "archived private session={} pattern={pattern} archive={} encrypted={} index=excluded",
+            record.session_id, record.archive_path, record.encrypted
+        ),
+        serde_json::json!({
+            "session": record.session_id,
+            "pattern": pattern,
+            "archive": record.archive_path,
+            "encrypted": record.encrypted,
+            "index": "excluded",
+        }),
+    );
+    hooks::fire(
+        paths,
+        HookEvent::PostArchive,
+        &[
+            ("session_id", &record.session_id),
+            ("source_path", &record.source_path),
+            ("archive_path", &record.archive_path),
+            ("projection_path", ""),
+            ("collection", &record.indexed_collection),
+            ("content_hash", &record.content_hash),
+        ],
+    );
+
+    Ok(ArchivePipelineOutcome {
+        record,
+        deduped: false,
+        ledger_path: ledger,
+    })
+}
+
+/// Dry-run counterpart of [`archive_and_index`]: reports the snapshot, projection and qmd work
+/// that would happen for `source` without writing anything.
+pub fn plan_archive_and_index(
+    paths: &MoonPaths,
+    source: &Path,
+    collection_name: &str,
+) -> Result<ArchivePlan> {
+    let ledger = ledger_path(paths);
+    let source_bytes = fs::metadata(source)
+        .with_context(|| format!("failed to stat {}", source.display()))?
+        .len();
+    let source_hash = file_hash(source)?;
+
+    if let Some(record) = read_ledger(&ledger)?
+        .into_iter()
+        .find(|r| r.content_hash == source_hash && r.source_path == portable_path_string(source))
+    {
+        let archive_path = PathBuf::from(&record.archive_path);
+        let projection_path = record
+            .projection_path
+            .as_deref()
+            .map(PathBuf::from)
+            .unwrap_or_else(|| projection_path_for_archive_path(&archive_path));
+        return Ok(ArchivePlan {
+            source_path: source.to_path_buf(),
+            source_bytes,
+            deduped: true,
+            archive_path,
+            projection_path,
+            projection_bytes_estimate: None,
+            projection_error: None,
+            qmd_operation: "none (source already archived)".to_string(),
+            ledger_path: ledger,
+        });
+    }
+
+    let cfg = load_config()?;
+    let subdir = cfg.collections.subdir(collection_name);
+    let archive_path =
+        planned_snapshot_path(&raw_archive_dir(&paths.archives_dir, subdir), source)?;
+    let projection_path = projection_path_for_archive_path(&archive_path);
+    if let Some(pattern) = privacy::private_pattern_for_source(paths, &cfg.privacy, source)? {
+        return Ok(ArchivePlan {
+            source_path: source.to_path_buf(),
+            source_bytes,
+            deduped: false,
+            archive_path,
+            projection_path,
+            projection_bytes_estimate: None,
+            projection_error: None,
+            qmd_operation: format!("none (private channel `{pattern}`)"),
+            ledger_path: ledger,
+        });
+    }
+    let session_id = session_file_stem(source).unwrap_or("session");
+    // The raw archive is a (decompressed) copy of the source, so project the source directly.
+    let (projection_bytes_estimate, projection_error) =
+        match extract_projection_data(&source.display().to_string()) {
+            Ok(data) => (
+                Some(
+                    render_projection_markdown_v2(
+                        session_id,
+                        source,
+                        &archive_path,
+                        &source_hash,
+                        epoch_now()?,
+                        &data,
+                        resolve_residential_tz(),
+                    )
+                    .len(),
+                ),
+                None,
+            ),
+            Err(err) => (None, Some(format!("{err:#}"))),
+        };
+
+    Ok(ArchivePlan {
+        source_path: source.to_path_buf(),
+        source_bytes,
+        deduped: false,
+        archive_path,
+        projection_path,
+        projection_bytes_estimate,
+        projection_error,
+        qmd_operation: qmd::describe_collection_sync(
+            &paths.qmd_bin,
+            &paths.archives_dir,
+            collection_name,
+            subdir,
+        ),
+        ledger_path: ledger,
+    })
+}
+
+/// When [`archive_and_index`] registers a new projection with qmd.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QmdIndexMode {
+    /// Sync the collection before returning, so the archive is searchable immediately.
+    Immediate,
+    /// Leave the sync to the caller; the watcher batches one per cycle. `indexed` then means the
+    /// projection is written and queued.
+    Deferred,
+}
+
 pub fn archive_and_index(
     paths: &MoonPaths,
     source: &Path,
     collection_name: &str,
+    index_mode: QmdIndexMode,
 ) -> Result<ArchivePipelineOutcome> {
     fs::create_dir_all(&paths.archives_dir)
         .with_context(|| format!("failed to create {}", paths.archives_dir.display()))?;
@@ -914,7 +2070,7 @@ pub fn archive_and_index(
 
     if let Some(record) = existing
         .iter()
-        .find(|r| r.content_hash == source_hash && r.source_path == source.display().to_string())
+        .find(|r| r.content_hash == source_hash && r.source_path == portable_path_string(source))
     {
         return Ok(ArchivePipelineOutcome {
             record: record.clone(),
@@ -923,20 +2079,74 @@ pub fn archive_and_index(
         });
     }
 
-    let write = write_snapshot(&paths.archives_dir, source)?;
+    let cfg = load_config()?;
+    let subdir = cfg.collections.subdir(collection_name);
+    let (privacy_cfg, projection_cfg) = (cfg.privacy, cfg.projection);
+    let private_pattern = privacy::private_pattern_for_source(paths, &privacy_cfg, source)?;
+    let write = write_snapshot(&raw_archive_dir(&paths.archives_dir, subdir), source)?;
     let archive_hash = file_hash(&write.archive_path)?;
-    let session_id = source
-        .file_stem()
-        .and_then(|s| s.to_str())
-        .unwrap_or("session")
-        .to_string();
+    let session_id = session_file_stem(source).unwrap_or("session").to_string();
+
+    // Older archives of this session predating `content_bytes` fall back to their file size.
+    let candidates = existing
+        .iter()
+        .enumerate()
+        .filter(|(_, r)| r.session_id == session_id && r.superseded_by.is_none())
+        .filter_map(|(idx, r)| {
+            r.content_bytes
+                .or_else(|| fs::metadata(&r.archive_path).ok().map(|m| m.len()))
+                .map(|len| (idx, len))
+        })
+        .collect::<Vec<_>>();
+    let prefix = rolling_prefix_hashes(
+        &write.archive_path,
+        &candidates.iter().map(|(_, len)| *len).collect(),
+    )?;
+    let new_archive_path = portable_path_string(&write.archive_path);
+    let superseded = candidates
+        .iter()
+        .filter(|(idx, len)| is_superseded_by(&existing[*idx], *len, &prefix))
+        .map(|(idx, _)| *idx)
+        .collect::<BTreeSet<_>>();
     let created_at_epoch_secs = epoch_now()?;
+    if let Some(pattern) = &private_pattern {
+        return archive_private(
+            paths,
+            &privacy_cfg,
+            pattern,
+            ArchiveRecord {
+                session_id,
+                source_path: portable_path_string(&write.source_path),
+                archive_path: new_archive_path,
+                projection_path: None,
+                projection_filtered_noise_count: None,
+                content_hash: archive_hash,
+                created_at_epoch_secs,
+                indexed_collection: collection_name.to_string(),
+                indexed: false,
+                content_bytes: Some(prefix.total_bytes),
+                prefix_hashes: prefix.stride,
+                superseded_by: None,
+                private: true,
+                encrypted: false,
+                distill: None,
+                compaction_anchors: Vec::new(),
+                projection_simhash: None,
+                duplicate_of: None,
+                distill_skip: false,
+            },
+            &existing,
+            &superseded,
+        );
+    }
     let projection_out = match write_archive_projection(
         &session_id,
         &write.source_path,
         &write.archive_path,
         &archive_hash,
         created_at_epoch_secs,
+        &[],
+        resolve_residential_tz(),
     ) {
         Ok(path) => Some(path),
         Err(err) => {
@@ -958,20 +2168,34 @@ pub fn archive_and_index(
     let projection_path = projection_out.as_ref().map(|out| out.path.clone());
     let projection_filtered_noise_count =
         projection_out.as_ref().map(|out| out.filtered_noise_count);
+    let projection_simhash = projection_out.as_ref().and_then(|out| out.simhash);
+    let duplicate_of = projection_simhash
+        .filter(|_| projection_cfg.duplicate_detection)
+        .and_then(|hash| {
+            find_duplicate_archive(
+                &existing,
+                &session_id,
+                hash,
+                projection_cfg.duplicate_max_distance as u32,
+            )
+        })
+        .map(|original| original.archive_path.clone());
 
     let mut indexed = projection_path.is_some();
-    if let Err(err) =
-        qmd::collection_add_or_update(&paths.qmd_bin, &paths.archives_dir, collection_name)
+    if index_mode == QmdIndexMode::Immediate
+        && let Err(err) = qmd::collection_add_or_update(
+            &paths.qmd_bin,
+            &paths.archives_dir,
+            collection_name,
+            subdir,
+        )
     {
         indexed = false;
         warn::emit(WarnEvent {
             code: "INDEX_FAILED",
             stage: "qmd-index",
             action: "archive-index",
-            session: source
-                .file_stem()
-                .and_then(|s| s.to_str())
-                .unwrap_or("session"),
+            session: session_file_stem(source).unwrap_or("session"),
             archive: &write.archive_path.display().to_string(),
             source: &write.source_path.display().to_string(),
             retry: "retry-next-cycle",
@@ -983,17 +2207,42 @@ pub fn archive_and_index(
 
     let record = ArchiveRecord {
         session_id,
-        source_path: write.source_path.display().to_string(),
-        archive_path: write.archive_path.display().to_string(),
-        projection_path: projection_path.map(|p| p.display().to_string()),
+        source_path: portable_path_string(&write.source_path),
+        archive_path: new_archive_path,
+        projection_path: projection_path.map(|p| portable_path_string(&p)),
         projection_filtered_noise_count,
         content_hash: archive_hash,
         created_at_epoch_secs,
         indexed_collection: collection_name.to_string(),
         indexed,
+        content_bytes: Some(prefix.total_bytes),
+        prefix_hashes: prefix.stride,
+        superseded_by: None,
+        private: false,
+        encrypted: false,
+        distill: None,
+        compaction_anchors: Vec::new(),
+        projection_simhash: projection_simhash.map(simhash::format_simhash),
+        duplicate_of,
+        distill_skip: superseded.iter().any(|idx| existing[*idx].distill_skip),
     };
 
-    append_ledger(&ledger, &record)?;
+    commit_ledger_record(&ledger, &record, &existing, &superseded)?;
+    hooks::fire(
+        paths,
+        HookEvent::PostArchive,
+        &[
+            ("session_id", &record.session_id),
+            ("source_path", &record.source_path),
+            ("archive_path", &record.archive_path),
+            (
+                "projection_path",
+                record.projection_path.as_deref().unwrap_or_default(),
+            ),
+            ("collection", &record.indexed_collection),
+            ("content_hash", &record.content_hash),
+        ],
+    );
 
     Ok(ArchivePipelineOutcome {
         record,
@@ -1001,3 +2250,320 @@ pub fn archive_and_index(
         ledger_path: ledger,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        ArchiveRecord, DistillProvenance, MigrationRunOptions, PREFIX_HASH_STRIDE,
+        ProjectionLineAnchor, compact_ledger, diff_projection_markdown,
+        duplicate_canonical_archive, file_hash, is_superseded_by, ledger_path,
+        mark_duplicate_archives, migration_window, newest_archive_version,
+        parse_projection_line_anchors, portable_path_str, read_ledger, record_distill_provenance,
+        rename_needs_copy_fallback, render_projection_markdown_v2, rolling_prefix_hashes,
+        write_ledger,
+    };
+    use crate::moon::distill::{ProjectionData, extract_projection_data};
+    use crate::moon::lease;
+    use crate::moon::paths::MoonPaths;
+    use std::collections::BTreeSet;
+    use std::fs;
+    use std::path::Path;
+    use tempfile::tempdir;
+
+    #[test]
+    fn portable_path_str_normalizes_windows_forms_and_keeps_posix_paths() {
+        assert_eq!(
+            portable_path_str(r"C:\Users\moon\archives\raw\a.jsonl"),
+            "C:/Users/moon/archives/raw/a.jsonl"
+        );
+        assert_eq!(
+            portable_path_str(r"\\?\C:\moon\ledger.jsonl"),
+            "C:/moon/ledger.jsonl"
+        );
+        assert_eq!(
+            portable_path_str(r"\\?\UNC\nas\share\a.jsonl"),
+            "//nas/share/a.jsonl"
+        );
+        assert_eq!(
+            portable_path_str("/home/moon/archives/raw/a.jsonl"),
+            "/home/moon/archives/raw/a.jsonl"
+        );
+    }
+
+    #[test]
+    fn read_ledger_normalizes_paths_written_on_windows() {
+        let tmp = tempdir().expect("tempdir");
+        let ledger = tmp.path().join("ledger.jsonl");
+        let row = serde_json::json!({
+            "session_id": "s1",
+            "source_path": r"C:\moon\sessions\s1.jsonl",
+            "archive_path": r"C:\moon\archives\raw\s1.jsonl",
+            "projection_path": r"C:\moon\archives\mlib\s1.md",
+            "content_hash": "abc",
+            "created_at_epoch_secs": 1,
+            "indexed_collection": "history",
+            "indexed": true,
+        });
+        fs::write(&ledger, format!("{row}\n")).expect("write ledger");
+
+        let records = read_ledger(&ledger).expect("read ledger");
+        assert_eq!(records[0].source_path, "C:/moon/sessions/s1.jsonl");
+        assert_eq!(records[0].archive_path, "C:/moon/archives/raw/s1.jsonl");
+        assert_eq!(
+            records[0].projection_path.as_deref(),
+            Some("C:/moon/archives/mlib/s1.md")
+        );
+    }
+
+    #[test]
+    fn rename_fallback_covers_raw_cross_device_codes() {
+        let code = if cfg!(windows) { 17 } else { 18 };
+        assert!(rename_needs_copy_fallback(
+            &std::io::Error::from_raw_os_error(code)
+        ));
+        assert!(!rename_needs_copy_fallback(&std::io::Error::from(
+            std::io::ErrorKind::NotFound
+        )));
+    }
+
+    #[test]
+    fn projection_renders_local_times_in_residential_timezone() {
+        let data = ProjectionData {
+            entries: Vec::new(),
+            tool_calls: Vec::new(),
+            keywords: Vec::new(),
+            topics: Vec::new(),
+            time_start_epoch: Some(1_700_000_000),
+            time_end_epoch: Some(1_700_000_600),
+            message_count: 0,
+            filtered_noise_count: 0,
+            truncated: false,
+            compaction_anchors: Vec::new(),
+            sample_stride: 1,
+        };
+        let markdown = render_projection_markdown_v2(
+            "s1",
+            Path::new("/tmp/s1.jsonl"),
+            Path::new("/tmp/raw/s1.jsonl"),
+            "hash",
+            1_700_000_000,
+            &data,
+            chrono_tz::Asia::Tokyo,
+        );
+        assert!(markdown.contains("time_range_local: \"2023-11-15T07:13:20+09:00"));
+        assert!(markdown.contains("local_timezone: 'Asia/Tokyo'"));
+        assert!(markdown.contains("> Session: 2023-11-15 07:13–07:23 Asia/Tokyo"));
+    }
+
+    #[test]
+    fn migration_window_resumes_and_limits() {
+        let unlimited = MigrationRunOptions::default();
+        let limited = MigrationRunOptions {
+            limit: Some(2),
+            ..MigrationRunOptions::default()
+        };
+        assert_eq!(migration_window(0, 5, &unlimited), (0, 5));
+        assert_eq!(migration_window(3, 5, &unlimited), (3, 5));
+        assert_eq!(migration_window(3, 5, &limited), (3, 5));
+        assert_eq!(migration_window(1, 5, &limited), (1, 3));
+        // A cursor past the end (ledger shrank) restarts the pass.
+        assert_eq!(migration_window(9, 5, &limited), (0, 2));
+    }
+
+    #[test]
+    fn projection_diff_reports_sections_and_message_delta() {
+        let previous = "---\nmessage_count: 4\n---\n## Timeline\n\n## Keywords\n";
+        let current = "---\nmessage_count: 6\n---\n## Timeline\n\n## Search Capsules\n";
+        let diff = diff_projection_markdown(Path::new("/tmp/mlib/s1.md"), previous, current);
+        assert_eq!(diff.sections_added, vec!["Search Capsules"]);
+        assert_eq!(diff.sections_removed, vec!["Keywords"]);
+        assert_eq!(diff.message_count_delta, 2);
+        assert!(diff.summary().contains("message_count_delta=+2"));
+    }
+
+    #[test]
+    fn projection_records_raw_line_anchors_for_timeline_and_capsules() {
+        let tmp = tempdir().expect("tempdir");
+        let raw = tmp.path().join("s1.jsonl");
+        let first = r#"{"timestamp":1700000000,"message":{"role":"user","content":[{"type":"text","text":"deploy the billing service"}]}}"#;
+        let second = r#"{"timestamp":1700000060,"message":{"role":"assistant","content":[{"type":"text","text":"billing service deployed to staging"}]}}"#;
+        fs::write(&raw, format!("{first}\n\n{second}\n")).expect("write raw");
+
+        let data = extract_projection_data(&raw.display().to_string()).expect("extract");
+        let markdown = render_projection_markdown_v2(
+            "s1",
+            Path::new("/tmp/s1.jsonl"),
+            &raw,
+            "hash",
+            1_700_000_000,
+            &data,
+            chrono_tz::UTC,
+        );
+        let anchors = parse_projection_line_anchors(&markdown).expect("line anchors");
+        let expected = vec![
+            ProjectionLineAnchor(Some(1), Some(0)),
+            ProjectionLineAnchor(Some(3), Some(first.len() as u64 + 2)),
+        ];
+        assert_eq!(anchors.timeline, expected);
+        assert_eq!(anchors.capsules, expected);
+        assert!(parse_projection_line_anchors("---\nmessage_count: 1\n---\n").is_none());
+    }
+
+    fn ledger_row(archive_path: &str, content_hash: &str) -> ArchiveRecord {
+        ArchiveRecord {
+            session_id: "s1".to_string(),
+            source_path: "/tmp/s1.jsonl".to_string(),
+            archive_path: archive_path.to_string(),
+            projection_path: None,
+            projection_filtered_noise_count: None,
+            content_hash: content_hash.to_string(),
+            created_at_epoch_secs: 1,
+            indexed_collection: "history".to_string(),
+            indexed: true,
+            content_bytes: None,
+            prefix_hashes: Vec::new(),
+            superseded_by: None,
+            private: false,
+            encrypted: false,
+            distill: None,
+            compaction_anchors: Vec::new(),
+            projection_simhash: None,
+            duplicate_of: None,
+            distill_skip: false,
+        }
+    }
+
+    #[test]
+    fn compact_ledger_keeps_a_rewrite_made_while_it_waited_for_the_lease() {
+        let tmp = tempdir().expect("tempdir");
+        let paths = MoonPaths::for_test(tmp.path());
+        fs::create_dir_all(&paths.archives_dir).expect("mkdir archives");
+        let live = paths.archives_dir.join("live.jsonl");
+        fs::write(&live, "{}\n").expect("write archive");
+        let live = live.to_string_lossy().to_string();
+        let ledger = ledger_path(&paths);
+        write_ledger(
+            &ledger,
+            &[ledger_row(&live, "old"), ledger_row(&live, "new")],
+        )
+        .expect("write ledger");
+
+        let held = lease::acquire(&ledger, "test rewrite").expect("lease");
+        let compacting = std::thread::spawn({
+            let paths = paths.clone();
+            move || compact_ledger(&paths, false)
+        });
+        std::thread::sleep(std::time::Duration::from_millis(300));
+        let mut rows = read_ledger(&ledger).expect("read ledger");
+        for row in &mut rows {
+            row.distill_skip = true;
+        }
+        write_ledger(&ledger, &rows).expect("rewrite ledger");
+        drop(held);
+
+        let out = compacting.join().expect("join").expect("compact");
+        assert_eq!((out.before, out.kept, out.superseded), (2, 1, 1));
+        let rows = read_ledger(&ledger).expect("read ledger");
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].content_hash, "new");
+        assert!(rows[0].distill_skip, "concurrent rewrite was reverted");
+    }
+
+    #[test]
+    fn rolling_prefix_hashes_detect_superset_snapshots() {
+        let tmp = tempdir().expect("tempdir");
+        let line = "{\"message\":{\"role\":\"user\",\"content\":\"hello\"}}\n";
+        let older_body = line.repeat((PREFIX_HASH_STRIDE as usize / line.len()) + 10);
+        let older = tmp.path().join("older.jsonl");
+        let newer = tmp.path().join("newer.jsonl");
+        let forked = tmp.path().join("forked.jsonl");
+        fs::write(&older, &older_body).expect("write older");
+        fs::write(&newer, format!("{older_body}{line}{line}")).expect("write newer");
+        fs::write(&forked, format!("x{older_body}")).expect("write forked");
+
+        let older_prefix = rolling_prefix_hashes(&older, &BTreeSet::new()).expect("older hashes");
+        assert_eq!(older_prefix.total_bytes, older_body.len() as u64);
+        assert_eq!(older_prefix.stride.len(), 1);
+        let mut record = ledger_row(&older.display().to_string(), &file_hash(&older).unwrap());
+        record.prefix_hashes = older_prefix.stride;
+        let len = older_body.len() as u64;
+
+        let lengths = BTreeSet::from([len]);
+        let newer_prefix = rolling_prefix_hashes(&newer, &lengths).expect("newer hashes");
+        assert!(is_superseded_by(&record, len, &newer_prefix));
+        let forked_prefix = rolling_prefix_hashes(&forked, &lengths).expect("forked hashes");
+        assert!(!is_superseded_by(&record, len, &forked_prefix));
+        let same_prefix = rolling_prefix_hashes(&older, &lengths).expect("same hashes");
+        assert!(!is_superseded_by(&record, len, &same_prefix));
+    }
+
+    #[test]
+    fn newest_archive_version_follows_supersede_chain() {
+        let mut a = ledger_row("/a.jsonl", "ha");
+        a.superseded_by = Some("/b.jsonl".to_string());
+        let mut b = ledger_row("/b.jsonl", "hb");
+        b.superseded_by = Some("/c.jsonl".to_string());
+        let c = ledger_row("/c.jsonl", "hc");
+        let records = vec![a, b, c];
+        assert_eq!(newest_archive_version(&records, "/a.jsonl"), "/c.jsonl");
+        assert_eq!(newest_archive_version(&records, "/c.jsonl"), "/c.jsonl");
+        assert_eq!(newest_archive_version(&records, "/x.jsonl"), "/x.jsonl");
+    }
+
+    #[test]
+    fn mark_duplicate_archives_links_near_matches_from_other_sessions() {
+        let row = |path: &str, session: &str, hash: &str| {
+            let mut record = ledger_row(path, path);
+            record.session_id = session.to_string();
+            record.projection_simhash = Some(hash.to_string());
+            record
+        };
+        let mut private = row("/p.jsonl", "s0", "00000000000000ff");
+        private.private = true;
+        let mut records = vec![
+            private,
+            row("/a.jsonl", "s1", "00000000000000ff"),
+            // Same session: a later snapshot, not a duplicate.
+            row("/a2.jsonl", "s1", "00000000000000ff"),
+            // Renamed session, two bits away.
+            row("/b.jsonl", "s2", "00000000000000fc"),
+            row("/c.jsonl", "s3", "ffffffff00000000"),
+        ];
+        assert_eq!(mark_duplicate_archives(&mut records, 3), 1);
+        assert_eq!(records[1].duplicate_of, None);
+        assert_eq!(records[2].duplicate_of, None);
+        assert_eq!(records[3].duplicate_of.as_deref(), Some("/a.jsonl"));
+        assert_eq!(records[4].duplicate_of, None);
+        assert_eq!(
+            duplicate_canonical_archive(&records, "/b.jsonl").as_deref(),
+            Some("/a.jsonl")
+        );
+        assert_eq!(duplicate_canonical_archive(&records, "/a.jsonl"), None);
+    }
+
+    #[test]
+    fn record_distill_provenance_stamps_matching_ledger_rows() {
+        let tmp = tempdir().expect("tempdir");
+        let paths = MoonPaths::for_test(tmp.path());
+        let provenance = DistillProvenance {
+            distilled_at_epoch_secs: 1_700_000_000,
+            provider: "gemini".to_string(),
+            summary_path: "/m/2023-11-14.md".to_string(),
+            chunk_count: 3,
+        };
+        assert!(!record_distill_provenance(&paths, "/a.jsonl", &provenance).expect("no ledger"));
+
+        let ledger = ledger_path(&paths);
+        write_ledger(
+            &ledger,
+            &[ledger_row("/a.jsonl", "ha"), ledger_row("/b.jsonl", "hb")],
+        )
+        .expect("write ledger");
+        assert!(record_distill_provenance(&paths, "/a.jsonl", &provenance).expect("stamp"));
+        assert!(!record_distill_provenance(&paths, "/x.jsonl", &provenance).expect("no row"));
+
+        let records = read_ledger(&ledger).expect("read ledger");
+        assert_eq!(records[0].distill.as_ref(), Some(&provenance));
+        assert!(records[1].distill.is_none());
+    }
+}
@@ -1,43 +1,217 @@
 use crate::moon::paths::MoonPaths;
 use crate::moon::util::now_epoch_secs;
 use anyhow::{Context, Result};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::cell::RefCell;
 use std::fs;
-use std::path::Path;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 
 const MAX_AUDIT_LOG_SIZE: u64 = 10 * 1024 * 1024; // 10MB
+/// Version 2 added typed `details`; lines without `schema_version` are version 1.
+pub const AUDIT_SCHEMA_VERSION: u32 = 2;
 
-#[derive(Debug, Clone, Serialize)]
+fn legacy_schema_version() -> u32 {
+    1
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AuditEvent {
+    #[serde(default = "legacy_schema_version")]
+    pub schema_version: u32,
     pub at_epoch_secs: u64,
     pub phase: String,
     pub status: String,
+    /// Human-readable summary; query on `details` instead.
     pub message: String,
+    /// Structured fields (`session`, `archive`, `provider`, ...).
+    #[serde(default, skip_serializing_if = "Value::is_null")]
+    pub details: Value,
+    /// Watcher cycle that emitted the event; `None` outside a cycle.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cycle_id: Option<String>,
+}
+
+impl AuditEvent {
+    /// `details[key]` as text; version 1 events carry no details and never match.
+    pub fn field(&self, key: &str) -> Option<String> {
+        let value = self.details.get(key)?.clone();
+        match value {
+            Value::Null => None,
+            Value::String(text) => Some(text),
+            other => Some(other.to_string()),
+        }
+    }
+}
+
+/// Events buffered for the current thread's watcher cycle, per logs dir.
+struct CycleBuffer {
+    cycle_id: String,
+    lines: Vec<(PathBuf, String)>,
+}
+
+thread_local! {
+    static CYCLE_BUFFER: RefCell<Option<CycleBuffer>> = const { RefCell::new(None) };
+}
+
+/// Buffers this thread's audit events for one watcher cycle.
+///
+/// Events are tagged with the cycle id and appended in a single write when the batch is
+/// flushed or dropped (including on error and unwind). Nested batches join the outer one.
+pub struct CycleAuditBatch {
+    cycle_id: String,
+    owner: bool,
+}
+
+impl CycleAuditBatch {
+    pub fn cycle_id(&self) -> &str {
+        &self.cycle_id
+    }
+
+    pub fn flush(mut self) -> Result<()> {
+        self.flush_buffered()
+    }
+
+    fn flush_buffered(&mut self) -> Result<()> {
+        if !self.owner {
+            return Ok(());
+        }
+        let lines = CYCLE_BUFFER.with(|buffer| {
+            buffer
+                .borrow_mut()
+                .as_mut()
+                .map(|buffer| std::mem::take(&mut buffer.lines))
+                .unwrap_or_default()
+        });
+        let mut by_dir: Vec<(PathBuf, String)> = Vec::new();
+        for (logs_dir, line) in lines {
+            match by_dir.iter_mut().find(|(dir, _)| *dir == logs_dir) {
+                Some((_, joined)) => joined.push_str(&line),
+                None => by_dir.push((logs_dir, line)),
+            }
+        }
+        for (logs_dir, joined) in by_dir {
+            write_lines(&logs_dir, &joined, true)?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for CycleAuditBatch {
+    fn drop(&mut self) {
+        let _ = self.flush_buffered();
+        if self.owner {
+            CYCLE_BUFFER.with(|buffer| buffer.borrow_mut().take());
+        }
+    }
 }
 
-pub fn append_event(paths: &MoonPaths, phase: &str, status: &str, message: &str) -> Result<()> {
-    fs::create_dir_all(&paths.logs_dir)
-        .with_context(|| format!("failed to create {}", paths.logs_dir.display()))?;
-    let event = AuditEvent {
+/// Starts buffering this thread's audit events under a fresh cycle id.
+pub fn begin_cycle() -> CycleAuditBatch {
+    static SEQ: AtomicU64 = AtomicU64::new(0);
+    CYCLE_BUFFER.with(|buffer| {
+        let mut buffer = buffer.borrow_mut();
+        if let Some(active) = buffer.as_ref() {
+            return CycleAuditBatch {
+                cycle_id: active.cycle_id.clone(),
+                owner: false,
+            };
+        }
+        let cycle_id = format!(
+            "{}-{}-{}",
+            now_epoch_secs().unwrap_or_default(),
+            std::process::id(),
+            SEQ.fetch_add(1, Ordering::Relaxed)
+        );
+        *buffer = Some(CycleBuffer {
+            cycle_id: cycle_id.clone(),
+            lines: Vec::new(),
+        });
+        CycleAuditBatch {
+            cycle_id,
+            owner: true,
+        }
+    })
+}
+
+/// Appends one event; `details` holds the typed fields that `moon audit --where` and the
+/// reports query, `message` is only for people reading the log.
+pub fn append_event(
+    paths: &MoonPaths,
+    phase: &str,
+    status: &str,
+    message: &str,
+    details: Value,
+) -> Result<()> {
+    if crate::moon::util::read_only_mode() {
+        return Ok(());
+    }
+    let mut event = AuditEvent {
+        schema_version: AUDIT_SCHEMA_VERSION,
         at_epoch_secs: now_epoch_secs()?,
         phase: phase.to_string(),
         status: status.to_string(),
         message: message.to_string(),
+        details,
+        cycle_id: None,
     };
+    let buffered = CYCLE_BUFFER.with(|buffer| -> Result<bool> {
+        let mut buffer = buffer.borrow_mut();
+        let Some(buffer) = buffer.as_mut() else {
+            return Ok(false);
+        };
+        event.cycle_id = Some(buffer.cycle_id.clone());
+        let line = format!("{}\n", serde_json::to_string(&event)?);
+        buffer.lines.push((paths.logs_dir.clone(), line));
+        Ok(true)
+    })?;
+    if buffered {
+        return Ok(());
+    }
 
     let line = format!("{}\n", serde_json::to_string(&event)?);
-    let path = paths.logs_dir.join("audit.log");
+    write_lines(&paths.logs_dir, &line, false)
+}
+
+fn write_lines(logs_dir: &Path, lines: &str, sync: bool) -> Result<()> {
+    fs::create_dir_all(logs_dir)
+        .with_context(|| format!("failed to create {}", logs_dir.display()))?;
+    let path = logs_dir.join("audit.log");
     let _ = maybe_rotate_log(&path);
 
-    use std::io::Write;
     let mut file = fs::OpenOptions::new()
         .create(true)
         .append(true)
-        .open(path)?;
-    file.write_all(line.as_bytes())?;
+        .open(&path)?;
+    file.write_all(lines.as_bytes())?;
+    if sync {
+        file.sync_data()
+            .with_context(|| format!("failed to sync {}", path.display()))?;
+    }
     Ok(())
 }
 
+/// Events from the rotated and current audit logs, oldest first. Unparseable lines are skipped.
+pub fn read_events(paths: &MoonPaths) -> Result<Vec<AuditEvent>> {
+    let current = paths.logs_dir.join("audit.log");
+    let rotated = paths.logs_dir.join("audit.log.1");
+    let mut out = Vec::new();
+    for path in [rotated, current] {
+        if !path.exists() {
+            continue;
+        }
+        let raw = fs::read_to_string(&path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        out.extend(
+            raw.lines()
+                .filter_map(|line| serde_json::from_str::<AuditEvent>(line).ok()),
+        );
+    }
+    Ok(out)
+}
+
 fn maybe_rotate_log(path: &Path) -> Result<()> {
     if let Ok(meta) = fs::metadata(path)
         && meta.len() >= MAX_AUDIT_LOG_SIZE
@@ -47,3 +221,69 @@ fn maybe_rotate_log(path: &Path) -> Result<()> {
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cycle_batch_defers_events_and_tags_them_with_the_cycle_id() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let paths = MoonPaths::for_test(tmp.path());
+
+        let batch = begin_cycle();
+        let cycle_id = batch.cycle_id().to_string();
+        append_event(&paths, "archive", "ok", "first", Value::Null).expect("append");
+        let nested = begin_cycle();
+        assert_eq!(nested.cycle_id(), cycle_id);
+        drop(nested);
+        append_event(&paths, "compaction", "ok", "second", Value::Null).expect("append");
+        assert!(read_events(&paths).expect("read").is_empty());
+
+        batch.flush().expect("flush");
+        let events = read_events(&paths).expect("read");
+        assert_eq!(events.len(), 2);
+        assert!(
+            events
+                .iter()
+                .all(|event| event.cycle_id.as_deref() == Some(cycle_id.as_str()))
+        );
+
+        append_event(&paths, "watch", "ok", "outside", Value::Null).expect("append");
+        let events = read_events(&paths).expect("read");
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[2].cycle_id, None);
+    }
+
+    #[test]
+    fn events_carry_typed_details_and_legacy_lines_still_parse() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let paths = MoonPaths::for_test(tmp.path());
+        fs::create_dir_all(&paths.logs_dir).expect("mkdir logs");
+        fs::write(
+            paths.logs_dir.join("audit.log"),
+            "{\"at_epoch_secs\":1,\"phase\":\"compaction\",\"status\":\"ok\",\"message\":\"manual ok key=agent:main:x archived=/a.jsonl\"}\n",
+        )
+        .expect("write legacy line");
+
+        append_event(
+            &paths,
+            "distill",
+            "ok",
+            "distilled session=s1 topic_count=3",
+            serde_json::json!({"session": "s1", "topic_count": 3}),
+        )
+        .expect("append typed");
+
+        let events = read_events(&paths).expect("read");
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].schema_version, 1);
+        assert_eq!(events[0].phase, "compaction");
+        // Version 1 messages are not scraped for fields.
+        assert_eq!(events[0].field("key"), None);
+        assert_eq!(events[1].schema_version, AUDIT_SCHEMA_VERSION);
+        assert_eq!(events[1].field("session").as_deref(), Some("s1"));
+        assert_eq!(events[1].field("topic_count").as_deref(), Some("3"));
+        assert_eq!(events[1].field("missing"), None);
+    }
+}
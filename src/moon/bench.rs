@@ -0,0 +1,390 @@
+use crate::moon::archive::{ArchiveRecord, read_ledger_records, remove_ledger_records};
+use crate::moon::distill::{
+    DistillInput, Distiller, LocalDistiller, distill_chunk_bytes, extract_projection_data,
+    stream_archive_chunks,
+};
+use crate::moon::paths::MoonPaths;
+use crate::moon::recall::{RecallMatch, hydrate_match};
+use crate::moon::util::now_epoch_secs;
+use anyhow::{Context, Result};
+use serde::Serialize;
+use serde_json::json;
+use std::borrow::Cow;
+use std::collections::BTreeSet;
+use std::fs;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+const BYTES_PER_MB: u64 = 1024 * 1024;
+const TOPICS: [&str; 8] = [
+    "release pipeline",
+    "ledger compaction",
+    "qmd index refresh",
+    "channel continuity",
+    "distill budget",
+    "retention sweep",
+    "embedding backfill",
+    "watcher cooldown",
+];
+/// Phrase planted in the middle of the synthetic archive so recall hydration has a real target.
+const RECALL_NEEDLE: &str = "decision: pin the moonbench sentinel rollout";
+
+#[derive(Debug, Clone)]
+pub struct BenchOptions {
+    pub archive_mb: u64,
+    pub ledger_records: usize,
+    pub iterations: usize,
+    /// Scratch directory root; a unique subdirectory is created inside it.
+    pub scratch_root: PathBuf,
+    pub keep: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BenchStage {
+    pub name: &'static str,
+    /// Fastest of the timed iterations.
+    pub best: Duration,
+    pub mean: Duration,
+    pub bytes: u64,
+    pub items: usize,
+}
+
+impl BenchStage {
+    pub fn mb_per_sec(&self) -> f64 {
+        let secs = self.best.as_secs_f64();
+        if secs <= 0.0 {
+            return 0.0;
+        }
+        self.bytes as f64 / BYTES_PER_MB as f64 / secs
+    }
+
+    pub fn items_per_sec(&self) -> f64 {
+        let secs = self.best.as_secs_f64();
+        if secs <= 0.0 {
+            return 0.0;
+        }
+        self.items as f64 / secs
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BenchReport {
+    pub scratch_dir: PathBuf,
+    pub archive_bytes: u64,
+    pub archive_lines: usize,
+    pub stages: Vec<BenchStage>,
+}
+
+/// Times `run` `iterations` times; `run` returns the (bytes, items) it processed.
+fn time_stage<F>(name: &'static str, iterations: usize, mut run: F) -> Result<BenchStage>
+where
+    F: FnMut() -> Result<(u64, usize)>,
+{
+    let mut best = Duration::MAX;
+    let mut total = Duration::ZERO;
+    let mut processed = (0, 0);
+    for _ in 0..iterations.max(1) {
+        let started = Instant::now();
+        processed = run().with_context(|| format!("bench stage {name} failed"))?;
+        let elapsed = started.elapsed();
+        best = best.min(elapsed);
+        total += elapsed;
+    }
+    Ok(BenchStage {
+        name,
+        best,
+        mean: total / iterations.max(1) as u32,
+        bytes: processed.0,
+        items: processed.1,
+    })
+}
+
+fn scratch_paths(scratch_dir: &Path) -> MoonPaths {
+    MoonPaths {
+        moon_home: scratch_dir.to_path_buf(),
+        archives_dir: scratch_dir.join("archives"),
+        memory_dir: scratch_dir.join("memory"),
+        memory_file: scratch_dir.join("MEMORY.md"),
+        logs_dir: scratch_dir.join("moon/logs"),
+        openclaw_sessions_dir: scratch_dir.join("sessions"),
+        qmd_bin: PathBuf::from("qmd"),
+        qmd_db: scratch_dir.join("qmd.sqlite"),
+        moon_home_is_explicit: true,
+    }
+}
+
+/// Small deterministic LCG so runs are comparable across machines and releases.
+struct SyntheticRng(u64);
+
+impl SyntheticRng {
+    fn next(&mut self) -> u64 {
+        self.0 = self
+            .0
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        self.0 >> 33
+    }
+
+    fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+        items[(self.next() as usize) % items.len()]
+    }
+}
+
+fn synthetic_event(rng: &mut SyntheticRng, turn: usize, needle: bool) -> serde_json::Value {
+    let topic = rng.pick(&TOPICS);
+    let other = rng.pick(&TOPICS);
+    let timestamp = 1_700_000_000 + turn as u64 * 7;
+    match turn % 4 {
+        0 => json!({
+            "type": "message",
+            "timestamp": timestamp,
+            "message": {"role": "user", "content": [{"type": "text", "text": if needle {
+                RECALL_NEEDLE.to_string()
+            } else {
+                format!("turn {turn}: can you check the {topic} and compare it with the {other} numbers from yesterday?")
+            }}]}
+        }),
+        1 => json!({
+            "type": "message",
+            "timestamp": timestamp,
+            "message": {"role": "assistant", "content": [
+                {"type": "text", "text": format!("Looking at the {topic} now; the {other} run finished {} seconds ago.", rng.next() % 900)},
+                {"type": "toolCall", "name": "exec", "arguments": {"command": format!("moon status --json # {topic}")}}
+            ]}
+        }),
+        2 => json!({
+            "type": "message",
+            "timestamp": timestamp,
+            "message": {"role": "toolResult", "toolName": "exec", "content": [{"type": "text", "text": format!(
+                "ok: true\ndetails:\n- {topic}.latency_ms={}\n- {other}.rows={}\n",
+                rng.next() % 5_000,
+                rng.next() % 100_000
+            )}]}
+        }),
+        _ => json!({
+            "type": "message",
+            "timestamp": timestamp,
+            "message": {"role": "assistant", "content": [{"type": "text", "text": format!(
+                "decision: keep the {topic} settings; next: revisit {other} after the {} cycle.",
+                rng.next() % 64
+            )}]}
+        }),
+    }
+}
+
+/// Writes a JSONL session archive of roughly `target_bytes`, returning (bytes, lines).
+pub fn write_synthetic_archive(path: &Path, target_bytes: u64) -> Result<(u64, usize)> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+    let file =
+        fs::File::create(path).with_context(|| format!("failed to create {}", path.display()))?;
+    let mut out = BufWriter::new(file);
+    let mut rng = SyntheticRng(0x6d6f_6f6e);
+    let mut written = 0u64;
+    let mut lines = 0usize;
+    let mut needle_planted = false;
+    while written < target_bytes.max(1) {
+        let needle = !needle_planted && written >= target_bytes / 2 && lines.is_multiple_of(4);
+        needle_planted |= needle;
+        let line = format!("{}\n", synthetic_event(&mut rng, lines, needle));
+        out.write_all(line.as_bytes())?;
+        written += line.len() as u64;
+        lines += 1;
+    }
+    out.flush()?;
+    Ok((written, lines))
+}
+
+fn synthetic_ledger_record(scratch_dir: &Path, idx: usize) -> ArchiveRecord {
+    ArchiveRecord {
+        session_id: format!("bench-{idx}"),
+        source_path: format!("/tmp/sessions/bench-{idx}.jsonl"),
+        archive_path: scratch_dir
+            .join(format!("archives/raw/bench-{idx}.jsonl"))
+            .display()
+            .to_string(),
+        projection_path: None,
+        projection_filtered_noise_count: None,
+        content_hash: format!("{idx:016x}"),
+        created_at_epoch_secs: 1_700_000_000 + idx as u64,
+        indexed_collection: "history".to_string(),
+        indexed: true,
+        content_bytes: Some(4096),
+        prefix_hashes: Vec::new(),
+        superseded_by: None,
+        private: false,
+        encrypted: false,
+        distill: None,
+        compaction_anchors: Vec::new(),
+        projection_simhash: None,
+        duplicate_of: None,
+        distill_skip: false,
+    }
+}
+
+fn write_synthetic_ledger(path: &Path, records: &[ArchiveRecord]) -> Result<u64> {
+    let file =
+        fs::File::create(path).with_context(|| format!("failed to create {}", path.display()))?;
+    let mut out = BufWriter::new(file);
+    let mut written = 0u64;
+    for record in records {
+        let line = format!("{}\n", serde_json::to_string(record)?);
+        out.write_all(line.as_bytes())?;
+        written += line.len() as u64;
+    }
+    out.flush()?;
+    Ok(written)
+}
+
+/// Generates synthetic data under a scratch directory and times each pipeline stage.
+pub fn run_bench(opts: &BenchOptions) -> Result<BenchReport> {
+    let scratch_dir = opts.scratch_root.join(format!(
+        "moon-bench-{}-{}",
+        std::process::id(),
+        now_epoch_secs()?
+    ));
+    let result = run_stages(opts, &scratch_dir);
+    if !opts.keep {
+        let _ = fs::remove_dir_all(&scratch_dir);
+    }
+    result
+}
+
+fn run_stages(opts: &BenchOptions, scratch_dir: &Path) -> Result<BenchReport> {
+    let paths = scratch_paths(scratch_dir);
+    fs::create_dir_all(paths.archives_dir.join("raw"))
+        .with_context(|| format!("failed to create {}", paths.archives_dir.display()))?;
+    let iterations = opts.iterations.max(1);
+    let mut stages = Vec::new();
+
+    let archive = paths.archives_dir.join("raw/bench-session.jsonl");
+    let archive_str = archive.display().to_string();
+    let started = Instant::now();
+    let (archive_bytes, archive_lines) =
+        write_synthetic_archive(&archive, opts.archive_mb.max(1) * BYTES_PER_MB)?;
+    let generate = started.elapsed();
+    stages.push(BenchStage {
+        name: "generate",
+        best: generate,
+        mean: generate,
+        bytes: archive_bytes,
+        items: archive_lines,
+    });
+
+    stages.push(time_stage("projection", iterations, || {
+        let data = extract_projection_data(&archive_str)?;
+        Ok((archive_bytes, data.entries.len()))
+    })?);
+
+    let chunk_bytes = distill_chunk_bytes();
+    stages.push(time_stage("chunking", iterations, || {
+        let (chunks, _) =
+            stream_archive_chunks(&archive_str, chunk_bytes, usize::MAX, |_, chunk| {
+                std::hint::black_box(chunk.len());
+                Ok(())
+            })?;
+        Ok((archive_bytes, chunks))
+    })?);
+
+    stages.push(time_stage("local_distill", iterations, || {
+        let (chunks, _) =
+            stream_archive_chunks(&archive_str, chunk_bytes, usize::MAX, |_, chunk| {
+                let input = DistillInput {
+                    session_id: "bench-session".to_string(),
+                    archive_path: archive_str.clone(),
+                    archive_text: Cow::Borrowed(chunk),
+                    archive_epoch_secs: None,
+                };
+                std::hint::black_box(LocalDistiller.distill(&input)?);
+                Ok(())
+            })?;
+        Ok((archive_bytes, chunks))
+    })?);
+
+    let ledger = paths.archives_dir.join("ledger.jsonl");
+    let records = (0..opts.ledger_records)
+        .map(|idx| synthetic_ledger_record(scratch_dir, idx))
+        .collect::<Vec<_>>();
+    let mut ledger_bytes = 0u64;
+    stages.push(time_stage("ledger_write", iterations, || {
+        ledger_bytes = write_synthetic_ledger(&ledger, &records)?;
+        Ok((ledger_bytes, records.len()))
+    })?);
+    stages.push(time_stage("ledger_read", iterations, || {
+        let read = read_ledger_records(&paths)?;
+        Ok((ledger_bytes, read.len()))
+    })?);
+    let doomed = records
+        .iter()
+        .step_by(10)
+        .map(|record| record.archive_path.clone())
+        .collect::<BTreeSet<_>>();
+    stages.push(time_stage("ledger_remove", iterations, || {
+        write_synthetic_ledger(&ledger, &records)?;
+        let removed = remove_ledger_records(&paths, &doomed)?;
+        Ok((ledger_bytes, removed))
+    })?);
+
+    let needle = RecallMatch {
+        archive_path: archive_str.clone(),
+        snippet: RECALL_NEEDLE.to_string(),
+        score: 1.0,
+        metadata: json!({}),
+    };
+    stages.push(time_stage("recall_hydrate", iterations, || {
+        let hydration = hydrate_match(&needle, 8)?;
+        Ok((archive_bytes, hydration.entries.len()))
+    })?);
+
+    Ok(BenchReport {
+        scratch_dir: scratch_dir.to_path_buf(),
+        archive_bytes,
+        archive_lines,
+        stages,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn bench_runs_every_stage_on_a_small_synthetic_archive() {
+        let tmp = tempdir().expect("tempdir");
+        let report = run_bench(&BenchOptions {
+            archive_mb: 1,
+            ledger_records: 200,
+            iterations: 1,
+            scratch_root: tmp.path().to_path_buf(),
+            keep: false,
+        })
+        .expect("bench");
+
+        let names = report.stages.iter().map(|s| s.name).collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            [
+                "generate",
+                "projection",
+                "chunking",
+                "local_distill",
+                "ledger_write",
+                "ledger_read",
+                "ledger_remove",
+                "recall_hydrate"
+            ]
+        );
+        assert!(report.archive_bytes >= BYTES_PER_MB);
+        let stage = |name: &str| report.stages.iter().find(|s| s.name == name).unwrap();
+        assert!(stage("projection").items > 0);
+        assert!(stage("chunking").items >= 1);
+        assert_eq!(stage("ledger_read").items, 200);
+        assert_eq!(stage("ledger_remove").items, 20);
+        assert!(stage("recall_hydrate").items > 0);
+        assert!(!report.scratch_dir.exists());
+    }
+}
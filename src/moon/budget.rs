@@ -0,0 +1,192 @@
+use crate::moon::paths::MoonPaths;
+use anyhow::{Context, Result};
+use chrono::{TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+/// Earlier days kept in the ledger's `history` so reports can look back at them.
+pub const HISTORY_DAYS: usize = 31;
+
+/// Estimated remote-distillation spend for one residential day.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DistillBudget {
+    pub day_key: String,
+    pub tokens_used: u64,
+    pub exhausted_at_epoch_secs: Option<u64>,
+    /// Tokens used on earlier days, by day key; at most `HISTORY_DAYS` entries.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub history: BTreeMap<String, u64>,
+}
+
+impl DistillBudget {
+    pub fn is_exhausted(&self, limit: u64) -> bool {
+        limit > 0 && (self.exhausted_at_epoch_secs.is_some() || self.tokens_used >= limit)
+    }
+}
+
+pub fn budget_path(paths: &MoonPaths) -> PathBuf {
+    paths.logs_dir.join("distill-budget.json")
+}
+
+pub fn day_key_for_epoch(epoch_secs: u64, tz: Tz) -> String {
+    Utc.timestamp_opt(epoch_secs as i64, 0)
+        .single()
+        .unwrap_or_else(Utc::now)
+        .with_timezone(&tz)
+        .format("%Y-%m-%d")
+        .to_string()
+}
+
+fn load(paths: &MoonPaths) -> Result<Option<DistillBudget>> {
+    let path = budget_path(paths);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let raw =
+        fs::read_to_string(&path).with_context(|| format!("failed to read {}", path.display()))?;
+    let parsed = serde_json::from_str(&raw)
+        .with_context(|| format!("failed to parse {}", path.display()))?;
+    Ok(Some(parsed))
+}
+
+/// Budget for `day_key`; a ledger left over from an earlier day starts fresh, moving that
+/// day's spend into `history`.
+pub fn load_for_day(paths: &MoonPaths, day_key: &str) -> Result<DistillBudget> {
+    let Some(mut budget) = load(paths)? else {
+        return Ok(DistillBudget {
+            day_key: day_key.to_string(),
+            ..DistillBudget::default()
+        });
+    };
+    if budget.day_key != day_key {
+        if !budget.day_key.is_empty() && budget.tokens_used > 0 {
+            budget
+                .history
+                .insert(std::mem::take(&mut budget.day_key), budget.tokens_used);
+        }
+        while budget.history.len() > HISTORY_DAYS {
+            budget.history.pop_first();
+        }
+        budget.tokens_used = budget.history.remove(day_key).unwrap_or(0);
+        budget.day_key = day_key.to_string();
+        budget.exhausted_at_epoch_secs = None;
+    }
+    Ok(budget)
+}
+
+/// Estimated tokens spent on `day_key`, from the current day or the ledger's history.
+pub fn tokens_for_day(paths: &MoonPaths, day_key: &str) -> Result<u64> {
+    let Some(budget) = load(paths)? else {
+        return Ok(0);
+    };
+    if budget.day_key == day_key {
+        return Ok(budget.tokens_used);
+    }
+    Ok(budget.history.get(day_key).copied().unwrap_or(0))
+}
+
+/// Estimated cost of `tokens` at `cost_per_million_tokens`; `None` when no rate is set.
+pub fn estimated_cost(tokens: u64, cost_per_million_tokens: f64) -> Option<f64> {
+    (cost_per_million_tokens > 0.0).then(|| tokens as f64 / 1_000_000.0 * cost_per_million_tokens)
+}
+
+fn save(paths: &MoonPaths, budget: &DistillBudget) -> Result<()> {
+    let path = budget_path(paths);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+    let data = serde_json::to_string_pretty(budget)?;
+    fs::write(&path, format!("{data}\n"))
+        .with_context(|| format!("failed to write {}", path.display()))?;
+    Ok(())
+}
+
+/// Adds `tokens` to the day's spend. Returns `true` when this call exhausted the budget.
+pub fn record_tokens(
+    paths: &MoonPaths,
+    day_key: &str,
+    tokens: u64,
+    limit: u64,
+    now_epoch_secs: u64,
+) -> Result<bool> {
+    let mut budget = load_for_day(paths, day_key)?;
+    budget.tokens_used = budget.tokens_used.saturating_add(tokens);
+    let newly_exhausted =
+        limit > 0 && budget.exhausted_at_epoch_secs.is_none() && budget.tokens_used >= limit;
+    if newly_exhausted {
+        budget.exhausted_at_epoch_secs = Some(now_epoch_secs);
+    }
+    save(paths, &budget)?;
+    Ok(newly_exhausted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        HISTORY_DAYS, day_key_for_epoch, estimated_cost, load_for_day, record_tokens,
+        tokens_for_day,
+    };
+    use crate::moon::paths::MoonPaths;
+    use tempfile::tempdir;
+
+    #[test]
+    fn record_tokens_flags_exhaustion_once_and_resets_next_day() {
+        let tmp = tempdir().expect("tempdir");
+        let paths = MoonPaths::for_test(tmp.path());
+
+        assert!(!record_tokens(&paths, "2023-11-14", 600, 1_000, 10).expect("record"));
+        assert!(record_tokens(&paths, "2023-11-14", 500, 1_000, 20).expect("record"));
+        assert!(!record_tokens(&paths, "2023-11-14", 50, 1_000, 30).expect("record"));
+
+        let today = load_for_day(&paths, "2023-11-14").expect("load");
+        assert_eq!(today.tokens_used, 1_150);
+        assert_eq!(today.exhausted_at_epoch_secs, Some(20));
+        assert!(today.is_exhausted(1_000));
+        assert!(!today.is_exhausted(0));
+
+        let tomorrow = load_for_day(&paths, "2023-11-15").expect("load");
+        assert_eq!(tomorrow.tokens_used, 0);
+        assert!(!tomorrow.is_exhausted(1_000));
+    }
+
+    #[test]
+    fn earlier_days_stay_queryable_from_history() {
+        let tmp = tempdir().expect("tempdir");
+        let paths = MoonPaths::for_test(tmp.path());
+
+        record_tokens(&paths, "2023-11-14", 1_500, 0, 10).expect("record");
+        record_tokens(&paths, "2023-11-15", 250, 0, 20).expect("record");
+        assert_eq!(tokens_for_day(&paths, "2023-11-14").expect("tokens"), 1_500);
+        assert_eq!(tokens_for_day(&paths, "2023-11-15").expect("tokens"), 250);
+        assert_eq!(tokens_for_day(&paths, "2023-11-13").expect("tokens"), 0);
+
+        let mut last_day = String::new();
+        for day in 1..=(HISTORY_DAYS as u64 + 5) {
+            last_day = day_key_for_epoch(1_800_000_000 + day * 86_400, chrono_tz::UTC);
+            record_tokens(&paths, &last_day, 1, 0, 30).expect("record");
+        }
+        let current = load_for_day(&paths, &last_day).expect("load");
+        assert_eq!(current.history.len(), HISTORY_DAYS);
+        assert_eq!(tokens_for_day(&paths, "2023-11-14").expect("tokens"), 0);
+
+        assert_eq!(estimated_cost(2_000_000, 0.0), None);
+        assert_eq!(estimated_cost(2_000_000, 1.5), Some(3.0));
+    }
+
+    #[test]
+    fn day_key_follows_residential_timezone() {
+        assert_eq!(
+            day_key_for_epoch(1_700_000_000, chrono_tz::UTC),
+            "2023-11-14"
+        );
+        assert_eq!(
+            day_key_for_epoch(1_700_000_000, chrono_tz::Asia::Tokyo),
+            "2023-11-15"
+        );
+    }
+}
@@ -0,0 +1,69 @@
+use serde::{Deserialize, Serialize};
+
+/// Identity of a moon build, embedded in the daemon lock and state heartbeat so a mismatch
+/// can be explained by version and commit rather than the random `BUILD_UUID` alone.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BuildInfo {
+    pub version: String,
+    pub git_sha: String,
+    pub build_uuid: String,
+    pub features: Vec<String>,
+    pub distill_providers: Vec<String>,
+    pub embed_providers: Vec<String>,
+}
+
+impl BuildInfo {
+    pub fn current() -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            git_sha: env!("BUILD_GIT_SHA").to_string(),
+            build_uuid: env!("BUILD_UUID").to_string(),
+            features: env!("BUILD_FEATURES")
+                .split(',')
+                .filter(|feature| !feature.is_empty())
+                .map(str::to_string)
+                .collect(),
+            distill_providers: crate::moon::distill::supported_providers()
+                .iter()
+                .map(|provider| provider.to_string())
+                .collect(),
+            embed_providers: crate::moon::vectors::EmbedProvider::ALL
+                .iter()
+                .map(|provider| provider.label().to_string())
+                .collect(),
+        }
+    }
+
+    /// `version=... git_sha=... build_uuid=...`, for mismatch messages.
+    pub fn summary(&self) -> String {
+        format!(
+            "version={} git_sha={} build_uuid={}",
+            self.version, self.git_sha, self.build_uuid
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn current_build_lists_providers_and_round_trips() {
+        let info = BuildInfo::current();
+        assert_eq!(info.version, env!("CARGO_PKG_VERSION"));
+        assert!(!info.git_sha.is_empty());
+        assert!(info.distill_providers.iter().any(|p| p == "local"));
+        assert!(info.embed_providers.iter().any(|p| p == "qmd"));
+
+        let raw = serde_json::to_string(&info).expect("serialize");
+        assert_eq!(
+            serde_json::from_str::<BuildInfo>(&raw).expect("parse"),
+            info
+        );
+        assert_eq!(
+            serde_json::from_str::<BuildInfo>("{}").expect("legacy"),
+            BuildInfo::default()
+        );
+    }
+}
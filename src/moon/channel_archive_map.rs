@@ -1,8 +1,10 @@
+use crate::moon::continuity;
+use crate::moon::lease;
 use crate::moon::paths::MoonPaths;
 use crate::moon::util::now_epoch_secs;
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::collections::{BTreeMap, BTreeSet};
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::PathBuf;
 
@@ -12,6 +14,20 @@ pub struct ChannelArchiveRecord {
     pub source_path: String,
     pub archive_path: String,
     pub updated_at_epoch_secs: u64,
+    /// Strategy label of the `/compact` sent after this archive (`default` for plain `/compact`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compaction_strategy: Option<String>,
+    /// Archive retention removed; `archive_path` then names its distilled summary or the
+    /// channel's continuity records instead.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retired_archive_path: Option<String>,
+}
+
+/// How [`retire_archive_paths`] handled channels whose archive left retention.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RetireOutcome {
+    pub repointed: usize,
+    pub removed: usize,
 }
 
 pub fn map_path(paths: &MoonPaths) -> PathBuf {
@@ -59,6 +75,7 @@ pub fn upsert(
     channel_key: &str,
     source_path: &str,
     archive_path: &str,
+    compaction_strategy: Option<&str>,
 ) -> Result<ChannelArchiveRecord> {
     if channel_key.trim().is_empty() {
         anyhow::bail!("channel key cannot be empty");
@@ -70,12 +87,15 @@ pub fn upsert(
         anyhow::bail!("archive path cannot be empty");
     }
 
+    let _lease = lease::acquire(&map_path(paths), "channel map update")?;
     let mut map = load(paths)?;
     let record = ChannelArchiveRecord {
         channel_key: channel_key.to_string(),
         source_path: source_path.to_string(),
         archive_path: archive_path.to_string(),
         updated_at_epoch_secs: now_epoch_secs()?,
+        compaction_strategy: compaction_strategy.map(str::to_string),
+        retired_archive_path: None,
     };
     map.insert(channel_key.to_string(), record.clone());
 
@@ -84,23 +104,66 @@ pub fn upsert(
     Ok(record)
 }
 
-pub fn remove_by_archive_paths(
+/// Repoints channels mapped to a retired archive at its distilled summary (the value in
+/// `retired`), else at the channel's continuity records; channels with neither are removed.
+pub fn retire_archive_paths(
     paths: &MoonPaths,
-    archive_paths: &BTreeSet<String>,
-) -> Result<usize> {
-    if archive_paths.is_empty() {
-        return Ok(0);
+    retired: &BTreeMap<String, Option<String>>,
+) -> Result<RetireOutcome> {
+    let mut outcome = RetireOutcome::default();
+    if retired.is_empty() {
+        return Ok(outcome);
     }
 
+    let _lease = lease::acquire(&map_path(paths), "channel map update")?;
     let mut map = load(paths)?;
-    let before = map.len();
-    map.retain(|_, record| !archive_paths.contains(&record.archive_path));
-    let removed = before.saturating_sub(map.len());
-    if removed > 0 {
+    let now = now_epoch_secs()?;
+    let continuity_path = continuity::records_path(paths).display().to_string();
+    map.retain(|channel_key, record| {
+        let Some(summary) = retired.get(&record.archive_path) else {
+            return true;
+        };
+        let target = summary.clone().or_else(|| {
+            continuity::read_records(paths, channel_key)
+                .is_ok_and(|records| !records.is_empty())
+                .then(|| continuity_path.clone())
+        });
+        let Some(target) = target else {
+            outcome.removed += 1;
+            return false;
+        };
+        record.retired_archive_path = Some(std::mem::replace(&mut record.archive_path, target));
+        record.updated_at_epoch_secs = now;
+        outcome.repointed += 1;
+        true
+    });
+    if outcome != RetireOutcome::default() {
+        save(paths, &map)?;
+    }
+
+    Ok(outcome)
+}
+
+/// Points channels retired from `archive_path` back at it; used when the archive is restored.
+pub fn restore_retired_archive_path(paths: &MoonPaths, archive_path: &str) -> Result<usize> {
+    let _lease = lease::acquire(&map_path(paths), "channel map update")?;
+    let mut map = load(paths)?;
+    let now = now_epoch_secs()?;
+    let mut restored = 0usize;
+    for record in map.values_mut() {
+        if record.retired_archive_path.as_deref() != Some(archive_path) {
+            continue;
+        }
+        record.archive_path = archive_path.to_string();
+        record.retired_archive_path = None;
+        record.updated_at_epoch_secs = now;
+        restored += 1;
+    }
+    if restored > 0 {
         save(paths, &map)?;
     }
 
-    Ok(removed)
+    Ok(restored)
 }
 
 pub fn rewrite_archive_paths(
@@ -111,6 +174,7 @@ pub fn rewrite_archive_paths(
         return Ok(0);
     }
 
+    let _lease = lease::acquire(&map_path(paths), "channel map update")?;
     let mut map = load(paths)?;
     if map.is_empty() {
         return Ok(0);
@@ -144,17 +208,7 @@ mod tests {
     use tempfile::tempdir;
 
     fn test_paths(root: &std::path::Path) -> MoonPaths {
-        MoonPaths {
-            moon_home: root.join("moon"),
-            archives_dir: root.join("moon/archives"),
-            memory_dir: root.join("moon/memory"),
-            memory_file: root.join("moon/MEMORY.md"),
-            logs_dir: root.join("moon/logs"),
-            openclaw_sessions_dir: root.join("sessions"),
-            qmd_bin: root.join("qmd"),
-            qmd_db: root.join("qmd.sqlite"),
-            moon_home_is_explicit: false,
-        }
+        MoonPaths::for_test(&root.join("moon"))
     }
 
     #[test]
@@ -168,6 +222,7 @@ mod tests {
             "agent:main:discord:channel:123",
             "/tmp/source.jsonl",
             "/tmp/archive.jsonl",
+            Some("focus=decisions keep_last=10"),
         )
         .expect("upsert");
 
@@ -176,43 +231,82 @@ mod tests {
             .expect("some");
         assert_eq!(got.archive_path, "/tmp/archive.jsonl");
         assert_eq!(got.source_path, "/tmp/source.jsonl");
+        assert_eq!(
+            got.compaction_strategy.as_deref(),
+            Some("focus=decisions keep_last=10")
+        );
     }
 
     #[test]
-    fn remove_by_archive_paths_removes_matching_entries() {
+    fn retire_archive_paths_repoints_or_removes_entries() {
         let tmp = tempdir().expect("tempdir");
         let paths = test_paths(tmp.path());
         fs::create_dir_all(&paths.moon_home).expect("mkdir");
 
-        upsert(
-            &paths,
-            "agent:main:discord:channel:1",
-            "/tmp/s1.jsonl",
-            "/tmp/a1.jsonl",
-        )
-        .expect("upsert1");
-        upsert(
+        for idx in 1..=4 {
+            upsert(
+                &paths,
+                &format!("agent:main:discord:channel:{idx}"),
+                &format!("/tmp/s{idx}.jsonl"),
+                &format!("/tmp/a{idx}.jsonl"),
+                None,
+            )
+            .expect("upsert");
+        }
+        continuity::append_record(
             &paths,
-            "agent:main:discord:channel:2",
-            "/tmp/s2.jsonl",
-            "/tmp/a2.jsonl",
+            &continuity::ContinuityRecord {
+                channel_key: "agent:main:discord:channel:2".to_string(),
+                reason: "compaction".to_string(),
+                source_session_id: "s2".to_string(),
+                target_session_id: "s2".to_string(),
+                archive_path: Some("/tmp/a2.jsonl".to_string()),
+                summary_path: None,
+                recorded_at_epoch_secs: 1,
+            },
         )
-        .expect("upsert2");
+        .expect("append continuity");
 
-        let mut purge = BTreeSet::new();
-        purge.insert("/tmp/a1.jsonl".to_string());
-        let removed = remove_by_archive_paths(&paths, &purge).expect("remove");
-        assert_eq!(removed, 1);
-        assert!(
-            get(&paths, "agent:main:discord:channel:1")
-                .expect("get1")
-                .is_none()
+        let mut retired = BTreeMap::new();
+        retired.insert(
+            "/tmp/a1.jsonl".to_string(),
+            Some("/tmp/memory/2026-01-01.md".to_string()),
+        );
+        retired.insert("/tmp/a2.jsonl".to_string(), None);
+        retired.insert("/tmp/a3.jsonl".to_string(), None);
+        let outcome = retire_archive_paths(&paths, &retired).expect("retire");
+        assert_eq!(
+            outcome,
+            RetireOutcome {
+                repointed: 2,
+                removed: 1
+            }
+        );
+
+        let summary = get(&paths, "agent:main:discord:channel:1")
+            .expect("get1")
+            .expect("repointed to summary");
+        assert_eq!(summary.archive_path, "/tmp/memory/2026-01-01.md");
+        assert_eq!(
+            summary.retired_archive_path.as_deref(),
+            Some("/tmp/a1.jsonl")
+        );
+        let continuity = get(&paths, "agent:main:discord:channel:2")
+            .expect("get2")
+            .expect("repointed to continuity");
+        assert_eq!(
+            continuity.archive_path,
+            continuity::records_path(&paths).display().to_string()
         );
         assert!(
-            get(&paths, "agent:main:discord:channel:2")
-                .expect("get2")
-                .is_some()
+            get(&paths, "agent:main:discord:channel:3")
+                .expect("get3")
+                .is_none()
         );
+        let untouched = get(&paths, "agent:main:discord:channel:4")
+            .expect("get4")
+            .expect("kept");
+        assert!(untouched.retired_archive_path.is_none());
     }
 
     #[test]
@@ -226,6 +320,7 @@ mod tests {
             "agent:main:discord:channel:1",
             "/tmp/s1.jsonl",
             "/tmp/a1.jsonl",
+            None,
         )
         .expect("upsert1");
 
@@ -0,0 +1,174 @@
+use crate::moon::paths::MoonPaths;
+use crate::moon::util::read_only_mode;
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+/// Entries neither written nor hit for this long are pruned by the watcher's retention pass;
+/// they only pay off for reruns over a day's log that is still growing.
+pub const ENTRY_TTL_SECS: u64 = 7 * 86_400;
+
+/// Bump when the synthesis chunk or rollup prompt wording changes, so answers given to the
+/// old wording stop matching.
+pub const PROMPT_TEMPLATE_VERSION: u32 = 2;
+
+pub fn cache_dir(paths: &MoonPaths) -> PathBuf {
+    paths.moon_home.join("cache").join("distill")
+}
+
+/// Hash of `model_key`, the prompt template version and the chunk `body`, so an unchanged
+/// chunk of a grown input maps to the same entry wherever it lands in the day.
+///
+/// The bounded MEMORY.md the prompt also carries is left out on purpose: synthesis rewrites it
+/// on every run, so keying on it would make every rerun miss. A hit can therefore reuse an
+/// answer given against an older MEMORY.md; the partials are still normalized and merged
+/// against the current one.
+pub fn cache_key(model_key: &str, body: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(model_key.as_bytes());
+    hasher.update([0u8]);
+    hasher.update(PROMPT_TEMPLATE_VERSION.to_le_bytes());
+    hasher.update(body.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+pub fn entry_path(paths: &MoonPaths, key: &str) -> PathBuf {
+    cache_dir(paths).join(format!("{key}.md"))
+}
+
+/// The cached summary for `key`; a missing, unreadable or empty entry is a miss. A hit
+/// refreshes the entry's mtime (except under `MOON_READ_ONLY`) so `prune_expired` keeps it.
+pub fn lookup(paths: &MoonPaths, key: &str) -> Option<String> {
+    let path = entry_path(paths, key);
+    let summary = fs::read_to_string(&path)
+        .ok()
+        .filter(|summary| !summary.trim().is_empty())?;
+    if !read_only_mode() {
+        let _ = fs::File::options()
+            .append(true)
+            .open(&path)
+            .and_then(|file| file.set_modified(SystemTime::now()));
+    }
+    Some(summary)
+}
+
+pub fn store(paths: &MoonPaths, key: &str, summary: &str) -> Result<()> {
+    let path = entry_path(paths, key);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+    // Written beside the entry and renamed, so concurrent chunk workers never read half a file.
+    let tmp = path.with_extension(format!("md.tmp-{}", std::process::id()));
+    fs::write(&tmp, summary).with_context(|| format!("failed to write {}", tmp.display()))?;
+    fs::rename(&tmp, &path).with_context(|| format!("failed to write {}", path.display()))?;
+    Ok(())
+}
+
+/// Removes entries last written or hit more than `ttl_secs` before `now`; returns how many
+/// went.
+pub fn prune_expired(paths: &MoonPaths, ttl_secs: u64, now: SystemTime) -> Result<usize> {
+    let dir = cache_dir(paths);
+    let entries = match fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(err) => return Err(err).with_context(|| format!("failed to read {}", dir.display())),
+    };
+    let cutoff = now
+        .checked_sub(Duration::from_secs(ttl_secs))
+        .unwrap_or(SystemTime::UNIX_EPOCH);
+    let mut pruned = 0usize;
+    for entry in entries.flatten() {
+        let expired = entry
+            .metadata()
+            .and_then(|meta| meta.modified())
+            .is_ok_and(|modified| modified < cutoff);
+        if expired && fs::remove_file(entry.path()).is_ok() {
+            pruned += 1;
+        }
+    }
+    Ok(pruned)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ENTRY_TTL_SECS, cache_key, entry_path, lookup, prune_expired, store};
+    use crate::moon::paths::MoonPaths;
+    use std::time::{Duration, SystemTime};
+    use tempfile::tempdir;
+
+    #[test]
+    fn summaries_are_reused_only_for_identical_chunk_and_model() {
+        let tmp = tempdir().expect("tempdir");
+        let paths = MoonPaths::for_test(tmp.path());
+        let key = cache_key("gemini:gemini-2.5-flash", "- decided to ship v2\n");
+        assert_eq!(key.len(), 64);
+        assert_eq!(
+            entry_path(&paths, &key),
+            tmp.path().join(format!("cache/distill/{key}.md"))
+        );
+        assert!(lookup(&paths, &key).is_none());
+
+        store(&paths, &key, "## Lessons Learned\n- ship v2\n").expect("store");
+        assert_eq!(
+            lookup(&paths, &key).as_deref(),
+            Some("## Lessons Learned\n- ship v2\n")
+        );
+        assert!(
+            lookup(
+                &paths,
+                &cache_key("gemini:gemini-2.5-flash", "- decided to ship v3\n")
+            )
+            .is_none()
+        );
+        assert_ne!(
+            key,
+            cache_key("openai:gpt-4.1-mini", "- decided to ship v2\n")
+        );
+    }
+
+    #[test]
+    fn prune_expired_drops_only_entries_past_the_ttl() {
+        let tmp = tempdir().expect("tempdir");
+        let paths = MoonPaths::for_test(tmp.path());
+        let key = cache_key("gemini:gemini-2.5-flash", "prompt");
+        store(&paths, &key, "- kept\n").expect("store");
+        let now = SystemTime::now();
+
+        assert_eq!(
+            prune_expired(&paths, ENTRY_TTL_SECS, now).expect("prune"),
+            0
+        );
+        assert!(lookup(&paths, &key).is_some());
+
+        let later = now + Duration::from_secs(ENTRY_TTL_SECS + 60);
+        assert_eq!(
+            prune_expired(&paths, ENTRY_TTL_SECS, later).expect("prune"),
+            1
+        );
+        assert!(lookup(&paths, &key).is_none());
+    }
+
+    #[test]
+    fn lookup_hit_keeps_the_entry_from_expiring() {
+        let tmp = tempdir().expect("tempdir");
+        let paths = MoonPaths::for_test(tmp.path());
+        let key = cache_key("gemini:gemini-2.5-flash", "- still in use\n");
+        store(&paths, &key, "- reused\n").expect("store");
+        let stale = SystemTime::now() - Duration::from_secs(ENTRY_TTL_SECS + 60);
+        std::fs::File::options()
+            .append(true)
+            .open(entry_path(&paths, &key))
+            .and_then(|file| file.set_modified(stale))
+            .expect("backdate entry");
+
+        assert!(lookup(&paths, &key).is_some());
+        assert_eq!(
+            prune_expired(&paths, ENTRY_TTL_SECS, SystemTime::now()).expect("prune"),
+            0
+        );
+        assert!(lookup(&paths, &key).is_some());
+    }
+}
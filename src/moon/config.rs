@@ -1,29 +1,53 @@
+use crate::moon::notify::{NotifyEvent, NotifySink};
+use crate::moon::snapshot::glob_matches;
+use crate::moon::warn::{self, WarnEvent};
 use anyhow::{Result, anyhow};
+use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::env;
 use std::fs;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 mod generated_env_allowlist {
     include!(concat!(env!("OUT_DIR"), "/moon_env_allowlist.rs"));
 }
 
-pub const SECRET_ENV_KEYS: [&str; 4] = [
+pub const SECRET_ENV_KEYS: [&str; 5] = [
     "GEMINI_API_KEY",
     "OPENAI_API_KEY",
     "ANTHROPIC_API_KEY",
     "AI_API_KEY",
+    "AZURE_OPENAI_API_KEY",
 ];
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MoonThresholds {
+    /// Compaction ratio; archive fires with it unless the archive trigger is enabled.
     pub trigger_ratio: f64,
+    /// Earlier, archive-only ratio; honored when `archive_ratio_trigger_enabled`.
+    pub archive_ratio: f64,
+    pub archive_ratio_trigger_enabled: bool,
 }
 
 impl Default for MoonThresholds {
     fn default() -> Self {
         Self {
             trigger_ratio: 0.85,
+            archive_ratio: 0.70,
+            archive_ratio_trigger_enabled: false,
+        }
+    }
+}
+
+impl MoonThresholds {
+    /// Usage ratio at which an archive fires.
+    pub fn archive_threshold(&self) -> f64 {
+        if self.archive_ratio_trigger_enabled {
+            self.archive_ratio
+        } else {
+            self.trigger_ratio
         }
     }
 }
@@ -32,6 +56,26 @@ impl Default for MoonThresholds {
 pub struct MoonWatcherConfig {
     pub poll_interval_secs: u64,
     pub cooldown_secs: u64,
+    #[serde(default)]
+    pub predictive_trigger: bool,
+    /// Cycle time budget; a cycle still running past it is aborted at the next phase
+    /// boundary. `0` disables the watchdog.
+    #[serde(default = "default_watcher_max_cycle_secs")]
+    pub max_cycle_secs: u64,
+    /// Archive sessions whose file has not changed for this long, whatever their usage.
+    /// `0` disables idle archiving.
+    #[serde(default)]
+    pub idle_archive_secs: u64,
+    /// Run the state/ledger/channel-map consistency check every Nth cycle. `0` disables it.
+    #[serde(default)]
+    pub consistency_check_every: u64,
+    /// Repair what the periodic consistency check finds instead of only reporting it.
+    #[serde(default)]
+    pub consistency_repair: bool,
+}
+
+fn default_watcher_max_cycle_secs() -> u64 {
+    600
 }
 
 impl Default for MoonWatcherConfig {
@@ -39,6 +83,11 @@ impl Default for MoonWatcherConfig {
         Self {
             poll_interval_secs: 30,
             cooldown_secs: 60,
+            predictive_trigger: false,
+            max_cycle_secs: default_watcher_max_cycle_secs(),
+            idle_archive_secs: 0,
+            consistency_check_every: 0,
+            consistency_repair: false,
         }
     }
 }
@@ -49,6 +98,16 @@ pub struct MoonInboundWatchConfig {
     pub recursive: bool,
     pub watch_paths: Vec<String>,
     pub event_mode: String,
+    /// `text` sends `openclaw system event --text`; `json` sends a structured payload via
+    /// `openclaw gateway call wake`.
+    #[serde(default = "default_inbound_event_format")]
+    pub event_format: String,
+}
+
+pub const INBOUND_EVENT_FORMATS: &[&str] = &["text", "json"];
+
+fn default_inbound_event_format() -> String {
+    "text".to_string()
 }
 
 impl Default for MoonInboundWatchConfig {
@@ -58,6 +117,7 @@ impl Default for MoonInboundWatchConfig {
             recursive: true,
             watch_paths: Vec::new(),
             event_mode: "now".to_string(),
+            event_format: default_inbound_event_format(),
         }
     }
 }
@@ -70,26 +130,95 @@ pub struct MoonDistillConfig {
     #[serde(default)]
     pub topic_discovery: bool,
     #[serde(default)]
+    pub graph_extraction: bool,
+    #[serde(default)]
     pub chunk_bytes: Option<String>,
     #[serde(default)]
     pub max_chunks: Option<u64>,
     #[serde(default)]
     pub model_context_tokens: Option<u64>,
+    /// Seconds a provider-reported context limit stays cached in `model-limits.json`; `0` disables.
+    #[serde(default = "default_model_limits_cache_secs")]
+    pub model_limits_cache_secs: u64,
+    /// Estimated remote-provider tokens allowed per residential day; `0` disables the budget.
+    #[serde(default)]
+    pub daily_token_budget: u64,
+    /// `auto` lets `moon watch` distill pending archives; `manual` leaves it to `moon distill`.
+    #[serde(default = "default_distill_mode")]
+    pub mode: String,
+    /// Seconds the newest session file must sit untouched before `moon watch` distills; `0` disables.
+    #[serde(default)]
+    pub idle_secs: u64,
+    /// Distill trigger cooldown; unset shares `[watcher] cooldown_secs`.
+    #[serde(default)]
+    pub cooldown_secs: Option<u64>,
+    /// Synthesis chunks sent to the remote provider at once; `1` keeps them serial.
+    #[serde(default = "default_distill_concurrency")]
+    pub concurrency: u64,
+    /// Synthesis chunk count above which partial summaries get an LLM rollup pass; `0` disables.
+    #[serde(default = "default_distill_rollup_threshold_chunks")]
+    pub rollup_threshold_chunks: u64,
+    /// Provider price per million tokens, for the daily report's cost estimate; `0` omits it.
+    #[serde(default)]
+    pub cost_per_million_tokens: f64,
+}
+
+/// Upper bound for `[distill] concurrency`, so a typo cannot open hundreds of provider connections.
+pub const MAX_DISTILL_CONCURRENCY: u64 = 16;
+
+fn default_distill_concurrency() -> u64 {
+    1
+}
+
+fn default_distill_rollup_threshold_chunks() -> u64 {
+    4
+}
+
+fn default_model_limits_cache_secs() -> u64 {
+    86_400
+}
+
+fn default_distill_mode() -> String {
+    "auto".to_string()
 }
 
 fn default_residential_timezone() -> String {
     "UTC".to_string()
 }
 
+impl MoonDistillConfig {
+    /// Residential timezone used for user-facing dates; unknown or empty names fall back to UTC.
+    pub fn residential_tz(&self) -> Tz {
+        self.residential_timezone
+            .trim()
+            .parse::<Tz>()
+            .unwrap_or(chrono_tz::UTC)
+    }
+
+    /// Synthesis chunk calls to run at once, `concurrency` kept within `1..=MAX_DISTILL_CONCURRENCY`.
+    pub fn chunk_workers(&self) -> usize {
+        self.concurrency.clamp(1, MAX_DISTILL_CONCURRENCY) as usize
+    }
+}
+
 impl Default for MoonDistillConfig {
     fn default() -> Self {
         Self {
             max_per_cycle: 1,
             residential_timezone: "UTC".to_string(),
             topic_discovery: false,
+            graph_extraction: false,
             chunk_bytes: None,
             max_chunks: None,
             model_context_tokens: None,
+            model_limits_cache_secs: default_model_limits_cache_secs(),
+            daily_token_budget: 0,
+            mode: default_distill_mode(),
+            idle_secs: 0,
+            cooldown_secs: None,
+            concurrency: default_distill_concurrency(),
+            rollup_threshold_chunks: default_distill_rollup_threshold_chunks(),
+            cost_per_million_tokens: 0.0,
         }
     }
 }
@@ -99,6 +228,16 @@ pub struct MoonRetentionConfig {
     pub active_days: u64,
     pub warm_days: u64,
     pub cold_days: u64,
+    /// Delete cold archives even when no distilled summary or projection backs them.
+    #[serde(default)]
+    pub force: bool,
+    /// Days a retention-deleted file stays in `archives/trash/` before it is purged for good.
+    #[serde(default = "default_trash_days")]
+    pub trash_days: u64,
+}
+
+fn default_trash_days() -> u64 {
+    14
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -109,6 +248,40 @@ pub struct MoonEmbedConfig {
     pub max_docs_per_cycle: u64,
     pub min_pending_docs: u64,
     pub max_cycle_secs: u64,
+    /// `qmd` (default) lets qmd embed; `openai`, `gemini`, or `openai-compatible` embed
+    /// projection sections remotely into `archives/vectors/`.
+    #[serde(default = "default_embed_provider")]
+    pub provider: String,
+    /// Remote embedding model; empty picks the provider default.
+    #[serde(default)]
+    pub model: String,
+    #[serde(default)]
+    pub base_url: String,
+    /// Projection sections sent per remote embedding request.
+    #[serde(default = "default_embed_batch_size")]
+    pub batch_size: u64,
+    /// Remote request pacing; `0` disables it.
+    #[serde(default = "default_embed_requests_per_minute")]
+    pub requests_per_minute: u64,
+    /// Retries per batch on rate limits, server errors, and transport failures.
+    #[serde(default = "default_embed_max_retries")]
+    pub max_retries: u64,
+}
+
+fn default_embed_provider() -> String {
+    "qmd".to_string()
+}
+
+fn default_embed_batch_size() -> u64 {
+    64
+}
+
+fn default_embed_requests_per_minute() -> u64 {
+    60
+}
+
+fn default_embed_max_retries() -> u64 {
+    3
 }
 
 impl Default for MoonEmbedConfig {
@@ -120,6 +293,12 @@ impl Default for MoonEmbedConfig {
             max_docs_per_cycle: 25,
             min_pending_docs: 1,
             max_cycle_secs: 300,
+            provider: default_embed_provider(),
+            model: String::new(),
+            base_url: String::new(),
+            batch_size: default_embed_batch_size(),
+            requests_per_minute: default_embed_requests_per_minute(),
+            max_retries: default_embed_max_retries(),
         }
     }
 }
@@ -130,6 +309,421 @@ impl Default for MoonRetentionConfig {
             active_days: 7,
             warm_days: 30,
             cold_days: 31,
+            force: false,
+            trash_days: default_trash_days(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MoonMemoryConfig {
+    pub inject_on_new_session: bool,
+    pub primer_max_tokens: u64,
+}
+
+impl Default for MoonMemoryConfig {
+    fn default() -> Self {
+        Self {
+            inject_on_new_session: false,
+            primer_max_tokens: 800,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum MoonToolPriorityLevel {
+    High,
+    Normal,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MoonToolPriorityRule {
+    pub tool: String,
+    pub priority: MoonToolPriorityLevel,
+    #[serde(default)]
+    pub boost: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MoonToolPriorityConfig {
+    pub high_boost: f64,
+    pub normal_boost: f64,
+    pub rules: Vec<MoonToolPriorityRule>,
+}
+
+impl Default for MoonToolPriorityConfig {
+    fn default() -> Self {
+        let rule = |tool: &str, priority| MoonToolPriorityRule {
+            tool: tool.to_string(),
+            priority,
+            boost: None,
+        };
+        Self {
+            high_boost: 1.30,
+            normal_boost: 1.05,
+            rules: vec![
+                rule("write_to_file", MoonToolPriorityLevel::High),
+                rule("exec", MoonToolPriorityLevel::High),
+                rule("edit", MoonToolPriorityLevel::High),
+                rule("gateway", MoonToolPriorityLevel::High),
+                rule("read_file", MoonToolPriorityLevel::Normal),
+                rule("web_search", MoonToolPriorityLevel::Normal),
+                rule("ls", MoonToolPriorityLevel::Normal),
+            ],
+        }
+    }
+}
+
+impl MoonToolPriorityConfig {
+    /// Priority for a tool call by exact (case-insensitive) tool name; unknown tools are normal.
+    pub fn priority_for_tool(&self, tool: &str) -> MoonToolPriorityLevel {
+        self.rules
+            .iter()
+            .find(|rule| rule.tool.eq_ignore_ascii_case(tool))
+            .map(|rule| rule.priority)
+            .unwrap_or(MoonToolPriorityLevel::Normal)
+    }
+
+    pub fn boost_for_rule(&self, rule: &MoonToolPriorityRule) -> f64 {
+        rule.boost.unwrap_or(match rule.priority {
+            MoonToolPriorityLevel::High => self.high_boost,
+            MoonToolPriorityLevel::Normal => self.normal_boost,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MoonCollectionsConfig {
+    pub default: String,
+    /// Session-key prefix -> qmd collection; the longest matching prefix wins.
+    pub channels: BTreeMap<String, String>,
+}
+
+impl Default for MoonCollectionsConfig {
+    fn default() -> Self {
+        Self {
+            default: "history".to_string(),
+            channels: BTreeMap::new(),
+        }
+    }
+}
+
+impl MoonCollectionsConfig {
+    /// Collection that archives for `session_key` are indexed into.
+    pub fn for_session(&self, session_key: Option<&str>) -> &str {
+        session_key
+            .and_then(|key| {
+                self.channels
+                    .iter()
+                    .filter(|(prefix, _)| key.starts_with(prefix.as_str()))
+                    .max_by_key(|(prefix, _)| prefix.len())
+            })
+            .map(|(_, name)| name.as_str())
+            .unwrap_or(&self.default)
+    }
+
+    /// Subdirectory of `raw/` and `mlib/` holding `name`'s archives and projections, so each
+    /// qmd collection indexes only its own; the default collection keeps the top level.
+    pub fn subdir<'a>(&self, name: &'a str) -> Option<&'a str> {
+        (name != self.default).then_some(name)
+    }
+
+    /// Every distinct configured collection, default first.
+    pub fn names(&self) -> Vec<String> {
+        let mut names = vec![self.default.clone()];
+        for name in self.channels.values() {
+            if !names.contains(name) {
+                names.push(name.clone());
+            }
+        }
+        names
+    }
+}
+
+/// How the `/compact` request for a channel is phrased.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct MoonCompactionStrategy {
+    /// Topics the compacted summary should keep, sent as `focus=a,b`.
+    pub focus: Vec<String>,
+    /// Keep the newest N messages verbatim (`keep_last=N`); 0 compacts everything.
+    pub keep_last: u64,
+}
+
+impl MoonCompactionStrategy {
+    /// Strategy arguments as sent after `/compact`, or `None` for a plain `/compact`.
+    fn args(&self) -> Option<String> {
+        let mut args = Vec::new();
+        if !self.focus.is_empty() {
+            args.push(format!("focus={}", self.focus.join(",")));
+        }
+        if self.keep_last > 0 {
+            args.push(format!("keep_last={}", self.keep_last));
+        }
+        (!args.is_empty()).then(|| args.join(" "))
+    }
+
+    pub fn command(&self) -> String {
+        match self.args() {
+            Some(args) => format!("/compact {args}"),
+            None => "/compact".to_string(),
+        }
+    }
+
+    /// Short form recorded in the channel archive map and compaction details.
+    pub fn label(&self) -> String {
+        self.args().unwrap_or_else(|| "default".to_string())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct MoonCompactionConfig {
+    pub default: MoonCompactionStrategy,
+    /// Session-key prefix -> strategy; the longest matching prefix wins.
+    pub channels: BTreeMap<String, MoonCompactionStrategy>,
+}
+
+impl MoonCompactionConfig {
+    pub fn for_session(&self, session_key: &str) -> &MoonCompactionStrategy {
+        self.channels
+            .iter()
+            .filter(|(prefix, _)| session_key.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, strategy)| strategy)
+            .unwrap_or(&self.default)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct MoonSnapshotConfig {
+    pub exclude: Vec<String>,
+}
+
+/// Session-key patterns a channel had to match to be compacted before `[sessions]` existed.
+pub const DEFAULT_COMPACTION_SESSIONS: [&str; 2] = ["*:discord:channel:*", "*:whatsapp:*"];
+
+/// `*`/`?` globs over session keys, matched case-insensitively, choosing which sessions
+/// one pipeline stage handles.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct MoonSessionFilter {
+    /// Sessions the stage handles; unset keeps the stage default.
+    pub include: Option<Vec<String>>,
+    /// Sessions the stage never handles, even when they match `include`.
+    pub exclude: Vec<String>,
+}
+
+impl MoonSessionFilter {
+    fn allows(&self, session_key: &str, default_include: &[&str]) -> bool {
+        let key = session_key.trim().to_ascii_lowercase();
+        let matches = |pattern: &str| {
+            let pattern = pattern.trim().to_ascii_lowercase();
+            !pattern.is_empty() && glob_matches(pattern.as_bytes(), key.as_bytes())
+        };
+        let included = match &self.include {
+            Some(include) => include.iter().any(|pattern| matches(pattern)),
+            None => default_include.iter().any(|pattern| matches(pattern)),
+        };
+        included && !self.exclude.iter().any(|pattern| matches(pattern))
+    }
+
+    fn patterns(&self) -> impl Iterator<Item = &String> {
+        self.include.iter().flatten().chain(&self.exclude)
+    }
+}
+
+/// Per-stage session allow/deny lists for `moon snapshot`, watcher archiving, compaction,
+/// and distillation.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct MoonSessionsConfig {
+    pub snapshot: MoonSessionFilter,
+    pub archive: MoonSessionFilter,
+    /// Defaults to Discord channels and WhatsApp chats (`DEFAULT_COMPACTION_SESSIONS`).
+    pub compaction: MoonSessionFilter,
+    pub distill: MoonSessionFilter,
+}
+
+impl MoonSessionsConfig {
+    pub fn allows_snapshot(&self, session_key: &str) -> bool {
+        self.snapshot.allows(session_key, &["*"])
+    }
+
+    pub fn allows_archive(&self, session_key: &str) -> bool {
+        self.archive.allows(session_key, &["*"])
+    }
+
+    pub fn allows_compaction(&self, session_key: &str) -> bool {
+        self.compaction
+            .allows(session_key, &DEFAULT_COMPACTION_SESSIONS)
+    }
+
+    pub fn allows_distill(&self, session_key: &str) -> bool {
+        self.distill.allows(session_key, &["*"])
+    }
+
+    pub fn filters(&self) -> [(&'static str, &MoonSessionFilter); 4] {
+        [
+            ("snapshot", &self.snapshot),
+            ("archive", &self.archive),
+            ("compaction", &self.compaction),
+            ("distill", &self.distill),
+        ]
+    }
+}
+
+/// Bounds for reading a raw archive into its projection.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MoonProjectionConfig {
+    pub max_scan_bytes: u64,
+    pub max_scan_lines: u64,
+    /// Entries kept in memory and rendered into the projection.
+    pub max_entries: u64,
+    /// Read the whole archive regardless of the scan limits, evenly thinning kept entries to
+    /// `max_entries` so memory stays bounded while coverage spans the full session.
+    pub full_scan: bool,
+    /// Also write the extracted projection data as `mlib/<stem>.projection.json`.
+    pub json_sidecar: bool,
+    /// Mark an archive whose conversation simhash is within `duplicate_max_distance` bits of
+    /// another session's archive as `duplicate_of` it in the ledger.
+    pub duplicate_detection: bool,
+    pub duplicate_max_distance: u64,
+}
+
+impl Default for MoonProjectionConfig {
+    fn default() -> Self {
+        Self {
+            max_scan_bytes: 16 * 1024 * 1024,
+            max_scan_lines: 200_000,
+            max_entries: 2_000,
+            full_scan: false,
+            json_sidecar: false,
+            duplicate_detection: true,
+            duplicate_max_distance: 3,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MoonNotifyConfig {
+    pub discord_webhook_url: String,
+    pub slack_webhook_url: String,
+    pub distill_failure_threshold: u64,
+    /// Event type -> sink names; events without a route go to every configured sink.
+    pub routes: BTreeMap<String, Vec<String>>,
+}
+
+impl Default for MoonNotifyConfig {
+    fn default() -> Self {
+        Self {
+            discord_webhook_url: String::new(),
+            slack_webhook_url: String::new(),
+            distill_failure_threshold: 3,
+            routes: BTreeMap::new(),
+        }
+    }
+}
+
+/// User shell commands run after pipeline events; an empty command disables the hook.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MoonHooksConfig {
+    pub post_archive: String,
+    pub post_distill: String,
+    pub post_compaction: String,
+    pub retention_delete: String,
+    pub timeout_secs: u64,
+}
+
+impl Default for MoonHooksConfig {
+    fn default() -> Self {
+        Self {
+            post_archive: String::new(),
+            post_distill: String::new(),
+            post_compaction: String::new(),
+            retention_delete: String::new(),
+            timeout_secs: 30,
+        }
+    }
+}
+
+/// Optional Lua script that overrides the watcher's trigger decision.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct MoonPolicyConfig {
+    pub script: String,
+}
+
+/// Channels whose archives are kept out of projections, qmd, and recall.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct MoonPrivacyConfig {
+    /// `*`/`?` globs over session keys, matched case-insensitively.
+    pub channels: Vec<String>,
+    /// Seal private raw archives with `MOON_PRIVACY_KEY`.
+    pub encrypt: bool,
+}
+
+impl MoonPrivacyConfig {
+    /// The first pattern marking `session_key` private, if any.
+    pub fn matching_pattern(&self, session_key: &str) -> Option<&str> {
+        let key = session_key.trim().to_ascii_lowercase();
+        if key.is_empty() {
+            return None;
+        }
+        self.channels
+            .iter()
+            .map(|pattern| pattern.trim())
+            .find(|pattern| {
+                !pattern.is_empty()
+                    && glob_matches(pattern.to_ascii_lowercase().as_bytes(), key.as_bytes())
+            })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct MoonReportConfig {
+    pub daily: bool,
+    pub notify: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MoonRecallConfig {
+    /// Budget for a `moon recall` search; past it the deterministic and cached matches are
+    /// returned as degraded. `0` waits for qmd.
+    pub deadline_ms: u64,
+}
+
+impl Default for MoonRecallConfig {
+    fn default() -> Self {
+        Self {
+            deadline_ms: 10_000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MoonUsageConfig {
+    /// Layout of OpenClaw's `sessions --json` payloads: `auto` probes the declared
+    /// `schemaVersion` first and falls back to the other known layouts; `v1`/`v2` pin one.
+    pub schema: String,
+}
+
+impl Default for MoonUsageConfig {
+    fn default() -> Self {
+        Self {
+            schema: "auto".to_string(),
         }
     }
 }
@@ -194,6 +788,34 @@ pub struct MoonConfig {
     pub distill: MoonDistillConfig,
     pub retention: MoonRetentionConfig,
     pub embed: MoonEmbedConfig,
+    #[serde(default)]
+    pub memory: MoonMemoryConfig,
+    #[serde(default)]
+    pub collections: MoonCollectionsConfig,
+    #[serde(default)]
+    pub compaction: MoonCompactionConfig,
+    #[serde(default)]
+    pub snapshot: MoonSnapshotConfig,
+    #[serde(default)]
+    pub sessions: MoonSessionsConfig,
+    #[serde(default)]
+    pub projection: MoonProjectionConfig,
+    #[serde(default)]
+    pub tool_priority: MoonToolPriorityConfig,
+    #[serde(default)]
+    pub report: MoonReportConfig,
+    #[serde(default)]
+    pub recall: MoonRecallConfig,
+    #[serde(default)]
+    pub usage: MoonUsageConfig,
+    #[serde(default)]
+    pub notify: MoonNotifyConfig,
+    #[serde(default)]
+    pub hooks: MoonHooksConfig,
+    #[serde(default)]
+    pub policy: MoonPolicyConfig,
+    #[serde(default)]
+    pub privacy: MoonPrivacyConfig,
     pub context: Option<MoonContextConfig>,
 }
 
@@ -205,6 +827,20 @@ struct PartialMoonConfig {
     distill: Option<MoonDistillConfig>,
     retention: Option<MoonRetentionConfig>,
     embed: Option<MoonEmbedConfig>,
+    memory: Option<MoonMemoryConfig>,
+    collections: Option<MoonCollectionsConfig>,
+    compaction: Option<MoonCompactionConfig>,
+    snapshot: Option<MoonSnapshotConfig>,
+    sessions: Option<MoonSessionsConfig>,
+    projection: Option<MoonProjectionConfig>,
+    tool_priority: Option<MoonToolPriorityConfig>,
+    report: Option<MoonReportConfig>,
+    recall: Option<MoonRecallConfig>,
+    usage: Option<MoonUsageConfig>,
+    notify: Option<MoonNotifyConfig>,
+    hooks: Option<MoonHooksConfig>,
+    policy: Option<MoonPolicyConfig>,
+    privacy: Option<MoonPrivacyConfig>,
     context: Option<MoonContextConfig>,
 }
 
@@ -214,8 +850,7 @@ struct PartialMoonThresholds {
     archive_ratio: Option<f64>,
     #[serde(alias = "prune_ratio")]
     compaction_ratio: Option<f64>,
-    #[serde(rename = "archive_ratio_trigger_enabled")]
-    _archive_ratio_trigger_enabled: Option<bool>,
+    archive_ratio_trigger_enabled: Option<bool>,
 }
 
 fn env_or_f64_first(vars: &[&str], fallback: f64) -> f64 {
@@ -296,6 +931,14 @@ fn validate(cfg: &MoonConfig) -> Result<()> {
     if !(trigger > 0.0 && trigger <= 1.0) {
         return Err(anyhow!("invalid trigger ratio: require 0 < trigger <= 1.0"));
     }
+    if cfg.thresholds.archive_ratio_trigger_enabled {
+        let archive = cfg.thresholds.archive_ratio;
+        if !(archive > 0.0 && archive <= trigger) {
+            return Err(anyhow!(
+                "invalid archive ratio: require 0 < archive_ratio <= trigger_ratio when archive_ratio_trigger_enabled"
+            ));
+        }
+    }
     if cfg.watcher.poll_interval_secs == 0 {
         return Err(anyhow!(
             "invalid watcher poll interval: must be >= 1 second"
@@ -304,14 +947,39 @@ fn validate(cfg: &MoonConfig) -> Result<()> {
     if cfg.inbound_watch.event_mode.trim().is_empty() {
         return Err(anyhow!("invalid inbound event mode: cannot be empty"));
     }
+    if !INBOUND_EVENT_FORMATS.contains(&cfg.inbound_watch.event_format.as_str()) {
+        return Err(anyhow!(
+            "invalid inbound event format `{}`: expected one of {}",
+            cfg.inbound_watch.event_format,
+            INBOUND_EVENT_FORMATS.join(", ")
+        ));
+    }
+    crate::moon::session_usage::parse_usage_schema(&cfg.usage.schema)?;
     if cfg.distill.max_per_cycle == 0 {
         return Err(anyhow!("invalid distill max per cycle: must be >= 1"));
     }
+    if !matches!(cfg.distill.mode.as_str(), "auto" | "manual") {
+        return Err(anyhow!(
+            "invalid distill mode `{}`: use `auto` or `manual`",
+            cfg.distill.mode
+        ));
+    }
     if let Some(max_chunks) = cfg.distill.max_chunks
         && max_chunks == 0
     {
         return Err(anyhow!("invalid distill max_chunks: must be >= 1"));
     }
+    if !cfg.distill.cost_per_million_tokens.is_finite() || cfg.distill.cost_per_million_tokens < 0.0
+    {
+        return Err(anyhow!(
+            "invalid distill cost_per_million_tokens: must be a number >= 0"
+        ));
+    }
+    if !(1..=MAX_DISTILL_CONCURRENCY).contains(&cfg.distill.concurrency) {
+        return Err(anyhow!(
+            "invalid distill concurrency: must be between 1 and {MAX_DISTILL_CONCURRENCY}"
+        ));
+    }
     if let Some(chunk_bytes) = &cfg.distill.chunk_bytes {
         let trimmed = chunk_bytes.trim();
         if !trimmed.is_empty()
@@ -353,6 +1021,133 @@ fn validate(cfg: &MoonConfig) -> Result<()> {
     if cfg.embed.max_cycle_secs == 0 {
         return Err(anyhow!("invalid embed max cycle secs: must be >= 1"));
     }
+    if crate::moon::vectors::EmbedProvider::parse(&cfg.embed.provider).is_none() {
+        return Err(anyhow!(
+            "invalid embed provider: use `qmd`, `openai`, `gemini`, or `openai-compatible`"
+        ));
+    }
+    if cfg.embed.batch_size == 0 {
+        return Err(anyhow!("invalid embed batch size: must be >= 1"));
+    }
+    if cfg.memory.primer_max_tokens == 0 {
+        return Err(anyhow!("invalid memory primer max tokens: must be >= 1"));
+    }
+    if cfg.notify.distill_failure_threshold == 0 {
+        return Err(anyhow!(
+            "invalid notify distill_failure_threshold: must be >= 1"
+        ));
+    }
+    for (event, sinks) in &cfg.notify.routes {
+        if NotifyEvent::parse(event).is_none() {
+            return Err(anyhow!(
+                "invalid notify route `{event}`: use distill_failures, retention_undistilled, or daemon_restart"
+            ));
+        }
+        if let Some(sink) = sinks.iter().find(|s| NotifySink::parse(s).is_none()) {
+            return Err(anyhow!(
+                "invalid notify sink `{sink}` for `{event}`: use discord or slack"
+            ));
+        }
+    }
+    if cfg.hooks.timeout_secs == 0 {
+        return Err(anyhow!("invalid hooks timeout_secs: must be >= 1"));
+    }
+    if cfg.collections.default.trim().is_empty() {
+        return Err(anyhow!("invalid collections default: cannot be empty"));
+    }
+    for (prefix, name) in &cfg.collections.channels {
+        if prefix.trim().is_empty() || name.trim().is_empty() {
+            return Err(anyhow!(
+                "invalid collections channel mapping: prefix and collection name cannot be empty"
+            ));
+        }
+    }
+    if let Some(name) = cfg.collections.names().into_iter().find(|name| {
+        !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    }) {
+        return Err(anyhow!(
+            "invalid collection name `{name}`: use letters, digits, `_` or `-`"
+        ));
+    }
+    if cfg
+        .collections
+        .names()
+        .iter()
+        .any(|name| name.trim() == crate::moon::qmd::MEMORY_COLLECTION)
+    {
+        return Err(anyhow!(
+            "invalid collections: `{}` is reserved for distilled daily memory",
+            crate::moon::qmd::MEMORY_COLLECTION
+        ));
+    }
+    let strategies = std::iter::once(("default", &cfg.compaction.default)).chain(
+        cfg.compaction
+            .channels
+            .iter()
+            .map(|(prefix, strategy)| (prefix.as_str(), strategy)),
+    );
+    for (prefix, strategy) in strategies {
+        if prefix.trim().is_empty() {
+            return Err(anyhow!(
+                "invalid compaction channel mapping: prefix cannot be empty"
+            ));
+        }
+        if let Some(topic) = strategy.focus.iter().find(|topic| {
+            topic.is_empty()
+                || !topic
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        }) {
+            return Err(anyhow!(
+                "invalid compaction focus `{topic}` for `{prefix}`: use letters, digits, `_` or `-`"
+            ));
+        }
+    }
+    if cfg.projection.max_scan_bytes == 0
+        || cfg.projection.max_scan_lines == 0
+        || cfg.projection.max_entries == 0
+    {
+        return Err(anyhow!(
+            "invalid projection limits: max_scan_bytes, max_scan_lines, and max_entries must be >= 1"
+        ));
+    }
+    if cfg.projection.duplicate_max_distance > 16 {
+        return Err(anyhow!(
+            "invalid projection.duplicate_max_distance: must be <= 16 of the 64 simhash bits"
+        ));
+    }
+    if cfg.snapshot.exclude.iter().any(|p| p.trim().is_empty()) {
+        return Err(anyhow!(
+            "invalid snapshot exclude: patterns cannot be empty"
+        ));
+    }
+    let boosts = [
+        ("high_boost", cfg.tool_priority.high_boost),
+        ("normal_boost", cfg.tool_priority.normal_boost),
+    ]
+    .into_iter()
+    .chain(
+        cfg.tool_priority
+            .rules
+            .iter()
+            .filter_map(|rule| rule.boost.map(|boost| (rule.tool.as_str(), boost))),
+    );
+    for (name, boost) in boosts {
+        if !boost.is_finite() || boost <= 0.0 {
+            return Err(anyhow!(
+                "invalid tool_priority boost for `{name}`: must be a number > 0"
+            ));
+        }
+    }
+    for (stage, filter) in cfg.sessions.filters() {
+        if filter.patterns().any(|p| p.trim().is_empty()) {
+            return Err(anyhow!(
+                "invalid sessions.{stage}: patterns cannot be empty"
+            ));
+        }
+    }
     if let Some(context) = &cfg.context {
         if matches!(context.window_mode, MoonContextWindowMode::Fixed) {
             let Some(window_tokens) = context.window_tokens else {
@@ -410,6 +1205,26 @@ pub fn resolve_config_path() -> Option<PathBuf> {
     Some(home.join("moon").join("moon.toml"))
 }
 
+fn merge_thresholds(base: &mut MoonThresholds, thresholds: PartialMoonThresholds) {
+    if let Some(enabled) = thresholds.archive_ratio_trigger_enabled {
+        base.archive_ratio_trigger_enabled = enabled;
+    }
+    if let Some(archive_ratio) = thresholds.archive_ratio {
+        base.archive_ratio = archive_ratio;
+    }
+    // A lone `archive_ratio` is the legacy spelling of the shared trigger ratio.
+    let legacy_archive = thresholds
+        .archive_ratio
+        .filter(|_| !base.archive_ratio_trigger_enabled);
+    if let Some(trigger_ratio) = thresholds
+        .trigger_ratio
+        .or(thresholds.compaction_ratio)
+        .or(legacy_archive)
+    {
+        base.trigger_ratio = trigger_ratio;
+    }
+}
+
 fn merge_file_config(base: &mut MoonConfig) -> Result<()> {
     let Some(path) = resolve_config_path() else {
         return Ok(());
@@ -421,13 +1236,8 @@ fn merge_file_config(base: &mut MoonConfig) -> Result<()> {
     let raw = fs::read_to_string(&path)?;
     let parsed: PartialMoonConfig = toml::from_str(&raw)
         .map_err(|err| anyhow!("failed to parse moon config {}: {err}", path.display()))?;
-    if let Some(thresholds) = parsed.thresholds
-        && let Some(trigger_ratio) = thresholds
-            .trigger_ratio
-            .or(thresholds.compaction_ratio)
-            .or(thresholds.archive_ratio)
-    {
-        base.thresholds.trigger_ratio = trigger_ratio;
+    if let Some(thresholds) = parsed.thresholds {
+        merge_thresholds(&mut base.thresholds, thresholds);
     }
     if let Some(watcher) = parsed.watcher {
         base.watcher = watcher;
@@ -444,34 +1254,161 @@ fn merge_file_config(base: &mut MoonConfig) -> Result<()> {
     if let Some(embed) = parsed.embed {
         base.embed = embed;
     }
+    if let Some(memory) = parsed.memory {
+        base.memory = memory;
+    }
+    if let Some(collections) = parsed.collections {
+        base.collections = collections;
+    }
+    if let Some(compaction) = parsed.compaction {
+        base.compaction = compaction;
+    }
+    if let Some(snapshot) = parsed.snapshot {
+        base.snapshot = snapshot;
+    }
+    if let Some(sessions) = parsed.sessions {
+        base.sessions = sessions;
+    }
+    if let Some(projection) = parsed.projection {
+        base.projection = projection;
+    }
+    if let Some(tool_priority) = parsed.tool_priority {
+        base.tool_priority = tool_priority;
+    }
+    if let Some(report) = parsed.report {
+        base.report = report;
+    }
+    if let Some(recall) = parsed.recall {
+        base.recall = recall;
+    }
+    if let Some(usage) = parsed.usage {
+        base.usage = usage;
+    }
+    if let Some(notify) = parsed.notify {
+        base.notify = notify;
+    }
+    if let Some(hooks) = parsed.hooks {
+        base.hooks = hooks;
+    }
+    if let Some(policy) = parsed.policy {
+        base.policy = policy;
+    }
+    if let Some(privacy) = parsed.privacy {
+        base.privacy = privacy;
+    }
     if let Some(context) = parsed.context {
         base.context = Some(context);
     }
     Ok(())
 }
 
+/// Residential timezone from the resolved config, or UTC when the config cannot be loaded.
+pub fn resolve_residential_tz() -> Tz {
+    load_config()
+        .map(|cfg| cfg.distill.residential_tz())
+        .unwrap_or(chrono_tz::UTC)
+}
+
+static LOCAL_TIMEZONE_WARNED: AtomicBool = AtomicBool::new(false);
+
+/// Timezone `MOON_LOCAL_TIMEZONE` stands in for, or why it is ignored.
+///
+/// It is an alias for `MOON_RESIDENTIAL_TIMEZONE` when that is unset and the value
+/// is an IANA name; offsets such as `+09:00` are not timezones.
+fn local_timezone_alias(
+    local: &str,
+    residential_env: Option<&str>,
+) -> std::result::Result<String, &'static str> {
+    if residential_env.is_some_and(|value| !value.trim().is_empty()) {
+        return Err("residential-timezone-set");
+    }
+    let local = local.trim();
+    match local.parse::<Tz>() {
+        Ok(_) => Ok(local.to_string()),
+        Err(_) => Err("not-an-iana-timezone"),
+    }
+}
+
+fn apply_local_timezone_alias(distill: &mut MoonDistillConfig) {
+    let Some(local) = env::var("MOON_LOCAL_TIMEZONE")
+        .ok()
+        .filter(|value| !value.trim().is_empty())
+    else {
+        return;
+    };
+    let residential_env = env::var("MOON_RESIDENTIAL_TIMEZONE").ok();
+    let reason = match local_timezone_alias(&local, residential_env.as_deref()) {
+        Ok(tz) => {
+            distill.residential_timezone = tz;
+            "aliased-to-residential-timezone"
+        }
+        Err(reason) => reason,
+    };
+    if LOCAL_TIMEZONE_WARNED.swap(true, Ordering::Relaxed) {
+        return;
+    }
+    warn::emit(WarnEvent {
+        code: "LOCAL_TIMEZONE_DEPRECATED",
+        stage: "config",
+        action: "read-env",
+        session: "na",
+        archive: "na",
+        source: "MOON_LOCAL_TIMEZONE",
+        retry: "none",
+        reason,
+        err: &local,
+    });
+}
+
 pub fn load_config() -> Result<MoonConfig> {
     let mut cfg = MoonConfig::default();
     merge_file_config(&mut cfg)?;
 
-    cfg.thresholds.trigger_ratio = env_or_f64_first(
-        &[
-            "MOON_TRIGGER_RATIO",
-            "MOON_THRESHOLD_COMPACTION_RATIO",
-            "MOON_THRESHOLD_PRUNE_RATIO",
-            "MOON_THRESHOLD_ARCHIVE_RATIO",
-        ],
-        cfg.thresholds.trigger_ratio,
+    cfg.thresholds.archive_ratio_trigger_enabled = env_or_bool(
+        "MOON_ARCHIVE_RATIO_TRIGGER_ENABLED",
+        cfg.thresholds.archive_ratio_trigger_enabled,
+    );
+    cfg.thresholds.archive_ratio = env_or_f64_first(
+        &["MOON_THRESHOLD_ARCHIVE_RATIO"],
+        cfg.thresholds.archive_ratio,
     );
+    let mut trigger_vars = vec![
+        "MOON_TRIGGER_RATIO",
+        "MOON_THRESHOLD_COMPACTION_RATIO",
+        "MOON_THRESHOLD_PRUNE_RATIO",
+    ];
+    if !cfg.thresholds.archive_ratio_trigger_enabled {
+        trigger_vars.push("MOON_THRESHOLD_ARCHIVE_RATIO");
+    }
+    cfg.thresholds.trigger_ratio = env_or_f64_first(&trigger_vars, cfg.thresholds.trigger_ratio);
     cfg.watcher.poll_interval_secs =
         env_or_u64("MOON_POLL_INTERVAL_SECS", cfg.watcher.poll_interval_secs);
     cfg.watcher.cooldown_secs = env_or_u64("MOON_COOLDOWN_SECS", cfg.watcher.cooldown_secs);
+    cfg.watcher.max_cycle_secs =
+        env_or_u64("MOON_WATCHER_MAX_CYCLE_SECS", cfg.watcher.max_cycle_secs);
+    cfg.watcher.predictive_trigger =
+        env_or_bool("MOON_PREDICTIVE_TRIGGER", cfg.watcher.predictive_trigger);
+    cfg.watcher.idle_archive_secs = env_or_u64(
+        "MOON_WATCHER_IDLE_ARCHIVE_SECS",
+        cfg.watcher.idle_archive_secs,
+    );
+    cfg.watcher.consistency_check_every = env_or_u64(
+        "MOON_WATCHER_CONSISTENCY_CHECK_EVERY",
+        cfg.watcher.consistency_check_every,
+    );
+    cfg.watcher.consistency_repair = env_or_bool(
+        "MOON_WATCHER_CONSISTENCY_REPAIR",
+        cfg.watcher.consistency_repair,
+    );
     cfg.inbound_watch.enabled =
         env_or_bool("MOON_INBOUND_WATCH_ENABLED", cfg.inbound_watch.enabled);
     cfg.inbound_watch.recursive =
         env_or_bool("MOON_INBOUND_RECURSIVE", cfg.inbound_watch.recursive);
     cfg.inbound_watch.event_mode =
         env_or_string("MOON_INBOUND_EVENT_MODE", &cfg.inbound_watch.event_mode);
+    cfg.inbound_watch.event_format =
+        env_or_string("MOON_INBOUND_EVENT_FORMAT", &cfg.inbound_watch.event_format)
+            .to_ascii_lowercase();
     cfg.inbound_watch.watch_paths =
         env_or_csv_paths("MOON_INBOUND_WATCH_PATHS", &cfg.inbound_watch.watch_paths);
     cfg.distill.max_per_cycle = env_or_u64("MOON_DISTILL_MAX_PER_CYCLE", cfg.distill.max_per_cycle);
@@ -479,10 +1416,41 @@ pub fn load_config() -> Result<MoonConfig> {
         "MOON_RESIDENTIAL_TIMEZONE",
         &cfg.distill.residential_timezone,
     );
+    apply_local_timezone_alias(&mut cfg.distill);
     cfg.distill.topic_discovery = env_or_bool("MOON_TOPIC_DISCOVERY", cfg.distill.topic_discovery);
+    cfg.distill.graph_extraction =
+        env_or_bool("MOON_GRAPH_EXTRACTION", cfg.distill.graph_extraction);
+    cfg.distill.model_limits_cache_secs = env_or_u64(
+        "MOON_DISTILL_MODEL_LIMITS_CACHE_SECS",
+        cfg.distill.model_limits_cache_secs,
+    );
+    cfg.distill.daily_token_budget = env_or_u64(
+        "MOON_DISTILL_DAILY_TOKEN_BUDGET",
+        cfg.distill.daily_token_budget,
+    );
+    cfg.distill.mode = env_or_string("MOON_DISTILL_MODE", &cfg.distill.mode)
+        .trim()
+        .to_ascii_lowercase();
+    cfg.distill.idle_secs = env_or_u64("MOON_DISTILL_IDLE_SECS", cfg.distill.idle_secs);
+    cfg.distill.concurrency = env_or_u64("MOON_DISTILL_CONCURRENCY", cfg.distill.concurrency);
+    cfg.distill.rollup_threshold_chunks = env_or_u64(
+        "MOON_DISTILL_ROLLUP_THRESHOLD_CHUNKS",
+        cfg.distill.rollup_threshold_chunks,
+    );
+    cfg.distill.cost_per_million_tokens = env_or_f64_first(
+        &["MOON_DISTILL_COST_PER_MILLION_TOKENS"],
+        cfg.distill.cost_per_million_tokens,
+    );
+    if let Ok(raw) = env::var("MOON_DISTILL_COOLDOWN_SECS")
+        && let Ok(secs) = raw.trim().parse::<u64>()
+    {
+        cfg.distill.cooldown_secs = Some(secs);
+    }
     cfg.retention.active_days = env_or_u64("MOON_RETENTION_ACTIVE_DAYS", cfg.retention.active_days);
     cfg.retention.warm_days = env_or_u64("MOON_RETENTION_WARM_DAYS", cfg.retention.warm_days);
     cfg.retention.cold_days = env_or_u64("MOON_RETENTION_COLD_DAYS", cfg.retention.cold_days);
+    cfg.retention.force = env_or_bool("MOON_RETENTION_FORCE", cfg.retention.force);
+    cfg.retention.trash_days = env_or_u64("MOON_RETENTION_TRASH_DAYS", cfg.retention.trash_days);
     cfg.embed.mode = env_or_string("MOON_EMBED_MODE", &cfg.embed.mode);
     cfg.embed.idle_secs = env_or_u64("MOON_EMBED_IDLE_SECS", cfg.embed.idle_secs);
     cfg.embed.cooldown_secs = env_or_u64("MOON_EMBED_COOLDOWN_SECS", cfg.embed.cooldown_secs);
@@ -493,7 +1461,63 @@ pub fn load_config() -> Result<MoonConfig> {
     cfg.embed.min_pending_docs =
         env_or_u64("MOON_EMBED_MIN_PENDING_DOCS", cfg.embed.min_pending_docs);
     cfg.embed.max_cycle_secs = env_or_u64("MOON_EMBED_MAX_CYCLE_SECS", cfg.embed.max_cycle_secs);
+    cfg.embed.provider = env_or_string("MOON_EMBED_PROVIDER", &cfg.embed.provider);
+    cfg.embed.model = env_or_string("MOON_EMBED_MODEL", &cfg.embed.model);
+    cfg.embed.base_url = env_or_string("MOON_EMBED_BASE_URL", &cfg.embed.base_url);
+    cfg.embed.batch_size = env_or_u64("MOON_EMBED_BATCH_SIZE", cfg.embed.batch_size);
+    cfg.embed.requests_per_minute = env_or_u64(
+        "MOON_EMBED_REQUESTS_PER_MINUTE",
+        cfg.embed.requests_per_minute,
+    );
+    cfg.embed.max_retries = env_or_u64("MOON_EMBED_MAX_RETRIES", cfg.embed.max_retries);
     cfg.embed.mode = normalize_embed_mode(&cfg.embed.mode);
+    cfg.memory.inject_on_new_session = env_or_bool(
+        "MOON_MEMORY_INJECT_ON_NEW_SESSION",
+        cfg.memory.inject_on_new_session,
+    );
+    cfg.memory.primer_max_tokens = env_or_u64(
+        "MOON_MEMORY_PRIMER_MAX_TOKENS",
+        cfg.memory.primer_max_tokens,
+    );
+    cfg.collections.default = env_or_string("MOON_ARCHIVE_COLLECTION", &cfg.collections.default);
+    cfg.snapshot.exclude = env_or_csv_paths("MOON_SNAPSHOT_EXCLUDE", &cfg.snapshot.exclude);
+    cfg.projection.max_scan_bytes = env_or_u64(
+        "MOON_PROJECTION_MAX_SCAN_BYTES",
+        cfg.projection.max_scan_bytes,
+    );
+    cfg.projection.max_scan_lines = env_or_u64(
+        "MOON_PROJECTION_MAX_SCAN_LINES",
+        cfg.projection.max_scan_lines,
+    );
+    cfg.projection.max_entries =
+        env_or_u64("MOON_PROJECTION_MAX_ENTRIES", cfg.projection.max_entries);
+    cfg.projection.full_scan = env_or_bool("MOON_PROJECTION_FULL_SCAN", cfg.projection.full_scan);
+    cfg.projection.json_sidecar =
+        env_or_bool("MOON_PROJECTION_JSON_SIDECAR", cfg.projection.json_sidecar);
+    cfg.projection.duplicate_detection = env_or_bool(
+        "MOON_PROJECTION_DUPLICATE_DETECTION",
+        cfg.projection.duplicate_detection,
+    );
+    cfg.projection.duplicate_max_distance = env_or_u64(
+        "MOON_PROJECTION_DUPLICATE_MAX_DISTANCE",
+        cfg.projection.duplicate_max_distance,
+    );
+    cfg.report.daily = env_or_bool("MOON_REPORT_DAILY", cfg.report.daily);
+    cfg.report.notify = env_or_bool("MOON_REPORT_NOTIFY", cfg.report.notify);
+    cfg.recall.deadline_ms = env_or_u64("MOON_RECALL_DEADLINE_MS", cfg.recall.deadline_ms);
+    cfg.usage.schema = env_or_string("MOON_USAGE_SCHEMA", &cfg.usage.schema);
+    cfg.notify.discord_webhook_url =
+        env_or_string("MOON_DISCORD_WEBHOOK_URL", &cfg.notify.discord_webhook_url);
+    cfg.notify.slack_webhook_url =
+        env_or_string("MOON_SLACK_WEBHOOK_URL", &cfg.notify.slack_webhook_url);
+    cfg.notify.distill_failure_threshold = env_or_u64(
+        "MOON_NOTIFY_DISTILL_FAILURE_THRESHOLD",
+        cfg.notify.distill_failure_threshold,
+    );
+    cfg.hooks.timeout_secs = env_or_u64("MOON_HOOKS_TIMEOUT_SECS", cfg.hooks.timeout_secs);
+    cfg.policy.script = env_or_string("MOON_POLICY_SCRIPT", &cfg.policy.script);
+    cfg.privacy.channels = env_or_csv_paths("MOON_PRIVATE_CHANNELS", &cfg.privacy.channels);
+    cfg.privacy.encrypt = env_or_bool("MOON_PRIVACY_ENCRYPT", cfg.privacy.encrypt);
 
     validate(&cfg)?;
     audit_env_vars();
@@ -609,7 +1633,31 @@ pub fn load_context_policy_if_explicit_env() -> Result<Option<MoonContextConfig>
 
 #[cfg(test)]
 mod tests {
-    use super::mask_secret;
+    use super::{
+        MoonCollectionsConfig, MoonCompactionConfig, MoonConfig, MoonPrivacyConfig,
+        MoonSessionsConfig, MoonThresholds, MoonToolPriorityConfig, MoonToolPriorityLevel,
+        PartialMoonThresholds, local_timezone_alias, mask_secret, merge_thresholds, validate,
+    };
+
+    #[test]
+    fn local_timezone_aliases_residential_timezone_only_when_unset_and_named() {
+        assert_eq!(
+            local_timezone_alias("Asia/Tokyo", None),
+            Ok("Asia/Tokyo".to_string())
+        );
+        assert_eq!(
+            local_timezone_alias(" Asia/Tokyo ", Some("")),
+            Ok("Asia/Tokyo".to_string())
+        );
+        assert_eq!(
+            local_timezone_alias("Asia/Tokyo", Some("Europe/Berlin")),
+            Err("residential-timezone-set")
+        );
+        assert_eq!(
+            local_timezone_alias("+09:00", None),
+            Err("not-an-iana-timezone")
+        );
+    }
 
     #[test]
     fn mask_secret_unset_and_short_values() {
@@ -621,4 +1669,133 @@ mod tests {
     fn mask_secret_keeps_prefix_and_suffix() {
         assert_eq!(mask_secret("sk-1234567890abcdef"), "sk-...cdef");
     }
+
+    #[test]
+    fn tool_priority_boosts_must_be_positive_numbers() {
+        assert!(validate(&MoonConfig::default()).is_ok());
+        for bad in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            let mut cfg = MoonConfig::default();
+            cfg.tool_priority.high_boost = bad;
+            assert!(validate(&cfg).is_err(), "high_boost={bad}");
+
+            let mut cfg = MoonConfig::default();
+            cfg.tool_priority.normal_boost = bad;
+            assert!(validate(&cfg).is_err(), "normal_boost={bad}");
+
+            let mut cfg = MoonConfig::default();
+            cfg.tool_priority.rules[0].boost = Some(bad);
+            let err = validate(&cfg).expect_err("rule boost");
+            assert!(err.to_string().contains("`write_to_file`"));
+        }
+    }
+
+    #[test]
+    fn tool_priority_rules_parse_with_per_rule_boosts() {
+        let cfg: MoonToolPriorityConfig = toml::from_str(
+            "normal_boost = 1.1\n[[rules]]\ntool = \"Deploy\"\npriority = \"high\"\nboost = 2.0\n[[rules]]\ntool = \"grep\"\npriority = \"normal\"\n",
+        )
+        .expect("parse tool priority");
+        assert_eq!(cfg.priority_for_tool("deploy"), MoonToolPriorityLevel::High);
+        assert_eq!(cfg.priority_for_tool("exec"), MoonToolPriorityLevel::Normal);
+        assert_eq!(cfg.boost_for_rule(&cfg.rules[0]), 2.0);
+        assert_eq!(cfg.boost_for_rule(&cfg.rules[1]), 1.1);
+        assert_eq!(cfg.high_boost, 1.30);
+    }
+
+    #[test]
+    fn privacy_channels_match_session_key_globs_case_insensitively() {
+        let cfg: MoonPrivacyConfig =
+            toml::from_str("channels = [\"agent:*:discord:channel:hr-*\", \" \"]\n")
+                .expect("parse privacy");
+        assert!(!cfg.encrypt);
+        assert_eq!(
+            cfg.matching_pattern("agent:main:discord:channel:HR-payroll"),
+            Some("agent:*:discord:channel:hr-*")
+        );
+        assert_eq!(cfg.matching_pattern("agent:main:discord:channel:ops"), None);
+        assert_eq!(cfg.matching_pattern(""), None);
+    }
+
+    #[test]
+    fn sessions_filters_default_compaction_to_channels_and_let_exclude_win() {
+        let defaults = MoonSessionsConfig::default();
+        assert!(defaults.allows_compaction("agent:main:discord:channel:ops"));
+        assert!(defaults.allows_compaction("agent:main:whatsapp:+61400000000"));
+        assert!(!defaults.allows_compaction("agent:main:main"));
+        assert!(defaults.allows_archive("agent:main:main"));
+        assert!(defaults.allows_distill("agent:main:main"));
+
+        let cfg: MoonSessionsConfig = toml::from_str(
+            "[compaction]\nexclude = [\"*:channel:OPS\"]\n\n[archive]\ninclude = [\"agent:main:*\"]\nexclude = [\"agent:main:main\"]\n",
+        )
+        .expect("parse sessions");
+        assert!(!cfg.allows_compaction("agent:main:discord:channel:ops"));
+        assert!(cfg.allows_compaction("agent:main:discord:channel:dev"));
+        assert!(!cfg.allows_archive("agent:main:main"));
+        assert!(cfg.allows_archive("agent:main:discord:channel:dev"));
+        assert!(!cfg.allows_archive("agent:ops:main"));
+        assert!(cfg.allows_snapshot("agent:ops:main"));
+    }
+
+    #[test]
+    fn thresholds_split_archive_ratio_only_when_trigger_enabled() {
+        let merged = |raw: &str| {
+            let mut base = MoonThresholds::default();
+            let partial: PartialMoonThresholds = toml::from_str(raw).expect("parse thresholds");
+            merge_thresholds(&mut base, partial);
+            base
+        };
+
+        let legacy = merged("archive_ratio = 0.6\n");
+        assert_eq!(legacy.trigger_ratio, 0.6);
+        assert_eq!(legacy.archive_threshold(), 0.6);
+
+        let split = merged(
+            "archive_ratio = 0.6\nprune_ratio = 0.9\narchive_ratio_trigger_enabled = true\n",
+        );
+        assert_eq!(split.trigger_ratio, 0.9);
+        assert_eq!(split.archive_threshold(), 0.6);
+
+        let enabled_only = merged("archive_ratio_trigger_enabled = true\n");
+        assert_eq!(enabled_only.trigger_ratio, 0.85);
+        assert_eq!(enabled_only.archive_threshold(), 0.70);
+    }
+
+    #[test]
+    fn collections_route_by_longest_channel_prefix() {
+        let cfg: MoonCollectionsConfig = toml::from_str(
+            "[channels]\n\"agent:main:discord:\" = \"discord\"\n\"agent:main:discord:channel:ops\" = \"ops\"\n",
+        )
+        .expect("parse collections");
+        assert_eq!(cfg.default, "history");
+        assert_eq!(cfg.for_session(None), "history");
+        assert_eq!(cfg.for_session(Some("agent:main:main")), "history");
+        assert_eq!(
+            cfg.for_session(Some("agent:main:discord:channel:general")),
+            "discord"
+        );
+        assert_eq!(
+            cfg.for_session(Some("agent:main:discord:channel:ops")),
+            "ops"
+        );
+        assert_eq!(cfg.names(), vec!["history", "discord", "ops"]);
+    }
+
+    #[test]
+    fn compaction_strategy_routes_by_prefix_and_renders_command() {
+        let cfg: MoonCompactionConfig = toml::from_str(
+            "[channels.\"agent:main:discord:\"]\nfocus = [\"decisions\", \"tasks\"]\nkeep_last = 20\n",
+        )
+        .expect("parse compaction");
+        let plain = cfg.for_session("agent:main:main");
+        assert_eq!(plain.command(), "/compact");
+        assert_eq!(plain.label(), "default");
+
+        let discord = cfg.for_session("agent:main:discord:channel:ops");
+        assert_eq!(
+            discord.command(),
+            "/compact focus=decisions,tasks keep_last=20"
+        );
+        assert_eq!(discord.label(), "focus=decisions,tasks keep_last=20");
+    }
 }
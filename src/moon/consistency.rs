@@ -0,0 +1,318 @@
+use crate::moon::archive::{read_ledger_records, remove_ledger_records};
+use crate::moon::channel_archive_map;
+use crate::moon::paths::MoonPaths;
+use crate::moon::state::MoonState;
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+/// A reference between state, ledger, channel archive map, and disk that no longer resolves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ConsistencyKind {
+    /// Ledger row whose raw archive file is gone.
+    LedgerArchiveMissing,
+    /// Ledger row whose projection file is gone; `moon index` rebuilds it.
+    LedgerProjectionMissing,
+    /// `state.distilled_archives` entry for an archive with no live ledger row.
+    StateDistilledDangling,
+    /// `state.embedded_projections` entry for a projection file that is gone.
+    StateEmbeddedMissing,
+    /// `state.retention_protected_archives` entry for an archive with no live ledger row.
+    StateProtectedDangling,
+    /// Channel archive map entry pointing at a file that is gone.
+    MapArchiveMissing,
+}
+
+impl ConsistencyKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::LedgerArchiveMissing => "ledger-archive-missing",
+            Self::LedgerProjectionMissing => "ledger-projection-missing",
+            Self::StateDistilledDangling => "state-distilled-dangling",
+            Self::StateEmbeddedMissing => "state-embedded-missing",
+            Self::StateProtectedDangling => "state-protected-dangling",
+            Self::MapArchiveMissing => "map-archive-missing",
+        }
+    }
+
+    /// Whether [`repair`] fixes this kind; missing projections are rebuilt by `moon index`.
+    pub fn repairable(self) -> bool {
+        !matches!(self, Self::LedgerProjectionMissing)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConsistencyFinding {
+    pub kind: ConsistencyKind,
+    /// Archive or projection path, or the channel key for map entries.
+    pub subject: String,
+    /// The dangling path the subject refers to.
+    pub target: String,
+}
+
+impl ConsistencyFinding {
+    pub fn detail(&self) -> String {
+        if self.subject == self.target {
+            format!("kind={} path={}", self.kind.as_str(), self.subject)
+        } else {
+            format!(
+                "kind={} key={} path={}",
+                self.kind.as_str(),
+                self.subject,
+                self.target
+            )
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct RepairOutcome {
+    pub ledger_removed: usize,
+    pub state_removed: usize,
+    pub map_repointed: usize,
+    pub map_removed: usize,
+}
+
+impl RepairOutcome {
+    pub fn summary(&self) -> String {
+        format!(
+            "ledger_removed={} state_removed={} map_repointed={} map_removed={}",
+            self.ledger_removed, self.state_removed, self.map_repointed, self.map_removed
+        )
+    }
+}
+
+/// Count of findings per kind name, in kind order.
+pub fn kind_counts(findings: &[ConsistencyFinding]) -> Vec<(&'static str, usize)> {
+    let mut counts = BTreeMap::<ConsistencyKind, usize>::new();
+    for finding in findings {
+        *counts.entry(finding.kind).or_default() += 1;
+    }
+    counts
+        .into_iter()
+        .map(|(kind, count)| (kind.as_str(), count))
+        .collect()
+}
+
+/// Count of findings per kind, as `kind=N` pairs in kind order.
+pub fn summarize(findings: &[ConsistencyFinding]) -> String {
+    let counts = kind_counts(findings);
+    if counts.is_empty() {
+        return "none".to_string();
+    }
+    counts
+        .into_iter()
+        .map(|(kind, count)| format!("{kind}={count}"))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Cross-references `state`, the ledger, the channel archive map, and the files they name.
+///
+/// State entries are checked against ledger rows whose raw archive still exists, so one repair
+/// pass also clears state left behind by the ledger rows it drops.
+pub fn check(paths: &MoonPaths, state: &MoonState) -> Result<Vec<ConsistencyFinding>> {
+    let mut findings = Vec::new();
+    let mut live_archives = BTreeSet::new();
+    for record in read_ledger_records(paths)? {
+        if !Path::new(&record.archive_path).exists() {
+            findings.push(ConsistencyFinding {
+                kind: ConsistencyKind::LedgerArchiveMissing,
+                subject: record.archive_path.clone(),
+                target: record.archive_path.clone(),
+            });
+            continue;
+        }
+        if let Some(projection) = &record.projection_path
+            && !Path::new(projection).exists()
+        {
+            findings.push(ConsistencyFinding {
+                kind: ConsistencyKind::LedgerProjectionMissing,
+                subject: record.archive_path.clone(),
+                target: projection.clone(),
+            });
+        }
+        live_archives.insert(record.archive_path);
+    }
+
+    for (kind, archives) in [
+        (
+            ConsistencyKind::StateDistilledDangling,
+            &state.distilled_archives,
+        ),
+        (
+            ConsistencyKind::StateProtectedDangling,
+            &state.retention_protected_archives,
+        ),
+    ] {
+        for archive_path in archives.keys() {
+            if !live_archives.contains(archive_path) {
+                findings.push(ConsistencyFinding {
+                    kind,
+                    subject: archive_path.clone(),
+                    target: archive_path.clone(),
+                });
+            }
+        }
+    }
+    for projection in state.embedded_projections.keys() {
+        if !Path::new(projection).exists() {
+            findings.push(ConsistencyFinding {
+                kind: ConsistencyKind::StateEmbeddedMissing,
+                subject: projection.clone(),
+                target: projection.clone(),
+            });
+        }
+    }
+
+    for (channel_key, record) in channel_archive_map::load(paths)? {
+        if !Path::new(&record.archive_path).exists() {
+            findings.push(ConsistencyFinding {
+                kind: ConsistencyKind::MapArchiveMissing,
+                subject: channel_key,
+                target: record.archive_path,
+            });
+        }
+    }
+
+    Ok(findings)
+}
+
+/// Drops or repoints every repairable finding. Ledger and channel map changes are written
+/// here; `state` is only updated in memory and the caller saves it.
+pub fn repair(
+    paths: &MoonPaths,
+    state: &mut MoonState,
+    findings: &[ConsistencyFinding],
+) -> Result<RepairOutcome> {
+    let mut outcome = RepairOutcome::default();
+    let mut ledger_paths = BTreeSet::new();
+    let mut map_targets = BTreeMap::new();
+    for finding in findings {
+        let removed = match finding.kind {
+            ConsistencyKind::LedgerArchiveMissing => {
+                ledger_paths.insert(finding.subject.clone());
+                false
+            }
+            ConsistencyKind::LedgerProjectionMissing => false,
+            ConsistencyKind::StateDistilledDangling => {
+                state.distilled_archives.remove(&finding.subject).is_some()
+            }
+            ConsistencyKind::StateProtectedDangling => state
+                .retention_protected_archives
+                .remove(&finding.subject)
+                .is_some(),
+            ConsistencyKind::StateEmbeddedMissing => state
+                .embedded_projections
+                .remove(&finding.subject)
+                .is_some(),
+            ConsistencyKind::MapArchiveMissing => {
+                map_targets.insert(finding.target.clone(), None);
+                false
+            }
+        };
+        if removed {
+            outcome.state_removed += 1;
+        }
+    }
+
+    outcome.ledger_removed = remove_ledger_records(paths, &ledger_paths)?;
+    // Same fallback as retention: the channel's continuity records, else drop the entry.
+    let retired = channel_archive_map::retire_archive_paths(paths, &map_targets)?;
+    outcome.map_repointed = retired.repointed;
+    outcome.map_removed = retired.removed;
+    Ok(outcome)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ConsistencyKind, check, repair, summarize};
+    use crate::moon::archive::read_ledger_records;
+    use crate::moon::channel_archive_map;
+    use crate::moon::paths::MoonPaths;
+    use crate::moon::state::MoonState;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn check_and_repair_drop_dangling_references() {
+        let tmp = tempdir().expect("tempdir");
+        let paths = MoonPaths::for_test(tmp.path());
+        let raw_dir = paths.archives_dir.join("raw");
+        fs::create_dir_all(&raw_dir).expect("mkdir raw");
+        let live = raw_dir.join("live.jsonl");
+        let gone = raw_dir.join("gone.jsonl");
+        fs::write(&live, "{}\n").expect("write live archive");
+        let live = live.display().to_string();
+        let gone = gone.display().to_string();
+        let missing_projection = paths
+            .archives_dir
+            .join("mlib/live.md")
+            .display()
+            .to_string();
+        let ledger = [
+            serde_json::json!({
+                "session_id": "live", "source_path": "/tmp/live.jsonl", "archive_path": live,
+                "projection_path": missing_projection, "content_hash": "a",
+                "created_at_epoch_secs": 1, "indexed_collection": "history", "indexed": true
+            }),
+            serde_json::json!({
+                "session_id": "gone", "source_path": "/tmp/gone.jsonl", "archive_path": gone,
+                "projection_path": null, "content_hash": "b",
+                "created_at_epoch_secs": 2, "indexed_collection": "history", "indexed": true
+            }),
+        ]
+        .iter()
+        .map(|row| format!("{row}\n"))
+        .collect::<String>();
+        fs::write(paths.archives_dir.join("ledger.jsonl"), ledger).expect("write ledger");
+        channel_archive_map::upsert(
+            &paths,
+            "agent:main:discord:channel:ops",
+            "/tmp/gone.jsonl",
+            &gone,
+            None,
+        )
+        .expect("map gone");
+        channel_archive_map::upsert(
+            &paths,
+            "agent:main:discord:channel:dev",
+            "/tmp/live.jsonl",
+            &live,
+            None,
+        )
+        .expect("map live");
+
+        let mut state = MoonState::default();
+        state.distilled_archives.insert(live.clone(), 10);
+        state.distilled_archives.insert(gone.clone(), 10);
+        state
+            .embedded_projections
+            .insert("/nonexistent/p.md".to_string(), 10);
+
+        let findings = check(&paths, &state).expect("check");
+        assert_eq!(
+            summarize(&findings),
+            "ledger-archive-missing=1,ledger-projection-missing=1,state-distilled-dangling=1,state-embedded-missing=1,map-archive-missing=1"
+        );
+        assert!(!ConsistencyKind::LedgerProjectionMissing.repairable());
+
+        let outcome = repair(&paths, &mut state, &findings).expect("repair");
+        assert_eq!(
+            outcome.summary(),
+            "ledger_removed=1 state_removed=2 map_repointed=0 map_removed=1"
+        );
+        assert_eq!(read_ledger_records(&paths).expect("ledger").len(), 1);
+        assert!(state.distilled_archives.contains_key(&live));
+        assert!(state.embedded_projections.is_empty());
+        let map = channel_archive_map::load(&paths).expect("map");
+        assert_eq!(
+            map.keys().collect::<Vec<_>>(),
+            ["agent:main:discord:channel:dev"]
+        );
+
+        let remaining = check(&paths, &state).expect("recheck");
+        assert_eq!(summarize(&remaining), "ledger-projection-missing=1");
+    }
+}
@@ -4,6 +4,8 @@ use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
 use std::process::Command;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -16,6 +18,18 @@ pub struct ContinuityMap {
     pub generated_at_epoch_secs: u64,
 }
 
+/// One hand-over for a channel: `compaction` keeps the session, `rollover` replaces it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContinuityRecord {
+    pub channel_key: String,
+    pub reason: String,
+    pub source_session_id: String,
+    pub target_session_id: String,
+    pub archive_path: Option<String>,
+    pub summary_path: Option<String>,
+    pub recorded_at_epoch_secs: u64,
+}
+
 #[derive(Debug, Clone)]
 pub struct ContinuityOutcome {
     pub map_path: String,
@@ -113,3 +127,72 @@ pub fn build_continuity(
         rollover_ok,
     })
 }
+
+pub fn records_path(paths: &MoonPaths) -> PathBuf {
+    paths.moon_home.join("continuity").join("records.jsonl")
+}
+
+pub fn append_record(paths: &MoonPaths, record: &ContinuityRecord) -> Result<()> {
+    let path = records_path(paths);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("failed to open {}", path.display()))?;
+    writeln!(file, "{}", serde_json::to_string(record)?)
+        .with_context(|| format!("failed to write {}", path.display()))?;
+    Ok(())
+}
+
+/// Records for `channel_key`, oldest first; unparseable lines are skipped.
+pub fn read_records(paths: &MoonPaths, channel_key: &str) -> Result<Vec<ContinuityRecord>> {
+    let path = records_path(paths);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let raw =
+        fs::read_to_string(&path).with_context(|| format!("failed to read {}", path.display()))?;
+    Ok(raw
+        .lines()
+        .filter_map(|line| serde_json::from_str::<ContinuityRecord>(line).ok())
+        .filter(|record| record.channel_key == channel_key)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ContinuityRecord, append_record, read_records};
+    use crate::moon::paths::MoonPaths;
+    use tempfile::tempdir;
+
+    fn record(channel_key: &str, reason: &str, target: &str) -> ContinuityRecord {
+        ContinuityRecord {
+            channel_key: channel_key.to_string(),
+            reason: reason.to_string(),
+            source_session_id: "sess-a".to_string(),
+            target_session_id: target.to_string(),
+            archive_path: Some("/tmp/raw/sess-a.jsonl".to_string()),
+            summary_path: None,
+            recorded_at_epoch_secs: 1,
+        }
+    }
+
+    #[test]
+    fn read_records_filters_by_channel_in_append_order() {
+        let tmp = tempdir().expect("tempdir");
+        let paths = MoonPaths::for_test(tmp.path());
+        append_record(&paths, &record("chan:a", "compaction", "sess-a")).expect("append");
+        append_record(&paths, &record("chan:b", "compaction", "sess-a")).expect("append");
+        append_record(&paths, &record("chan:a", "rollover", "sess-b")).expect("append");
+
+        let records = read_records(&paths, "chan:a").expect("read");
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].reason, "compaction");
+        assert_eq!(records[1].target_session_id, "sess-b");
+        assert!(read_records(&paths, "chan:c").expect("read").is_empty());
+    }
+}
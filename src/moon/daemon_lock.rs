@@ -1,3 +1,4 @@
+use crate::moon::build_info::BuildInfo;
 use crate::moon::paths::MoonPaths;
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
@@ -15,6 +16,8 @@ pub struct DaemonLockPayload {
     pub build_uuid: String,
     #[serde(default)]
     pub moon_home: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub build: Option<BuildInfo>,
 }
 
 pub fn daemon_lock_path(paths: &MoonPaths) -> PathBuf {
@@ -38,6 +41,7 @@ pub fn parse_daemon_lock_payload(raw: &str) -> Option<DaemonLockPayload> {
         started_at_epoch_secs: 0,
         build_uuid: String::new(),
         moon_home: String::new(),
+        build: None,
     })
 }
 
@@ -1,25 +1,47 @@
+use crate::moon::archive::TimeRangeLabels;
 use crate::moon::audit;
-use crate::moon::paths::MoonPaths;
-use crate::moon::util::{now_epoch_secs, truncate_with_ellipsis};
+use crate::moon::budget;
+use crate::moon::chunk_cache;
+use crate::moon::config::{
+    MoonDistillConfig, MoonProjectionConfig, MoonToolPriorityConfig, MoonToolPriorityLevel,
+    load_config, resolve_residential_tz,
+};
+use crate::moon::graph;
+use crate::moon::hooks::{self, HookEvent};
+use crate::moon::memory::{
+    MEMORY_CONFLICTS_HEADING, apply_memory_decay, bullet_similarity, bullet_terms,
+    ensure_memory_baseline, memory_bullets, record_memory_snapshot,
+};
+use crate::moon::model_limits;
+use crate::moon::paths::{MoonPaths, resolve_paths};
+use crate::moon::session_file::{open_session_reader, read_session_bytes, session_extension};
+use crate::moon::util::{STOPWORDS, now_epoch_secs, read_only_mode, truncate_with_ellipsis};
+use crate::moon::vectors::{self, RemoteEmbedder};
+use crate::moon::warn::{self, WarnEvent};
 use anyhow::{Context, Result};
-use chrono::{Datelike, Local, TimeZone};
+use chrono::{Datelike, TimeZone, Utc};
+use chrono_tz::Tz;
 use fs2::FileExt;
 use reqwest::blocking::Client;
+use serde::de::{DeserializeSeed, Deserializer, IgnoredAny, MapAccess, SeqAccess, Visitor};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use sha2::{Digest, Sha256};
+use std::borrow::Cow;
+use std::cell::Cell;
 use std::collections::{BTreeMap, BTreeSet};
 use std::env;
 use std::fs;
-use std::io::{BufRead, BufReader, ErrorKind, Write};
-use std::path::Path;
+use std::io::{BufRead, ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
 use std::sync::OnceLock;
 
 #[derive(Debug, Clone)]
-pub struct DistillInput {
+pub struct DistillInput<'a> {
     pub session_id: String,
     pub archive_path: String,
-    pub archive_text: String,
+    /// Borrowed when the caller already holds the text (e.g. a streamed archive chunk).
+    pub archive_text: Cow<'a, str>,
     pub archive_epoch_secs: Option<u64>,
 }
 
@@ -30,6 +52,314 @@ pub struct DistillOutput {
     pub summary_path: String,
     pub audit_log_path: String,
     pub created_at_epoch_secs: u64,
+    #[serde(default)]
+    pub memory_conflicts: Vec<MemoryConflict>,
+    #[serde(default)]
+    pub expired_memory: Vec<String>,
+    /// Estimated tokens sent to and received from a remote provider.
+    #[serde(default)]
+    pub remote_tokens: u64,
+    /// Source chunks summarised; `1` for single-pass L1 normalisation.
+    #[serde(default)]
+    pub chunk_count: usize,
+    /// Wall time of each chunk's provider call in chunk order (`0` for skipped chunks);
+    /// empty when nothing was sent to a remote provider.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub chunk_durations_ms: Vec<u64>,
+    /// Model rollup passes over the chunk summaries; `0` when they were merged directly.
+    #[serde(default)]
+    pub rollup_levels: usize,
+    /// Set when the configured remote provider failed and its output was replaced, in whole or
+    /// in part, by local or surviving-chunk output.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider_fallback: Option<ProviderFallback>,
+}
+
+/// Coarse cause of a remote provider failure, so a broken API key is told apart from an outage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ProviderErrorClass {
+    Auth,
+    Quota,
+    Network,
+    SanitizeReject,
+    Other,
+}
+
+impl ProviderErrorClass {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ProviderErrorClass::Auth => "auth",
+            ProviderErrorClass::Quota => "quota",
+            ProviderErrorClass::Network => "network",
+            ProviderErrorClass::SanitizeReject => "sanitize-reject",
+            ProviderErrorClass::Other => "other",
+        }
+    }
+
+    fn from_status(status: u16) -> Self {
+        match status {
+            401 | 403 => ProviderErrorClass::Auth,
+            402 | 429 => ProviderErrorClass::Quota,
+            408 | 500..=599 => ProviderErrorClass::Network,
+            _ => ProviderErrorClass::Other,
+        }
+    }
+
+    /// Classifies a remote call error from its transport error or the `status NNN` in its text.
+    pub fn classify(err: &anyhow::Error) -> Self {
+        for cause in err.chain() {
+            if let Some(http) = cause.downcast_ref::<reqwest::Error>() {
+                if let Some(status) = http.status() {
+                    return Self::from_status(status.as_u16());
+                }
+                if http.is_timeout() || http.is_connect() || http.is_request() {
+                    return ProviderErrorClass::Network;
+                }
+            }
+        }
+        let text = format!("{err:#}").to_ascii_lowercase();
+        if let Some(status) = text
+            .split("status ")
+            .skip(1)
+            .find_map(|rest| rest.get(..3)?.parse::<u16>().ok())
+        {
+            return Self::from_status(status);
+        }
+        if text.contains("api key") || text.contains("unauthorized") {
+            ProviderErrorClass::Auth
+        } else if text.contains("quota") || text.contains("rate limit") {
+            ProviderErrorClass::Quota
+        } else {
+            ProviderErrorClass::Other
+        }
+    }
+}
+
+/// A remote provider failure that distillation recovered from instead of failing the run.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProviderFallback {
+    /// Remote provider that failed, e.g. `gemini`.
+    pub provider: String,
+    pub error_class: ProviderErrorClass,
+    pub error: String,
+    /// Chunks whose remote call failed; `0` when the whole summary fell back.
+    #[serde(default)]
+    pub failed_chunks: usize,
+}
+
+impl ProviderFallback {
+    /// The error text is kept for the audit log, so the API key (Gemini puts it in the request
+    /// URL) is masked first.
+    fn new(remote: &RemoteModelConfig, err: &anyhow::Error, failed_chunks: usize) -> Self {
+        let mut error = format!("{err:#}");
+        if !remote.api_key.is_empty() {
+            error = error.replace(&remote.api_key, "***");
+        }
+        Self {
+            provider: remote.provider.label().to_string(),
+            error_class: ProviderErrorClass::classify(err),
+            error: truncate_with_ellipsis(&error, MAX_FALLBACK_ERROR_CHARS),
+            failed_chunks,
+        }
+    }
+
+    fn sanitize_reject(remote: &RemoteModelConfig) -> Self {
+        Self {
+            provider: remote.provider.label().to_string(),
+            error_class: ProviderErrorClass::SanitizeReject,
+            error: "model output rejected by summary sanitizer".to_string(),
+            failed_chunks: 0,
+        }
+    }
+
+    pub fn detail(&self) -> String {
+        format!(
+            "from={} error_class={} failed_chunks={} error={}",
+            self.provider,
+            self.error_class.as_str(),
+            self.failed_chunks,
+            self.error
+        )
+    }
+
+    /// Adds `fallback_*` fields to a distill audit event; `moon status` and the daily report
+    /// count fallback rates from them.
+    fn annotate_audit(fallback: Option<&Self>, message: &mut String, details: &mut Value) {
+        let Some(fallback) = fallback else {
+            return;
+        };
+        message.push_str(&format!(
+            " fallback_from={} fallback_error_class={}",
+            fallback.provider,
+            fallback.error_class.as_str()
+        ));
+        if let Value::Object(map) = details {
+            map.insert(
+                "fallback_from".to_string(),
+                fallback.provider.clone().into(),
+            );
+            map.insert(
+                "fallback_error_class".to_string(),
+                fallback.error_class.as_str().into(),
+            );
+            map.insert(
+                "fallback_failed_chunks".to_string(),
+                fallback.failed_chunks.into(),
+            );
+            map.insert("fallback_error".to_string(), fallback.error.clone().into());
+        }
+    }
+}
+
+/// Result of the minimal authenticated call `moon config check-keys` makes per provider key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyStatus {
+    Valid,
+    /// 401/403: the key is wrong, revoked or expired.
+    Rejected,
+    /// 402/429: the key works but has no quota or credit left.
+    NoQuota,
+    Unreachable,
+    Failed,
+}
+
+impl KeyStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            KeyStatus::Valid => "valid",
+            KeyStatus::Rejected => "rejected",
+            KeyStatus::NoQuota => "no-quota",
+            KeyStatus::Unreachable => "unreachable",
+            KeyStatus::Failed => "failed",
+        }
+    }
+
+    pub fn from_error(err: &anyhow::Error) -> Self {
+        match ProviderErrorClass::classify(err) {
+            ProviderErrorClass::Auth => KeyStatus::Rejected,
+            ProviderErrorClass::Quota => KeyStatus::NoQuota,
+            ProviderErrorClass::Network => KeyStatus::Unreachable,
+            ProviderErrorClass::SanitizeReject | ProviderErrorClass::Other => KeyStatus::Failed,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ProviderKeyCheck {
+    /// `distill`, `wisdom` or `embed`.
+    pub role: &'static str,
+    pub provider: String,
+    pub model: String,
+    pub masked_key: String,
+    pub status: KeyStatus,
+    /// Error text with the key masked; `None` when valid.
+    pub error: Option<String>,
+    pub duration_ms: u128,
+}
+
+impl ProviderKeyCheck {
+    pub fn from_outcome(
+        role: &'static str,
+        provider: &str,
+        model: &str,
+        api_key: &str,
+        duration_ms: u128,
+        outcome: Result<()>,
+    ) -> Self {
+        let (status, error) = match outcome {
+            Ok(()) => (KeyStatus::Valid, None),
+            Err(err) => {
+                let mut text = format!("{err:#}");
+                if !api_key.is_empty() {
+                    text = text.replace(api_key, "***");
+                }
+                (
+                    KeyStatus::from_error(&err),
+                    Some(truncate_with_ellipsis(&text, MAX_FALLBACK_ERROR_CHARS)),
+                )
+            }
+        };
+        Self {
+            role,
+            provider: provider.to_string(),
+            model: model.to_string(),
+            masked_key: crate::moon::config::mask_secret(api_key),
+            status,
+            error,
+            duration_ms,
+        }
+    }
+}
+
+const KEY_CHECK_PROMPT: &str = "Reply with the single word OK.";
+
+/// Sends a one-line prompt with each configured distill and wisdom model. A model shared by
+/// both roles is called once. Wisdom resolution errors (other than no provider at all) are
+/// returned as `Err` entries so the caller can report the misconfiguration.
+pub fn check_distill_keys() -> Vec<(&'static str, Result<ProviderKeyCheck>)> {
+    let mut roles = Vec::new();
+    if let Some(remote) = resolve_remote_config() {
+        roles.push(("distill", Ok(remote)));
+    }
+    if env_non_empty("MOON_WISDOM_PROVIDER").is_some() {
+        match resolve_wisdom_remote_config() {
+            Ok(Some(remote)) => roles.push(("wisdom", Ok(remote))),
+            Ok(None) => {}
+            Err(err) => roles.push(("wisdom", Err(err))),
+        }
+    }
+
+    let mut checked: Vec<(RemoteModelConfig, ProviderKeyCheck)> = Vec::new();
+    let mut out = Vec::new();
+    for (role, remote) in roles {
+        let remote = match remote {
+            Ok(remote) => remote,
+            Err(err) => {
+                out.push((role, Err(err)));
+                continue;
+            }
+        };
+        let same = |other: &RemoteModelConfig| {
+            other.provider == remote.provider
+                && other.model == remote.model
+                && other.api_key == remote.api_key
+                && other.base_url == remote.base_url
+        };
+        if let Some((_, previous)) = checked.iter().find(|(other, _)| same(other)) {
+            out.push((
+                role,
+                Ok(ProviderKeyCheck {
+                    role,
+                    ..previous.clone()
+                }),
+            ));
+            continue;
+        }
+        let started = std::time::Instant::now();
+        let outcome = call_remote_prompt(&remote, KEY_CHECK_PROMPT).map(|_| ());
+        let check = ProviderKeyCheck::from_outcome(
+            role,
+            remote.provider.label(),
+            &remote.model,
+            &remote.api_key,
+            started.elapsed().as_millis(),
+            outcome,
+        );
+        checked.push((remote, check.clone()));
+        out.push((role, Ok(check)));
+    }
+    out
+}
+
+/// A new synthesis bullet that looks like it contradicts an existing MEMORY.md bullet.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MemoryConflict {
+    pub existing: String,
+    pub incoming: String,
+    pub similarity: f64,
+    /// True when the configured synthesis model confirmed the contradiction.
+    pub model_confirmed: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -42,6 +372,9 @@ pub struct ChunkedDistillOutput {
     pub chunk_count: usize,
     pub chunk_target_bytes: usize,
     pub truncated: bool,
+    /// Wall time of each chunk in chunk order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub chunk_durations_ms: Vec<u64>,
 }
 
 #[derive(Debug, Clone)]
@@ -50,6 +383,10 @@ pub struct WisdomDistillInput {
     pub day_epoch_secs: Option<u64>,
     pub source_paths: Vec<String>,
     pub dry_run: bool,
+    /// Chunk calls in flight at once (`MoonDistillConfig::chunk_workers`).
+    pub concurrency: usize,
+    /// `[distill] rollup_threshold_chunks`; `0` disables the rollup pass.
+    pub rollup_threshold_chunks: usize,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -76,6 +413,9 @@ pub struct ProjectionData {
     pub filtered_noise_count: usize,
     pub truncated: bool,
     pub compaction_anchors: Vec<CompactionAnchor>,
+    /// Full-scan mode keeps every `sample_stride`-th entry once `max_entries` is reached.
+    #[serde(default)]
+    pub sample_stride: usize,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -99,6 +439,12 @@ pub struct ProjectionEntry {
     pub tool_target: Option<String>,
     pub priority: Option<ToolPriority>,
     pub coupled_result: Option<String>,
+    /// 1-based raw-archive line (event index for whole-document `.json` archives).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_line: Option<usize>,
+    /// Byte offset of `source_line` in the raw archive; `None` for whole-document archives.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_byte_offset: Option<u64>,
 }
 
 pub trait Distiller {
@@ -123,6 +469,17 @@ pub struct OpenAiCompatDistiller {
     pub model: String,
     pub base_url: String,
 }
+pub struct OllamaDistiller {
+    pub model: String,
+    pub base_url: String,
+}
+pub struct AzureOpenAiDistiller {
+    pub api_key: String,
+    /// Resource endpoint, e.g. `https://<resource>.openai.azure.com`.
+    pub endpoint: String,
+    pub deployment: String,
+    pub api_version: String,
+}
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum RemoteProvider {
@@ -130,15 +487,28 @@ enum RemoteProvider {
     Anthropic,
     Gemini,
     OpenAiCompatible,
+    Ollama,
+    AzureOpenAi,
 }
 
 impl RemoteProvider {
+    const ALL: [Self; 6] = [
+        RemoteProvider::OpenAi,
+        RemoteProvider::Anthropic,
+        RemoteProvider::Gemini,
+        RemoteProvider::OpenAiCompatible,
+        RemoteProvider::Ollama,
+        RemoteProvider::AzureOpenAi,
+    ];
+
     fn label(self) -> &'static str {
         match self {
             RemoteProvider::OpenAi => "openai",
             RemoteProvider::Anthropic => "anthropic",
             RemoteProvider::Gemini => "gemini",
             RemoteProvider::OpenAiCompatible => "openai-compatible",
+            RemoteProvider::Ollama => "ollama",
+            RemoteProvider::AzureOpenAi => "azure-openai",
         }
     }
 }
@@ -153,6 +523,7 @@ struct RemoteModelConfig {
 
 const SIGNAL_KEYWORDS: [&str; 5] = ["decision", "rule", "todo", "next", "milestone"];
 const MAX_SIGNAL_LINES: usize = 20;
+const MAX_FALLBACK_ERROR_CHARS: usize = 240;
 const MAX_FALLBACK_LINES: usize = 12;
 const MAX_CANDIDATE_CHARS: usize = 512;
 const MAX_SUMMARY_CHARS: usize = 12_000;
@@ -160,6 +531,10 @@ const MAX_PROMPT_LINES: usize = 80;
 const MAX_MODEL_LINES: usize = 80;
 const MIN_MODEL_BULLETS: usize = 3;
 const REQUEST_TIMEOUT_SECS: u64 = 45;
+/// Local models on CPU can take minutes per prompt, so Ollama calls get a longer budget.
+const OLLAMA_REQUEST_TIMEOUT_SECS: u64 = 300;
+const DEFAULT_OLLAMA_BASE_URL: &str = "http://127.0.0.1:11434";
+const DEFAULT_AZURE_OPENAI_API_VERSION: &str = "2024-10-21";
 const DEFAULT_DISTILL_CHUNK_BYTES: usize = 512 * 1024;
 const DEFAULT_DISTILL_MAX_CHUNKS: usize = 128;
 const DEFAULT_AUTO_CONTEXT_TOKENS: u64 = 250_000;
@@ -169,14 +544,16 @@ const AUTO_CHUNK_BYTES_PER_TOKEN: f64 = 3.0;
 const AUTO_CHUNK_SAFETY_RATIO: f64 = 0.60;
 const MAX_ROLLUP_LINES_PER_SECTION: usize = 30;
 const MAX_ROLLUP_TOTAL_LINES: usize = 120;
-const MAX_ARCHIVE_SCAN_BYTES: usize = 16 * 1024 * 1024;
-const MAX_ARCHIVE_SCAN_LINES: usize = 200_000;
-const MAX_ARCHIVE_CANDIDATES: usize = 2_000;
 const MAX_WISDOM_LINES: usize = 240;
 const MAX_WISDOM_ITEMS_PER_SECTION: usize = 8;
 const WISDOM_CONTEXT_SAFETY_RATIO: f64 = 0.90;
 const WISDOM_PROMPT_OVERHEAD_BYTES: usize = 8 * 1024;
 const WISDOM_MIN_DAILY_CHUNK_BYTES: usize = 16 * 1024;
+const MAX_WISDOM_ROLLUP_LEVELS: usize = 3;
+const MEMORY_CONFLICT_MIN_SIMILARITY: f64 = 0.5;
+const MEMORY_CONFLICT_MIN_SHARED_TERMS: usize = 2;
+const MEMORY_CONFLICT_MIN_VECTOR_SIMILARITY: f64 = 0.8;
+const MAX_MEMORY_CONFLICTS: usize = 8;
 const DAILY_MEMORY_FORMAT_MARKER: &str = "<!-- moon_memory_format: conversation_v1 -->";
 const SESSION_BLOCK_BEGIN_PREFIX: &str = "<!-- MOON_SESSION_BEGIN:";
 const SESSION_BLOCK_END_PREFIX: &str = "<!-- MOON_SESSION_END:";
@@ -185,12 +562,9 @@ const MEMORY_LOCK_FILE: &str = "memory.md.lock";
 const DISTILL_AUDIT_FILE: &str = "distill.audit.log";
 const ENTITY_ANCHORS_BEGIN: &str = "<!-- MOON_ENTITY_ANCHORS_BEGIN -->";
 const ENTITY_ANCHORS_END: &str = "<!-- MOON_ENTITY_ANCHORS_END -->";
-const TOPIC_STOPWORDS: [&str; 38] = [
-    "the", "and", "for", "with", "that", "this", "from", "into", "about", "after", "before",
-    "were", "was", "are", "is", "be", "been", "being", "have", "has", "had", "will", "would",
-    "should", "could", "can", "did", "done", "not", "you", "your", "our", "their", "they", "them",
-    "then", "than", "there",
-];
+
+const KEYWORD_LIMIT: usize = 30;
+const KEYWORD_IDENTIFIER_BOOST: f64 = 1.5;
 
 static AUTO_CHUNK_BYTES_CACHE: OnceLock<usize> = OnceLock::new();
 
@@ -201,12 +575,21 @@ fn env_non_empty(var: &str) -> Option<String> {
     }
 }
 
+/// Distill backends this build can use: `local` plus every remote provider.
+pub fn supported_providers() -> Vec<&'static str> {
+    std::iter::once("local")
+        .chain(RemoteProvider::ALL.iter().map(|provider| provider.label()))
+        .collect()
+}
+
 fn parse_provider_alias(raw: &str) -> Option<RemoteProvider> {
     match raw.trim().to_ascii_lowercase().as_str() {
         "openai" => Some(RemoteProvider::OpenAi),
         "anthropic" | "claude" => Some(RemoteProvider::Anthropic),
         "gemini" | "google" => Some(RemoteProvider::Gemini),
         "openai-compatible" | "compatible" | "deepseek" => Some(RemoteProvider::OpenAiCompatible),
+        "ollama" => Some(RemoteProvider::Ollama),
+        "azure-openai" | "azure" => Some(RemoteProvider::AzureOpenAi),
         _ => None,
     }
 }
@@ -258,6 +641,11 @@ fn first_available_provider() -> Option<RemoteProvider> {
     if env_non_empty("GEMINI_API_KEY").is_some() {
         return Some(RemoteProvider::Gemini);
     }
+    if env_non_empty("AZURE_OPENAI_API_KEY").is_some()
+        && env_non_empty("AZURE_OPENAI_ENDPOINT").is_some()
+    {
+        return Some(RemoteProvider::AzureOpenAi);
+    }
     None
 }
 
@@ -267,6 +655,8 @@ fn default_model_for_provider(provider: RemoteProvider) -> &'static str {
         RemoteProvider::Anthropic => "claude-3-5-haiku-latest",
         RemoteProvider::Gemini => "gemini-2.5-flash-lite",
         RemoteProvider::OpenAiCompatible => "deepseek-chat",
+        RemoteProvider::Ollama => "llama3.1",
+        RemoteProvider::AzureOpenAi => "gpt-4.1-mini",
     }
 }
 
@@ -284,6 +674,9 @@ fn resolve_api_key(provider: RemoteProvider) -> Option<String> {
         RemoteProvider::OpenAiCompatible => env_non_empty("AI_API_KEY")
             .or_else(|| env_non_empty("DEEPSEEK_API_KEY"))
             .or_else(|| env_non_empty("OPENAI_API_KEY")),
+        // Ollama serves without auth; the key is unused.
+        RemoteProvider::Ollama => Some(String::new()),
+        RemoteProvider::AzureOpenAi => env_non_empty("AZURE_OPENAI_API_KEY"),
     }
 }
 
@@ -297,6 +690,47 @@ fn resolve_compatible_base_url(model: &str) -> Option<String> {
     None
 }
 
+/// Ollama server for the `ollama` provider: `MOON_OLLAMA_BASE_URL`, then Ollama's own
+/// `OLLAMA_HOST` (which may omit the scheme), then the local default port.
+fn resolve_ollama_base_url() -> String {
+    let raw = env_non_empty("MOON_OLLAMA_BASE_URL")
+        .or_else(|| env_non_empty("OLLAMA_HOST"))
+        .unwrap_or_else(|| DEFAULT_OLLAMA_BASE_URL.to_string());
+    let base = raw.trim_end_matches('/');
+    if base.contains("://") {
+        base.to_string()
+    } else {
+        format!("http://{base}")
+    }
+}
+
+/// Azure routes requests by deployment rather than model name: `AZURE_OPENAI_DEPLOYMENT`
+/// when set, else the configured model doubles as the deployment name.
+fn resolve_azure_deployment(model: &str) -> String {
+    env_non_empty("AZURE_OPENAI_DEPLOYMENT").unwrap_or_else(|| model.to_string())
+}
+
+fn resolve_azure_api_version() -> String {
+    env_non_empty("AZURE_OPENAI_API_VERSION")
+        .unwrap_or_else(|| DEFAULT_AZURE_OPENAI_API_VERSION.to_string())
+}
+
+fn azure_chat_completions_url(endpoint: &str, deployment: &str, api_version: &str) -> String {
+    format!(
+        "{}/openai/deployments/{deployment}/chat/completions?api-version={api_version}",
+        endpoint.trim_end_matches('/')
+    )
+}
+
+fn resolve_provider_base_url(provider: RemoteProvider, model: &str) -> Option<String> {
+    match provider {
+        RemoteProvider::OpenAiCompatible => resolve_compatible_base_url(model),
+        RemoteProvider::Ollama => Some(resolve_ollama_base_url()),
+        RemoteProvider::AzureOpenAi => env_non_empty("AZURE_OPENAI_ENDPOINT"),
+        _ => None,
+    }
+}
+
 fn resolve_remote_config() -> Option<RemoteModelConfig> {
     if env_non_empty("MOON_DISTILL_PROVIDER")
         .as_deref()
@@ -332,10 +766,10 @@ fn resolve_remote_config() -> Option<RemoteModelConfig> {
     if model.trim().is_empty() {
         model = default_model_for_provider(provider).to_string();
     }
-    let base_url = match provider {
-        RemoteProvider::OpenAiCompatible => resolve_compatible_base_url(&model),
-        _ => None,
-    };
+    let base_url = resolve_provider_base_url(provider, &model);
+    if provider == RemoteProvider::AzureOpenAi && base_url.is_none() {
+        return None;
+    }
     let api_key = resolve_api_key(provider)?;
     Some(RemoteModelConfig {
         provider,
@@ -435,6 +869,29 @@ fn detect_openai_compatible_input_token_limit(
     )
 }
 
+fn detect_ollama_context_length(base_url: &str, model: &str) -> Option<u64> {
+    let url = format!("{}/api/show", base_url.trim_end_matches('/'));
+    let client = Client::builder()
+        .timeout(std::time::Duration::from_secs(REQUEST_TIMEOUT_SECS))
+        .build()
+        .ok()?;
+    let response = client
+        .post(&url)
+        .json(&serde_json::json!({ "model": model }))
+        .send()
+        .ok()?;
+    if !response.status().is_success() {
+        return None;
+    }
+    let json: Value = response.json().ok()?;
+    // `model_info` keys are prefixed by architecture, e.g. `llama.context_length`.
+    json.get("model_info")
+        .and_then(Value::as_object)?
+        .iter()
+        .find(|(key, _)| key.ends_with(".context_length"))
+        .and_then(|(_, value)| value.as_u64())
+}
+
 fn infer_context_tokens_from_model(provider: RemoteProvider, model: &str) -> u64 {
     let lower = model.to_ascii_lowercase();
     match provider {
@@ -445,7 +902,7 @@ fn infer_context_tokens_from_model(provider: RemoteProvider, model: &str) -> u64
                 250_000
             }
         }
-        RemoteProvider::OpenAi => {
+        RemoteProvider::OpenAi | RemoteProvider::AzureOpenAi => {
             if lower.starts_with("gpt-4.1") {
                 1_000_000
             } else if lower.starts_with("gpt-4o") {
@@ -462,6 +919,8 @@ fn infer_context_tokens_from_model(provider: RemoteProvider, model: &str) -> u64
                 200_000
             }
         }
+        // Ollama's default `num_ctx` is small; assume a conservative window.
+        RemoteProvider::Ollama => 8_192,
     }
 }
 
@@ -473,8 +932,45 @@ fn detect_context_tokens_from_remote(remote: &RemoteModelConfig) -> Option<u64>
             remote.base_url.as_deref(),
             &remote.model,
         ),
-        RemoteProvider::OpenAi | RemoteProvider::Anthropic => None,
+        RemoteProvider::Ollama => detect_ollama_context_length(
+            remote
+                .base_url
+                .as_deref()
+                .unwrap_or(DEFAULT_OLLAMA_BASE_URL),
+            &remote.model,
+        ),
+        RemoteProvider::OpenAi | RemoteProvider::Anthropic | RemoteProvider::AzureOpenAi => None,
+    }
+}
+
+/// `detect_context_tokens_from_remote` behind the `model-limits.json` cache, so each CLI
+/// invocation does not ask the provider again. Misses are cached too.
+fn cached_context_tokens_from_remote(remote: &RemoteModelConfig) -> Option<u64> {
+    if matches!(
+        remote.provider,
+        RemoteProvider::OpenAi | RemoteProvider::Anthropic | RemoteProvider::AzureOpenAi
+    ) {
+        return None;
+    }
+    let (Ok(paths), Ok(now)) = (resolve_paths(), now_epoch_secs()) else {
+        return detect_context_tokens_from_remote(remote);
+    };
+    let ttl_secs = load_config()
+        .map(|cfg| cfg.distill.model_limits_cache_secs)
+        .unwrap_or_else(|_| MoonDistillConfig::default().model_limits_cache_secs);
+    let key = model_limits::cache_key(
+        remote.provider.label(),
+        remote.base_url.as_deref(),
+        &remote.model,
+    );
+    if let Some(cached) = model_limits::lookup(&paths, &key, now, ttl_secs) {
+        return cached.context_tokens;
     }
+    let detected = detect_context_tokens_from_remote(remote);
+    if ttl_secs > 0 && !read_only_mode() {
+        let _ = model_limits::store(&paths, &key, detected, now);
+    }
+    detected
 }
 
 fn detect_auto_chunk_bytes() -> usize {
@@ -488,7 +984,7 @@ fn detect_auto_chunk_bytes() -> usize {
     }
 
     if let Some(remote) = resolve_remote_config() {
-        if let Some(tokens) = detect_context_tokens_from_remote(&remote) {
+        if let Some(tokens) = cached_context_tokens_from_remote(&remote) {
             return token_limit_to_chunk_bytes(tokens);
         }
         return token_limit_to_chunk_bytes(infer_context_tokens_from_model(
@@ -876,7 +1372,10 @@ fn collect_tool_input_signals(value: &Value, out: &mut BTreeSet<String>, depth:
     }
 }
 
-fn extract_message_entry(entry: &Value) -> Option<ProjectionEntry> {
+fn extract_message_entry(
+    entry: &Value,
+    tool_priority: &MoonToolPriorityConfig,
+) -> Option<ProjectionEntry> {
     let message = entry.get("message")?;
     let role = message
         .get("role")
@@ -917,9 +1416,9 @@ fn extract_message_entry(entry: &Value) -> Option<ProjectionEntry> {
                 && let Some(name) = part.get("name").and_then(Value::as_str)
             {
                 tool_name = Some(name.to_string());
-                priority = Some(match name {
-                    "write_to_file" | "exec" | "edit" | "gateway" => ToolPriority::High,
-                    _ => ToolPriority::Normal,
+                priority = Some(match tool_priority.priority_for_tool(name) {
+                    MoonToolPriorityLevel::High => ToolPriority::High,
+                    MoonToolPriorityLevel::Normal => ToolPriority::Normal,
                 });
 
                 if let Some(input) = part
@@ -963,6 +1462,8 @@ fn extract_message_entry(entry: &Value) -> Option<ProjectionEntry> {
         tool_target,
         priority,
         coupled_result: None,
+        source_line: None,
+        source_byte_offset: None,
     })
 }
 
@@ -1027,25 +1528,99 @@ fn is_projection_noise_entry(entry: &ProjectionEntry) -> bool {
     false
 }
 
+fn is_code_identifier(token: &str) -> bool {
+    let inner_separator = token
+        .char_indices()
+        .any(|(idx, c)| matches!(c, '_' | '.' | '-' | ':') && idx > 0 && idx + 1 < token.len());
+    let camel_case = token
+        .chars()
+        .zip(token.chars().skip(1))
+        .any(|(a, b)| a.is_ascii_lowercase() && b.is_ascii_uppercase());
+    inner_separator || camel_case
+}
+
+fn is_keyword_noise(token: &str) -> bool {
+    // Version strings, timestamps, and commit/uuid-like hex runs.
+    let unversioned = token.strip_prefix(['v', 'V']).unwrap_or(token);
+    unversioned
+        .chars()
+        .all(|c| c.is_ascii_digit() || matches!(c, '.' | '-' | '_' | ':'))
+        || (token.len() >= 7
+            && token.chars().all(|c| c.is_ascii_hexdigit() || c == '-')
+            && token.chars().any(|c| c.is_ascii_digit()))
+}
+
+fn keyword_tokens(text: &str) -> Vec<(String, bool)> {
+    let mut out = Vec::new();
+    for raw in text.split(|c: char| !c.is_alphanumeric() && !matches!(c, '_' | '-' | '.' | ':')) {
+        let token = raw.trim_matches(|c: char| matches!(c, '_' | '-' | '.' | ':'));
+        if token.len() > 40 || is_keyword_noise(token) {
+            continue;
+        }
+        let identifier = is_code_identifier(token);
+        let lower = token.to_lowercase();
+        if identifier {
+            if token.len() >= 3 {
+                out.push((lower, true));
+            }
+            continue;
+        }
+        if token.chars().count() < 4 || STOPWORDS.contains(&lower.as_str()) {
+            continue;
+        }
+        out.push((lower, false));
+    }
+    out
+}
+
+/// Ranks terms by TF-IDF across the session's user/assistant entries, so words repeated in
+/// every turn do not crowd out the distinctive ones; code identifiers get a flat boost.
 fn extract_keywords(entries: &[ProjectionEntry]) -> Vec<String> {
-    let mut keywords = BTreeSet::new();
+    let mut term_freq = BTreeMap::<String, usize>::new();
+    let mut doc_freq = BTreeMap::<String, usize>::new();
+    let mut identifiers = BTreeSet::new();
+    let mut docs = 0usize;
     for entry in entries {
         if entry.role != "user" && entry.role != "assistant" {
             continue;
         }
-        for word in entry
-            .content
-            .split(|c: char| !c.is_alphanumeric() && c != '_' && c != '-' && c != '.')
-        {
-            if word.len() > 4 && word.len() < 24 && !word.chars().all(|c| c.is_numeric()) {
-                keywords.insert(word.to_lowercase());
-            }
+        let tokens = keyword_tokens(&entry.content);
+        if tokens.is_empty() {
+            continue;
         }
-        if keywords.len() > 100 {
-            break;
+        docs += 1;
+        let mut seen = BTreeSet::new();
+        for (token, identifier) in tokens {
+            if identifier {
+                identifiers.insert(token.clone());
+            }
+            *term_freq.entry(token.clone()).or_insert(0) += 1;
+            if seen.insert(token.clone()) {
+                *doc_freq.entry(token).or_insert(0) += 1;
+            }
         }
     }
-    keywords.into_iter().take(30).collect()
+
+    let mut scored = term_freq
+        .into_iter()
+        .map(|(term, tf)| {
+            let df = doc_freq.get(&term).copied().unwrap_or(1);
+            let idf = ((docs as f64 + 1.0) / (df as f64 + 1.0)).ln() + 1.0;
+            let boost = if identifiers.contains(&term) {
+                KEYWORD_IDENTIFIER_BOOST
+            } else {
+                1.0
+            };
+            let score = (1.0 + (tf as f64).ln()) * idf * boost;
+            (term, score)
+        })
+        .collect::<Vec<_>>();
+    scored.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    scored
+        .into_iter()
+        .take(KEYWORD_LIMIT)
+        .map(|(term, _)| term)
+        .collect()
 }
 
 fn infer_topics(_entries: &[ProjectionEntry], keywords: &[String]) -> Vec<String> {
@@ -1056,120 +1631,688 @@ fn infer_topics(_entries: &[ProjectionEntry], keywords: &[String]) -> Vec<String
     }
 }
 
-pub fn extract_projection_data(path: &str) -> Result<ProjectionData> {
-    let file = fs::File::open(path).with_context(|| format!("failed to open {path}"))?;
-    let reader = BufReader::new(file);
-
-    let mut scanned_bytes = 0usize;
-    let mut scanned_lines = 0usize;
-    let mut entries: Vec<ProjectionEntry> = Vec::new();
-    let mut tool_calls_set = BTreeSet::new();
-    let mut compaction_anchors = Vec::new();
-    let mut filtered_noise_count = 0usize;
-    let mut truncated = false;
+#[derive(Default)]
+struct ProjectionCollector {
+    tool_priority: MoonToolPriorityConfig,
+    scanned_bytes: usize,
+    scanned_lines: usize,
+    entries: Vec<ProjectionEntry>,
+    tool_calls_set: BTreeSet<String>,
+    compaction_anchors: Vec<CompactionAnchor>,
+    filtered_noise_count: usize,
+    truncated: bool,
+    pending_tool_uses: Vec<PendingToolUse>,
+    limits: MoonProjectionConfig,
+    /// Full-scan thinning: only every `sample_stride`-th accepted entry is kept.
+    sample_stride: usize,
+    accepted_entries: usize,
+    time_start_epoch: Option<u64>,
+    time_end_epoch: Option<u64>,
+    /// Raw-archive position of the event being pushed, stamped onto its entry.
+    current_line: Option<usize>,
+    current_byte_offset: Option<u64>,
+}
+
+struct PendingToolUse {
+    entry_idx: usize,
+    call_id: Option<String>,
+}
 
-    let mut pending_tool_uses: Vec<usize> = Vec::new();
+const TOOL_CALL_ID_KEYS: [&str; 5] = [
+    "toolCallId",
+    "tool_call_id",
+    "toolUseId",
+    "tool_use_id",
+    "callId",
+];
 
-    for line in reader.split(b'\n') {
-        let raw = line.with_context(|| format!("failed to read line from {path}"))?;
-        scanned_lines = scanned_lines.saturating_add(1);
-        scanned_bytes = scanned_bytes.saturating_add(raw.len().saturating_add(1));
+fn tool_call_id_field(value: &Value) -> Option<String> {
+    TOOL_CALL_ID_KEYS
+        .iter()
+        .find_map(|key| value.get(*key).and_then(Value::as_str))
+        .filter(|id| !id.trim().is_empty())
+        .map(str::to_string)
+}
 
-        let decoded = String::from_utf8_lossy(&raw);
-        let trimmed = decoded.trim();
-        if trimmed.is_empty() {
-            continue;
+/// Call id of the last named `toolUse`/`toolCall` part, the one `extract_message_entry` keeps.
+fn tool_use_call_id(message: &Value) -> Option<String> {
+    message
+        .get("content")
+        .and_then(Value::as_array)?
+        .iter()
+        .rev()
+        .find(|part| {
+            matches!(
+                part.get("type").and_then(Value::as_str),
+                Some("toolUse" | "toolCall")
+            ) && part.get("name").and_then(Value::as_str).is_some()
+        })
+        .and_then(|part| {
+            part.get("id")
+                .and_then(Value::as_str)
+                .map(str::to_string)
+                .or_else(|| tool_call_id_field(part))
+        })
+}
+
+/// Call id and tool name a `toolResult` message points back to, when the payload carries them.
+fn tool_result_link(message: &Value) -> (Option<String>, Option<String>) {
+    let call_id = tool_call_id_field(message).or_else(|| {
+        message
+            .get("content")
+            .and_then(Value::as_array)
+            .and_then(|parts| parts.iter().find_map(tool_call_id_field))
+    });
+    let tool_name = ["toolName", "tool_name", "name"]
+        .iter()
+        .find_map(|key| message.get(*key).and_then(Value::as_str))
+        .map(str::to_string);
+    (call_id, tool_name)
+}
+
+/// Stateless half of [`ProjectionCollector::push_event`], safe to compute on worker threads.
+struct PreparedEvent {
+    anchor: Option<CompactionAnchor>,
+    /// Extracted entry plus the normalized `message` used for tool-call pairing.
+    message_entry: Option<(ProjectionEntry, Value)>,
+}
+
+fn prepare_event(raw_event: &Value, tool_priority: &MoonToolPriorityConfig) -> PreparedEvent {
+    // OpenClaw writes compactions as `{"type":"compaction","id":…,"summary":…}` entries; older
+    // transcripts carry a `compaction_summary` field instead.
+    let compaction_entry = raw_event.get("type").and_then(Value::as_str) == Some("compaction");
+    let anchor = raw_event
+        .get("compaction_summary")
+        .or_else(|| compaction_entry.then(|| raw_event.get("summary")).flatten())
+        .and_then(Value::as_str)
+        .filter(|note| !note.trim().is_empty())
+        .map(|note| CompactionAnchor {
+            note: note.to_string(),
+            origin_message_id: ["message_id", "id"]
+                .iter()
+                .find_map(|key| raw_event.get(*key).and_then(Value::as_str))
+                .map(|s| s.to_string()),
+        });
+    let message_entry = normalize_session_event(raw_event).and_then(|mut json_entry| {
+        let entry = extract_message_entry(&json_entry, tool_priority)?;
+        let message = json_entry
+            .get_mut("message")
+            .map(Value::take)
+            .unwrap_or(Value::Null);
+        Some((entry, message))
+    });
+    PreparedEvent {
+        anchor,
+        message_entry,
+    }
+}
+
+/// Wraps bare `{role, content}` chat messages (common in whole-document `.json` sessions)
+/// into the `{message: ...}` envelope used by JSONL session events.
+fn normalize_session_event(value: &Value) -> Option<Value> {
+    if value.get("message").is_some() {
+        return Some(value.clone());
+    }
+    value.get("role").and_then(Value::as_str)?;
+    let mut message = value.as_object()?.clone();
+    match message.get("content") {
+        Some(Value::String(text)) => {
+            let parts = serde_json::json!([{ "type": "text", "text": text }]);
+            message.insert("content".to_string(), parts);
+        }
+        Some(Value::Array(_)) => {}
+        _ => return None,
+    }
+    let mut event = serde_json::Map::new();
+    for key in ["timestamp_epoch", "timestamp", "createdAt", "created_at"] {
+        if let Some(ts) = message.get(key) {
+            event.insert(key.to_string(), ts.clone());
         }
+    }
+    event.insert("message".to_string(), Value::Object(message));
+    Some(Value::Object(event))
+}
 
-        if let Ok(json_entry) = serde_json::from_str::<Value>(trimmed) {
-            if let Some(note) = json_entry.get("compaction_summary").and_then(Value::as_str) {
-                compaction_anchors.push(CompactionAnchor {
-                    note: note.to_string(),
-                    origin_message_id: json_entry
-                        .get("message_id")
-                        .and_then(Value::as_str)
-                        .map(|s| s.to_string()),
-                });
-            }
+/// Message events held by a whole-document session file: a top-level array, an object with a
+/// `messages`/`entries`/`events`/`history` array, or a single event object.
+/// Keys whose array value holds a JSON session document's events.
+const SESSION_DOCUMENT_EVENT_KEYS: [&str; 4] = ["messages", "entries", "events", "history"];
 
-            if let Some(entry) = extract_message_entry(&json_entry) {
-                if is_projection_noise_entry(&entry) {
-                    filtered_noise_count = filtered_noise_count.saturating_add(1);
-                    if entry.role == "toolResult" {
-                        let _ = pending_tool_uses.pop();
-                    }
-                    continue;
-                }
+fn session_document_events(document: &Value) -> Vec<Value> {
+    match document {
+        Value::Array(items) => items.clone(),
+        Value::Object(map) => SESSION_DOCUMENT_EVENT_KEYS
+            .iter()
+            .find_map(|key| map.get(*key).and_then(Value::as_array).cloned())
+            .unwrap_or_else(|| vec![document.clone()]),
+        _ => Vec::new(),
+    }
+}
 
-                let idx = entries.len();
+impl ProjectionCollector {
+    fn new() -> Self {
+        let cfg = crate::moon::config::load_config().ok();
+        Self {
+            tool_priority: cfg
+                .as_ref()
+                .map(|cfg| cfg.tool_priority.clone())
+                .unwrap_or_default(),
+            limits: cfg.map(|cfg| cfg.projection).unwrap_or_default(),
+            sample_stride: 1,
+            ..Self::default()
+        }
+    }
 
-                if entry.role == "assistant" && entry.tool_name.is_some() {
-                    tool_calls_set.insert(entry.tool_name.clone().unwrap());
-                    pending_tool_uses.push(idx);
-                } else if entry.role == "toolResult"
-                    && let Some(use_idx) = pending_tool_uses.pop()
-                {
-                    entries[use_idx].coupled_result = Some(entry.content.clone());
-                }
+    /// Accounts one raw line (without its newline) and feeds its parsed form; returns true
+    /// once the collector is full and scanning should stop.
+    fn push_parsed_line(&mut self, raw_len: usize, parsed: ParsedLine) -> bool {
+        self.scanned_lines = self.scanned_lines.saturating_add(1);
+        self.current_line = Some(self.scanned_lines);
+        self.current_byte_offset = Some(self.scanned_bytes as u64);
+        self.scanned_bytes = self.scanned_bytes.saturating_add(raw_len.saturating_add(1));
 
-                entries.push(entry);
-            }
-        } else if !looks_like_json_blob(trimmed)
-            && let Some(cleaned) = clean_candidate_text(trimmed)
-        {
-            let entry = ProjectionEntry {
-                timestamp_epoch: None,
-                role: "system".to_string(),
-                content: cleaned,
-                tool_name: None,
-                tool_target: None,
-                priority: None,
-                coupled_result: None,
-            };
-            if is_projection_noise_entry(&entry) {
-                filtered_noise_count = filtered_noise_count.saturating_add(1);
-            } else {
-                entries.push(entry);
-            }
+        match parsed {
+            ParsedLine::Blank => return false,
+            ParsedLine::Event(prepared) => self.push_prepared(*prepared),
+            ParsedLine::Text(text) => self.push_text_line(&text),
         }
 
-        if entries.len() >= MAX_ARCHIVE_CANDIDATES
-            || scanned_lines >= MAX_ARCHIVE_SCAN_LINES
-            || scanned_bytes >= MAX_ARCHIVE_SCAN_BYTES
-        {
-            truncated = true;
-            break;
+        if self.is_full() {
+            self.truncated = true;
+            return true;
         }
+        false
     }
 
-    let message_count = entries.len();
-    let time_start_epoch = entries
-        .iter()
-        .filter_map(|entry| entry.timestamp_epoch)
-        .min();
-    let time_end_epoch = entries
-        .iter()
-        .filter_map(|entry| entry.timestamp_epoch)
-        .max();
-    let keywords = extract_keywords(&entries);
-    let topics = infer_topics(&entries, &keywords);
-
-    Ok(ProjectionData {
-        entries,
-        tool_calls: tool_calls_set.into_iter().collect(),
-        keywords,
-        topics,
-        time_start_epoch,
-        time_end_epoch,
-        message_count,
-        filtered_noise_count,
-        truncated,
-        compaction_anchors,
-    })
-}
+    fn max_entries(&self) -> usize {
+        usize::try_from(self.limits.max_entries)
+            .unwrap_or(usize::MAX)
+            .max(1)
+    }
 
-impl ProjectionData {
-    pub fn to_excerpt(&self) -> String {
+    fn is_full(&self) -> bool {
+        if self.limits.full_scan {
+            return false;
+        }
+        self.entries.len() >= self.max_entries()
+            || self.scanned_lines as u64 >= self.limits.max_scan_lines
+            || self.scanned_bytes as u64 >= self.limits.max_scan_bytes
+    }
+
+    /// Drops every other kept entry and doubles the stride, re-pointing pending tool calls at
+    /// their surviving entries.
+    fn thin_entries(&mut self) {
+        let mut kept = Vec::with_capacity(self.entries.len() / 2 + 1);
+        for (idx, entry) in std::mem::take(&mut self.entries).into_iter().enumerate() {
+            if idx.is_multiple_of(2) {
+                kept.push(entry);
+            }
+        }
+        self.entries = kept;
+        self.pending_tool_uses.retain_mut(|pending| {
+            if pending.entry_idx.is_multiple_of(2) {
+                pending.entry_idx /= 2;
+                true
+            } else {
+                false
+            }
+        });
+        self.sample_stride = self.sample_stride.saturating_mul(2);
+    }
+
+    fn push_event(&mut self, raw_event: &Value) {
+        let prepared = prepare_event(raw_event, &self.tool_priority);
+        self.push_prepared(prepared);
+    }
+
+    fn push_prepared(&mut self, prepared: PreparedEvent) {
+        if let Some(anchor) = prepared.anchor {
+            self.compaction_anchors.push(anchor);
+        }
+        let Some((mut entry, message)) = prepared.message_entry else {
+            return;
+        };
+        let message = &message;
+        entry.source_line = self.current_line;
+        entry.source_byte_offset = self.current_byte_offset;
+        if is_projection_noise_entry(&entry) {
+            self.filtered_noise_count = self.filtered_noise_count.saturating_add(1);
+            if entry.role == "toolResult" {
+                let _ = self.take_pending_tool_use(message);
+            }
+            return;
+        }
+
+        if let Some(ts) = entry.timestamp_epoch {
+            self.time_start_epoch = Some(self.time_start_epoch.map_or(ts, |v| v.min(ts)));
+            self.time_end_epoch = Some(self.time_end_epoch.map_or(ts, |v| v.max(ts)));
+        }
+        if entry.role == "assistant"
+            && let Some(name) = &entry.tool_name
+        {
+            self.tool_calls_set.insert(name.clone());
+        }
+        if !self.accept_sample() {
+            if entry.role == "toolResult" {
+                let _ = self.take_pending_tool_use(message);
+            }
+            return;
+        }
+
+        let idx = self.entries.len();
+        if entry.role == "assistant" && entry.tool_name.is_some() {
+            self.pending_tool_uses.push(PendingToolUse {
+                entry_idx: idx,
+                call_id: tool_use_call_id(message),
+            });
+        } else if entry.role == "toolResult"
+            && let Some(use_idx) = self.take_pending_tool_use(message)
+        {
+            self.entries[use_idx].coupled_result = Some(entry.content.clone());
+        }
+        self.keep_entry(entry);
+    }
+
+    /// Counts one accepted entry and reports whether the current sample stride keeps it.
+    fn accept_sample(&mut self) -> bool {
+        let seq = self.accepted_entries;
+        self.accepted_entries = self.accepted_entries.saturating_add(1);
+        seq.is_multiple_of(self.sample_stride.max(1))
+    }
+
+    fn keep_entry(&mut self, entry: ProjectionEntry) {
+        self.entries.push(entry);
+        if self.limits.full_scan && self.entries.len() >= self.max_entries() {
+            self.thin_entries();
+        }
+    }
+
+    /// Pairs a tool result with its pending call: by call id when the payload carries one,
+    /// else the oldest pending call with the same tool name, else the most recent call.
+    fn take_pending_tool_use(&mut self, result_message: &Value) -> Option<usize> {
+        let (call_id, tool_name) = tool_result_link(result_message);
+        let position = if let Some(call_id) = call_id {
+            // An unknown id means the call was filtered or lives elsewhere; never guess.
+            self.pending_tool_uses
+                .iter()
+                .position(|pending| pending.call_id.as_deref() == Some(call_id.as_str()))
+        } else if let Some(tool_name) = tool_name {
+            self.pending_tool_uses.iter().position(|pending| {
+                self.entries[pending.entry_idx]
+                    .tool_name
+                    .as_deref()
+                    .is_some_and(|name| name.eq_ignore_ascii_case(&tool_name))
+            })
+        } else {
+            self.pending_tool_uses.len().checked_sub(1)
+        }?;
+        Some(self.pending_tool_uses.remove(position).entry_idx)
+    }
+
+    fn push_text_line(&mut self, trimmed: &str) {
+        if looks_like_json_blob(trimmed) {
+            return;
+        }
+        let Some(cleaned) = clean_candidate_text(trimmed) else {
+            return;
+        };
+        let entry = ProjectionEntry {
+            timestamp_epoch: None,
+            role: "system".to_string(),
+            content: cleaned,
+            tool_name: None,
+            tool_target: None,
+            priority: None,
+            coupled_result: None,
+            source_line: self.current_line,
+            source_byte_offset: self.current_byte_offset,
+        };
+        if is_projection_noise_entry(&entry) {
+            self.filtered_noise_count = self.filtered_noise_count.saturating_add(1);
+        } else if self.accept_sample() {
+            self.keep_entry(entry);
+        }
+    }
+
+    fn finish(self) -> ProjectionData {
+        let entries = self.entries;
+        // Thinned full scans still count and time-range every accepted message.
+        let message_count = self.accepted_entries;
+        let time_start_epoch = self.time_start_epoch;
+        let time_end_epoch = self.time_end_epoch;
+        let keywords = extract_keywords(&entries);
+        let topics = infer_topics(&entries, &keywords);
+
+        ProjectionData {
+            entries,
+            tool_calls: self.tool_calls_set.into_iter().collect(),
+            keywords,
+            topics,
+            time_start_epoch,
+            time_end_epoch,
+            message_count,
+            filtered_noise_count: self.filtered_noise_count,
+            truncated: self.truncated,
+            compaction_anchors: self.compaction_anchors,
+            sample_stride: self.sample_stride.max(1),
+        }
+    }
+}
+
+/// Parses a whole-document `.json` session. Returns `None` when the file is not a single JSON
+/// document (for example JSONL saved with a `.json` extension).
+/// Feeds a JSON session document's events to the collector as they are parsed, so the
+/// document is never held whole and `max_scan_bytes` bounds how much of it is read.
+struct DocumentEvents<'a> {
+    collector: &'a mut ProjectionCollector,
+    read: &'a Cell<usize>,
+}
+
+/// Error message used to abort parsing once the collector is full.
+const DOCUMENT_SCAN_FULL: &str = "projection scan limits reached";
+
+impl DocumentEvents<'_> {
+    fn push_all<'de, A: SeqAccess<'de>>(&mut self, mut seq: A) -> Result<(), A::Error> {
+        while let Some(event) = seq.next_element::<Value>()? {
+            if !self.push(&event) {
+                return Err(serde::de::Error::custom(DOCUMENT_SCAN_FULL));
+            }
+        }
+        Ok(())
+    }
+
+    /// Pushes one event; false once the collector is full.
+    fn push(&mut self, event: &Value) -> bool {
+        let collector = &mut *self.collector;
+        collector.scanned_lines = collector.scanned_lines.saturating_add(1);
+        collector.current_line = Some(collector.scanned_lines);
+        collector.scanned_bytes = self.read.get();
+        collector.push_event(event);
+        if collector.is_full() {
+            collector.truncated = true;
+            return false;
+        }
+        true
+    }
+}
+
+impl<'de> Visitor<'de> for DocumentEvents<'_> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("a JSON session document")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(mut self, seq: A) -> Result<(), A::Error> {
+        self.push_all(seq)
+    }
+
+    fn visit_map<A: MapAccess<'de>>(mut self, mut map: A) -> Result<(), A::Error> {
+        let mut fields = serde_json::Map::new();
+        let mut streamed = false;
+        while let Some(key) = map.next_key::<String>()? {
+            if streamed {
+                map.next_value::<IgnoredAny>()?;
+            } else if SESSION_DOCUMENT_EVENT_KEYS.contains(&key.as_str()) {
+                map.next_value_seed(DocumentEventArray(&mut self))?;
+                streamed = true;
+            } else {
+                fields.insert(key, map.next_value()?);
+            }
+        }
+        if !streamed {
+            self.push(&Value::Object(fields));
+        }
+        Ok(())
+    }
+}
+
+/// The event array under one of [`SESSION_DOCUMENT_EVENT_KEYS`]; any other value there means
+/// the file is not a session document.
+struct DocumentEventArray<'a, 'b>(&'a mut DocumentEvents<'b>);
+
+impl<'de> DeserializeSeed<'de> for DocumentEventArray<'_, '_> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de> Visitor<'de> for DocumentEventArray<'_, '_> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("an array of session events")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<(), A::Error> {
+        self.0.push_all(seq)
+    }
+}
+
+/// Counts bytes pulled from a session file.
+struct CountingReader<'a, R> {
+    inner: R,
+    read: &'a Cell<usize>,
+}
+
+impl<R: Read> Read for CountingReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.read.set(self.read.get().saturating_add(n));
+        Ok(n)
+    }
+}
+
+/// Projects a JSON (array or object) session document, streaming its events; `None` when the
+/// file is not one, so the caller falls back to line-by-line scanning.
+fn extract_projection_document(path: &str) -> Result<Option<ProjectionData>> {
+    stream_projection_document(path, ProjectionCollector::new())
+}
+
+fn stream_projection_document(
+    path: &str,
+    mut collector: ProjectionCollector,
+) -> Result<Option<ProjectionData>> {
+    let budget = if collector.limits.full_scan {
+        u64::MAX
+    } else {
+        collector.limits.max_scan_bytes
+    };
+    let read = Cell::new(0usize);
+    let reader = std::io::BufReader::new(CountingReader {
+        inner: open_session_reader(Path::new(path))?.take(budget),
+        read: &read,
+    });
+    let mut deserializer = serde_json::Deserializer::from_reader(reader);
+    let parsed = deserializer
+        .deserialize_any(DocumentEvents {
+            collector: &mut collector,
+            read: &read,
+        })
+        .and_then(|()| deserializer.end());
+    collector.scanned_bytes = read.get();
+    if parsed.is_err() {
+        // Stopped at the entry/line limits, or the byte budget cut the document short.
+        if !collector.truncated && (read.get() as u64) < budget {
+            return Ok(None);
+        }
+        collector.truncated = true;
+    }
+    Ok(Some(collector.finish()))
+}
+
+fn starts_with_json_array(path: &str) -> Result<bool> {
+    let mut reader = open_session_reader(Path::new(path))?;
+    loop {
+        let buf = reader
+            .fill_buf()
+            .with_context(|| format!("failed to read {path}"))?;
+        if buf.is_empty() {
+            return Ok(false);
+        }
+        if let Some(byte) = buf.iter().find(|b| !b.is_ascii_whitespace()) {
+            return Ok(*byte == b'[');
+        }
+        let len = buf.len();
+        reader.consume(len);
+    }
+}
+
+pub fn extract_projection_data(path: &str) -> Result<ProjectionData> {
+    let is_json_document = session_extension(Path::new(path)).as_deref() == Some("json");
+    if (is_json_document || starts_with_json_array(path)?)
+        && let Some(data) = extract_projection_document(path)?
+    {
+        return Ok(data);
+    }
+
+    // Compressed size for gzip sessions, which only ever lowers the worker count.
+    let file_len = fs::metadata(path).map(|meta| meta.len()).unwrap_or(0);
+    let mut collector = ProjectionCollector::new();
+    let workers = projection_parse_workers(file_len, &collector.limits);
+    let reader = open_session_reader(Path::new(path))?;
+    if workers > 1 {
+        collect_projection_lines_parallel(
+            reader,
+            &mut collector,
+            workers,
+            PARALLEL_PROJECTION_BATCH_BYTES,
+            path,
+        )?;
+    } else {
+        collect_projection_lines(reader, &mut collector, path)?;
+    }
+
+    Ok(collector.finish())
+}
+
+/// Archives at least this large (within the scan budget) are parsed on several threads.
+const PARALLEL_PROJECTION_MIN_BYTES: u64 = 64 * 1024 * 1024;
+/// Bytes of whole lines read per parallel round; bounds memory held as parsed JSON.
+const PARALLEL_PROJECTION_BATCH_BYTES: usize = 16 * 1024 * 1024;
+const PARALLEL_PROJECTION_MAX_WORKERS: usize = 8;
+
+enum ParsedLine {
+    Blank,
+    Event(Box<PreparedEvent>),
+    Text(String),
+}
+
+fn parse_projection_line(raw: &[u8], tool_priority: &MoonToolPriorityConfig) -> ParsedLine {
+    let decoded = String::from_utf8_lossy(raw);
+    let trimmed = decoded.trim();
+    if trimmed.is_empty() {
+        return ParsedLine::Blank;
+    }
+    match serde_json::from_str::<Value>(trimmed) {
+        Ok(value) => ParsedLine::Event(Box::new(prepare_event(&value, tool_priority))),
+        Err(_) => ParsedLine::Text(trimmed.to_string()),
+    }
+}
+
+fn projection_parse_workers(file_len: u64, limits: &MoonProjectionConfig) -> usize {
+    let scan_len = if limits.full_scan {
+        file_len
+    } else {
+        file_len.min(limits.max_scan_bytes)
+    };
+    if scan_len < PARALLEL_PROJECTION_MIN_BYTES {
+        return 1;
+    }
+    std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+        .min(PARALLEL_PROJECTION_MAX_WORKERS)
+}
+
+fn collect_projection_lines<R: BufRead>(
+    reader: R,
+    collector: &mut ProjectionCollector,
+    path: &str,
+) -> Result<()> {
+    for line in reader.split(b'\n') {
+        let raw = line.with_context(|| format!("failed to read line from {path}"))?;
+        let parsed = parse_projection_line(&raw, &collector.tool_priority);
+        if collector.push_parsed_line(raw.len(), parsed) {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Reads `batch_bytes` of whole lines at a time, parses and prepares them as `workers`
+/// line-aligned segments in parallel, then feeds the results to `collector` in file order so entries, tool
+/// coupling and scan limits match the sequential path exactly.
+fn collect_projection_lines_parallel<R: BufRead>(
+    mut reader: R,
+    collector: &mut ProjectionCollector,
+    workers: usize,
+    batch_bytes: usize,
+    path: &str,
+) -> Result<()> {
+    let mut buf = Vec::with_capacity(batch_bytes);
+    let mut lines = Vec::<std::ops::Range<usize>>::new();
+    loop {
+        buf.clear();
+        lines.clear();
+        while buf.len() < batch_bytes {
+            let start = buf.len();
+            let read = reader
+                .read_until(b'\n', &mut buf)
+                .with_context(|| format!("failed to read line from {path}"))?;
+            if read == 0 {
+                break;
+            }
+            let end = if buf.last() == Some(&b'\n') {
+                buf.len() - 1
+            } else {
+                buf.len()
+            };
+            lines.push(start..end);
+        }
+        if lines.is_empty() {
+            return Ok(());
+        }
+
+        let per_worker = lines.len().div_ceil(workers.max(1));
+        let buf_ref = &buf;
+        let tool_priority = &collector.tool_priority;
+        let segments = std::thread::scope(|scope| {
+            let handles = lines
+                .chunks(per_worker)
+                .map(|segment| {
+                    scope.spawn(move || {
+                        segment
+                            .iter()
+                            .map(|range| {
+                                parse_projection_line(&buf_ref[range.clone()], tool_priority)
+                            })
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect::<Vec<_>>();
+            handles
+                .into_iter()
+                .map(|handle| {
+                    handle
+                        .join()
+                        .map_err(|_| anyhow::anyhow!("projection parse worker panicked for {path}"))
+                })
+                .collect::<Result<Vec<_>>>()
+        })?;
+
+        for (range, parsed) in lines.iter().zip(segments.into_iter().flatten()) {
+            if collector.push_parsed_line(range.len(), parsed) {
+                return Ok(());
+            }
+        }
+    }
+}
+
+impl ProjectionData {
+    pub fn to_excerpt(&self) -> String {
         let mut out = Vec::new();
         for entry in &self.entries {
             let candidate = match entry.role.as_str() {
@@ -1205,6 +2348,103 @@ impl ProjectionData {
     }
 }
 
+/// One raw-archive event rendered in full for recall hydration; `line` is 1-based (the event
+/// index for whole-document `.json` archives).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RawArchiveEntry {
+    pub line: usize,
+    pub timestamp_epoch: Option<u64>,
+    pub role: String,
+    pub text: String,
+}
+
+fn render_raw_archive_event(line: usize, event: &Value) -> Option<RawArchiveEntry> {
+    let event = normalize_session_event(event)?;
+    let message = event.get("message")?;
+    let role = message
+        .get("role")
+        .and_then(Value::as_str)
+        .unwrap_or("event")
+        .to_string();
+    let mut parts = Vec::new();
+    for part in message
+        .get("content")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+    {
+        match part.get("type").and_then(Value::as_str).unwrap_or("") {
+            "text" => {
+                if let Some(text) = part.get("text").and_then(Value::as_str) {
+                    parts.push(text.trim().to_string());
+                }
+            }
+            "toolUse" | "toolCall" => {
+                let name = part.get("name").and_then(Value::as_str).unwrap_or("tool");
+                let input = part
+                    .get("input")
+                    .or_else(|| part.get("arguments"))
+                    .map(Value::to_string)
+                    .unwrap_or_default();
+                parts.push(format!("[toolUse {name}] {input}").trim().to_string());
+            }
+            _ => {}
+        }
+    }
+    let text = parts
+        .into_iter()
+        .filter(|p| !p.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n");
+    if text.is_empty() {
+        return None;
+    }
+    Some(RawArchiveEntry {
+        line,
+        timestamp_epoch: resolve_entry_timestamp_epoch(&event, message),
+        role,
+        text,
+    })
+}
+
+/// Every message event of a raw archive, unfiltered (tool results included), in file order.
+pub fn read_raw_archive_entries(path: &str) -> Result<Vec<RawArchiveEntry>> {
+    let is_json_document = session_extension(Path::new(path)).as_deref() == Some("json");
+    if is_json_document || starts_with_json_array(path)? {
+        let raw = read_session_bytes(Path::new(path))?;
+        if let Ok(document) = serde_json::from_slice::<Value>(&raw) {
+            return Ok(session_document_events(&document)
+                .iter()
+                .enumerate()
+                .filter_map(|(idx, event)| render_raw_archive_event(idx + 1, event))
+                .collect());
+        }
+    }
+
+    let mut out = Vec::new();
+    for (idx, line) in open_session_reader(Path::new(path))?
+        .split(b'\n')
+        .enumerate()
+    {
+        let raw = line.with_context(|| format!("failed to read line from {path}"))?;
+        let decoded = String::from_utf8_lossy(&raw);
+        let trimmed = decoded.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<Value>(trimmed) {
+            Ok(event) => out.extend(render_raw_archive_event(idx + 1, &event)),
+            Err(_) => out.push(RawArchiveEntry {
+                line: idx + 1,
+                timestamp_epoch: None,
+                role: "text".to_string(),
+                text: trimmed.to_string(),
+            }),
+        }
+    }
+    Ok(out)
+}
+
 pub fn load_archive_excerpt(path: &str) -> Result<String> {
     let data = extract_projection_data(path)?;
     Ok(data.to_excerpt())
@@ -1329,6 +2569,14 @@ fn extract_openai_compatible_text(json: &Value) -> Option<String> {
     }
 }
 
+fn extract_ollama_text(json: &Value) -> Option<String> {
+    json.get("message")
+        .and_then(|message| message.get("content"))
+        .and_then(Value::as_str)
+        .filter(|text| !text.trim().is_empty())
+        .map(str::to_string)
+}
+
 fn sanitize_model_summary(summary: &str) -> Option<String> {
     let mut lines = Vec::new();
     let mut bullet_count = 0usize;
@@ -1510,6 +2758,63 @@ impl Distiller for OpenAiCompatDistiller {
     }
 }
 
+impl Distiller for OllamaDistiller {
+    fn distill(&self, input: &DistillInput) -> Result<String> {
+        let prompt = build_llm_prompt(input);
+        let url = format!("{}/api/chat", self.base_url.trim_end_matches('/'));
+        let payload = serde_json::json!({
+            "model": self.model,
+            "messages": [
+                {"role": "user", "content": prompt}
+            ],
+            "stream": false,
+            "options": {"temperature": 0.2}
+        });
+
+        let client = Client::builder()
+            .timeout(std::time::Duration::from_secs(OLLAMA_REQUEST_TIMEOUT_SECS))
+            .build()?;
+        let response = client.post(&url).json(&payload).send()?;
+        if !response.status().is_success() {
+            anyhow::bail!("ollama call failed with status {}", response.status());
+        }
+
+        let json: Value = response.json()?;
+        let text = extract_ollama_text(&json).context("ollama response missing text content")?;
+        Ok(text)
+    }
+}
+
+impl Distiller for AzureOpenAiDistiller {
+    fn distill(&self, input: &DistillInput) -> Result<String> {
+        let prompt = build_llm_prompt(input);
+        let url = azure_chat_completions_url(&self.endpoint, &self.deployment, &self.api_version);
+        let payload = serde_json::json!({
+            "messages": [
+                {"role": "user", "content": prompt}
+            ],
+            "temperature": 0.2
+        });
+
+        let client = Client::builder()
+            .timeout(std::time::Duration::from_secs(REQUEST_TIMEOUT_SECS))
+            .build()?;
+        let response = client
+            .post(&url)
+            .header("api-key", &self.api_key)
+            .json(&payload)
+            .send()?;
+        if !response.status().is_success() {
+            anyhow::bail!("azure-openai call failed with status {}", response.status());
+        }
+
+        let json: Value = response.json()?;
+        let text = extract_openai_compatible_text(&json)
+            .context("azure-openai response missing text content")?;
+        Ok(text)
+    }
+}
+
 impl Distiller for AnthropicDistiller {
     fn distill(&self, input: &DistillInput) -> Result<String> {
         let prompt = build_llm_prompt(input);
@@ -1545,10 +2850,10 @@ impl Distiller for AnthropicDistiller {
     }
 }
 
-fn daily_memory_path(paths: &MoonPaths, archive_epoch_secs: Option<u64>) -> String {
+fn daily_memory_path(paths: &MoonPaths, archive_epoch_secs: Option<u64>, tz: Tz) -> String {
     let timestamp = archive_epoch_secs
-        .and_then(|secs| Local.timestamp_opt(secs as i64, 0).single())
-        .unwrap_or_else(Local::now);
+        .and_then(|secs| tz.timestamp_opt(secs as i64, 0).single())
+        .unwrap_or_else(|| Utc::now().with_timezone(&tz));
     let date = format!(
         "{:04}-{:02}-{:02}",
         timestamp.year(),
@@ -1562,7 +2867,43 @@ fn daily_memory_path(paths: &MoonPaths, archive_epoch_secs: Option<u64>) -> Stri
         .to_string()
 }
 
-fn distill_summary(input: &DistillInput) -> Result<(String, String)> {
+/// Whether a daily memory file holds the L1 session block for `session_id`.
+pub fn daily_memory_has_session(
+    paths: &MoonPaths,
+    session_id: &str,
+    archive_epoch_secs: Option<u64>,
+) -> bool {
+    daily_memory_file_for_session(paths, session_id, archive_epoch_secs).is_some()
+}
+
+/// The daily memory file holding the L1 session block for `session_id`: the file distillation
+/// writes in the residential timezone, or the UTC day's file (the default before a timezone
+/// was configured). Only those two are read, since retention asks this for every cold archive
+/// on every cycle; a block left under another timezone keeps its archive protected.
+pub fn daily_memory_file_for_session(
+    paths: &MoonPaths,
+    session_id: &str,
+    archive_epoch_secs: Option<u64>,
+) -> Option<PathBuf> {
+    let (begin_marker, _) = session_block_markers(session_id);
+    let contains_marker = |path: &Path| {
+        fs::read_to_string(path)
+            .map(|raw| raw.contains(&begin_marker))
+            .unwrap_or(false)
+    };
+    let expected = PathBuf::from(daily_memory_path(
+        paths,
+        archive_epoch_secs,
+        resolve_residential_tz(),
+    ));
+    if contains_marker(&expected) {
+        return Some(expected);
+    }
+    let utc = PathBuf::from(daily_memory_path(paths, archive_epoch_secs, Tz::UTC));
+    (utc != expected && contains_marker(&utc)).then_some(utc)
+}
+
+fn distill_summary(input: &DistillInput) -> Result<(String, String, Option<ProviderFallback>)> {
     let mut local_summary_cache: Option<String> = None;
     let mut local_summary = || -> Result<String> {
         if let Some(existing) = &local_summary_cache {
@@ -1573,7 +2914,8 @@ fn distill_summary(input: &DistillInput) -> Result<(String, String)> {
         Ok(summary)
     };
 
-    let (provider_used, generated_summary) = if let Some(remote) = resolve_remote_config() {
+    let (provider_used, generated_summary, fallback) = if let Some(remote) = resolve_remote_config()
+    {
         let remote_result = match remote.provider {
             RemoteProvider::OpenAi => OpenAiDistiller {
                 api_key: remote.api_key.clone(),
@@ -1599,20 +2941,43 @@ fn distill_summary(input: &DistillInput) -> Result<(String, String)> {
                     .unwrap_or_else(|| "https://api.openai.com".to_string()),
             }
             .distill(input),
+            RemoteProvider::Ollama => OllamaDistiller {
+                model: remote.model.clone(),
+                base_url: remote
+                    .base_url
+                    .clone()
+                    .unwrap_or_else(|| DEFAULT_OLLAMA_BASE_URL.to_string()),
+            }
+            .distill(input),
+            RemoteProvider::AzureOpenAi => AzureOpenAiDistiller {
+                api_key: remote.api_key.clone(),
+                endpoint: remote.base_url.clone().unwrap_or_default(),
+                deployment: resolve_azure_deployment(&remote.model),
+                api_version: resolve_azure_api_version(),
+            }
+            .distill(input),
         };
 
         match remote_result {
             Ok(out) => match sanitize_model_summary(&out) {
-                Some(cleaned) => (remote.provider.label().to_string(), cleaned),
-                None => ("local".to_string(), local_summary()?),
+                Some(cleaned) => (remote.provider.label().to_string(), cleaned, None),
+                None => (
+                    "local".to_string(),
+                    local_summary()?,
+                    Some(ProviderFallback::sanitize_reject(&remote)),
+                ),
             },
-            Err(_) => ("local".to_string(), local_summary()?),
+            Err(err) => (
+                "local".to_string(),
+                local_summary()?,
+                Some(ProviderFallback::new(&remote, &err, 0)),
+            ),
         }
     } else {
-        ("local".to_string(), local_summary()?)
+        ("local".to_string(), local_summary()?, None)
     };
     let deduped = apply_semantic_dedup(&generated_summary);
-    Ok((provider_used, clamp_summary(&deduped)))
+    Ok((provider_used, clamp_summary(&deduped), fallback))
 }
 
 fn topic_discovery_enabled() -> bool {
@@ -1625,6 +2990,16 @@ fn topic_discovery_enabled() -> bool {
     }
 }
 
+fn graph_extraction_enabled() -> bool {
+    if let Ok(cfg) = crate::moon::config::load_config() {
+        return cfg.distill.graph_extraction;
+    }
+    match env::var("MOON_GRAPH_EXTRACTION") {
+        Ok(raw) => matches!(raw.trim(), "1" | "true" | "TRUE" | "yes" | "on"),
+        Err(_) => false,
+    }
+}
+
 fn is_valid_topic_key(key: &str) -> bool {
     if key.len() < 3 || key.len() > 32 {
         return false;
@@ -1675,7 +3050,7 @@ fn normalize_semantic_key_fragment(fragment: &str) -> Option<String> {
         if token.chars().all(|c| c.is_ascii_digit()) {
             continue;
         }
-        if TOPIC_STOPWORDS.contains(&token.as_str()) {
+        if STOPWORDS.contains(&token.as_str()) {
             continue;
         }
         tokens.push(token);
@@ -1913,8 +3288,9 @@ fn append_distilled_summary(
     input: &DistillInput,
     provider_used: String,
     summary: String,
+    provider_fallback: Option<ProviderFallback>,
 ) -> Result<DistillOutput> {
-    let summary_path = daily_memory_path(paths, input.archive_epoch_secs);
+    let summary_path = daily_memory_path(paths, input.archive_epoch_secs, resolve_residential_tz());
     let mut full_text = fs::read_to_string(&summary_path).unwrap_or_default();
     let topic_tags = if topic_discovery_enabled() {
         discover_topic_tags(&summary)
@@ -1940,18 +3316,22 @@ fn append_distilled_summary(
     fs::write(&summary_path, full_text)
         .with_context(|| format!("failed to write {}", summary_path))?;
 
-    audit::append_event(
-        paths,
-        "distill",
-        "ok",
-        &format!(
-            "distilled session {} into {} provider={} topic_count={}",
-            input.session_id,
-            summary_path,
-            provider_used,
-            topic_tags.len()
-        ),
-    )?;
+    let mut message = format!(
+        "distilled session {} into {} provider={} topic_count={}",
+        input.session_id,
+        summary_path,
+        provider_used,
+        topic_tags.len()
+    );
+    let mut details = serde_json::json!({
+        "session": input.session_id,
+        "archive": input.archive_path,
+        "target": summary_path,
+        "provider": provider_used,
+        "topic_count": topic_tags.len(),
+    });
+    ProviderFallback::annotate_audit(provider_fallback.as_ref(), &mut message, &mut details);
+    audit::append_event(paths, "distill", "ok", &message, details)?;
 
     Ok(DistillOutput {
         provider: provider_used,
@@ -1959,6 +3339,13 @@ fn append_distilled_summary(
         summary_path: summary_path.clone(),
         audit_log_path: paths.logs_dir.join("audit.log").display().to_string(),
         created_at_epoch_secs: now_epoch_secs()?,
+        memory_conflicts: Vec::new(),
+        expired_memory: Vec::new(),
+        remote_tokens: 0,
+        chunk_count: 1,
+        chunk_durations_ms: Vec::new(),
+        rollup_levels: 0,
+        provider_fallback,
     })
 }
 
@@ -2043,10 +3430,12 @@ impl ChunkSummaryRollup {
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn render(
         &self,
         session_id: &str,
         archive_path: &str,
+        time_range: Option<&TimeRangeLabels>,
         chunk_count: usize,
         chunk_target_bytes: usize,
         max_chunks: usize,
@@ -2069,6 +3458,7 @@ impl ChunkSummaryRollup {
         out.push_str("## Distilled Session Summary\n");
         out.push_str(&format!("- session_id: {session_id}\n"));
         out.push_str(&format!("- archive_path: {archive_path}\n"));
+        push_time_range_metadata(&mut out, time_range);
         out.push_str(&format!("- chunk_count: {chunk_count}\n"));
         out.push_str(&format!("- chunk_target_bytes: {chunk_target_bytes}\n"));
         if truncated {
@@ -2107,32 +3497,49 @@ fn summarize_provider_mix(provider_counts: &BTreeMap<String, usize>) -> String {
     format!("mixed({parts})")
 }
 
-fn stream_archive_chunks<F>(
+pub fn stream_archive_chunks<F>(
     path: &str,
     chunk_target_bytes: usize,
     max_chunks: usize,
     mut on_chunk: F,
 ) -> Result<(usize, bool)>
 where
-    F: FnMut(usize, String) -> Result<()>,
+    F: FnMut(usize, &str) -> Result<()>,
 {
-    let file = fs::File::open(path).with_context(|| format!("failed to open {path}"))?;
-    let reader = BufReader::new(file);
-
-    let mut current_chunk = String::new();
+    let file_len = fs::metadata(path).map(|meta| meta.len()).unwrap_or(0);
+    let mut reader = open_session_reader(Path::new(path))?;
+
+    // One buffer sized for a full chunk is reused for every chunk; callers get a slice and
+    // copy only what they keep.
+    let capacity = usize::try_from(file_len)
+        .unwrap_or(usize::MAX)
+        .min(chunk_target_bytes)
+        .saturating_add(1);
+    let mut current_chunk = String::with_capacity(capacity);
+    let mut line = Vec::new();
     let mut current_bytes = 0usize;
     let mut chunk_count = 0usize;
     let mut truncated = false;
 
-    for line in reader.split(b'\n') {
-        let raw = line.with_context(|| format!("failed to read line from {path}"))?;
-        let line_bytes = raw.len().saturating_add(1);
+    loop {
+        line.clear();
+        let read = reader
+            .read_until(b'\n', &mut line)
+            .with_context(|| format!("failed to read line from {path}"))?;
+        if read == 0 {
+            break;
+        }
+        if line.last() == Some(&b'\n') {
+            line.pop();
+        }
+        let line_bytes = line.len().saturating_add(1);
 
         if !current_chunk.is_empty()
             && current_bytes.saturating_add(line_bytes) > chunk_target_bytes
         {
             chunk_count = chunk_count.saturating_add(1);
-            on_chunk(chunk_count, std::mem::take(&mut current_chunk))?;
+            on_chunk(chunk_count, &current_chunk)?;
+            current_chunk.clear();
             current_bytes = 0;
             if chunk_count >= max_chunks {
                 truncated = true;
@@ -2140,24 +3547,96 @@ where
             }
         }
 
-        current_chunk.push_str(&String::from_utf8_lossy(&raw));
+        current_chunk.push_str(&String::from_utf8_lossy(&line));
         current_chunk.push('\n');
         current_bytes = current_bytes.saturating_add(line_bytes);
     }
 
-    if !truncated {
-        if current_chunk.is_empty() {
-            if chunk_count == 0 {
-                chunk_count = 1;
-                on_chunk(chunk_count, String::new())?;
+    if !truncated && (chunk_count == 0 || !current_chunk.is_empty()) {
+        chunk_count = chunk_count.saturating_add(1);
+        on_chunk(chunk_count, &current_chunk)?;
+    }
+
+    Ok((chunk_count, truncated))
+}
+
+/// What a chunk belongs to: an archive distill or a synthesis day (and its rollup level).
+#[derive(Debug, Clone, Copy)]
+enum ChunkSubject<'a> {
+    Session(&'a str),
+    Syns {
+        day_key: &'a str,
+        rollup: Option<usize>,
+    },
+}
+
+/// One finished distill chunk, logged as a `distill-chunk` audit event so long runs show
+/// steady progress in `logs/audit.log` instead of looking hung.
+#[derive(Debug, Clone, Copy)]
+struct ChunkProgress<'a> {
+    subject: ChunkSubject<'a>,
+    chunk_index: usize,
+    chunk_count: usize,
+    provider: &'a str,
+    duration_ms: u128,
+    bullets: usize,
+}
+
+impl ChunkProgress<'_> {
+    fn message(&self) -> String {
+        let subject = match self.subject {
+            ChunkSubject::Session(session) => format!("session={session}"),
+            ChunkSubject::Syns {
+                day_key,
+                rollup: None,
+            } => format!("syns={day_key}"),
+            ChunkSubject::Syns {
+                day_key,
+                rollup: Some(level),
+            } => format!("syns={day_key} rollup={level}"),
+        };
+        format!(
+            "{subject} chunk={}/{} provider={} duration_ms={} bullets={}",
+            self.chunk_index, self.chunk_count, self.provider, self.duration_ms, self.bullets
+        )
+    }
+
+    fn details(&self) -> serde_json::Value {
+        let mut details = serde_json::json!({
+            "chunk_index": self.chunk_index,
+            "chunk_count": self.chunk_count,
+            "provider": self.provider,
+            "duration_ms": self.duration_ms,
+            "bullets": self.bullets,
+        });
+        match self.subject {
+            ChunkSubject::Session(session) => details["session"] = session.into(),
+            ChunkSubject::Syns { day_key, rollup } => {
+                details["syns"] = day_key.into();
+                if let Some(level) = rollup {
+                    details["rollup"] = level.into();
+                }
             }
-        } else {
-            chunk_count = chunk_count.saturating_add(1);
-            on_chunk(chunk_count, current_chunk)?;
         }
+        details
     }
+}
 
-    Ok((chunk_count, truncated))
+fn record_chunk_progress(paths: &MoonPaths, status: &str, progress: ChunkProgress<'_>) {
+    let _ = audit::append_event(
+        paths,
+        "distill-chunk",
+        status,
+        &progress.message(),
+        progress.details(),
+    );
+}
+
+fn count_summary_bullets(summary: &str) -> usize {
+    summary
+        .lines()
+        .filter(|line| line.trim_start().starts_with("- "))
+        .count()
 }
 
 pub fn run_chunked_archive_distillation(
@@ -2166,7 +3645,21 @@ pub fn run_chunked_archive_distillation(
 ) -> Result<ChunkedDistillOutput> {
     // Layer 1 is conversation-preserving normalization. Chunked mode is retained as a
     // compatibility wrapper and delegates to single-pass output generation.
+    let started = std::time::Instant::now();
     let out = run_distillation(paths, input)?;
+    let duration_ms = started.elapsed().as_millis();
+    record_chunk_progress(
+        paths,
+        "ok",
+        ChunkProgress {
+            subject: ChunkSubject::Session(&input.session_id),
+            chunk_index: 1,
+            chunk_count: 1,
+            provider: &out.provider,
+            duration_ms,
+            bullets: count_summary_bullets(&out.summary),
+        },
+    );
     Ok(ChunkedDistillOutput {
         provider: out.provider.clone(),
         summary: out.summary.clone(),
@@ -2176,6 +3669,7 @@ pub fn run_chunked_archive_distillation(
         chunk_count: 1,
         chunk_target_bytes: distill_chunk_bytes(),
         truncated: false,
+        chunk_durations_ms: vec![u64::try_from(duration_ms).unwrap_or(u64::MAX)],
     })
 }
 
@@ -2218,7 +3712,25 @@ fn strip_projection_bullet_text(raw: &str) -> String {
     trimmed.to_string()
 }
 
-type Layer1ProjectionExtract = (Vec<(String, String)>, Option<Vec<String>>, usize, usize);
+type Layer1ProjectionExtract = (
+    Vec<(String, String)>,
+    Option<Vec<String>>,
+    usize,
+    usize,
+    Option<(u64, Option<u64>)>,
+);
+
+/// Start/end epochs from a projection's `time_range_utc: "START — END"` frontmatter line.
+fn parse_projection_time_range(raw: &str) -> Option<(u64, Option<u64>)> {
+    let parse = |value: &str| {
+        chrono::DateTime::parse_from_rfc3339(value.trim())
+            .ok()
+            .and_then(|at| u64::try_from(at.timestamp()).ok())
+    };
+    let raw = raw.trim().trim_matches('"');
+    let (start, end) = raw.split_once('—').unwrap_or((raw, ""));
+    Some((parse(start)?, parse(end)))
+}
 
 fn extract_layer1_from_projection_markdown(projection_md: &str) -> Layer1ProjectionExtract {
     #[derive(Clone, Copy, PartialEq, Eq)]
@@ -2234,9 +3746,15 @@ fn extract_layer1_from_projection_markdown(projection_md: &str) -> Layer1Project
     let mut tool_lines = Vec::<String>::new();
     let mut message_count: Option<usize> = None;
     let mut filtered_noise_count: Option<usize> = None;
+    let mut time_range: Option<(u64, Option<u64>)> = None;
 
     for raw_line in projection_md.lines() {
         let line = raw_line.trim();
+        if let Some(raw_range) = line.strip_prefix("time_range_utc:")
+            && time_range.is_none()
+        {
+            time_range = parse_projection_time_range(raw_range);
+        }
         if let Some(raw_count) = line.strip_prefix("message_count:")
             && message_count.is_none()
         {
@@ -2307,6 +3825,7 @@ fn extract_layer1_from_projection_markdown(projection_md: &str) -> Layer1Project
         execution_summary,
         message_count.unwrap_or(fallback_messages),
         filtered_noise_count.unwrap_or(0),
+        time_range,
     )
 }
 
@@ -2453,9 +3972,18 @@ fn build_execution_summary_lines(data: &ProjectionData) -> Option<Vec<String>> {
     Some(lines)
 }
 
+fn push_time_range_metadata(out: &mut String, time_range: Option<&TimeRangeLabels>) {
+    if let Some(range) = time_range {
+        out.push_str(&format!("- time_range_local: {}\n", range.local));
+        out.push_str(&format!("- time_range_utc: {}\n", range.utc));
+        out.push_str(&format!("- local_timezone: {}\n", range.timezone));
+    }
+}
+
 fn build_layer1_signal_summary(
     session_id: &str,
     archive_path: &str,
+    time_range: Option<&TimeRangeLabels>,
     turns: &[(String, String)],
     execution_summary: Option<&[String]>,
 ) -> String {
@@ -2463,6 +3991,7 @@ fn build_layer1_signal_summary(
     out.push_str("## L1 Normalisation Session Digest\n");
     out.push_str(&format!("- session_id: {session_id}\n"));
     out.push_str(&format!("- archive_path: {archive_path}\n"));
+    push_time_range_metadata(&mut out, time_range);
     if let Some(lines) = execution_summary {
         for line in lines {
             out.push_str(line);
@@ -2490,6 +4019,7 @@ fn build_layer1_signal_summary(
 
 fn render_layer1_session_block(
     input: &DistillInput,
+    time_range: Option<&TimeRangeLabels>,
     message_count: usize,
     filtered_noise_count: usize,
     turns: &[(String, String)],
@@ -2501,6 +4031,13 @@ fn render_layer1_session_block(
     out.push('\n');
     out.push_str(&format!("## Session {}\n", input.session_id));
     out.push_str(&format!("- Source Archive: `{}`\n", input.archive_path));
+    if let Some(range) = time_range {
+        out.push_str(&format!(
+            "- Time Range: {} ({})\n",
+            range.local, range.timezone
+        ));
+        out.push_str(&format!("- Time Range (UTC): {}\n", range.utc));
+    }
     out.push_str(&format!("- Message Count: {message_count}\n"));
     out.push_str(&format!("- Noise Filtered: {filtered_noise_count}\n\n"));
     out.push_str("### Conversation\n");
@@ -2582,7 +4119,7 @@ fn ensure_daily_memory_header(existing: &str, date_label: &str) -> String {
 }
 
 fn today_daily_memory_path(paths: &MoonPaths, epoch_secs: u64) -> String {
-    daily_memory_path(paths, Some(epoch_secs))
+    daily_memory_path(paths, Some(epoch_secs), resolve_residential_tz())
 }
 
 fn sha256_hex(input: &str) -> String {
@@ -2591,7 +4128,7 @@ fn sha256_hex(input: &str) -> String {
     format!("{:x}", hasher.finalize())
 }
 
-fn atomic_write_file(path: &Path, content: &str) -> Result<()> {
+pub(crate) fn atomic_write_file(path: &Path, content: &str) -> Result<()> {
     if let Some(parent) = path.parent() {
         fs::create_dir_all(parent)
             .with_context(|| format!("failed to create {}", parent.display()))?;
@@ -2614,7 +4151,8 @@ fn atomic_write_file(path: &Path, content: &str) -> Result<()> {
     Ok(())
 }
 
-fn acquire_memory_lock(paths: &MoonPaths) -> Result<fs::File> {
+/// Serialises every read-merge-write of `memory.md` (synthesis, `moon memory import`).
+pub(crate) fn acquire_memory_lock(paths: &MoonPaths) -> Result<fs::File> {
     fs::create_dir_all(&paths.logs_dir)
         .with_context(|| format!("failed to create {}", paths.logs_dir.display()))?;
     let lock_path = paths.logs_dir.join(MEMORY_LOCK_FILE);
@@ -2905,6 +4443,7 @@ fn normalize_wisdom_summary(raw: &str, daily_memory: &str, current_memory: &str)
         Lessons,
         Prefs,
         Durable,
+        Conflicts,
         Unknown,
     }
 
@@ -2923,7 +4462,9 @@ fn normalize_wisdom_summary(raw: &str, daily_memory: &str, current_memory: &str)
         }
         if line.starts_with("##") || line.starts_with('#') {
             let lower = line.to_ascii_lowercase();
-            section = if lower.contains("lesson") {
+            section = if line.eq_ignore_ascii_case(MEMORY_CONFLICTS_HEADING) {
+                Section::Conflicts
+            } else if lower.contains("lesson") {
                 Section::Lessons
             } else if lower.contains("preference") || lower.contains("like") {
                 Section::Prefs
@@ -2938,6 +4479,10 @@ fn normalize_wisdom_summary(raw: &str, daily_memory: &str, current_memory: &str)
             continue;
         }
 
+        if section == Section::Conflicts {
+            continue;
+        }
+
         let normalized = line
             .trim_start_matches("- ")
             .trim_start_matches("* ")
@@ -2987,7 +4532,7 @@ fn normalize_wisdom_summary(raw: &str, daily_memory: &str, current_memory: &str)
                 normalized,
                 MAX_WISDOM_ITEMS_PER_SECTION,
             ),
-            Section::Unknown => {}
+            Section::Conflicts | Section::Unknown => {}
         }
     }
 
@@ -3002,27 +4547,202 @@ fn normalize_wisdom_summary(raw: &str, daily_memory: &str, current_memory: &str)
     if durable.is_empty() {
         durable = fallback_durable;
     }
-    render_wisdom_summary(&lessons, &prefs, &durable)
+    render_wisdom_summary(&lessons, &prefs, &durable)
+}
+
+fn validate_wisdom_summary(summary: &str) -> Result<()> {
+    let lower = summary.to_ascii_lowercase();
+    if !lower.contains("## lessons learned") {
+        anyhow::bail!("wisdom summary missing `Lessons Learned` section");
+    }
+    if !lower.contains("## user preferences") {
+        anyhow::bail!("wisdom summary missing `User Preferences` section");
+    }
+    if !lower.contains("## durable decisions & context") {
+        anyhow::bail!("wisdom summary missing `Durable Decisions & Context` section");
+    }
+    if summary.lines().count() > MAX_WISDOM_LINES {
+        anyhow::bail!("wisdom summary exceeds concise line budget");
+    }
+    if summary.contains("**User:**") || summary.contains("**Assistant:**") {
+        anyhow::bail!("wisdom summary contains raw dialogue markers");
+    }
+    Ok(())
+}
+
+/// Embeds a batch of bullets, one vector per input in order.
+type EmbedBullets<'a> = &'a mut dyn FnMut(&[String]) -> Result<Vec<Vec<f32>>>;
+
+/// Pairs new bullets with existing bullets that share a topic but state a different value.
+///
+/// With `embed` (a remote `[embed]` provider), topic similarity is the cosine of the bullets'
+/// embeddings, which also pairs paraphrases with little word overlap. Without it, or when the
+/// embedding call fails, it is the cosine of stopword-filtered term counts over at least two
+/// shared terms. Either way candidates need to disagree on at least one term on each side, and
+/// a configured synthesis model confirms them afterwards.
+fn detect_memory_conflict_candidates(
+    existing_memory: &str,
+    summary: &str,
+    embed: Option<EmbedBullets<'_>>,
+) -> Vec<MemoryConflict> {
+    let existing = memory_bullets(existing_memory)
+        .into_iter()
+        .map(|bullet| {
+            let terms = bullet_terms(&bullet);
+            (bullet, terms)
+        })
+        .collect::<Vec<_>>();
+    let incoming = memory_bullets(summary);
+    let vectors = match embed {
+        Some(embed) if !existing.is_empty() && !incoming.is_empty() => {
+            let texts = existing
+                .iter()
+                .map(|(bullet, _)| bullet.clone())
+                .chain(incoming.iter().cloned())
+                .collect::<Vec<_>>();
+            match embed(&texts) {
+                Ok(vectors) if vectors.len() == texts.len() => Some(vectors),
+                Ok(vectors) => {
+                    warn_conflict_embedding_failed(&format!(
+                        "{} embeddings for {} bullets",
+                        vectors.len(),
+                        texts.len()
+                    ));
+                    None
+                }
+                Err(err) => {
+                    warn_conflict_embedding_failed(&format!("{err:#}"));
+                    None
+                }
+            }
+        }
+        _ => None,
+    };
+    let mut out = Vec::new();
+    for (incoming_idx, incoming) in incoming.into_iter().enumerate() {
+        if out.len() >= MAX_MEMORY_CONFLICTS {
+            break;
+        }
+        let incoming_terms = bullet_terms(&incoming);
+        let mut best: Option<(f64, &String)> = None;
+        for (existing_idx, (bullet, terms)) in existing.iter().enumerate() {
+            if terms == &incoming_terms {
+                continue;
+            }
+            let shared = terms
+                .keys()
+                .filter(|term| incoming_terms.contains_key(*term))
+                .count();
+            let only_existing = terms.len().saturating_sub(shared);
+            let only_incoming = incoming_terms.len().saturating_sub(shared);
+            if only_existing == 0 || only_incoming == 0 {
+                continue;
+            }
+            let similarity = match &vectors {
+                Some(vectors) => {
+                    let similarity = vectors::cosine_similarity(
+                        &vectors[existing_idx],
+                        &vectors[existing.len() + incoming_idx],
+                    );
+                    if similarity < MEMORY_CONFLICT_MIN_VECTOR_SIMILARITY {
+                        continue;
+                    }
+                    similarity
+                }
+                None => {
+                    let similarity = bullet_similarity(terms, &incoming_terms);
+                    if shared < MEMORY_CONFLICT_MIN_SHARED_TERMS
+                        || similarity < MEMORY_CONFLICT_MIN_SIMILARITY
+                    {
+                        continue;
+                    }
+                    similarity
+                }
+            };
+            if best.is_none_or(|(score, _)| similarity > score) {
+                best = Some((similarity, bullet));
+            }
+        }
+        if let Some((similarity, bullet)) = best {
+            out.push(MemoryConflict {
+                existing: bullet.clone(),
+                incoming,
+                similarity,
+                model_confirmed: false,
+            });
+        }
+    }
+    out
+}
+
+fn warn_conflict_embedding_failed(err: &str) {
+    warn::emit(WarnEvent {
+        code: "EMBED_FAILED",
+        stage: "distill",
+        action: "memory-conflicts",
+        session: "na",
+        archive: "na",
+        source: "na",
+        retry: "lexical-fallback",
+        reason: "conflict-embedding-failed",
+        err,
+    });
+}
+
+fn build_memory_conflict_prompt(conflict: &MemoryConflict) -> String {
+    format!(
+        concat!(
+            "Two memory statements about the same topic are shown below.\n",
+            "Answer YES if the new statement contradicts or replaces the earlier one ",
+            "(for example a changed default, a renamed service, or a reversed decision). ",
+            "Answer NO if both can be true at the same time.\n",
+            "Reply with YES or NO only.\n\n",
+            "Earlier: {existing}\n",
+            "New: {incoming}\n"
+        ),
+        existing = conflict.existing,
+        incoming = conflict.incoming
+    )
+}
+
+fn confirm_memory_conflicts(
+    remote: Option<&RemoteModelConfig>,
+    candidates: Vec<MemoryConflict>,
+    remote_tokens: &mut u64,
+) -> Vec<MemoryConflict> {
+    let Some(remote) = remote else {
+        return candidates;
+    };
+    let mut out = Vec::new();
+    for mut conflict in candidates {
+        let prompt = build_memory_conflict_prompt(&conflict);
+        match call_remote_prompt(remote, &prompt) {
+            Ok(answer) => {
+                *remote_tokens += estimate_remote_tokens(&prompt, &answer);
+                let verdict = answer.trim().to_ascii_lowercase();
+                if verdict.starts_with("yes") {
+                    conflict.model_confirmed = true;
+                    out.push(conflict);
+                }
+            }
+            // Keep the lexical verdict when the model check is unavailable.
+            Err(_) => out.push(conflict),
+        }
+    }
+    out
 }
 
-fn validate_wisdom_summary(summary: &str) -> Result<()> {
-    let lower = summary.to_ascii_lowercase();
-    if !lower.contains("## lessons learned") {
-        anyhow::bail!("wisdom summary missing `Lessons Learned` section");
-    }
-    if !lower.contains("## user preferences") {
-        anyhow::bail!("wisdom summary missing `User Preferences` section");
-    }
-    if !lower.contains("## durable decisions & context") {
-        anyhow::bail!("wisdom summary missing `Durable Decisions & Context` section");
-    }
-    if summary.lines().count() > MAX_WISDOM_LINES {
-        anyhow::bail!("wisdom summary exceeds concise line budget");
-    }
-    if summary.contains("**User:**") || summary.contains("**Assistant:**") {
-        anyhow::bail!("wisdom summary contains raw dialogue markers");
+fn render_memory_conflicts(conflicts: &[MemoryConflict]) -> String {
+    let mut out = String::new();
+    out.push_str(MEMORY_CONFLICTS_HEADING);
+    out.push('\n');
+    for conflict in conflicts.iter().take(MAX_MEMORY_CONFLICTS) {
+        out.push_str(&format!(
+            "- New: {} | Earlier: {}\n",
+            conflict.incoming, conflict.existing
+        ));
     }
-    Ok(())
+    out
 }
 
 fn build_wisdom_prompt(day_key: &str, daily_memory: &str, current_memory: &str) -> String {
@@ -3038,6 +4758,7 @@ fn build_wisdom_prompt(day_key: &str, daily_memory: &str, current_memory: &str)
             "- Keep concise, high-signal bullets only.\n",
             "- Prefer repeated user preferences and durable decisions.\n",
             "- Do not include raw dialogue transcripts.\n",
+            "- Keep `[decay:...]` and `[ttl:...]` tags on bullets verbatim.\n",
             "- Merge with existing MEMORY context and avoid duplicates.\n\n",
             "Current MEMORY.md:\n{current_memory}\n\n",
             "Today's daily memory:\n{daily_memory}\n"
@@ -3048,18 +4769,13 @@ fn build_wisdom_prompt(day_key: &str, daily_memory: &str, current_memory: &str)
     )
 }
 
-fn build_wisdom_chunk_prompt(
-    day_key: &str,
-    chunk_index: usize,
-    chunk_total: usize,
-    daily_chunk: &str,
-    current_memory: &str,
-) -> String {
+/// Carries no chunk position, so a chunk's prompt (and its `chunk_cache` entry) stays the same
+/// when the day's log grows around it.
+fn build_wisdom_chunk_prompt(day_key: &str, daily_chunk: &str, current_memory: &str) -> String {
     format!(
         concat!(
             "You are maintaining MEMORY.md from daily conversation memory.\n",
             "Date: {day_key}\n",
-            "Chunk: {chunk_index}/{chunk_total}\n",
             "Return markdown only with exactly these sections:\n",
             "## Lessons Learned\n",
             "## User Preferences\n",
@@ -3068,18 +4784,70 @@ fn build_wisdom_chunk_prompt(
             "- Keep concise, high-signal bullets only.\n",
             "- Prefer repeated user preferences and durable decisions.\n",
             "- Do not include raw dialogue transcripts.\n",
+            "- Keep `[decay:...]` and `[ttl:...]` tags on bullets verbatim.\n",
             "- Treat this as partial input; preserve only durable points.\n\n",
             "Current MEMORY.md (bounded):\n{current_memory}\n\n",
             "Daily memory chunk:\n{daily_chunk}\n"
         ),
         day_key = day_key,
-        chunk_index = chunk_index,
-        chunk_total = chunk_total,
         current_memory = current_memory,
         daily_chunk = daily_chunk
     )
 }
 
+fn build_wisdom_rollup_prompt(
+    day_key: &str,
+    partial_summaries: &str,
+    current_memory: &str,
+) -> String {
+    format!(
+        concat!(
+            "You are maintaining MEMORY.md from daily conversation memory.\n",
+            "Date: {day_key}\n",
+            "The input below is a series of partial summaries of consecutive chunks of the day.\n",
+            "Return markdown only with exactly these sections:\n",
+            "## Lessons Learned\n",
+            "## User Preferences\n",
+            "## Durable Decisions & Context\n",
+            "Rules:\n",
+            "- Merge the partial summaries into one coherent summary, not a concatenation.\n",
+            "- Collapse points repeated across chunks; when chunks disagree keep the later one.\n",
+            "- Keep points from every chunk, not just the first ones.\n",
+            "- Keep `[decay:...]` and `[ttl:...]` tags on bullets verbatim.\n\n",
+            "Current MEMORY.md (bounded):\n{current_memory}\n\n",
+            "Partial summaries:\n{partial_summaries}\n"
+        ),
+        day_key = day_key,
+        current_memory = current_memory,
+        partial_summaries = partial_summaries
+    )
+}
+
+/// Packs consecutive partial summaries into groups of at most `max_bytes`, keeping chunk
+/// order; a single summary larger than the budget is truncated into a group of its own.
+fn group_partial_summaries(partials: &[String], max_bytes: usize) -> Vec<String> {
+    let mut groups = Vec::new();
+    let mut current = String::new();
+    for (idx, partial) in partials.iter().enumerate() {
+        let block = format!("### Chunk {}\n{}\n", idx + 1, partial.trim());
+        if !current.is_empty() && current.len() + block.len() + 1 > max_bytes {
+            groups.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push('\n');
+        }
+        current.push_str(&block);
+        if current.len() > max_bytes {
+            groups.push(truncate_text_to_bytes(&current, max_bytes));
+            current.clear();
+        }
+    }
+    if !current.is_empty() {
+        groups.push(current);
+    }
+    groups
+}
+
 fn truncate_text_to_bytes(text: &str, max_bytes: usize) -> String {
     if text.len() <= max_bytes {
         return text.to_string();
@@ -3156,7 +4924,7 @@ fn detect_wisdom_context_tokens(remote: &RemoteModelConfig) -> u64 {
     if let Some(tokens) = parse_env_u64("MOON_WISDOM_CONTEXT_TOKENS") {
         return tokens;
     }
-    if let Some(tokens) = detect_context_tokens_from_remote(remote) {
+    if let Some(tokens) = cached_context_tokens_from_remote(remote) {
         return tokens;
     }
     infer_context_tokens_from_model(remote.provider, &remote.model)
@@ -3174,7 +4942,7 @@ fn resolve_wisdom_remote_config() -> Result<Option<RemoteModelConfig>> {
 
     let provider = parse_provider_alias(&raw_provider).ok_or_else(|| {
         anyhow::anyhow!(
-            "syns skipped: invalid MOON_WISDOM_PROVIDER `{}`. Use one of: openai, anthropic, gemini, openai-compatible, local.",
+            "syns skipped: invalid MOON_WISDOM_PROVIDER `{}`. Use one of: openai, anthropic, gemini, openai-compatible, ollama, azure-openai, local.",
             raw_provider
         )
     })?;
@@ -3189,10 +4957,12 @@ fn resolve_wisdom_remote_config() -> Result<Option<RemoteModelConfig>> {
         anyhow::bail!("syns skipped: MOON_WISDOM_MODEL is empty after normalization");
     }
 
-    let base_url = match provider {
-        RemoteProvider::OpenAiCompatible => resolve_compatible_base_url(&normalized_model),
-        _ => None,
-    };
+    let base_url = resolve_provider_base_url(provider, &normalized_model);
+    if provider == RemoteProvider::AzureOpenAi && base_url.is_none() {
+        anyhow::bail!(
+            "syns skipped: missing AZURE_OPENAI_ENDPOINT for provider `azure-openai` (for example https://<resource>.openai.azure.com)."
+        );
+    }
     let api_key = resolve_api_key(provider).ok_or_else(|| {
         anyhow::anyhow!(
             "syns skipped: missing API key for provider `{}`. Fix the primary model credentials.",
@@ -3209,8 +4979,12 @@ fn resolve_wisdom_remote_config() -> Result<Option<RemoteModelConfig>> {
 }
 
 fn call_remote_prompt(remote: &RemoteModelConfig, prompt: &str) -> Result<String> {
+    let timeout_secs = match remote.provider {
+        RemoteProvider::Ollama => OLLAMA_REQUEST_TIMEOUT_SECS,
+        _ => REQUEST_TIMEOUT_SECS,
+    };
     let client = Client::builder()
-        .timeout(std::time::Duration::from_secs(REQUEST_TIMEOUT_SECS))
+        .timeout(std::time::Duration::from_secs(timeout_secs))
         .build()?;
 
     match remote.provider {
@@ -3316,15 +5090,258 @@ fn call_remote_prompt(remote: &RemoteModelConfig, prompt: &str) -> Result<String
             extract_openai_compatible_text(&json)
                 .context("openai-compatible wisdom response missing text content")
         }
+        RemoteProvider::Ollama => {
+            let base = remote
+                .base_url
+                .as_deref()
+                .unwrap_or(DEFAULT_OLLAMA_BASE_URL)
+                .trim_end_matches('/');
+            let url = format!("{base}/api/chat");
+            let payload = serde_json::json!({
+                "model": remote.model,
+                "messages": [{"role": "user", "content": prompt}],
+                "stream": false,
+                "options": {"temperature": 0.2}
+            });
+            let response = client.post(&url).json(&payload).send()?;
+            if !response.status().is_success() {
+                anyhow::bail!(
+                    "ollama wisdom call failed with status {}",
+                    response.status()
+                );
+            }
+            let json: Value = response.json()?;
+            extract_ollama_text(&json).context("ollama wisdom response missing text content")
+        }
+        RemoteProvider::AzureOpenAi => {
+            let url = azure_chat_completions_url(
+                remote.base_url.as_deref().unwrap_or_default(),
+                &resolve_azure_deployment(&remote.model),
+                &resolve_azure_api_version(),
+            );
+            let payload = serde_json::json!({
+                "messages": [{"role": "user", "content": prompt}],
+                "temperature": 0.2
+            });
+            let response = client
+                .post(&url)
+                .header("api-key", &remote.api_key)
+                .json(&payload)
+                .send()?;
+            if !response.status().is_success() {
+                anyhow::bail!(
+                    "azure-openai wisdom call failed with status {}",
+                    response.status()
+                );
+            }
+            let json: Value = response.json()?;
+            extract_openai_compatible_text(&json)
+                .context("azure-openai wisdom response missing text content")
+        }
+    }
+}
+
+struct WisdomSummary {
+    provider: String,
+    summary: String,
+    chunk_count: usize,
+    /// Provider call time per chunk, in chunk order; empty for local synthesis.
+    chunk_durations_ms: Vec<u64>,
+    rollup_levels: usize,
+}
+
+/// One synthesis chunk's provider call: estimated tokens and the normalized summary.
+struct ChunkCall {
+    result: Result<(u64, String)>,
+    duration_ms: u128,
+    /// Answered from `cache/distill` without calling the provider.
+    cached: bool,
+}
+
+/// Runs `work` over `items` on up to `concurrency` threads and returns the results in input
+/// order. `on_done` sees each result as it finishes, on the calling thread, so audit events
+/// still land in the watcher cycle's buffer.
+fn run_bounded<T, R, W, D>(items: &[T], concurrency: usize, work: W, mut on_done: D) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    W: Fn(&T) -> R + Sync,
+    D: FnMut(usize, &R),
+{
+    let workers = concurrency.clamp(1, items.len().max(1));
+    let next = std::sync::atomic::AtomicUsize::new(0);
+    let mut slots = items.iter().map(|_| None).collect::<Vec<Option<R>>>();
+    std::thread::scope(|scope| {
+        let (tx, rx) = std::sync::mpsc::channel();
+        for _ in 0..workers {
+            let tx = tx.clone();
+            let (next, work) = (&next, &work);
+            scope.spawn(move || {
+                loop {
+                    let idx = next.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                    let Some(item) = items.get(idx) else {
+                        break;
+                    };
+                    if tx.send((idx, work(item))).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(tx);
+        for (idx, result) in rx {
+            on_done(idx, &result);
+            slots[idx] = Some(result);
+        }
+    });
+    slots.into_iter().flatten().collect()
+}
+
+fn estimate_remote_tokens(prompt: &str, response: &str) -> u64 {
+    ((prompt.len() + response.len()) as f64 / AUTO_CHUNK_BYTES_PER_TOKEN).ceil() as u64
+}
+
+/// Summary of the chunk summaries: partials are packed into context-sized groups and each
+/// group is merged by the model, level by level, until one summary is left. A group whose
+/// call fails keeps its flat merge, so no chunk drops out. Returns the normalized summary and
+/// the number of levels that ran.
+#[allow(clippy::too_many_arguments)]
+fn rollup_wisdom_summaries(
+    paths: &MoonPaths,
+    remote: &RemoteModelConfig,
+    day_key: &str,
+    partials: Vec<String>,
+    bounded_current_memory: &str,
+    context_budget_bytes: usize,
+    current_memory: &str,
+    concurrency: usize,
+    remote_tokens: &mut u64,
+) -> (String, usize) {
+    let group_budget = context_budget_bytes
+        .saturating_sub(bounded_current_memory.len())
+        .saturating_sub(WISDOM_PROMPT_OVERHEAD_BYTES)
+        .max(WISDOM_MIN_DAILY_CHUNK_BYTES);
+    let model_key = format!(
+        "{}:rollup",
+        model_limits::cache_key(
+            remote.provider.label(),
+            remote.base_url.as_deref(),
+            &remote.model,
+        )
+    );
+
+    let mut summaries = partials;
+    let mut levels = 0usize;
+    while summaries.len() > 1 && levels < MAX_WISDOM_ROLLUP_LEVELS {
+        let groups = group_partial_summaries(&summaries, group_budget);
+        if groups.len() >= summaries.len() {
+            // Every summary already fills a group on its own; another level cannot shrink it.
+            break;
+        }
+        levels += 1;
+        let progress = |idx: usize, duration_ms: u128, bullets| ChunkProgress {
+            subject: ChunkSubject::Syns {
+                day_key,
+                rollup: Some(levels),
+            },
+            chunk_index: idx + 1,
+            chunk_count: groups.len(),
+            provider: remote.provider.label(),
+            duration_ms,
+            bullets,
+        };
+        let prepared = groups
+            .iter()
+            .map(|group| {
+                let prompt = build_wisdom_rollup_prompt(day_key, group, bounded_current_memory);
+                (group.as_str(), prompt)
+            })
+            .collect::<Vec<_>>();
+        let calls = run_bounded(
+            &prepared,
+            concurrency,
+            |(group, prompt)| {
+                let started = std::time::Instant::now();
+                let cache_key = chunk_cache::cache_key(&model_key, group);
+                if let Some(raw) = chunk_cache::lookup(paths, &cache_key) {
+                    return ChunkCall {
+                        result: Ok((0, normalize_wisdom_summary(&raw, group, current_memory))),
+                        duration_ms: started.elapsed().as_millis(),
+                        cached: true,
+                    };
+                }
+                let result = call_remote_prompt(remote, prompt).map(|raw| {
+                    if !read_only_mode() {
+                        let _ = chunk_cache::store(paths, &cache_key, &raw);
+                    }
+                    (
+                        estimate_remote_tokens(prompt, &raw),
+                        normalize_wisdom_summary(&raw, group, current_memory),
+                    )
+                });
+                ChunkCall {
+                    result,
+                    duration_ms: started.elapsed().as_millis(),
+                    cached: false,
+                }
+            },
+            |idx, call| match call {
+                ChunkCall {
+                    result: Ok((_, normalized)),
+                    duration_ms,
+                    cached,
+                } => record_chunk_progress(
+                    paths,
+                    if *cached { "cached" } else { "ok" },
+                    progress(idx, *duration_ms, count_summary_bullets(normalized)),
+                ),
+                ChunkCall {
+                    result: Err(_),
+                    duration_ms,
+                    ..
+                } => record_chunk_progress(paths, "failed", progress(idx, *duration_ms, 0)),
+            },
+        );
+        summaries = calls
+            .into_iter()
+            .zip(&groups)
+            .map(|(call, group)| match call.result {
+                Ok((tokens, normalized)) => {
+                    *remote_tokens += tokens;
+                    normalized
+                }
+                Err(_) => normalize_wisdom_summary(group, group, current_memory),
+            })
+            .collect();
     }
+
+    let merged = if summaries.len() == 1 {
+        summaries.remove(0)
+    } else {
+        let joined = summaries.join("\n\n");
+        normalize_wisdom_summary(&joined, &joined, current_memory)
+    };
+    (merged, levels)
 }
 
+#[allow(clippy::too_many_arguments)]
 fn generate_wisdom_summary(
+    paths: &MoonPaths,
     day_key: &str,
     daily_memory: &str,
     current_memory: &str,
-) -> Result<(String, String)> {
-    if let Some(remote) = resolve_wisdom_remote_config()? {
+    force_local: bool,
+    concurrency: usize,
+    rollup_threshold_chunks: usize,
+    remote_tokens: &mut u64,
+    fallback: &mut Option<ProviderFallback>,
+) -> Result<WisdomSummary> {
+    let remote = if force_local {
+        None
+    } else {
+        resolve_wisdom_remote_config()?
+    };
+    if let Some(remote) = remote {
         let context_tokens = detect_wisdom_context_tokens(&remote);
         let context_budget_bytes =
             token_limit_to_bytes_with_ratio(context_tokens, WISDOM_CONTEXT_SAFETY_RATIO);
@@ -3339,42 +5356,112 @@ fn generate_wisdom_summary(
             .max(WISDOM_MIN_DAILY_CHUNK_BYTES);
         let daily_chunks = split_text_by_max_bytes(daily_memory, daily_chunk_budget);
 
-        let mut partial_summaries = Vec::new();
-        let mut first_remote_error: Option<anyhow::Error> = None;
-        for (idx, chunk) in daily_chunks.iter().enumerate() {
-            let mut chunk_body = chunk.clone();
-            let mut prompt = build_wisdom_chunk_prompt(
-                day_key,
-                idx + 1,
-                daily_chunks.len(),
-                &chunk_body,
-                &bounded_current_memory,
-            );
+        // Prompts are built up front so they never depend on the order workers finish in.
+        let prompts = daily_chunks
+            .iter()
+            .map(|chunk| {
+                let mut chunk_body = chunk.clone();
+                let mut prompt =
+                    build_wisdom_chunk_prompt(day_key, &chunk_body, &bounded_current_memory);
+                while prompt.len() > context_budget_bytes
+                    && chunk_body.len() > WISDOM_MIN_DAILY_CHUNK_BYTES
+                {
+                    let next_budget = chunk_body.len().saturating_mul(8).saturating_div(10);
+                    chunk_body = truncate_text_to_bytes(&chunk_body, next_budget);
+                    prompt =
+                        build_wisdom_chunk_prompt(day_key, &chunk_body, &bounded_current_memory);
+                }
+                (prompt.len() <= context_budget_bytes).then_some((chunk_body, prompt))
+            })
+            .collect::<Vec<_>>();
 
-            while prompt.len() > context_budget_bytes
-                && chunk_body.len() > WISDOM_MIN_DAILY_CHUNK_BYTES
-            {
-                let next_budget = chunk_body.len().saturating_mul(8).saturating_div(10);
-                chunk_body = truncate_text_to_bytes(&chunk_body, next_budget);
-                prompt = build_wisdom_chunk_prompt(
-                    day_key,
-                    idx + 1,
-                    daily_chunks.len(),
-                    &chunk_body,
-                    &bounded_current_memory,
-                );
-            }
+        // Chunks of a grown daily log that are byte-identical to an earlier run reuse the model's
+        // answer from `cache/distill` instead of paying for it again.
+        let model_key = model_limits::cache_key(
+            remote.provider.label(),
+            remote.base_url.as_deref(),
+            &remote.model,
+        );
+        let progress = |idx: usize, duration_ms: u128, bullets| ChunkProgress {
+            subject: ChunkSubject::Syns {
+                day_key,
+                rollup: None,
+            },
+            chunk_index: idx + 1,
+            chunk_count: daily_chunks.len(),
+            provider: remote.provider.label(),
+            duration_ms,
+            bullets,
+        };
+        let calls = run_bounded(
+            &prompts,
+            concurrency,
+            |prepared| {
+                let (chunk_body, prompt) = prepared.as_ref()?;
+                let started = std::time::Instant::now();
+                let cache_key = chunk_cache::cache_key(&model_key, chunk_body);
+                if let Some(raw) = chunk_cache::lookup(paths, &cache_key) {
+                    return Some(ChunkCall {
+                        result: Ok((
+                            0,
+                            normalize_wisdom_summary(&raw, chunk_body, current_memory),
+                        )),
+                        duration_ms: started.elapsed().as_millis(),
+                        cached: true,
+                    });
+                }
+                let result = call_remote_prompt(&remote, prompt).map(|raw| {
+                    if !read_only_mode() {
+                        let _ = chunk_cache::store(paths, &cache_key, &raw);
+                    }
+                    (
+                        estimate_remote_tokens(prompt, &raw),
+                        normalize_wisdom_summary(&raw, chunk_body, current_memory),
+                    )
+                });
+                Some(ChunkCall {
+                    result,
+                    duration_ms: started.elapsed().as_millis(),
+                    cached: false,
+                })
+            },
+            |idx, call| match call {
+                None => record_chunk_progress(paths, "skipped", progress(idx, 0, 0)),
+                Some(ChunkCall {
+                    result: Ok((_, normalized)),
+                    duration_ms,
+                    cached,
+                }) => record_chunk_progress(
+                    paths,
+                    if *cached { "cached" } else { "ok" },
+                    progress(idx, *duration_ms, count_summary_bullets(normalized)),
+                ),
+                Some(ChunkCall {
+                    result: Err(_),
+                    duration_ms,
+                    ..
+                }) => record_chunk_progress(paths, "failed", progress(idx, *duration_ms, 0)),
+            },
+        );
 
-            if prompt.len() > context_budget_bytes {
+        // Folded in chunk order, so the rollup and the reported error match a serial run.
+        let mut partial_summaries = Vec::new();
+        let mut first_remote_error: Option<anyhow::Error> = None;
+        let mut failed_chunks = 0usize;
+        let mut chunk_durations_ms = Vec::with_capacity(calls.len());
+        for call in calls {
+            let Some(call) = call else {
+                chunk_durations_ms.push(0);
                 continue;
-            }
-
-            match call_remote_prompt(&remote, &prompt) {
-                Ok(raw) => {
-                    let normalized = normalize_wisdom_summary(&raw, &chunk_body, current_memory);
+            };
+            chunk_durations_ms.push(u64::try_from(call.duration_ms).unwrap_or(u64::MAX));
+            match call.result {
+                Ok((tokens, normalized)) => {
+                    *remote_tokens += tokens;
                     partial_summaries.push(normalized);
                 }
                 Err(err) => {
+                    failed_chunks += 1;
                     if first_remote_error.is_none() {
                         first_remote_error = Some(err);
                     }
@@ -3383,16 +5470,42 @@ fn generate_wisdom_summary(
         }
 
         if !partial_summaries.is_empty() {
-            let merged = if partial_summaries.len() == 1 {
-                partial_summaries.remove(0)
+            if let Some(err) = &first_remote_error {
+                *fallback = Some(ProviderFallback::new(&remote, err, failed_chunks));
+            }
+            let (merged, rollup_levels) = if partial_summaries.len() == 1 {
+                (partial_summaries.remove(0), 0)
+            } else if rollup_threshold_chunks > 0
+                && partial_summaries.len() > rollup_threshold_chunks
+            {
+                // Past the threshold a flat merge keeps only the first chunks' bullets per
+                // section, so the model merges the partials into one document first.
+                rollup_wisdom_summaries(
+                    paths,
+                    &remote,
+                    day_key,
+                    partial_summaries,
+                    &bounded_current_memory,
+                    context_budget_bytes,
+                    current_memory,
+                    concurrency,
+                    remote_tokens,
+                )
             } else {
-                normalize_wisdom_summary(
+                let merged = normalize_wisdom_summary(
                     &partial_summaries.join("\n\n"),
                     daily_memory,
                     current_memory,
-                )
+                );
+                (merged, 0)
             };
-            return Ok((remote.provider.label().to_string(), merged));
+            return Ok(WisdomSummary {
+                provider: remote.provider.label().to_string(),
+                summary: merged,
+                chunk_count: daily_chunks.len(),
+                chunk_durations_ms,
+                rollup_levels,
+            });
         }
 
         // Single bounded attempt before failing synthesis for this run.
@@ -3404,17 +5517,30 @@ fn generate_wisdom_summary(
                 .max(WISDOM_MIN_DAILY_CHUNK_BYTES),
         );
         let prompt = build_wisdom_prompt(day_key, &bounded_daily, &bounded_current_memory);
+        let started = std::time::Instant::now();
         if prompt.len() <= context_budget_bytes
             && let Ok(raw) = call_remote_prompt(&remote, &prompt)
         {
-            let normalized = normalize_wisdom_summary(&raw, daily_memory, current_memory);
-            return Ok((remote.provider.label().to_string(), normalized));
+            let duration_ms = u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX);
+            *remote_tokens += estimate_remote_tokens(&prompt, &raw);
+            if let Some(err) = &first_remote_error {
+                *fallback = Some(ProviderFallback::new(&remote, err, failed_chunks));
+            }
+            return Ok(WisdomSummary {
+                provider: remote.provider.label().to_string(),
+                summary: normalize_wisdom_summary(&raw, daily_memory, current_memory),
+                chunk_count: 1,
+                chunk_durations_ms: vec![duration_ms],
+                rollup_levels: 0,
+            });
         }
 
         if let Some(err) = first_remote_error {
-            return Err(err).context(
-                "syns skipped: configured primary model failed. Fix MOON_WISDOM_PROVIDER / MOON_WISDOM_MODEL and provider credentials.",
-            );
+            let error_class = ProviderErrorClass::classify(&err);
+            return Err(err).context(format!(
+                "syns skipped: configured primary model failed (error_class={}). Fix MOON_WISDOM_PROVIDER / MOON_WISDOM_MODEL and provider credentials.",
+                error_class.as_str()
+            ));
         }
         anyhow::bail!(
             "syns skipped: configured primary model produced no usable output. Fix MOON_WISDOM_PROVIDER / MOON_WISDOM_MODEL and retry."
@@ -3422,10 +5548,13 @@ fn generate_wisdom_summary(
     }
 
     let (lessons, prefs, durable) = local_wisdom_sections(daily_memory, current_memory);
-    Ok((
-        "local".to_string(),
-        render_wisdom_summary(&lessons, &prefs, &durable),
-    ))
+    Ok(WisdomSummary {
+        provider: "local".to_string(),
+        summary: render_wisdom_summary(&lessons, &prefs, &durable),
+        chunk_count: 1,
+        chunk_durations_ms: Vec::new(),
+        rollup_levels: 0,
+    })
 }
 
 pub fn run_distillation(paths: &MoonPaths, input: &DistillInput) -> Result<DistillOutput> {
@@ -3438,47 +5567,56 @@ pub fn run_distillation(paths: &MoonPaths, input: &DistillInput) -> Result<Disti
         .and_then(|v| v.to_str())
         .is_some_and(|ext| ext.eq_ignore_ascii_case("md"));
 
-    let (turns, execution_summary, message_count, filtered_noise_count) = if source_is_markdown {
-        let projection_md = fs::read_to_string(&input.archive_path)
-            .with_context(|| format!("failed to read {}", input.archive_path))?;
-        extract_layer1_from_projection_markdown(&projection_md)
-    } else {
-        let projection = extract_projection_data(&input.archive_path)
-            .with_context(|| format!("failed to parse archive {}", input.archive_path))?;
-        let turns = projection
-            .entries
-            .iter()
-            .filter_map(|entry| {
-                if entry.role != "user" && entry.role != "assistant" {
-                    return None;
-                }
-                normalize_turn_text(&entry.content).map(|text| (entry.role.clone(), text))
-            })
-            .collect::<Vec<_>>();
-        let execution_summary = build_execution_summary_lines(&projection);
-        (
-            turns,
-            execution_summary,
-            projection.message_count,
-            projection.filtered_noise_count,
-        )
-    };
+    let tz = resolve_residential_tz();
+    let (turns, execution_summary, message_count, filtered_noise_count, time_range) =
+        if source_is_markdown {
+            let projection_md = fs::read_to_string(&input.archive_path)
+                .with_context(|| format!("failed to read {}", input.archive_path))?;
+            extract_layer1_from_projection_markdown(&projection_md)
+        } else {
+            let projection = extract_projection_data(&input.archive_path)
+                .with_context(|| format!("failed to parse archive {}", input.archive_path))?;
+            let turns = projection
+                .entries
+                .iter()
+                .filter_map(|entry| {
+                    if entry.role != "user" && entry.role != "assistant" {
+                        return None;
+                    }
+                    normalize_turn_text(&entry.content).map(|text| (entry.role.clone(), text))
+                })
+                .collect::<Vec<_>>();
+            let execution_summary = build_execution_summary_lines(&projection);
+            (
+                turns,
+                execution_summary,
+                projection.message_count,
+                projection.filtered_noise_count,
+                projection
+                    .time_start_epoch
+                    .map(|start| (start, projection.time_end_epoch)),
+            )
+        };
+    let time_range =
+        time_range.and_then(|(start, end)| TimeRangeLabels::from_epochs(start, end, tz));
 
     let summary = build_layer1_signal_summary(
         &input.session_id,
         &input.archive_path,
+        time_range.as_ref(),
         &turns,
         execution_summary.as_deref(),
     );
     let session_block = render_layer1_session_block(
         input,
+        time_range.as_ref(),
         message_count,
         filtered_noise_count,
         &turns,
         execution_summary.as_deref(),
     );
 
-    let summary_path = daily_memory_path(paths, input.archive_epoch_secs);
+    let summary_path = daily_memory_path(paths, input.archive_epoch_secs, tz);
     let date_label = Path::new(&summary_path)
         .file_stem()
         .and_then(|v| v.to_str())
@@ -3491,15 +5629,67 @@ pub fn run_distillation(paths: &MoonPaths, input: &DistillInput) -> Result<Disti
     fs::write(&summary_path, full_text)
         .with_context(|| format!("failed to write {}", summary_path))?;
 
+    let mut graph_edges = None;
+    if graph_extraction_enabled() {
+        let mut texts = turns
+            .iter()
+            .map(|(_, text)| text.clone())
+            .collect::<Vec<_>>();
+        if let Some(execution) = &execution_summary {
+            texts.extend(execution.iter().cloned());
+        }
+        let edges = graph::extract_edges(
+            &input.session_id,
+            &input.archive_path,
+            &texts,
+            now_epoch_secs()?,
+        );
+        match graph::replace_session_edges(paths, &input.session_id, &edges) {
+            Ok(_) => graph_edges = Some(edges.len()),
+            Err(err) => warn::emit(WarnEvent {
+                code: "GRAPH_WRITE_FAILED",
+                stage: "distill",
+                action: "graph-extract",
+                session: &input.session_id,
+                archive: &input.archive_path,
+                source: "na",
+                retry: "retry-next-distill",
+                reason: "graph-write-failed",
+                err: &format!("{err:#}"),
+            }),
+        }
+    }
+
     audit::append_event(
         paths,
         "distill",
         "ok",
         &format!(
-            "l1_normalised session={} source={} target={}",
-            input.session_id, input.archive_path, summary_path
+            "l1_normalised session={} source={} target={}{}",
+            input.session_id,
+            input.archive_path,
+            summary_path,
+            graph_edges.map_or_else(String::new, |edges| format!(" graph_edges={edges}"))
         ),
+        serde_json::json!({
+            "mode": "l1_normalised",
+            "session": input.session_id,
+            "archive": input.archive_path,
+            "target": summary_path,
+            "graph_edges": graph_edges,
+        }),
     )?;
+    hooks::fire(
+        paths,
+        HookEvent::PostDistill,
+        &[
+            ("mode", "norm"),
+            ("session_id", &input.session_id),
+            ("archive_path", &input.archive_path),
+            ("summary_path", &summary_path),
+            ("provider", "l1-normaliser"),
+        ],
+    );
 
     Ok(DistillOutput {
         provider: "l1-normaliser".to_string(),
@@ -3507,9 +5697,67 @@ pub fn run_distillation(paths: &MoonPaths, input: &DistillInput) -> Result<Disti
         summary_path: summary_path.clone(),
         audit_log_path: paths.logs_dir.join("audit.log").display().to_string(),
         created_at_epoch_secs: now_epoch_secs()?,
+        memory_conflicts: Vec::new(),
+        expired_memory: Vec::new(),
+        remote_tokens: 0,
+        chunk_count: 1,
+        chunk_durations_ms: Vec::new(),
+        rollup_levels: 0,
+        provider_fallback: None,
     })
 }
 
+fn record_distill_budget(paths: &MoonPaths, day_key: &str, tokens: u64, limit: u64) {
+    if tokens == 0 {
+        return;
+    }
+    let now = now_epoch_secs().unwrap_or(0);
+    match budget::record_tokens(paths, day_key, tokens, limit, now) {
+        Ok(true) => {
+            let used = budget::load_for_day(paths, day_key)
+                .map(|spent| spent.tokens_used)
+                .unwrap_or(tokens);
+            warn::emit(WarnEvent {
+                code: "DISTILL_BUDGET_EXHAUSTED",
+                stage: "distill",
+                action: "record-budget",
+                session: "na",
+                archive: "na",
+                source: "na",
+                retry: "next-day",
+                reason: "daily-token-budget-exceeded",
+                err: &format!("tokens_used={used} budget={limit}"),
+            });
+            let _ = audit::append_event(
+                paths,
+                "distill-budget",
+                "degraded",
+                &format!("day={day_key} tokens_used={used} budget={limit} fallback=local"),
+                serde_json::json!({
+                    "day": day_key,
+                    "tokens_used": used,
+                    "budget": limit,
+                    "fallback": "local",
+                }),
+            );
+        }
+        Ok(false) => {}
+        Err(err) => {
+            warn::emit(WarnEvent {
+                code: "DISTILL_BUDGET_FAILED",
+                stage: "distill",
+                action: "record-budget",
+                session: "na",
+                archive: "na",
+                source: "na",
+                retry: "none",
+                reason: "budget-ledger-write-failed",
+                err: &format!("{err:#}"),
+            });
+        }
+    }
+}
+
 pub fn run_wisdom_distillation(
     paths: &MoonPaths,
     input: &WisdomDistillInput,
@@ -3584,12 +5832,71 @@ pub fn run_wisdom_distillation(
         "default:today+memory".to_string()
     };
     let synthesis_input = source_blocks.join("\n");
-    let (provider, summary) = generate_wisdom_summary(&synthesis_label, &synthesis_input, "")
-        .with_context(
-            || "syns skipped: failed to run synthesis with the configured primary model",
-        )?;
+    // Once the day's remote budget is spent, synthesis stays local until the next residential day.
+    let budget_limit = load_config()
+        .map(|cfg| cfg.distill.daily_token_budget)
+        .unwrap_or(0);
+    let budget_day_key = budget::day_key_for_epoch(now_epoch_secs()?, resolve_residential_tz());
+    let force_local = budget::load_for_day(paths, &budget_day_key)
+        .map(|spent| spent.is_exhausted(budget_limit))
+        .unwrap_or(false);
+    let mut remote_tokens = 0u64;
+    let mut provider_fallback = None;
+    let WisdomSummary {
+        provider,
+        mut summary,
+        chunk_count,
+        chunk_durations_ms,
+        rollup_levels,
+    } = generate_wisdom_summary(
+        paths,
+        &synthesis_label,
+        &synthesis_input,
+        "",
+        force_local,
+        input.concurrency,
+        input.rollup_threshold_chunks,
+        &mut remote_tokens,
+        &mut provider_fallback,
+    )
+    .with_context(|| "syns skipped: failed to run synthesis with the configured primary model")?;
     validate_wisdom_summary(&summary)?;
 
+    let existing_memory = fs::read_to_string(&paths.memory_file).unwrap_or_default();
+    let (decayed_summary, expired_memory) =
+        apply_memory_decay(&summary, &existing_memory, now_epoch_secs()?);
+    summary = decayed_summary;
+    // No remote `[embed]` provider (or no API key for it) leaves conflict detection lexical.
+    let mut embedder = load_config()
+        .ok()
+        .and_then(|cfg| RemoteEmbedder::from_config(&cfg.embed).ok().flatten());
+    let conflict_candidates = match embedder.as_mut() {
+        Some(embedder) => detect_memory_conflict_candidates(
+            &existing_memory,
+            &summary,
+            Some(&mut |texts: &[String]| embedder.embed_texts(texts)),
+        ),
+        None => detect_memory_conflict_candidates(&existing_memory, &summary, None),
+    };
+    let memory_conflicts = if conflict_candidates.is_empty() {
+        Vec::new()
+    } else {
+        let remote = if force_local {
+            None
+        } else {
+            resolve_wisdom_remote_config().ok().flatten()
+        };
+        confirm_memory_conflicts(remote.as_ref(), conflict_candidates, &mut remote_tokens)
+    };
+    record_distill_budget(paths, &budget_day_key, remote_tokens, budget_limit);
+    if !memory_conflicts.is_empty() {
+        summary = format!(
+            "{}\n\n{}",
+            summary.trim_end(),
+            render_memory_conflicts(&memory_conflicts)
+        );
+    }
+
     if input.dry_run {
         return Ok(DistillOutput {
             provider,
@@ -3601,6 +5908,13 @@ pub fn run_wisdom_distillation(
                 .display()
                 .to_string(),
             created_at_epoch_secs: now_epoch_secs()?,
+            memory_conflicts,
+            expired_memory,
+            remote_tokens,
+            chunk_count,
+            chunk_durations_ms,
+            rollup_levels,
+            provider_fallback,
         });
     }
 
@@ -3613,7 +5927,27 @@ pub fn run_wisdom_distillation(
     let output_hash = sha256_hex(&merged_memory);
 
     let previous_snapshot = latest_memory.clone();
+    // History only feeds `moon memory diff`; a failed snapshot must not block synthesis.
+    let warn_history = |action: &str, err: anyhow::Error| {
+        warn::emit(WarnEvent {
+            code: "MEMORY_HISTORY_FAILED",
+            stage: "distill",
+            action,
+            session: "na",
+            archive: "na",
+            source: &paths.memory_file.display().to_string(),
+            retry: "next-syns",
+            reason: "memory-history-write-failed",
+            err: &format!("{err:#}"),
+        });
+    };
+    if let Err(err) = ensure_memory_baseline(paths, &previous_snapshot) {
+        warn_history("record-memory-baseline", err);
+    }
     atomic_write_file(&paths.memory_file, &merged_memory)?;
+    if let Err(err) = record_memory_snapshot(paths, &merged_memory, now_epoch_secs()?) {
+        warn_history("record-memory-snapshot", err);
+    }
 
     let event = DistillAuditEvent {
         at_epoch_secs: now_epoch_secs()?,
@@ -3633,17 +5967,39 @@ pub fn run_wisdom_distillation(
         }
     };
 
-    let _ = audit::append_event(
+    let mut message = format!(
+        "mode=syns trigger={} sources={} target={} provider={} memory_conflicts={} memory_expired={} remote_tokens={}{}",
+        input.trigger,
+        participating_sources.join(";"),
+        paths.memory_file.display(),
+        provider,
+        memory_conflicts.len(),
+        expired_memory.len(),
+        remote_tokens,
+        if force_local { " budget=exhausted" } else { "" }
+    );
+    let mut details = serde_json::json!({
+        "mode": "syns",
+        "trigger": input.trigger,
+        "sources": participating_sources.join(";"),
+        "target": paths.memory_file.display().to_string(),
+        "provider": provider,
+        "memory_conflicts": memory_conflicts.len(),
+        "memory_expired": expired_memory.len(),
+        "remote_tokens": remote_tokens,
+        "budget_exhausted": force_local,
+    });
+    ProviderFallback::annotate_audit(provider_fallback.as_ref(), &mut message, &mut details);
+    let _ = audit::append_event(paths, "distill", "ok", &message, details);
+    hooks::fire(
         paths,
-        "distill",
-        "ok",
-        &format!(
-            "mode=syns trigger={} sources={} target={} provider={}",
-            input.trigger,
-            participating_sources.join(";"),
-            paths.memory_file.display(),
-            provider
-        ),
+        HookEvent::PostDistill,
+        &[
+            ("mode", "syns"),
+            ("source_path", &participating_sources.join(";")),
+            ("summary_path", &paths.memory_file.display().to_string()),
+            ("provider", &provider),
+        ],
     );
 
     Ok(DistillOutput {
@@ -3652,6 +6008,13 @@ pub fn run_wisdom_distillation(
         summary_path: paths.memory_file.display().to_string(),
         audit_log_path,
         created_at_epoch_secs: now_epoch_secs()?,
+        memory_conflicts,
+        expired_memory,
+        remote_tokens,
+        chunk_count,
+        chunk_durations_ms,
+        rollup_levels,
+        provider_fallback,
     })
 }
 
@@ -3659,13 +6022,17 @@ pub fn run_wisdom_distillation(
 mod tests {
     use super::{
         ChunkSummaryRollup, DistillInput, Distiller, LocalDistiller, MAX_SUMMARY_CHARS,
-        RemoteProvider, WisdomDistillInput, clamp_summary, extract_anthropic_text,
-        extract_openai_compatible_text, extract_openai_text, infer_provider_from_model,
-        parse_prefixed_model, run_distillation, run_wisdom_distillation, sanitize_model_summary,
-        stream_archive_chunks, summarize_provider_mix,
+        ProviderErrorClass, ProviderFallback, RemoteModelConfig, RemoteProvider,
+        WisdomDistillInput, clamp_summary, extract_anthropic_text, extract_ollama_text,
+        extract_openai_compatible_text, extract_openai_text, group_partial_summaries,
+        infer_provider_from_model, parse_prefixed_model, run_bounded,
+        run_chunked_archive_distillation, run_distillation, run_wisdom_distillation,
+        sanitize_model_summary, stream_archive_chunks, summarize_provider_mix,
     };
+    use crate::moon::archive::TimeRangeLabels;
     use crate::moon::paths::MoonPaths;
     use serde_json::json;
+    use std::borrow::Cow;
     use std::collections::BTreeMap;
     use std::fs;
     use std::path::PathBuf;
@@ -3705,17 +6072,7 @@ mod tests {
     }
 
     fn make_test_paths(root: &std::path::Path) -> MoonPaths {
-        MoonPaths {
-            moon_home: root.join("moon-home"),
-            archives_dir: root.join("archives"),
-            memory_dir: root.join("memory"),
-            memory_file: root.join("MEMORY.md"),
-            logs_dir: root.join("moon/logs"),
-            openclaw_sessions_dir: root.join("sessions"),
-            qmd_bin: root.join("qmd"),
-            qmd_db: root.join("qmd.db"),
-            moon_home_is_explicit: true,
-        }
+        MoonPaths::for_test(&root.join("moon-home"))
     }
 
     #[test]
@@ -3726,7 +6083,8 @@ mod tests {
             archive_text: format!(
                 "{{\"type\":\"message\",\"message\":{{\"role\":\"toolResult\",\"content\":[{{\"type\":\"text\",\"text\":\"{{\\\"payload\\\":\\\"{}\\\"}}\"}}]}}}}\n{{\"type\":\"message\",\"message\":{{\"role\":\"user\",\"content\":[{{\"type\":\"text\",\"text\":\"Decision: set qmd mask to jsonl for archive indexing.\"}}]}}}}\n",
                 "X".repeat(4096)
-            ),
+            )
+            .into(),
             archive_epoch_secs: None,
         };
 
@@ -3746,6 +6104,42 @@ mod tests {
         assert!(clamped.contains("[summary truncated]"));
     }
 
+    #[test]
+    fn provider_fallback_classifies_errors_and_masks_the_api_key() {
+        let remote = RemoteModelConfig {
+            provider: RemoteProvider::Gemini,
+            model: "gemini-2.5-flash".to_string(),
+            api_key: "AIza-secret".to_string(),
+            base_url: None,
+        };
+        let classify =
+            |text: &str| ProviderErrorClass::classify(&anyhow::anyhow!(text.to_string()));
+        assert_eq!(
+            classify("gemini wisdom call failed with status 403 Forbidden"),
+            ProviderErrorClass::Auth
+        );
+        assert_eq!(
+            classify("openai call failed with status 429 Too Many Requests"),
+            ProviderErrorClass::Quota
+        );
+        assert_eq!(
+            classify("openai call failed with status 503 Service Unavailable"),
+            ProviderErrorClass::Network
+        );
+        assert_eq!(
+            classify("openai response missing text content"),
+            ProviderErrorClass::Other
+        );
+
+        let err = anyhow::anyhow!("request to https://host/models/x?key=AIza-secret failed")
+            .context("gemini call failed with status 401 Unauthorized");
+        let fallback = ProviderFallback::new(&remote, &err, 2);
+        assert_eq!(fallback.error_class, ProviderErrorClass::Auth);
+        assert_eq!(fallback.failed_chunks, 2);
+        assert!(!fallback.error.contains("AIza-secret"));
+        assert!(fallback.detail().contains("from=gemini error_class=auth"));
+    }
+
     #[test]
     fn sanitize_model_summary_rejects_json_blob_output() {
         let raw = "{ \"type\": \"message\" }\n{ \"payload\": \"x\" }\n";
@@ -3775,6 +6169,18 @@ mod tests {
         let (provider, model) = parse_prefixed_model("deepseek:deepseek-chat");
         assert_eq!(provider, Some(RemoteProvider::OpenAiCompatible));
         assert_eq!(model, "deepseek-chat");
+
+        let (provider, model) = parse_prefixed_model("ollama:llama3.1:8b");
+        assert_eq!(provider, Some(RemoteProvider::Ollama));
+        assert_eq!(model, "llama3.1:8b");
+
+        let (provider, model) = parse_prefixed_model("azure:gpt-4.1");
+        assert_eq!(provider, Some(RemoteProvider::AzureOpenAi));
+        assert_eq!(model, "gpt-4.1");
+
+        let (provider, model) = parse_prefixed_model("llama3.1:8b");
+        assert_eq!(provider, None);
+        assert_eq!(model, "llama3.1:8b");
     }
 
     #[test]
@@ -3839,6 +6245,23 @@ mod tests {
         );
     }
 
+    #[test]
+    fn extract_ollama_text_reads_chat_message_content() {
+        let payload = json!({
+            "model": "llama3.1",
+            "message": {"role": "assistant", "content": "hello from ollama"},
+            "done": true
+        });
+        assert_eq!(
+            extract_ollama_text(&payload).as_deref(),
+            Some("hello from ollama")
+        );
+        assert_eq!(
+            extract_ollama_text(&json!({"error": "model not found"})),
+            None
+        );
+    }
+
     #[test]
     fn chunk_rollup_groups_keyword_sections() {
         let mut rollup = ChunkSummaryRollup::default();
@@ -3846,13 +6269,78 @@ mod tests {
             "- Decision: enable chunk distill\n- Rule: keep archive gate at 2MB\n- Milestone: watcher can process 10MB archives\n- Open task: tune chunk size by workload",
         );
 
-        let rendered = rollup.render("session-1", "/tmp/a.jsonl", 4, 524_288, 128, false);
+        let range = TimeRangeLabels::from_epochs(
+            1_700_000_000,
+            Some(1_700_000_600),
+            chrono_tz::Asia::Tokyo,
+        );
+        let rendered = rollup.render(
+            "session-1",
+            "/tmp/a.jsonl",
+            range.as_ref(),
+            4,
+            524_288,
+            128,
+            false,
+        );
+        assert!(
+            rendered.contains(
+                "- time_range_local: 2023-11-15T07:13:20+09:00 — 2023-11-15T07:23:20+09:00"
+            )
+        );
+        assert!(rendered.contains("- time_range_utc: 2023-11-14T22:13:20Z — 2023-11-14T22:23:20Z"));
+        assert!(rendered.contains("- local_timezone: Asia/Tokyo"));
         assert!(rendered.contains("### Decisions"));
         assert!(rendered.contains("### Rules"));
         assert!(rendered.contains("### Milestones"));
         assert!(rendered.contains("### Open Tasks"));
     }
 
+    #[test]
+    fn run_bounded_returns_results_in_input_order_whatever_finishes_first() {
+        let items = (0..12u64).collect::<Vec<_>>();
+        let mut seen = Vec::new();
+        let results = run_bounded(
+            &items,
+            4,
+            |item| {
+                // Earlier items sleep longer, so they finish last.
+                std::thread::sleep(std::time::Duration::from_millis(24 - 2 * item));
+                item * 10
+            },
+            |idx, result| seen.push((idx, *result)),
+        );
+
+        assert_eq!(
+            results,
+            (0..12u64).map(|item| item * 10).collect::<Vec<_>>()
+        );
+        seen.sort_unstable();
+        assert_eq!(seen.len(), 12);
+        assert!(seen.iter().all(|(idx, result)| *result == *idx as u64 * 10));
+        assert!(run_bounded(&[] as &[u64], 4, |item| *item, |_, _| {}).is_empty());
+    }
+
+    #[test]
+    fn group_partial_summaries_packs_chunks_in_order_within_budget() {
+        let partials = (1..=5)
+            .map(|idx| format!("## Lessons Learned\n- lesson {idx} {}", "x".repeat(60)))
+            .collect::<Vec<_>>();
+        let groups = group_partial_summaries(&partials, 250);
+
+        assert_eq!(groups.len(), 3);
+        assert!(groups.iter().all(|group| group.len() <= 250));
+        assert!(groups[0].starts_with("### Chunk 1\n") && groups[0].contains("### Chunk 2\n"));
+        assert!(groups[1].starts_with("### Chunk 3\n") && groups[1].contains("lesson 4"));
+        assert!(groups[2].starts_with("### Chunk 5\n"));
+
+        let oversized = vec!["- big".repeat(200), "- small".to_string()];
+        let groups = group_partial_summaries(&oversized, 128);
+        assert_eq!(groups.len(), 2);
+        assert!(groups[0].len() <= 128);
+        assert!(groups[1].contains("- small"));
+    }
+
     #[test]
     fn stream_archive_chunks_splits_input_by_target_size() {
         let stamp = SystemTime::now()
@@ -3865,7 +6353,7 @@ mod tests {
         let mut chunks = Vec::new();
         let path_str = path.to_string_lossy().to_string();
         let (count, truncated) = stream_archive_chunks(&path_str, 10, 16, |idx, text| {
-            chunks.push((idx, text));
+            chunks.push((idx, text.to_string()));
             Ok(())
         })
         .expect("chunking should succeed");
@@ -3931,6 +6419,160 @@ mod tests {
         assert_eq!(data.time_end_epoch, Some(late));
     }
 
+    #[test]
+    fn extract_projection_data_reads_whole_document_json_sessions() {
+        let stamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("clock should be after epoch")
+            .as_nanos();
+        let dir = std::env::temp_dir().join(format!("moon-projection-doc-test-{stamp}"));
+        fs::create_dir_all(&dir).expect("mkdir");
+
+        let array_path = dir.join("array.json");
+        let array_doc = json!([
+            {"role": "user", "content": "deploy the billing service", "timestamp": 1_700_000_000},
+            {"message": {"role": "assistant", "content": [{"type": "text", "text": "deployment started"}]}}
+        ]);
+        fs::write(
+            &array_path,
+            serde_json::to_string_pretty(&array_doc).expect("render"),
+        )
+        .expect("write array doc");
+        let data = super::extract_projection_data(&array_path.to_string_lossy())
+            .expect("extract array document");
+        assert_eq!(data.entries.len(), 2);
+        assert!(data.entries.iter().all(|entry| entry.role != "system"));
+        assert_eq!(data.entries[0].role, "user");
+        assert_eq!(data.entries[0].content, "deploy the billing service");
+        assert_eq!(data.time_start_epoch, Some(1_700_000_000));
+
+        let object_path = dir.join("object.json");
+        let object_doc = json!({
+            "sessionId": "abc",
+            "messages": [
+                {"role": "user", "content": [{"type": "text", "text": "rotate the api keys"}]},
+                {"role": "assistant", "content": "keys rotated"}
+            ]
+        });
+        fs::write(
+            &object_path,
+            serde_json::to_string_pretty(&object_doc).expect("render"),
+        )
+        .expect("write object doc");
+        let data = super::extract_projection_data(&object_path.to_string_lossy())
+            .expect("extract object document");
+        assert_eq!(data.entries.len(), 2);
+        assert_eq!(data.entries[1].content, "keys rotated");
+
+        let jsonl_path = dir.join("lines.json");
+        let line1 = json!({"message": {"role": "user", "content": [{"type": "text", "text": "first line"}]}});
+        let line2 = json!({"message": {"role": "assistant", "content": [{"type": "text", "text": "second line"}]}});
+        fs::write(&jsonl_path, format!("{line1}\n{line2}\n")).expect("write jsonl");
+        let data = super::extract_projection_data(&jsonl_path.to_string_lossy())
+            .expect("extract jsonl with json extension");
+        let _ = fs::remove_dir_all(&dir);
+        assert_eq!(data.entries.len(), 2);
+        assert_eq!(data.entries[1].content, "second line");
+    }
+
+    #[test]
+    fn json_document_projection_stops_reading_at_the_scan_byte_budget() {
+        let dir = tempdir().expect("tempdir");
+        let path = dir.path().join("large.json");
+        let mut body = String::from("[\n");
+        for idx in 0..2_000 {
+            let event = json!({"role": "user", "content": format!("step {idx} of the rollout")});
+            body.push_str(&format!("{event},\n"));
+        }
+        // Never closed: only a reader that stops at the budget can still project it.
+        body.push_str("{\"role\": \"user\", \"content\": ");
+        fs::write(&path, &body).expect("write document");
+
+        let collector = super::ProjectionCollector {
+            limits: super::MoonProjectionConfig {
+                max_scan_bytes: 8 * 1024,
+                ..super::MoonProjectionConfig::default()
+            },
+            sample_stride: 1,
+            ..super::ProjectionCollector::default()
+        };
+        let data = super::stream_projection_document(&path.to_string_lossy(), collector)
+            .expect("stream document")
+            .expect("json document");
+        assert!(data.truncated);
+        assert!(!data.entries.is_empty());
+        assert!(data.entries.len() < 2_000);
+        assert_eq!(data.entries[0].content, "step 0 of the rollout");
+    }
+
+    #[test]
+    fn projection_and_chunk_streaming_read_gzip_sessions() {
+        use flate2::Compression;
+        use flate2::write::GzEncoder;
+        use std::io::Write;
+
+        let tmp = tempdir().expect("tempdir");
+        let line1 = json!({"message": {"role": "user", "content": [{"type": "text", "text": "rotated question"}]}});
+        let line2 = json!({"message": {"role": "assistant", "content": [{"type": "text", "text": "rotated answer"}]}});
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        write!(encoder, "{line1}\n{line2}\n").expect("compress");
+        let path = tmp.path().join("s1.jsonl.gz");
+        fs::write(&path, encoder.finish().expect("finish gzip")).expect("write gz");
+        let path = path.to_string_lossy();
+
+        let data = super::extract_projection_data(&path).expect("extract gz projection");
+        assert_eq!(data.entries.len(), 2);
+        assert_eq!(data.entries[1].content, "rotated answer");
+
+        let mut chunks = Vec::new();
+        stream_archive_chunks(&path, 1024, 4, |_, chunk| {
+            chunks.push(chunk.to_string());
+            Ok(())
+        })
+        .expect("stream gz chunks");
+        assert_eq!(chunks, vec![format!("{line1}\n{line2}\n")]);
+    }
+
+    #[test]
+    fn extract_projection_data_stitches_interleaved_tool_results_by_call_id() {
+        let stamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("clock should be after epoch")
+            .as_nanos();
+        let path = std::env::temp_dir().join(format!("moon-projection-stitch-test-{stamp}.jsonl"));
+        let lines = [
+            json!({"message": {"role": "assistant", "content": [{"type": "toolCall", "id": "call-a", "name": "read_file", "arguments": {"path": "src/a.rs"}}]}}),
+            json!({"message": {"role": "assistant", "content": [{"type": "toolCall", "id": "call-b", "name": "exec", "arguments": {"command": "cargo test"}}]}}),
+            json!({"message": {"role": "toolResult", "toolCallId": "call-a", "content": [{"type": "text", "text": "contents of a.rs"}]}}),
+            json!({"message": {"role": "toolResult", "toolCallId": "call-b", "content": [{"type": "text", "text": "all tests passed"}]}}),
+            json!({"message": {"role": "assistant", "content": [{"type": "toolUse", "name": "read_file", "input": {"path": "src/b.rs"}}]}}),
+            json!({"message": {"role": "assistant", "content": [{"type": "toolUse", "name": "exec", "input": {"command": "cargo build"}}]}}),
+            json!({"message": {"role": "toolResult", "toolName": "read_file", "content": [{"type": "text", "text": "contents of b.rs"}]}}),
+            json!({"message": {"role": "toolResult", "toolName": "exec", "content": [{"type": "text", "text": "build finished"}]}}),
+        ];
+        let body = lines
+            .iter()
+            .map(|line| line.to_string())
+            .collect::<Vec<_>>()
+            .join("\n");
+        fs::write(&path, body).expect("write test file");
+
+        let data = super::extract_projection_data(&path.to_string_lossy())
+            .expect("extract projection data");
+        let _ = fs::remove_file(&path);
+
+        let coupled = |target: &str| {
+            data.entries
+                .iter()
+                .find(|entry| entry.tool_target.as_deref() == Some(target))
+                .and_then(|entry| entry.coupled_result.clone())
+        };
+        assert_eq!(coupled("src/a.rs").as_deref(), Some("contents of a.rs"));
+        assert_eq!(coupled("cargo test").as_deref(), Some("all tests passed"));
+        assert_eq!(coupled("src/b.rs").as_deref(), Some("contents of b.rs"));
+        assert_eq!(coupled("cargo build").as_deref(), Some("build finished"));
+    }
+
     #[test]
     fn extract_projection_data_accepts_numeric_and_top_level_timestamps() {
         let stamp = SystemTime::now()
@@ -4098,6 +6740,8 @@ mod tests {
             tool_target: None,
             priority: None,
             coupled_result: None,
+            source_line: None,
+            source_byte_offset: None,
         };
         let keywords = super::extract_keywords(&[entry]);
         assert!(
@@ -4107,6 +6751,54 @@ mod tests {
         );
     }
 
+    #[test]
+    fn extract_keywords_ranks_distinctive_terms_and_identifiers() {
+        let entry = |role: &str, text: &str| super::ProjectionEntry {
+            timestamp_epoch: None,
+            role: role.to_string(),
+            content: text.to_string(),
+            tool_name: None,
+            tool_target: None,
+            priority: None,
+            coupled_result: None,
+            source_line: None,
+            source_byte_offset: None,
+        };
+        let entries = vec![
+            entry(
+                "user",
+                "Could you please check why the watcher_cycle stalls again?",
+            ),
+            entry(
+                "assistant",
+                "Looking into watcher_cycle now; the lockfile seems stale.",
+            ),
+            entry(
+                "assistant",
+                "The stale lockfile came from commit 3f9a2c1d at v1.2.3.",
+            ),
+            entry("user", "Thanks, please also check retention there."),
+            entry(
+                "assistant",
+                "Something about that retention thing should be fine.",
+            ),
+        ];
+        let keywords = super::extract_keywords(&entries);
+        assert_eq!(keywords.first().map(String::as_str), Some("watcher_cycle"));
+        assert!(keywords.contains(&"lockfile".to_string()));
+        assert!(keywords.contains(&"retention".to_string()));
+        for junk in [
+            "please",
+            "something",
+            "thanks",
+            "3f9a2c1d",
+            "v1.2.3",
+            "1.2.3",
+        ] {
+            assert!(!keywords.contains(&junk.to_string()), "unexpected {junk}");
+        }
+    }
+
     #[test]
     fn semantic_dedup_keeps_latest_state_line() {
         let raw =
@@ -4198,7 +6890,7 @@ mod tests {
             &DistillInput {
                 session_id: "s1".to_string(),
                 archive_path: archive.display().to_string(),
-                archive_text: String::new(),
+                archive_text: Cow::Borrowed(""),
                 archive_epoch_secs: Some(1_700_000_000),
             },
         )
@@ -4213,6 +6905,54 @@ mod tests {
         assert!(!daily.contains("[tool-input]"));
     }
 
+    #[test]
+    fn chunked_archive_distillation_logs_progress_per_chunk() {
+        let tmp = tempdir().expect("tempdir");
+        let paths = make_test_paths(tmp.path());
+        fs::create_dir_all(&paths.memory_dir).expect("mkdir memory");
+        fs::create_dir_all(&paths.logs_dir).expect("mkdir logs");
+
+        let archive = tmp.path().join("chunked.jsonl");
+        let user = json!({
+            "message": {
+                "role": "user",
+                "timestamp": 1_700_000_000u64,
+                "content": [{"type":"text","text":"Decision: ship the parser fix today."}]
+            }
+        });
+        fs::write(&archive, format!("{user}\n")).expect("write archive");
+
+        let out = run_chunked_archive_distillation(
+            &paths,
+            &DistillInput {
+                session_id: "chunked".to_string(),
+                archive_path: archive.display().to_string(),
+                archive_text: Cow::Borrowed(""),
+                archive_epoch_secs: Some(1_700_000_000),
+            },
+        )
+        .expect("chunked distill");
+
+        let events = crate::moon::audit::read_events(&paths).expect("audit events");
+        let progress = events
+            .iter()
+            .find(|event| event.phase == "distill-chunk")
+            .expect("chunk progress event");
+        assert_eq!(progress.status, "ok");
+        assert!(
+            progress
+                .message
+                .starts_with("session=chunked chunk=1/1 provider=")
+        );
+        assert!(
+            progress
+                .message
+                .contains(&format!("provider={}", out.provider))
+        );
+        assert!(progress.message.contains("duration_ms="));
+        assert!(progress.message.contains("bullets="));
+    }
+
     #[test]
     fn run_distillation_accepts_projection_markdown_source() {
         let tmp = tempdir().expect("tempdir");
@@ -4227,6 +6967,7 @@ mod tests {
 moon_archive_projection: 2
 message_count: 7
 filtered_noise_count: 2
+time_range_utc: "2023-11-14T22:13:20Z — 2023-11-14T22:23:20Z"
 ---
 
 ## Conversations
@@ -4251,7 +6992,7 @@ filtered_noise_count: 2
             &DistillInput {
                 session_id: "md1".to_string(),
                 archive_path: projection.display().to_string(),
-                archive_text: String::new(),
+                archive_text: Cow::Borrowed(""),
                 archive_epoch_secs: Some(1_700_000_100),
             },
         )
@@ -4262,6 +7003,11 @@ filtered_noise_count: 2
         assert!(daily.contains("Please keep answers concise."));
         assert!(daily.contains("I will update the command flow and rerun tests."));
         assert!(daily.contains("### Execution Summary"));
+        assert!(daily.contains("- Time Range (UTC): 2023-11-14T22:13:20Z — 2023-11-14T22:23:20Z"));
+        assert!(
+            out.summary
+                .contains("- time_range_utc: 2023-11-14T22:13:20Z")
+        );
     }
 
     #[test]
@@ -4278,10 +7024,59 @@ filtered_noise_count: 2
         }
     }
 
+    #[test]
+    fn daily_memory_path_uses_residential_timezone_date() {
+        let tmp = tempdir().expect("tempdir");
+        let paths = make_test_paths(tmp.path());
+        // 2023-11-14T22:13:20Z is already the next day in Tokyo.
+        let epoch = 1_700_000_000u64;
+        let utc_path = super::daily_memory_path(&paths, Some(epoch), chrono_tz::UTC);
+        let tokyo_path = super::daily_memory_path(&paths, Some(epoch), chrono_tz::Asia::Tokyo);
+        assert!(utc_path.ends_with("2023-11-14.md"));
+        assert!(tokyo_path.ends_with("2023-11-15.md"));
+    }
+
+    #[test]
+    fn daily_memory_session_lookup_reads_residential_and_utc_day_files_only() {
+        let _env_lock = TEST_ENV_LOCK.lock().expect("lock test env");
+        let _tz = ScopedEnvVar::set("MOON_RESIDENTIAL_TIMEZONE", "Asia/Tokyo");
+
+        let tmp = tempdir().expect("tempdir");
+        let paths = make_test_paths(tmp.path());
+        fs::create_dir_all(&paths.memory_dir).expect("mkdir memory");
+        // 2023-11-14T22:13:20Z is 2023-11-15 in Tokyo.
+        let epoch = 1_700_000_000u64;
+        let block = |id: &str| {
+            let (begin, end) = super::session_block_markers(id);
+            format!("{begin}\n- note\n{end}\n")
+        };
+        let tokyo = paths.memory_dir.join("2023-11-15.md");
+        let utc = paths.memory_dir.join("2023-11-14.md");
+        fs::write(&tokyo, block("s-tokyo")).expect("write tokyo day");
+        fs::write(&utc, block("s-utc")).expect("write utc day");
+        fs::write(paths.memory_dir.join("2023-11-13.md"), block("s-elsewhere"))
+            .expect("write other day");
+
+        assert_eq!(
+            super::daily_memory_file_for_session(&paths, "s-tokyo", Some(epoch)),
+            Some(tokyo)
+        );
+        assert_eq!(
+            super::daily_memory_file_for_session(&paths, "s-utc", Some(epoch)),
+            Some(utc)
+        );
+        assert!(!super::daily_memory_has_session(
+            &paths,
+            "s-elsewhere",
+            Some(epoch)
+        ));
+    }
+
     #[test]
     fn run_wisdom_distillation_updates_memory_file_and_audit_log() {
         let _env_lock = TEST_ENV_LOCK.lock().expect("lock test env");
         let _provider = ScopedEnvVar::set("MOON_WISDOM_PROVIDER", "local");
+        let _tz = ScopedEnvVar::set("MOON_RESIDENTIAL_TIMEZONE", "UTC");
 
         let tmp = tempdir().expect("tempdir");
         let paths = make_test_paths(tmp.path());
@@ -4290,7 +7085,7 @@ filtered_noise_count: 2
         fs::write(&paths.memory_file, "# MEMORY\n").expect("write memory");
 
         let epoch = 1_700_000_000u64;
-        let daily_path = super::daily_memory_path(&paths, Some(epoch));
+        let daily_path = super::daily_memory_path(&paths, Some(epoch), chrono_tz::UTC);
         fs::write(
             &daily_path,
             r#"# Daily Memory 2023-11-14
@@ -4317,6 +7112,8 @@ filtered_noise_count: 2
                 day_epoch_secs: Some(epoch),
                 source_paths: Vec::new(),
                 dry_run: false,
+                concurrency: 1,
+                rollup_threshold_chunks: 0,
             },
         )
         .expect("wisdom distill should succeed");
@@ -4373,6 +7170,8 @@ filtered_noise_count: 2
                 day_epoch_secs: Some(1_700_000_000),
                 source_paths: vec![source.display().to_string()],
                 dry_run: false,
+                concurrency: 1,
+                rollup_threshold_chunks: 0,
             },
         )
         .expect("wisdom distill should succeed");
@@ -4384,4 +7183,259 @@ filtered_noise_count: 2
         assert!(memory.contains("## User Preferences"));
         assert!(memory.contains("## Durable Decisions & Context"));
     }
+
+    #[test]
+    fn detect_memory_conflict_candidates_flags_changed_values() {
+        let existing = "# MEMORY\n\n## Durable Decisions & Context\n- The default branch for moon repo is main.\n- User prefers concise answers.\n";
+        let summary = "## Durable Decisions & Context\n- The default branch for moon repo is develop.\n- User prefers concise answers.\n";
+        let conflicts = super::detect_memory_conflict_candidates(existing, summary, None);
+        assert_eq!(conflicts.len(), 1);
+        assert!(conflicts[0].incoming.contains("develop"));
+        assert!(conflicts[0].existing.contains("main"));
+        assert!(!conflicts[0].model_confirmed);
+
+        let rendered = super::render_memory_conflicts(&conflicts);
+        assert!(rendered.starts_with("## Memory Conflicts"));
+        assert!(crate::moon::memory::memory_bullets(&rendered).is_empty());
+    }
+
+    #[test]
+    fn detect_memory_conflict_candidates_pairs_paraphrases_by_embedding() {
+        let existing = "## Durable Decisions & Context\n- Deploys go out every Friday afternoon.\n- User prefers concise answers.\n";
+        let summary = "## Durable Decisions & Context\n- Releases now ship Monday mornings.\n";
+        let mut embed = |texts: &[String]| {
+            Ok(texts
+                .iter()
+                .map(|text| {
+                    if text.contains("Friday") || text.contains("Monday") {
+                        vec![1.0, 0.1]
+                    } else {
+                        vec![0.0, 1.0]
+                    }
+                })
+                .collect())
+        };
+        let conflicts =
+            super::detect_memory_conflict_candidates(existing, summary, Some(&mut embed));
+        assert_eq!(conflicts.len(), 1);
+        assert!(conflicts[0].existing.contains("Friday"));
+        assert!(conflicts[0].incoming.contains("Monday"));
+
+        // Without shared terms the lexical pass has nothing to pair, and a failed embedding
+        // call falls back to it.
+        assert!(super::detect_memory_conflict_candidates(existing, summary, None).is_empty());
+        let mut failing = |_: &[String]| Err(anyhow::anyhow!("embedding provider unavailable"));
+        assert!(
+            super::detect_memory_conflict_candidates(existing, summary, Some(&mut failing))
+                .is_empty()
+        );
+        let branch_existing = "- The default branch for moon repo is main.\n";
+        let branch_summary = "- The default branch for moon repo is develop.\n";
+        assert_eq!(
+            super::detect_memory_conflict_candidates(
+                branch_existing,
+                branch_summary,
+                Some(&mut failing)
+            )
+            .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn normalize_wisdom_summary_drops_only_the_memory_conflicts_section() {
+        let raw = concat!(
+            "## Lessons Learned: conflict resolution\n",
+            "- Resolve merge conflicts by rebasing onto main\n",
+            "## Memory Conflicts\n",
+            "- New: branch is develop | Earlier: branch is main\n",
+        );
+        let normalized = super::normalize_wisdom_summary(raw, raw, "");
+        assert!(normalized.contains("Resolve merge conflicts by rebasing onto main"));
+        assert!(!normalized.contains("branch is develop"));
+    }
+
+    #[test]
+    fn run_wisdom_distillation_flags_memory_conflicts_in_summary() {
+        let _env_lock = TEST_ENV_LOCK.lock().expect("lock test env");
+        let _provider = ScopedEnvVar::set("MOON_WISDOM_PROVIDER", "local");
+
+        let tmp = tempdir().expect("tempdir");
+        let paths = make_test_paths(tmp.path());
+        fs::create_dir_all(&paths.memory_dir).expect("mkdir memory");
+        fs::create_dir_all(&paths.logs_dir).expect("mkdir logs");
+        fs::write(
+            &paths.memory_file,
+            "# MEMORY\n\n## Durable Decisions & Context\n- Decision: use billing-api service for invoices.\n",
+        )
+        .expect("write memory");
+
+        let source = tmp.path().join("renamed.md");
+        fs::write(
+            &source,
+            "## Session r\n### Conversation\n**User:** Decision: use payments-api service for invoices.\n",
+        )
+        .expect("write source");
+
+        let out = run_wisdom_distillation(
+            &paths,
+            &WisdomDistillInput {
+                trigger: "conflict".to_string(),
+                day_epoch_secs: Some(1_700_000_000),
+                source_paths: vec![source.display().to_string()],
+                dry_run: false,
+                concurrency: 1,
+                rollup_threshold_chunks: 0,
+            },
+        )
+        .expect("wisdom distill should succeed");
+
+        assert_eq!(out.memory_conflicts.len(), 1);
+        let memory = fs::read_to_string(&paths.memory_file).expect("read memory");
+        assert!(memory.contains("## Memory Conflicts"));
+        assert!(memory.contains("payments-api"));
+    }
+
+    fn collector_with_limits(
+        limits: crate::moon::config::MoonProjectionConfig,
+    ) -> super::ProjectionCollector {
+        super::ProjectionCollector {
+            limits,
+            sample_stride: 1,
+            ..Default::default()
+        }
+    }
+
+    fn user_event(idx: u64) -> serde_json::Value {
+        json!({
+            "timestamp": 1_700_000_000 + idx,
+            "message": {"role": "user", "content": [{"type": "text", "text": format!("planning turn number {idx}")}]}
+        })
+    }
+
+    #[test]
+    fn projection_collector_stops_at_configured_entry_limit() {
+        let mut collector = collector_with_limits(crate::moon::config::MoonProjectionConfig {
+            max_entries: 3,
+            ..Default::default()
+        });
+        for idx in 0..3 {
+            assert!(!collector.is_full());
+            collector.push_event(&user_event(idx));
+        }
+        assert!(collector.is_full());
+    }
+
+    #[test]
+    fn projection_collects_openclaw_compaction_entries_as_anchors() {
+        let mut collector = collector_with_limits(Default::default());
+        collector.push_event(&json!({"compaction_summary": "legacy note", "message_id": "m1"}));
+        collector.push_event(&json!({"type": "compaction", "id": "c2", "summary": "new note"}));
+        collector.push_event(&json!({"type": "message", "summary": "not a compaction"}));
+        let anchors = collector
+            .finish()
+            .compaction_anchors
+            .into_iter()
+            .map(|a| (a.note, a.origin_message_id))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            anchors,
+            vec![
+                ("legacy note".to_string(), Some("m1".to_string())),
+                ("new note".to_string(), Some("c2".to_string())),
+            ]
+        );
+    }
+
+    #[test]
+    fn projection_full_scan_thins_entries_across_whole_archive() {
+        let mut collector = collector_with_limits(crate::moon::config::MoonProjectionConfig {
+            max_entries: 4,
+            max_scan_lines: 1,
+            full_scan: true,
+            ..Default::default()
+        });
+        for idx in 0..10 {
+            collector.scanned_lines += 1;
+            assert!(!collector.is_full());
+            collector.push_event(&user_event(idx));
+        }
+        let data = collector.finish();
+        assert_eq!(data.message_count, 10);
+        assert_eq!(data.sample_stride, 4);
+        assert_eq!(data.time_start_epoch, Some(1_700_000_000));
+        assert_eq!(data.time_end_epoch, Some(1_700_000_009));
+        let kept = data
+            .entries
+            .iter()
+            .map(|e| e.content.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kept,
+            vec![
+                "planning turn number 0",
+                "planning turn number 4",
+                "planning turn number 8"
+            ]
+        );
+    }
+
+    #[test]
+    fn parallel_projection_parse_matches_sequential_scan() {
+        let mut archive = String::new();
+        for idx in 0..40u64 {
+            archive.push_str(&format!("{}\n", user_event(idx)));
+            archive.push_str(&format!(
+                "{}\n",
+                json!({
+                    "timestamp": 1_700_000_000 + idx,
+                    "message": {"role": "assistant", "content": [
+                        {"type": "text", "text": format!("checking file {idx}")},
+                        {"type": "toolCall", "id": format!("call-{idx}"), "name": "read", "arguments": {"path": format!("src/{idx}.rs")}}
+                    ]}
+                })
+            ));
+            archive.push_str(&format!(
+                "{}\n",
+                json!({
+                    "timestamp": 1_700_000_000 + idx,
+                    "message": {"role": "toolResult", "toolCallId": format!("call-{idx}"), "content": [{"type": "text", "text": format!("contents of {idx}")}]}
+                })
+            ));
+            if idx % 7 == 0 {
+                archive.push_str("\n  \nplain text note\n");
+            }
+        }
+        archive.push_str("trailing line without newline");
+
+        for limits in [
+            crate::moon::config::MoonProjectionConfig::default(),
+            crate::moon::config::MoonProjectionConfig {
+                max_entries: 25,
+                ..Default::default()
+            },
+            crate::moon::config::MoonProjectionConfig {
+                max_entries: 8,
+                full_scan: true,
+                ..Default::default()
+            },
+        ] {
+            let mut sequential = collector_with_limits(limits.clone());
+            super::collect_projection_lines(archive.as_bytes(), &mut sequential, "mem")
+                .expect("sequential");
+            let mut parallel = collector_with_limits(limits);
+            super::collect_projection_lines_parallel(
+                archive.as_bytes(),
+                &mut parallel,
+                3,
+                512,
+                "mem",
+            )
+            .expect("parallel");
+            assert_eq!(
+                serde_json::to_value(sequential.finish()).expect("sequential json"),
+                serde_json::to_value(parallel.finish()).expect("parallel json")
+            );
+        }
+    }
 }
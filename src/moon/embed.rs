@@ -1,11 +1,14 @@
+use crate::moon::archive::{projection_path_for_archive, read_ledger_records};
 use crate::moon::config::MoonEmbedConfig;
 use crate::moon::paths::MoonPaths;
 use crate::moon::qmd;
 use crate::moon::state::MoonState;
 use crate::moon::util::now_epoch_secs;
+use crate::moon::vectors::{self, EmbedProvider, RemoteEmbedder, VectorMeta, VectorRecord};
 use anyhow::{Context, Result};
 use fs2::FileExt;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
 use std::fs::OpenOptions;
 use std::io::{ErrorKind, Write};
@@ -39,7 +42,7 @@ pub struct EmbedRunOptions {
     pub max_cycle_secs: Option<u64>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct EmbedRunSummary {
     pub collection: String,
     pub mode: String,
@@ -52,6 +55,13 @@ pub struct EmbedRunSummary {
     pub elapsed_ms: u128,
     pub degraded: bool,
     pub skip_reason: String,
+    /// `qmd` or the remote embedding provider from `[embed] provider`.
+    pub provider: String,
+    pub model: String,
+    /// The vector store was built with another provider/model, so every projection is pending.
+    pub model_changed: bool,
+    pub requests: usize,
+    pub vectors: usize,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -291,12 +301,38 @@ pub fn run(
     state: &mut MoonState,
     cfg: &MoonEmbedConfig,
     opts: &EmbedRunOptions,
+) -> std::result::Result<EmbedRunSummary, EmbedRunError> {
+    let (provider, model) = vectors::configured_model(cfg);
+    let model_changed = provider != EmbedProvider::Qmd
+        && vectors::model_changed(
+            vectors::read_vector_meta(paths).ok().flatten().as_ref(),
+            provider,
+            &model,
+        );
+    let mut summary = run_inner(paths, state, cfg, opts, provider, model_changed)?;
+    summary.provider = provider.label().to_string();
+    summary.model = model;
+    summary.model_changed = model_changed;
+    Ok(summary)
+}
+
+fn run_inner(
+    paths: &MoonPaths,
+    state: &mut MoonState,
+    cfg: &MoonEmbedConfig,
+    opts: &EmbedRunOptions,
+    provider: EmbedProvider,
+    model_changed: bool,
 ) -> std::result::Result<EmbedRunSummary, EmbedRunError> {
     let started = Instant::now();
     let now_epoch = now_epoch_secs().map_err(|err| EmbedRunError::Failed(format!("{err:#}")))?;
 
     let docs = projection_docs(paths).map_err(|err| EmbedRunError::Failed(format!("{err:#}")))?;
-    let pending = pending_docs(state, &docs);
+    let pending = if model_changed {
+        docs.iter().collect()
+    } else {
+        pending_docs(state, &docs)
+    };
     let pending_before = pending.len();
 
     if opts.caller == EmbedCaller::Watcher {
@@ -317,6 +353,7 @@ pub fn run(
                 elapsed_ms: started.elapsed().as_millis(),
                 degraded: false,
                 skip_reason: SkipReason::Cooldown.as_str().to_string(),
+                ..Default::default()
             });
         }
 
@@ -333,6 +370,7 @@ pub fn run(
                 elapsed_ms: started.elapsed().as_millis(),
                 degraded: false,
                 skip_reason: SkipReason::None.as_str().to_string(),
+                ..Default::default()
             });
         }
     }
@@ -355,6 +393,7 @@ pub fn run(
             elapsed_ms: started.elapsed().as_millis(),
             degraded: false,
             skip_reason: SkipReason::None.as_str().to_string(),
+            ..Default::default()
         });
     }
 
@@ -371,6 +410,7 @@ pub fn run(
             elapsed_ms: started.elapsed().as_millis(),
             degraded: false,
             skip_reason: SkipReason::None.as_str().to_string(),
+            ..Default::default()
         });
     }
 
@@ -378,48 +418,79 @@ pub fn run(
         state.last_embed_trigger_epoch_secs = Some(now_epoch);
     }
 
-    let probe = qmd::probe_embed_capability(&paths.qmd_bin);
     let mut skip_reason = SkipReason::None;
-
-    match probe.capability {
-        qmd::EmbedCapability::Bounded => {}
-        qmd::EmbedCapability::UnboundedOnly => {
-            if opts.caller == EmbedCaller::Watcher {
-                return Ok(EmbedRunSummary {
-                    collection: opts.collection_name.clone(),
-                    mode: opts.caller.as_str().to_string(),
-                    capability: probe.capability.as_str().to_string(),
-                    requested_max_docs: opts.max_docs,
-                    selected_docs,
-                    embedded_docs: 0,
-                    pending_before,
-                    pending_after: pending_before,
-                    elapsed_ms: started.elapsed().as_millis(),
-                    degraded: true,
-                    skip_reason: SkipReason::CapabilityMissing.as_str().to_string(),
-                });
+    let mut remote = None;
+    let capability = if provider == EmbedProvider::Qmd {
+        let probe = qmd::probe_embed_capability(&paths.qmd_bin);
+        match probe.capability {
+            qmd::EmbedCapability::Bounded => {}
+            qmd::EmbedCapability::UnboundedOnly => {
+                if opts.caller == EmbedCaller::Watcher {
+                    return Ok(EmbedRunSummary {
+                        collection: opts.collection_name.clone(),
+                        mode: opts.caller.as_str().to_string(),
+                        capability: probe.capability.as_str().to_string(),
+                        requested_max_docs: opts.max_docs,
+                        selected_docs,
+                        embedded_docs: 0,
+                        pending_before,
+                        pending_after: pending_before,
+                        elapsed_ms: started.elapsed().as_millis(),
+                        degraded: true,
+                        skip_reason: SkipReason::CapabilityMissing.as_str().to_string(),
+                        ..Default::default()
+                    });
+                }
+                return Err(EmbedRunError::CapabilityMissing(probe.note));
+            }
+            qmd::EmbedCapability::Missing => {
+                if opts.caller == EmbedCaller::Watcher {
+                    return Ok(EmbedRunSummary {
+                        collection: opts.collection_name.clone(),
+                        mode: opts.caller.as_str().to_string(),
+                        capability: probe.capability.as_str().to_string(),
+                        requested_max_docs: opts.max_docs,
+                        selected_docs,
+                        embedded_docs: 0,
+                        pending_before,
+                        pending_after: pending_before,
+                        elapsed_ms: started.elapsed().as_millis(),
+                        degraded: true,
+                        skip_reason: SkipReason::CapabilityMissing.as_str().to_string(),
+                        ..Default::default()
+                    });
+                }
+                return Err(EmbedRunError::CapabilityMissing(probe.note));
             }
-            return Err(EmbedRunError::CapabilityMissing(probe.note));
         }
-        qmd::EmbedCapability::Missing => {
-            if opts.caller == EmbedCaller::Watcher {
-                return Ok(EmbedRunSummary {
-                    collection: opts.collection_name.clone(),
-                    mode: opts.caller.as_str().to_string(),
-                    capability: probe.capability.as_str().to_string(),
-                    requested_max_docs: opts.max_docs,
-                    selected_docs,
-                    embedded_docs: 0,
-                    pending_before,
-                    pending_after: pending_before,
-                    elapsed_ms: started.elapsed().as_millis(),
-                    degraded: true,
-                    skip_reason: SkipReason::CapabilityMissing.as_str().to_string(),
-                });
+        probe.capability.as_str().to_string()
+    } else {
+        match RemoteEmbedder::from_config(cfg) {
+            Ok(embedder) => {
+                remote = embedder;
+                "remote".to_string()
+            }
+            Err(err) => {
+                if opts.caller == EmbedCaller::Watcher {
+                    return Ok(EmbedRunSummary {
+                        collection: opts.collection_name.clone(),
+                        mode: opts.caller.as_str().to_string(),
+                        capability: "missing".to_string(),
+                        requested_max_docs: opts.max_docs,
+                        selected_docs,
+                        embedded_docs: 0,
+                        pending_before,
+                        pending_after: pending_before,
+                        elapsed_ms: started.elapsed().as_millis(),
+                        degraded: true,
+                        skip_reason: SkipReason::CapabilityMissing.as_str().to_string(),
+                        ..Default::default()
+                    });
+                }
+                return Err(EmbedRunError::CapabilityMissing(format!("{err:#}")));
             }
-            return Err(EmbedRunError::CapabilityMissing(probe.note));
         }
-    }
+    };
 
     let _lock = match acquire_lock(paths, opts.caller, &opts.collection_name, now_epoch) {
         Ok(Some(lock)) => lock,
@@ -429,7 +500,7 @@ pub fn run(
                 return Ok(EmbedRunSummary {
                     collection: opts.collection_name.clone(),
                     mode: opts.caller.as_str().to_string(),
-                    capability: probe.capability.as_str().to_string(),
+                    capability: capability.clone(),
                     requested_max_docs: opts.max_docs,
                     selected_docs,
                     embedded_docs: 0,
@@ -438,6 +509,7 @@ pub fn run(
                     elapsed_ms: started.elapsed().as_millis(),
                     degraded: true,
                     skip_reason: skip_reason.as_str().to_string(),
+                    ..Default::default()
                 });
             }
             return Err(EmbedRunError::Locked(
@@ -451,20 +523,44 @@ pub fn run(
         }
     };
 
-    let (embedded_docs, exec) = run_bounded_embed_with_backoff(paths, opts, selected_docs)?;
+    let mut requests = 0;
+    let mut vector_count = 0;
+    let embedded_docs = match remote.as_mut() {
+        Some(embedder) => {
+            let outcome = embed_remote(
+                paths,
+                state,
+                embedder,
+                RemoteBatch {
+                    docs: &docs,
+                    selected: &selected,
+                    now_epoch,
+                    model_changed,
+                },
+            );
+            requests = embedder.requests();
+            let (embedded_docs, vectors) = outcome?;
+            vector_count = vectors;
+            embedded_docs
+        }
+        None => {
+            let (embedded_docs, exec) = run_bounded_embed_with_backoff(paths, opts, selected_docs)?;
 
-    if qmd::output_indicates_embed_status_failed(&exec.stdout, &exec.stderr) {
-        return Err(EmbedRunError::StatusFailed(
-            "qmd output indicates failed status".to_string(),
-        ));
-    }
+            if qmd::output_indicates_embed_status_failed(&exec.stdout, &exec.stderr) {
+                return Err(EmbedRunError::StatusFailed(
+                    "qmd output indicates failed status".to_string(),
+                ));
+            }
 
-    for doc in selected.iter().take(embedded_docs) {
-        state.embedded_projections.insert(
-            doc.path.display().to_string(),
-            now_epoch.max(doc.mtime_epoch_secs),
-        );
-    }
+            for doc in selected.iter().take(embedded_docs) {
+                state.embedded_projections.insert(
+                    doc.path.display().to_string(),
+                    now_epoch.max(doc.mtime_epoch_secs),
+                );
+            }
+            embedded_docs
+        }
+    };
 
     let existing_projection_paths = docs
         .iter()
@@ -479,7 +575,7 @@ pub fn run(
     Ok(EmbedRunSummary {
         collection: opts.collection_name.clone(),
         mode: opts.caller.as_str().to_string(),
-        capability: probe.capability.as_str().to_string(),
+        capability,
         requested_max_docs: opts.max_docs,
         selected_docs,
         embedded_docs,
@@ -488,9 +584,366 @@ pub fn run(
         elapsed_ms: started.elapsed().as_millis(),
         degraded: false,
         skip_reason: skip_reason.as_str().to_string(),
+        requests,
+        vectors: vector_count,
+        ..Default::default()
     })
 }
 
+/// Embeds freshly archived projections straight away, so semantic recall sees them without
+/// waiting for the cooldown-gated embed stage. Only runs once a remote vector index exists
+/// and still matches the configured model; returns `Ok(None)` otherwise.
+pub fn embed_new_projections(
+    paths: &MoonPaths,
+    state: &mut MoonState,
+    cfg: &MoonEmbedConfig,
+    projection_paths: &[PathBuf],
+) -> std::result::Result<Option<EmbedRunSummary>, EmbedRunError> {
+    let (provider, model) = vectors::configured_model(cfg);
+    if provider == EmbedProvider::Qmd || !vectors::vector_index_exists(paths) {
+        return Ok(None);
+    }
+    let meta = vectors::read_vector_meta(paths)
+        .map_err(|err| EmbedRunError::Failed(format!("{err:#}")))?;
+    if vectors::model_changed(meta.as_ref(), provider, &model) {
+        return Ok(None);
+    }
+
+    let started = Instant::now();
+    let now_epoch = now_epoch_secs().map_err(|err| EmbedRunError::Failed(format!("{err:#}")))?;
+    let docs = projection_docs(paths).map_err(|err| EmbedRunError::Failed(format!("{err:#}")))?;
+    let selected = pending_docs(state, &docs)
+        .into_iter()
+        .filter(|doc| projection_paths.contains(&doc.path))
+        .collect::<Vec<_>>();
+    if selected.is_empty() {
+        return Ok(None);
+    }
+
+    let mut summary = EmbedRunSummary {
+        collection: String::new(),
+        mode: EmbedCaller::Watcher.as_str().to_string(),
+        capability: "remote".to_string(),
+        requested_max_docs: projection_paths.len(),
+        selected_docs: selected.len(),
+        pending_before: selected.len(),
+        pending_after: selected.len(),
+        skip_reason: SkipReason::None.as_str().to_string(),
+        provider: provider.label().to_string(),
+        model,
+        ..Default::default()
+    };
+    let mut embedder = match RemoteEmbedder::from_config(cfg) {
+        Ok(Some(embedder)) => embedder,
+        Ok(None) => return Ok(None),
+        Err(err) => return Err(EmbedRunError::CapabilityMissing(format!("{err:#}"))),
+    };
+    let _lock = match acquire_lock(paths, EmbedCaller::Watcher, "vectors", now_epoch) {
+        Ok(Some(lock)) => lock,
+        Ok(None) => {
+            summary.degraded = true;
+            summary.skip_reason = SkipReason::Locked.as_str().to_string();
+            summary.elapsed_ms = started.elapsed().as_millis();
+            return Ok(Some(summary));
+        }
+        Err(err) => {
+            return Err(EmbedRunError::Failed(format!(
+                "acquire-lock-failed error={err:#}"
+            )));
+        }
+    };
+
+    let outcome = embed_remote(
+        paths,
+        state,
+        &mut embedder,
+        RemoteBatch {
+            docs: &docs,
+            selected: &selected,
+            now_epoch,
+            model_changed: false,
+        },
+    );
+    summary.requests = embedder.requests();
+    let (embedded_docs, vector_count) = outcome?;
+    summary.embedded_docs = embedded_docs;
+    summary.vectors = vector_count;
+    summary.pending_after = selected.len().saturating_sub(embedded_docs);
+    summary.elapsed_ms = started.elapsed().as_millis();
+    Ok(Some(summary))
+}
+
+/// Vector coverage of the ledger's projections, from the vector store (remote providers)
+/// or from the embed markers in state (qmd, which keeps its vectors to itself).
+#[derive(Debug, Clone, Default)]
+pub struct EmbedVerifyReport {
+    pub provider: String,
+    pub model: String,
+    pub source: String,
+    pub ledger_archives: usize,
+    pub projections: usize,
+    pub embedded: usize,
+    pub missing: Vec<String>,
+    pub stale: Vec<String>,
+    pub orphaned: Vec<String>,
+    pub vectors: usize,
+    pub model_mismatch: usize,
+    pub dimension_mismatch: usize,
+}
+
+impl EmbedVerifyReport {
+    pub fn coverage_pct(&self) -> f64 {
+        if self.projections == 0 {
+            100.0
+        } else {
+            self.embedded as f64 * 100.0 / self.projections as f64
+        }
+    }
+}
+
+pub fn verify(
+    paths: &MoonPaths,
+    state: &MoonState,
+    cfg: &MoonEmbedConfig,
+) -> Result<EmbedVerifyReport> {
+    let (provider, model) = vectors::configured_model(cfg);
+    let ledger = read_ledger_records(paths)?;
+    let mut out = EmbedVerifyReport {
+        provider: provider.label().to_string(),
+        model: model.clone(),
+        ledger_archives: ledger.len(),
+        ..Default::default()
+    };
+    let projections = ledger
+        .iter()
+        .map(|record| {
+            record
+                .projection_path
+                .as_deref()
+                .map(PathBuf::from)
+                .unwrap_or_else(|| projection_path_for_archive(&record.archive_path))
+        })
+        .filter(|path| path.exists())
+        .collect::<std::collections::BTreeSet<_>>();
+    out.projections = projections.len();
+
+    if provider == EmbedProvider::Qmd {
+        out.source = "state".to_string();
+        for path in &projections {
+            let key = path.display().to_string();
+            match state.embedded_projections.get(&key) {
+                Some(at) if *at >= path_epoch_secs(path) => out.embedded += 1,
+                Some(_) => out.stale.push(key),
+                None => out.missing.push(key),
+            }
+        }
+        return Ok(out);
+    }
+
+    out.source = "vectors".to_string();
+    let meta = vectors::read_vector_meta(paths)?;
+    let records = vectors::read_vector_records(paths)?;
+    out.vectors = records.len();
+    let dimensions = meta
+        .as_ref()
+        .filter(|meta| meta.model == model)
+        .map(|meta| meta.dimensions)
+        .unwrap_or(0);
+    out.model_mismatch = records.iter().filter(|r| r.model != model).count();
+    out.dimension_mismatch = records
+        .iter()
+        .filter(|r| {
+            r.model == model && (r.dimensions != dimensions || r.vector.len() != r.dimensions)
+        })
+        .count();
+
+    let mut by_projection = BTreeMap::<&str, Vec<&VectorRecord>>::new();
+    for record in &records {
+        by_projection
+            .entry(record.projection_path.as_str())
+            .or_default()
+            .push(record);
+    }
+    for path in &projections {
+        let key = path.display().to_string();
+        let Some(rows) = by_projection.get(key.as_str()) else {
+            out.missing.push(key);
+            continue;
+        };
+        let sections = fs::read_to_string(path)
+            .map(|md| vectors::projection_sections(&md))
+            .unwrap_or_default();
+        let current = rows.len() == sections.len()
+            && rows.iter().all(|r| r.model == model)
+            && sections.iter().all(|(_, text)| {
+                let hash = vectors::content_hash(text);
+                rows.iter().any(|r| r.content_hash == hash)
+            });
+        if current {
+            out.embedded += 1;
+        } else {
+            out.stale.push(key);
+        }
+    }
+    let expected = projections
+        .iter()
+        .map(|p| p.display().to_string())
+        .collect::<std::collections::BTreeSet<_>>();
+    out.orphaned = by_projection
+        .keys()
+        .filter(|path| !expected.contains(**path))
+        .map(|path| path.to_string())
+        .collect();
+    Ok(out)
+}
+
+/// Drops the remote vector store and re-embeds every projection with the configured model.
+pub fn rebuild(
+    paths: &MoonPaths,
+    state: &mut MoonState,
+    cfg: &MoonEmbedConfig,
+    opts: &EmbedRunOptions,
+) -> std::result::Result<EmbedRunSummary, EmbedRunError> {
+    let (provider, _) = vectors::configured_model(cfg);
+    if provider == EmbedProvider::Qmd {
+        return Err(EmbedRunError::CapabilityMissing(
+            "--rebuild needs a remote [embed] provider; qmd manages its own vector index"
+                .to_string(),
+        ));
+    }
+    let docs = projection_docs(paths).map_err(|err| EmbedRunError::Failed(format!("{err:#}")))?;
+    if !opts.dry_run {
+        vectors::clear_vector_store(paths)
+            .map_err(|err| EmbedRunError::Failed(format!("{err:#}")))?;
+        state.embedded_projections.clear();
+    }
+    let rebuild_opts = EmbedRunOptions {
+        max_docs: docs.len().max(1),
+        max_cycle_secs: None,
+        ..opts.clone()
+    };
+    let mut summary = run(paths, state, cfg, &rebuild_opts)?;
+    if opts.dry_run {
+        // Nothing was cleared, so report the rebuild as if every projection were pending.
+        summary.selected_docs = docs.len();
+        summary.pending_before = docs.len();
+        summary.pending_after = docs.len();
+    }
+    Ok(summary)
+}
+
+struct RemoteBatch<'a> {
+    docs: &'a [ProjectionDoc],
+    selected: &'a [&'a ProjectionDoc],
+    now_epoch: u64,
+    model_changed: bool,
+}
+
+/// Embeds the selected projections section by section into the vector store, reusing
+/// vectors for unchanged sections. Progress is persisted before a failed batch is reported.
+fn embed_remote(
+    paths: &MoonPaths,
+    state: &mut MoonState,
+    embedder: &mut RemoteEmbedder,
+    batch: RemoteBatch<'_>,
+) -> std::result::Result<(usize, usize), EmbedRunError> {
+    let failed = |err: anyhow::Error| EmbedRunError::Failed(format!("{err:#}"));
+    let model = embedder.model().to_string();
+    let meta = vectors::read_vector_meta(paths).map_err(failed)?;
+    let mut records = vectors::read_vector_records(paths).map_err(failed)?;
+    if batch.model_changed {
+        state.embedded_projections.clear();
+    }
+    let mut dimensions = meta
+        .filter(|meta| meta.model == model)
+        .map(|meta| meta.dimensions)
+        .unwrap_or(0);
+
+    let mut embedded_docs = 0usize;
+    let mut failure = None;
+    for doc in batch.selected {
+        let key = doc.path.display().to_string();
+        let Ok(markdown) = fs::read_to_string(&doc.path) else {
+            continue;
+        };
+        let sections = vectors::projection_sections(&markdown);
+        let mut doc_records = Vec::with_capacity(sections.len());
+        let mut missing = Vec::new();
+        for (section, text) in sections {
+            let content_hash = vectors::content_hash(&text);
+            let reused = records.iter().find(|r| {
+                r.projection_path == key && r.content_hash == content_hash && r.model == model
+            });
+            doc_records.push(VectorRecord {
+                projection_path: key.clone(),
+                section,
+                content_hash,
+                model: model.clone(),
+                dimensions: reused.map(|r| r.dimensions).unwrap_or(0),
+                vector: reused.map(|r| r.vector.clone()).unwrap_or_default(),
+            });
+            if reused.is_none() {
+                missing.push((doc_records.len() - 1, text));
+            }
+        }
+
+        let texts = missing.iter().map(|(_, t)| t.clone()).collect::<Vec<_>>();
+        let embedded = match embedder.embed_texts(&texts) {
+            Ok(embedded) => embedded,
+            Err(err) => {
+                failure = Some(err);
+                break;
+            }
+        };
+        for ((idx, _), vector) in missing.iter().zip(embedded) {
+            // A provider-side dimension change invalidates every older vector.
+            dimensions = vector.len();
+            doc_records[*idx].dimensions = vector.len();
+            doc_records[*idx].vector = vector;
+        }
+
+        records.retain(|r| r.projection_path != key);
+        records.extend(doc_records);
+        state
+            .embedded_projections
+            .insert(key, batch.now_epoch.max(doc.mtime_epoch_secs));
+        embedded_docs += 1;
+    }
+
+    // Mixed-model or mixed-dimension rows cannot be compared; drop them and re-queue
+    // their projections so the next cycle re-embeds them with the current model.
+    let existing = batch
+        .docs
+        .iter()
+        .map(|doc| doc.path.display().to_string())
+        .collect::<std::collections::BTreeSet<_>>();
+    let stale = records
+        .iter()
+        .filter(|r| r.model != model || (dimensions > 0 && r.dimensions != dimensions))
+        .map(|r| r.projection_path.clone())
+        .collect::<std::collections::BTreeSet<_>>();
+    records
+        .retain(|r| !stale.contains(&r.projection_path) && existing.contains(&r.projection_path));
+    for path in &stale {
+        state.embedded_projections.remove(path);
+    }
+
+    let meta = VectorMeta {
+        provider: embedder.provider().label().to_string(),
+        model,
+        dimensions,
+        updated_at_epoch_secs: batch.now_epoch,
+    };
+    vectors::write_vector_store(paths, &meta, &records).map_err(failed)?;
+
+    match failure {
+        Some(err) => Err(EmbedRunError::Failed(format!(
+            "remote-embed-failed embedded_docs={embedded_docs} error={err:#}"
+        ))),
+        None => Ok((embedded_docs, records.len())),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::{ProjectionDoc, pending_docs};
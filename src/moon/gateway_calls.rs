@@ -0,0 +1,181 @@
+use crate::moon::lease;
+use crate::moon::paths::MoonPaths;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+
+pub const OUTCOME_PENDING: &str = "pending";
+pub const OUTCOME_SENT: &str = "sent";
+pub const OUTCOME_FAILED: &str = "failed";
+pub const OUTCOME_SUPPRESSED: &str = "suppressed";
+
+/// Records are kept at least this long, even with a shorter idempotency window, so a day of
+/// sends stays available for diagnosis.
+pub const MIN_RETENTION_SECS: u64 = 86_400;
+
+/// One gateway `chat.send` attempt, appended before the call (`pending`) and after it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GatewayCallRecord {
+    pub idempotency_key: String,
+    pub kind: String,
+    pub session_key: String,
+    pub outcome: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub detail: String,
+    pub at_epoch_secs: u64,
+}
+
+pub fn calls_path(paths: &MoonPaths) -> PathBuf {
+    paths
+        .moon_home
+        .join("continuity")
+        .join("gateway_calls.jsonl")
+}
+
+/// Stable key for sending `kind` to `session_key` after archiving `content_hash`.
+///
+/// The same archive always yields the same key, so a daemon that restarts mid-cycle finds its
+/// earlier send here, and the gateway can dedupe a resend of a call left `pending`.
+pub fn idempotency_key(kind: &str, session_key: &str, content_hash: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(session_key.as_bytes());
+    hasher.update([0]);
+    hasher.update(content_hash.as_bytes());
+    let digest = format!("{:x}", hasher.finalize());
+    format!("moon-{kind}-{}", &digest[..16])
+}
+
+pub fn append(paths: &MoonPaths, record: &GatewayCallRecord) -> Result<()> {
+    let path = calls_path(paths);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+    let _lease = lease::acquire(&path, "gateway call record")?;
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("failed to open {}", path.display()))?;
+    writeln!(file, "{}", serde_json::to_string(record)?)
+        .with_context(|| format!("failed to write {}", path.display()))?;
+    Ok(())
+}
+
+/// Records oldest first; unparseable lines are skipped.
+pub fn read_all(paths: &MoonPaths) -> Result<Vec<GatewayCallRecord>> {
+    let path = calls_path(paths);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let raw =
+        fs::read_to_string(&path).with_context(|| format!("failed to read {}", path.display()))?;
+    Ok(raw
+        .lines()
+        .filter_map(|line| serde_json::from_str::<GatewayCallRecord>(line).ok())
+        .collect())
+}
+
+/// The newest successful send for `idempotency_key` at or after `since_epoch_secs`, if any.
+pub fn find_sent(
+    paths: &MoonPaths,
+    idempotency_key: &str,
+    since_epoch_secs: u64,
+) -> Result<Option<GatewayCallRecord>> {
+    Ok(read_all(paths)?.into_iter().rev().find(|record| {
+        record.idempotency_key == idempotency_key
+            && record.outcome == OUTCOME_SENT
+            && record.at_epoch_secs >= since_epoch_secs
+    }))
+}
+
+/// Drops records older than `cutoff_epoch_secs` so the ledger, and every `find_sent` read,
+/// stays bounded by the idempotency window. Returns how many records were dropped.
+pub fn trim_before(paths: &MoonPaths, cutoff_epoch_secs: u64) -> Result<usize> {
+    let path = calls_path(paths);
+    if !path.exists() {
+        return Ok(0);
+    }
+    // Held across the read and the rewrite so an append in between is not dropped.
+    let _lease = lease::acquire(&path, "gateway calls trim")?;
+    let records = read_all(paths)?;
+    let kept = records
+        .iter()
+        .filter(|record| record.at_epoch_secs >= cutoff_epoch_secs)
+        .collect::<Vec<_>>();
+    let dropped = records.len() - kept.len();
+    if dropped == 0 {
+        return Ok(0);
+    }
+    let mut body = String::new();
+    for record in kept {
+        body.push_str(&serde_json::to_string(record)?);
+        body.push('\n');
+    }
+    let tmp = path.with_extension("jsonl.tmp");
+    fs::write(&tmp, body).with_context(|| format!("failed to write {}", tmp.display()))?;
+    fs::rename(&tmp, &path).with_context(|| format!("failed to write {}", path.display()))?;
+    Ok(dropped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn find_sent_ignores_pending_and_failed_attempts() {
+        let tmp = tempdir().expect("tempdir");
+        let paths = MoonPaths::for_test(tmp.path());
+        let key = idempotency_key("compact", "chan:a", "hash-1");
+        assert_eq!(key, idempotency_key("compact", "chan:a", "hash-1"));
+        assert_ne!(key, idempotency_key("compact", "chan:a", "hash-2"));
+
+        let record = |outcome: &str| GatewayCallRecord {
+            idempotency_key: key.clone(),
+            kind: "compact".to_string(),
+            session_key: "chan:a".to_string(),
+            outcome: outcome.to_string(),
+            detail: String::new(),
+            at_epoch_secs: 1,
+        };
+        append(&paths, &record(OUTCOME_PENDING)).expect("append pending");
+        append(&paths, &record(OUTCOME_FAILED)).expect("append failed");
+        assert!(find_sent(&paths, &key, 0).expect("find").is_none());
+
+        append(&paths, &record(OUTCOME_SENT)).expect("append sent");
+        assert_eq!(
+            find_sent(&paths, &key, 1).expect("find"),
+            Some(record(OUTCOME_SENT))
+        );
+        assert!(find_sent(&paths, &key, 2).expect("find").is_none());
+        assert_eq!(read_all(&paths).expect("read").len(), 3);
+    }
+
+    #[test]
+    fn trim_before_keeps_only_records_inside_the_window() {
+        let tmp = tempdir().expect("tempdir");
+        let paths = MoonPaths::for_test(tmp.path());
+        let record = |at_epoch_secs: u64| GatewayCallRecord {
+            idempotency_key: "moon-compact-1".to_string(),
+            kind: "compact".to_string(),
+            session_key: "chan:a".to_string(),
+            outcome: OUTCOME_SENT.to_string(),
+            detail: String::new(),
+            at_epoch_secs,
+        };
+        for at in [10, 20, 30] {
+            append(&paths, &record(at)).expect("append");
+        }
+
+        assert_eq!(trim_before(&paths, 20).expect("trim"), 1);
+        assert_eq!(
+            read_all(&paths).expect("read"),
+            vec![record(20), record(30)]
+        );
+        assert_eq!(trim_before(&paths, 20).expect("trim"), 0);
+    }
+}
@@ -0,0 +1,397 @@
+use crate::moon::lease;
+use crate::moon::paths::MoonPaths;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+const GRAPH_DIR: &str = "graph";
+const GRAPH_EDGES_FILE: &str = "edges.jsonl";
+const MAX_EDGES_PER_SESSION: usize = 256;
+const MAX_ENTITIES_PER_TEXT: usize = 12;
+const FILE_EXTENSIONS: [&str; 16] = [
+    "rs", "ts", "tsx", "js", "py", "go", "md", "toml", "json", "jsonl", "yaml", "yml", "sh", "sql",
+    "css", "html",
+];
+const SERVICE_SUFFIXES: [&str; 6] = ["api", "service", "svc", "worker", "gateway", "db"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EntityKind {
+    Session,
+    Person,
+    Repo,
+    File,
+    Service,
+}
+
+impl EntityKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            EntityKind::Session => "session",
+            EntityKind::Person => "person",
+            EntityKind::Repo => "repo",
+            EntityKind::File => "file",
+            EntityKind::Service => "service",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GraphEdge {
+    pub subject: String,
+    pub subject_kind: EntityKind,
+    pub relation: String,
+    pub object: String,
+    pub object_kind: EntityKind,
+    pub session_id: String,
+    pub source_path: String,
+    pub at_epoch_secs: u64,
+}
+
+pub fn graph_edges_path(paths: &MoonPaths) -> PathBuf {
+    paths.moon_home.join(GRAPH_DIR).join(GRAPH_EDGES_FILE)
+}
+
+fn trim_token(raw: &str) -> &str {
+    raw.trim_matches(|c: char| {
+        matches!(
+            c,
+            '`' | '"'
+                | '\''
+                | '('
+                | ')'
+                | '['
+                | ']'
+                | '{'
+                | '}'
+                | '<'
+                | '>'
+                | ','
+                | ';'
+                | ':'
+                | '!'
+                | '?'
+        )
+    })
+    .trim_end_matches('.')
+}
+
+fn repo_from_token(token: &str) -> Option<String> {
+    for host in ["github.com/", "gitlab.com/", "bitbucket.org/"] {
+        let Some(idx) = token.find(host) else {
+            continue;
+        };
+        let mut parts = token[idx + host.len()..].split('/');
+        let owner = parts.next().filter(|v| !v.is_empty())?;
+        let repo = parts
+            .next()
+            .map(|v| v.trim_end_matches(".git"))
+            .filter(|v| !v.is_empty())?;
+        return Some(format!("{owner}/{repo}"));
+    }
+    None
+}
+
+fn is_file_token(token: &str) -> bool {
+    if token.contains("://") || token.len() < 4 || token.len() > 200 {
+        return false;
+    }
+    let Some(ext) = Path::new(token).extension().and_then(|v| v.to_str()) else {
+        return false;
+    };
+    let stem_ok = Path::new(token)
+        .file_stem()
+        .and_then(|v| v.to_str())
+        .is_some_and(|stem| stem.chars().any(|c| c.is_ascii_alphabetic()));
+    stem_ok && FILE_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str())
+}
+
+fn is_service_token(token: &str) -> bool {
+    let Some((name, suffix)) = token.rsplit_once('-') else {
+        return false;
+    };
+    !name.is_empty()
+        && name.starts_with(|c: char| c.is_ascii_lowercase())
+        && token
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+        && SERVICE_SUFFIXES.contains(&suffix)
+}
+
+fn person_from_token(token: &str) -> Option<String> {
+    let handle = token.strip_prefix('@')?;
+    let valid = handle.len() >= 2
+        && handle
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    valid.then(|| format!("@{}", handle.to_ascii_lowercase()))
+}
+
+/// Entities mentioned in free text, in first-seen order.
+pub fn extract_entities(text: &str) -> Vec<(EntityKind, String)> {
+    let mut seen = BTreeSet::new();
+    let mut out = Vec::new();
+    for raw in text.split_whitespace() {
+        if out.len() >= MAX_ENTITIES_PER_TEXT {
+            break;
+        }
+        let token = trim_token(raw);
+        let entity = if let Some(repo) = repo_from_token(token) {
+            Some((EntityKind::Repo, repo))
+        } else if let Some(person) = person_from_token(token) {
+            Some((EntityKind::Person, person))
+        } else if is_file_token(token) {
+            Some((EntityKind::File, token.to_string()))
+        } else if is_service_token(token) {
+            Some((EntityKind::Service, token.to_string()))
+        } else {
+            None
+        };
+        if let Some(entity) = entity
+            && seen.insert(entity.clone())
+        {
+            out.push(entity);
+        }
+    }
+    out
+}
+
+fn relation_between(left: EntityKind, right: EntityKind) -> &'static str {
+    match (left, right) {
+        (EntityKind::File, EntityKind::Repo) => "in_repo",
+        (EntityKind::Person, EntityKind::Repo) | (EntityKind::Person, EntityKind::Service) => {
+            "works_on"
+        }
+        (EntityKind::Person, EntityKind::File) => "edited",
+        (EntityKind::Service, EntityKind::Repo) => "hosted_in",
+        _ => "related_to",
+    }
+}
+
+/// Builds session mention edges plus co-occurrence relations between entities in the same text.
+pub fn extract_edges(
+    session_id: &str,
+    source_path: &str,
+    texts: &[String],
+    at_epoch_secs: u64,
+) -> Vec<GraphEdge> {
+    let mut seen = BTreeSet::new();
+    let mut out = Vec::new();
+    let mut push = |subject: (EntityKind, &str), relation: &str, object: (EntityKind, &str)| {
+        if out.len() >= MAX_EDGES_PER_SESSION {
+            return;
+        }
+        let key = (
+            subject.1.to_string(),
+            relation.to_string(),
+            object.1.to_string(),
+        );
+        if !seen.insert(key) {
+            return;
+        }
+        out.push(GraphEdge {
+            subject: subject.1.to_string(),
+            subject_kind: subject.0,
+            relation: relation.to_string(),
+            object: object.1.to_string(),
+            object_kind: object.0,
+            session_id: session_id.to_string(),
+            source_path: source_path.to_string(),
+            at_epoch_secs,
+        });
+    };
+
+    for text in texts {
+        let entities = extract_entities(text);
+        for (kind, name) in &entities {
+            push((EntityKind::Session, session_id), "mentions", (*kind, name));
+        }
+        for (idx, left) in entities.iter().enumerate() {
+            for right in entities.iter().skip(idx + 1) {
+                // Orient the pair so typed relations read subject -> object.
+                let (subject, object) = if relation_between(left.0, right.0) == "related_to"
+                    && relation_between(right.0, left.0) != "related_to"
+                {
+                    (right, left)
+                } else {
+                    (left, right)
+                };
+                push(
+                    (subject.0, &subject.1),
+                    relation_between(subject.0, object.0),
+                    (object.0, &object.1),
+                );
+            }
+        }
+    }
+    out
+}
+
+pub fn read_edges(paths: &MoonPaths) -> Result<Vec<GraphEdge>> {
+    let path = graph_edges_path(paths);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let raw =
+        fs::read_to_string(&path).with_context(|| format!("failed to read {}", path.display()))?;
+    Ok(raw
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| serde_json::from_str::<GraphEdge>(line).ok())
+        .collect())
+}
+
+/// Replaces any previous edges for `session_id` with `edges`.
+pub fn replace_session_edges(
+    paths: &MoonPaths,
+    session_id: &str,
+    edges: &[GraphEdge],
+) -> Result<PathBuf> {
+    let path = graph_edges_path(paths);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+    let _lease = lease::acquire(&path, "graph edges update")?;
+    let mut kept = read_edges(paths)?
+        .into_iter()
+        .filter(|edge| edge.session_id != session_id)
+        .collect::<Vec<_>>();
+    kept.extend(edges.iter().cloned());
+
+    let tmp = path.with_extension("jsonl.tmp");
+    let mut file =
+        fs::File::create(&tmp).with_context(|| format!("failed to create {}", tmp.display()))?;
+    for edge in &kept {
+        writeln!(file, "{}", serde_json::to_string(edge)?)
+            .with_context(|| format!("failed to write {}", tmp.display()))?;
+    }
+    fs::rename(&tmp, &path).with_context(|| format!("failed to replace {}", path.display()))?;
+    Ok(path)
+}
+
+fn entity_matches(name: &str, needle: &str) -> bool {
+    name.to_ascii_lowercase().contains(needle)
+}
+
+/// Edges touching an entity whose name contains `entity` (case-insensitive).
+pub fn query(paths: &MoonPaths, entity: &str, limit: usize) -> Result<Vec<GraphEdge>> {
+    let needle = entity.trim().to_ascii_lowercase();
+    if needle.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut out = read_edges(paths)?
+        .into_iter()
+        .filter(|edge| {
+            entity_matches(&edge.subject, &needle) || entity_matches(&edge.object, &needle)
+        })
+        .collect::<Vec<_>>();
+    out.sort_by_key(|edge| std::cmp::Reverse(edge.at_epoch_secs));
+    out.truncate(limit);
+    Ok(out)
+}
+
+/// File stems of archives whose sessions mention an entity named in `query`.
+pub fn related_archive_stems(paths: &MoonPaths, query: &str) -> Result<BTreeSet<String>> {
+    let terms = query
+        .split_whitespace()
+        .map(|t| trim_token(t).to_ascii_lowercase())
+        .filter(|t| t.len() >= 3)
+        .collect::<Vec<_>>();
+    let mut out = BTreeSet::new();
+    if terms.is_empty() {
+        return Ok(out);
+    }
+    for edge in read_edges(paths)? {
+        if edge.subject_kind != EntityKind::Session {
+            continue;
+        }
+        let object = edge.object.to_ascii_lowercase();
+        if terms.iter().any(|term| object.contains(term.as_str()))
+            && let Some(stem) = Path::new(&edge.source_path)
+                .file_stem()
+                .and_then(|v| v.to_str())
+        {
+            out.insert(stem.to_string());
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EntityKind, extract_edges, extract_entities, read_edges, replace_session_edges};
+    use crate::moon::paths::MoonPaths;
+    use std::thread;
+
+    #[test]
+    fn extract_entities_finds_repos_files_services_and_people() {
+        let entities = extract_entities(
+            "@alice moved `src/moon/graph.rs` into https://github.com/coinbuidl/MOON and billing-api now.",
+        );
+        assert_eq!(
+            entities,
+            vec![
+                (EntityKind::Person, "@alice".to_string()),
+                (EntityKind::File, "src/moon/graph.rs".to_string()),
+                (EntityKind::Repo, "coinbuidl/MOON".to_string()),
+                (EntityKind::Service, "billing-api".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn extract_edges_links_session_mentions_and_cooccurrence() {
+        let edges = extract_edges(
+            "s1",
+            "/tmp/archives/mlib/s1.md",
+            &["@bob edited src/lib.rs in github.com/acme/widgets".to_string()],
+            10,
+        );
+        assert!(
+            edges
+                .iter()
+                .any(|e| e.subject == "s1" && e.relation == "mentions" && e.object == "@bob")
+        );
+        assert!(edges.iter().any(|e| e.subject == "src/lib.rs"
+            && e.relation == "in_repo"
+            && e.object == "acme/widgets"));
+        assert!(
+            edges
+                .iter()
+                .any(|e| e.subject == "@bob" && e.relation == "edited" && e.object == "src/lib.rs")
+        );
+    }
+
+    #[test]
+    fn concurrent_session_edge_replacements_keep_every_session() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let paths = MoonPaths::for_test(dir.path());
+        let sessions = (0..8).map(|i| format!("s{i}")).collect::<Vec<_>>();
+        thread::scope(|scope| {
+            for session in &sessions {
+                let paths = &paths;
+                scope.spawn(move || {
+                    let edges = extract_edges(
+                        session,
+                        &format!("/tmp/archives/mlib/{session}.md"),
+                        &["@carol edited src/lib.rs".to_string()],
+                        10,
+                    );
+                    replace_session_edges(paths, session, &edges).expect("replace edges");
+                });
+            }
+        });
+
+        let edges = read_edges(&paths).expect("read edges");
+        for session in &sessions {
+            assert!(
+                edges.iter().any(|e| &e.session_id == session),
+                "edges for {session} were lost"
+            );
+        }
+    }
+}
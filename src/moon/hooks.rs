@@ -0,0 +1,211 @@
+use crate::moon::audit;
+use crate::moon::config::{MoonHooksConfig, load_config};
+use crate::moon::paths::MoonPaths;
+use crate::moon::util::{
+    read_only_mode, run_command_with_optional_timeout, truncate_with_ellipsis,
+};
+use crate::moon::warn::{self, WarnEvent};
+use std::process::{Command, Stdio};
+use std::time::Instant;
+
+const OUTPUT_TAIL_CHARS: usize = 300;
+
+/// Pipeline events that can run a `[hooks]` command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookEvent {
+    PostArchive,
+    PostDistill,
+    PostCompaction,
+    RetentionDelete,
+}
+
+impl HookEvent {
+    pub const ALL: [HookEvent; 4] = [
+        HookEvent::PostArchive,
+        HookEvent::PostDistill,
+        HookEvent::PostCompaction,
+        HookEvent::RetentionDelete,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            HookEvent::PostArchive => "post_archive",
+            HookEvent::PostDistill => "post_distill",
+            HookEvent::PostCompaction => "post_compaction",
+            HookEvent::RetentionDelete => "retention_delete",
+        }
+    }
+
+    pub fn command(self, cfg: &MoonHooksConfig) -> &str {
+        match self {
+            HookEvent::PostArchive => cfg.post_archive.trim(),
+            HookEvent::PostDistill => cfg.post_distill.trim(),
+            HookEvent::PostCompaction => cfg.post_compaction.trim(),
+            HookEvent::RetentionDelete => cfg.retention_delete.trim(),
+        }
+    }
+}
+
+/// Runs the configured hook for `event` using the current config; see [`run`].
+pub fn fire(paths: &MoonPaths, event: HookEvent, context: &[(&str, &str)]) -> Option<String> {
+    let cfg = load_config().ok()?;
+    run(paths, &cfg.hooks, event, context)
+}
+
+/// Runs the hook command for `event` through the shell with `context` exported as
+/// `MOON_HOOK_<KEY>` variables, plus `MOON_HOOK_EVENT` and `MOON_HOME`.
+///
+/// Hook failures and timeouts are warned and audited, never returned: a broken integration
+/// must not stall the pipeline. Returns `None` when no hook is configured.
+pub fn run(
+    paths: &MoonPaths,
+    cfg: &MoonHooksConfig,
+    event: HookEvent,
+    context: &[(&str, &str)],
+) -> Option<String> {
+    let command = event.command(cfg);
+    if command.is_empty() || read_only_mode() {
+        return None;
+    }
+
+    let mut cmd = if cfg!(windows) {
+        let mut cmd = Command::new("cmd");
+        cmd.args(["/C", command]);
+        cmd
+    } else {
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", command]);
+        cmd
+    };
+    cmd.stdin(Stdio::null())
+        .env("MOON_HOOK_EVENT", event.as_str())
+        .env("MOON_HOME", &paths.moon_home);
+    if paths.moon_home.is_dir() {
+        cmd.current_dir(&paths.moon_home);
+    }
+    for (key, value) in context {
+        cmd.env(format!("MOON_HOOK_{}", key.to_ascii_uppercase()), value);
+    }
+
+    let started = Instant::now();
+    let result = run_command_with_optional_timeout(&mut cmd, Some(cfg.timeout_secs));
+    let elapsed_ms = started.elapsed().as_millis();
+    let (status, outcome) = match result {
+        Ok(out) if out.status.success() => (
+            "ok",
+            format!("event={} status=ok elapsed_ms={elapsed_ms}", event.as_str()),
+        ),
+        Ok(out) => {
+            let stderr = String::from_utf8_lossy(&out.stderr);
+            let code = out
+                .status
+                .code()
+                .map_or("signal".to_string(), |code| code.to_string());
+            (
+                "degraded",
+                format!(
+                    "event={} status=failed exit={code} elapsed_ms={elapsed_ms} stderr={}",
+                    event.as_str(),
+                    truncate_with_ellipsis(stderr.trim(), OUTPUT_TAIL_CHARS)
+                ),
+            )
+        }
+        Err(err) => (
+            "degraded",
+            format!(
+                "event={} status=failed elapsed_ms={elapsed_ms} error={err:#}",
+                event.as_str()
+            ),
+        ),
+    };
+
+    if status != "ok" {
+        warn::emit(WarnEvent {
+            code: "HOOK_FAILED",
+            stage: "hooks",
+            action: event.as_str(),
+            session: context
+                .iter()
+                .find(|(key, _)| *key == "session_id")
+                .map_or("na", |(_, value)| value),
+            archive: context
+                .iter()
+                .find(|(key, _)| *key == "archive_path")
+                .map_or("na", |(_, value)| value),
+            source: command,
+            retry: "none",
+            reason: "hook-command-failed",
+            err: &outcome,
+        });
+    }
+    let mut details = serde_json::Map::new();
+    details.insert("event".to_string(), event.as_str().into());
+    for (key, value) in context {
+        let key = match *key {
+            "session_id" => "session",
+            "archive_path" => "archive",
+            other => other,
+        };
+        if !value.is_empty() {
+            details.insert(key.to_string(), (*value).into());
+        }
+    }
+    let _ = audit::append_event(paths, "hook", status, &outcome, details.into());
+    Some(outcome)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::{HookEvent, run};
+    use crate::moon::config::MoonHooksConfig;
+    use crate::moon::paths::MoonPaths;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn hook_receives_event_context_and_is_audited() {
+        let tmp = tempdir().expect("tempdir");
+        let paths = MoonPaths::for_test(tmp.path());
+        let cfg = MoonHooksConfig {
+            post_archive:
+                "printf '%s %s' \"$MOON_HOOK_EVENT\" \"$MOON_HOOK_ARCHIVE_PATH\" > hook.out"
+                    .to_string(),
+            ..MoonHooksConfig::default()
+        };
+
+        assert!(run(&paths, &cfg, HookEvent::PostDistill, &[]).is_none());
+        let outcome = run(
+            &paths,
+            &cfg,
+            HookEvent::PostArchive,
+            &[("archive_path", "/a/raw.jsonl")],
+        )
+        .expect("hook ran");
+        assert!(outcome.starts_with("event=post_archive status=ok"));
+        assert_eq!(
+            fs::read_to_string(tmp.path().join("hook.out")).expect("hook output"),
+            "post_archive /a/raw.jsonl"
+        );
+        let audit = fs::read_to_string(paths.logs_dir.join("audit.log")).expect("audit");
+        assert!(audit.contains("\"phase\":\"hook\",\"status\":\"ok\""));
+    }
+
+    #[test]
+    fn failing_and_slow_hooks_report_without_erroring() {
+        let tmp = tempdir().expect("tempdir");
+        let paths = MoonPaths::for_test(tmp.path());
+        let cfg = MoonHooksConfig {
+            post_compaction: "echo boom >&2; exit 3".to_string(),
+            retention_delete: "sleep 5".to_string(),
+            timeout_secs: 1,
+            ..MoonHooksConfig::default()
+        };
+
+        let failed = run(&paths, &cfg, HookEvent::PostCompaction, &[]).expect("hook ran");
+        assert!(failed.contains("status=failed exit=3"));
+        assert!(failed.contains("stderr=boom"));
+
+        let timed_out = run(&paths, &cfg, HookEvent::RetentionDelete, &[]).expect("hook ran");
+        assert!(timed_out.contains("timed out after 1s"));
+    }
+}
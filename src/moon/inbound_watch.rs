@@ -1,10 +1,13 @@
-use crate::moon::config::MoonConfig;
+use crate::moon::config::{MoonConfig, MoonInboundWatchConfig};
 use crate::moon::paths::MoonPaths;
-use crate::moon::state::MoonState;
+use crate::moon::state::{MoonState, state_file_path};
+use crate::moon::util::truncate_with_ellipsis;
 use crate::openclaw::gateway;
 use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
 use std::collections::BTreeSet;
 use std::fs;
+use std::io::Read;
 use std::path::{Path, PathBuf};
 use std::time::UNIX_EPOCH;
 
@@ -25,6 +28,15 @@ pub struct InboundWatchOutcome {
     pub events: Vec<InboundWatchEvent>,
 }
 
+/// Files above this size are compared by content but summarized by size only.
+const DIFF_MAX_BYTES: usize = 256 * 1024;
+/// Changed lines quoted per side in a file-change event.
+const DIFF_PREVIEW_LINES: usize = 5;
+const DIFF_PREVIEW_CHARS: usize = 160;
+/// Bytes read from the head of an inbound file for the mime sniff and preview.
+const PREVIEW_READ_BYTES: usize = 4096;
+const PREVIEW_CHARS: usize = 200;
+
 fn modified_epoch_secs(path: &Path) -> Result<u64> {
     let meta = fs::metadata(path).with_context(|| format!("failed to stat {}", path.display()))?;
     let modified = meta.modified().unwrap_or(UNIX_EPOCH);
@@ -51,22 +63,275 @@ fn collect_files(root: &Path, recursive: bool, out: &mut Vec<PathBuf>) -> Result
     Ok(())
 }
 
-fn trigger_event(file_path: &Path, mode: &str) -> Result<()> {
+/// A watch path naming a single file rather than a directory to scan.
+///
+/// Missing paths with an extension (e.g. `TODO.md`) are treated as files that may appear
+/// later, so they are not created as directories.
+fn is_file_watch_path(path: &Path) -> bool {
+    if path.exists() {
+        return path.is_file();
+    }
+    path.extension().is_some()
+}
+
+/// Last seen content of watched single files, kept next to the state file.
+fn snapshot_path(paths: &MoonPaths, file_key: &str) -> PathBuf {
+    let digest = format!("{:x}", Sha256::digest(file_key.as_bytes()));
+    let state_file = state_file_path(paths);
+    state_file
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| paths.moon_home.join("moon").join("state"))
+        .join("inbound_snapshots")
+        .join(&digest[..16])
+}
+
+fn write_snapshot(path: &Path, content: &[u8]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+    fs::write(path, content).with_context(|| format!("failed to write {}", path.display()))
+}
+
+/// Line-level change summary between two versions of a watched file.
+///
+/// Common leading and trailing lines are trimmed and the remainder is reported as one
+/// changed region, which matches the usual single-edit case without a full diff.
+pub fn diff_summary(previous: &[u8], current: &[u8]) -> String {
+    if previous.len() > DIFF_MAX_BYTES || current.len() > DIFF_MAX_BYTES {
+        return format!("bytes {} -> {}", previous.len(), current.len());
+    }
+    let (Ok(previous), Ok(current)) = (std::str::from_utf8(previous), std::str::from_utf8(current))
+    else {
+        return format!("binary bytes {} -> {}", previous.len(), current.len());
+    };
+    let old: Vec<&str> = previous.lines().collect();
+    let new: Vec<&str> = current.lines().collect();
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let removed = &old[prefix..old.len() - suffix];
+    let added = &new[prefix..new.len() - suffix];
+
+    let mut out = format!("lines +{} -{}", added.len(), removed.len());
+    for (sign, lines) in [('-', removed), ('+', added)] {
+        for line in lines.iter().take(DIFF_PREVIEW_LINES) {
+            out.push_str(&format!(
+                "\n{sign} {}",
+                truncate_with_ellipsis(line, DIFF_PREVIEW_CHARS)
+            ));
+        }
+        if lines.len() > DIFF_PREVIEW_LINES {
+            out.push_str(&format!(
+                "\n{sign} … {} more",
+                lines.len() - DIFF_PREVIEW_LINES
+            ));
+        }
+    }
+    out
+}
+
+/// What the receiving agent needs to triage an inbound file without opening it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileFacts {
+    pub size_bytes: u64,
+    pub mime: &'static str,
+    /// First printable characters, whitespace collapsed; empty for binary files.
+    pub preview: String,
+}
+
+impl FileFacts {
+    fn read(path: &Path) -> Result<Self> {
+        let size_bytes = fs::metadata(path)
+            .with_context(|| format!("failed to stat {}", path.display()))?
+            .len();
+        let mut head = Vec::with_capacity(PREVIEW_READ_BYTES);
+        fs::File::open(path)
+            .and_then(|file| file.take(PREVIEW_READ_BYTES as u64).read_to_end(&mut head))
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        Ok(Self::from_head(path, size_bytes, &head))
+    }
+
+    fn from_head(path: &Path, size_bytes: u64, head: &[u8]) -> Self {
+        let head = &head[..head.len().min(PREVIEW_READ_BYTES)];
+        // A multi-byte character cut at the read boundary still counts as text.
+        let text = match std::str::from_utf8(head) {
+            Ok(text) => Some(text),
+            Err(err) if err.error_len().is_none() => {
+                std::str::from_utf8(&head[..err.valid_up_to()]).ok()
+            }
+            Err(_) => None,
+        };
+        let text = text.filter(|text| !text.contains('\0'));
+        let preview = text
+            .map(|text| {
+                let collapsed = text.split_whitespace().collect::<Vec<_>>().join(" ");
+                truncate_with_ellipsis(&collapsed, PREVIEW_CHARS)
+            })
+            .unwrap_or_default();
+        Self {
+            size_bytes,
+            mime: guess_mime(path, text.is_some()),
+            preview,
+        }
+    }
+}
+
+/// Mime type from the file extension, falling back to a text/binary sniff.
+pub fn guess_mime(path: &Path, looks_like_text: bool) -> &'static str {
+    let ext = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_ascii_lowercase)
+        .unwrap_or_default();
+    match ext.as_str() {
+        "md" | "markdown" => "text/markdown",
+        "txt" | "log" => "text/plain",
+        "csv" => "text/csv",
+        "html" | "htm" => "text/html",
+        "json" | "jsonl" => "application/json",
+        "yaml" | "yml" => "application/yaml",
+        "toml" => "application/toml",
+        "xml" => "application/xml",
+        "pdf" => "application/pdf",
+        "zip" => "application/zip",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "mp3" => "audio/mpeg",
+        "wav" => "audio/wav",
+        "mp4" => "video/mp4",
+        _ if looks_like_text => "text/plain",
+        _ => "application/octet-stream",
+    }
+}
+
+fn event_text(
+    file_path: &Path,
+    format: &str,
+    facts: &FileFacts,
+    change: Option<&str>,
+) -> Result<String> {
     let filename = file_path
         .file_name()
         .and_then(|s| s.to_str())
         .unwrap_or("unknown");
-    let event_text = format!(
-        "Moon System inbound file detected: {} ({})",
+    if format == "json" {
+        let payload = serde_json::json!({
+            "source": "moon",
+            "type": "inbound_file",
+            "event": if change.is_some() { "changed" } else { "detected" },
+            "file_name": filename,
+            "path": file_path.display().to_string(),
+            "size_bytes": facts.size_bytes,
+            "mime": facts.mime,
+            "preview": facts.preview,
+            "change": change,
+        });
+        return Ok(serde_json::to_string(&payload)?);
+    }
+
+    let head = match change {
+        Some(_) => "Moon System watched file changed",
+        None => "Moon System inbound file detected",
+    };
+    let mut text = format!(
+        "{head}: {} ({}) size={} mime={}",
         filename,
-        file_path.display()
+        file_path.display(),
+        facts.size_bytes,
+        facts.mime
     );
+    if let Some(summary) = change {
+        text.push(' ');
+        text.push_str(summary);
+    } else if !facts.preview.is_empty() {
+        text.push_str(&format!("\npreview: {}", facts.preview));
+    }
+    Ok(text)
+}
 
-    gateway::run_system_event(&event_text, mode)
+fn trigger_event(
+    file_path: &Path,
+    cfg: &MoonInboundWatchConfig,
+    facts: &FileFacts,
+    change: Option<&str>,
+) -> Result<()> {
+    let text = event_text(file_path, &cfg.event_format, facts, change)?;
+    if cfg.event_format == "json" {
+        gateway::run_system_event_call(&text, &cfg.event_mode)
+    } else {
+        gateway::run_system_event(&text, &cfg.event_mode)
+    }
+}
+
+/// Sends an event when a watched single file appears or its content changes.
+fn process_watched_file(
+    paths: &MoonPaths,
+    cfg: &MoonInboundWatchConfig,
+    file: &Path,
+    state: &mut MoonState,
+    out: &mut InboundWatchOutcome,
+) -> Result<()> {
+    let key = file.display().to_string();
+    let snapshot = snapshot_path(paths, &key);
+    if !file.is_file() {
+        state.inbound_seen_files.remove(&key);
+        if snapshot.exists() {
+            fs::remove_file(&snapshot)
+                .with_context(|| format!("failed to remove {}", snapshot.display()))?;
+        }
+        return Ok(());
+    }
+
+    let current = fs::read(file).with_context(|| format!("failed to read {}", file.display()))?;
+    let previous = fs::read(&snapshot).ok();
+    if previous.as_deref() == Some(current.as_slice()) {
+        return Ok(());
+    }
+
+    out.detected_files += 1;
+    let change = previous
+        .as_deref()
+        .map(|previous| diff_summary(previous, &current));
+    let facts = FileFacts::from_head(file, current.len() as u64, &current);
+    match trigger_event(file, cfg, &facts, change.as_deref()) {
+        Ok(_) => {
+            out.triggered_events += 1;
+            out.events.push(InboundWatchEvent {
+                file_path: key.clone(),
+                status: "triggered".to_string(),
+                message: match &change {
+                    Some(summary) => format!("openclaw system event sent change={summary}"),
+                    None => "openclaw system event sent".to_string(),
+                },
+            });
+            write_snapshot(&snapshot, &current)?;
+            state
+                .inbound_seen_files
+                .insert(key, modified_epoch_secs(file)?);
+        }
+        Err(err) => {
+            out.failed_events += 1;
+            out.events.push(InboundWatchEvent {
+                file_path: key,
+                status: "failed".to_string(),
+                message: err.to_string(),
+            });
+        }
+    }
+    Ok(())
 }
 
 pub fn process(
-    _paths: &MoonPaths,
+    paths: &MoonPaths,
     cfg: &MoonConfig,
     state: &mut MoonState,
 ) -> Result<InboundWatchOutcome> {
@@ -81,8 +346,13 @@ pub fn process(
     }
 
     let mut files = Vec::new();
+    let mut watched_files = BTreeSet::new();
     for watch_path in &cfg.inbound_watch.watch_paths {
         let dir = Path::new(watch_path);
+        if is_file_watch_path(dir) {
+            watched_files.insert(dir.to_path_buf());
+            continue;
+        }
         if !dir.exists() {
             fs::create_dir_all(dir)
                 .with_context(|| format!("failed to create inbound watch dir {}", dir.display()))?;
@@ -91,8 +361,15 @@ pub fn process(
     }
 
     files.sort();
+    files.dedup();
+    files.retain(|file| !watched_files.contains(file));
     let mut currently_seen = BTreeSet::new();
 
+    for file in &watched_files {
+        currently_seen.insert(file.display().to_string());
+        process_watched_file(paths, &cfg.inbound_watch, file, state, &mut out)?;
+    }
+
     for file in files {
         let key = file.display().to_string();
         currently_seen.insert(key.clone());
@@ -106,7 +383,9 @@ pub fn process(
 
         out.detected_files += 1;
 
-        match trigger_event(&file, &cfg.inbound_watch.event_mode) {
+        let sent = FileFacts::read(&file)
+            .and_then(|facts| trigger_event(&file, &cfg.inbound_watch, &facts, None));
+        match sent {
             Ok(_) => {
                 out.triggered_events += 1;
                 out.events.push(InboundWatchEvent {
@@ -133,3 +412,35 @@ pub fn process(
 
     Ok(out)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{FileFacts, diff_summary, event_text};
+    use std::path::Path;
+
+    #[test]
+    fn diff_summary_reports_the_changed_region() {
+        let summary = diff_summary(b"a\nb\nc\n", b"a\nB\nnew\nc\n");
+        assert_eq!(summary, "lines +2 -1\n- b\n+ B\n+ new");
+        assert_eq!(diff_summary(b"a\n", b"a\nb\n"), "lines +1 -0\n+ b");
+        assert_eq!(diff_summary(&[0xff], &[0xfe, 0xff]), "binary bytes 1 -> 2");
+    }
+
+    #[test]
+    fn file_facts_guess_mime_and_preview_printable_head() {
+        let facts =
+            FileFacts::from_head(Path::new("/in/notes.md"), 42, b"# Title\n\n  body\ttext\n");
+        assert_eq!(facts.mime, "text/markdown");
+        assert_eq!(facts.preview, "# Title body text");
+
+        let binary = FileFacts::from_head(Path::new("/in/blob"), 3, &[0, 159, 146]);
+        assert_eq!(binary.mime, "application/octet-stream");
+        assert!(binary.preview.is_empty());
+
+        let json = event_text(Path::new("/in/notes.md"), "json", &facts, None).expect("json");
+        let parsed: serde_json::Value = serde_json::from_str(&json).expect("parse");
+        assert_eq!(parsed["event"], "detected");
+        assert_eq!(parsed["size_bytes"], 42);
+        assert_eq!(parsed["preview"], "# Title body text");
+    }
+}
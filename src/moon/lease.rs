@@ -0,0 +1,197 @@
+use crate::moon::util::{ensure_writable, now_epoch_secs, pid_alive};
+use anyhow::{Context, Result};
+use fs2::FileExt;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How long a writer waits for a local holder before giving up.
+const LEASE_WAIT: Duration = Duration::from_secs(5);
+const LEASE_POLL: Duration = Duration::from_millis(50);
+/// Lease records from other hosts older than this are treated as abandoned.
+pub const LEASE_TTL_SECS: u64 = 300;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct LeaseRecord {
+    pub pid: u32,
+    pub host: String,
+    pub operation: String,
+    pub acquired_at_epoch_secs: u64,
+}
+
+/// Exclusive write lease on a shared MOON_HOME file (ledger, channel map).
+///
+/// The `flock` serializes writers on one host; the lease record inside the lock file catches
+/// writers on other hosts when the sync layer (NFS, Syncthing, ...) does not propagate locks.
+/// The record is cleared and the lock released on drop.
+#[derive(Debug)]
+pub struct WriteLease {
+    file: File,
+}
+
+impl Drop for WriteLease {
+    fn drop(&mut self) {
+        let _ = self.file.set_len(0);
+    }
+}
+
+pub fn lease_path(target: &Path) -> PathBuf {
+    let mut name = target
+        .file_name()
+        .map(|name| name.to_os_string())
+        .unwrap_or_default();
+    name.push(".lock");
+    target.with_file_name(name)
+}
+
+pub fn host_name() -> String {
+    std::env::var("HOSTNAME")
+        .ok()
+        .or_else(|| fs::read_to_string("/etc/hostname").ok())
+        .or_else(|| std::env::var("COMPUTERNAME").ok())
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+pub fn read_lease(target: &Path) -> Option<LeaseRecord> {
+    let raw = fs::read_to_string(lease_path(target)).ok()?;
+    serde_json::from_str(raw.trim()).ok()
+}
+
+/// A record left by another live writer, or `None` when the lease is free to take.
+fn foreign_holder(record: Option<LeaseRecord>, host: &str, now_epoch: u64) -> Option<LeaseRecord> {
+    let record = record?;
+    if record.host == host {
+        // Same host: the flock we already hold is authoritative, so the record is stale.
+        return None;
+    }
+    if now_epoch.saturating_sub(record.acquired_at_epoch_secs) > LEASE_TTL_SECS {
+        return None;
+    }
+    Some(record)
+}
+
+fn conflict_error(target: &Path, holder: Option<&LeaseRecord>, now_epoch: u64) -> anyhow::Error {
+    let holder = match holder {
+        Some(record) => format!(
+            "`{}` (pid {} on host {}, {}s ago)",
+            record.operation,
+            record.pid,
+            record.host,
+            now_epoch.saturating_sub(record.acquired_at_epoch_secs)
+        ),
+        None => "another moon process".to_string(),
+    };
+    anyhow::anyhow!(
+        "concurrent write detected on {}: held by {holder}; another moon writer shares this MOON_HOME. \
+         Stop it (`moon stop` on that host) or set MOON_READ_ONLY=true on secondary hosts, then retry \
+         (leases from other hosts expire after {LEASE_TTL_SECS}s; remove {} if that host is gone)",
+        target.display(),
+        lease_path(target).display()
+    )
+}
+
+/// Takes the write lease for `target`, waiting briefly for a local holder to finish.
+pub fn acquire(target: &Path, operation: &str) -> Result<WriteLease> {
+    acquire_with_wait(target, operation, LEASE_WAIT)
+}
+
+fn acquire_with_wait(target: &Path, operation: &str, wait: Duration) -> Result<WriteLease> {
+    ensure_writable(operation)?;
+    let path = lease_path(target);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(&path)
+        .with_context(|| format!("failed to open write lease {}", path.display()))?;
+
+    let started = Instant::now();
+    loop {
+        match file.try_lock_exclusive() {
+            Ok(()) => break,
+            Err(err) if err.kind() == ErrorKind::WouldBlock => {
+                if started.elapsed() >= wait {
+                    let holder = read_lease(target).filter(|record| pid_alive(record.pid));
+                    return Err(conflict_error(target, holder.as_ref(), now_epoch_secs()?));
+                }
+                thread::sleep(LEASE_POLL);
+            }
+            Err(err) => {
+                return Err(err).with_context(|| format!("failed to lock {}", path.display()));
+            }
+        }
+    }
+
+    let host = host_name();
+    let now = now_epoch_secs()?;
+    if let Some(holder) = foreign_holder(read_lease(target), &host, now) {
+        return Err(conflict_error(target, Some(&holder), now));
+    }
+
+    let record = LeaseRecord {
+        pid: std::process::id(),
+        host,
+        operation: operation.to_string(),
+        acquired_at_epoch_secs: now,
+    };
+    file.set_len(0)?;
+    file.write_all(format!("{}\n", serde_json::to_string(&record)?).as_bytes())?;
+    file.flush()?;
+    Ok(WriteLease { file })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn lease_blocks_second_local_writer_and_clears_record_on_drop() {
+        let tmp = tempdir().expect("tempdir");
+        let target = tmp.path().join("ledger.jsonl");
+
+        let held = acquire_with_wait(&target, "archive", Duration::ZERO).expect("first lease");
+        let record = read_lease(&target).expect("lease record");
+        assert_eq!(record.operation, "archive");
+        assert_eq!(record.pid, std::process::id());
+
+        let err = acquire_with_wait(&target, "gc purge", Duration::ZERO)
+            .expect_err("second writer must be refused");
+        let message = format!("{err:#}");
+        assert!(message.contains("concurrent write detected"));
+        assert!(message.contains("`archive`"));
+
+        drop(held);
+        assert!(read_lease(&target).is_none());
+        acquire_with_wait(&target, "gc purge", Duration::ZERO).expect("lease after release");
+    }
+
+    #[test]
+    fn foreign_holder_ignores_own_host_and_expired_records() {
+        let record = |host: &str, at: u64| LeaseRecord {
+            pid: 1,
+            host: host.to_string(),
+            operation: "watch".to_string(),
+            acquired_at_epoch_secs: at,
+        };
+        let now = 10_000;
+        assert!(foreign_holder(Some(record("here", now)), "here", now).is_none());
+        assert!(
+            foreign_holder(Some(record("there", now - LEASE_TTL_SECS - 1)), "here", now).is_none()
+        );
+        assert_eq!(
+            foreign_holder(Some(record("there", now - 5)), "here", now),
+            Some(record("there", now - 5))
+        );
+    }
+}
@@ -0,0 +1,949 @@
+use crate::moon::config::resolve_residential_tz;
+use crate::moon::paths::MoonPaths;
+use crate::moon::util::STOPWORDS;
+use anyhow::{Context, Result};
+use chrono::{NaiveDate, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const MEMORY_CONFLICTS_HEADING: &str = "## Memory Conflicts";
+const MEMORY_HISTORY_DIR: &str = ".history";
+const MEMORY_HISTORY_PREFIX: &str = "MEMORY-";
+const BULLET_MATCH_MIN_SIMILARITY: f64 = 0.5;
+pub(crate) const PRIMER_HEADER: &str = "[MOON_MEMORY_PRIMER]";
+const PRIMER_BYTES_PER_TOKEN: u64 = 3;
+const DAY_SECS: u64 = 86_400;
+pub const MEMORY_EXPORT_SCHEMA_VERSION: u32 = 1;
+const IMPORTED_SECTION_FALLBACK: &str = "Imported";
+/// MEMORY.md snapshots older than this are pruned, except the newest of them.
+pub const MEMORY_HISTORY_KEEP_SECS: u64 = 90 * DAY_SECS;
+
+#[derive(Debug, Clone, Default)]
+pub struct MemoryDiff {
+    pub since_epoch_secs: u64,
+    pub baseline_path: Option<PathBuf>,
+    pub added: Vec<String>,
+    pub modified: Vec<(String, String)>,
+    pub consolidated: Vec<(Vec<String>, String)>,
+    pub removed: Vec<String>,
+    pub daily_files: Vec<DailyMemoryActivity>,
+}
+
+#[derive(Debug, Clone)]
+pub struct DailyMemoryActivity {
+    pub path: PathBuf,
+    pub day_key: String,
+    pub sessions: usize,
+    pub lines: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MemoryRecord {
+    pub section: String,
+    pub bullet: String,
+    #[serde(default)]
+    pub date: Option<String>,
+    pub source: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryExport {
+    pub schema_version: u32,
+    pub exported_at_epoch_secs: u64,
+    pub records: Vec<MemoryRecord>,
+}
+
+/// Lowercased term counts for a memory bullet, used as a lightweight text embedding.
+pub fn bullet_terms(text: &str) -> BTreeMap<String, usize> {
+    let mut terms = BTreeMap::new();
+    for token in text
+        .split(|c: char| !(c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.'))
+        .map(|t| {
+            t.trim_matches(|c: char| c == '-' || c == '.')
+                .to_ascii_lowercase()
+        })
+    {
+        if token.len() < 2 || STOPWORDS.contains(&token.as_str()) {
+            continue;
+        }
+        *terms.entry(token).or_insert(0) += 1;
+    }
+    terms
+}
+
+pub fn bullet_similarity(a: &BTreeMap<String, usize>, b: &BTreeMap<String, usize>) -> f64 {
+    let dot = a
+        .iter()
+        .filter_map(|(term, count)| b.get(term).map(|other| (*count * *other) as f64))
+        .sum::<f64>();
+    let norm_a = a.values().map(|v| (*v * *v) as f64).sum::<f64>().sqrt();
+    let norm_b = b.values().map(|v| (*v * *v) as f64).sum::<f64>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    dot / (norm_a * norm_b)
+}
+
+/// Bullet text from a MEMORY.md-style document, skipping the conflicts section.
+pub fn memory_bullets(markdown: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut in_conflicts = false;
+    for raw_line in markdown.lines() {
+        let line = raw_line.trim();
+        if line.starts_with('#') {
+            in_conflicts = line.eq_ignore_ascii_case(MEMORY_CONFLICTS_HEADING);
+            continue;
+        }
+        if in_conflicts {
+            continue;
+        }
+        if let Some(rest) = line.strip_prefix("- ").or_else(|| line.strip_prefix("* ")) {
+            let bullet = rest.trim();
+            if !bullet.is_empty() {
+                out.push(bullet.to_string());
+            }
+        }
+    }
+    out
+}
+
+/// Renders MEMORY.md headings and bullets as a session primer bounded by `max_tokens`.
+///
+/// Returns `None` when memory has no bullets worth sending.
+pub fn build_memory_primer(memory_markdown: &str, max_tokens: u64) -> Option<String> {
+    let budget = max_tokens.saturating_mul(PRIMER_BYTES_PER_TOKEN) as usize;
+    let mut out = format!("{PRIMER_HEADER}\n");
+    let mut pending_heading: Option<&str> = None;
+    let mut in_conflicts = false;
+    let mut bullets = 0usize;
+
+    for raw_line in memory_markdown.lines() {
+        let line = raw_line.trim();
+        if line.starts_with("##") {
+            in_conflicts = line.eq_ignore_ascii_case(MEMORY_CONFLICTS_HEADING);
+            pending_heading = (!in_conflicts).then_some(line);
+            continue;
+        }
+        if in_conflicts || !(line.starts_with("- ") || line.starts_with("* ")) {
+            continue;
+        }
+        let mut addition = String::new();
+        if let Some(heading) = pending_heading.take() {
+            addition.push_str(heading);
+            addition.push('\n');
+        }
+        addition.push_str(line);
+        addition.push('\n');
+        if out.len() + addition.len() > budget {
+            break;
+        }
+        out.push_str(&addition);
+        bullets += 1;
+    }
+
+    (bullets > 0).then_some(out)
+}
+
+/// Lifetime class attached to a memory bullet via a `[decay:<class>]` or `[ttl:<window>]` tag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemoryDecay {
+    Ephemeral,
+    Weekly,
+    Permanent,
+    Ttl(u64),
+}
+
+impl MemoryDecay {
+    fn parse(tag: &str) -> Option<Self> {
+        let tag = tag.trim();
+        if let Some(class) = tag.strip_prefix("decay:") {
+            return match class.trim().to_ascii_lowercase().as_str() {
+                "ephemeral" => Some(Self::Ephemeral),
+                "weekly" => Some(Self::Weekly),
+                "permanent" => Some(Self::Permanent),
+                _ => None,
+            };
+        }
+        tag.strip_prefix("ttl:")
+            .and_then(|window| parse_since_window(window).ok())
+            .map(Self::Ttl)
+    }
+
+    fn label(self) -> String {
+        match self {
+            Self::Ephemeral => "decay:ephemeral".to_string(),
+            Self::Weekly => "decay:weekly".to_string(),
+            Self::Permanent => "decay:permanent".to_string(),
+            Self::Ttl(secs) if secs % DAY_SECS == 0 => format!("ttl:{}d", secs / DAY_SECS),
+            Self::Ttl(secs) => format!("ttl:{secs}s"),
+        }
+    }
+
+    fn lifetime_secs(self) -> Option<u64> {
+        match self {
+            Self::Ephemeral => Some(DAY_SECS),
+            Self::Weekly => Some(7 * DAY_SECS),
+            Self::Permanent => None,
+            Self::Ttl(secs) => Some(secs),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct DecayTag {
+    decay: MemoryDecay,
+    since_epoch_secs: Option<u64>,
+    start: usize,
+    end: usize,
+}
+
+fn day_key_for_epoch(epoch_secs: u64) -> String {
+    chrono::DateTime::from_timestamp(epoch_secs as i64, 0)
+        .map(|dt| dt.format("%Y-%m-%d").to_string())
+        .unwrap_or_else(|| "1970-01-01".to_string())
+}
+
+fn utc_day_start_epoch(day_key: &str) -> Option<u64> {
+    NaiveDate::parse_from_str(day_key, "%Y-%m-%d")
+        .ok()?
+        .and_hms_opt(0, 0, 0)
+        .map(|dt| dt.and_utc().timestamp().max(0) as u64)
+}
+
+fn find_decay_tag(bullet: &str) -> Option<DecayTag> {
+    let mut offset = 0usize;
+    while let Some(open) = bullet[offset..].find('[') {
+        let start = offset + open;
+        let close = bullet[start..].find(']')?;
+        let end = start + close + 1;
+        let inner = &bullet[start + 1..end - 1];
+        let mut parts = inner.split_whitespace();
+        if let Some(decay) = parts.next().and_then(MemoryDecay::parse) {
+            let since_epoch_secs = parts
+                .find_map(|part| part.strip_prefix("since:"))
+                .and_then(utc_day_start_epoch);
+            return Some(DecayTag {
+                decay,
+                since_epoch_secs,
+                start,
+                end,
+            });
+        }
+        offset = end;
+    }
+    None
+}
+
+fn strip_decay_tag(bullet: &str) -> String {
+    match find_decay_tag(bullet) {
+        Some(tag) => format!("{}{}", &bullet[..tag.start], &bullet[tag.end..])
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .to_ascii_lowercase(),
+        None => bullet.trim().to_ascii_lowercase(),
+    }
+}
+
+/// Stamps decay-tagged bullets with their first-seen day and drops bullets whose lifetime ended.
+///
+/// The first-seen day is carried over from `existing_memory` when the same bullet was already
+/// stamped there. Returns the rewritten summary and the expired bullet texts.
+pub fn apply_memory_decay(
+    summary: &str,
+    existing_memory: &str,
+    now_epoch_secs: u64,
+) -> (String, Vec<String>) {
+    let mut known_since = BTreeMap::new();
+    for bullet in memory_bullets(existing_memory) {
+        if let Some(since) = find_decay_tag(&bullet).and_then(|tag| tag.since_epoch_secs) {
+            known_since.entry(strip_decay_tag(&bullet)).or_insert(since);
+        }
+    }
+
+    let mut out = String::with_capacity(summary.len());
+    let mut expired = Vec::new();
+    for line in summary.lines() {
+        let trimmed = line.trim_start();
+        let is_bullet = trimmed.starts_with("- ") || trimmed.starts_with("* ");
+        let Some(tag) = is_bullet.then(|| find_decay_tag(line)).flatten() else {
+            out.push_str(line);
+            out.push('\n');
+            continue;
+        };
+
+        let since = tag
+            .since_epoch_secs
+            .or_else(|| {
+                known_since
+                    .get(&strip_decay_tag(trimmed[2..].trim()))
+                    .copied()
+            })
+            .unwrap_or(now_epoch_secs);
+        if let Some(lifetime) = tag.decay.lifetime_secs()
+            && now_epoch_secs.saturating_sub(since) >= lifetime
+        {
+            expired.push(trimmed[2..].trim().to_string());
+            continue;
+        }
+
+        out.push_str(&line[..tag.start]);
+        out.push_str(&format!(
+            "[{} since:{}]",
+            tag.decay.label(),
+            day_key_for_epoch(since)
+        ));
+        out.push_str(&line[tag.end..]);
+        out.push('\n');
+    }
+    if !summary.ends_with('\n') && out.ends_with('\n') {
+        out.pop();
+    }
+    (out, expired)
+}
+
+/// Parses relative windows such as `7d`, `12h`, `2w`, `30m` or `90s` into seconds.
+pub fn parse_since_window(raw: &str) -> Result<u64> {
+    let trimmed = raw.trim();
+    let split = trimmed
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(trimmed.len());
+    let (digits, unit) = trimmed.split_at(split);
+    let value = digits
+        .parse::<u64>()
+        .with_context(|| format!("invalid --since `{raw}`; expected forms like 7d, 12h, 2w"))?;
+    let multiplier = match unit.trim().to_ascii_lowercase().as_str() {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3_600,
+        "" | "d" => 86_400,
+        "w" => 7 * 86_400,
+        other => anyhow::bail!("invalid --since unit `{other}`; use s, m, h, d or w"),
+    };
+    Ok(value.saturating_mul(multiplier))
+}
+
+fn memory_history_dir(paths: &MoonPaths) -> PathBuf {
+    paths.memory_dir.join(MEMORY_HISTORY_DIR)
+}
+
+fn history_snapshot_epoch(path: &Path) -> Option<u64> {
+    path.file_stem()
+        .and_then(|v| v.to_str())
+        .and_then(|stem| stem.strip_prefix(MEMORY_HISTORY_PREFIX))
+        .and_then(|epoch| epoch.parse::<u64>().ok())
+}
+
+fn list_memory_snapshots(paths: &MoonPaths) -> Result<Vec<(u64, PathBuf)>> {
+    let dir = memory_history_dir(paths);
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut out = Vec::new();
+    for entry in fs::read_dir(&dir).with_context(|| format!("failed to read {}", dir.display()))? {
+        let path = entry?.path();
+        if let Some(epoch) = history_snapshot_epoch(&path) {
+            out.push((epoch, path));
+        }
+    }
+    out.sort();
+    Ok(out)
+}
+
+/// Keeps a copy of MEMORY.md content so later diffs have a baseline to compare against.
+pub fn record_memory_snapshot(
+    paths: &MoonPaths,
+    content: &str,
+    epoch_secs: u64,
+) -> Result<PathBuf> {
+    let dir = memory_history_dir(paths);
+    fs::create_dir_all(&dir).with_context(|| format!("failed to create {}", dir.display()))?;
+    let path = dir.join(format!("{MEMORY_HISTORY_PREFIX}{epoch_secs}.md"));
+    fs::write(&path, content).with_context(|| format!("failed to write {}", path.display()))?;
+    Ok(path)
+}
+
+/// Deletes history snapshots taken before `now_epoch_secs - keep_secs`, keeping the newest of
+/// them as the baseline for diffs reaching past the cutoff. Returns how many were removed.
+pub fn prune_memory_history(
+    paths: &MoonPaths,
+    keep_secs: u64,
+    now_epoch_secs: u64,
+) -> Result<usize> {
+    let cutoff = now_epoch_secs.saturating_sub(keep_secs);
+    let expired = list_memory_snapshots(paths)?
+        .into_iter()
+        .filter(|(epoch, _)| *epoch < cutoff)
+        .collect::<Vec<_>>();
+    let mut removed = 0usize;
+    for (_, path) in expired.iter().rev().skip(1) {
+        fs::remove_file(path).with_context(|| format!("failed to remove {}", path.display()))?;
+        removed += 1;
+    }
+    Ok(removed)
+}
+
+/// Seeds history with the pre-existing MEMORY.md the first time synthesis replaces it.
+pub fn ensure_memory_baseline(paths: &MoonPaths, previous: &str) -> Result<()> {
+    if previous.trim().is_empty() || !list_memory_snapshots(paths)?.is_empty() {
+        return Ok(());
+    }
+    let epoch = fs::metadata(&paths.memory_file)
+        .and_then(|meta| meta.modified())
+        .ok()
+        .and_then(|mtime| mtime.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    record_memory_snapshot(paths, previous, epoch)?;
+    Ok(())
+}
+
+fn day_key_start_epoch(day_key: &str) -> Option<u64> {
+    let date = NaiveDate::parse_from_str(day_key, "%Y-%m-%d").ok()?;
+    let start = date.and_hms_opt(0, 0, 0)?;
+    resolve_residential_tz()
+        .from_local_datetime(&start)
+        .earliest()
+        .map(|dt| dt.timestamp().max(0) as u64)
+}
+
+fn daily_memory_activity(paths: &MoonPaths, since_epoch: u64) -> Result<Vec<DailyMemoryActivity>> {
+    if !paths.memory_dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut out = Vec::new();
+    for entry in fs::read_dir(&paths.memory_dir)
+        .with_context(|| format!("failed to read {}", paths.memory_dir.display()))?
+    {
+        let path = entry?.path();
+        if path.extension().and_then(|v| v.to_str()) != Some("md") {
+            continue;
+        }
+        let Some(day_key) = path.file_stem().and_then(|v| v.to_str()) else {
+            continue;
+        };
+        let Some(day_start) = day_key_start_epoch(day_key) else {
+            continue;
+        };
+        // Keep the day that contains the cutoff.
+        if day_start.saturating_add(86_400) <= since_epoch {
+            continue;
+        }
+        let content = fs::read_to_string(&path).unwrap_or_default();
+        let sessions = content
+            .lines()
+            .filter(|line| line.trim_start().starts_with("## Session"))
+            .count();
+        let lines = content
+            .lines()
+            .filter(|line| {
+                let trimmed = line.trim_start();
+                trimmed.starts_with("**User:**")
+                    || trimmed.starts_with("**Assistant:**")
+                    || trimmed.starts_with("- ")
+            })
+            .count();
+        out.push(DailyMemoryActivity {
+            path: path.clone(),
+            day_key: day_key.to_string(),
+            sessions,
+            lines,
+        });
+    }
+    out.sort_by(|a, b| a.day_key.cmp(&b.day_key));
+    Ok(out)
+}
+
+/// Classifies bullet changes between a baseline and the current memory.
+pub fn diff_bullets(baseline: &[String], current: &[String]) -> MemoryDiff {
+    let baseline_set = baseline.iter().collect::<BTreeSet<_>>();
+    let current_set = current.iter().collect::<BTreeSet<_>>();
+    let old_only = baseline
+        .iter()
+        .filter(|b| !current_set.contains(b))
+        .collect::<Vec<_>>();
+    let new_only = current
+        .iter()
+        .filter(|b| !baseline_set.contains(b))
+        .collect::<Vec<_>>();
+    let new_terms = new_only.iter().map(|b| bullet_terms(b)).collect::<Vec<_>>();
+
+    let mut matched_to: BTreeMap<usize, Vec<String>> = BTreeMap::new();
+    let mut removed = Vec::new();
+    for old in old_only {
+        let old_terms = bullet_terms(old);
+        let best = new_terms
+            .iter()
+            .enumerate()
+            .map(|(idx, terms)| (idx, bullet_similarity(&old_terms, terms)))
+            .filter(|(_, score)| *score >= BULLET_MATCH_MIN_SIMILARITY)
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+        match best {
+            Some((idx, _)) => matched_to.entry(idx).or_default().push(old.clone()),
+            None => removed.push(old.clone()),
+        }
+    }
+
+    let mut diff = MemoryDiff {
+        removed,
+        ..MemoryDiff::default()
+    };
+    for (idx, new) in new_only.iter().enumerate() {
+        match matched_to.remove(&idx) {
+            Some(olds) if olds.len() > 1 => diff.consolidated.push((olds, (*new).clone())),
+            Some(mut olds) => diff.modified.push((olds.remove(0), (*new).clone())),
+            None => diff.added.push((*new).clone()),
+        }
+    }
+    diff
+}
+
+/// Compares MEMORY.md against the newest history snapshot taken at or before `since_epoch`.
+pub fn diff_memory_since(paths: &MoonPaths, since_epoch: u64) -> Result<MemoryDiff> {
+    let snapshots = list_memory_snapshots(paths)?;
+    let baseline_path = snapshots
+        .iter()
+        .rev()
+        .find(|(epoch, _)| *epoch <= since_epoch)
+        .map(|(_, path)| path.clone());
+    let baseline = match &baseline_path {
+        Some(path) => fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?,
+        None => String::new(),
+    };
+    let current = fs::read_to_string(&paths.memory_file).unwrap_or_default();
+
+    let mut diff = diff_bullets(&memory_bullets(&baseline), &memory_bullets(&current));
+    diff.since_epoch_secs = since_epoch;
+    diff.baseline_path = baseline_path;
+    diff.daily_files = daily_memory_activity(paths, since_epoch)?;
+    Ok(diff)
+}
+
+fn heading_title(line: &str) -> &str {
+    line.trim_start_matches('#').trim()
+}
+
+/// Structured records for bullets and conversation lines in a memory markdown document.
+///
+/// Sections are the `##` heading, joined with the `###` heading as `H2 / H3` when present.
+/// Records take their date from a `since:` decay stamp, falling back to `default_date`.
+pub fn parse_memory_records(
+    markdown: &str,
+    source: &str,
+    default_date: Option<&str>,
+) -> Vec<MemoryRecord> {
+    let mut out = Vec::new();
+    let mut h2 = String::new();
+    let mut h3 = String::new();
+    let mut in_conflicts = false;
+    for raw_line in markdown.lines() {
+        let line = raw_line.trim();
+        if line.starts_with("###") {
+            h3 = heading_title(line).to_string();
+            continue;
+        }
+        if line.starts_with("##") {
+            in_conflicts = line.eq_ignore_ascii_case(MEMORY_CONFLICTS_HEADING);
+            h2 = heading_title(line).to_string();
+            h3.clear();
+            continue;
+        }
+        if in_conflicts || line.starts_with('#') {
+            continue;
+        }
+        let bullet = if let Some(rest) = line.strip_prefix("- ").or_else(|| line.strip_prefix("* "))
+        {
+            rest.trim()
+        } else if line.starts_with("**User:**") || line.starts_with("**Assistant:**") {
+            line
+        } else {
+            continue;
+        };
+        if bullet.is_empty() {
+            continue;
+        }
+        let section = if h3.is_empty() {
+            h2.clone()
+        } else if h2.is_empty() {
+            h3.clone()
+        } else {
+            format!("{h2} / {h3}")
+        };
+        let date = find_decay_tag(bullet)
+            .and_then(|tag| tag.since_epoch_secs)
+            .map(day_key_for_epoch)
+            .or_else(|| default_date.map(str::to_string));
+        out.push(MemoryRecord {
+            section,
+            bullet: bullet.to_string(),
+            date,
+            source: source.to_string(),
+        });
+    }
+    out
+}
+
+/// One `##` section of a daily memory file, such as an L1 `## Session <id>` block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DailyMemorySection {
+    pub title: String,
+    /// Non-blank lines under the heading, `###` sub-headings included, block markers dropped.
+    pub lines: Vec<String>,
+}
+
+pub fn daily_memory_file(paths: &MoonPaths, day_key: &str) -> PathBuf {
+    paths.memory_dir.join(format!("{day_key}.md"))
+}
+
+/// Splits a daily memory file into its `##` sections in file order; the `#` title and any
+/// text before the first section are skipped.
+pub fn parse_daily_memory_sections(markdown: &str) -> Vec<DailyMemorySection> {
+    let mut out: Vec<DailyMemorySection> = Vec::new();
+    for raw_line in markdown.lines() {
+        let line = raw_line.trim();
+        if line.is_empty() || line.starts_with("<!--") {
+            continue;
+        }
+        if line.starts_with("##") && !line.starts_with("###") {
+            out.push(DailyMemorySection {
+                title: heading_title(line).to_string(),
+                lines: Vec::new(),
+            });
+            continue;
+        }
+        if let Some(section) = out.last_mut() {
+            section.lines.push(line.to_string());
+        }
+    }
+    out
+}
+
+fn memory_source_label(paths: &MoonPaths, path: &Path) -> String {
+    path.strip_prefix(&paths.moon_home)
+        .unwrap_or(path)
+        .display()
+        .to_string()
+}
+
+fn daily_memory_files(paths: &MoonPaths) -> Result<Vec<(String, PathBuf)>> {
+    if !paths.memory_dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut out = Vec::new();
+    for entry in fs::read_dir(&paths.memory_dir)
+        .with_context(|| format!("failed to read {}", paths.memory_dir.display()))?
+    {
+        let path = entry?.path();
+        if path.extension().and_then(|v| v.to_str()) != Some("md") {
+            continue;
+        }
+        let Some(day_key) = path.file_stem().and_then(|v| v.to_str()) else {
+            continue;
+        };
+        if NaiveDate::parse_from_str(day_key, "%Y-%m-%d").is_err() {
+            continue;
+        }
+        out.push((day_key.to_string(), path.clone()));
+    }
+    out.sort();
+    Ok(out)
+}
+
+/// Records from MEMORY.md followed by every daily memory file, oldest day first.
+pub fn export_memory_records(
+    paths: &MoonPaths,
+    exported_at_epoch_secs: u64,
+) -> Result<MemoryExport> {
+    let mut records = Vec::new();
+    if paths.memory_file.exists() {
+        let raw = fs::read_to_string(&paths.memory_file)
+            .with_context(|| format!("failed to read {}", paths.memory_file.display()))?;
+        records.extend(parse_memory_records(
+            &raw,
+            &memory_source_label(paths, &paths.memory_file),
+            None,
+        ));
+    }
+    for (day_key, path) in daily_memory_files(paths)? {
+        let raw = fs::read_to_string(&path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        records.extend(parse_memory_records(
+            &raw,
+            &memory_source_label(paths, &path),
+            Some(&day_key),
+        ));
+    }
+    Ok(MemoryExport {
+        schema_version: MEMORY_EXPORT_SCHEMA_VERSION,
+        exported_at_epoch_secs,
+        records,
+    })
+}
+
+/// YAML rendering of an export; scalars use JSON string quoting, which YAML accepts verbatim.
+pub fn render_memory_export_yaml(export: &MemoryExport) -> Result<String> {
+    let mut out = String::new();
+    out.push_str(&format!("schema_version: {}\n", export.schema_version));
+    out.push_str(&format!(
+        "exported_at_epoch_secs: {}\n",
+        export.exported_at_epoch_secs
+    ));
+    if export.records.is_empty() {
+        out.push_str("records: []\n");
+        return Ok(out);
+    }
+    out.push_str("records:\n");
+    for record in &export.records {
+        out.push_str(&format!(
+            "  - section: {}\n",
+            serde_json::to_string(&record.section)?
+        ));
+        out.push_str(&format!(
+            "    bullet: {}\n",
+            serde_json::to_string(&record.bullet)?
+        ));
+        match &record.date {
+            Some(date) => out.push_str(&format!("    date: {}\n", serde_json::to_string(date)?)),
+            None => out.push_str("    date: null\n"),
+        }
+        out.push_str(&format!(
+            "    source: {}\n",
+            serde_json::to_string(&record.source)?
+        ));
+    }
+    Ok(out)
+}
+
+/// True when a record was exported from MEMORY.md rather than a daily file.
+pub fn is_memory_file_record(paths: &MoonPaths, record: &MemoryRecord) -> bool {
+    let memory_name = paths
+        .memory_file
+        .file_name()
+        .and_then(|v| v.to_str())
+        .unwrap_or("MEMORY.md");
+    Path::new(&record.source)
+        .file_name()
+        .and_then(|v| v.to_str())
+        .is_some_and(|name| name.eq_ignore_ascii_case(memory_name))
+}
+
+/// Adds records to MEMORY.md content under their section heading, skipping bullets already present.
+///
+/// Missing sections are created ahead of the conflicts section. Returns the merged markdown and
+/// the bullets that were added.
+pub fn merge_memory_records(existing: &str, records: &[MemoryRecord]) -> (String, Vec<String>) {
+    let mut lines = if existing.trim().is_empty() {
+        vec!["# MEMORY".to_string()]
+    } else {
+        existing.lines().map(str::to_string).collect::<Vec<_>>()
+    };
+    let mut known = memory_bullets(existing)
+        .into_iter()
+        .collect::<BTreeSet<_>>();
+    let mut added = Vec::new();
+
+    for record in records {
+        let bullet = record.bullet.trim();
+        if bullet.is_empty() || !known.insert(bullet.to_string()) {
+            continue;
+        }
+        let section = match record.section.split(" / ").next().map(str::trim) {
+            Some(title) if !title.is_empty() => title,
+            _ => IMPORTED_SECTION_FALLBACK,
+        };
+        let heading_idx = lines.iter().position(|line| {
+            let line = line.trim();
+            line.starts_with("##")
+                && !line.starts_with("###")
+                && heading_title(line).eq_ignore_ascii_case(section)
+        });
+        let entry = format!("- {bullet}");
+        match heading_idx {
+            Some(idx) => {
+                let section_end = lines
+                    .iter()
+                    .skip(idx + 1)
+                    .position(|line| line.trim_start().starts_with("## "))
+                    .map(|offset| idx + 1 + offset)
+                    .unwrap_or(lines.len());
+                let insert_at = (idx + 1..section_end)
+                    .rev()
+                    .find(|i| !lines[*i].trim().is_empty())
+                    .map(|i| i + 1)
+                    .unwrap_or(idx + 1);
+                lines.insert(insert_at, entry);
+            }
+            None => {
+                let insert_at = lines
+                    .iter()
+                    .position(|line| line.trim().eq_ignore_ascii_case(MEMORY_CONFLICTS_HEADING))
+                    .unwrap_or(lines.len());
+                let mut block = vec![String::new(), format!("## {section}"), entry];
+                if insert_at < lines.len() {
+                    block.push(String::new());
+                }
+                lines.splice(insert_at..insert_at, block);
+            }
+        }
+        added.push(bullet.to_string());
+    }
+
+    let mut merged = lines.join("\n");
+    merged.push('\n');
+    (merged, added)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        MemoryRecord, apply_memory_decay, build_memory_primer, diff_bullets, memory_bullets,
+        merge_memory_records, parse_daily_memory_sections, parse_memory_records,
+        parse_since_window, prune_memory_history, record_memory_snapshot,
+    };
+    use crate::moon::paths::MoonPaths;
+
+    #[test]
+    fn parse_daily_memory_sections_splits_session_blocks() {
+        let daily = "# Daily Memory 2024-05-12\n<!-- MOON_DAILY_FORMAT -->\n\n<!-- MOON_SESSION_BEGIN:s1 -->\n## Session s1\n- Message Count: 2\n\n### Conversation\n**User:** Decision: ship on Friday.\n<!-- MOON_SESSION_END:s1 -->\n\n## Session s2\n- No user/assistant turns captured.\n";
+        let sections = parse_daily_memory_sections(daily);
+        assert_eq!(sections.len(), 2);
+        assert_eq!(sections[0].title, "Session s1");
+        assert_eq!(
+            sections[0].lines,
+            vec![
+                "- Message Count: 2",
+                "### Conversation",
+                "**User:** Decision: ship on Friday."
+            ]
+        );
+        assert_eq!(sections[1].title, "Session s2");
+        assert!(parse_daily_memory_sections("# Daily Memory\n").is_empty());
+    }
+
+    #[test]
+    fn apply_memory_decay_stamps_new_tags_and_expires_old_ones() {
+        let now = 1_700_000_000u64; // 2023-11-14
+        let existing = "## Durable Decisions & Context\n- Deploy freeze until Friday [decay:weekly since:2023-11-01]\n";
+        let summary = "## Durable Decisions & Context\n- Deploy freeze until Friday [decay:weekly]\n- Staging is flaky today [decay:ephemeral]\n- Repo lives on GitHub [decay:permanent since:2020-01-01]\n- Sprint ends soon [ttl:30d since:2023-11-10]\n";
+
+        let (out, expired) = apply_memory_decay(summary, existing, now);
+        assert_eq!(
+            expired,
+            vec!["Deploy freeze until Friday [decay:weekly]".to_string()]
+        );
+        assert!(out.contains("- Staging is flaky today [decay:ephemeral since:2023-11-14]"));
+        assert!(out.contains("- Repo lives on GitHub [decay:permanent since:2020-01-01]"));
+        assert!(out.contains("- Sprint ends soon [ttl:30d since:2023-11-10]"));
+
+        let (later, expired_later) = apply_memory_decay(&out, &out, now + 2 * 86_400);
+        assert_eq!(expired_later.len(), 1);
+        assert!(!later.contains("Staging is flaky"));
+    }
+
+    #[test]
+    fn build_memory_primer_respects_token_budget_and_skips_conflicts() {
+        let memory = "# MEMORY\n\n## User Preferences\n- Keep answers concise.\n- Prefer rust examples.\n\n## Memory Conflicts\n- New: a | Earlier: b\n";
+        let primer = build_memory_primer(memory, 800).expect("primer");
+        assert!(primer.starts_with("[MOON_MEMORY_PRIMER]\n## User Preferences\n"));
+        assert!(primer.contains("- Prefer rust examples."));
+        assert!(!primer.contains("Memory Conflicts"));
+
+        let tight = build_memory_primer(memory, 25).expect("tight primer");
+        assert!(tight.contains("- Keep answers concise."));
+        assert!(!tight.contains("Prefer rust examples"));
+
+        assert!(build_memory_primer("# MEMORY\n", 800).is_none());
+    }
+
+    #[test]
+    fn parse_since_window_supports_common_units() {
+        assert_eq!(parse_since_window("7d").unwrap(), 7 * 86_400);
+        assert_eq!(parse_since_window("12h").unwrap(), 12 * 3_600);
+        assert_eq!(parse_since_window("2w").unwrap(), 14 * 86_400);
+        assert_eq!(parse_since_window("3").unwrap(), 3 * 86_400);
+        assert!(parse_since_window("soon").is_err());
+        assert!(parse_since_window("4y").is_err());
+    }
+
+    #[test]
+    fn diff_bullets_classifies_added_modified_consolidated_and_removed() {
+        let baseline = memory_bullets(
+            "# MEMORY\n\n## Durable Decisions & Context\n- Default branch for moon is main.\n- Run cargo test before push.\n- Run cargo clippy before push.\n- Legacy deploy uses ftp uploads.\n",
+        );
+        let current = memory_bullets(
+            "# MEMORY\n\n## Durable Decisions & Context\n- Default branch for moon is develop.\n- Run cargo test and cargo clippy before push.\n- User prefers concise answers.\n",
+        );
+        let diff = diff_bullets(&baseline, &current);
+        assert_eq!(
+            diff.added,
+            vec!["User prefers concise answers.".to_string()]
+        );
+        assert_eq!(diff.modified.len(), 1);
+        assert!(diff.modified[0].1.contains("develop"));
+        assert_eq!(diff.consolidated.len(), 1);
+        assert_eq!(diff.consolidated[0].0.len(), 2);
+        assert_eq!(
+            diff.removed,
+            vec!["Legacy deploy uses ftp uploads.".to_string()]
+        );
+    }
+
+    #[test]
+    fn parse_memory_records_tracks_sections_and_dates() {
+        let daily =
+            "## Session s1\n- Source Archive: `a.md`\n\n### Conversation\n**User:** ship it\n";
+        let records = parse_memory_records(daily, "memory/2026-01-02.md", Some("2026-01-02"));
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].section, "Session s1");
+        assert_eq!(records[1].section, "Session s1 / Conversation");
+        assert_eq!(records[1].bullet, "**User:** ship it");
+        assert_eq!(records[1].date.as_deref(), Some("2026-01-02"));
+
+        let memory = "# MEMORY\n\n## User Preferences\n- Be brief [decay:weekly since:2026-01-05]\n\n## Memory Conflicts\n- New: a | Earlier: b\n";
+        let records = parse_memory_records(memory, "MEMORY.md", None);
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].date.as_deref(), Some("2026-01-05"));
+    }
+
+    #[test]
+    fn merge_memory_records_inserts_under_sections_and_skips_duplicates() {
+        let existing = "# MEMORY\n\n## User Preferences\n- Be brief.\n\n## Memory Conflicts\n- New: a | Earlier: b\n";
+        let record = |section: &str, bullet: &str| MemoryRecord {
+            section: section.to_string(),
+            bullet: bullet.to_string(),
+            date: None,
+            source: "MEMORY.md".to_string(),
+        };
+        let (merged, added) = merge_memory_records(
+            existing,
+            &[
+                record("User Preferences", "Be brief."),
+                record("User Preferences", "Use metric units."),
+                record("Lessons Learned", "Run clippy first."),
+            ],
+        );
+        assert_eq!(added, vec!["Use metric units.", "Run clippy first."]);
+        assert!(merged.contains("## User Preferences\n- Be brief.\n- Use metric units.\n"));
+        assert!(merged.contains("## Lessons Learned\n- Run clippy first.\n\n## Memory Conflicts"));
+    }
+
+    #[test]
+    fn prune_memory_history_keeps_recent_snapshots_and_the_newest_baseline() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let paths = MoonPaths::for_test(tmp.path());
+        for epoch in [100, 200, 300, 1_000] {
+            record_memory_snapshot(&paths, "# MEMORY\n", epoch).expect("snapshot");
+        }
+
+        assert_eq!(prune_memory_history(&paths, 500, 1_200).expect("prune"), 2);
+        let history = paths.memory_dir.join(".history");
+        assert!(!history.join("MEMORY-100.md").exists());
+        assert!(!history.join("MEMORY-200.md").exists());
+        assert!(history.join("MEMORY-300.md").exists());
+        assert!(history.join("MEMORY-1000.md").exists());
+        assert_eq!(prune_memory_history(&paths, 500, 1_200).expect("prune"), 0);
+    }
+}
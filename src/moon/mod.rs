@@ -1,20 +1,44 @@
 pub mod archive;
 pub mod audit;
+pub mod bench;
+pub mod budget;
+pub mod build_info;
 pub mod channel_archive_map;
+pub mod chunk_cache;
 pub mod config;
+pub mod consistency;
 pub mod continuity;
 pub mod daemon_lock;
 #[allow(dead_code)]
 pub mod distill;
 pub mod embed;
+pub mod gateway_calls;
+pub mod graph;
+pub mod hooks;
 pub mod inbound_watch;
+pub mod lease;
+pub mod memory;
+pub mod model_limits;
+pub mod notify;
 pub mod paths;
+pub mod pause;
+pub mod policy;
+pub mod privacy;
+pub mod prune;
 pub mod qmd;
 pub mod recall;
+pub mod report;
+pub mod session_file;
 pub mod session_usage;
+pub mod simhash;
 pub mod snapshot;
 pub mod state;
 pub mod thresholds;
+pub mod timeline;
+pub mod tokens;
+pub mod trash;
 pub mod util;
+pub mod vectors;
 pub mod warn;
+pub mod watchdog;
 pub mod watcher;
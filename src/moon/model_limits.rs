@@ -0,0 +1,109 @@
+use crate::moon::paths::MoonPaths;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+/// A provider's answer to a context-limit lookup for one model.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CachedModelLimit {
+    /// `None` records that the provider was asked but reported no limit.
+    pub context_tokens: Option<u64>,
+    pub detected_at_epoch_secs: u64,
+}
+
+/// Detected context limits keyed by [`cache_key`], so short-lived CLI runs skip provider APIs.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ModelLimitCache {
+    pub models: BTreeMap<String, CachedModelLimit>,
+}
+
+pub fn cache_path(paths: &MoonPaths) -> PathBuf {
+    paths.logs_dir.join("model-limits.json")
+}
+
+pub fn cache_key(provider: &str, base_url: Option<&str>, model: &str) -> String {
+    match base_url.map(|url| url.trim().trim_end_matches('/')) {
+        Some(url) if !url.is_empty() => format!("{provider}:{url}:{model}"),
+        _ => format!("{provider}:{model}"),
+    }
+}
+
+/// Cache contents; a missing or unparseable file is an empty cache.
+pub fn load(paths: &MoonPaths) -> ModelLimitCache {
+    fs::read_to_string(cache_path(paths))
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+/// The cached lookup for `key` when younger than `ttl_secs`; `ttl_secs = 0` disables the cache.
+pub fn lookup(
+    paths: &MoonPaths,
+    key: &str,
+    now_epoch_secs: u64,
+    ttl_secs: u64,
+) -> Option<CachedModelLimit> {
+    if ttl_secs == 0 {
+        return None;
+    }
+    load(paths)
+        .models
+        .remove(key)
+        .filter(|entry| now_epoch_secs.saturating_sub(entry.detected_at_epoch_secs) < ttl_secs)
+}
+
+pub fn store(
+    paths: &MoonPaths,
+    key: &str,
+    context_tokens: Option<u64>,
+    now_epoch_secs: u64,
+) -> Result<()> {
+    let mut cache = load(paths);
+    cache.models.insert(
+        key.to_string(),
+        CachedModelLimit {
+            context_tokens,
+            detected_at_epoch_secs: now_epoch_secs,
+        },
+    );
+    let path = cache_path(paths);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+    let data = serde_json::to_string_pretty(&cache)?;
+    fs::write(&path, format!("{data}\n"))
+        .with_context(|| format!("failed to write {}", path.display()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{cache_key, lookup, store};
+    use crate::moon::paths::MoonPaths;
+    use tempfile::tempdir;
+
+    #[test]
+    fn cached_limits_expire_after_ttl_and_keep_misses() {
+        let tmp = tempdir().expect("tempdir");
+        let paths = MoonPaths::for_test(tmp.path());
+        let gemini = cache_key("gemini", None, "gemini-2.5-flash");
+        let local = cache_key("openai-compatible", Some("http://llm.local/"), "qwen");
+        assert_eq!(local, "openai-compatible:http://llm.local:qwen");
+
+        store(&paths, &gemini, Some(1_048_576), 1_000).expect("store");
+        store(&paths, &local, None, 1_000).expect("store");
+
+        let hit = lookup(&paths, &gemini, 1_500, 3_600).expect("fresh entry");
+        assert_eq!(hit.context_tokens, Some(1_048_576));
+        let miss = lookup(&paths, &local, 1_500, 3_600).expect("cached miss");
+        assert_eq!(miss.context_tokens, None);
+
+        assert!(lookup(&paths, &gemini, 4_600, 3_600).is_none());
+        assert!(lookup(&paths, &gemini, 1_500, 0).is_none());
+    }
+}
@@ -0,0 +1,203 @@
+use crate::moon::config::MoonNotifyConfig;
+use crate::moon::warn::{self, WarnEvent};
+use anyhow::Result;
+use reqwest::blocking::Client;
+use serde_json::{Value, json};
+
+const WEBHOOK_TIMEOUT_SECS: u64 = 10;
+const MESSAGE_MAX_CHARS: usize = 1_800;
+
+/// High-severity watcher events that can be routed to webhooks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotifyEvent {
+    DistillFailures,
+    RetentionUndistilled,
+    DaemonRestart,
+}
+
+impl NotifyEvent {
+    pub const ALL: [NotifyEvent; 3] = [
+        NotifyEvent::DistillFailures,
+        NotifyEvent::RetentionUndistilled,
+        NotifyEvent::DaemonRestart,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            NotifyEvent::DistillFailures => "distill_failures",
+            NotifyEvent::RetentionUndistilled => "retention_undistilled",
+            NotifyEvent::DaemonRestart => "daemon_restart",
+        }
+    }
+
+    pub fn parse(raw: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|event| event.as_str() == raw.trim())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotifySink {
+    Discord,
+    Slack,
+}
+
+impl NotifySink {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            NotifySink::Discord => "discord",
+            NotifySink::Slack => "slack",
+        }
+    }
+
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "discord" => Some(NotifySink::Discord),
+            "slack" => Some(NotifySink::Slack),
+            _ => None,
+        }
+    }
+
+    fn webhook_url(self, cfg: &MoonNotifyConfig) -> &str {
+        match self {
+            NotifySink::Discord => cfg.discord_webhook_url.trim(),
+            NotifySink::Slack => cfg.slack_webhook_url.trim(),
+        }
+    }
+
+    fn payload(self, text: &str) -> Value {
+        match self {
+            NotifySink::Discord => json!({ "content": text }),
+            NotifySink::Slack => json!({ "text": text }),
+        }
+    }
+}
+
+/// Configured sinks for `event`: its route when one is set, otherwise every sink with a webhook.
+pub fn sinks_for_event(cfg: &MoonNotifyConfig, event: NotifyEvent) -> Vec<NotifySink> {
+    let candidates = match cfg.routes.get(event.as_str()) {
+        Some(route) => route
+            .iter()
+            .filter_map(|name| NotifySink::parse(name))
+            .collect(),
+        None => vec![NotifySink::Discord, NotifySink::Slack],
+    };
+    candidates
+        .into_iter()
+        .filter(|sink| !sink.webhook_url(cfg).is_empty())
+        .collect()
+}
+
+fn post_webhook(url: &str, payload: &Value) -> Result<()> {
+    let client = Client::builder()
+        .timeout(std::time::Duration::from_secs(WEBHOOK_TIMEOUT_SECS))
+        .build()?;
+    let response = client.post(url).json(payload).send()?;
+    if !response.status().is_success() {
+        anyhow::bail!("webhook call failed with status {}", response.status());
+    }
+    Ok(())
+}
+
+/// Sends `text` to each sink routed for `event`; failures are warned, never returned.
+/// Returns one `sink=sent|failed` entry per attempted sink.
+pub fn notify(cfg: &MoonNotifyConfig, event: NotifyEvent, text: &str) -> Vec<String> {
+    let message = crate::moon::util::truncate_with_ellipsis(
+        &format!("[moon] {}: {text}", event.as_str()),
+        MESSAGE_MAX_CHARS,
+    );
+    let mut out = Vec::new();
+    for sink in sinks_for_event(cfg, event) {
+        match post_webhook(sink.webhook_url(cfg), &sink.payload(&message)) {
+            Ok(()) => out.push(format!("{}=sent", sink.as_str())),
+            Err(err) => {
+                warn::emit(WarnEvent {
+                    code: "NOTIFY_FAILED",
+                    stage: "notify",
+                    action: "post-webhook",
+                    session: "na",
+                    archive: "na",
+                    source: sink.as_str(),
+                    retry: "none",
+                    reason: event.as_str(),
+                    err: &format!("{err:#}"),
+                });
+                out.push(format!("{}=failed", sink.as_str()));
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{NotifyEvent, NotifySink, notify, sinks_for_event};
+    use crate::moon::config::MoonNotifyConfig;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+
+    #[test]
+    fn routes_default_to_configured_sinks_and_respect_overrides() {
+        let mut cfg = MoonNotifyConfig {
+            discord_webhook_url: "https://discord.example/hook".to_string(),
+            ..MoonNotifyConfig::default()
+        };
+        assert_eq!(
+            sinks_for_event(&cfg, NotifyEvent::DaemonRestart),
+            vec![NotifySink::Discord]
+        );
+
+        cfg.slack_webhook_url = "https://slack.example/hook".to_string();
+        cfg.routes
+            .insert("daemon_restart".to_string(), vec!["slack".to_string()]);
+        cfg.routes
+            .insert("distill_failures".to_string(), Vec::new());
+        assert_eq!(
+            sinks_for_event(&cfg, NotifyEvent::DaemonRestart),
+            vec![NotifySink::Slack]
+        );
+        assert!(sinks_for_event(&cfg, NotifyEvent::DistillFailures).is_empty());
+        assert_eq!(
+            sinks_for_event(&cfg, NotifyEvent::RetentionUndistilled),
+            vec![NotifySink::Discord, NotifySink::Slack]
+        );
+    }
+
+    #[test]
+    fn notify_posts_slack_payload_to_webhook() {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+        let url = format!("http://{}/hook", listener.local_addr().expect("addr"));
+        let server = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().expect("accept");
+            let mut reader = BufReader::new(stream);
+            let mut content_length = 0usize;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).expect("read header");
+                if line.trim().is_empty() {
+                    break;
+                }
+                if let Some(value) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                    content_length = value.trim().parse().expect("content length");
+                }
+            }
+            let mut body = vec![0u8; content_length];
+            reader.read_exact(&mut body).expect("read body");
+            reader
+                .get_mut()
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\nconnection: close\r\n\r\n")
+                .expect("respond");
+            String::from_utf8(body).expect("utf8 body")
+        });
+
+        let cfg = MoonNotifyConfig {
+            slack_webhook_url: url,
+            ..MoonNotifyConfig::default()
+        };
+        let outcomes = notify(&cfg, NotifyEvent::DistillFailures, "3 consecutive failures");
+        let body = server.join().expect("server thread");
+        assert_eq!(outcomes, vec!["slack=sent".to_string()]);
+        assert!(body.contains("\"text\":\"[moon] distill_failures: 3 consecutive failures\""));
+    }
+}
@@ -1,6 +1,6 @@
 use anyhow::Result;
 use std::env;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, Clone)]
 pub struct MoonPaths {
@@ -15,6 +15,24 @@ pub struct MoonPaths {
     pub moon_home_is_explicit: bool,
 }
 
+impl MoonPaths {
+    /// The default layout under `moon_home`, with `qmd` looked up on `PATH`.
+    #[cfg(test)]
+    pub fn for_test(moon_home: &Path) -> Self {
+        Self {
+            moon_home: moon_home.to_path_buf(),
+            archives_dir: moon_home.join("archives"),
+            memory_dir: moon_home.join("memory"),
+            memory_file: moon_home.join("MEMORY.md"),
+            logs_dir: moon_home.join("moon/logs"),
+            openclaw_sessions_dir: moon_home.join("sessions"),
+            qmd_bin: PathBuf::from("qmd"),
+            qmd_db: moon_home.join("qmd.sqlite"),
+            moon_home_is_explicit: true,
+        }
+    }
+}
+
 fn required_home_dir() -> Result<PathBuf> {
     if let Some(home) = dirs::home_dir() {
         return Ok(home);
@@ -37,8 +55,16 @@ fn moon_home_from_inputs(home: PathBuf, moon_home_env: Option<&str>) -> (PathBuf
 }
 
 pub fn resolve_paths() -> Result<MoonPaths> {
+    resolve_paths_with_home(None)
+}
+
+/// Paths rooted at `moon_home` when given (as `moon init --moon-home` does), else `MOON_HOME`.
+pub fn resolve_paths_with_home(moon_home: Option<&Path>) -> Result<MoonPaths> {
     let home = required_home_dir()?;
-    let moon_home_env = env::var("MOON_HOME").ok();
+    let moon_home_env = match moon_home {
+        Some(path) => Some(path.display().to_string()),
+        None => env::var("MOON_HOME").ok(),
+    };
     let (moon_home, is_explicit) = moon_home_from_inputs(home.clone(), moon_home_env.as_deref());
 
     let archives_dir = env_or_default_path("MOON_ARCHIVES_DIR", moon_home.join("archives"));
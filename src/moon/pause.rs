@@ -0,0 +1,69 @@
+use crate::moon::paths::MoonPaths;
+use crate::moon::state::state_file_path;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+/// Maintenance pause set by `moon watch pause`. While it exists, watcher cycles still record
+/// usage and the heartbeat but skip every mutating action.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct WatchPause {
+    pub paused_at_epoch_secs: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+impl WatchPause {
+    pub fn detail(&self) -> String {
+        match &self.reason {
+            Some(reason) => format!(
+                "paused_at_epoch_secs={} reason={reason}",
+                self.paused_at_epoch_secs
+            ),
+            None => format!("paused_at_epoch_secs={}", self.paused_at_epoch_secs),
+        }
+    }
+}
+
+/// Flag file kept beside the state file, so a cycle rewriting state cannot drop the pause.
+pub fn pause_file_path(paths: &MoonPaths) -> PathBuf {
+    state_file_path(paths).with_file_name("watch.paused")
+}
+
+/// The active pause, if any. An unreadable flag file still pauses: failing open would let the
+/// daemon mutate archives an operator is working on.
+pub fn read_pause(paths: &MoonPaths) -> Option<WatchPause> {
+    let raw = fs::read_to_string(pause_file_path(paths)).ok()?;
+    Some(serde_json::from_str(raw.trim()).unwrap_or_default())
+}
+
+pub fn pause(paths: &MoonPaths, reason: Option<&str>, now_epoch_secs: u64) -> Result<WatchPause> {
+    let path = pause_file_path(paths);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+    let record = WatchPause {
+        paused_at_epoch_secs: now_epoch_secs,
+        reason: reason
+            .map(str::trim)
+            .filter(|reason| !reason.is_empty())
+            .map(ToOwned::to_owned),
+    };
+    fs::write(&path, format!("{}\n", serde_json::to_string(&record)?))
+        .with_context(|| format!("failed to write {}", path.display()))?;
+    Ok(record)
+}
+
+/// Clears the pause; returns the pause that was lifted, `None` when the watcher was not paused.
+pub fn resume(paths: &MoonPaths) -> Result<Option<WatchPause>> {
+    let previous = read_pause(paths);
+    let path = pause_file_path(paths);
+    match fs::remove_file(&path) {
+        Ok(()) => Ok(previous),
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err).with_context(|| format!("failed to remove {}", path.display())),
+    }
+}
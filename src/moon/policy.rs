@@ -0,0 +1,196 @@
+use crate::moon::config::MoonConfig;
+use crate::moon::session_usage::SessionUsageSnapshot;
+use crate::moon::state::MoonState;
+use crate::moon::thresholds::TriggerKind;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Datelike, Timelike};
+use serde_json::{Value, json};
+use std::fs;
+use std::path::Path;
+
+/// The table passed to the script's `decide(ctx)`.
+///
+/// `default` is the built-in decision, so scripts can adjust it rather than re-derive it;
+/// `local_hour`/`local_weekday` use `[distill].residential_timezone` and `trend` holds the
+/// session's recent usage samples for velocity heuristics.
+fn context(
+    cfg: &MoonConfig,
+    state: &MoonState,
+    usage: &SessionUsageSnapshot,
+    default: &[TriggerKind],
+) -> Value {
+    let local = DateTime::from_timestamp(usage.captured_at_epoch_secs as i64, 0)
+        .map(|at| at.with_timezone(&cfg.distill.residential_tz()));
+    let trend = state
+        .usage_trends
+        .get(&usage.session_id)
+        .map(|trend| {
+            trend
+                .samples
+                .iter()
+                .map(|sample| json!({ "epoch_secs": sample.epoch_secs, "ratio": sample.ratio }))
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    json!({
+        "default": default.iter().map(|kind| kind.as_str()).collect::<Vec<_>>(),
+        "now_epoch_secs": usage.captured_at_epoch_secs,
+        "local_hour": local.map(|at| at.hour()),
+        "local_weekday": local.map(|at| at.weekday().num_days_from_monday()),
+        "usage": {
+            "session_id": usage.session_id,
+            "used_tokens": usage.used_tokens,
+            "max_tokens": usage.max_tokens,
+            "usage_ratio": usage.usage_ratio,
+            "provider": usage.provider,
+        },
+        "trend": trend,
+        "state": {
+            "last_heartbeat_epoch_secs": state.last_heartbeat_epoch_secs,
+            "last_archive_epoch_secs": state.last_archive_epoch_secs(),
+            "last_compaction_epoch_secs": state.last_compaction_epoch_secs(),
+            "last_distill_epoch_secs": state.last_distill_epoch_secs(),
+            "channels": state.channels,
+        },
+        "config": {
+            "trigger_ratio": cfg.thresholds.trigger_ratio,
+            "archive_ratio": cfg.thresholds.archive_threshold(),
+            "archive_ratio_trigger_enabled": cfg.thresholds.archive_ratio_trigger_enabled,
+            "cooldown_secs": cfg.watcher.cooldown_secs,
+            "poll_interval_secs": cfg.watcher.poll_interval_secs,
+            "compaction_start_ratio": cfg.context.as_ref().map(|c| c.compaction_start_ratio),
+            "compaction_emergency_ratio": cfg.context.as_ref().map(|c| c.compaction_emergency_ratio),
+        },
+    })
+}
+
+/// Runs `[policy].script` and returns its trigger decision.
+///
+/// `Ok(None)` means no script is configured or the script returned `nil` (keep `default`).
+pub fn decide(
+    cfg: &MoonConfig,
+    state: &MoonState,
+    usage: &SessionUsageSnapshot,
+    default: &[TriggerKind],
+) -> Result<Option<Vec<TriggerKind>>> {
+    let script = cfg.policy.script.trim();
+    if script.is_empty() {
+        return Ok(None);
+    }
+    let path = Path::new(script);
+    let source =
+        fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    let decided = run_script(&source, &context(cfg, state, usage, default))
+        .with_context(|| format!("policy script {} failed", path.display()))?;
+    decided
+        .map(|names| {
+            names
+                .iter()
+                .map(|name| {
+                    TriggerKind::parse(name)
+                        .filter(|kind| *kind != TriggerKind::Distill)
+                        .with_context(|| {
+                        format!("policy script returned unknown trigger `{name}`; use archive or compaction")
+                    })
+                })
+                .collect()
+        })
+        .transpose()
+}
+
+/// Evaluates `decide(ctx)` in a sandboxed Lua state (no `io`/`os`, bounded instructions and
+/// memory). Returns the trigger names, or `None` when the script returned `nil`.
+#[cfg(feature = "lua-policy")]
+fn run_script(source: &str, ctx: &Value) -> Result<Option<Vec<String>>> {
+    use mlua::{HookTriggers, Lua, LuaOptions, LuaSerdeExt, StdLib};
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    /// Lua instructions a policy script may run per decision before it is aborted.
+    const MAX_INSTRUCTIONS: u32 = 1_000_000;
+    const HOOK_EVERY: u32 = 1_000;
+    const MEMORY_LIMIT_BYTES: usize = 16 * 1024 * 1024;
+    let lua = Lua::new_with(
+        StdLib::TABLE | StdLib::STRING | StdLib::MATH | StdLib::UTF8,
+        LuaOptions::default(),
+    )
+    .map_err(|err| anyhow::anyhow!("failed to start lua: {err}"))?;
+    lua.set_memory_limit(MEMORY_LIMIT_BYTES)
+        .map_err(|err| anyhow::anyhow!("failed to limit lua memory: {err}"))?;
+    let executed = AtomicU32::new(0);
+    lua.set_hook(
+        HookTriggers::new().every_nth_instruction(HOOK_EVERY),
+        move |_, _| {
+            if executed.fetch_add(HOOK_EVERY, Ordering::Relaxed) >= MAX_INSTRUCTIONS {
+                return Err(mlua::Error::RuntimeError(format!(
+                    "instruction limit of {MAX_INSTRUCTIONS} exceeded"
+                )));
+            }
+            Ok(())
+        },
+    );
+
+    let run = || -> mlua::Result<Option<Vec<String>>> {
+        // The base library is always loaded; these would read files or undo the limits.
+        let globals = lua.globals();
+        for name in ["dofile", "loadfile", "load", "collectgarbage"] {
+            globals.set(name, mlua::Value::Nil)?;
+        }
+        lua.load(source).set_name("policy").exec()?;
+        let decide: mlua::Function = lua.globals().get("decide")?;
+        let returned: mlua::Value = decide.call(lua.to_value(ctx)?)?;
+        match returned {
+            mlua::Value::Nil => Ok(None),
+            other => Ok(Some(lua.from_value(other)?)),
+        }
+    };
+    run().map_err(|err| anyhow::anyhow!("{err}"))
+}
+
+#[cfg(not(feature = "lua-policy"))]
+fn run_script(_source: &str, _ctx: &Value) -> Result<Option<Vec<String>>> {
+    anyhow::bail!("this build has no Lua support (rebuild with the `lua-policy` feature)")
+}
+
+#[cfg(all(test, feature = "lua-policy"))]
+mod tests {
+    use super::run_script;
+    use serde_json::json;
+
+    #[test]
+    fn script_can_override_keep_or_reject_the_default() {
+        let ctx = json!({ "default": ["archive", "compaction"], "local_hour": 3 });
+        let night_only = r#"
+            function decide(ctx)
+              if ctx.local_hour < 6 then return {"archive"} end
+              return nil
+            end
+        "#;
+        assert_eq!(
+            run_script(night_only, &ctx).expect("run"),
+            Some(vec!["archive".to_string()])
+        );
+        let keep = "function decide(ctx) return nil end";
+        assert_eq!(run_script(keep, &ctx).expect("run"), None);
+        let none = "function decide(ctx) return {} end";
+        assert_eq!(run_script(none, &ctx).expect("run"), Some(Vec::new()));
+    }
+
+    #[test]
+    fn script_is_sandboxed_and_bounded() {
+        let ctx = json!({});
+        let io = "function decide(ctx) return io.open('/etc/passwd') end";
+        assert!(run_script(io, &ctx).is_err());
+        for name in ["dofile", "loadfile", "load", "collectgarbage"] {
+            let script = format!("function decide(ctx) return {name}('/etc/passwd') end");
+            let err = run_script(&script, &ctx).expect_err("base loader removed");
+            assert!(
+                format!("{err:#}")
+                    .contains(&format!("attempt to call a nil value (global '{name}')")),
+                "{name}: {err:#}"
+            );
+        }
+        let spin = "function decide(ctx) while true do end end";
+        let err = run_script(spin, &ctx).expect_err("spin aborted");
+        assert!(format!("{err:#}").contains("instruction limit"));
+    }
+}
@@ -0,0 +1,234 @@
+use crate::moon::archive::read_ledger_records;
+use crate::moon::config::MoonPrivacyConfig;
+use crate::moon::paths::MoonPaths;
+use crate::moon::watcher::load_session_source_map;
+#[cfg(feature = "encryption")]
+use anyhow::Context;
+use anyhow::Result;
+use std::collections::BTreeSet;
+#[cfg(feature = "encryption")]
+use std::fs;
+use std::path::Path;
+
+/// Header of a raw archive sealed by `[privacy] encrypt`.
+pub const ENCRYPTED_ARCHIVE_MAGIC: &[u8] = b"MOONENC1";
+#[cfg(feature = "encryption")]
+const NONCE_LEN: usize = 12;
+
+/// `[privacy]` pattern marking the session that owns `source` (per sessions.json) private.
+pub fn private_pattern_for_source(
+    paths: &MoonPaths,
+    privacy: &MoonPrivacyConfig,
+    source: &Path,
+) -> Result<Option<String>> {
+    if privacy
+        .channels
+        .iter()
+        .all(|pattern| pattern.trim().is_empty())
+    {
+        return Ok(None);
+    }
+    let source_map = load_session_source_map(&paths.openclaw_sessions_dir)?;
+    Ok(source_map
+        .iter()
+        .filter(|(_, path)| path.as_path() == source)
+        .find_map(|(key, _)| privacy.matching_pattern(key))
+        .map(ToOwned::to_owned))
+}
+
+/// Archive paths the ledger marks private; recall drops these regardless of what qmd returns.
+pub fn private_archive_paths(paths: &MoonPaths) -> BTreeSet<String> {
+    read_ledger_records(paths)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|record| record.private)
+        .map(|record| record.archive_path)
+        .collect()
+}
+
+pub fn is_encrypted(raw: &[u8]) -> bool {
+    raw.starts_with(ENCRYPTED_ARCHIVE_MAGIC)
+}
+
+/// The 256-bit key in `MOON_PRIVACY_KEY`, given as 64 hex digits or 32 base64-encoded bytes
+/// (e.g. `openssl rand -hex 32`). Passphrases are refused: a bare hash of one would make every
+/// sealed archive as weak as the passphrase, with nothing to slow a guess down.
+#[cfg(feature = "encryption")]
+fn privacy_key() -> Result<[u8; 32]> {
+    let secret = std::env::var("MOON_PRIVACY_KEY").unwrap_or_default();
+    if secret.trim().is_empty() {
+        anyhow::bail!("MOON_PRIVACY_KEY is not set; refusing to store a private archive unsealed");
+    }
+    parse_privacy_key(&secret)
+}
+
+/// Decodes a `MOON_PRIVACY_KEY` value; see `privacy_key`.
+pub fn parse_privacy_key(raw: &str) -> Result<[u8; 32]> {
+    let raw = raw.trim();
+    let bytes = if raw.len() == 64 && raw.bytes().all(|b| b.is_ascii_hexdigit()) {
+        decode_hex(raw)
+    } else {
+        decode_base64(raw)
+    };
+    bytes
+        .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "MOON_PRIVACY_KEY must be a 32-byte key as 64 hex digits or base64 (e.g. `openssl rand -hex 32`), not a passphrase"
+            )
+        })
+}
+
+fn decode_hex(raw: &str) -> Option<Vec<u8>> {
+    raw.as_bytes()
+        .chunks(2)
+        .map(|pair| u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok())
+        .collect()
+}
+
+/// Standard or URL-safe base64, padding optional.
+fn decode_base64(raw: &str) -> Option<Vec<u8>> {
+    let digits = raw
+        .trim_end_matches('=')
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' => Some(b - b'A'),
+            b'a'..=b'z' => Some(b - b'a' + 26),
+            b'0'..=b'9' => Some(b - b'0' + 52),
+            b'+' | b'-' => Some(62),
+            b'/' | b'_' => Some(63),
+            _ => None,
+        })
+        .collect::<Option<Vec<u8>>>()?;
+    if digits.len() % 4 == 1 {
+        return None;
+    }
+    let mut out = Vec::with_capacity(digits.len() * 3 / 4);
+    for group in digits.chunks(4) {
+        let mut acc = 0u32;
+        for (idx, digit) in group.iter().enumerate() {
+            acc |= u32::from(*digit) << (18 - 6 * idx);
+        }
+        let bytes = acc.to_be_bytes();
+        out.extend_from_slice(&bytes[1..group.len()]);
+    }
+    Some(out)
+}
+
+#[cfg(feature = "encryption")]
+fn seal(plain: &[u8], key: &[u8; 32]) -> Result<Vec<u8>> {
+    use chacha20poly1305::ChaCha20Poly1305;
+    use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let sealed = ChaCha20Poly1305::new(key.into())
+        .encrypt(&nonce, plain)
+        .map_err(|_| anyhow::anyhow!("encryption failed"))?;
+    let mut out = Vec::with_capacity(ENCRYPTED_ARCHIVE_MAGIC.len() + NONCE_LEN + sealed.len());
+    out.extend_from_slice(ENCRYPTED_ARCHIVE_MAGIC);
+    out.extend_from_slice(&nonce);
+    out.extend_from_slice(&sealed);
+    Ok(out)
+}
+
+#[cfg(feature = "encryption")]
+fn open(raw: &[u8], key: &[u8; 32]) -> Result<Vec<u8>> {
+    use chacha20poly1305::aead::{Aead, KeyInit};
+    use chacha20poly1305::{ChaCha20Poly1305, Nonce};
+
+    let Some(body) = raw.strip_prefix(ENCRYPTED_ARCHIVE_MAGIC) else {
+        anyhow::bail!("not an encrypted MOON archive");
+    };
+    if body.len() < NONCE_LEN {
+        anyhow::bail!("encrypted archive is truncated");
+    }
+    let (nonce, sealed) = body.split_at(NONCE_LEN);
+    ChaCha20Poly1305::new(key.into())
+        .decrypt(Nonce::from_slice(nonce), sealed)
+        .map_err(|_| {
+            anyhow::anyhow!("failed to decrypt archive: wrong MOON_PRIVACY_KEY or corrupt file")
+        })
+}
+
+/// Replaces the file at `path` with its ChaCha20-Poly1305 sealed form. The sealed copy is
+/// written and fsynced beside it first, so a crash leaves either the plaintext or the sealed
+/// archive, never a torn one.
+#[cfg(feature = "encryption")]
+pub fn encrypt_file_in_place(path: &Path) -> Result<()> {
+    use std::io::Write;
+
+    let key = privacy_key()?;
+    let plain = fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+    if is_encrypted(&plain) {
+        return Ok(());
+    }
+    let sealed =
+        seal(&plain, &key).with_context(|| format!("failed to encrypt {}", path.display()))?;
+    let parent = path
+        .parent()
+        .with_context(|| format!("{} has no parent directory", path.display()))?;
+    let mut temp = tempfile::NamedTempFile::new_in(parent)
+        .with_context(|| format!("failed to create a temp file in {}", parent.display()))?;
+    temp.write_all(&sealed)
+        .and_then(|()| temp.as_file().sync_all())
+        .with_context(|| format!("failed to write sealed copy of {}", path.display()))?;
+    temp.persist(path)
+        .map_err(|e| anyhow::anyhow!("failed to replace {}: {}", path.display(), e.error))?;
+    Ok(())
+}
+
+#[cfg(not(feature = "encryption"))]
+pub fn encrypt_file_in_place(path: &Path) -> Result<()> {
+    anyhow::bail!(
+        "cannot encrypt {}: this build has no encryption support (rebuild with the `encryption` feature)",
+        path.display()
+    )
+}
+
+/// Plaintext of an archive written by `encrypt_file_in_place`.
+#[cfg(feature = "encryption")]
+pub fn decrypt_bytes(raw: &[u8]) -> Result<Vec<u8>> {
+    open(raw, &privacy_key()?)
+}
+
+#[cfg(not(feature = "encryption"))]
+pub fn decrypt_bytes(_raw: &[u8]) -> Result<Vec<u8>> {
+    anyhow::bail!("this build has no encryption support (rebuild with the `encryption` feature)")
+}
+
+#[cfg(all(test, feature = "encryption"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sealed_archives_round_trip_and_reject_wrong_keys() {
+        let key = [7u8; 32];
+        let plain = b"{\"message\":\"secret\"}\n";
+        let sealed = seal(plain, &key).expect("seal");
+        assert!(is_encrypted(&sealed));
+        assert!(!sealed.windows(6).any(|w| w == b"secret"));
+        assert_eq!(open(&sealed, &key).expect("open"), plain);
+        assert!(open(&sealed, &[8u8; 32]).is_err());
+        assert!(open(plain, &key).is_err());
+    }
+
+    #[test]
+    fn privacy_keys_must_be_32_bytes_of_hex_or_base64() {
+        let hex = "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f";
+        let expected: [u8; 32] = std::array::from_fn(|idx| idx as u8);
+        assert_eq!(parse_privacy_key(hex).expect("hex"), expected);
+        assert_eq!(
+            parse_privacy_key(&hex.to_ascii_uppercase()).expect("upper hex"),
+            expected
+        );
+        let base64 = "AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8=";
+        assert_eq!(parse_privacy_key(base64).expect("base64"), expected);
+        assert_eq!(
+            parse_privacy_key(base64.trim_end_matches('=')).expect("unpadded base64"),
+            expected
+        );
+        assert!(parse_privacy_key("correct horse battery staple").is_err());
+        assert!(parse_privacy_key(&hex[..62]).is_err());
+        assert!(parse_privacy_key("AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwd").is_err());
+    }
+}
@@ -2,7 +2,39 @@ use crate::moon::paths::MoonPaths;
 use crate::openclaw::config::{MIN_AGENT_CONTEXT_TOKENS, read_config_value, write_config_atomic};
 use crate::openclaw::paths::resolve_paths;
 use anyhow::Result;
+use serde::Serialize;
 use serde_json::Value;
+use std::path::PathBuf;
+
+/// One openclaw config value the profile changes; `old` is `null` when the key was unset.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ConfigChange {
+    pub path: String,
+    pub old: Value,
+    pub new: Value,
+}
+
+/// What `apply_aggressive_profile` changed, or would change without `apply`.
+#[derive(Debug, Clone, Serialize)]
+pub struct PruneProfileOutcome {
+    pub config_path: PathBuf,
+    pub changes: Vec<ConfigChange>,
+    pub applied: bool,
+    /// Why changes were planned but not written, when they were not.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub skipped: Option<String>,
+}
+
+impl PruneProfileOutcome {
+    /// `{"config_path", "changes": [{"path", "old", "new"}]}` on one line.
+    pub fn diff_json(&self) -> String {
+        serde_json::json!({
+            "config_path": self.config_path.display().to_string(),
+            "changes": self.changes,
+        })
+        .to_string()
+    }
+}
 
 fn set_path(root: &mut Value, path: &[&str], value: Value) {
     if path.is_empty() {
@@ -35,49 +67,65 @@ fn path_u64(root: &Value, path: &[&str]) -> Option<u64> {
     cursor.as_u64()
 }
 
-fn set_path_u64_floor(root: &mut Value, path: &[&str], floor: u64) -> bool {
+fn path_value(root: &Value, path: &[&str]) -> Value {
+    path.iter()
+        .try_fold(root, |cursor, key| cursor.get(*key))
+        .cloned()
+        .unwrap_or(Value::Null)
+}
+
+fn set_path_u64_floor(
+    root: &mut Value,
+    path: &[&str],
+    floor: u64,
+    changes: &mut Vec<ConfigChange>,
+) {
     let current = path_u64(root, path);
     if current.is_some_and(|v| v >= floor) {
-        return false;
+        return;
     }
+    changes.push(ConfigChange {
+        path: path.join("."),
+        old: path_value(root, path),
+        new: Value::from(floor),
+    });
     set_path(root, path, Value::from(floor));
-    true
 }
 
-pub fn apply_aggressive_profile(_paths: &MoonPaths, plugin_id: &str) -> Result<String> {
-    let enabled = std::env::var("MOON_ENABLE_COMPACTION_WRITE")
-        .or_else(|_| std::env::var("MOON_ENABLE_PRUNE_WRITE"))
-        .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
-        .unwrap_or(false);
-    if !enabled {
-        return Ok(
-            "skipped (set MOON_ENABLE_COMPACTION_WRITE=true to enable writes)".to_string(),
-        );
-    }
-
+/// Raises the openclaw safety floors for `plugin_id`. Without `apply` nothing is written and
+/// the outcome only lists the planned changes; writes also need
+/// `MOON_ENABLE_COMPACTION_WRITE=true`.
+pub fn apply_aggressive_profile(
+    _paths: &MoonPaths,
+    plugin_id: &str,
+    apply: bool,
+) -> Result<PruneProfileOutcome> {
     let oc_paths = resolve_paths()?;
     let mut cfg = read_config_value(&oc_paths)?;
-    let mut changed = false;
+    let mut changes = Vec::new();
 
     if let Some(reserve_floor) = path_u64(&cfg, &["agents", "defaults", "contextTokens"]) {
-        changed |= set_path_u64_floor(
+        set_path_u64_floor(
             &mut cfg,
             &["agents", "defaults", "contextTokens"],
             reserve_floor.max(MIN_AGENT_CONTEXT_TOKENS),
+            &mut changes,
         );
     }
 
-    changed |= set_path_u64_floor(
+    set_path_u64_floor(
         &mut cfg,
         &["plugins", "entries", plugin_id, "config", "maxTokens"],
         12_000,
+        &mut changes,
     );
-    changed |= set_path_u64_floor(
+    set_path_u64_floor(
         &mut cfg,
         &["plugins", "entries", plugin_id, "config", "maxChars"],
         60_000,
+        &mut changes,
     );
-    changed |= set_path_u64_floor(
+    set_path_u64_floor(
         &mut cfg,
         &[
             "plugins",
@@ -87,14 +135,33 @@ pub fn apply_aggressive_profile(_paths: &MoonPaths, plugin_id: &str) -> Result<S
             "maxRetainedBytes",
         ],
         250_000,
+        &mut changes,
     );
 
-    if !changed {
-        return Ok(format!(
-            "unchanged (safety floors already satisfied): {}",
-            oc_paths.config_path.display()
-        ));
+    let mut outcome = PruneProfileOutcome {
+        config_path: oc_paths.config_path.clone(),
+        changes,
+        applied: false,
+        skipped: None,
+    };
+    if outcome.changes.is_empty() {
+        return Ok(outcome);
+    }
+    if !apply {
+        outcome.skipped = Some("dry-run (pass --apply to write)".to_string());
+        return Ok(outcome);
+    }
+    let enabled = std::env::var("MOON_ENABLE_COMPACTION_WRITE")
+        .or_else(|_| std::env::var("MOON_ENABLE_PRUNE_WRITE"))
+        .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+        .unwrap_or(false);
+    if !enabled {
+        outcome.skipped =
+            Some("set MOON_ENABLE_COMPACTION_WRITE=true to enable writes".to_string());
+        return Ok(outcome);
     }
 
-    write_config_atomic(&oc_paths, &cfg)
+    write_config_atomic(&oc_paths, &cfg)?;
+    outcome.applied = true;
+    Ok(outcome)
 }
@@ -1,9 +1,15 @@
+use crate::moon::config::MoonCollectionsConfig;
 use anyhow::{Context, Result};
 use serde_json::Value;
+use std::collections::BTreeSet;
 use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::time::Duration;
 
-const ARCHIVE_COLLECTION_MASK: &str = "mlib/**/*.md";
+const MEMORY_COLLECTION_MASK: &str = "*.md";
+
+/// Collection over the daily memory logs in `memory_dir`, kept in sync by the watcher.
+pub const MEMORY_COLLECTION: &str = "memory";
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CollectionSyncResult {
@@ -54,11 +60,65 @@ fn is_existing_collection_error(stdout: &str, stderr: &str) -> bool {
     combined.contains("collection") && combined.contains("already exists")
 }
 
-fn collection_pattern(qmd_bin: &Path, collection_name: &str) -> Result<Option<String>> {
-    let mut cmd = Command::new(qmd_bin);
+fn is_missing_collection_error(stdout: &str, stderr: &str) -> bool {
+    let combined = format!("{stdout}\n{stderr}").to_ascii_lowercase();
+    combined.contains("collection")
+        && (combined.contains("not found") || combined.contains("does not exist"))
+}
+
+/// One collection from `qmd collection list`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CollectionInfo {
+    pub name: String,
+    pub pattern: Option<String>,
+    /// Indexed document count (`Files:` / `Documents:`), when qmd reports one.
+    pub documents: Option<u64>,
+}
+
+fn parse_collection_list(stdout: &str) -> Vec<CollectionInfo> {
+    let mut out = Vec::new();
+    let mut current: Option<CollectionInfo> = None;
+    for line in stdout.lines() {
+        let trimmed = line.trim();
+        if let Some((name, _)) = trimmed.split_once(" (qmd://") {
+            out.extend(current.take());
+            current = Some(CollectionInfo {
+                name: name.trim().to_string(),
+                pattern: None,
+                documents: None,
+            });
+            continue;
+        }
+        let Some(info) = current.as_mut() else {
+            continue;
+        };
+        if trimmed.is_empty() {
+            out.extend(current.take());
+        } else if let Some(pattern) = trimmed.strip_prefix("Pattern:") {
+            let pattern = pattern.trim();
+            if info.pattern.is_none() && !pattern.is_empty() {
+                info.pattern = Some(pattern.to_string());
+            }
+        } else if let Some(count) = trimmed
+            .strip_prefix("Files:")
+            .or_else(|| trimmed.strip_prefix("Documents:"))
+        {
+            info.documents = count
+                .split_whitespace()
+                .next()
+                .and_then(|v| v.replace(',', "").parse().ok());
+        }
+    }
+    out.extend(current);
+    out
+}
+
+pub fn collection_list(qmd_bin: &Path) -> Result<Vec<CollectionInfo>> {
+    let bin = resolve_qmd_bin(qmd_bin)?;
+    let mut cmd = Command::new(&bin);
     cmd.arg("collection").arg("list");
     let output = crate::moon::util::run_command_with_optional_timeout(&mut cmd, Some(30))
-        .with_context(|| format!("failed to run `{}`", qmd_bin.display()))?;
+        .with_context(|| format!("failed to run `{}`", bin.display()))?;
     if !output.status.success() {
         anyhow::bail!(
             "qmd collection list failed\nstdout: {}\nstderr: {}",
@@ -66,46 +126,79 @@ fn collection_pattern(qmd_bin: &Path, collection_name: &str) -> Result<Option<St
             String::from_utf8_lossy(&output.stderr)
         );
     }
+    Ok(parse_collection_list(&String::from_utf8_lossy(
+        &output.stdout,
+    )))
+}
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let mut in_collection_block = false;
-    for line in stdout.lines() {
-        let trimmed = line.trim();
-        if trimmed.starts_with(&format!("{collection_name} (qmd://")) {
-            in_collection_block = true;
-            continue;
-        }
-        if in_collection_block {
-            if trimmed.is_empty() {
-                break;
-            }
-            if let Some(pattern) = trimmed.strip_prefix("Pattern:") {
-                let normalized = pattern.trim();
-                if !normalized.is_empty() {
-                    return Ok(Some(normalized.to_string()));
-                }
-                break;
-            }
-        }
+fn collection_pattern(qmd_bin: &Path, collection_name: &str) -> Result<Option<String>> {
+    Ok(collection_list(qmd_bin)?
+        .into_iter()
+        .find(|info| info.name == collection_name)
+        .and_then(|info| info.pattern))
+}
+
+/// Mask an archive collection is registered with: the top level of `mlib/` for the default
+/// collection, `mlib/<subdir>/` for the others (see [`MoonCollectionsConfig::subdir`]).
+pub fn archive_collection_mask(subdir: Option<&str>) -> String {
+    match subdir {
+        Some(subdir) => format!("mlib/{subdir}/*.md"),
+        None => "mlib/*.md".to_string(),
     }
+}
 
-    Ok(None)
+/// First line of `qmd --version`.
+pub fn version(qmd_bin: &Path) -> Result<String> {
+    let bin = resolve_qmd_bin(qmd_bin)?;
+    let mut cmd = Command::new(&bin);
+    cmd.arg("--version");
+    let output = crate::moon::util::run_command_with_optional_timeout(&mut cmd, Some(10))
+        .with_context(|| format!("failed to run `{}`", bin.display()))?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "qmd --version exited with {:?}: {}",
+            output.status.code(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(stdout
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .unwrap_or("unknown")
+        .to_string())
 }
 
 pub fn collection_add_or_update(
     qmd_bin: &Path,
     archives_dir: &Path,
     collection_name: &str,
+    subdir: Option<&str>,
+) -> Result<CollectionSyncResult> {
+    collection_add_or_update_with_mask(
+        qmd_bin,
+        archives_dir,
+        collection_name,
+        &archive_collection_mask(subdir),
+    )
+}
+
+fn collection_add_or_update_with_mask(
+    qmd_bin: &Path,
+    root_dir: &Path,
+    collection_name: &str,
+    mask: &str,
 ) -> Result<CollectionSyncResult> {
     let bin = resolve_qmd_bin(qmd_bin)?;
     let mut cmd = Command::new(&bin);
     cmd.arg("collection")
         .arg("add")
-        .arg(archives_dir)
+        .arg(root_dir)
         .arg("--name")
         .arg(collection_name)
         .arg("--mask")
-        .arg(ARCHIVE_COLLECTION_MASK);
+        .arg(mask);
     let add_output = crate::moon::util::run_command_with_optional_timeout(&mut cmd, Some(30))
         .with_context(|| format!("failed to run `{}`", bin.display()))?;
 
@@ -119,7 +212,7 @@ pub fn collection_add_or_update(
         let existing_pattern = collection_pattern(&bin, collection_name).ok().flatten();
         if existing_pattern
             .as_deref()
-            .is_some_and(|pattern| pattern != ARCHIVE_COLLECTION_MASK)
+            .is_some_and(|pattern| pattern != mask)
         {
             let mut cmd = Command::new(&bin);
             cmd.arg("collection").arg("remove").arg(collection_name);
@@ -138,11 +231,11 @@ pub fn collection_add_or_update(
             let mut cmd = Command::new(&bin);
             cmd.arg("collection")
                 .arg("add")
-                .arg(archives_dir)
+                .arg(root_dir)
                 .arg("--name")
                 .arg(collection_name)
                 .arg("--mask")
-                .arg(ARCHIVE_COLLECTION_MASK);
+                .arg(mask);
             let recreate_output =
                 crate::moon::util::run_command_with_optional_timeout(&mut cmd, Some(30))
                     .with_context(|| format!("failed to run `{}`", bin.display()))?;
@@ -182,14 +275,101 @@ pub fn collection_add_or_update(
     )
 }
 
-pub fn search(qmd_bin: &Path, collection_name: &str, query: &str) -> Result<String> {
+/// Brings every collection in `collections` up to date with at most one `qmd update`.
+///
+/// Collections qmd does not list yet, or lists under another mask, are (re)added, which indexes
+/// them; the rest share the single update. [`MEMORY_COLLECTION`] is rooted at `memory_dir`,
+/// everything else at `archives_dir` under its own [`archive_collection_mask`].
+pub fn sync_collections(
+    qmd_bin: &Path,
+    archives_dir: &Path,
+    memory_dir: &Path,
+    collections: &BTreeSet<String>,
+    collections_cfg: &MoonCollectionsConfig,
+) -> Result<()> {
+    let listed = collection_list(qmd_bin)?;
+    let mut needs_update = false;
+    let mut updated_all = false;
+    for name in collections {
+        let (root, mask) = if name == MEMORY_COLLECTION {
+            (memory_dir, MEMORY_COLLECTION_MASK.to_string())
+        } else {
+            (
+                archives_dir,
+                archive_collection_mask(collections_cfg.subdir(name)),
+            )
+        };
+        if listed
+            .iter()
+            .any(|info| info.name == *name && info.pattern.as_deref() == Some(mask.as_str()))
+        {
+            needs_update = true;
+            continue;
+        }
+        // An add conflict falls back to `qmd update`, which refreshes every collection.
+        updated_all |= collection_add_or_update_with_mask(qmd_bin, root, name, &mask)?
+            == CollectionSyncResult::Updated;
+    }
+    if needs_update && !updated_all {
+        update(qmd_bin)?;
+    }
+    Ok(())
+}
+
+/// Removes `collection_name` from qmd; returns `false` when it did not exist.
+pub fn collection_remove(qmd_bin: &Path, collection_name: &str) -> Result<bool> {
+    let bin = resolve_qmd_bin(qmd_bin)?;
+    let mut cmd = Command::new(&bin);
+    cmd.arg("collection").arg("remove").arg(collection_name);
+    let output = crate::moon::util::run_command_with_optional_timeout(&mut cmd, Some(30))
+        .with_context(|| format!("failed to run `{}`", bin.display()))?;
+    if output.status.success() {
+        return Ok(true);
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+    if is_missing_collection_error(&stdout, &stderr) {
+        return Ok(false);
+    }
+    anyhow::bail!(
+        "qmd collection remove failed for {}\nstdout: {}\nstderr: {}",
+        collection_name,
+        stdout,
+        stderr
+    )
+}
+
+/// Human-readable form of the command [`collection_add_or_update`] would run.
+pub fn describe_collection_sync(
+    qmd_bin: &Path,
+    archives_dir: &Path,
+    collection_name: &str,
+    subdir: Option<&str>,
+) -> String {
+    format!(
+        "{} collection add {} --name {} --mask {} (update when the collection already exists)",
+        qmd_bin.display(),
+        archives_dir.display(),
+        collection_name,
+        archive_collection_mask(subdir)
+    )
+}
+
+/// Runs `qmd search`, killing qmd once `timeout` has passed.
+pub fn search(
+    qmd_bin: &Path,
+    collection_name: &str,
+    query: &str,
+    timeout: Duration,
+) -> Result<String> {
     let bin = resolve_qmd_bin(qmd_bin)?;
     let mut cmd = Command::new(&bin);
     cmd.arg("search")
         .arg(collection_name)
         .arg(query)
         .arg("--json");
-    let output = crate::moon::util::run_command_with_optional_timeout(&mut cmd, Some(30))
+    let output = crate::moon::util::run_command_with_deadline(&mut cmd, timeout)
         .with_context(|| format!("failed to run `{}`", bin.display()))?;
 
     if output.status.success() {
@@ -1,15 +1,25 @@
-use crate::moon::archive::projection_path_for_archive;
+use crate::moon::archive::{
+    ProjectionLineAnchor, duplicate_canonical_archive, newest_archive_version,
+    parse_projection_line_anchors, projection_path_for_archive, read_ledger_records,
+};
 use crate::moon::channel_archive_map;
+use crate::moon::config::{MoonToolPriorityConfig, load_config, resolve_residential_tz};
+use crate::moon::distill::{RawArchiveEntry, read_raw_archive_entries};
+use crate::moon::graph;
+use crate::moon::memory::bullet_terms;
 use crate::moon::paths::MoonPaths;
+use crate::moon::privacy;
 use crate::moon::qmd;
-use crate::moon::util::now_epoch_secs;
-use anyhow::Result;
+use crate::moon::tokens;
+use crate::moon::util::{now_epoch_secs, read_only_mode};
+use anyhow::{Result, anyhow};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use serde_json::json;
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RecallMatch {
@@ -19,27 +29,214 @@ pub struct RecallMatch {
     pub metadata: Value,
 }
 
+/// Which qmd collections a recall searches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RecallScope {
+    /// Archive projections in the routed collection.
+    #[default]
+    Archives,
+    /// Distilled daily memory in the `memory` collection.
+    Memory,
+    All,
+}
+
+impl RecallScope {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "" | "archives" | "archive" => Some(Self::Archives),
+            "memory" => Some(Self::Memory),
+            "all" => Some(Self::All),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Archives => "archives",
+            Self::Memory => "memory",
+            Self::All => "all",
+        }
+    }
+
+    fn includes_archives(self) -> bool {
+        matches!(self, Self::Archives | Self::All)
+    }
+
+    fn includes_memory(self) -> bool {
+        matches!(self, Self::Memory | Self::All)
+    }
+}
+
+/// `true` for matches found in the `memory` collection rather than an archive projection.
+pub fn is_memory_match(m: &RecallMatch) -> bool {
+    m.metadata.get("scope").and_then(Value::as_str) == Some(RecallScope::Memory.as_str())
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RecallResult {
     pub query: String,
     pub matches: Vec<RecallMatch>,
     pub generated_at_epoch_secs: u64,
+    /// The search missed its deadline, so `matches` holds only deterministic and cached hits.
+    #[serde(default)]
+    pub degraded: bool,
+}
+
+/// Raw-archive slice around a recall match, for jumping from a snippet to full context.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecallHydration {
+    pub archive_path: String,
+    /// Raw line the match was located at; `anchor_method` says how it was found.
+    pub anchor_line: usize,
+    pub anchor_method: String,
+    pub entries: Vec<RawArchiveEntry>,
+}
+
+fn shared_term_count(wanted: &BTreeMap<String, usize>, text: &str) -> usize {
+    let terms = bullet_terms(text);
+    wanted.keys().filter(|t| terms.contains_key(*t)).count()
+}
+
+/// Resolves a snippet to the recorded raw-archive anchor of the projection timeline row or
+/// search capsule sharing the most terms with it.
+pub fn projection_anchor_for_snippet(
+    archive_path: &str,
+    snippet: &str,
+) -> Option<ProjectionLineAnchor> {
+    let wanted = bullet_terms(snippet);
+    if wanted.is_empty() {
+        return None;
+    }
+    let markdown = fs::read_to_string(projection_path_for_archive(archive_path)).ok()?;
+    let anchors = parse_projection_line_anchors(&markdown)?;
+
+    let mut section = "";
+    let mut capsule_idx = 0usize;
+    let mut best: Option<(ProjectionLineAnchor, usize)> = None;
+    for line in markdown.lines() {
+        let trimmed = line.trim();
+        if let Some(heading) = trimmed.strip_prefix("## ") {
+            section = heading.trim();
+            continue;
+        }
+        let anchor = match section {
+            "Timeline" => trimmed
+                .strip_prefix('|')
+                .and_then(|rest| rest.split('|').next())
+                .and_then(|cell| cell.trim().parse::<usize>().ok())
+                .and_then(|row| anchors.timeline.get(row.checked_sub(1)?)),
+            "Search Capsules"
+                if trimmed.starts_with("- [") && trimmed != "- [search capsules truncated]" =>
+            {
+                capsule_idx += 1;
+                anchors.capsules.get(capsule_idx - 1)
+            }
+            _ => None,
+        };
+        let Some(anchor) = anchor.filter(|a| a.line().is_some()) else {
+            continue;
+        };
+        let shared = shared_term_count(&wanted, trimmed);
+        if shared > 0 && best.is_none_or(|(_, top)| shared > top) {
+            best = Some((*anchor, shared));
+        }
+    }
+    best.map(|(anchor, _)| anchor)
+}
+
+fn locate_snippet_anchor(entries: &[RawArchiveEntry], snippet: &str) -> Option<usize> {
+    let wanted = bullet_terms(snippet);
+    if wanted.is_empty() {
+        return None;
+    }
+    let mut best: Option<(usize, usize)> = None;
+    for (idx, entry) in entries.iter().enumerate() {
+        let shared = shared_term_count(&wanted, &entry.text);
+        if shared > 0 && best.is_none_or(|(_, top)| shared > top) {
+            best = Some((idx, shared));
+        }
+    }
+    best.map(|(idx, _)| idx)
+}
+
+/// Loads the raw archive behind `m` and keeps `context` entries on each side of the event that
+/// best matches the snippet (the first event when nothing matches).
+pub fn hydrate_match(m: &RecallMatch, context: usize) -> Result<RecallHydration> {
+    let archive_path = m.archive_path.trim();
+    if is_memory_match(m) {
+        return Err(anyhow!(
+            "match is a distilled memory summary, not a raw archive: {archive_path}"
+        ));
+    }
+    if let Some(retired) = m.metadata.get("retiredArchive").and_then(Value::as_str) {
+        return Err(anyhow!(
+            "raw archive was removed by retention: {retired} (mapped to {archive_path})"
+        ));
+    }
+    if archive_path.is_empty() || archive_path.starts_with("qmd://") {
+        return Err(anyhow!("match has no local raw archive to open"));
+    }
+    if !Path::new(archive_path).exists() {
+        return Err(anyhow!("raw archive not found: {archive_path}"));
+    }
+    let entries = read_raw_archive_entries(archive_path)?;
+    if entries.is_empty() {
+        return Err(anyhow!("raw archive has no message events: {archive_path}"));
+    }
+
+    let recorded = projection_anchor_for_snippet(archive_path, &m.snippet)
+        .and_then(|anchor| anchor.line())
+        .and_then(|line| entries.iter().position(|entry| entry.line >= line));
+    let (anchor_idx, anchor_method) = match recorded {
+        Some(idx) => (idx, "line-anchor"),
+        None => match locate_snippet_anchor(&entries, &m.snippet) {
+            Some(idx) => (idx, "snippet-terms"),
+            None => (0, "archive-start"),
+        },
+    };
+    let start = anchor_idx.saturating_sub(context);
+    let end = anchor_idx
+        .saturating_add(context)
+        .saturating_add(1)
+        .min(entries.len());
+    Ok(RecallHydration {
+        archive_path: archive_path.to_string(),
+        anchor_line: entries[anchor_idx].line,
+        anchor_method: anchor_method.to_string(),
+        entries: entries[start..end].to_vec(),
+    })
 }
 
-fn boost_score_for_priority(snippet: &str, base_score: f64) -> f64 {
+fn boost_score_for_priority(
+    snippet: &str,
+    base_score: f64,
+    tool_priority: &MoonToolPriorityConfig,
+) -> f64 {
     let lower = snippet.to_ascii_lowercase();
-    if lower.contains("write_to_file")
-        || lower.contains("exec")
-        || lower.contains("edit")
-        || lower.contains("gateway")
-    {
-        // High priority side-effects
-        base_score * 1.30
-    } else if lower.contains("read_file") || lower.contains("web_search") || lower.contains("ls") {
-        // Normal priority side-effects
-        base_score * 1.05
-    } else {
-        base_score
+    // Strongest matching side-effect rule wins; snippets without tool mentions keep their score.
+    let boost = tool_priority
+        .rules
+        .iter()
+        .filter(|rule| lower.contains(&rule.tool.to_ascii_lowercase()))
+        .map(|rule| tool_priority.boost_for_rule(rule))
+        .reduce(f64::max);
+    base_score * boost.unwrap_or(1.0)
+}
+
+const GRAPH_RELATED_BOOST: f64 = 1.15;
+
+fn apply_graph_boost(matches: &mut [RecallMatch], related_stems: &BTreeSet<String>) {
+    if related_stems.is_empty() {
+        return;
+    }
+    for item in matches.iter_mut() {
+        let related = Path::new(&item.archive_path)
+            .file_stem()
+            .and_then(|v| v.to_str())
+            .is_some_and(|stem| related_stems.contains(stem));
+        if related {
+            item.score *= GRAPH_RELATED_BOOST;
+        }
     }
 }
 
@@ -47,16 +244,26 @@ fn archive_path_from_projection_path(path: &Path) -> PathBuf {
     let Some(file_name) = path.file_name() else {
         return path.with_extension("jsonl");
     };
-    if path
-        .parent()
-        .and_then(|parent| parent.file_name())
-        .and_then(|name| name.to_str())
-        .is_some_and(|name| name == "mlib" || name == "lib")
-        && let Some(archives_root) = path.parent().and_then(Path::parent)
-    {
-        let mut archive_name = PathBuf::from(file_name);
-        archive_name.set_extension("jsonl");
-        return archives_root.join("raw").join(archive_name);
+    let is_mlib = |dir: &Path| {
+        dir.file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name == "mlib" || name == "lib")
+    };
+    let mut archive_name = PathBuf::from(file_name);
+    archive_name.set_extension("jsonl");
+    if let Some(parent) = path.parent() {
+        if is_mlib(parent)
+            && let Some(archives_root) = parent.parent()
+        {
+            return archives_root.join("raw").join(archive_name);
+        }
+        // A non-default collection's `mlib/<collection>/x.md`.
+        if let (Some(subdir), Some(mlib_dir)) = (parent.file_name(), parent.parent())
+            && is_mlib(mlib_dir)
+            && let Some(archives_root) = mlib_dir.parent()
+        {
+            return archives_root.join("raw").join(subdir).join(archive_name);
+        }
     }
     path.with_extension("jsonl")
 }
@@ -107,7 +314,67 @@ fn resolve_archive_path(paths: &MoonPaths, item: &Value) -> String {
     String::new()
 }
 
-fn parse_matches(paths: &MoonPaths, raw: &str) -> Vec<RecallMatch> {
+/// Daily memory file behind a `memory` collection search result.
+fn resolve_memory_path(paths: &MoonPaths, item: &Value) -> String {
+    let candidate = ["path", "source", "file"]
+        .iter()
+        .find_map(|key| item.get(*key).and_then(Value::as_str))
+        .unwrap_or("")
+        .trim();
+    if let Some(uri_body) = candidate.strip_prefix("qmd://")
+        && let Some((_collection, relative_path)) = uri_body.split_once('/')
+    {
+        return paths.memory_dir.join(relative_path).display().to_string();
+    }
+    candidate.to_string()
+}
+
+/// Matches from the `memory` collection, tagged `scope=memory` in their metadata.
+fn parse_memory_matches(paths: &MoonPaths, raw: &str) -> Vec<RecallMatch> {
+    let Ok(v) = serde_json::from_str::<Value>(raw) else {
+        return Vec::new();
+    };
+    let items = v
+        .as_array()
+        .cloned()
+        .or_else(|| v.get("results").and_then(Value::as_array).cloned())
+        .unwrap_or_default();
+
+    items
+        .into_iter()
+        .map(|mut item| {
+            let snippet = item
+                .get("snippet")
+                .and_then(Value::as_str)
+                .or_else(|| item.get("text").and_then(Value::as_str))
+                .unwrap_or("")
+                .to_string();
+            let archive_path = resolve_memory_path(paths, &item);
+            let score = item
+                .get("score")
+                .and_then(Value::as_f64)
+                .unwrap_or_else(|| (snippet.len() as f64) / 1000.0);
+            if let Value::Object(meta) = &mut item {
+                meta.insert(
+                    "scope".to_string(),
+                    Value::String(RecallScope::Memory.as_str().to_string()),
+                );
+            }
+            RecallMatch {
+                archive_path,
+                snippet,
+                score,
+                metadata: item,
+            }
+        })
+        .collect()
+}
+
+fn parse_matches(
+    paths: &MoonPaths,
+    raw: &str,
+    tool_priority: &MoonToolPriorityConfig,
+) -> Vec<RecallMatch> {
     let mut out = Vec::new();
     let parsed = serde_json::from_str::<Value>(raw);
     let Ok(v) = parsed else {
@@ -133,7 +400,7 @@ fn parse_matches(paths: &MoonPaths, raw: &str) -> Vec<RecallMatch> {
             .and_then(Value::as_f64)
             .unwrap_or_else(|| (snippet.len() as f64) / 1000.0);
 
-        let score = boost_score_for_priority(&snippet, base_score);
+        let score = boost_score_for_priority(&snippet, base_score, tool_priority);
 
         out.push(RecallMatch {
             archive_path,
@@ -178,6 +445,8 @@ fn snippet_from_archive(path: &str) -> String {
                 || trimmed.starts_with("time_range_local:")
                 || trimmed.starts_with("local_timezone:")
                 || trimmed.starts_with("message_count:")
+                || trimmed.starts_with("projection_sample_stride:")
+                || trimmed.starts_with("line_anchors:")
                 || trimmed.starts_with("tool_calls:")
                 || trimmed.starts_with("keywords:")
                 || trimmed.starts_with("topics:")
@@ -218,12 +487,148 @@ fn snippet_from_archive(path: &str) -> String {
         .collect()
 }
 
+/// Most recent completed search per query, the fallback when a later one misses its deadline.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct RecallCache {
+    entries: BTreeMap<String, CachedRecall>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedRecall {
+    cached_at_epoch_secs: u64,
+    matches: Vec<RecallMatch>,
+}
+
+const RECALL_CACHE_MAX_ENTRIES: usize = 64;
+
+fn recall_cache_path(paths: &MoonPaths) -> PathBuf {
+    paths.logs_dir.join("recall-cache.json")
+}
+
+fn recall_cache_key(collection_name: &str, scope: RecallScope, query: &str) -> String {
+    format!("{}:{collection_name}:{}", scope.as_str(), query.trim())
+}
+
+fn load_recall_cache(paths: &MoonPaths) -> RecallCache {
+    fs::read_to_string(recall_cache_path(paths))
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+/// Best effort: a cache that cannot be written only leaves a later degraded recall without it.
+fn store_recall_cache(paths: &MoonPaths, key: &str, matches: &[RecallMatch]) {
+    if matches.is_empty() || read_only_mode() {
+        return;
+    }
+    let mut cache = load_recall_cache(paths);
+    cache.entries.insert(
+        key.to_string(),
+        CachedRecall {
+            cached_at_epoch_secs: now_epoch_secs().unwrap_or_default(),
+            matches: matches.to_vec(),
+        },
+    );
+    while cache.entries.len() > RECALL_CACHE_MAX_ENTRIES {
+        let Some(oldest) = cache
+            .entries
+            .iter()
+            .min_by_key(|(_, entry)| entry.cached_at_epoch_secs)
+            .map(|(key, _)| key.clone())
+        else {
+            break;
+        };
+        cache.entries.remove(&oldest);
+    }
+    let path = recall_cache_path(paths);
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    if let Ok(data) = serde_json::to_string(&cache) {
+        let _ = fs::write(&path, format!("{data}\n"));
+    }
+}
+
+/// Cached search hits for `key`, tagged with `metadata.cached` so callers can tell them apart.
+fn cached_search_matches(paths: &MoonPaths, key: &str) -> Vec<RecallMatch> {
+    let Some(entry) = load_recall_cache(paths).entries.remove(key) else {
+        return Vec::new();
+    };
+    entry
+        .matches
+        .into_iter()
+        .map(|mut item| {
+            if let Value::Object(meta) = &mut item.metadata {
+                meta.insert("cached".to_string(), Value::Bool(true));
+                meta.insert(
+                    "cachedAtEpochSecs".to_string(),
+                    Value::from(entry.cached_at_epoch_secs),
+                );
+            }
+            item
+        })
+        .collect()
+}
+
+/// How long a qmd search may run when recall has no deadline.
+const QMD_SEARCH_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// qmd hits for `scope`, before supersede resolution, privacy filtering and dedupe.
+fn search_matches(
+    paths: &MoonPaths,
+    query: &str,
+    enhanced_query: &str,
+    collection_name: &str,
+    scope: RecallScope,
+    tool_priority: &MoonToolPriorityConfig,
+    deadline: Option<Instant>,
+) -> Result<Vec<RecallMatch>> {
+    // Each qmd call gets what is left until the deadline and is killed once it passes.
+    let qmd_timeout = || {
+        deadline.map_or(QMD_SEARCH_TIMEOUT, |deadline| {
+            deadline.saturating_duration_since(Instant::now())
+        })
+    };
+    let mut matches = Vec::new();
+    if scope.includes_archives() {
+        let raw = qmd::search(
+            &paths.qmd_bin,
+            collection_name,
+            enhanced_query,
+            qmd_timeout(),
+        )?;
+        let mut searched = parse_matches(paths, &raw, tool_priority);
+        // Graph lookups are advisory; recall still works when the graph is missing or unreadable.
+        if let Ok(related_stems) = graph::related_archive_stems(paths, query) {
+            apply_graph_boost(&mut searched, &related_stems);
+        }
+        matches.extend(searched);
+    }
+    if scope.includes_memory() {
+        let raw = qmd::search(
+            &paths.qmd_bin,
+            qmd::MEMORY_COLLECTION,
+            enhanced_query,
+            qmd_timeout(),
+        )?;
+        matches.extend(parse_memory_matches(paths, &raw));
+    }
+    Ok(matches)
+}
+
+/// Searches `scope` for `query`. With a `deadline`, qmd is killed once it has passed since the
+/// call and recall returns the deterministic channel-map match plus the last cached search for
+/// the query, marked `degraded`.
 pub fn recall(
     paths: &MoonPaths,
     query: &str,
     collection_name: &str,
     channel_key: Option<&str>,
+    scope: RecallScope,
+    deadline: Option<Duration>,
 ) -> Result<RecallResult> {
+    let started = Instant::now();
     let mut matches = Vec::new();
 
     let key_hint = channel_key.or_else(|| {
@@ -235,20 +640,51 @@ pub fn recall(
         }
     });
 
+    let cfg = load_config().ok();
+    let private_key = key_hint.is_some_and(|key| {
+        cfg.as_ref()
+            .is_some_and(|cfg| cfg.privacy.matching_pattern(key).is_some())
+    });
     if let Some(key) = key_hint
+        && !private_key
+        && scope.includes_archives()
         && let Some(record) = channel_archive_map::get(paths, key)?
     {
+        let mut metadata = json!({
+            "deterministic": true,
+            "channelKey": record.channel_key,
+            "sourcePath": record.source_path,
+            "updatedAtEpochSecs": record.updated_at_epoch_secs,
+        });
+        if let Value::Object(meta) = &mut metadata {
+            match &record.retired_archive_path {
+                // Retention removed the archive; the mapping now names what outlived it.
+                Some(retired) => {
+                    meta.insert("retiredArchive".to_string(), Value::String(retired.clone()));
+                    if Path::new(&record.archive_path).starts_with(&paths.memory_dir) {
+                        meta.insert(
+                            "scope".to_string(),
+                            Value::String(RecallScope::Memory.as_str().to_string()),
+                        );
+                    }
+                }
+                None => {
+                    meta.insert(
+                        "projectionPath".to_string(),
+                        Value::String(
+                            projection_path_for_archive(&record.archive_path)
+                                .display()
+                                .to_string(),
+                        ),
+                    );
+                }
+            }
+        }
         matches.push(RecallMatch {
             archive_path: record.archive_path.clone(),
             snippet: snippet_from_archive(&record.archive_path),
             score: 1_000_000.0,
-            metadata: json!({
-                "deterministic": true,
-                "channelKey": record.channel_key,
-                "sourcePath": record.source_path,
-                "projectionPath": projection_path_for_archive(&record.archive_path).display().to_string(),
-                "updatedAtEpochSecs": record.updated_at_epoch_secs,
-            }),
+            metadata,
         });
     }
 
@@ -259,13 +695,74 @@ pub fn recall(
         || query.to_lowercase().contains("am")
         || query.to_lowercase().contains("pm")
     {
-        use chrono::Local;
-        let offset = Local::now().offset().to_string();
+        let tz = resolve_residential_tz();
+        let offset = chrono::Utc::now().with_timezone(&tz).format("%:z");
         enhanced_query.push_str(&format!(" UTC {}", offset));
     }
 
-    let raw = qmd::search(&paths.qmd_bin, collection_name, &enhanced_query)?;
-    matches.extend(parse_matches(paths, &raw));
+    let tool_priority = cfg.map(|cfg| cfg.tool_priority).unwrap_or_default();
+    let cache_key = recall_cache_key(collection_name, scope, query);
+    let deadline_at = deadline.map(|deadline| started + deadline);
+    let searched = match search_matches(
+        paths,
+        query,
+        &enhanced_query,
+        collection_name,
+        scope,
+        &tool_priority,
+        deadline_at,
+    ) {
+        Ok(searched) => Some(searched),
+        // qmd failing at or past the deadline means it was killed for running out of time.
+        Err(_) if deadline_at.is_some_and(|at| Instant::now() >= at) => None,
+        Err(err) => return Err(err),
+    };
+    let degraded = searched.is_none();
+    match searched {
+        Some(searched) => {
+            store_recall_cache(paths, &cache_key, &searched);
+            matches.extend(searched);
+        }
+        None => matches.extend(cached_search_matches(paths, &cache_key)),
+    }
+
+    // Superseded snapshots resolve to the newest archive of their session, so a stale partial
+    // copy never outranks (or duplicates) the complete one. Archives marked as duplicates of
+    // another session's conversation collapse into that archive the same way.
+    if let Ok(ledger) = read_ledger_records(paths)
+        && ledger
+            .iter()
+            .any(|r| r.superseded_by.is_some() || r.duplicate_of.is_some())
+    {
+        for item in matches.iter_mut() {
+            let newest = newest_archive_version(&ledger, &item.archive_path);
+            if newest != item.archive_path {
+                if let Value::Object(meta) = &mut item.metadata {
+                    meta.insert(
+                        "supersededArchive".to_string(),
+                        Value::String(item.archive_path.clone()),
+                    );
+                }
+                item.archive_path = newest;
+            }
+            if let Some(original) = duplicate_canonical_archive(&ledger, &item.archive_path) {
+                if let Value::Object(meta) = &mut item.metadata {
+                    meta.insert(
+                        "duplicateArchive".to_string(),
+                        Value::String(item.archive_path.clone()),
+                    );
+                }
+                item.archive_path = original;
+            }
+        }
+    }
+
+    // Private-channel archives are never projected, but a stale projection from before the
+    // channel was marked private must not resurface either.
+    let private_archives = privacy::private_archive_paths(paths);
+    if !private_archives.is_empty() {
+        matches.retain(|item| !private_archives.contains(&item.archive_path));
+    }
 
     let mut deduped = Vec::with_capacity(matches.len());
     let mut seen_paths = BTreeSet::new();
@@ -285,5 +782,200 @@ pub fn recall(
         query: query.to_string(),
         matches: deduped,
         generated_at_epoch_secs: now_epoch_secs()?,
+        degraded,
     })
 }
+
+/// Topics shown in a `session[N]` header; the projection lists them by weight.
+const SESSION_HEADER_TOPICS: usize = 3;
+
+/// Recall matches that came from the same session, with the metadata they share.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecallSessionGroup {
+    pub session_id: String,
+    pub channel_key: Option<String>,
+    /// `time_range_local` from the projection frontmatter.
+    pub time_range: Option<String>,
+    pub topics: Vec<String>,
+    /// Positions in the grouped match list, in rank order.
+    pub match_indexes: Vec<usize>,
+}
+
+/// Session header fields read from a projection's frontmatter.
+fn projection_session_header(archive_path: &str) -> (Option<String>, Vec<String>) {
+    let Ok(raw) = fs::read_to_string(projection_path_for_archive(archive_path)) else {
+        return (None, Vec::new());
+    };
+    let mut lines = raw.lines();
+    if lines.next().map(str::trim) != Some("---") {
+        return (None, Vec::new());
+    }
+    let mut time_range = None;
+    let mut topics = Vec::new();
+    let mut keywords = Vec::new();
+    for line in lines.take_while(|line| line.trim() != "---") {
+        if let Some(value) = line.strip_prefix("time_range_local:") {
+            let value = value.trim().trim_matches('"').trim();
+            time_range = (!value.is_empty()).then(|| value.to_string());
+        } else if let Some(value) = line.strip_prefix("topics:") {
+            topics = serde_json::from_str(value.trim()).unwrap_or_default();
+        } else if let Some(value) = line.strip_prefix("keywords:") {
+            keywords = serde_json::from_str(value.trim()).unwrap_or_default();
+        }
+    }
+    // Older projections carry keywords but no topics.
+    if topics.is_empty() {
+        topics = keywords;
+    }
+    topics.truncate(SESSION_HEADER_TOPICS);
+    (time_range, topics)
+}
+
+/// Groups `matches` by the session they were archived from, ordered by each session's best rank.
+///
+/// Sessions come from the archive ledger (falling back to the archive file stem) and channels
+/// from the channel archive map entry for the same source file.
+pub fn group_matches_by_session(
+    paths: &MoonPaths,
+    matches: &[RecallMatch],
+) -> Vec<RecallSessionGroup> {
+    let ledger = read_ledger_records(paths).unwrap_or_default();
+    let channels = channel_archive_map::load(paths).unwrap_or_default();
+    let mut groups: Vec<RecallSessionGroup> = Vec::new();
+    for (idx, m) in matches.iter().enumerate() {
+        let record = ledger.iter().find(|r| r.archive_path == m.archive_path);
+        let memory_day = is_memory_match(m)
+            .then(|| Path::new(&m.archive_path).file_stem())
+            .flatten()
+            .map(|stem| format!("memory:{}", stem.to_string_lossy()));
+        let session_id = memory_day
+            .or_else(|| record.map(|r| r.session_id.clone()))
+            .or_else(|| {
+                Path::new(&m.archive_path)
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().to_string())
+            })
+            .filter(|id| !id.is_empty())
+            .unwrap_or_else(|| "unknown".to_string());
+        if let Some(group) = groups.iter_mut().find(|g| g.session_id == session_id) {
+            group.match_indexes.push(idx);
+            continue;
+        }
+        let channel_key = m
+            .metadata
+            .get("channelKey")
+            .and_then(Value::as_str)
+            .map(ToOwned::to_owned)
+            .or_else(|| {
+                channels
+                    .values()
+                    .find(|c| {
+                        c.archive_path == m.archive_path
+                            || record.is_some_and(|r| r.source_path == c.source_path)
+                    })
+                    .map(|c| c.channel_key.clone())
+            });
+        let (time_range, topics) = projection_session_header(&m.archive_path);
+        groups.push(RecallSessionGroup {
+            session_id,
+            channel_key,
+            time_range,
+            topics,
+            match_indexes: vec![idx],
+        });
+    }
+    groups
+}
+
+/// Snippets that would be cut below this many tokens are dropped instead.
+const MIN_BUDGET_SNIPPET_TOKENS: usize = 16;
+
+/// How `fit_snippets_to_token_budget` trimmed a result set.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SnippetBudgetOutcome {
+    pub used_tokens: usize,
+    /// Indexes (into the kept matches) whose snippet was shortened.
+    pub truncated: Vec<usize>,
+    pub dropped: usize,
+}
+
+/// Per-match token allowance for snippets costing `costs`, listed in priority order.
+///
+/// Lowest-priority matches are dropped until every kept one can show at least
+/// `MIN_BUDGET_SNIPPET_TOKENS`; the budget is then water-filled so short snippets stay whole
+/// and long ones share the rest evenly, with any remainder going to the highest priority.
+fn allot_snippet_tokens(costs: &[usize], max_tokens: usize) -> Vec<usize> {
+    let mut kept = 0usize;
+    let mut floor_total = 0usize;
+    for cost in costs {
+        let floor = (*cost).min(MIN_BUDGET_SNIPPET_TOKENS);
+        if floor_total + floor > max_tokens {
+            break;
+        }
+        floor_total += floor;
+        kept += 1;
+    }
+    // The top match always survives, cut to whatever fits.
+    let kept = kept.max(costs.len().min(1));
+
+    let mut allot = vec![0usize; kept];
+    let mut order: Vec<usize> = (0..kept).collect();
+    // Among equal costs the higher-ranked match goes last, so it inherits the rounding slack.
+    order.sort_by_key(|idx| (costs[*idx], std::cmp::Reverse(*idx)));
+    let mut remaining = max_tokens;
+    for (pos, idx) in order.iter().enumerate() {
+        let share = remaining / (kept - pos);
+        allot[*idx] = costs[*idx].min(share);
+        remaining -= allot[*idx];
+    }
+    for (idx, slot) in allot.iter_mut().enumerate() {
+        let extra = (costs[idx] - *slot).min(remaining);
+        *slot += extra;
+        remaining -= extra;
+    }
+    allot
+}
+
+/// Trims `matches` (already in rank order) so their snippets total at most `max_tokens`.
+///
+/// Higher-ranked matches are truncated last and dropped last; see `allot_snippet_tokens`.
+pub fn fit_snippets_to_token_budget(
+    matches: &mut Vec<RecallMatch>,
+    max_tokens: usize,
+) -> SnippetBudgetOutcome {
+    let costs: Vec<usize> = matches.iter().map(|m| tokens::count(&m.snippet)).collect();
+    let allot = allot_snippet_tokens(&costs, max_tokens);
+    let mut outcome = SnippetBudgetOutcome {
+        dropped: matches.len() - allot.len(),
+        ..SnippetBudgetOutcome::default()
+    };
+    matches.truncate(allot.len());
+    for (idx, (m, allowance)) in matches.iter_mut().zip(&allot).enumerate() {
+        if costs[idx] > *allowance {
+            // Reserve a token for the ellipsis marking the cut.
+            let cut = tokens::truncate(&m.snippet, allowance.saturating_sub(1)).trim_end();
+            m.snippet = format!("{cut}…");
+            outcome.truncated.push(idx);
+        }
+        outcome.used_tokens += tokens::count(&m.snippet);
+    }
+    outcome
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn allot_snippet_tokens_keeps_short_snippets_whole_and_drops_lowest_priority() {
+        assert_eq!(allot_snippet_tokens(&[10, 20], 100), vec![10, 20]);
+        // Short snippets stay whole; the long ones split what is left.
+        assert_eq!(allot_snippet_tokens(&[200, 10, 200], 110), vec![50, 10, 50]);
+        // Remainders favour the highest-ranked match.
+        assert_eq!(allot_snippet_tokens(&[200, 200], 101), vec![51, 50]);
+        // Not enough room for a third readable snippet.
+        assert_eq!(allot_snippet_tokens(&[100, 100, 100], 40), vec![20, 20]);
+        assert_eq!(allot_snippet_tokens(&[100], 5), vec![5]);
+        assert!(allot_snippet_tokens(&[], 5).is_empty());
+    }
+}
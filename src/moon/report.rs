@@ -0,0 +1,298 @@
+use crate::moon::archive::read_ledger_records;
+use crate::moon::audit::{self, AuditEvent};
+use crate::moon::budget;
+use crate::moon::paths::MoonPaths;
+use crate::moon::util::truncate_with_ellipsis;
+use anyhow::{Context, Result, anyhow};
+use chrono::{NaiveDate, TimeZone};
+use chrono_tz::Tz;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::PathBuf;
+
+const REPORTS_DIR: &str = "reports";
+const REPORT_LINE_MAX_CHARS: usize = 200;
+
+#[derive(Debug, Clone, Default)]
+pub struct DailyReport {
+    pub day_key: String,
+    pub timezone: String,
+    pub archived: Vec<String>,
+    pub compactions: Vec<String>,
+    pub decisions: Vec<String>,
+    pub open_tasks: Vec<String>,
+    pub distill_runs: BTreeMap<String, usize>,
+    /// Estimated remote tokens from the distill budget ledger.
+    pub distill_tokens: u64,
+    /// `distill_tokens` priced at `[distill] cost_per_million_tokens`, when set.
+    pub distill_cost: Option<f64>,
+    pub distill_fallbacks: DistillFallbackStats,
+    pub warnings: Vec<String>,
+}
+
+/// Provider-attributed distill runs and how many fell back after a remote provider error.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DistillFallbackStats {
+    pub runs: usize,
+    /// Fallback count per error class (`auth`, `quota`, `network`, `sanitize-reject`, `other`).
+    pub by_class: BTreeMap<String, usize>,
+}
+
+impl DistillFallbackStats {
+    pub fn record(&mut self, event: &AuditEvent) {
+        if event.phase != "distill" || event.status != "ok" || event.field("provider").is_none() {
+            return;
+        }
+        self.runs += 1;
+        if let Some(class) = event.field("fallback_error_class") {
+            *self.by_class.entry(class).or_default() += 1;
+        }
+    }
+
+    pub fn fallbacks(&self) -> usize {
+        self.by_class.values().sum()
+    }
+
+    /// Share of runs that fell back, `0.0` when nothing ran.
+    pub fn rate(&self) -> f64 {
+        if self.runs == 0 {
+            return 0.0;
+        }
+        self.fallbacks() as f64 / self.runs as f64
+    }
+
+    pub fn class_summary(&self) -> String {
+        self.by_class
+            .iter()
+            .map(|(class, count)| format!("{class}:{count}"))
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
+pub fn daily_report_path(paths: &MoonPaths, day_key: &str) -> PathBuf {
+    paths
+        .memory_dir
+        .join(REPORTS_DIR)
+        .join(format!("daily-{day_key}.md"))
+}
+
+/// `[start, end)` epoch bounds of `day_key` in `tz`.
+pub fn day_bounds(day_key: &str, tz: Tz) -> Result<(u64, u64)> {
+    let date = NaiveDate::parse_from_str(day_key, "%Y-%m-%d")
+        .map_err(|_| anyhow!("invalid day `{day_key}`; use YYYY-MM-DD"))?;
+    let start_of = |date: NaiveDate| {
+        date.and_hms_opt(0, 0, 0)
+            .and_then(|start| tz.from_local_datetime(&start).earliest())
+            .map(|dt| dt.timestamp().max(0) as u64)
+    };
+    let next = date
+        .succ_opt()
+        .ok_or_else(|| anyhow!("invalid day `{day_key}`"))?;
+    match (start_of(date), start_of(next)) {
+        (Some(start), Some(end)) => Ok((start, end)),
+        _ => Err(anyhow!("day `{day_key}` has no start in {}", tz.name())),
+    }
+}
+
+fn distill_provider(event: &AuditEvent) -> String {
+    if event.field("mode").as_deref() == Some("l1_normalised") {
+        return "l1-normaliser".to_string();
+    }
+    event
+        .field("provider")
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Decision and open-task bullets from a daily memory file.
+fn collect_daily_signals(raw: &str) -> (Vec<String>, Vec<String>) {
+    let mut decisions = BTreeSet::new();
+    let mut tasks = BTreeSet::new();
+    let mut section = "";
+    for line in raw.lines() {
+        let trimmed = line.trim();
+        if let Some(heading) = trimmed.strip_prefix('#') {
+            let heading = heading.trim_start_matches('#').trim().to_ascii_lowercase();
+            section = if heading.starts_with("decision") {
+                "decisions"
+            } else if heading.contains("open task") || heading == "tasks" {
+                "tasks"
+            } else {
+                ""
+            };
+            continue;
+        }
+        if let Some(task) = trimmed.strip_prefix("- [ ]") {
+            tasks.insert(task.trim().to_string());
+            continue;
+        }
+        let Some(bullet) = trimmed.strip_prefix("- ").map(str::trim) else {
+            continue;
+        };
+        if bullet.is_empty() {
+            continue;
+        }
+        match section {
+            "decisions" => {
+                decisions.insert(bullet.to_string());
+            }
+            "tasks" => {
+                tasks.insert(bullet.to_string());
+            }
+            _ => {}
+        }
+    }
+    (decisions.into_iter().collect(), tasks.into_iter().collect())
+}
+
+pub fn build_daily_report(
+    paths: &MoonPaths,
+    day_key: &str,
+    tz: Tz,
+    cost_per_million_tokens: f64,
+) -> Result<DailyReport> {
+    let (start, end) = day_bounds(day_key, tz)?;
+    let in_day = |epoch: u64| epoch >= start && epoch < end;
+    let mut report = DailyReport {
+        day_key: day_key.to_string(),
+        timezone: tz.name().to_string(),
+        ..DailyReport::default()
+    };
+
+    for record in read_ledger_records(paths)? {
+        if in_day(record.created_at_epoch_secs) {
+            report.archived.push(format!(
+                "`{}` -> {} (indexed={})",
+                record.session_id, record.archive_path, record.indexed
+            ));
+        }
+    }
+
+    for event in audit::read_events(paths)? {
+        if !in_day(event.at_epoch_secs) {
+            continue;
+        }
+        let message = truncate_with_ellipsis(&event.message, REPORT_LINE_MAX_CHARS);
+        if event.phase == "compaction" {
+            report
+                .compactions
+                .push(format!("{}: {message}", event.status));
+        }
+        report.distill_fallbacks.record(&event);
+        if event.phase == "distill" && event.status == "ok" {
+            *report
+                .distill_runs
+                .entry(distill_provider(&event))
+                .or_default() += 1;
+        }
+        if event.status != "ok" {
+            report
+                .warnings
+                .push(format!("{} {}: {message}", event.phase, event.status));
+        }
+    }
+
+    report.distill_tokens = budget::tokens_for_day(paths, day_key)?;
+    report.distill_cost = budget::estimated_cost(report.distill_tokens, cost_per_million_tokens);
+
+    let daily_path = paths.memory_dir.join(format!("{day_key}.md"));
+    if let Ok(raw) = fs::read_to_string(&daily_path) {
+        (report.decisions, report.open_tasks) = collect_daily_signals(&raw);
+    }
+    Ok(report)
+}
+
+pub fn render_daily_report_markdown(report: &DailyReport) -> String {
+    fn section(out: &mut String, title: &str, lines: &[String]) {
+        out.push_str(&format!("\n## {title} ({})\n", lines.len()));
+        if lines.is_empty() {
+            out.push_str("- None\n");
+        }
+        for line in lines {
+            out.push_str(&format!("- {line}\n"));
+        }
+    }
+
+    let mut out = format!("# Daily Report {}\n", report.day_key);
+    out.push_str(&format!("- timezone: {}\n", report.timezone));
+    section(&mut out, "Sessions Archived", &report.archived);
+    section(&mut out, "Compactions", &report.compactions);
+    section(&mut out, "Decisions", &report.decisions);
+    section(&mut out, "Open Tasks", &report.open_tasks);
+    let mut spend = vec![format!("estimated tokens: {}", report.distill_tokens)];
+    if let Some(cost) = report.distill_cost {
+        spend.push(format!("estimated cost: {cost:.4}"));
+    }
+    spend.extend(
+        report
+            .distill_runs
+            .iter()
+            .map(|(provider, runs)| format!("{provider}: {runs} run(s)")),
+    );
+    section(&mut out, "Distillation Spend", &spend);
+    let fallbacks = report
+        .distill_fallbacks
+        .by_class
+        .iter()
+        .map(|(class, count)| {
+            format!(
+                "{class}: {count} of {} provider run(s)",
+                report.distill_fallbacks.runs
+            )
+        })
+        .collect::<Vec<_>>();
+    section(&mut out, "Provider Fallbacks", &fallbacks);
+    section(&mut out, "Warnings", &report.warnings);
+    out
+}
+
+pub fn write_daily_report(paths: &MoonPaths, report: &DailyReport) -> Result<PathBuf> {
+    let path = daily_report_path(paths, &report.day_key);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+    fs::write(&path, render_daily_report_markdown(report))
+        .with_context(|| format!("failed to write {}", path.display()))?;
+    Ok(path)
+}
+
+/// One-line digest used for the openclaw system event.
+pub fn daily_report_event_text(report: &DailyReport, path: &std::path::Path) -> String {
+    format!(
+        "Moon daily report {}: archived={} compactions={} decisions={} open_tasks={} distill_runs={} distill_tokens={} warnings={} report={}",
+        report.day_key,
+        report.archived.len(),
+        report.compactions.len(),
+        report.decisions.len(),
+        report.open_tasks.len(),
+        report.distill_runs.values().sum::<usize>(),
+        report.distill_tokens,
+        report.warnings.len(),
+        path.display()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{collect_daily_signals, day_bounds};
+
+    #[test]
+    fn day_bounds_follow_residential_timezone() {
+        let (start, end) = day_bounds("2023-11-15", chrono_tz::Asia::Tokyo).expect("bounds");
+        assert_eq!(start, 1_699_974_000);
+        assert_eq!(end - start, 24 * 60 * 60);
+        assert!(day_bounds("15/11/2023", chrono_tz::UTC).is_err());
+    }
+
+    #[test]
+    fn collect_daily_signals_reads_decision_and_task_sections() {
+        let raw = "# Daily Memory 2023-11-15\n\n### Decisions\n- ship v2 parser\n\n### Open Tasks\n- backfill archives\n\n### Rules\n- keep tests green\n- [ ] rotate keys\n";
+        let (decisions, tasks) = collect_daily_signals(raw);
+        assert_eq!(decisions, vec!["ship v2 parser".to_string()]);
+        assert_eq!(
+            tasks,
+            vec!["backfill archives".to_string(), "rotate keys".to_string()]
+        );
+    }
+}
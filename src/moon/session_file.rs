@@ -0,0 +1,110 @@
+use anyhow::{Context, Result};
+use flate2::read::MultiGzDecoder;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// True for `.gz` names, e.g. session logs OpenClaw rotated to `<id>.jsonl.gz`.
+pub fn is_gzip_name(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("gz"))
+}
+
+/// Extension of the session format, looking through a trailing `.gz` (`jsonl` for
+/// `a.jsonl.gz`).
+pub fn session_extension(path: &Path) -> Option<String> {
+    let name = if is_gzip_name(path) {
+        Path::new(path.file_stem()?)
+    } else {
+        path
+    };
+    name.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_ascii_lowercase())
+}
+
+/// File stem without the session extension or a trailing `.gz` (`a` for `a.jsonl.gz`).
+pub fn session_file_stem(path: &Path) -> Option<&str> {
+    let stem = path.file_stem()?;
+    let stem = if is_gzip_name(path) {
+        Path::new(stem).file_stem()?
+    } else {
+        stem
+    };
+    stem.to_str()
+}
+
+/// Buffered reader over a session file's contents, decompressing gzip when the file starts
+/// with the gzip magic bytes whatever its name.
+pub fn open_session_reader(path: &Path) -> Result<Box<dyn BufRead + Send>> {
+    let file = File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+    let mut reader = BufReader::new(file);
+    let head = reader
+        .fill_buf()
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    if head.starts_with(&GZIP_MAGIC) {
+        Ok(Box::new(BufReader::new(MultiGzDecoder::new(reader))))
+    } else {
+        Ok(Box::new(reader))
+    }
+}
+
+/// Whole (decompressed) contents of a session file.
+pub fn read_session_bytes(path: &Path) -> Result<Vec<u8>> {
+    let mut out = Vec::new();
+    open_session_reader(path)?
+        .read_to_end(&mut out)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_gzip_name, read_session_bytes, session_extension, session_file_stem};
+    use flate2::Compression;
+    use flate2::write::GzEncoder;
+    use std::fs;
+    use std::io::Write;
+    use std::path::Path;
+    use tempfile::tempdir;
+
+    #[test]
+    fn session_names_look_through_gz_suffix() {
+        let rotated = Path::new("/tmp/abc-123.jsonl.gz");
+        assert!(is_gzip_name(rotated));
+        assert_eq!(session_extension(rotated).as_deref(), Some("jsonl"));
+        assert_eq!(session_file_stem(rotated), Some("abc-123"));
+        let live = Path::new("/tmp/abc-123.JSONL");
+        assert!(!is_gzip_name(live));
+        assert_eq!(session_extension(live).as_deref(), Some("jsonl"));
+        assert_eq!(session_file_stem(live), Some("abc-123"));
+        assert_eq!(session_extension(Path::new("/tmp/abc.gz")), None);
+    }
+
+    #[test]
+    fn read_session_bytes_decompresses_concatenated_gzip_members() {
+        let tmp = tempdir().expect("tempdir");
+        let mut bytes = Vec::new();
+        for line in ["{\"a\":1}\n", "{\"b\":2}\n"] {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(line.as_bytes()).expect("compress");
+            bytes.extend(encoder.finish().expect("finish gzip member"));
+        }
+        let gz = tmp.path().join("s.jsonl.gz");
+        fs::write(&gz, bytes).expect("write gz");
+        let plain = tmp.path().join("s.jsonl");
+        fs::write(&plain, "{\"a\":1}\n").expect("write plain");
+
+        assert_eq!(
+            read_session_bytes(&gz).expect("read gz"),
+            b"{\"a\":1}\n{\"b\":2}\n"
+        );
+        assert_eq!(
+            read_session_bytes(&plain).expect("read plain"),
+            b"{\"a\":1}\n"
+        );
+    }
+}
@@ -1,12 +1,21 @@
 use crate::moon::paths::MoonPaths;
+use crate::moon::util::read_only_mode;
+use crate::moon::warn::{self, WarnEvent};
 use crate::openclaw::gateway::resolve_openclaw_bin_path;
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::env;
+use std::fs;
+use std::path::PathBuf;
 use std::process::Command;
 use std::time::{SystemTime, UNIX_EPOCH};
 
+/// Bytes of an unparseable usage payload kept in `logs/payload_failures/`.
+const PAYLOAD_CAPTURE_MAX_BYTES: usize = 16 * 1024;
+/// Captured payloads kept; older captures are removed when a new one is written.
+const PAYLOAD_CAPTURE_KEEP: usize = 20;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SessionUsageSnapshot {
     pub session_id: String,
@@ -36,6 +45,8 @@ struct ParsedSessionUsage {
 pub struct OpenClawUsageBatch {
     pub current: SessionUsageSnapshot,
     pub sessions: Vec<SessionUsageSnapshot>,
+    /// Schema the `sessions --json` payload was read with.
+    pub schema: UsageSchema,
 }
 
 fn epoch_now() -> Result<u64> {
@@ -125,99 +136,273 @@ fn openclaw_sessions_args() -> Vec<String> {
     vec!["sessions".into(), "--json".into()]
 }
 
-fn parse_openclaw_usage(raw: &str) -> Result<(String, u64, u64)> {
-    if let Ok(sessions) = parse_openclaw_sessions(raw)
-        && let Some(latest) = sessions.iter().max_by_key(|entry| entry.updated_at)
-    {
-        return Ok((
-            latest.session_id.clone(),
-            latest.used_tokens,
-            latest.max_tokens,
-        ));
+/// Field layout of an OpenClaw `sessions --json` / `sessions current --json` payload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UsageSchema {
+    /// `totalTokens` / `contextTokens` per session, or the nested `usage.totalTokens` /
+    /// `limits.maxTokens` current-session payload.
+    V1,
+    /// `schemaVersion: 2`: `tokensUsed` / `contextWindow` (or a `tokens {used, max}` object),
+    /// `sessionKey` and `lastActiveAt`, with the current session under `session`.
+    V2,
+}
+
+const USAGE_SCHEMAS: [UsageSchema; 2] = [UsageSchema::V1, UsageSchema::V2];
+
+impl UsageSchema {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::V1 => "v1",
+            Self::V2 => "v2",
+        }
     }
 
-    let parsed: Value = serde_json::from_str(raw).context("invalid OpenClaw usage JSON")?;
-    let session_id = parsed
-        .get("sessionId")
-        .and_then(Value::as_str)
-        .or_else(|| parsed.get("id").and_then(Value::as_str))
+    fn fields(self) -> &'static UsageFields {
+        match self {
+            Self::V1 => &V1_FIELDS,
+            Self::V2 => &V2_FIELDS,
+        }
+    }
+}
+
+/// `[usage] schema`: `auto` probes every known schema, `v1`/`v2` pins one.
+pub fn parse_usage_schema(value: &str) -> Result<Option<UsageSchema>> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "" | "auto" => Ok(None),
+        "v1" => Ok(Some(UsageSchema::V1)),
+        "v2" => Ok(Some(UsageSchema::V2)),
+        other => anyhow::bail!("invalid usage schema `{other}`: use `auto`, `v1` or `v2`"),
+    }
+}
+
+fn configured_usage_schema() -> Option<UsageSchema> {
+    crate::moon::config::load_config()
+        .ok()
+        .and_then(|cfg| parse_usage_schema(&cfg.usage.schema).ok())
+        .flatten()
+}
+
+/// Where one schema keeps each session field, most specific first.
+struct UsageFields {
+    session_id: &'static [&'static str],
+    used: &'static [&'static [&'static str]],
+    max: &'static [&'static [&'static str]],
+    updated_at: &'static [&'static str],
+    /// Object holding the current session in a `sessions current --json` payload.
+    current: Option<&'static str>,
+}
+
+static V1_FIELDS: UsageFields = UsageFields {
+    session_id: &["key", "sessionId", "id"],
+    used: &[
+        &["totalTokens"],
+        &["inputTokens"],
+        &["usage", "totalTokens"],
+        &["usage", "inputTokens"],
+        &["tokenUsage", "total"],
+        &["context", "usedTokens"],
+        &["usedTokens"],
+    ],
+    max: &[
+        &["contextTokens"],
+        &["maxTokens"],
+        &["limits", "maxTokens"],
+        &["context", "maxTokens"],
+        &["tokenUsage", "max"],
+    ],
+    updated_at: &["updatedAt"],
+    current: None,
+};
+
+static V2_FIELDS: UsageFields = UsageFields {
+    session_id: &["sessionKey", "key", "sessionId", "id"],
+    used: &[
+        &["tokensUsed"],
+        &["tokens", "used"],
+        &["usage", "tokensUsed"],
+    ],
+    max: &[
+        &["contextWindow"],
+        &["tokenLimit"],
+        &["tokens", "max"],
+        &["tokens", "limit"],
+    ],
+    updated_at: &["lastActiveAt", "updatedAt"],
+    current: Some("session"),
+};
+
+/// Schemas to try for `parsed`: the pinned one only, else the payload's declared
+/// `schemaVersion` first and the others after it, so renamed fields still parse.
+fn probe_order(parsed: &Value, pinned: Option<UsageSchema>) -> Vec<UsageSchema> {
+    if let Some(schema) = pinned {
+        return vec![schema];
+    }
+    let declared = parse_u64(parsed.get("schemaVersion"))
+        .or_else(|| parse_u64(parsed.get("version")))
+        .unwrap_or(1);
+    let first = if declared >= 2 {
+        UsageSchema::V2
+    } else {
+        UsageSchema::V1
+    };
+    let mut order = vec![first];
+    order.extend(USAGE_SCHEMAS.into_iter().filter(|schema| *schema != first));
+    order
+}
+
+fn parse_session_entry(entry: &Value, fields: &UsageFields) -> Option<ParsedSessionUsage> {
+    let used_tokens = find_u64(entry, fields.used)?;
+    let session_id = fields
+        .session_id
+        .iter()
+        .find_map(|key| entry.get(*key).and_then(Value::as_str))
         .unwrap_or("current")
         .to_string();
-
-    let used = find_u64(
-        &parsed,
-        &[
-            &["usage", "totalTokens"],
-            &["usage", "inputTokens"],
-            &["tokenUsage", "total"],
-            &["context", "usedTokens"],
-        ],
-    )
-    .or_else(|| parse_u64(parsed.get("usedTokens")))
-    .context("OpenClaw usage payload missing used token fields")?;
-
-    let max = find_u64(
-        &parsed,
-        &[
-            &["limits", "maxTokens"],
-            &["context", "maxTokens"],
-            &["tokenUsage", "max"],
-        ],
-    )
-    .or_else(|| parse_u64(parsed.get("maxTokens")))
-    .unwrap_or(200_000);
-
-    Ok((session_id, used, max))
+    Some(ParsedSessionUsage {
+        session_id,
+        used_tokens,
+        max_tokens: find_u64(entry, fields.max).unwrap_or(200_000),
+        updated_at: fields
+            .updated_at
+            .iter()
+            .find_map(|key| parse_u64(entry.get(*key)))
+            .unwrap_or(0),
+    })
 }
 
-fn parse_openclaw_sessions(raw: &str) -> Result<Vec<ParsedSessionUsage>> {
-    let parsed: Value = serde_json::from_str(raw).context("invalid OpenClaw sessions JSON")?;
+fn parse_sessions_with(parsed: &Value, schema: UsageSchema) -> Result<Vec<ParsedSessionUsage>> {
     let sessions = parsed
         .get("sessions")
         .and_then(Value::as_array)
         .context("OpenClaw sessions payload missing sessions array")?;
+    let out = sessions
+        .iter()
+        .filter_map(|entry| parse_session_entry(entry, schema.fields()))
+        .collect::<Vec<_>>();
+    if out.is_empty() {
+        anyhow::bail!(
+            "OpenClaw sessions payload missing used token fields (schema {})",
+            schema.as_str()
+        );
+    }
+    Ok(out)
+}
 
-    let mut out = Vec::with_capacity(sessions.len());
-    for entry in sessions {
-        let Some(used) = find_u64(
-            entry,
-            &[
-                &["totalTokens"],
-                &["inputTokens"],
-                &["usage", "totalTokens"],
-                &["usage", "inputTokens"],
-            ],
-        ) else {
-            continue;
-        };
-
-        let session_id = entry
-            .get("key")
-            .and_then(Value::as_str)
-            .or_else(|| entry.get("sessionId").and_then(Value::as_str))
-            .or_else(|| entry.get("id").and_then(Value::as_str))
-            .unwrap_or("current")
-            .to_string();
-
-        let max = find_u64(
-            entry,
-            &[&["contextTokens"], &["maxTokens"], &["limits", "maxTokens"]],
+fn parse_current_with(parsed: &Value, schema: UsageSchema) -> Result<ParsedSessionUsage> {
+    if let Ok(sessions) = parse_sessions_with(parsed, schema)
+        && let Some(latest) = sessions.into_iter().max_by_key(|entry| entry.updated_at)
+    {
+        return Ok(latest);
+    }
+    let fields = schema.fields();
+    let entry = fields
+        .current
+        .and_then(|key| parsed.get(key))
+        .unwrap_or(parsed);
+    parse_session_entry(entry, fields).with_context(|| {
+        format!(
+            "OpenClaw usage payload missing used token fields (schema {})",
+            schema.as_str()
         )
-        .unwrap_or(200_000);
+    })
+}
 
-        out.push(ParsedSessionUsage {
-            session_id,
-            used_tokens: used,
-            max_tokens: max,
-            updated_at: entry.get("updatedAt").and_then(Value::as_u64).unwrap_or(0),
-        });
+/// Runs `parse` for each schema in probe order and returns the first that reads the payload;
+/// when none does, the error is the first schema's.
+fn probe_schemas<T>(
+    raw: &str,
+    what: &str,
+    pinned: Option<UsageSchema>,
+    parse: impl Fn(&Value, UsageSchema) -> Result<T>,
+) -> Result<(UsageSchema, T)> {
+    let parsed: Value =
+        serde_json::from_str(raw).with_context(|| format!("invalid OpenClaw {what} JSON"))?;
+    let mut first_err = None;
+    for schema in probe_order(&parsed, pinned) {
+        match parse(&parsed, schema) {
+            Ok(out) => return Ok((schema, out)),
+            Err(err) => {
+                first_err.get_or_insert(err);
+            }
+        }
     }
+    Err(first_err.unwrap_or_else(|| anyhow::anyhow!("no OpenClaw usage schema to try")))
+}
 
-    if out.is_empty() {
-        anyhow::bail!("OpenClaw sessions payload missing used token fields");
+fn parse_openclaw_usage(
+    raw: &str,
+    pinned: Option<UsageSchema>,
+) -> Result<(UsageSchema, ParsedSessionUsage)> {
+    probe_schemas(raw, "usage", pinned, parse_current_with)
+}
+
+pub fn payload_failures_dir(paths: &MoonPaths) -> PathBuf {
+    paths.logs_dir.join("payload_failures")
+}
+
+/// Saves the first `PAYLOAD_CAPTURE_MAX_BYTES` of a payload that failed to parse and removes
+/// all but the newest `PAYLOAD_CAPTURE_KEEP` captures.
+fn capture_payload_failure(paths: &MoonPaths, label: &str, raw: &str) -> Result<PathBuf> {
+    let dir = payload_failures_dir(paths);
+    fs::create_dir_all(&dir).with_context(|| format!("failed to create {}", dir.display()))?;
+    let now_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .context("system clock is before UNIX_EPOCH")?
+        .as_millis();
+    let path = dir.join(format!("{now_ms:015}-{label}.txt"));
+    let mut end = raw.len().min(PAYLOAD_CAPTURE_MAX_BYTES);
+    while !raw.is_char_boundary(end) {
+        end -= 1;
     }
+    fs::write(&path, &raw[..end]).with_context(|| format!("failed to write {}", path.display()))?;
 
-    Ok(out)
+    let mut captures = fs::read_dir(&dir)
+        .with_context(|| format!("failed to read {}", dir.display()))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "txt"))
+        .collect::<Vec<_>>();
+    captures.sort();
+    let excess = captures.len().saturating_sub(PAYLOAD_CAPTURE_KEEP);
+    for old in captures.into_iter().take(excess) {
+        let _ = fs::remove_file(old);
+    }
+    Ok(path)
+}
+
+/// `parse_openclaw_usage` that, on failure, keeps a copy of the payload and names it in a
+/// `USAGE_PAYLOAD_UNPARSEABLE` warning so format changes can be diagnosed after the fact.
+fn parse_openclaw_usage_or_capture(
+    paths: &MoonPaths,
+    raw: &str,
+) -> Result<(UsageSchema, ParsedSessionUsage)> {
+    parse_openclaw_usage(raw, configured_usage_schema()).inspect_err(|err| {
+        let capture = if read_only_mode() {
+            "read-only".to_string()
+        } else {
+            match capture_payload_failure(paths, "openclaw-usage", raw) {
+                Ok(path) => path.display().to_string(),
+                Err(capture_err) => format!("capture-failed:{capture_err:#}"),
+            }
+        };
+        warn::emit(WarnEvent {
+            code: "USAGE_PAYLOAD_UNPARSEABLE",
+            stage: "usage",
+            action: "parse-openclaw-usage",
+            session: "na",
+            archive: "na",
+            source: &capture,
+            retry: "retry-next-cycle",
+            reason: "usage-payload-unparseable",
+            err: &format!("{err:#}"),
+        });
+    })
+}
+
+fn parse_openclaw_sessions(
+    raw: &str,
+    pinned: Option<UsageSchema>,
+) -> Result<(UsageSchema, Vec<ParsedSessionUsage>)> {
+    probe_schemas(raw, "sessions", pinned, parse_sessions_with)
 }
 
 impl SessionUsageProvider for OpenClawUsageProvider {
@@ -225,7 +410,7 @@ impl SessionUsageProvider for OpenClawUsageProvider {
         "openclaw"
     }
 
-    fn collect(&self, _paths: &MoonPaths) -> Result<SessionUsageSnapshot> {
+    fn collect(&self, paths: &MoonPaths) -> Result<SessionUsageSnapshot> {
         let bin = resolve_openclaw_bin_path()?;
         let args = openclaw_usage_args();
         let mut cmd = Command::new(&bin);
@@ -241,8 +426,13 @@ impl SessionUsageProvider for OpenClawUsageProvider {
         }
 
         let raw = String::from_utf8_lossy(&output.stdout).to_string();
-        let (session_id, used, max) = parse_openclaw_usage(&raw)?;
-        to_snapshot(session_id, used, max, self.name())
+        let (_, current) = parse_openclaw_usage_or_capture(paths, &raw)?;
+        to_snapshot(
+            current.session_id,
+            current.used_tokens,
+            current.max_tokens,
+            self.name(),
+        )
     }
 }
 
@@ -267,7 +457,7 @@ pub fn collect_openclaw_usage_batch() -> Result<OpenClawUsageBatch> {
     }
 
     let raw = String::from_utf8_lossy(&output.stdout).to_string();
-    let parsed = parse_openclaw_sessions(&raw)?;
+    let (schema, parsed) = parse_openclaw_sessions(&raw, configured_usage_schema())?;
     let captured_at_epoch_secs = epoch_now()?;
     let sessions = parsed
         .iter()
@@ -294,20 +484,31 @@ pub fn collect_openclaw_usage_batch() -> Result<OpenClawUsageBatch> {
         captured_at_epoch_secs,
     );
 
-    Ok(OpenClawUsageBatch { current, sessions })
+    Ok(OpenClawUsageBatch {
+        current,
+        sessions,
+        schema,
+    })
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{parse_openclaw_sessions, parse_openclaw_usage};
+    use super::{
+        PAYLOAD_CAPTURE_KEEP, PAYLOAD_CAPTURE_MAX_BYTES, UsageSchema, parse_openclaw_sessions,
+        parse_openclaw_usage, parse_openclaw_usage_or_capture, parse_usage_schema,
+        payload_failures_dir,
+    };
+    use crate::moon::paths::MoonPaths;
+    use std::fs;
+    use tempfile::tempdir;
 
     #[test]
     fn parse_openclaw_usage_accepts_nested_payload() {
         let raw = r#"{"id":"abc","usage":{"totalTokens":4200},"limits":{"maxTokens":10000}}"#;
-        let parsed = parse_openclaw_usage(raw).expect("parse should succeed");
-        assert_eq!(parsed.0, "abc");
-        assert_eq!(parsed.1, 4200);
-        assert_eq!(parsed.2, 10000);
+        let (_, parsed) = parse_openclaw_usage(raw, None).expect("parse should succeed");
+        assert_eq!(parsed.session_id, "abc");
+        assert_eq!(parsed.used_tokens, 4200);
+        assert_eq!(parsed.max_tokens, 10000);
     }
 
     #[test]
@@ -319,10 +520,10 @@ mod tests {
                 {"key":"newer","updatedAt":2000,"totalTokens":86000,"contextTokens":64000}
             ]
         }"#;
-        let parsed = parse_openclaw_usage(raw).expect("parse should succeed");
-        assert_eq!(parsed.0, "newer");
-        assert_eq!(parsed.1, 86000);
-        assert_eq!(parsed.2, 64000);
+        let (_, parsed) = parse_openclaw_usage(raw, None).expect("parse should succeed");
+        assert_eq!(parsed.session_id, "newer");
+        assert_eq!(parsed.used_tokens, 86000);
+        assert_eq!(parsed.max_tokens, 64000);
     }
 
     #[test]
@@ -334,7 +535,7 @@ mod tests {
                 {"key":"agent:main:whatsapp:+614","updatedAt":2000,"totalTokens":86000,"contextTokens":64000}
             ]
         }"#;
-        let parsed = parse_openclaw_sessions(raw).expect("parse should succeed");
+        let (_, parsed) = parse_openclaw_sessions(raw, None).expect("parse should succeed");
         assert_eq!(parsed.len(), 2);
         assert_eq!(parsed[0].session_id, "agent:main:discord:channel:1");
         assert_eq!(parsed[0].used_tokens, 1200);
@@ -353,10 +554,10 @@ mod tests {
                 {"key":"good","updatedAt":1000,"totalTokens":86000,"contextTokens":64000}
             ]
         }"#;
-        let parsed = parse_openclaw_usage(raw).expect("parse should succeed");
-        assert_eq!(parsed.0, "good");
-        assert_eq!(parsed.1, 86000);
-        assert_eq!(parsed.2, 64000);
+        let (_, parsed) = parse_openclaw_usage(raw, None).expect("parse should succeed");
+        assert_eq!(parsed.session_id, "good");
+        assert_eq!(parsed.used_tokens, 86000);
+        assert_eq!(parsed.max_tokens, 64000);
     }
 
     #[test]
@@ -368,10 +569,99 @@ mod tests {
                 {"key":"good","totalTokens":2000,"contextTokens":32000}
             ]
         }"#;
-        let parsed = parse_openclaw_sessions(raw).expect("parse should succeed");
+        let (_, parsed) = parse_openclaw_sessions(raw, None).expect("parse should succeed");
         assert_eq!(parsed.len(), 1);
         assert_eq!(parsed[0].session_id, "good");
         assert_eq!(parsed[0].used_tokens, 2000);
         assert_eq!(parsed[0].max_tokens, 32000);
     }
+
+    const V1_SESSIONS: &str = include_str!("../../tests/fixtures/openclaw_usage/v1_sessions.json");
+    const V1_CURRENT: &str = include_str!("../../tests/fixtures/openclaw_usage/v1_current.json");
+    const V2_SESSIONS: &str = include_str!("../../tests/fixtures/openclaw_usage/v2_sessions.json");
+    const V2_CURRENT: &str = include_str!("../../tests/fixtures/openclaw_usage/v2_current.json");
+    const V2_UNDECLARED: &str =
+        include_str!("../../tests/fixtures/openclaw_usage/v2_undeclared_sessions.json");
+
+    #[test]
+    fn usage_fixtures_parse_with_their_schema() {
+        for (fixture, schema) in [
+            (V1_SESSIONS, UsageSchema::V1),
+            (V2_SESSIONS, UsageSchema::V2),
+        ] {
+            let (used, sessions) =
+                parse_openclaw_sessions(fixture, None).expect("sessions fixture");
+            assert_eq!(used, schema);
+            let rows = sessions
+                .iter()
+                .map(|s| {
+                    (
+                        s.session_id.as_str(),
+                        s.used_tokens,
+                        s.max_tokens,
+                        s.updated_at,
+                    )
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(
+                rows,
+                [
+                    ("agent:main:discord:channel:ops", 9000, 10000, 1_700_000_200),
+                    ("agent:main:main", 1000, 10000, 1_700_000_100),
+                ]
+            );
+        }
+        for (fixture, schema) in [(V1_CURRENT, UsageSchema::V1), (V2_CURRENT, UsageSchema::V2)] {
+            let (used, current) = parse_openclaw_usage(fixture, None).expect("current fixture");
+            assert_eq!(used, schema);
+            assert_eq!(current.session_id, "agent:main:main");
+            assert_eq!(current.used_tokens, 4200);
+            assert_eq!(current.max_tokens, 10000);
+        }
+    }
+
+    #[test]
+    fn auto_schema_probes_renamed_fields_and_pinned_schema_does_not() {
+        let (schema, sessions) =
+            parse_openclaw_sessions(V2_UNDECLARED, None).expect("probe falls back to v2");
+        assert_eq!(schema, UsageSchema::V2);
+        assert_eq!(sessions[0].used_tokens, 2500);
+        assert_eq!(sessions[0].updated_at, 1_700_000_100);
+
+        let err = parse_openclaw_sessions(V2_UNDECLARED, Some(UsageSchema::V1))
+            .expect_err("pinned v1 rejects v2 fields");
+        assert!(format!("{err:#}").contains("schema v1"));
+        let (schema, _) = parse_openclaw_usage(V1_CURRENT, Some(UsageSchema::V1)).expect("v1");
+        assert_eq!(schema, UsageSchema::V1);
+
+        assert_eq!(parse_usage_schema(" AUTO ").expect("auto"), None);
+        assert_eq!(parse_usage_schema("v2").expect("v2"), Some(UsageSchema::V2));
+        assert!(parse_usage_schema("v3").is_err());
+    }
+
+    #[test]
+    fn unparseable_usage_payloads_are_captured_truncated_and_rotated() {
+        let tmp = tempdir().expect("tempdir");
+        let root = tmp.path();
+        let paths = MoonPaths::for_test(root);
+        let dir = payload_failures_dir(&paths);
+        fs::create_dir_all(&dir).expect("mkdir captures");
+        for idx in 0..PAYLOAD_CAPTURE_KEEP {
+            fs::write(dir.join(format!("{idx:015}-openclaw-usage.txt")), "old").expect("seed");
+        }
+
+        let raw = format!("{{\"tokens\":\"{}\"", "x".repeat(PAYLOAD_CAPTURE_MAX_BYTES));
+        assert!(parse_openclaw_usage_or_capture(&paths, &raw).is_err());
+
+        let mut captures = fs::read_dir(&dir)
+            .expect("read captures")
+            .map(|entry| entry.expect("entry").path())
+            .collect::<Vec<_>>();
+        captures.sort();
+        assert_eq!(captures.len(), PAYLOAD_CAPTURE_KEEP);
+        assert!(!captures.contains(&dir.join(format!("{:015}-openclaw-usage.txt", 0))));
+        let newest = fs::read_to_string(captures.last().expect("newest")).expect("read");
+        assert_eq!(newest.len(), PAYLOAD_CAPTURE_MAX_BYTES);
+        assert!(newest.starts_with("{\"tokens\":\"xxx"));
+    }
 }
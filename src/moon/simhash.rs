@@ -0,0 +1,143 @@
+use crate::moon::distill::ProjectionData;
+
+/// Words per shingle; three-word windows keep reordered boilerplate from matching.
+const SHINGLE_WORDS: usize = 3;
+/// Conversations with fewer shingles carry too little text to call two of them the same.
+const MIN_SHINGLES: usize = 24;
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// FNV-1a, chosen over `DefaultHasher` because fingerprints are persisted in the ledger and
+/// must not change between Rust releases.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(FNV_OFFSET_BASIS, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(FNV_PRIME)
+    })
+}
+
+/// 64-bit simhash over the word shingles of a projection's conversation, or `None` when the
+/// conversation is too short to fingerprint. Session ids, paths and timestamps are not part
+/// of the input, so a renamed session hashes like the original.
+pub fn projection_simhash(data: &ProjectionData) -> Option<u64> {
+    let words = data
+        .entries
+        .iter()
+        .flat_map(|entry| {
+            entry
+                .content
+                .split(|ch: char| !ch.is_alphanumeric())
+                .filter(|word| !word.is_empty())
+                .map(str::to_lowercase)
+        })
+        .collect::<Vec<_>>();
+    if words.len() < SHINGLE_WORDS + MIN_SHINGLES - 1 {
+        return None;
+    }
+
+    let mut weights = [0i64; 64];
+    for shingle in words.windows(SHINGLE_WORDS) {
+        let hash = fnv1a(shingle.join(" ").as_bytes());
+        for (bit, weight) in weights.iter_mut().enumerate() {
+            if hash >> bit & 1 == 1 {
+                *weight += 1;
+            } else {
+                *weight -= 1;
+            }
+        }
+    }
+    Some(
+        weights
+            .iter()
+            .enumerate()
+            .filter(|(_, weight)| **weight > 0)
+            .fold(0u64, |acc, (bit, _)| acc | 1 << bit),
+    )
+}
+
+pub fn format_simhash(hash: u64) -> String {
+    format!("{hash:016x}")
+}
+
+pub fn parse_simhash(raw: &str) -> Option<u64> {
+    u64::from_str_radix(raw.trim(), 16).ok()
+}
+
+/// Number of differing bits between two fingerprints.
+pub fn distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{distance, format_simhash, parse_simhash, projection_simhash};
+    use crate::moon::distill::{ProjectionData, ProjectionEntry};
+
+    fn projection(lines: &[&str]) -> ProjectionData {
+        ProjectionData {
+            entries: lines
+                .iter()
+                .enumerate()
+                .map(|(idx, line)| ProjectionEntry {
+                    timestamp_epoch: None,
+                    role: if idx % 2 == 0 { "user" } else { "assistant" }.to_string(),
+                    content: line.to_string(),
+                    tool_name: None,
+                    tool_target: None,
+                    priority: None,
+                    coupled_result: None,
+                    source_line: None,
+                    source_byte_offset: None,
+                })
+                .collect(),
+            tool_calls: Vec::new(),
+            keywords: Vec::new(),
+            topics: Vec::new(),
+            time_start_epoch: None,
+            time_end_epoch: None,
+            message_count: lines.len(),
+            filtered_noise_count: 0,
+            truncated: false,
+            compaction_anchors: Vec::new(),
+            sample_stride: 0,
+        }
+    }
+
+    const CONVERSATION: [&str; 4] = [
+        "Can you move the nightly backup job from the cron host to the new scheduler?",
+        "Sure. I moved the backup job, kept the retention at fourteen days, and added an alert when a run takes longer than an hour.",
+        "Great, also make sure the restore drill runs every Friday against the staging database.",
+        "Done: the restore drill now runs Fridays at noon against staging and posts its result to the ops channel.",
+    ];
+
+    #[test]
+    fn near_identical_conversations_hash_close_and_unrelated_ones_far() {
+        let original = projection_simhash(&projection(&CONVERSATION)).expect("hash");
+        let mut edited = CONVERSATION;
+        edited[3] = "Done: the restore drill now runs Fridays at noon against staging and posts its result to the ops room.";
+        let near = projection_simhash(&projection(&edited)).expect("hash");
+        let unrelated = projection_simhash(&projection(&[
+            "Draft a release note for the tokenizer upgrade and the new recall deadline option.",
+            "Here is a draft covering the tokenizer switch to cl100k, the recall deadline_ms setting, and degraded results.",
+            "Shorten it to three bullets and drop the migration section entirely please.",
+            "Three bullets: faster token counts, bounded recall latency, and cached matches when qmd is slow.",
+        ]))
+        .expect("hash");
+
+        assert!(
+            distance(original, near) <= 3,
+            "{}",
+            distance(original, near)
+        );
+        assert!(distance(original, unrelated) > 10);
+        assert_eq!(parse_simhash(&format_simhash(original)), Some(original));
+    }
+
+    #[test]
+    fn short_conversations_are_not_fingerprinted() {
+        assert_eq!(
+            projection_simhash(&projection(&["hi", "hello there"])),
+            None
+        );
+    }
+}
@@ -1,3 +1,5 @@
+use crate::moon::archive::portable_path_string;
+use crate::moon::session_file::{read_session_bytes, session_extension, session_file_stem};
 use anyhow::{Context, Result};
 use std::fs;
 use std::path::{Path, PathBuf};
@@ -10,10 +12,52 @@ pub struct SnapshotOutcome {
     pub bytes: usize,
 }
 
-fn is_session_snapshot_candidate(path: &Path) -> bool {
+pub(crate) fn glob_matches(pattern: &[u8], text: &[u8]) -> bool {
+    let (mut p, mut t) = (0usize, 0usize);
+    let mut backtrack: Option<(usize, usize)> = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == b'?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == b'*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = backtrack {
+            p = star_p + 1;
+            t = star_t + 1;
+            backtrack = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == b'*')
+}
+
+/// True when `path` matches one of the `*`/`?` exclude globs.
+///
+/// Patterns containing `/` match the full path; other patterns match the file name only.
+/// Windows `\` separators in the path or the pattern are compared as `/`.
+pub fn is_snapshot_excluded(path: &Path, exclude: &[String]) -> bool {
+    let full_path = portable_path_string(path).to_ascii_lowercase();
+    let file_name = full_path.rsplit('/').next().unwrap_or_default().to_string();
+    exclude.iter().any(|pattern| {
+        let pattern = pattern.trim().replace('\\', "/").to_ascii_lowercase();
+        let target = if pattern.contains('/') {
+            &full_path
+        } else {
+            &file_name
+        };
+        !pattern.is_empty() && glob_matches(pattern.as_bytes(), target.as_bytes())
+    })
+}
+
+fn is_session_snapshot_candidate(path: &Path, exclude: &[String]) -> bool {
     let Some(file_name) = path.file_name().and_then(|s| s.to_str()) else {
         return false;
     };
+    if is_snapshot_excluded(path, exclude) {
+        return false;
+    }
 
     let lower_name = file_name.to_ascii_lowercase();
     if lower_name.ends_with(".lock")
@@ -24,14 +68,10 @@ fn is_session_snapshot_candidate(path: &Path) -> bool {
         return false;
     }
 
-    let ext = path
-        .extension()
-        .and_then(|s| s.to_str())
-        .map(|s| s.to_ascii_lowercase());
-
-    match ext.as_deref() {
+    // Rotated `.jsonl.gz` / `.json.gz` sessions are candidates too.
+    match session_extension(path).as_deref() {
         Some("jsonl") => true,
-        Some("json") => lower_name != "sessions.json",
+        Some("json") => !matches!(lower_name.as_str(), "sessions.json" | "sessions.json.gz"),
         _ => false,
     }
 }
@@ -60,7 +100,7 @@ fn epoch_seconds_string() -> Result<String> {
     Ok(secs.to_string())
 }
 
-pub fn latest_session_file(dir: &Path) -> Result<Option<PathBuf>> {
+pub fn latest_session_file(dir: &Path, exclude: &[String]) -> Result<Option<PathBuf>> {
     let mut latest: Option<(std::time::SystemTime, PathBuf)> = None;
     let read_dir =
         fs::read_dir(dir).with_context(|| format!("failed to read {}", dir.display()))?;
@@ -71,7 +111,7 @@ pub fn latest_session_file(dir: &Path) -> Result<Option<PathBuf>> {
         if !path.is_file() {
             continue;
         }
-        if !is_session_snapshot_candidate(&path) {
+        if !is_session_snapshot_candidate(&path, exclude) {
             continue;
         }
         let meta = entry.metadata()?;
@@ -85,35 +125,57 @@ pub fn latest_session_file(dir: &Path) -> Result<Option<PathBuf>> {
     Ok(latest.map(|(_, p)| p))
 }
 
-pub fn write_snapshot(archives_dir: &Path, source_path: &Path) -> Result<SnapshotOutcome> {
-    fs::create_dir_all(archives_dir)
-        .with_context(|| format!("failed to create {}", archives_dir.display()))?;
-    let raw_archives_dir = archives_dir.join("raw");
-    fs::create_dir_all(&raw_archives_dir)
-        .with_context(|| format!("failed to create {}", raw_archives_dir.display()))?;
+/// `raw/` under `archives_dir`, or its `subdir` for archives of a non-default collection.
+pub fn raw_archive_dir(archives_dir: &Path, subdir: Option<&str>) -> PathBuf {
+    let raw_dir = archives_dir.join("raw");
+    match subdir {
+        Some(subdir) => raw_dir.join(subdir),
+        None => raw_dir,
+    }
+}
 
-    let raw = fs::read(source_path)
-        .with_context(|| format!("failed to read source session {}", source_path.display()))?;
+/// Raw archive path a snapshot of `source_path` taken now would be written to in `raw_dir`;
+/// gzip sources are archived decompressed, so `a.jsonl.gz` lands at `a-<epoch>.jsonl`.
+pub fn planned_snapshot_path(raw_dir: &Path, source_path: &Path) -> Result<PathBuf> {
+    Ok(snapshot_path_at(
+        raw_dir,
+        source_path,
+        &epoch_seconds_string()?,
+    ))
+}
 
-    let ext = source_path
-        .extension()
-        .and_then(|s| s.to_str())
+/// `a.jsonl` and a rotated `a.jsonl.gz` archived in the same second would both land at
+/// `a-<epoch>.jsonl`, so a taken name gets a `-<n>` suffix instead of being overwritten.
+fn snapshot_path_at(raw_dir: &Path, source_path: &Path, stamp: &str) -> PathBuf {
+    let ext = session_extension(source_path)
         .filter(|s| !s.trim().is_empty())
-        .unwrap_or("json");
+        .unwrap_or_else(|| "json".to_string());
 
-    let source_stem = source_path
-        .file_stem()
-        .and_then(|s| s.to_str())
-        .unwrap_or("session");
+    let source_stem = session_file_stem(source_path).unwrap_or("session");
     let slug = sanitize_slug(source_stem);
-    let stamp = epoch_seconds_string()?;
 
-    let filename = if slug.is_empty() {
-        format!("snapshot-{stamp}.{ext}")
+    let base = if slug.is_empty() {
+        format!("snapshot-{stamp}")
     } else {
-        format!("{slug}-{stamp}.{ext}")
+        format!("{slug}-{stamp}")
     };
-    let archive_path = raw_archives_dir.join(filename);
+    let mut candidate = raw_dir.join(format!("{base}.{ext}"));
+    let mut index = 1usize;
+    while candidate.exists() {
+        candidate = raw_dir.join(format!("{base}-{index}.{ext}"));
+        index = index.saturating_add(1);
+    }
+    candidate
+}
+
+/// Copies `source_path` into `raw_dir` (see [`raw_archive_dir`]).
+pub fn write_snapshot(raw_dir: &Path, source_path: &Path) -> Result<SnapshotOutcome> {
+    fs::create_dir_all(raw_dir)
+        .with_context(|| format!("failed to create {}", raw_dir.display()))?;
+
+    let raw = read_session_bytes(source_path)
+        .with_context(|| format!("failed to read source session {}", source_path.display()))?;
+    let archive_path = planned_snapshot_path(raw_dir, source_path)?;
 
     fs::write(&archive_path, &raw)
         .with_context(|| format!("failed to write {}", archive_path.display()))?;
@@ -127,7 +189,14 @@ pub fn write_snapshot(archives_dir: &Path, source_path: &Path) -> Result<Snapsho
 
 #[cfg(test)]
 mod tests {
-    use super::{is_session_snapshot_candidate, sanitize_slug};
+    use super::{
+        is_session_snapshot_candidate, is_snapshot_excluded, sanitize_slug, snapshot_path_at,
+        write_snapshot,
+    };
+    use flate2::Compression;
+    use flate2::write::GzEncoder;
+    use std::fs;
+    use std::io::Write;
     use std::path::Path;
 
     #[test]
@@ -139,21 +208,108 @@ mod tests {
 
     #[test]
     fn snapshot_candidate_filter_excludes_non_session_files() {
-        assert!(is_session_snapshot_candidate(Path::new(
-            "/tmp/abc-123.jsonl"
-        )));
-        assert!(is_session_snapshot_candidate(Path::new(
-            "/tmp/abc-123.json"
-        )));
-        assert!(!is_session_snapshot_candidate(Path::new(
-            "/tmp/sessions.json"
-        )));
-        assert!(!is_session_snapshot_candidate(Path::new(
-            "/tmp/abc-123.jsonl.lock"
-        )));
-        assert!(!is_session_snapshot_candidate(Path::new(
-            "/tmp/abc-123.lock"
-        )));
-        assert!(!is_session_snapshot_candidate(Path::new("/tmp/abc-123.md")));
+        assert!(is_session_snapshot_candidate(
+            Path::new("/tmp/abc-123.jsonl"),
+            &[]
+        ));
+        assert!(is_session_snapshot_candidate(
+            Path::new("/tmp/abc-123.json"),
+            &[]
+        ));
+        assert!(is_session_snapshot_candidate(
+            Path::new("/tmp/abc-123.jsonl.gz"),
+            &[]
+        ));
+        assert!(!is_session_snapshot_candidate(
+            Path::new("/tmp/sessions.json"),
+            &[]
+        ));
+        assert!(!is_session_snapshot_candidate(
+            Path::new("/tmp/abc-123.gz"),
+            &[]
+        ));
+        assert!(!is_session_snapshot_candidate(
+            Path::new("/tmp/abc-123.jsonl.lock"),
+            &[]
+        ));
+        assert!(!is_session_snapshot_candidate(
+            Path::new("/tmp/abc-123.lock"),
+            &[]
+        ));
+        assert!(!is_session_snapshot_candidate(
+            Path::new("/tmp/abc-123.md"),
+            &[]
+        ));
+    }
+
+    #[test]
+    fn snapshot_exclude_globs_match_file_names_and_paths() {
+        let exclude = vec!["*-sandbox.jsonl".to_string(), "*/scratch/*".to_string()];
+        assert!(is_snapshot_excluded(
+            Path::new("/tmp/agent-Sandbox.jsonl"),
+            &exclude
+        ));
+        assert!(is_snapshot_excluded(
+            Path::new("/tmp/scratch/abc.jsonl"),
+            &exclude
+        ));
+        assert!(!is_snapshot_excluded(Path::new("/tmp/abc.jsonl"), &exclude));
+        assert!(is_snapshot_excluded(
+            Path::new(r"C:\Users\me\scratch\abc.jsonl"),
+            &exclude
+        ));
+        assert!(is_snapshot_excluded(
+            Path::new(r"C:\Users\me\agent-sandbox.jsonl"),
+            &exclude
+        ));
+        assert!(is_snapshot_excluded(
+            Path::new("/tmp/scratch/abc.jsonl"),
+            &[r"*\scratch\*".to_string()]
+        ));
+        assert!(!is_session_snapshot_candidate(
+            Path::new("/tmp/test-sandbox.jsonl"),
+            &exclude
+        ));
+        assert!(is_session_snapshot_candidate(
+            Path::new("/tmp/test-prod.jsonl"),
+            &exclude
+        ));
+    }
+
+    #[test]
+    fn plain_and_rotated_sessions_archived_together_keep_separate_raw_files() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let raw_dir = tmp.path().join("archives/raw");
+        let sessions_dir = tmp.path().join("sessions");
+        fs::create_dir_all(&sessions_dir).expect("mkdir sessions");
+        let plain = sessions_dir.join("abc.jsonl");
+        fs::write(&plain, "{\"messages\":[\"live\"]}\n").expect("write plain");
+        let rotated = sessions_dir.join("abc.jsonl.gz");
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder
+            .write_all(b"{\"messages\":[\"rotated\"]}\n")
+            .expect("gzip");
+        fs::write(&rotated, encoder.finish().expect("finish gzip")).expect("write rotated");
+
+        let first = write_snapshot(&raw_dir, &plain).expect("archive plain");
+        let second = write_snapshot(&raw_dir, &rotated).expect("archive rotated");
+        assert_ne!(first.archive_path, second.archive_path);
+        assert!(
+            fs::read_to_string(&first.archive_path)
+                .expect("read plain archive")
+                .contains("live")
+        );
+        assert!(
+            fs::read_to_string(&second.archive_path)
+                .expect("read rotated archive")
+                .contains("rotated")
+        );
+
+        // Pinned to one stamp, the second name is the suffixed one.
+        fs::write(raw_dir.join("abc-100.jsonl"), "taken").expect("occupy name");
+        assert_eq!(
+            snapshot_path_at(&raw_dir, &rotated, "100"),
+            raw_dir.join("abc-100-1.jsonl")
+        );
     }
 }
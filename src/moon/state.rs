@@ -1,20 +1,78 @@
+use crate::moon::build_info::BuildInfo;
 use crate::moon::paths::MoonPaths;
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::env;
 use std::fs;
 use std::path::PathBuf;
 
+/// Newest usage samples kept per session.
+pub const USAGE_TREND_CAPACITY: usize = 12;
+/// Trends whose newest sample is older than this are dropped.
+const USAGE_TREND_STALE_SECS: u64 = 7 * 24 * 60 * 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct UsageSample {
+    pub epoch_secs: u64,
+    pub ratio: f64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UsageTrend {
+    pub samples: Vec<UsageSample>,
+    pub last_predictive_archive_epoch_secs: Option<u64>,
+}
+
+/// Current state schema; v4 moved trigger bookkeeping into per-channel records.
+pub const STATE_SCHEMA_VERSION: u32 = 4;
+/// Channel key for bookkeeping not tied to one session key: pre-v4 global fields and syns runs.
+pub const GLOBAL_CHANNEL: &str = "*";
+
+/// Trigger bookkeeping for one channel (OpenClaw session key).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChannelState {
+    pub last_archive_epoch_secs: Option<u64>,
+    pub last_compaction_epoch_secs: Option<u64>,
+    pub last_distill_epoch_secs: Option<u64>,
+    /// Consecutive failures since the last success of each stage.
+    pub archive_failures: u64,
+    pub compaction_failures: u64,
+    pub distill_failures: u64,
+}
+
+/// A moon-requested `/compact` whose summary has not been fetched from the gateway yet.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PendingCompactionAnchor {
+    /// Archive taken right before the compaction; the summary is recorded on its ledger row.
+    pub archive_path: String,
+    pub requested_at_epoch_secs: u64,
+    /// Cycles that looked for the summary without finding it.
+    pub attempts: u64,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct MoonState {
     pub schema_version: u32,
     pub last_heartbeat_epoch_secs: u64,
-    pub last_archive_trigger_epoch_secs: Option<u64>,
-    #[serde(alias = "last_prune_trigger_epoch_secs")]
-    pub last_compaction_trigger_epoch_secs: Option<u64>,
-    pub last_distill_trigger_epoch_secs: Option<u64>,
+    pub channels: BTreeMap<String, ChannelState>,
+    // Pre-v4 global fields, read only so `load` can fold them into `channels`.
+    #[serde(rename = "last_archive_trigger_epoch_secs", skip_serializing)]
+    legacy_last_archive_epoch_secs: Option<u64>,
+    #[serde(
+        rename = "last_compaction_trigger_epoch_secs",
+        alias = "last_prune_trigger_epoch_secs",
+        skip_serializing
+    )]
+    legacy_last_compaction_epoch_secs: Option<u64>,
+    #[serde(rename = "last_distill_trigger_epoch_secs", skip_serializing)]
+    legacy_last_distill_epoch_secs: Option<u64>,
+    #[serde(rename = "consecutive_distill_failures", skip_serializing)]
+    legacy_distill_failures: u64,
     pub last_syns_trigger_epoch_secs: Option<u64>,
     pub last_embed_trigger_epoch_secs: Option<u64>,
     pub last_session_id: Option<String>,
@@ -24,16 +82,42 @@ pub struct MoonState {
     pub embedded_projections: BTreeMap<String, u64>,
     pub compaction_hysteresis_active: BTreeMap<String, u64>,
     pub inbound_seen_files: BTreeMap<String, u64>,
+    pub memory_primer_seeded: bool,
+    pub memory_primed_sessions: BTreeMap<String, u64>,
+    /// Failed memory primer sends per session key, until the primer succeeds or gives up.
+    pub memory_primer_failures: BTreeMap<String, u64>,
+    pub usage_trends: BTreeMap<String, UsageTrend>,
+    pub last_daily_report_day: Option<String>,
+    /// Last `sessionId` seen in `sessions.json` per session key, for rollover detection.
+    pub session_ids: BTreeMap<String, String>,
+    /// Cold archives retention refused to delete (no distilled summary or projection), with
+    /// the epoch they were first refused; only newly refused archives are warned about.
+    pub retention_protected_archives: BTreeMap<String, u64>,
+    /// qmd collections changed since their last sync. The watcher flushes them with one sync
+    /// per cycle; entries survive a crash and are retried until a sync succeeds.
+    pub pending_qmd_sync: BTreeSet<String>,
+    /// Completed (non-dry-run, unpaused) watch cycles, for jobs that run every Nth cycle.
+    pub watch_cycles: u64,
+    /// Compactions awaiting their gateway-reported summary, by session key.
+    pub pending_compaction_anchors: BTreeMap<String, PendingCompactionAnchor>,
+    /// When the OpenClaw sessions dir was first found unreadable; cleared once it reads again.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sessions_dir_unavailable_since_epoch_secs: Option<u64>,
+    /// Build of the daemon that wrote the last heartbeat.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub build: Option<BuildInfo>,
 }
 
 impl Default for MoonState {
     fn default() -> Self {
         Self {
-            schema_version: 3,
+            schema_version: STATE_SCHEMA_VERSION,
             last_heartbeat_epoch_secs: 0,
-            last_archive_trigger_epoch_secs: None,
-            last_compaction_trigger_epoch_secs: None,
-            last_distill_trigger_epoch_secs: None,
+            channels: BTreeMap::new(),
+            legacy_last_archive_epoch_secs: None,
+            legacy_last_compaction_epoch_secs: None,
+            legacy_last_distill_epoch_secs: None,
+            legacy_distill_failures: 0,
             last_syns_trigger_epoch_secs: None,
             last_embed_trigger_epoch_secs: None,
             last_session_id: None,
@@ -43,7 +127,88 @@ impl Default for MoonState {
             embedded_projections: BTreeMap::new(),
             compaction_hysteresis_active: BTreeMap::new(),
             inbound_seen_files: BTreeMap::new(),
+            memory_primer_seeded: false,
+            memory_primed_sessions: BTreeMap::new(),
+            memory_primer_failures: BTreeMap::new(),
+            usage_trends: BTreeMap::new(),
+            last_daily_report_day: None,
+            session_ids: BTreeMap::new(),
+            retention_protected_archives: BTreeMap::new(),
+            pending_qmd_sync: BTreeSet::new(),
+            watch_cycles: 0,
+            pending_compaction_anchors: BTreeMap::new(),
+            sessions_dir_unavailable_since_epoch_secs: None,
+            build: None,
+        }
+    }
+}
+
+impl MoonState {
+    pub fn channel_mut(&mut self, key: &str) -> &mut ChannelState {
+        self.channels.entry(key.to_string()).or_default()
+    }
+
+    fn newest(&self, field: impl Fn(&ChannelState) -> Option<u64>) -> Option<u64> {
+        self.channels.values().filter_map(field).max()
+    }
+
+    /// Newest archive trigger across all channels.
+    pub fn last_archive_epoch_secs(&self) -> Option<u64> {
+        self.newest(|channel| channel.last_archive_epoch_secs)
+    }
+
+    /// Newest compaction trigger across all channels.
+    pub fn last_compaction_epoch_secs(&self) -> Option<u64> {
+        self.newest(|channel| channel.last_compaction_epoch_secs)
+    }
+
+    /// Newest distill trigger across all channels.
+    pub fn last_distill_epoch_secs(&self) -> Option<u64> {
+        self.newest(|channel| channel.last_distill_epoch_secs)
+    }
+
+    /// Newest archive or compaction trigger; both share the layer-1 cooldown.
+    pub fn last_layer1_epoch_secs(&self) -> Option<u64> {
+        self.last_archive_epoch_secs()
+            .max(self.last_compaction_epoch_secs())
+    }
+
+    pub fn record_archive(&mut self, key: &str, epoch_secs: u64) {
+        let channel = self.channel_mut(key);
+        channel.last_archive_epoch_secs = Some(epoch_secs);
+        channel.archive_failures = 0;
+    }
+
+    pub fn record_distill(&mut self, key: &str, epoch_secs: u64) {
+        let channel = self.channel_mut(key);
+        channel.last_distill_epoch_secs = Some(epoch_secs);
+        channel.distill_failures = 0;
+    }
+
+    /// Counts a failed distill for `key` and returns the channel's current streak.
+    pub fn record_distill_failure(&mut self, key: &str) -> u64 {
+        let channel = self.channel_mut(key);
+        channel.distill_failures = channel.distill_failures.saturating_add(1);
+        channel.distill_failures
+    }
+
+    /// Folds pre-v4 global trigger fields into the [`GLOBAL_CHANNEL`] record.
+    ///
+    /// The aggregate accessors take the newest value across channels, so a cooldown that was
+    /// running under the old shape keeps running after the upgrade.
+    fn migrate_legacy_fields(&mut self) {
+        let archive = self.legacy_last_archive_epoch_secs.take();
+        let compaction = self.legacy_last_compaction_epoch_secs.take();
+        let distill = self.legacy_last_distill_epoch_secs.take();
+        let failures = std::mem::take(&mut self.legacy_distill_failures);
+        if archive.is_some() || compaction.is_some() || distill.is_some() || failures > 0 {
+            let global = self.channel_mut(GLOBAL_CHANNEL);
+            global.last_archive_epoch_secs = global.last_archive_epoch_secs.max(archive);
+            global.last_compaction_epoch_secs = global.last_compaction_epoch_secs.max(compaction);
+            global.last_distill_epoch_secs = global.last_distill_epoch_secs.max(distill);
+            global.distill_failures = global.distill_failures.max(failures);
         }
+        self.schema_version = self.schema_version.max(STATE_SCHEMA_VERSION);
     }
 }
 
@@ -76,7 +241,7 @@ pub fn load(paths: &MoonPaths) -> Result<MoonState> {
     let raw =
         fs::read_to_string(&file).with_context(|| format!("failed to read {}", file.display()))?;
 
-    let mut parsed: MoonState = match serde_json::from_str(&raw) {
+    let parsed = match parse(&raw) {
         Ok(s) => s,
         Err(err) => {
             let timestamp = crate::moon::util::now_epoch_secs().unwrap_or(0);
@@ -99,13 +264,18 @@ pub fn load(paths: &MoonPaths) -> Result<MoonState> {
         }
     };
 
-    if parsed.schema_version < 3 {
-        parsed.schema_version = 3;
-    }
+    Ok(parsed)
+}
+
+/// Parses a state file payload, folding any pre-v4 fields into per-channel records.
+pub fn parse(raw: &str) -> serde_json::Result<MoonState> {
+    let mut parsed: MoonState = serde_json::from_str(raw)?;
+    parsed.migrate_legacy_fields();
     Ok(parsed)
 }
 
 pub fn save(paths: &MoonPaths, state: &MoonState) -> Result<PathBuf> {
+    crate::moon::util::ensure_writable("state save")?;
     let file = state_file_path(paths);
     if let Some(parent) = file.parent() {
         fs::create_dir_all(parent)
@@ -158,9 +328,41 @@ pub fn rewrite_distilled_archive_paths(
     Ok(rewritten)
 }
 
+/// Appends a usage sample to the session's ring buffer.
+///
+/// A drop in ratio means the context was compacted or reset, so the older samples no
+/// longer describe the current growth and are discarded.
+pub fn record_usage_sample(state: &mut MoonState, session_id: &str, epoch_secs: u64, ratio: f64) {
+    let trend = state
+        .usage_trends
+        .entry(session_id.to_string())
+        .or_default();
+    if let Some(last) = trend.samples.last() {
+        if ratio < last.ratio {
+            trend.samples.clear();
+        } else if epoch_secs <= last.epoch_secs {
+            trend.samples.pop();
+        }
+    }
+    trend.samples.push(UsageSample { epoch_secs, ratio });
+    let overflow = trend.samples.len().saturating_sub(USAGE_TREND_CAPACITY);
+    trend.samples.drain(..overflow);
+}
+
+pub fn prune_usage_trends(state: &mut MoonState, now_epoch_secs: u64) {
+    state.usage_trends.retain(|_, trend| {
+        trend.samples.last().is_some_and(|sample| {
+            now_epoch_secs.saturating_sub(sample.epoch_secs) < USAGE_TREND_STALE_SECS
+        })
+    });
+}
+
 #[cfg(test)]
 mod tests {
-    use super::MoonState;
+    use super::{
+        GLOBAL_CHANNEL, MoonState, STATE_SCHEMA_VERSION, USAGE_TREND_CAPACITY, parse,
+        record_usage_sample,
+    };
 
     #[test]
     fn deserializes_v1_state_with_embed_defaults() {
@@ -174,4 +376,47 @@ mod tests {
         assert!(parsed.last_embed_trigger_epoch_secs.is_none());
         assert!(parsed.embedded_projections.is_empty());
     }
+
+    #[test]
+    fn migrates_flat_trigger_fields_into_global_channel() {
+        let raw = r#"{
+  "schema_version": 3,
+  "last_archive_trigger_epoch_secs": 100,
+  "last_prune_trigger_epoch_secs": 120,
+  "last_distill_trigger_epoch_secs": null,
+  "consecutive_distill_failures": 2
+}"#;
+        let mut parsed = parse(raw).expect("parse state");
+        assert_eq!(parsed.schema_version, STATE_SCHEMA_VERSION);
+        let global = &parsed.channels[GLOBAL_CHANNEL];
+        assert_eq!(global.last_archive_epoch_secs, Some(100));
+        assert_eq!(global.last_compaction_epoch_secs, Some(120));
+        assert_eq!(global.distill_failures, 2);
+
+        parsed
+            .channel_mut("agent:main:discord:channel:1")
+            .last_compaction_epoch_secs = Some(150);
+        assert_eq!(parsed.last_layer1_epoch_secs(), Some(150));
+        assert_eq!(parsed.last_distill_epoch_secs(), None);
+
+        let saved = serde_json::to_value(&parsed).expect("serialize state");
+        assert!(saved.get("last_archive_trigger_epoch_secs").is_none());
+        assert!(saved.get("consecutive_distill_failures").is_none());
+        let reloaded: MoonState = serde_json::from_value(saved).expect("reparse state");
+        assert_eq!(reloaded.channels, parsed.channels);
+    }
+
+    #[test]
+    fn usage_samples_form_a_bounded_ring_that_resets_on_drop() {
+        let mut state = MoonState::default();
+        for i in 0..(USAGE_TREND_CAPACITY as u64 + 3) {
+            record_usage_sample(&mut state, "s", i * 30, 0.1 + i as f64 * 0.01);
+        }
+        let trend = &state.usage_trends["s"];
+        assert_eq!(trend.samples.len(), USAGE_TREND_CAPACITY);
+        assert_eq!(trend.samples[0].epoch_secs, 90);
+
+        record_usage_sample(&mut state, "s", 1_000, 0.05);
+        assert_eq!(state.usage_trends["s"].samples.len(), 1);
+    }
 }
@@ -1,11 +1,31 @@
 use crate::moon::config::MoonConfig;
 use crate::moon::session_usage::SessionUsageSnapshot;
-use crate::moon::state::MoonState;
+use crate::moon::state::{MoonState, UsageSample};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TriggerKind {
     Archive,
     Compaction,
+    Distill,
+}
+
+impl TriggerKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TriggerKind::Archive => "archive",
+            TriggerKind::Compaction => "compaction",
+            TriggerKind::Distill => "distill",
+        }
+    }
+
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim() {
+            "archive" => Some(TriggerKind::Archive),
+            "compaction" => Some(TriggerKind::Compaction),
+            "distill" => Some(TriggerKind::Distill),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -41,17 +61,6 @@ pub fn evaluate_context_compaction_candidate(
     }
 }
 
-fn unified_layer1_last_trigger(state: &MoonState) -> Option<u64> {
-    match (
-        state.last_archive_trigger_epoch_secs,
-        state.last_compaction_trigger_epoch_secs,
-    ) {
-        (Some(a), Some(b)) => Some(a.max(b)),
-        (Some(v), None) | (None, Some(v)) => Some(v),
-        (None, None) => None,
-    }
-}
-
 fn should_fire(last_epoch: Option<u64>, now_epoch: u64, cooldown_secs: u64) -> bool {
     match last_epoch {
         None => true,
@@ -59,6 +68,33 @@ fn should_fire(last_epoch: Option<u64>, now_epoch: u64, cooldown_secs: u64) -> b
     }
 }
 
+/// Last trigger the compaction cooldown counts from. With a separate archive ratio, early
+/// archives must not hold back compaction, so only compactions count.
+pub fn compaction_cooldown_epoch_secs(cfg: &MoonConfig, state: &MoonState) -> Option<u64> {
+    if cfg.thresholds.archive_ratio_trigger_enabled {
+        state.last_compaction_epoch_secs()
+    } else {
+        state.last_layer1_epoch_secs()
+    }
+}
+
+/// Archive-only trigger for usage between `[thresholds] archive_ratio` and the compaction
+/// threshold; never fires unless `archive_ratio_trigger_enabled`.
+pub fn evaluate_archive_only(
+    cfg: &MoonConfig,
+    state: &MoonState,
+    usage: &SessionUsageSnapshot,
+) -> Option<TriggerKind> {
+    (cfg.thresholds.archive_ratio_trigger_enabled
+        && usage.usage_ratio >= cfg.thresholds.archive_ratio
+        && should_fire(
+            state.last_archive_epoch_secs(),
+            usage.captured_at_epoch_secs,
+            cfg.watcher.cooldown_secs,
+        ))
+    .then_some(TriggerKind::Archive)
+}
+
 pub fn evaluate(
     cfg: &MoonConfig,
     state: &MoonState,
@@ -68,7 +104,7 @@ pub fn evaluate(
     let now = usage.captured_at_epoch_secs;
     if usage.usage_ratio >= cfg.thresholds.trigger_ratio
         && should_fire(
-            unified_layer1_last_trigger(state),
+            compaction_cooldown_epoch_secs(cfg, state),
             now,
             cfg.watcher.cooldown_secs,
         )
@@ -76,11 +112,105 @@ pub fn evaluate(
         // Unified trigger: archive-before-compact protocol.
         out.push(TriggerKind::Archive);
         out.push(TriggerKind::Compaction);
+    } else if usage.usage_ratio < cfg.thresholds.trigger_ratio {
+        out.extend(evaluate_archive_only(cfg, state, usage));
     }
 
     out
 }
 
+/// Distill trigger inputs that live outside `MoonConfig`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DistillTriggerInput {
+    pub now_epoch_secs: u64,
+    pub last_distill_epoch_secs: Option<u64>,
+    /// Newest session activity (latest session file mtime); `None` counts as idle.
+    pub last_activity_epoch_secs: Option<u64>,
+    /// Distill now regardless of mode, cooldown and idle time.
+    pub forced: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DistillTriggerDecision {
+    /// `Some(TriggerKind::Distill)` when pending archives should be selected this cycle.
+    pub trigger: Option<TriggerKind>,
+    /// Archives selected at most when the trigger fires.
+    pub max_per_cycle: u64,
+    /// `ready`, `manual_trigger=true`, or `skipped reason=...`.
+    pub note: String,
+}
+
+/// Distill cooldown: `[distill] cooldown_secs`, else the shared watcher cooldown.
+pub fn distill_cooldown_secs(cfg: &MoonConfig) -> u64 {
+    cfg.distill
+        .cooldown_secs
+        .unwrap_or(cfg.watcher.cooldown_secs)
+}
+
+/// Whether this cycle distills pending archives, with the reason when it does not.
+pub fn evaluate_distill(cfg: &MoonConfig, input: DistillTriggerInput) -> DistillTriggerDecision {
+    let decision = |fire: bool, note: String| DistillTriggerDecision {
+        trigger: fire.then_some(TriggerKind::Distill),
+        max_per_cycle: cfg.distill.max_per_cycle,
+        note,
+    };
+    if input.forced {
+        return decision(true, "manual_trigger=true".to_string());
+    }
+    if cfg.distill.mode == "manual" {
+        return decision(false, "skipped reason=mode-manual".to_string());
+    }
+    let cooldown_secs = distill_cooldown_secs(cfg);
+    if !should_fire(
+        input.last_distill_epoch_secs,
+        input.now_epoch_secs,
+        cooldown_secs,
+    ) {
+        return decision(
+            false,
+            format!("skipped reason=cooldown cooldown_secs={cooldown_secs}"),
+        );
+    }
+    let idle_secs = cfg.distill.idle_secs;
+    if idle_secs > 0
+        && let Some(last_activity) = input.last_activity_epoch_secs
+    {
+        let idle_for = input.now_epoch_secs.saturating_sub(last_activity);
+        if idle_for < idle_secs {
+            return decision(
+                false,
+                format!("skipped reason=not-idle idle_secs={idle_secs} idle_for_secs={idle_for}"),
+            );
+        }
+    }
+    decision(true, "ready".to_string())
+}
+
+/// Ratio expected `horizon_secs` from the newest sample, extrapolating the average
+/// growth across the sampled window. `None` when there is no growth to project.
+pub fn projected_usage_ratio(samples: &[UsageSample], horizon_secs: u64) -> Option<f64> {
+    let (first, last) = (samples.first()?, samples.last()?);
+    let elapsed = last.epoch_secs.checked_sub(first.epoch_secs)?;
+    if elapsed == 0 || last.ratio <= first.ratio {
+        return None;
+    }
+    let growth_per_sec = (last.ratio - first.ratio) / elapsed as f64;
+    Some(last.ratio + growth_per_sec * horizon_secs as f64)
+}
+
+/// True when usage is still below `threshold` but is projected to reach it within `horizon_secs`.
+pub fn predicts_threshold_crossing(
+    samples: &[UsageSample],
+    threshold: f64,
+    horizon_secs: u64,
+) -> bool {
+    let Some(last) = samples.last() else {
+        return false;
+    };
+    last.ratio < threshold
+        && projected_usage_ratio(samples, horizon_secs).is_some_and(|ratio| ratio >= threshold)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -126,12 +256,100 @@ mod tests {
         );
 
         let mut state_in_cooldown = state.clone();
-        state_in_cooldown.last_archive_trigger_epoch_secs = Some(995);
-        state_in_cooldown.last_compaction_trigger_epoch_secs = Some(998);
+        state_in_cooldown.record_archive("agent:main:discord:channel:1", 995);
+        state_in_cooldown
+            .channel_mut("agent:main:discord:channel:2")
+            .last_compaction_epoch_secs = Some(998);
         let triggers_cooldown = evaluate(&cfg, &state_in_cooldown, &usage);
         assert!(triggers_cooldown.is_empty());
     }
 
+    #[test]
+    fn evaluate_fires_archive_alone_between_archive_and_compaction_ratios() {
+        let mut cfg = MoonConfig::default();
+        cfg.thresholds.archive_ratio = 0.70;
+        cfg.thresholds.trigger_ratio = 0.90;
+        let usage = |ratio: f64| SessionUsageSnapshot {
+            session_id: "s".into(),
+            used_tokens: (ratio * 100.0) as u64,
+            max_tokens: 100,
+            usage_ratio: ratio,
+            captured_at_epoch_secs: 1000,
+            provider: "t".into(),
+        };
+        let state = MoonState::default();
+
+        // Disabled: the archive ratio is ignored.
+        assert!(evaluate(&cfg, &state, &usage(0.75)).is_empty());
+
+        cfg.thresholds.archive_ratio_trigger_enabled = true;
+        assert!(evaluate(&cfg, &state, &usage(0.65)).is_empty());
+        assert_eq!(
+            evaluate(&cfg, &state, &usage(0.75)),
+            vec![TriggerKind::Archive]
+        );
+
+        // A recent early archive cools down further archives but not compaction.
+        let mut archived = state.clone();
+        archived.record_archive("agent:main:discord:channel:1", 995);
+        assert!(evaluate(&cfg, &archived, &usage(0.75)).is_empty());
+        assert_eq!(
+            evaluate(&cfg, &archived, &usage(0.95)),
+            vec![TriggerKind::Archive, TriggerKind::Compaction]
+        );
+    }
+
+    #[test]
+    fn distill_trigger_honors_mode_cooldown_and_idle_time() {
+        let mut cfg = MoonConfig::default();
+        cfg.distill.idle_secs = 300;
+        cfg.distill.cooldown_secs = Some(600);
+        let input = DistillTriggerInput {
+            now_epoch_secs: 10_000,
+            last_distill_epoch_secs: Some(9_000),
+            last_activity_epoch_secs: Some(9_000),
+            forced: false,
+        };
+
+        let ready = evaluate_distill(&cfg, input);
+        assert_eq!(ready.trigger, Some(TriggerKind::Distill));
+        assert_eq!(ready.max_per_cycle, cfg.distill.max_per_cycle);
+
+        let busy = evaluate_distill(
+            &cfg,
+            DistillTriggerInput {
+                last_activity_epoch_secs: Some(9_900),
+                ..input
+            },
+        );
+        assert_eq!(busy.trigger, None);
+        assert_eq!(
+            busy.note,
+            "skipped reason=not-idle idle_secs=300 idle_for_secs=100"
+        );
+
+        let cooling = evaluate_distill(
+            &cfg,
+            DistillTriggerInput {
+                last_distill_epoch_secs: Some(9_500),
+                ..input
+            },
+        );
+        assert_eq!(cooling.note, "skipped reason=cooldown cooldown_secs=600");
+
+        cfg.distill.mode = "manual".to_string();
+        assert_eq!(evaluate_distill(&cfg, input).trigger, None);
+        let forced = evaluate_distill(
+            &cfg,
+            DistillTriggerInput {
+                forced: true,
+                ..input
+            },
+        );
+        assert_eq!(forced.trigger, Some(TriggerKind::Distill));
+        assert_eq!(forced.note, "manual_trigger=true");
+    }
+
     #[test]
     fn context_compaction_bypasses_cooldown_only_on_emergency() {
         let start = 0.78;
@@ -155,4 +373,19 @@ mod tests {
         assert!(ready.should_compact);
         assert!(!ready.bypassed_cooldown);
     }
+
+    #[test]
+    fn predictive_trigger_fires_only_when_growth_reaches_threshold_next_interval() {
+        let sample = |epoch_secs, ratio| UsageSample { epoch_secs, ratio };
+        let growing = [sample(0, 0.50), sample(30, 0.63), sample(60, 0.76)];
+        assert!(predicts_threshold_crossing(&growing, 0.85, 30));
+        assert!(!predicts_threshold_crossing(&growing, 0.95, 30));
+
+        let flat = [sample(0, 0.80), sample(60, 0.80)];
+        assert!(!predicts_threshold_crossing(&flat, 0.85, 30));
+        assert!(projected_usage_ratio(&flat, 30).is_none());
+
+        let already_over = [sample(0, 0.80), sample(30, 0.90)];
+        assert!(!predicts_threshold_crossing(&already_over, 0.85, 30));
+    }
 }
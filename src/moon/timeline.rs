@@ -0,0 +1,285 @@
+use crate::moon::archive::{ArchiveRecord, read_ledger_records};
+use crate::moon::channel_archive_map::{self, ChannelArchiveRecord};
+use crate::moon::continuity;
+use crate::moon::distill::extract_projection_data;
+use crate::moon::paths::MoonPaths;
+use crate::moon::report::day_bounds;
+use crate::moon::util::truncate_with_ellipsis;
+use anyhow::Result;
+use chrono::{TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::path::Path;
+
+const TIMELINE_TEXT_MAX_CHARS: usize = 160;
+
+/// Which archives and which part of their timelines to merge.
+#[derive(Debug, Clone, Default)]
+pub struct TimelineFilter {
+    pub day_key: Option<String>,
+    pub channel_key: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TimelineEntry {
+    pub at_epoch_secs: u64,
+    pub time_utc: String,
+    pub time_local: String,
+    pub session_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub channel_key: Option<String>,
+    pub role: String,
+    pub text: String,
+    pub archive_path: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Timeline {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub day: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub channel: Option<String>,
+    pub timezone: String,
+    pub archives: usize,
+    pub sessions: usize,
+    pub entries: Vec<TimelineEntry>,
+    /// Archives that matched the filter but whose raw file could not be read.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub skipped_archives: Vec<String>,
+}
+
+/// Archives and session ids the channel has been mapped to, current and past.
+struct ChannelScope {
+    source_path: Option<String>,
+    archive_paths: BTreeSet<String>,
+    session_ids: BTreeSet<String>,
+}
+
+impl ChannelScope {
+    fn load(paths: &MoonPaths, channel_key: &str) -> Result<Self> {
+        let mapped = channel_archive_map::get(paths, channel_key)?;
+        let mut archive_paths = BTreeSet::new();
+        let mut session_ids = BTreeSet::new();
+        if let Some(record) = &mapped {
+            archive_paths.insert(record.archive_path.clone());
+            archive_paths.extend(record.retired_archive_path.clone());
+        }
+        for record in continuity::read_records(paths, channel_key)? {
+            archive_paths.extend(record.archive_path);
+            session_ids.insert(record.source_session_id);
+            session_ids.insert(record.target_session_id);
+        }
+        session_ids.remove("");
+        Ok(Self {
+            source_path: mapped.map(|record| record.source_path),
+            archive_paths,
+            session_ids,
+        })
+    }
+
+    fn contains(&self, record: &ArchiveRecord) -> bool {
+        self.source_path.as_deref() == Some(record.source_path.as_str())
+            || self.archive_paths.contains(&record.archive_path)
+            || self.session_ids.contains(&record.session_id)
+    }
+}
+
+fn channel_for(record: &ArchiveRecord, channels: &[ChannelArchiveRecord]) -> Option<String> {
+    channels
+        .iter()
+        .find(|c| c.archive_path == record.archive_path || c.source_path == record.source_path)
+        .map(|c| c.channel_key.clone())
+}
+
+/// Latest ledger row per archive, leaving out private archives and archives whose
+/// conversation is already covered by a newer snapshot or an earlier duplicate.
+fn timeline_archives(paths: &MoonPaths) -> Result<Vec<ArchiveRecord>> {
+    let mut records: Vec<ArchiveRecord> = Vec::new();
+    for record in read_ledger_records(paths)? {
+        records.retain(|existing| existing.archive_path != record.archive_path);
+        records.push(record);
+    }
+    records.retain(|record| {
+        !record.private && record.superseded_by.is_none() && record.duplicate_of.is_none()
+    });
+    Ok(records)
+}
+
+/// Merges the projection timelines of every archive matching `filter` into one list,
+/// oldest first. Entries without a timestamp inherit the previous entry's, as in the
+/// projection's own `## Timeline`.
+pub fn build_timeline(paths: &MoonPaths, filter: &TimelineFilter, tz: Tz) -> Result<Timeline> {
+    let bounds = filter
+        .day_key
+        .as_deref()
+        .map(|day| day_bounds(day, tz))
+        .transpose()?;
+    let scope = filter
+        .channel_key
+        .as_deref()
+        .map(|channel| ChannelScope::load(paths, channel))
+        .transpose()?;
+    let channels = channel_archive_map::load(paths)?
+        .into_values()
+        .collect::<Vec<_>>();
+
+    let mut timeline = Timeline {
+        day: filter.day_key.clone(),
+        channel: filter.channel_key.clone(),
+        timezone: tz.name().to_string(),
+        archives: 0,
+        sessions: 0,
+        entries: Vec::new(),
+        skipped_archives: Vec::new(),
+    };
+    let mut sessions = BTreeSet::new();
+    for record in timeline_archives(paths)? {
+        if scope.as_ref().is_some_and(|scope| !scope.contains(&record)) {
+            continue;
+        }
+        if !Path::new(&record.archive_path).exists() {
+            timeline.skipped_archives.push(record.archive_path.clone());
+            continue;
+        }
+        let data = match extract_projection_data(&record.archive_path) {
+            Ok(data) => data,
+            Err(_) => {
+                timeline.skipped_archives.push(record.archive_path.clone());
+                continue;
+            }
+        };
+        if let (Some((start, end)), Some(first), Some(last)) =
+            (bounds, data.time_start_epoch, data.time_end_epoch)
+            && (last < start || first >= end)
+        {
+            continue;
+        }
+
+        let channel_key = filter
+            .channel_key
+            .clone()
+            .or_else(|| channel_for(&record, &channels));
+        let mut last_known = data.time_start_epoch;
+        let mut matched = false;
+        for entry in &data.entries {
+            let Some(at) = entry.timestamp_epoch.or(last_known) else {
+                continue;
+            };
+            last_known = Some(at);
+            if bounds.is_some_and(|(start, end)| at < start || at >= end) {
+                continue;
+            }
+            let Some(utc) = Utc.timestamp_opt(at as i64, 0).single() else {
+                continue;
+            };
+            matched = true;
+            timeline.entries.push(TimelineEntry {
+                at_epoch_secs: at,
+                time_utc: utc.to_rfc3339(),
+                time_local: utc
+                    .with_timezone(&tz)
+                    .format("%Y-%m-%d %H:%M:%S")
+                    .to_string(),
+                session_id: record.session_id.clone(),
+                channel_key: channel_key.clone(),
+                role: match &entry.tool_name {
+                    Some(tool) => format!("tool:{tool}"),
+                    None => entry.role.clone(),
+                },
+                text: truncate_with_ellipsis(
+                    entry
+                        .content
+                        .split_whitespace()
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                        .as_str(),
+                    TIMELINE_TEXT_MAX_CHARS,
+                ),
+                archive_path: record.archive_path.clone(),
+            });
+        }
+        if matched {
+            timeline.archives += 1;
+            sessions.insert(record.session_id.clone());
+        }
+    }
+    // Stable, so entries sharing a second keep their order within the archive.
+    timeline.entries.sort_by_key(|entry| entry.at_epoch_secs);
+    timeline.sessions = sessions.len();
+    Ok(timeline)
+}
+
+fn escape_cell(text: &str) -> String {
+    text.replace('|', "\\|")
+}
+
+pub fn render_timeline_markdown(timeline: &Timeline) -> String {
+    let scope = match (&timeline.day, &timeline.channel) {
+        (Some(day), Some(channel)) => format!("{day} · {channel}"),
+        (Some(day), None) => day.clone(),
+        (None, Some(channel)) => channel.clone(),
+        (None, None) => "all archives".to_string(),
+    };
+    let mut out = format!("# Timeline {scope}\n\n");
+    out.push_str(&format!(
+        "- Timezone: {}\n- Sessions: {}\n- Archives: {}\n- Entries: {}\n",
+        timeline.timezone,
+        timeline.sessions,
+        timeline.archives,
+        timeline.entries.len()
+    ));
+    if !timeline.skipped_archives.is_empty() {
+        out.push_str(&format!(
+            "- Skipped archives: {}\n",
+            timeline.skipped_archives.len()
+        ));
+    }
+    out.push_str("\n| Time (Local) | Time (UTC) | Channel | Session | Role | Summary |\n");
+    out.push_str("|---|---|---|---|---|---|\n");
+    for entry in &timeline.entries {
+        out.push_str(&format!(
+            "| {} | {} | {} | `{}` | {} | {} |\n",
+            entry.time_local,
+            entry.time_utc,
+            escape_cell(entry.channel_key.as_deref().unwrap_or("-")),
+            entry.session_id,
+            escape_cell(&entry.role),
+            escape_cell(&entry.text)
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Timeline, TimelineEntry, render_timeline_markdown};
+
+    #[test]
+    fn markdown_escapes_pipes_and_marks_unmapped_channels() {
+        let timeline = Timeline {
+            day: Some("2023-11-14".to_string()),
+            channel: None,
+            timezone: "UTC".to_string(),
+            archives: 1,
+            sessions: 1,
+            entries: vec![TimelineEntry {
+                at_epoch_secs: 1_700_000_000,
+                time_utc: "2023-11-14T22:13:20+00:00".to_string(),
+                time_local: "2023-11-14 22:13:20".to_string(),
+                session_id: "s-1".to_string(),
+                channel_key: None,
+                role: "user".to_string(),
+                text: "grep a|b".to_string(),
+                archive_path: "/tmp/raw/s-1.jsonl".to_string(),
+            }],
+            skipped_archives: Vec::new(),
+        };
+
+        let rendered = render_timeline_markdown(&timeline);
+        assert!(rendered.starts_with("# Timeline 2023-11-14\n"));
+        assert!(rendered.contains(
+            "| 2023-11-14 22:13:20 | 2023-11-14T22:13:20+00:00 | - | `s-1` | user | grep a\\|b |"
+        ));
+    }
+}
@@ -0,0 +1,60 @@
+/// Byte-per-token estimate used when the build has no BPE tokenizer.
+#[cfg(not(feature = "tokenizer"))]
+const FALLBACK_BYTES_PER_TOKEN: usize = 3;
+
+/// Number of tokens `text` costs in an LLM prompt.
+///
+/// Uses the `cl100k_base` BPE when the `tokenizer` feature is enabled; otherwise a byte
+/// estimate consistent with the rest of MOON's sizing heuristics.
+#[cfg(feature = "tokenizer")]
+pub fn count(text: &str) -> usize {
+    use std::sync::OnceLock;
+    use tiktoken_rs::CoreBPE;
+
+    static BPE: OnceLock<Option<CoreBPE>> = OnceLock::new();
+    match BPE.get_or_init(|| tiktoken_rs::cl100k_base().ok()) {
+        Some(bpe) => bpe.encode_ordinary(text).len(),
+        None => text.len().div_ceil(3),
+    }
+}
+
+#[cfg(not(feature = "tokenizer"))]
+pub fn count(text: &str) -> usize {
+    text.len().div_ceil(FALLBACK_BYTES_PER_TOKEN)
+}
+
+/// Longest prefix of `text` (on a char boundary) that costs at most `max_tokens`.
+pub fn truncate(text: &str, max_tokens: usize) -> &str {
+    if count(text) <= max_tokens {
+        return text;
+    }
+    // `boundaries[k]` ends the prefix of `k` chars; the whole text is already over budget.
+    let boundaries: Vec<usize> = text.char_indices().map(|(idx, _)| idx).collect();
+    // Token counts grow monotonically with prefix length, so binary search the cut point.
+    let (mut lo, mut hi) = (0usize, boundaries.len() - 1);
+    while lo < hi {
+        let mid = (lo + hi).div_ceil(2);
+        if count(&text[..boundaries[mid]]) <= max_tokens {
+            lo = mid;
+        } else {
+            hi = mid - 1;
+        }
+    }
+    &text[..boundaries[lo]]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truncate_respects_budget_and_char_boundaries() {
+        let text = "naïve café résumé ".repeat(40);
+        let cut = truncate(&text, 10);
+        assert!(count(cut) <= 10);
+        assert!(!cut.is_empty());
+        assert!(text.starts_with(cut));
+        assert_eq!(truncate("short", 100), "short");
+        assert_eq!(truncate("anything", 0), "");
+    }
+}
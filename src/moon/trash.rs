@@ -0,0 +1,330 @@
+use crate::moon::archive::{ArchiveRecord, move_file, portable_path_str, portable_path_string};
+use crate::moon::paths::MoonPaths;
+use anyhow::{Context, Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fs;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+/// One file moved out of the archive tree by retention; the manifest keeps enough to undo it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrashEntry {
+    pub original_path: String,
+    pub trashed_path: String,
+    /// Archive the file belonged to; restore brings back every file trashed with it.
+    pub archive_path: String,
+    pub trashed_at_epoch_secs: u64,
+    pub reason: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub record: Option<ArchiveRecord>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub distilled_at_epoch_secs: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct TrashOrigin<'a> {
+    pub archive_path: &'a str,
+    pub reason: &'a str,
+    pub record: Option<&'a ArchiveRecord>,
+    pub distilled_at_epoch_secs: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TrashPurgeOutcome {
+    pub purged: usize,
+    pub kept: usize,
+    /// Manifest entries whose trashed file was already gone.
+    pub missing: usize,
+    pub failed: usize,
+    pub bytes: u64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TrashRestoreOutcome {
+    pub archive_path: String,
+    pub restored: Vec<TrashEntry>,
+}
+
+/// Lives under `archives/` so moves stay on one filesystem, but outside `mlib/` so the
+/// qmd collection mask never indexes trashed projections.
+pub fn trash_dir(paths: &MoonPaths) -> PathBuf {
+    paths.archives_dir.join("trash")
+}
+
+pub fn trash_manifest_path(paths: &MoonPaths) -> PathBuf {
+    trash_dir(paths).join("manifest.jsonl")
+}
+
+pub fn read_trash_manifest(paths: &MoonPaths) -> Result<Vec<TrashEntry>> {
+    let path = trash_manifest_path(paths);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let raw =
+        fs::read_to_string(&path).with_context(|| format!("failed to read {}", path.display()))?;
+    let mut out = Vec::new();
+    for line in raw.lines() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Ok(entry) = serde_json::from_str::<TrashEntry>(line) {
+            out.push(entry);
+        }
+    }
+    Ok(out)
+}
+
+fn append_trash_manifest(paths: &MoonPaths, entry: &TrashEntry) -> Result<()> {
+    let path = trash_manifest_path(paths);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+    let line = format!("{}\n", serde_json::to_string(entry)?);
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("failed to open {}", path.display()))?;
+    file.write_all(line.as_bytes())
+        .with_context(|| format!("failed to write {}", path.display()))?;
+    Ok(())
+}
+
+fn write_trash_manifest(paths: &MoonPaths, entries: &[TrashEntry]) -> Result<()> {
+    let path = trash_manifest_path(paths);
+    if entries.is_empty() {
+        return match fs::remove_file(&path) {
+            Ok(()) => Ok(()),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(()),
+            Err(err) => Err(err).with_context(|| format!("failed to remove {}", path.display())),
+        };
+    }
+    let mut out = String::new();
+    for entry in entries {
+        out.push_str(&serde_json::to_string(entry)?);
+        out.push('\n');
+    }
+    fs::write(&path, out).with_context(|| format!("failed to write {}", path.display()))
+}
+
+fn trash_target(paths: &MoonPaths, source: &Path, now_epoch_secs: u64) -> PathBuf {
+    let dir = trash_dir(paths).join(now_epoch_secs.to_string());
+    let file_name = source
+        .file_name()
+        .and_then(|v| v.to_str())
+        .unwrap_or("archive")
+        .to_string();
+    let mut candidate = dir.join(&file_name);
+    let mut index = 1usize;
+    while candidate.exists() {
+        candidate = dir.join(format!("{index}-{file_name}"));
+        index = index.saturating_add(1);
+    }
+    candidate
+}
+
+/// Moves `path` into the trash and records it in the manifest; `Ok(None)` when the file is
+/// already gone.
+pub fn move_to_trash(
+    paths: &MoonPaths,
+    path: &Path,
+    origin: TrashOrigin<'_>,
+    now_epoch_secs: u64,
+) -> Result<Option<TrashEntry>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let target = trash_target(paths, path, now_epoch_secs);
+    move_file(path, &target)?;
+    let entry = TrashEntry {
+        original_path: portable_path_string(path),
+        trashed_path: portable_path_string(&target),
+        archive_path: origin.archive_path.to_string(),
+        trashed_at_epoch_secs: now_epoch_secs,
+        reason: origin.reason.to_string(),
+        record: origin.record.cloned(),
+        distilled_at_epoch_secs: origin.distilled_at_epoch_secs,
+    };
+    append_trash_manifest(paths, &entry)?;
+    Ok(Some(entry))
+}
+
+/// Permanently deletes trashed files older than `older_than_secs` (`None` purges everything).
+pub fn purge_trash(
+    paths: &MoonPaths,
+    older_than_secs: Option<u64>,
+    now_epoch_secs: u64,
+    dry_run: bool,
+) -> Result<TrashPurgeOutcome> {
+    let entries = read_trash_manifest(paths)?;
+    let mut out = TrashPurgeOutcome::default();
+    let mut kept = Vec::new();
+
+    for entry in entries {
+        let expired = older_than_secs
+            .map(|secs| now_epoch_secs.saturating_sub(entry.trashed_at_epoch_secs) >= secs)
+            .unwrap_or(true);
+        if !expired {
+            out.kept += 1;
+            kept.push(entry);
+            continue;
+        }
+
+        let trashed = Path::new(&entry.trashed_path);
+        let len = fs::metadata(trashed).map(|m| m.len()).ok();
+        if dry_run {
+            match len {
+                Some(bytes) => {
+                    out.purged += 1;
+                    out.bytes = out.bytes.saturating_add(bytes);
+                }
+                None => out.missing += 1,
+            }
+            kept.push(entry);
+            continue;
+        }
+        match fs::remove_file(trashed) {
+            Ok(()) => {
+                out.purged += 1;
+                out.bytes = out.bytes.saturating_add(len.unwrap_or(0));
+                if let Some(parent) = trashed.parent() {
+                    // Drops the per-cycle directory once its last file is purged.
+                    let _ = fs::remove_dir(parent);
+                }
+            }
+            Err(err) if err.kind() == ErrorKind::NotFound => out.missing += 1,
+            Err(_) => {
+                out.failed += 1;
+                kept.push(entry);
+            }
+        }
+    }
+
+    if !dry_run && (out.purged > 0 || out.missing > 0) {
+        write_trash_manifest(paths, &kept)?;
+    }
+    Ok(out)
+}
+
+/// Moves every file trashed with the archive matching `path` (original or trashed location)
+/// back into place; refuses to overwrite anything that has reappeared there since.
+pub fn restore_from_trash(paths: &MoonPaths, path: &str) -> Result<TrashRestoreOutcome> {
+    let entries = read_trash_manifest(paths)?;
+    let wanted = path.trim();
+    let portable = portable_path_str(wanted);
+    let matches = |value: &str| value == wanted || portable_path_str(value) == portable;
+    let archive_path = entries
+        .iter()
+        .rev()
+        .find(|e| matches(&e.original_path) || matches(&e.trashed_path) || matches(&e.archive_path))
+        .map(|e| e.archive_path.clone())
+        .ok_or_else(|| anyhow!("no trashed file matches `{wanted}`"))?;
+
+    let (group, mut rest): (Vec<_>, Vec<_>) = entries
+        .into_iter()
+        .partition(|e| e.archive_path == archive_path);
+
+    for entry in &group {
+        if Path::new(&entry.original_path).exists() {
+            return Err(anyhow!(
+                "refusing to restore over existing {}",
+                entry.original_path
+            ));
+        }
+    }
+
+    let mut out = TrashRestoreOutcome {
+        archive_path,
+        restored: Vec::new(),
+    };
+    let mut seen = BTreeSet::new();
+    for entry in group {
+        let trashed = Path::new(&entry.trashed_path);
+        if !trashed.exists() || !seen.insert(entry.original_path.clone()) {
+            rest.push(entry);
+            continue;
+        }
+        move_file(trashed, Path::new(&entry.original_path))?;
+        if let Some(parent) = trashed.parent() {
+            let _ = fs::remove_dir(parent);
+        }
+        out.restored.push(entry);
+    }
+
+    write_trash_manifest(paths, &rest)?;
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn test_paths(root: &Path) -> MoonPaths {
+        MoonPaths::for_test(&root.join("moon"))
+    }
+
+    #[test]
+    fn trash_round_trip_restores_archive_and_projection() {
+        let tmp = tempdir().expect("tempdir");
+        let paths = test_paths(tmp.path());
+        let archive = paths.archives_dir.join("raw/a.jsonl");
+        let projection = paths.archives_dir.join("mlib/a.md");
+        fs::create_dir_all(archive.parent().unwrap()).expect("mkdir raw");
+        fs::create_dir_all(projection.parent().unwrap()).expect("mkdir mlib");
+        fs::write(&archive, "{}\n").expect("write archive");
+        fs::write(&projection, "# a\n").expect("write projection");
+
+        let archive_str = archive.display().to_string();
+        let origin = TrashOrigin {
+            archive_path: &archive_str,
+            reason: "retention-cold",
+            record: None,
+            distilled_at_epoch_secs: Some(5),
+        };
+        let moved = move_to_trash(&paths, &archive, origin, 100).expect("trash archive");
+        assert!(moved.is_some());
+        move_to_trash(&paths, &projection, origin, 100).expect("trash projection");
+        assert!(!archive.exists());
+        assert!(!projection.exists());
+        assert_eq!(read_trash_manifest(&paths).expect("manifest").len(), 2);
+
+        let restored =
+            restore_from_trash(&paths, &projection.display().to_string()).expect("restore");
+        assert_eq!(restored.restored.len(), 2);
+        assert!(archive.exists());
+        assert!(projection.exists());
+        assert!(!trash_manifest_path(&paths).exists());
+    }
+
+    #[test]
+    fn purge_trash_only_drops_entries_past_delay() {
+        let tmp = tempdir().expect("tempdir");
+        let paths = test_paths(tmp.path());
+        fs::create_dir_all(&paths.archives_dir).expect("mkdir");
+        for (name, at) in [("old.jsonl", 10u64), ("new.jsonl", 1_000)] {
+            let file = paths.archives_dir.join(name);
+            fs::write(&file, "x").expect("write");
+            let file_str = file.display().to_string();
+            let origin = TrashOrigin {
+                archive_path: &file_str,
+                reason: "retention-cold",
+                record: None,
+                distilled_at_epoch_secs: None,
+            };
+            move_to_trash(&paths, &file, origin, at).expect("trash");
+        }
+
+        let dry = purge_trash(&paths, Some(500), 1_100, true).expect("dry purge");
+        assert_eq!((dry.purged, dry.kept), (1, 1));
+        assert_eq!(read_trash_manifest(&paths).expect("manifest").len(), 2);
+
+        let out = purge_trash(&paths, Some(500), 1_100, false).expect("purge");
+        assert_eq!((out.purged, out.kept, out.bytes), (1, 1, 1));
+        let left = read_trash_manifest(&paths).expect("manifest");
+        assert_eq!(left.len(), 1);
+        assert!(left[0].original_path.ends_with("new.jsonl"));
+    }
+}
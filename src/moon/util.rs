@@ -1,10 +1,144 @@
 use anyhow::Result;
+use std::io::Read;
 use std::process::{Command, Output};
 use std::thread;
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 pub const DEFAULT_EXTERNAL_COMMAND_TIMEOUT_SECS: u64 = 120;
 
+/// Filler words ignored when ranking keywords and comparing topics and memory bullets.
+pub(crate) const STOPWORDS: [&str; 129] = [
+    "able",
+    "about",
+    "above",
+    "actually",
+    "after",
+    "again",
+    "against",
+    "already",
+    "also",
+    "always",
+    "and",
+    "another",
+    "anything",
+    "are",
+    "around",
+    "be",
+    "because",
+    "been",
+    "before",
+    "being",
+    "below",
+    "between",
+    "both",
+    "can",
+    "cannot",
+    "could",
+    "did",
+    "does",
+    "doing",
+    "done",
+    "down",
+    "during",
+    "each",
+    "either",
+    "else",
+    "enough",
+    "even",
+    "every",
+    "everything",
+    "first",
+    "for",
+    "from",
+    "further",
+    "going",
+    "good",
+    "had",
+    "has",
+    "have",
+    "having",
+    "here",
+    "into",
+    "is",
+    "just",
+    "know",
+    "lets",
+    "like",
+    "look",
+    "made",
+    "make",
+    "many",
+    "maybe",
+    "more",
+    "most",
+    "much",
+    "must",
+    "need",
+    "next",
+    "not",
+    "nothing",
+    "okay",
+    "once",
+    "only",
+    "other",
+    "our",
+    "over",
+    "please",
+    "quite",
+    "really",
+    "right",
+    "same",
+    "seems",
+    "should",
+    "since",
+    "some",
+    "something",
+    "still",
+    "such",
+    "sure",
+    "take",
+    "than",
+    "thank",
+    "thanks",
+    "that",
+    "the",
+    "their",
+    "them",
+    "then",
+    "there",
+    "these",
+    "they",
+    "thing",
+    "things",
+    "think",
+    "this",
+    "those",
+    "through",
+    "under",
+    "until",
+    "used",
+    "using",
+    "very",
+    "want",
+    "was",
+    "were",
+    "what",
+    "when",
+    "where",
+    "whether",
+    "which",
+    "while",
+    "will",
+    "with",
+    "within",
+    "without",
+    "would",
+    "yeah",
+    "you",
+    "your",
+    "yours",
+];
+
 /// Return the current Unix epoch in seconds.
 ///
 /// This is the single, canonical implementation — **do not** duplicate
@@ -13,6 +147,25 @@ pub fn now_epoch_secs() -> Result<u64> {
     Ok(SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs())
 }
 
+/// Whether `MOON_READ_ONLY` is set, e.g. on a second machine pointed at a
+/// synced `MOON_HOME` that must never write to it.
+pub fn read_only_mode() -> bool {
+    std::env::var("MOON_READ_ONLY")
+        .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+        .unwrap_or(false)
+}
+
+/// Fail with an actionable error when `operation` would write under
+/// read-only mode.
+pub fn ensure_writable(operation: &str) -> Result<()> {
+    if read_only_mode() {
+        anyhow::bail!(
+            "MOON_READ_ONLY=true: refusing `{operation}` because it mutates MOON_HOME; run it on the writer host or unset MOON_READ_ONLY"
+        );
+    }
+    Ok(())
+}
+
 /// Truncate `input` to at most `max_chars` Unicode characters, stripping
 /// control characters and appending `…` when truncated.
 pub fn truncate_with_ellipsis(input: &str, max_chars: usize) -> String {
@@ -54,19 +207,44 @@ pub fn run_command_with_optional_timeout(
     let Some(timeout_secs) = timeout_secs else {
         return Ok(cmd.output()?);
     };
+    run_command_with_deadline(cmd, Duration::from_secs(timeout_secs))
+}
+
+/// Runs `cmd`, killing it once `timeout` has passed. Output is drained while waiting so a
+/// chatty child never stalls on a full pipe.
+pub fn run_command_with_deadline(cmd: &mut Command, timeout: Duration) -> Result<Output> {
     cmd.stdout(std::process::Stdio::piped());
     cmd.stderr(std::process::Stdio::piped());
     let mut child = cmd.spawn()?;
+    let stdout = drain_pipe(child.stdout.take());
+    let stderr = drain_pipe(child.stderr.take());
     let started = Instant::now();
     loop {
-        if child.try_wait()?.is_some() {
-            return Ok(child.wait_with_output()?);
+        if let Some(status) = child.try_wait()? {
+            return Ok(Output {
+                status,
+                stdout: stdout.join().unwrap_or_default(),
+                stderr: stderr.join().unwrap_or_default(),
+            });
         }
-        if started.elapsed() >= Duration::from_secs(timeout_secs) {
+        if started.elapsed() >= timeout {
             let _ = child.kill();
             let _ = child.wait();
-            anyhow::bail!("command timed out after {}s", timeout_secs);
+            if timeout.subsec_millis() == 0 {
+                anyhow::bail!("command timed out after {}s", timeout.as_secs());
+            }
+            anyhow::bail!("command timed out after {}ms", timeout.as_millis());
         }
-        thread::sleep(Duration::from_millis(50));
+        thread::sleep(Duration::from_millis(10));
     }
 }
+
+fn drain_pipe(pipe: Option<impl Read + Send + 'static>) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut out = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut out);
+        }
+        out
+    })
+}
@@ -0,0 +1,657 @@
+use crate::moon::config::MoonEmbedConfig;
+use crate::moon::distill::ProviderKeyCheck;
+use crate::moon::paths::MoonPaths;
+use anyhow::{Context, Result, anyhow};
+use reqwest::StatusCode;
+use reqwest::blocking::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeSet;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const REQUEST_TIMEOUT_SECS: u64 = 60;
+const MAX_SECTION_CHARS: usize = 8_000;
+const MAX_RETRY_AFTER_SECS: u64 = 60;
+const GEMINI_MAX_BATCH: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmbedProvider {
+    Qmd,
+    OpenAi,
+    Gemini,
+    OpenAiCompatible,
+}
+
+impl EmbedProvider {
+    pub const ALL: [Self; 4] = [
+        Self::Qmd,
+        Self::OpenAi,
+        Self::Gemini,
+        Self::OpenAiCompatible,
+    ];
+
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "" | "qmd" | "local" => Some(Self::Qmd),
+            "openai" => Some(Self::OpenAi),
+            "gemini" | "google" => Some(Self::Gemini),
+            "openai-compatible" | "compatible" => Some(Self::OpenAiCompatible),
+            _ => None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Qmd => "qmd",
+            Self::OpenAi => "openai",
+            Self::Gemini => "gemini",
+            Self::OpenAiCompatible => "openai-compatible",
+        }
+    }
+
+    fn default_model(self) -> &'static str {
+        match self {
+            Self::Qmd => "",
+            Self::OpenAi | Self::OpenAiCompatible => "text-embedding-3-small",
+            Self::Gemini => "text-embedding-004",
+        }
+    }
+}
+
+fn env_non_empty(var: &str) -> Option<String> {
+    match env::var(var) {
+        Ok(v) if !v.trim().is_empty() => Some(v.trim().to_string()),
+        _ => None,
+    }
+}
+
+fn resolve_api_key(provider: EmbedProvider) -> Option<String> {
+    match provider {
+        EmbedProvider::Qmd => None,
+        EmbedProvider::OpenAi => {
+            env_non_empty("OPENAI_API_KEY").or_else(|| env_non_empty("AI_API_KEY"))
+        }
+        EmbedProvider::Gemini => {
+            env_non_empty("GEMINI_API_KEY").or_else(|| env_non_empty("AI_API_KEY"))
+        }
+        EmbedProvider::OpenAiCompatible => {
+            env_non_empty("AI_API_KEY").or_else(|| env_non_empty("OPENAI_API_KEY"))
+        }
+    }
+}
+
+/// Resolved `[embed]` provider and model; `model` is empty for qmd.
+pub fn configured_model(cfg: &MoonEmbedConfig) -> (EmbedProvider, String) {
+    let provider = EmbedProvider::parse(&cfg.provider).unwrap_or(EmbedProvider::Qmd);
+    let model = if cfg.model.trim().is_empty() {
+        provider.default_model().to_string()
+    } else {
+        cfg.model.trim().to_string()
+    };
+    (provider, model)
+}
+
+/// Batching embedding client for one remote provider, paced to `requests_per_minute`.
+pub struct RemoteEmbedder {
+    provider: EmbedProvider,
+    model: String,
+    api_key: String,
+    base_url: String,
+    batch_size: usize,
+    min_interval: Duration,
+    max_retries: u64,
+    client: Client,
+    last_request: Option<Instant>,
+    requests: usize,
+}
+
+impl RemoteEmbedder {
+    /// `Ok(None)` when `[embed] provider` keeps embedding inside qmd.
+    pub fn from_config(cfg: &MoonEmbedConfig) -> Result<Option<Self>> {
+        let (provider, _) = configured_model(cfg);
+        Self::build(cfg, resolve_api_key(provider))
+    }
+
+    fn build(cfg: &MoonEmbedConfig, api_key: Option<String>) -> Result<Option<Self>> {
+        let (provider, model) = configured_model(cfg);
+        if provider == EmbedProvider::Qmd {
+            return Ok(None);
+        }
+        let api_key = api_key
+            .ok_or_else(|| anyhow!("no API key configured for {} embeddings", provider.label()))?;
+        let base_url = if !cfg.base_url.trim().is_empty() {
+            cfg.base_url.trim().to_string()
+        } else {
+            match provider {
+                EmbedProvider::OpenAi => "https://api.openai.com".to_string(),
+                EmbedProvider::Gemini => "https://generativelanguage.googleapis.com".to_string(),
+                EmbedProvider::OpenAiCompatible => {
+                    env_non_empty("AI_BASE_URL").ok_or_else(|| {
+                        anyhow!("openai-compatible embeddings need [embed] base_url or AI_BASE_URL")
+                    })?
+                }
+                EmbedProvider::Qmd => String::new(),
+            }
+        };
+        let mut batch_size = cfg.batch_size.max(1) as usize;
+        if provider == EmbedProvider::Gemini {
+            batch_size = batch_size.min(GEMINI_MAX_BATCH);
+        }
+        let min_interval = 60_000u64
+            .checked_div(cfg.requests_per_minute)
+            .map(Duration::from_millis)
+            .unwrap_or(Duration::ZERO);
+        let client = Client::builder()
+            .timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS))
+            .build()
+            .context("failed to build embedding http client")?;
+        Ok(Some(Self {
+            provider,
+            model,
+            api_key,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            batch_size,
+            min_interval,
+            max_retries: cfg.max_retries,
+            client,
+            last_request: None,
+            requests: 0,
+        }))
+    }
+
+    pub fn provider(&self) -> EmbedProvider {
+        self.provider
+    }
+
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    /// Number of HTTP requests sent so far, retries included.
+    pub fn requests(&self) -> usize {
+        self.requests
+    }
+
+    /// Embeds `texts` in `batch_size` requests; output order matches input order.
+    pub fn embed_texts(&mut self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        let mut out = Vec::with_capacity(texts.len());
+        for batch in texts.chunks(self.batch_size) {
+            let vectors = self.embed_batch_with_retry(batch)?;
+            if vectors.len() != batch.len() {
+                return Err(anyhow!(
+                    "{} returned {} embeddings for {} inputs",
+                    self.provider.label(),
+                    vectors.len(),
+                    batch.len()
+                ));
+            }
+            out.extend(vectors);
+        }
+        Ok(out)
+    }
+
+    fn pace(&mut self) {
+        if let Some(last) = self.last_request {
+            let elapsed = last.elapsed();
+            if elapsed < self.min_interval {
+                thread::sleep(self.min_interval - elapsed);
+            }
+        }
+        self.last_request = Some(Instant::now());
+    }
+
+    fn embed_batch_with_retry(&mut self, batch: &[String]) -> Result<Vec<Vec<f32>>> {
+        let mut attempt = 0u64;
+        loop {
+            self.pace();
+            self.requests += 1;
+            let retry_after = match self.send_batch(batch) {
+                Ok(BatchOutcome::Done(vectors)) => return Ok(vectors),
+                Ok(BatchOutcome::Retry {
+                    status,
+                    retry_after,
+                }) => {
+                    if attempt >= self.max_retries {
+                        return Err(anyhow!(
+                            "{} embeddings failed with status {status} after {} attempt(s)",
+                            self.provider.label(),
+                            attempt + 1
+                        ));
+                    }
+                    retry_after
+                }
+                Err(err) => {
+                    if attempt >= self.max_retries {
+                        return Err(err);
+                    }
+                    None
+                }
+            };
+            let backoff = retry_after.unwrap_or(1u64 << attempt.min(5));
+            thread::sleep(Duration::from_secs(backoff.min(MAX_RETRY_AFTER_SECS)));
+            attempt += 1;
+        }
+    }
+
+    fn send_batch(&self, batch: &[String]) -> Result<BatchOutcome> {
+        let request = match self.provider {
+            EmbedProvider::Gemini => {
+                let requests = batch
+                    .iter()
+                    .map(|text| {
+                        json!({
+                            "model": format!("models/{}", self.model),
+                            "content": {"parts": [{"text": text}]}
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                self.client
+                    .post(format!(
+                        "{}/v1beta/models/{}:batchEmbedContents?key={}",
+                        self.base_url, self.model, self.api_key
+                    ))
+                    .json(&json!({ "requests": requests }))
+            }
+            _ => self
+                .client
+                .post(format!("{}/v1/embeddings", self.base_url))
+                .bearer_auth(&self.api_key)
+                .json(&json!({ "model": self.model, "input": batch })),
+        };
+        let response = request
+            .send()
+            .with_context(|| format!("{} embeddings request failed", self.provider.label()))?;
+        let status = response.status();
+        if status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error() {
+            let retry_after = response
+                .headers()
+                .get("retry-after")
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.trim().parse::<u64>().ok());
+            return Ok(BatchOutcome::Retry {
+                status: status.as_u16(),
+                retry_after,
+            });
+        }
+        if !status.is_success() {
+            let body = response.text().unwrap_or_default();
+            return Err(anyhow!(
+                "{} embeddings returned status {}: {}",
+                self.provider.label(),
+                status.as_u16(),
+                crate::moon::util::truncate_with_ellipsis(body.trim(), 240)
+            ));
+        }
+        let body: Value = response
+            .json()
+            .with_context(|| format!("invalid {} embeddings response", self.provider.label()))?;
+        Ok(BatchOutcome::Done(parse_embeddings(self.provider, &body)?))
+    }
+}
+
+/// Embeds one short text with the configured remote provider, without retries, to check its
+/// key; `None` when `[embed] provider` is qmd.
+pub fn check_embed_key(cfg: &MoonEmbedConfig) -> Option<Result<ProviderKeyCheck>> {
+    let (provider, model) = configured_model(cfg);
+    if provider == EmbedProvider::Qmd {
+        return None;
+    }
+    let api_key = resolve_api_key(provider);
+    let probe_cfg = MoonEmbedConfig {
+        max_retries: 0,
+        ..cfg.clone()
+    };
+    let mut embedder = match RemoteEmbedder::build(&probe_cfg, api_key.clone()) {
+        Ok(Some(embedder)) => embedder,
+        Ok(None) => return None,
+        Err(err) => return Some(Err(err)),
+    };
+    let started = Instant::now();
+    let outcome = embedder
+        .embed_texts(&["moon key check".to_string()])
+        .map(|_| ());
+    Some(Ok(ProviderKeyCheck::from_outcome(
+        "embed",
+        provider.label(),
+        &model,
+        api_key.as_deref().unwrap_or_default(),
+        started.elapsed().as_millis(),
+        outcome,
+    )))
+}
+
+enum BatchOutcome {
+    Done(Vec<Vec<f32>>),
+    Retry {
+        status: u16,
+        retry_after: Option<u64>,
+    },
+}
+
+fn parse_vector(value: Option<&Value>) -> Option<Vec<f32>> {
+    value?
+        .as_array()?
+        .iter()
+        .map(|v| v.as_f64().map(|f| f as f32))
+        .collect()
+}
+
+fn parse_embeddings(provider: EmbedProvider, body: &Value) -> Result<Vec<Vec<f32>>> {
+    let parsed = match provider {
+        EmbedProvider::Gemini => {
+            body.get("embeddings")
+                .and_then(Value::as_array)
+                .and_then(|items| {
+                    items
+                        .iter()
+                        .map(|item| parse_vector(item.get("values")))
+                        .collect::<Option<Vec<_>>>()
+                })
+        }
+        _ => body
+            .get("data")
+            .and_then(Value::as_array)
+            .and_then(|items| {
+                let mut indexed = items
+                    .iter()
+                    .enumerate()
+                    .map(|(pos, item)| {
+                        let index = item
+                            .get("index")
+                            .and_then(Value::as_u64)
+                            .map(|i| i as usize)
+                            .unwrap_or(pos);
+                        parse_vector(item.get("embedding")).map(|v| (index, v))
+                    })
+                    .collect::<Option<Vec<_>>>()?;
+                indexed.sort_by_key(|(index, _)| *index);
+                Some(indexed.into_iter().map(|(_, v)| v).collect())
+            }),
+    };
+    parsed.ok_or_else(|| anyhow!("{} embeddings response had no vectors", provider.label()))
+}
+
+/// Provider and shape of the vectors currently in the store.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VectorMeta {
+    pub provider: String,
+    pub model: String,
+    pub dimensions: usize,
+    pub updated_at_epoch_secs: u64,
+}
+
+/// One embedded projection section.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VectorRecord {
+    pub projection_path: String,
+    pub section: String,
+    pub content_hash: String,
+    pub model: String,
+    pub dimensions: usize,
+    pub vector: Vec<f32>,
+}
+
+/// Lives under `archives/` but outside `mlib/`, so qmd never indexes the vector files.
+pub fn vectors_dir(paths: &MoonPaths) -> PathBuf {
+    paths.archives_dir.join("vectors")
+}
+
+fn meta_path(paths: &MoonPaths) -> PathBuf {
+    vectors_dir(paths).join("meta.json")
+}
+
+fn index_path(paths: &MoonPaths) -> PathBuf {
+    vectors_dir(paths).join("index.jsonl")
+}
+
+pub fn read_vector_meta(paths: &MoonPaths) -> Result<Option<VectorMeta>> {
+    let path = meta_path(paths);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let raw =
+        fs::read_to_string(&path).with_context(|| format!("failed to read {}", path.display()))?;
+    Ok(serde_json::from_str(&raw).ok())
+}
+
+pub fn read_vector_records(paths: &MoonPaths) -> Result<Vec<VectorRecord>> {
+    let path = index_path(paths);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let raw =
+        fs::read_to_string(&path).with_context(|| format!("failed to read {}", path.display()))?;
+    Ok(raw
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| serde_json::from_str::<VectorRecord>(line).ok())
+        .collect())
+}
+
+fn write_atomic(path: &Path, content: &str) -> Result<()> {
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, content).with_context(|| format!("failed to write {}", tmp.display()))?;
+    fs::rename(&tmp, path).with_context(|| format!("failed to replace {}", path.display()))
+}
+
+pub fn write_vector_store(
+    paths: &MoonPaths,
+    meta: &VectorMeta,
+    records: &[VectorRecord],
+) -> Result<()> {
+    let dir = vectors_dir(paths);
+    fs::create_dir_all(&dir).with_context(|| format!("failed to create {}", dir.display()))?;
+    let mut out = String::new();
+    for record in records {
+        out.push_str(&serde_json::to_string(record)?);
+        out.push('\n');
+    }
+    write_atomic(&index_path(paths), &out)?;
+    write_atomic(
+        &meta_path(paths),
+        &format!("{}\n", serde_json::to_string_pretty(meta)?),
+    )
+}
+
+/// Removes the vector store files so the next embed run starts from scratch.
+pub fn clear_vector_store(paths: &MoonPaths) -> Result<()> {
+    for path in [index_path(paths), meta_path(paths)] {
+        match fs::remove_file(&path) {
+            Ok(()) => {}
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => {
+                return Err(err).with_context(|| format!("failed to remove {}", path.display()));
+            }
+        }
+    }
+    Ok(())
+}
+
+pub fn vector_index_exists(paths: &MoonPaths) -> bool {
+    meta_path(paths).exists()
+}
+
+/// Drops every vector of the given projections; returns how many rows were removed.
+pub fn remove_projection_vectors(
+    paths: &MoonPaths,
+    projection_paths: &BTreeSet<String>,
+) -> Result<usize> {
+    if projection_paths.is_empty() {
+        return Ok(0);
+    }
+    let Some(meta) = read_vector_meta(paths)? else {
+        return Ok(0);
+    };
+    let mut records = read_vector_records(paths)?;
+    let before = records.len();
+    records.retain(|r| !projection_paths.contains(&r.projection_path));
+    let removed = before - records.len();
+    if removed > 0 {
+        write_vector_store(paths, &meta, &records)?;
+    }
+    Ok(removed)
+}
+
+/// Whether the store was built with a different provider/model than `[embed]` now selects.
+pub fn model_changed(meta: Option<&VectorMeta>, provider: EmbedProvider, model: &str) -> bool {
+    meta.is_some_and(|meta| meta.provider != provider.label() || meta.model != model)
+}
+
+/// Cosine of two embeddings; 0 when their dimensions differ or either is all zeros.
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f64 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    let (mut dot, mut norm_a, mut norm_b) = (0.0f64, 0.0f64, 0.0f64);
+    for (x, y) in a.iter().zip(b) {
+        let (x, y) = (f64::from(*x), f64::from(*y));
+        dot += x * y;
+        norm_a += x * x;
+        norm_b += y * y;
+    }
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    dot / (norm_a.sqrt() * norm_b.sqrt())
+}
+
+pub fn content_hash(text: &str) -> String {
+    let digest = Sha256::digest(text.as_bytes());
+    digest[..8].iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Splits a projection into `## ` sections (frontmatter dropped) for embedding.
+pub fn projection_sections(markdown: &str) -> Vec<(String, String)> {
+    let mut body = markdown;
+    if let Some(rest) = body.strip_prefix("---\n")
+        && let Some(end) = rest.find("\n---\n")
+    {
+        body = &rest[end + 5..];
+    }
+
+    let mut sections = Vec::new();
+    let mut heading = String::from("preamble");
+    let mut current = String::new();
+    let flush = |heading: &str, current: &mut String, out: &mut Vec<(String, String)>| {
+        let text = current.trim();
+        if !text.is_empty() {
+            let text = match text.char_indices().nth(MAX_SECTION_CHARS) {
+                Some((cut, _)) => &text[..cut],
+                None => text,
+            };
+            out.push((heading.to_string(), text.to_string()));
+        }
+        current.clear();
+    };
+    for line in body.lines() {
+        if let Some(title) = line.strip_prefix("## ") {
+            flush(&heading, &mut current, &mut sections);
+            heading = title.trim().to_string();
+        }
+        current.push_str(line);
+        current.push('\n');
+    }
+    flush(&heading, &mut current, &mut sections);
+    sections
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+
+    fn serve(
+        listener: TcpListener,
+        responses: Vec<(u16, String)>,
+    ) -> thread::JoinHandle<Vec<Value>> {
+        thread::spawn(move || {
+            let mut bodies = Vec::new();
+            for (status, payload) in responses {
+                let (stream, _) = listener.accept().expect("accept");
+                let mut reader = BufReader::new(stream);
+                let mut content_length = 0usize;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).expect("read header");
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                    if let Some(value) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                        content_length = value.trim().parse().expect("content length");
+                    }
+                }
+                let mut body = vec![0u8; content_length];
+                reader.read_exact(&mut body).expect("read body");
+                bodies.push(serde_json::from_slice(&body).expect("json body"));
+                let response = format!(
+                    "HTTP/1.1 {status} X\r\ncontent-type: application/json\r\nretry-after: 0\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{payload}",
+                    payload.len()
+                );
+                reader
+                    .get_mut()
+                    .write_all(response.as_bytes())
+                    .expect("respond");
+            }
+            bodies
+        })
+    }
+
+    #[test]
+    fn remote_embedder_batches_inputs_and_retries_rate_limits() {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+        let base_url = format!("http://{}", listener.local_addr().expect("addr"));
+        let server = serve(
+            listener,
+            vec![
+                (429, "{}".to_string()),
+                (
+                    200,
+                    r#"{"data":[{"index":1,"embedding":[0.0,1.0]},{"index":0,"embedding":[1.0,0.0]}]}"#
+                        .to_string(),
+                ),
+                (200, r#"{"data":[{"index":0,"embedding":[0.5,0.5]}]}"#.to_string()),
+            ],
+        );
+
+        let cfg = MoonEmbedConfig {
+            provider: "openai-compatible".to_string(),
+            model: "embed-small".to_string(),
+            base_url,
+            batch_size: 2,
+            requests_per_minute: 0,
+            max_retries: 2,
+            ..MoonEmbedConfig::default()
+        };
+        let mut embedder = RemoteEmbedder::build(&cfg, Some("test-key".to_string()))
+            .expect("config")
+            .expect("remote");
+        let texts = ["a", "b", "c"].map(String::from).to_vec();
+        let vectors = embedder.embed_texts(&texts).expect("embed");
+        let bodies = server.join().expect("server");
+
+        assert_eq!(
+            vectors,
+            vec![vec![1.0, 0.0], vec![0.0, 1.0], vec![0.5, 0.5]]
+        );
+        assert_eq!(embedder.requests(), 3);
+        assert_eq!(bodies[1]["input"], json!(["a", "b"]));
+        assert_eq!(bodies[1]["model"], json!("embed-small"));
+        assert_eq!(bodies[2]["input"], json!(["c"]));
+    }
+
+    #[test]
+    fn projection_sections_skip_frontmatter_and_split_headings() {
+        let md = "---\nmoon_archive_projection: 2\nkeywords: []\n---\n# Title\n\n## Timeline\n| a |\n\n## Conversation\n- [user] hi\n";
+        let sections = projection_sections(md);
+        let headings = sections.iter().map(|(h, _)| h.as_str()).collect::<Vec<_>>();
+        assert_eq!(headings, vec!["preamble", "Timeline", "Conversation"]);
+        assert!(sections[2].1.contains("- [user] hi"));
+        assert!(!sections[0].1.contains("moon_archive_projection"));
+    }
+}
@@ -0,0 +1,145 @@
+use crate::moon::audit;
+use crate::moon::paths::MoonPaths;
+use crate::moon::warn::{self, WarnEvent};
+use std::fmt;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// A watcher cycle ran past `[watcher] max_cycle_secs`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CycleOverrun {
+    /// Phase that was running when the budget ran out.
+    pub phase: &'static str,
+    pub elapsed_secs: u64,
+    pub max_cycle_secs: u64,
+}
+
+impl fmt::Display for CycleOverrun {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "watch cycle aborted by watchdog: phase={} elapsed_secs={} max_cycle_secs={}",
+            self.phase, self.elapsed_secs, self.max_cycle_secs
+        )
+    }
+}
+
+impl std::error::Error for CycleOverrun {}
+
+/// Time budget for one watcher cycle.
+///
+/// `enter` marks phase boundaries and fails once the budget is spent, so the cycle stops before
+/// starting more work. A monitor thread also audits the running phase the moment the budget
+/// runs out, which is the only trace left when a phase hangs and never reaches a boundary.
+pub struct CycleWatchdog {
+    started: Instant,
+    limit: Option<Duration>,
+    phase: Arc<Mutex<&'static str>>,
+    monitor: Option<(Sender<()>, JoinHandle<()>)>,
+}
+
+impl CycleWatchdog {
+    pub fn start(paths: &MoonPaths, max_cycle_secs: u64) -> Self {
+        let limit = (max_cycle_secs > 0).then(|| Duration::from_secs(max_cycle_secs));
+        Self::with_limit(paths, limit)
+    }
+
+    fn with_limit(paths: &MoonPaths, limit: Option<Duration>) -> Self {
+        let phase = Arc::new(Mutex::new("start"));
+        let monitor = limit.map(|limit| {
+            let (done, finished) = mpsc::channel::<()>();
+            let paths = paths.clone();
+            let phase = Arc::clone(&phase);
+            let handle = thread::spawn(move || {
+                if finished.recv_timeout(limit) == Err(RecvTimeoutError::Timeout) {
+                    let phase = *phase.lock().unwrap_or_else(|err| err.into_inner());
+                    report_overrun(&paths, phase, limit);
+                }
+            });
+            (done, handle)
+        });
+        Self {
+            started: Instant::now(),
+            limit,
+            phase,
+            monitor,
+        }
+    }
+
+    /// Starts `phase`, or fails with the phase that used up the budget.
+    pub fn enter(&self, phase: &'static str) -> Result<(), CycleOverrun> {
+        let mut current = self.phase.lock().unwrap_or_else(|err| err.into_inner());
+        if let Some(limit) = self.limit {
+            let elapsed = self.started.elapsed();
+            if elapsed > limit {
+                return Err(CycleOverrun {
+                    phase: *current,
+                    elapsed_secs: elapsed.as_secs(),
+                    max_cycle_secs: limit.as_secs(),
+                });
+            }
+        }
+        *current = phase;
+        Ok(())
+    }
+}
+
+impl Drop for CycleWatchdog {
+    fn drop(&mut self) {
+        if let Some((done, handle)) = self.monitor.take() {
+            let _ = done.send(());
+            let _ = handle.join();
+        }
+    }
+}
+
+fn report_overrun(paths: &MoonPaths, phase: &str, limit: Duration) {
+    let detail = format!(
+        "cycle over budget phase={phase} max_cycle_secs={}",
+        limit.as_secs()
+    );
+    warn::emit(WarnEvent {
+        code: "WATCH_CYCLE_OVERRUN",
+        stage: "watchdog",
+        action: phase,
+        session: "na",
+        archive: "na",
+        source: "na",
+        retry: "abort-at-next-phase",
+        reason: "max-cycle-secs-exceeded",
+        err: &detail,
+    });
+    let _ = audit::append_event(
+        paths,
+        "watchdog",
+        "degraded",
+        &detail,
+        serde_json::json!({"phase": phase, "max_cycle_secs": limit.as_secs()}),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn enter_names_the_phase_that_spent_the_budget() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let paths = MoonPaths::for_test(tmp.path());
+
+        let unlimited = CycleWatchdog::with_limit(&paths, None);
+        assert!(unlimited.enter("archive").is_ok());
+
+        let watchdog = CycleWatchdog::with_limit(&paths, Some(Duration::from_millis(20)));
+        watchdog.enter("archive").expect("within budget");
+        thread::sleep(Duration::from_millis(60));
+        let overrun = watchdog.enter("distill").expect_err("over budget");
+        assert_eq!(overrun.phase, "archive");
+        drop(watchdog);
+
+        let audit = std::fs::read_to_string(paths.logs_dir.join("audit.log")).expect("audit");
+        assert!(audit.contains("cycle over budget phase=archive"));
+    }
+}
@@ -1,43 +1,69 @@
 use crate::moon::archive::{
-    ArchivePipelineOutcome, archive_and_index, projection_path_for_archive, read_ledger_records,
-    remove_ledger_records,
+    ArchivePipelineOutcome, ArchivePlan, DistillProvenance, QmdIndexMode, archive_and_index,
+    plan_archive_and_index, portable_path_string, projection_path_for_archive,
+    projection_sidecar_path, read_ledger_records, record_compaction_anchors,
+    record_distill_provenance, remove_ledger_records,
 };
 use crate::moon::audit;
+use crate::moon::build_info::BuildInfo;
 use crate::moon::channel_archive_map;
+use crate::moon::chunk_cache;
 use crate::moon::config::{
-    MoonContextCompactionAuthority, MoonContextConfig, MoonRetentionConfig, load_config,
+    MoonCollectionsConfig, MoonCompactionStrategy, MoonContextCompactionAuthority,
+    MoonContextConfig, MoonSessionsConfig, load_config,
 };
-use crate::moon::continuity::{ContinuityOutcome, build_continuity};
+use crate::moon::consistency;
+use crate::moon::continuity::{self, ContinuityOutcome, ContinuityRecord, build_continuity};
 use crate::moon::daemon_lock::{DaemonLockPayload, daemon_lock_path, parse_daemon_lock_payload};
 use crate::moon::distill::{
-    DistillInput, DistillOutput, WisdomDistillInput, run_distillation, run_wisdom_distillation,
+    CompactionAnchor, DistillInput, DistillOutput, WisdomDistillInput,
+    daily_memory_file_for_session, daily_memory_has_session, run_distillation,
+    run_wisdom_distillation,
 };
 use crate::moon::embed::{self, EmbedCaller, EmbedRunError, EmbedRunOptions};
+use crate::moon::gateway_calls::{self, GatewayCallRecord};
+use crate::moon::hooks::{self, HookEvent};
 use crate::moon::inbound_watch::{self, InboundWatchOutcome};
+use crate::moon::memory::{self, build_memory_primer};
+use crate::moon::notify::{self, NotifyEvent};
 use crate::moon::paths::resolve_paths;
+use crate::moon::pause::{WatchPause, read_pause};
+use crate::moon::policy;
 use crate::moon::qmd;
+use crate::moon::report::{build_daily_report, daily_report_event_text, write_daily_report};
 use crate::moon::session_usage::{
     SessionUsageSnapshot, collect_openclaw_usage_batch, collect_usage,
 };
-use crate::moon::snapshot::latest_session_file;
-use crate::moon::state::{load, save, state_file_path};
-use crate::moon::thresholds::{TriggerKind, evaluate, evaluate_context_compaction_candidate};
+use crate::moon::snapshot::{is_snapshot_excluded, latest_session_file};
+use crate::moon::state::{
+    GLOBAL_CHANNEL, PendingCompactionAnchor, load, prune_usage_trends, record_usage_sample, save,
+    state_file_path,
+};
+use crate::moon::thresholds::{
+    DistillTriggerInput, TriggerKind, compaction_cooldown_epoch_secs, evaluate,
+    evaluate_archive_only, evaluate_context_compaction_candidate, evaluate_distill,
+    predicts_threshold_crossing, projected_usage_ratio,
+};
+use crate::moon::trash::{self, TrashOrigin, move_to_trash};
+use crate::moon::vectors;
 use crate::moon::warn::{self, WarnEvent};
+use crate::moon::watchdog::CycleWatchdog;
 use crate::openclaw::gateway;
 use anyhow::{Context, Result};
 use chrono::{TimeZone, Utc};
 use chrono_tz::Tz;
 use fs2::FileExt;
-use serde_json::Value;
+use serde::Deserialize;
+use std::borrow::Cow;
 use std::collections::{BTreeMap, BTreeSet};
 use std::fs;
 use std::fs::{File, OpenOptions};
 use std::io::{ErrorKind, Write};
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, UNIX_EPOCH};
 
 const BUILD_UUID: &str = env!("BUILD_UUID");
 
@@ -49,14 +75,22 @@ pub struct WatchRunOptions {
 
 #[derive(Debug, Clone)]
 pub struct WatchCycleOutcome {
+    /// Correlates this cycle's audit events (`cycle_id` in audit.log).
+    pub cycle_id: String,
     pub state_file: String,
     pub heartbeat_epoch_secs: u64,
     pub poll_interval_secs: u64,
-    pub trigger_threshold: f64,
+    pub archive_threshold: f64,
+    pub compaction_threshold: f64,
+    pub archive_trigger_enabled: bool,
     pub compaction_authority: String,
     pub compaction_emergency_ratio: Option<f64>,
     pub compaction_recover_ratio: Option<f64>,
     pub distill_max_per_cycle: u64,
+    pub distill_mode: String,
+    /// Why the distill trigger fired or was skipped this cycle.
+    pub distill_trigger: String,
+    pub distill_selection: Option<String>,
     pub embed_mode: String,
     pub embed_idle_secs: u64,
     pub embed_max_docs_per_cycle: u64,
@@ -67,29 +101,606 @@ pub struct WatchCycleOutcome {
     pub triggers: Vec<String>,
     pub inbound_watch: InboundWatchOutcome,
     pub archive: Option<ArchivePipelineOutcome>,
+    /// Set when the archive trigger fired for a session `[sessions] archive` excludes.
+    pub archive_skipped: Option<String>,
+    /// The every-Nth-cycle consistency check, when it ran this cycle.
+    pub consistency_result: Option<String>,
+    /// Gateway compaction summaries fetched for earlier `/compact` requests.
+    pub compaction_anchor_result: Option<String>,
     pub compaction_result: Option<String>,
     pub distill: Option<DistillOutput>,
     pub embed_result: Option<String>,
     pub continuity: Option<ContinuityOutcome>,
     pub archive_retention_result: Option<String>,
+    pub memory_primer_result: Option<String>,
+    pub predictive_archive_result: Option<String>,
+    pub daily_report_result: Option<String>,
+    /// The cycle's batched qmd sync, when any collection was queued.
+    pub qmd_sync_result: Option<String>,
+    pub idle_archive_result: Option<String>,
+    pub archive_plans: Vec<ArchivePlan>,
+    /// Set while the OpenClaw sessions dir is unreadable (archive phases were skipped) and
+    /// on the cycle it recovers.
+    pub sessions_dir: Option<String>,
+    /// Set when `moon watch pause` is active; the cycle only recorded usage and heartbeat.
+    pub paused: Option<WatchPause>,
 }
 
 type DistillCandidate = (crate::moon::archive::ArchiveRecord, String);
 type DistillSelection = (Vec<DistillCandidate>, Vec<String>);
 
-fn residential_tz_name(cfg: &crate::moon::config::MoonConfig) -> String {
-    let name = cfg.distill.residential_timezone.trim();
-    if name.is_empty() {
-        "UTC".to_string()
+fn parse_residential_tz(cfg: &crate::moon::config::MoonConfig) -> Tz {
+    cfg.distill.residential_tz()
+}
+
+#[allow(clippy::too_many_arguments)]
+fn run_predictive_archives(
+    paths: &crate::moon::paths::MoonPaths,
+    state: &mut crate::moon::state::MoonState,
+    collections: &MoonCollectionsConfig,
+    targets: &[SessionUsageSnapshot],
+    source_map: &BTreeMap<String, PathBuf>,
+    excluded_sessions: &BTreeMap<String, &'static str>,
+    threshold: f64,
+    horizon_secs: u64,
+    new_projections: &mut Vec<PathBuf>,
+    watchdog: &CycleWatchdog,
+    dry_run: bool,
+) -> Result<Option<String>> {
+    if targets.is_empty() {
+        return Ok(None);
+    }
+
+    let mut outcomes = Vec::new();
+    let mut outcome_details = Vec::new();
+    let mut failed = 0usize;
+    let mut archived_count = 0usize;
+    let mut overrun = None;
+    for target in targets {
+        if let Err(err) = watchdog_checkpoint(watchdog, paths, state, "predictive-archive", dry_run)
+        {
+            overrun = Some(err);
+            break;
+        }
+        let projected = state
+            .usage_trends
+            .get(&target.session_id)
+            .and_then(|trend| projected_usage_ratio(&trend.samples, horizon_secs))
+            .unwrap_or(target.usage_ratio);
+        if let Some(reason) = excluded_sessions.get(&target.session_id) {
+            outcomes.push(format!(
+                "skipped key={} ratio={:.4} reason={reason}",
+                target.session_id, target.usage_ratio
+            ));
+            outcome_details.push(serde_json::json!({
+                "status": "skipped",
+                "session": target.session_id,
+                "ratio": target.usage_ratio,
+                "reason": reason,
+            }));
+            continue;
+        }
+        let Some(source_path) = source_map.get(&target.session_id) else {
+            failed += 1;
+            outcomes.push(format!(
+                "failed key={} ratio={:.4} projected={projected:.4} reason=archive-source-not-found",
+                target.session_id, target.usage_ratio
+            ));
+            outcome_details.push(serde_json::json!({
+                "status": "failed",
+                "session": target.session_id,
+                "ratio": target.usage_ratio,
+                "projected": projected,
+                "reason": "archive-source-not-found",
+            }));
+            continue;
+        };
+        let collection = collections.for_session(Some(&target.session_id));
+        match archive_and_index(paths, source_path, collection, QmdIndexMode::Deferred) {
+            Ok(archived) => {
+                archived_count += 1;
+                if !archived.deduped && archived.record.indexed {
+                    queue_qmd_sync(paths, state, collection);
+                }
+                new_projections.extend(
+                    archived
+                        .record
+                        .projection_path
+                        .as_deref()
+                        .map(PathBuf::from),
+                );
+                if let Some(trend) = state.usage_trends.get_mut(&target.session_id) {
+                    trend.last_predictive_archive_epoch_secs = Some(target.captured_at_epoch_secs);
+                }
+                outcomes.push(format!(
+                    "ok key={} ratio={:.4} projected={projected:.4} archived={} deduped={} indexed={}",
+                    target.session_id,
+                    target.usage_ratio,
+                    archived.record.archive_path,
+                    archived.deduped,
+                    archived.record.indexed
+                ));
+                outcome_details.push(serde_json::json!({
+                    "status": "ok",
+                    "session": target.session_id,
+                    "ratio": target.usage_ratio,
+                    "projected": projected,
+                    "archive": archived.record.archive_path,
+                    "deduped": archived.deduped,
+                    "indexed": archived.record.indexed,
+                }));
+            }
+            Err(err) => {
+                failed += 1;
+                outcomes.push(format!(
+                    "failed key={} ratio={:.4} projected={projected:.4} reason=archive-failed error={err:#}",
+                    target.session_id, target.usage_ratio
+                ));
+                outcome_details.push(serde_json::json!({
+                    "status": "failed",
+                    "session": target.session_id,
+                    "ratio": target.usage_ratio,
+                    "projected": projected,
+                    "reason": "archive-failed",
+                    "error": format!("{err:#}"),
+                }));
+            }
+        }
+    }
+
+    let result = format!(
+        "threshold={threshold:.4} targets={} archived={archived_count} failed={failed} {}",
+        targets.len(),
+        outcomes.join(" | ")
+    );
+    let status = if failed > 0 { "degraded" } else { "ok" };
+    // The archives are written; an unwritable audit log must not fail the cycle after them.
+    let _ = audit::append_event(
+        paths,
+        "predictive-archive",
+        status,
+        &result,
+        serde_json::json!({
+            "threshold": threshold,
+            "targets": targets.len(),
+            "archived": archived_count,
+            "failed": failed,
+            "outcomes": outcome_details,
+        }),
+    );
+    match overrun {
+        Some(err) => Err(err),
+        None => Ok(Some(result)),
+    }
+}
+
+/// A session whose source file has not changed for `[watcher] idle_archive_secs`.
+#[derive(Debug, Clone)]
+struct IdleArchiveTarget {
+    session_key: String,
+    source: PathBuf,
+    idle_for_secs: u64,
+}
+
+/// Sessions idle past `[watcher] idle_archive_secs` whose latest contents are not archived yet.
+///
+/// A ledger row for the same source created after the file's last write means the idle
+/// conversation is already captured, so each idle session is archived once per change.
+fn select_idle_archive_targets(
+    paths: &crate::moon::paths::MoonPaths,
+    cfg: &crate::moon::config::MoonConfig,
+    skip_sessions: &BTreeSet<String>,
+    now_epoch_secs: u64,
+) -> Result<Vec<IdleArchiveTarget>> {
+    let idle_secs = cfg.watcher.idle_archive_secs;
+    if idle_secs == 0 {
+        return Ok(Vec::new());
+    }
+    let source_map = load_session_source_map(&paths.openclaw_sessions_dir)?;
+    if source_map.is_empty() {
+        return Ok(Vec::new());
+    }
+    let ledger = read_ledger_records(paths)?;
+    let mut targets = Vec::new();
+    for (session_key, source) in source_map {
+        if skip_sessions.contains(&session_key)
+            || !cfg.sessions.allows_archive(&session_key)
+            || is_snapshot_excluded(&source, &cfg.snapshot.exclude)
+        {
+            continue;
+        }
+        let Some(modified) = fs::metadata(&source)
+            .and_then(|meta| meta.modified())
+            .ok()
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+            .map(|elapsed| elapsed.as_secs())
+        else {
+            continue;
+        };
+        let idle_for_secs = now_epoch_secs.saturating_sub(modified);
+        if idle_for_secs < idle_secs {
+            continue;
+        }
+        let source_str = portable_path_string(&source);
+        let captured = ledger.iter().any(|record| {
+            record.source_path == source_str && record.created_at_epoch_secs >= modified
+        });
+        if !captured {
+            targets.push(IdleArchiveTarget {
+                session_key,
+                source,
+                idle_for_secs,
+            });
+        }
+    }
+    Ok(targets)
+}
+
+/// Archives idle sessions that never crossed a usage threshold; the regular distill
+/// selection then picks their archives up like any other pending archive.
+#[allow(clippy::too_many_arguments)]
+fn run_idle_archives(
+    paths: &crate::moon::paths::MoonPaths,
+    state: &mut crate::moon::state::MoonState,
+    collections: &MoonCollectionsConfig,
+    targets: &[IdleArchiveTarget],
+    idle_secs: u64,
+    new_projections: &mut Vec<PathBuf>,
+    watchdog: &CycleWatchdog,
+    dry_run: bool,
+) -> Result<Option<String>> {
+    if targets.is_empty() {
+        return Ok(None);
+    }
+
+    let mut outcomes = Vec::new();
+    let mut outcome_details = Vec::new();
+    let mut failed = 0usize;
+    let mut archived_count = 0usize;
+    let mut overrun = None;
+    for target in targets {
+        if let Err(err) = watchdog_checkpoint(watchdog, paths, state, "idle-archive", dry_run) {
+            overrun = Some(err);
+            break;
+        }
+        let collection = collections.for_session(Some(&target.session_key));
+        match archive_and_index(paths, &target.source, collection, QmdIndexMode::Deferred) {
+            Ok(archived) => {
+                archived_count += 1;
+                if !archived.deduped && archived.record.indexed {
+                    queue_qmd_sync(paths, state, collection);
+                }
+                new_projections.extend(
+                    archived
+                        .record
+                        .projection_path
+                        .as_deref()
+                        .map(PathBuf::from),
+                );
+                outcomes.push(format!(
+                    "ok key={} idle_for_secs={} archived={} deduped={} indexed={}",
+                    target.session_key,
+                    target.idle_for_secs,
+                    archived.record.archive_path,
+                    archived.deduped,
+                    archived.record.indexed
+                ));
+                outcome_details.push(serde_json::json!({
+                    "status": "ok",
+                    "session": target.session_key,
+                    "idle_for_secs": target.idle_for_secs,
+                    "archive": archived.record.archive_path,
+                    "deduped": archived.deduped,
+                    "indexed": archived.record.indexed,
+                }));
+            }
+            Err(err) => {
+                failed += 1;
+                state.channel_mut(&target.session_key).archive_failures += 1;
+                outcomes.push(format!(
+                    "failed key={} idle_for_secs={} reason=archive-failed error={err:#}",
+                    target.session_key, target.idle_for_secs
+                ));
+                outcome_details.push(serde_json::json!({
+                    "status": "failed",
+                    "session": target.session_key,
+                    "idle_for_secs": target.idle_for_secs,
+                    "reason": "archive-failed",
+                    "error": format!("{err:#}"),
+                }));
+            }
+        }
+    }
+
+    let result = format!(
+        "idle_secs={idle_secs} targets={} archived={archived_count} failed={failed} {}",
+        targets.len(),
+        outcomes.join(" | ")
+    );
+    let status = if failed > 0 { "degraded" } else { "ok" };
+    audit::append_event(
+        paths,
+        "idle-archive",
+        status,
+        &result,
+        serde_json::json!({
+            "idle_secs": idle_secs,
+            "targets": targets.len(),
+            "archived": archived_count,
+            "failed": failed,
+            "outcomes": outcome_details,
+        }),
+    )?;
+    match overrun {
+        Some(err) => Err(err),
+        None => Ok(Some(result)),
+    }
+}
+
+/// Embeds projections archived earlier in this cycle into the remote vector index.
+fn run_incremental_embed(
+    paths: &crate::moon::paths::MoonPaths,
+    state: &mut crate::moon::state::MoonState,
+    cfg: &crate::moon::config::MoonConfig,
+    session_id: &str,
+    new_projections: &[PathBuf],
+) -> Option<String> {
+    if new_projections.is_empty() {
+        return None;
+    }
+    match embed::embed_new_projections(paths, state, &cfg.embed, new_projections) {
+        Ok(None) => None,
+        Ok(Some(summary)) => {
+            let line = format!(
+                "incremental provider={} selected={} embedded={} vectors={} requests={} degraded={} skip_reason={}",
+                summary.provider,
+                summary.selected_docs,
+                summary.embedded_docs,
+                summary.vectors,
+                summary.requests,
+                summary.degraded,
+                summary.skip_reason
+            );
+            let status = if summary.degraded { "degraded" } else { "ok" };
+            let _ = audit::append_event(
+                paths,
+                "embed",
+                status,
+                &line,
+                serde_json::json!({
+                    "mode": "incremental",
+                    "provider": summary.provider,
+                    "selected": summary.selected_docs,
+                    "embedded": summary.embedded_docs,
+                    "vectors": summary.vectors,
+                    "requests": summary.requests,
+                    "degraded": summary.degraded,
+                    "skip_reason": summary.skip_reason,
+                }),
+            );
+            Some(line)
+        }
+        Err(err) => {
+            warn::emit(WarnEvent {
+                code: "EMBED_FAILED",
+                stage: "embed",
+                action: "incremental-embed",
+                session: session_id,
+                archive: "na",
+                source: "na",
+                retry: "retry-next-cycle",
+                reason: "incremental-embed-failed",
+                err: &format!("{err}"),
+            });
+            let line = format!("incremental failed error={err}");
+            let _ = audit::append_event(
+                paths,
+                "embed",
+                "degraded",
+                &line,
+                serde_json::json!({"mode": "incremental", "error": err.to_string()}),
+            );
+            Some(line)
+        }
+    }
+}
+
+/// Queues `collection` for the cycle's batched qmd sync. The queue is saved right away so a
+/// crash before the flush cannot leave a written projection unindexed.
+fn queue_qmd_sync(
+    paths: &crate::moon::paths::MoonPaths,
+    state: &mut crate::moon::state::MoonState,
+    collection: &str,
+) {
+    if state.pending_qmd_sync.insert(collection.to_string()) {
+        let _ = save(paths, state);
+    }
+}
+
+/// Runs the cycle's one qmd sync over every queued collection; failures stay queued.
+fn flush_qmd_sync(
+    paths: &crate::moon::paths::MoonPaths,
+    state: &mut crate::moon::state::MoonState,
+) -> Option<String> {
+    if state.pending_qmd_sync.is_empty() {
+        return None;
+    }
+    let collections = state
+        .pending_qmd_sync
+        .iter()
+        .cloned()
+        .collect::<Vec<_>>()
+        .join(",");
+    match qmd::sync_collections(
+        &paths.qmd_bin,
+        &paths.archives_dir,
+        &paths.memory_dir,
+        &state.pending_qmd_sync,
+        &load_config().map(|cfg| cfg.collections).unwrap_or_default(),
+    ) {
+        Ok(()) => {
+            state.pending_qmd_sync.clear();
+            let line = format!("ok collections={collections}");
+            let _ = audit::append_event(
+                paths,
+                "qmd-sync",
+                "ok",
+                &line,
+                serde_json::json!({"collections": collections}),
+            );
+            Some(line)
+        }
+        Err(err) => {
+            warn::emit(WarnEvent {
+                code: "INDEX_FAILED",
+                stage: "qmd-index",
+                action: "batched-sync",
+                session: "na",
+                archive: "na",
+                source: "na",
+                retry: "retry-next-cycle",
+                reason: "qmd-sync-failed",
+                err: &format!("{err:#}"),
+            });
+            let line = format!("failed collections={collections} error={err:#}");
+            let _ = audit::append_event(
+                paths,
+                "qmd-sync",
+                "degraded",
+                &line,
+                serde_json::json!({"collections": collections, "error": format!("{err:#}")}),
+            );
+            Some(line)
+        }
+    }
+}
+
+fn append_notify_audit(
+    paths: &crate::moon::paths::MoonPaths,
+    event: NotifyEvent,
+    outcomes: &[String],
+) {
+    if outcomes.is_empty() {
+        return;
+    }
+    let status = if outcomes.iter().all(|o| o.ends_with("=sent")) {
+        "ok"
     } else {
-        name.to_string()
+        "degraded"
+    };
+    let sinks = outcomes
+        .iter()
+        .filter_map(|outcome| outcome.split_once('='))
+        .map(|(sink, result)| (sink.to_string(), serde_json::Value::from(result)))
+        .collect::<serde_json::Map<_, _>>();
+    let _ = audit::append_event(
+        paths,
+        "notify",
+        status,
+        &format!("event={} {}", event.as_str(), outcomes.join(" ")),
+        serde_json::json!({"event": event.as_str(), "sinks": sinks}),
+    );
+}
+
+/// Counts a failed distill run for `channel` and alerts once its streak reaches the configured
+/// threshold.
+fn record_distill_failure(
+    paths: &crate::moon::paths::MoonPaths,
+    cfg: &crate::moon::config::MoonConfig,
+    state: &mut crate::moon::state::MoonState,
+    channel: &str,
+    detail: &str,
+) {
+    let failures = state.record_distill_failure(channel);
+    if failures != cfg.notify.distill_failure_threshold {
+        return;
     }
+    let outcomes = notify::notify(
+        &cfg.notify,
+        NotifyEvent::DistillFailures,
+        &format!("{failures} consecutive distill failures channel={channel}; last: {detail}"),
+    );
+    append_notify_audit(paths, NotifyEvent::DistillFailures, &outcomes);
 }
 
-fn parse_residential_tz(cfg: &crate::moon::config::MoonConfig) -> Tz {
-    residential_tz_name(cfg)
-        .parse::<Tz>()
-        .unwrap_or(chrono_tz::UTC)
+fn notify_daemon_restart() {
+    let (Ok(paths), Ok(cfg)) = (resolve_paths(), load_config()) else {
+        return;
+    };
+    let Ok(state) = load(&paths) else {
+        return;
+    };
+    if state.last_heartbeat_epoch_secs == 0 {
+        return;
+    }
+    let now = crate::moon::util::now_epoch_secs().unwrap_or(state.last_heartbeat_epoch_secs);
+    let outcomes = notify::notify(
+        &cfg.notify,
+        NotifyEvent::DaemonRestart,
+        &format!(
+            "watcher daemon restarted pid={} last_heartbeat_age_secs={}",
+            std::process::id(),
+            now.saturating_sub(state.last_heartbeat_epoch_secs)
+        ),
+    );
+    append_notify_audit(&paths, NotifyEvent::DaemonRestart, &outcomes);
+}
+
+fn run_scheduled_daily_report(
+    paths: &crate::moon::paths::MoonPaths,
+    day_key: &str,
+    tz: Tz,
+    notify: bool,
+    cost_per_million_tokens: f64,
+) -> String {
+    let written = build_daily_report(paths, day_key, tz, cost_per_million_tokens)
+        .and_then(|digest| write_daily_report(paths, &digest).map(|path| (digest, path)));
+    let (digest, path) = match written {
+        Ok(out) => out,
+        Err(err) => {
+            warn::emit(WarnEvent {
+                code: "DAILY_REPORT_FAILED",
+                stage: "report",
+                action: "write-daily-report",
+                session: "na",
+                archive: "na",
+                source: day_key,
+                retry: "next-day",
+                reason: "daily-report-failed",
+                err: &format!("{err:#}"),
+            });
+            let _ = audit::append_event(
+                paths,
+                "report",
+                "degraded",
+                &format!("daily day={day_key} error={err:#}"),
+                serde_json::json!({"kind": "daily", "day": day_key, "error": format!("{err:#}")}),
+            );
+            return format!("failed day={day_key} error={err:#}");
+        }
+    };
+
+    let mut result = format!("ok day={day_key} path={}", path.display());
+    let mut details = serde_json::json!({
+        "kind": "daily",
+        "day": day_key,
+        "path": path.display().to_string(),
+    });
+    if notify {
+        match gateway::run_system_event(&daily_report_event_text(&digest, &path), "now") {
+            Ok(()) => {
+                result.push_str(" notify=sent");
+                details["notify"] = "sent".into();
+            }
+            Err(err) => {
+                result.push_str(&format!(" notify=failed error={err:#}"));
+                details["notify"] = "failed".into();
+                details["error"] = format!("{err:#}").into();
+            }
+        }
+    }
+    let _ = audit::append_event(paths, "report", "ok", &format!("daily {result}"), details);
+    result
 }
 
 fn is_l1_norm_lock_contention(err: &anyhow::Error) -> bool {
@@ -129,8 +740,28 @@ fn daily_memory_path_for_day_key(paths: &crate::moon::paths::MoonPaths, day_key:
         .to_string()
 }
 
+/// Collection for an archive of `source`, routed by the session key that owns it in sessions.json.
+pub fn collection_for_source<'a>(
+    paths: &crate::moon::paths::MoonPaths,
+    collections: &'a MoonCollectionsConfig,
+    source: &Path,
+) -> &'a str {
+    if collections.channels.is_empty() {
+        return &collections.default;
+    }
+    let source_map = load_session_source_map(&paths.openclaw_sessions_dir).unwrap_or_default();
+    let session_key = source_map
+        .iter()
+        .find(|(_, path)| path.as_path() == source)
+        .map(|(key, _)| key.as_str());
+    collections.for_session(session_key)
+}
+
 fn run_archive_if_needed(
     paths: &crate::moon::paths::MoonPaths,
+    state: &mut crate::moon::state::MoonState,
+    collections: &MoonCollectionsConfig,
+    snapshot_exclude: &[String],
     trigger_set: &[TriggerKind],
     compaction_targets_present: bool,
 ) -> Result<Option<ArchivePipelineOutcome>> {
@@ -146,33 +777,409 @@ fn run_archive_if_needed(
         return Ok(None);
     }
 
-    let Some(source) = latest_session_file(&paths.openclaw_sessions_dir)? else {
+    let Some(source) = latest_session_file(&paths.openclaw_sessions_dir, snapshot_exclude)? else {
         anyhow::bail!("no source session file found in openclaw sessions dir");
     };
 
-    let out = archive_and_index(paths, &source, "history")?;
+    let collection = collection_for_source(paths, collections, &source);
+    let out = archive_and_index(paths, &source, collection, QmdIndexMode::Deferred)?;
+    if !out.deduped && out.record.indexed {
+        queue_qmd_sync(paths, state, collection);
+    }
     Ok(Some(out))
 }
 
-fn is_compaction_channel_session(session_id: &str) -> bool {
-    session_id.contains(":discord:channel:") || session_id.contains(":whatsapp:")
+/// Outcome of the archive-before-compact protocol for one session.
+#[derive(Debug, Clone)]
+pub struct CompactedSession {
+    pub archive_path: String,
+    pub projection_path: Option<String>,
+    pub compaction_strategy: String,
+    pub compact_summary: String,
+    pub index_note: String,
+}
+
+impl CompactedSession {
+    pub fn detail(&self) -> String {
+        format!(
+            "archived={} strategy={} {} {}",
+            self.archive_path, self.compaction_strategy, self.compact_summary, self.index_note
+        )
+    }
+}
+
+/// Archive `source_path` into `collection`, map it to `session_key`, then `/compact` the session
+/// with `strategy`. Compaction only runs once the archive is indexed and mapped; errors carry a
+/// `reason=` detail. A send already made for the same archive content within `resend_after_secs`
+/// is suppressed instead of repeated.
+pub fn archive_and_compact_session(
+    paths: &crate::moon::paths::MoonPaths,
+    session_key: &str,
+    source_path: &Path,
+    collection: &str,
+    strategy: &MoonCompactionStrategy,
+    resend_after_secs: u64,
+) -> std::result::Result<CompactedSession, String> {
+    // Compaction must not run before its archive is searchable, so this sync is never deferred.
+    let archived = archive_and_index(paths, source_path, collection, QmdIndexMode::Immediate)
+        .map_err(|err| format!("reason=archive-failed error={err:#}"))?;
+    // Private-channel archives are deliberately left out of the index.
+    if !archived.record.indexed && !archived.record.private {
+        return Err(format!(
+            "reason=index-failed archive={}",
+            archived.record.archive_path
+        ));
+    }
+    let mapped = channel_archive_map::upsert(
+        paths,
+        session_key,
+        &archived.record.source_path,
+        &archived.record.archive_path,
+        Some(&strategy.label()),
+    )
+    .map_err(|err| {
+        format!(
+            "reason=channel-archive-map-failed archive={} error={err:#}",
+            archived.record.archive_path
+        )
+    })?;
+
+    let once = GatewaySend {
+        paths,
+        session_key,
+        content_hash: &archived.record.content_hash,
+        resend_after_secs,
+    };
+    let compact_summary = once
+        .send("compact", |key| {
+            gateway::run_sessions_compact(session_key, strategy, key)
+        })
+        .map_err(|err| format!("archived={} error={err:#}", mapped.archive_path))?;
+    let index_note = match once.send("index-note", |key| {
+        gateway::run_sessions_index_note(
+            session_key,
+            &mapped.archive_path,
+            archived.record.projection_path.as_deref(),
+            &archived.record.source_path,
+            &archived.record.content_hash,
+            &archived.record.indexed_collection,
+            key,
+        )
+    }) {
+        Ok(note) => note,
+        Err(err) => {
+            warn::emit(WarnEvent {
+                code: "INDEX_NOTE_FAILED",
+                stage: "compaction",
+                action: "write-index-note",
+                session: session_key,
+                archive: &mapped.archive_path,
+                source: &archived.record.source_path,
+                retry: "retry-next-cycle",
+                reason: "chat-send-index-note-failed",
+                err: &format!("{err:#}"),
+            });
+            format!("index_note_failed error={err:#}")
+        }
+    };
+    let summary_path = archived
+        .record
+        .projection_path
+        .clone()
+        .unwrap_or_else(|| mapped.archive_path.clone());
+    append_continuity_record(
+        paths,
+        ContinuityRecord {
+            channel_key: session_key.to_string(),
+            reason: "compaction".to_string(),
+            source_session_id: archived.record.session_id.clone(),
+            target_session_id: archived.record.session_id.clone(),
+            archive_path: Some(mapped.archive_path.clone()),
+            summary_path: Some(summary_path),
+            recorded_at_epoch_secs: crate::moon::util::now_epoch_secs().unwrap_or(0),
+        },
+    );
+    hooks::fire(
+        paths,
+        HookEvent::PostCompaction,
+        &[
+            ("session_key", session_key),
+            ("session_id", &archived.record.session_id),
+            ("archive_path", &mapped.archive_path),
+            (
+                "projection_path",
+                archived
+                    .record
+                    .projection_path
+                    .as_deref()
+                    .unwrap_or_default(),
+            ),
+            ("strategy", &strategy.label()),
+        ],
+    );
+    Ok(CompactedSession {
+        archive_path: mapped.archive_path,
+        projection_path: archived.record.projection_path,
+        compaction_strategy: strategy.label(),
+        compact_summary,
+        index_note,
+    })
+}
+
+/// Gateway sends for one archived session content, recorded in the gateway call ledger.
+struct GatewaySend<'a> {
+    paths: &'a crate::moon::paths::MoonPaths,
+    session_key: &'a str,
+    content_hash: &'a str,
+    /// A matching send older than this may be repeated (the cooldown retrigger).
+    resend_after_secs: u64,
+}
+
+impl GatewaySend<'_> {
+    /// Runs `send` unless the same send for this content already succeeded within
+    /// `resend_after_secs` (e.g. just before a daemon restart); every attempt is logged.
+    fn send<F>(&self, kind: &str, send: F) -> Result<String>
+    where
+        F: FnOnce(&str) -> Result<String>,
+    {
+        let (paths, session_key) = (self.paths, self.session_key);
+        let key = gateway_calls::idempotency_key(kind, session_key, self.content_hash);
+        let now = crate::moon::util::now_epoch_secs()?;
+        let record = |outcome: &str, detail: String| {
+            gateway_calls::append(
+                paths,
+                &GatewayCallRecord {
+                    idempotency_key: key.clone(),
+                    kind: kind.to_string(),
+                    session_key: session_key.to_string(),
+                    outcome: outcome.to_string(),
+                    detail,
+                    at_epoch_secs: now,
+                },
+            )
+        };
+
+        // The ledger is bookkeeping: failing to write it must neither block a send nor turn a
+        // delivered one into a failure that gets retried.
+        let record_or_warn = |outcome: &str, detail: String| {
+            if let Err(err) = record(outcome, detail) {
+                warn::emit(WarnEvent {
+                    code: "GATEWAY_LEDGER_WRITE_FAILED",
+                    stage: "compaction",
+                    action: "record-gateway-call",
+                    session: session_key,
+                    archive: "na",
+                    source: "na",
+                    retry: "none",
+                    reason: outcome,
+                    err: &format!("{err:#}"),
+                });
+            }
+        };
+
+        let since = now.saturating_sub(self.resend_after_secs);
+        // An unreadable ledger only loses duplicate suppression; the send still goes out.
+        let previous = if self.resend_after_secs > 0 {
+            match gateway_calls::find_sent(paths, &key, since) {
+                Ok(previous) => previous,
+                Err(err) => {
+                    warn::emit(WarnEvent {
+                        code: "GATEWAY_LEDGER_READ_FAILED",
+                        stage: "compaction",
+                        action: "read-gateway-calls",
+                        session: session_key,
+                        archive: "na",
+                        source: "na",
+                        retry: "none",
+                        reason: "duplicate-check-skipped",
+                        err: &format!("{err:#}"),
+                    });
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        if let Some(previous) = previous {
+            record_or_warn(
+                gateway_calls::OUTCOME_SUPPRESSED,
+                format!("sent_at={}", previous.at_epoch_secs),
+            );
+            let summary = format!(
+                "suppressed-duplicate key={session_key} mode=chat.send:{kind} idempotency_key={key} sent_at={}",
+                previous.at_epoch_secs
+            );
+            let _ = audit::append_event(
+                paths,
+                "gateway",
+                "skipped",
+                &summary,
+                serde_json::json!({
+                    "reason": "suppressed-duplicate",
+                    "session": session_key,
+                    "mode": format!("chat.send:{kind}"),
+                    "idempotency_key": key,
+                    "sent_at": previous.at_epoch_secs,
+                }),
+            );
+            return Ok(summary);
+        }
+
+        record_or_warn(gateway_calls::OUTCOME_PENDING, String::new());
+        match send(&key) {
+            Ok(summary) => {
+                record_or_warn(gateway_calls::OUTCOME_SENT, summary.clone());
+                Ok(summary)
+            }
+            Err(err) => {
+                record_or_warn(gateway_calls::OUTCOME_FAILED, format!("{err:#}"));
+                Err(err)
+            }
+        }
+    }
+}
+
+fn append_continuity_record(paths: &crate::moon::paths::MoonPaths, record: ContinuityRecord) {
+    if let Err(err) = continuity::append_record(paths, &record) {
+        warn::emit(WarnEvent {
+            code: "CONTINUITY_FAILED",
+            stage: "continuity",
+            action: "append-record",
+            session: &record.channel_key,
+            archive: record.archive_path.as_deref().unwrap_or("na"),
+            source: "na",
+            retry: "none",
+            reason: "continuity-record-write-failed",
+            err: &format!("{err:#}"),
+        });
+    }
 }
 
-fn is_cooldown_ready(last_epoch: Option<u64>, now_epoch: u64, cooldown_secs: u64) -> bool {
+/// Records a rollover for each session key whose `sessionId` changed since the last cycle.
+/// The carried-over summary is the projection of the key's last mapped archive.
+fn record_session_rollovers(
+    paths: &crate::moon::paths::MoonPaths,
+    state: &mut crate::moon::state::MoonState,
+    now_epoch_secs: u64,
+) -> Option<ContinuityOutcome> {
+    let current = load_session_ids(&paths.openclaw_sessions_dir).ok()?;
+    let mut latest = None;
+    for (key, session_id) in &current {
+        let Some(previous) = state.session_ids.get(key) else {
+            continue;
+        };
+        if previous == session_id {
+            continue;
+        }
+        let mapped = channel_archive_map::get(paths, key).ok().flatten();
+        let summary_path = mapped.as_ref().map(|record| {
+            let projection = projection_path_for_archive(&record.archive_path);
+            if projection.exists() {
+                projection.display().to_string()
+            } else {
+                record.archive_path.clone()
+            }
+        });
+        append_continuity_record(
+            paths,
+            ContinuityRecord {
+                channel_key: key.clone(),
+                reason: "rollover".to_string(),
+                source_session_id: previous.clone(),
+                target_session_id: session_id.clone(),
+                archive_path: mapped.map(|record| record.archive_path),
+                summary_path,
+                recorded_at_epoch_secs: now_epoch_secs,
+            },
+        );
+        latest = Some(ContinuityOutcome {
+            map_path: continuity::records_path(paths).display().to_string(),
+            target_session_id: session_id.clone(),
+            rollover_ok: true,
+        });
+    }
+    state.session_ids = current;
+    latest
+}
+
+pub fn is_cooldown_ready(last_epoch: Option<u64>, now_epoch: u64, cooldown_secs: u64) -> bool {
     match last_epoch {
         None => true,
         Some(last) => now_epoch.saturating_sub(last) >= cooldown_secs,
     }
 }
 
-fn unified_layer1_last_trigger_epoch(state: &crate::moon::state::MoonState) -> Option<u64> {
-    match (
-        state.last_archive_trigger_epoch_secs,
-        state.last_compaction_trigger_epoch_secs,
-    ) {
-        (Some(a), Some(b)) => Some(a.max(b)),
-        (Some(v), None) | (None, Some(v)) => Some(v),
-        (None, None) => None,
+/// The `[policy] script` decision, or `default` when no script is set, it returns `nil`, or it
+/// fails (warned and audited; a broken script must not stop archiving).
+fn apply_policy_script(
+    paths: &crate::moon::paths::MoonPaths,
+    cfg: &crate::moon::config::MoonConfig,
+    state: &crate::moon::state::MoonState,
+    usage: &SessionUsageSnapshot,
+    default: Vec<TriggerKind>,
+) -> Vec<TriggerKind> {
+    let names = |triggers: &[TriggerKind]| {
+        triggers
+            .iter()
+            .map(|t| t.as_str())
+            .collect::<Vec<_>>()
+            .join(",")
+    };
+    match policy::decide(cfg, state, usage, &default) {
+        Ok(None) => default,
+        Ok(Some(decided)) => {
+            if decided != default {
+                let _ = audit::append_event(
+                    paths,
+                    "policy",
+                    "ok",
+                    &format!(
+                        "script={} session={} default=[{}] decided=[{}]",
+                        cfg.policy.script,
+                        usage.session_id,
+                        names(&default),
+                        names(&decided)
+                    ),
+                    serde_json::json!({
+                        "script": cfg.policy.script,
+                        "session": usage.session_id,
+                        "default": default.iter().map(|t| t.as_str()).collect::<Vec<_>>(),
+                        "decided": decided.iter().map(|t| t.as_str()).collect::<Vec<_>>(),
+                    }),
+                );
+            }
+            decided
+        }
+        Err(err) => {
+            warn::emit(WarnEvent {
+                code: "POLICY_SCRIPT_FAILED",
+                stage: "triggers",
+                action: "run-policy-script",
+                session: &usage.session_id,
+                archive: "na",
+                source: &cfg.policy.script,
+                retry: "builtin-decision",
+                reason: "policy-script-failed",
+                err: &format!("{err:#}"),
+            });
+            let _ = audit::append_event(
+                paths,
+                "policy",
+                "degraded",
+                &format!(
+                    "script={} fallback=[{}] error={err:#}",
+                    cfg.policy.script,
+                    names(&default)
+                ),
+                serde_json::json!({
+                    "script": cfg.policy.script,
+                    "session": usage.session_id,
+                    "fallback": default.iter().map(|t| t.as_str()).collect::<Vec<_>>(),
+                    "error": format!("{err:#}"),
+                }),
+            );
+            default
+        }
     }
 }
 
@@ -184,7 +1191,7 @@ fn compaction_authority_name(policy: Option<&MoonContextConfig>) -> String {
     }
 }
 
-fn effective_compaction_start_ratio(
+pub fn effective_compaction_start_ratio(
     cfg: &crate::moon::config::MoonConfig,
     policy: Option<&MoonContextConfig>,
 ) -> f64 {
@@ -199,6 +1206,49 @@ fn effective_compaction_start_ratio(
     cfg.thresholds.trigger_ratio
 }
 
+/// Stops the cycle at a phase boundary, or before the next item of an archive or distill loop,
+/// once `[watcher] max_cycle_secs` is spent. What the cycle already recorded, heartbeat
+/// included, is saved first so the daemon does not look dead.
+fn watchdog_checkpoint(
+    watchdog: &CycleWatchdog,
+    paths: &crate::moon::paths::MoonPaths,
+    state: &crate::moon::state::MoonState,
+    phase: &'static str,
+    dry_run: bool,
+) -> Result<()> {
+    let Err(overrun) = watchdog.enter(phase) else {
+        return Ok(());
+    };
+    if !dry_run {
+        let _ = save(paths, state);
+    }
+    let _ = audit::append_event(
+        paths,
+        "watchdog",
+        "failed",
+        &overrun.to_string(),
+        serde_json::json!({
+            "phase": overrun.phase,
+            "elapsed_secs": overrun.elapsed_secs,
+            "max_cycle_secs": overrun.max_cycle_secs,
+        }),
+    );
+    Err(overrun.into())
+}
+
+/// Mtime of the newest session file, the watcher's signal that a conversation is still active.
+fn latest_session_activity_epoch_secs(
+    paths: &crate::moon::paths::MoonPaths,
+    snapshot_exclude: &[String],
+) -> Option<u64> {
+    let source = latest_session_file(&paths.openclaw_sessions_dir, snapshot_exclude).ok()??;
+    let modified = fs::metadata(source).ok()?.modified().ok()?;
+    modified
+        .duration_since(UNIX_EPOCH)
+        .ok()
+        .map(|elapsed| elapsed.as_secs())
+}
+
 fn resolve_session_file_from_id(sessions_dir: &Path, session_id: &str) -> Option<PathBuf> {
     if session_id.trim().is_empty() {
         return None;
@@ -216,8 +1266,12 @@ fn resolve_session_file_from_id(sessions_dir: &Path, session_id: &str) -> Option
     None
 }
 
-fn resolve_session_file_from_entry(sessions_dir: &Path, entry: &Value) -> Option<PathBuf> {
-    if let Some(session_file) = entry.get("sessionFile").and_then(Value::as_str) {
+fn resolve_session_file_from_entry(
+    sessions_dir: &Path,
+    entry: &SessionStoreEntry,
+    dir_listing: &mut Option<Vec<PathBuf>>,
+) -> Option<PathBuf> {
+    if let Some(session_file) = entry.session_file.as_deref() {
         let trimmed = session_file.trim();
         if !trimmed.is_empty() {
             let candidate = PathBuf::from(trimmed);
@@ -227,10 +1281,7 @@ fn resolve_session_file_from_entry(sessions_dir: &Path, entry: &Value) -> Option
         }
     }
 
-    let session_id = entry
-        .get("sessionId")
-        .and_then(Value::as_str)
-        .or_else(|| entry.get("id").and_then(Value::as_str))?;
+    let session_id = entry.session_id()?;
 
     if let Some(source) = resolve_session_file_from_id(sessions_dir, session_id) {
         return Some(source);
@@ -243,85 +1294,578 @@ fn resolve_session_file_from_entry(sessions_dir: &Path, entry: &Value) -> Option
         format!("_{session_id}.json"),
     ];
 
-    let entries = fs::read_dir(sessions_dir).ok()?;
-    for item in entries.flatten() {
-        let path = item.path();
-        if !path.is_file() {
-            continue;
+    // Listed once per source-map load rather than once per unresolved entry.
+    let listing = dir_listing.get_or_insert_with(|| {
+        fs::read_dir(sessions_dir)
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .map(|item| item.path())
+                    .filter(|path| path.is_file())
+                    .collect()
+            })
+            .unwrap_or_default()
+    });
+    listing
+        .iter()
+        .find(|path| {
+            path.file_name()
+                .and_then(|value| value.to_str())
+                .is_some_and(|name| suffixes.iter().any(|suffix| name.ends_with(suffix)))
+        })
+        .cloned()
+}
+
+/// The fields moon reads from a `sessions.json` entry; everything else is skipped while
+/// parsing instead of being materialized.
+#[derive(Debug, Clone, Default, Deserialize)]
+struct SessionStoreEntry {
+    #[serde(default, rename = "sessionFile")]
+    session_file: Option<String>,
+    #[serde(default, rename = "sessionId")]
+    session_id: Option<String>,
+    #[serde(default)]
+    id: Option<String>,
+}
+
+impl SessionStoreEntry {
+    fn session_id(&self) -> Option<&str> {
+        self.session_id.as_deref().or(self.id.as_deref())
+    }
+}
+
+/// Non-object entries (and objects with unexpected field types) are ignored.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SessionStoreValue {
+    Entry(SessionStoreEntry),
+    Other(serde::de::IgnoredAny),
+}
+
+type SessionStore = Arc<BTreeMap<String, SessionStoreEntry>>;
+
+struct CachedSessionStore {
+    path: PathBuf,
+    modified: Option<std::time::SystemTime>,
+    len: u64,
+    entries: SessionStore,
+}
+
+/// Last parsed `sessions.json`, reused until its mtime or size changes; the watcher consults
+/// it several times per cycle and large installs carry thousands of entries.
+static SESSION_STORE_CACHE: Mutex<Option<CachedSessionStore>> = Mutex::new(None);
+
+fn load_session_store(sessions_dir: &Path) -> Result<Option<SessionStore>> {
+    let store = sessions_dir.join("sessions.json");
+    let Ok(meta) = fs::metadata(&store) else {
+        return Ok(None);
+    };
+    let modified = meta.modified().ok();
+    let len = meta.len();
+
+    let mut cache = SESSION_STORE_CACHE
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    if let Some(cached) = cache.as_ref()
+        && cached.path == store
+        && cached.len == len
+        && modified.is_some()
+        && cached.modified == modified
+    {
+        return Ok(Some(cached.entries.clone()));
+    }
+
+    let file = File::open(&store).with_context(|| format!("failed to read {}", store.display()))?;
+    let parsed: BTreeMap<String, SessionStoreValue> =
+        serde_json::from_reader(std::io::BufReader::new(file)).with_context(|| {
+            format!(
+                "failed to parse {} (sessions.json should be an object map keyed by session key)",
+                store.display()
+            )
+        })?;
+    let entries: SessionStore = Arc::new(
+        parsed
+            .into_iter()
+            .filter_map(|(key, value)| match value {
+                SessionStoreValue::Entry(entry) => Some((key, entry)),
+                SessionStoreValue::Other(_) => None,
+            })
+            .collect(),
+    );
+    *cache = Some(CachedSessionStore {
+        path: store,
+        modified,
+        len,
+        entries: entries.clone(),
+    });
+    Ok(Some(entries))
+}
+
+/// `sessionId` per session key from `sessions.json`.
+pub fn load_session_ids(sessions_dir: &Path) -> Result<BTreeMap<String, String>> {
+    let Some(store) = load_session_store(sessions_dir)? else {
+        return Ok(BTreeMap::new());
+    };
+    Ok(store
+        .iter()
+        .filter_map(|(key, entry)| entry.session_id().map(|id| (key.clone(), id.to_string())))
+        .collect())
+}
+
+pub fn load_session_source_map(sessions_dir: &Path) -> Result<BTreeMap<String, PathBuf>> {
+    let Some(store) = load_session_store(sessions_dir)? else {
+        return Ok(BTreeMap::new());
+    };
+
+    let mut dir_listing = None;
+    let mut out = BTreeMap::new();
+    for (key, entry) in store.iter() {
+        if let Some(source) = resolve_session_file_from_entry(sessions_dir, entry, &mut dir_listing)
+        {
+            out.insert(key.clone(), source);
         }
-        let Some(name) = path.file_name().and_then(|value| value.to_str()) else {
-            continue;
+    }
+
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{GatewaySend, load_session_ids, load_session_source_map};
+    use crate::moon::gateway_calls::{self, OUTCOME_SENT};
+    use crate::moon::paths::MoonPaths;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn gateway_send_suppresses_repeats_in_window_and_survives_ledger_failures() {
+        let tmp = tempdir().expect("tempdir");
+        let paths = MoonPaths::for_test(tmp.path());
+        let sends = std::cell::Cell::new(0);
+        let send = |_: &str| {
+            sends.set(sends.get() + 1);
+            Ok("sent".to_string())
+        };
+        let watcher = GatewaySend {
+            paths: &paths,
+            session_key: "chan:a",
+            content_hash: "hash-1",
+            resend_after_secs: 600,
+        };
+        assert_eq!(watcher.send("compact", send).expect("send"), "sent");
+        assert!(
+            watcher
+                .send("compact", send)
+                .expect("suppressed")
+                .starts_with("suppressed-duplicate")
+        );
+        assert_eq!(sends.get(), 1);
+
+        // A manual send (no window) always goes out.
+        let manual = GatewaySend {
+            resend_after_secs: 0,
+            ..watcher
+        };
+        manual.send("compact", send).expect("manual send");
+        assert_eq!(sends.get(), 2);
+        let records = gateway_calls::read_all(&paths).expect("read ledger");
+        assert_eq!(
+            records.iter().filter(|r| r.outcome == OUTCOME_SENT).count(),
+            2
+        );
+
+        // An unwritable ledger neither blocks the send nor turns it into a failure.
+        let broken = MoonPaths::for_test(&tmp.path().join("broken"));
+        fs::create_dir_all(&broken.moon_home).expect("mkdir");
+        fs::write(broken.moon_home.join("continuity"), "not a dir").expect("block ledger dir");
+        let unrecorded = GatewaySend {
+            paths: &broken,
+            ..watcher
+        };
+        assert_eq!(unrecorded.send("compact", send).expect("send"), "sent");
+        assert_eq!(sends.get(), 3);
+    }
+
+    #[test]
+    fn load_session_source_map_uses_session_file_for_timestamp_prefixed_sessions() {
+        let tmp = tempdir().expect("tempdir");
+        let sessions_dir = tmp.path();
+        let session_path = sessions_dir
+            .join("2026-03-09T01-23-35-028Z_27715212-d3cf-4100-8a06-c2ee9de2cccc.jsonl");
+        fs::write(&session_path, "{}\n").expect("write session file");
+        fs::write(
+            sessions_dir.join("sessions.json"),
+            format!(
+                concat!(
+                    "{{\n",
+                    "  \"agent:main:discord:channel:1480375183742206035\": {{\n",
+                    "    \"sessionId\": \"27715212-d3cf-4100-8a06-c2ee9de2cccc\",\n",
+                    "    \"sessionFile\": \"{}\"\n",
+                    "  }}\n",
+                    "}}\n"
+                ),
+                session_path.display()
+            ),
+        )
+        .expect("write sessions.json");
+
+        let map = load_session_source_map(sessions_dir).expect("load source map");
+        assert_eq!(
+            map.get("agent:main:discord:channel:1480375183742206035"),
+            Some(&session_path)
+        );
+    }
+
+    #[test]
+    fn load_session_ids_skips_non_object_entries_and_reloads_when_store_changes() {
+        let tmp = tempdir().expect("tempdir");
+        let store = tmp.path().join("sessions.json");
+        fs::write(
+            &store,
+            r#"{"agent:main:a":{"sessionId":"s-1","usage":{"totalTokens":10}},"meta":3,"agent:main:b":{"id":"s-2"}}"#,
+        )
+        .expect("write sessions.json");
+
+        let ids = load_session_ids(tmp.path()).expect("load ids");
+        assert_eq!(ids.len(), 2);
+        assert_eq!(ids.get("agent:main:a").map(String::as_str), Some("s-1"));
+        assert_eq!(ids.get("agent:main:b").map(String::as_str), Some("s-2"));
+        assert_eq!(load_session_ids(tmp.path()).expect("cached ids"), ids);
+
+        fs::write(&store, r#"{"agent:main:a":{"sessionId":"s-rolled-over"}}"#)
+            .expect("rewrite sessions.json");
+        let ids = load_session_ids(tmp.path()).expect("reload ids");
+        assert_eq!(ids.len(), 1);
+        assert_eq!(
+            ids.get("agent:main:a").map(String::as_str),
+            Some("s-rolled-over")
+        );
+    }
+}
+
+/// Every `[watcher] consistency_check_every` cycles, cross-checks state, ledger, channel map, and
+/// disk; with `consistency_repair` the dangling references found are dropped or repointed.
+fn run_consistency_check(
+    paths: &crate::moon::paths::MoonPaths,
+    state: &mut crate::moon::state::MoonState,
+    cfg: &crate::moon::config::MoonConfig,
+) -> Result<Option<String>> {
+    let every = cfg.watcher.consistency_check_every;
+    if every == 0 || !state.watch_cycles.is_multiple_of(every) {
+        return Ok(None);
+    }
+    let checked = consistency::check(paths, state).and_then(|findings| {
+        let repaired = if cfg.watcher.consistency_repair
+            && findings.iter().any(|finding| finding.kind.repairable())
+        {
+            Some(consistency::repair(paths, state, &findings)?)
+        } else {
+            None
+        };
+        Ok((findings, repaired))
+    });
+    let (result, status, details) = match checked {
+        Ok((findings, repaired)) => {
+            let mut result = format!(
+                "cycle={} findings={}",
+                state.watch_cycles,
+                consistency::summarize(&findings)
+            );
+            let unrepaired = findings
+                .iter()
+                .filter(|finding| repaired.is_none() || !finding.kind.repairable())
+                .count();
+            if let Some(outcome) = repaired {
+                result.push_str(&format!(" repaired {}", outcome.summary()));
+            }
+            if unrepaired > 0 {
+                warn::emit(WarnEvent {
+                    code: "CONSISTENCY_DANGLING_REFERENCES",
+                    stage: "consistency",
+                    action: "cross-check",
+                    session: "na",
+                    archive: "na",
+                    source: "na",
+                    retry: "run-moon-health-repair",
+                    reason: "dangling-references",
+                    err: &format!("unrepaired={unrepaired}"),
+                });
+            }
+            let status = if unrepaired > 0 {
+                "degraded"
+            } else if findings.is_empty() {
+                "ok"
+            } else {
+                "repaired"
+            };
+            let details = serde_json::json!({
+                "cycle": state.watch_cycles,
+                "findings": consistency::kind_counts(&findings)
+                    .into_iter()
+                    .collect::<BTreeMap<_, _>>(),
+                "repaired": repaired,
+                "unrepaired": unrepaired,
+            });
+            (result, status, details)
+        }
+        Err(err) => (
+            format!("failed error={err:#}"),
+            "degraded",
+            serde_json::json!({"cycle": state.watch_cycles, "error": format!("{err:#}")}),
+        ),
+    };
+    audit::append_event(paths, "consistency", status, &result, details)?;
+    Ok(Some(result))
+}
+
+/// Cycles a pending compaction summary is looked for before it is given up on.
+const COMPACTION_ANCHOR_MAX_ATTEMPTS: u64 = 5;
+
+/// Fetches the summaries OpenClaw wrote for `/compact` requests from earlier cycles and records
+/// them as compaction anchors on each pre-compaction archive, re-rendering its projection.
+fn run_compaction_anchor_fetch(
+    paths: &crate::moon::paths::MoonPaths,
+    state: &mut crate::moon::state::MoonState,
+    new_projections: &mut Vec<PathBuf>,
+) -> Result<Option<String>> {
+    if state.pending_compaction_anchors.is_empty() {
+        return Ok(None);
+    }
+    let pending = std::mem::take(&mut state.pending_compaction_anchors);
+    let mut outcomes = Vec::new();
+    let mut outcome_details = Vec::new();
+    let (mut recorded, mut waiting, mut expired) = (0usize, 0usize, 0usize);
+    for (session_key, mut entry) in pending {
+        let fetched = gateway::fetch_compaction_summaries(&session_key).and_then(|summaries| {
+            let anchors = summaries
+                .into_iter()
+                .filter(|summary| {
+                    summary
+                        .timestamp_epoch_secs
+                        .is_none_or(|ts| ts >= entry.requested_at_epoch_secs)
+                })
+                .map(|summary| CompactionAnchor {
+                    note: summary.summary,
+                    origin_message_id: summary.entry_id,
+                })
+                .collect::<Vec<_>>();
+            if anchors.is_empty() {
+                return Ok(None);
+            }
+            record_compaction_anchors(paths, &entry.archive_path, &anchors)
+                .map(|record| Some((anchors.len(), record)))
+        });
+        let err = match fetched {
+            Ok(Some((count, record))) => {
+                recorded += 1;
+                if let Some(record) = record
+                    && let Some(projection) = &record.projection_path
+                {
+                    new_projections.push(PathBuf::from(projection));
+                    if record.indexed {
+                        queue_qmd_sync(paths, state, &record.indexed_collection);
+                    }
+                }
+                outcomes.push(format!(
+                    "recorded key={session_key} archive={} anchors={count}",
+                    entry.archive_path
+                ));
+                outcome_details.push(serde_json::json!({
+                    "status": "recorded",
+                    "session": session_key,
+                    "archive": entry.archive_path,
+                    "anchors": count,
+                }));
+                continue;
+            }
+            Ok(None) => "no-summary-reported".to_string(),
+            Err(err) => format!("{err:#}"),
         };
-        if suffixes.iter().any(|suffix| name.ends_with(suffix)) {
-            return Some(path);
+        entry.attempts += 1;
+        if entry.attempts < COMPACTION_ANCHOR_MAX_ATTEMPTS {
+            waiting += 1;
+            state.pending_compaction_anchors.insert(session_key, entry);
+            continue;
         }
+        expired += 1;
+        warn::emit(WarnEvent {
+            code: "COMPACTION_ANCHOR_FETCH_FAILED",
+            stage: "compaction",
+            action: "fetch-compaction-summary",
+            session: &session_key,
+            archive: &entry.archive_path,
+            source: "gateway:chat.history",
+            retry: "none",
+            reason: "attempts-exhausted",
+            err: &err,
+        });
+        outcomes.push(format!(
+            "expired key={session_key} archive={} attempts={} error={err}",
+            entry.archive_path, entry.attempts
+        ));
+        outcome_details.push(serde_json::json!({
+            "status": "expired",
+            "session": session_key,
+            "archive": entry.archive_path,
+            "attempts": entry.attempts,
+            "error": err,
+        }));
     }
+    let mut result = format!("recorded={recorded} waiting={waiting} expired={expired}");
+    if !outcomes.is_empty() {
+        result.push_str(&format!(" {}", outcomes.join(" | ")));
+    }
+    let status = if expired > 0 { "degraded" } else { "ok" };
+    // Anchors are recorded and pending entries re-queued; an unwritable audit log must not
+    // fail the cycle after them.
+    let _ = audit::append_event(
+        paths,
+        "compaction-anchors",
+        status,
+        &result,
+        serde_json::json!({
+            "recorded": recorded,
+            "waiting": waiting,
+            "expired": expired,
+            "outcomes": outcome_details,
+        }),
+    );
+    Ok(Some(result))
+}
 
-    None
+/// Session key per ledger `source_path`, for matching archives against `[sessions]` filters.
+fn session_keys_by_source(paths: &crate::moon::paths::MoonPaths) -> BTreeMap<String, String> {
+    load_session_source_map(&paths.openclaw_sessions_dir)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(key, source)| (portable_path_string(&source), key))
+        .collect()
 }
 
-fn load_session_source_map(sessions_dir: &Path) -> Result<BTreeMap<String, PathBuf>> {
-    let store = sessions_dir.join("sessions.json");
-    if !store.exists() {
-        return Ok(BTreeMap::new());
+/// Failed `chat.send` attempts before a session's memory primer is given up on.
+const MEMORY_PRIMER_MAX_ATTEMPTS: u64 = 3;
+
+fn run_memory_primer_for_new_sessions(
+    paths: &crate::moon::paths::MoonPaths,
+    cfg: &crate::moon::config::MoonConfig,
+    state: &mut crate::moon::state::MoonState,
+    sessions: &[SessionUsageSnapshot],
+    now_epoch_secs: u64,
+) -> Option<String> {
+    if !cfg.memory.inject_on_new_session {
+        return None;
     }
+    let session_keys = sessions
+        .iter()
+        .map(|session| session.session_id.trim())
+        .filter(|key| !key.is_empty())
+        .collect::<BTreeSet<_>>();
 
-    let raw = fs::read_to_string(&store)
-        .with_context(|| format!("failed to read {}", store.display()))?;
-    let parsed: Value = serde_json::from_str(&raw)
-        .with_context(|| format!("failed to parse {}", store.display()))?;
-    let object = parsed
-        .as_object()
-        .context("sessions.json should be an object map keyed by session key")?;
+    // Sessions gone from sessions.json no longer need a primed marker; an unreadable store
+    // keeps every marker rather than re-priming live sessions.
+    if let Ok(Some(store)) = load_session_store(&paths.openclaw_sessions_dir) {
+        let live = |key: &String| store.contains_key(key) || session_keys.contains(key.as_str());
+        state.memory_primed_sessions.retain(|key, _| live(key));
+        state.memory_primer_failures.retain(|key, _| live(key));
+    }
 
-    let mut out = BTreeMap::new();
-    for (key, entry) in object {
-        if !entry.is_object() {
-            continue;
-        }
-        if let Some(source) = resolve_session_file_from_entry(sessions_dir, entry) {
-            out.insert(key.clone(), source);
+    // First enabled cycle only records existing sessions so they are not primed retroactively.
+    if !state.memory_primer_seeded {
+        for key in &session_keys {
+            state
+                .memory_primed_sessions
+                .insert((*key).to_string(), now_epoch_secs);
         }
+        state.memory_primer_seeded = true;
+        return Some(format!("seeded sessions={}", session_keys.len()));
     }
 
-    Ok(out)
-}
-
-#[cfg(test)]
-mod tests {
-    use super::load_session_source_map;
-    use std::fs;
-    use tempfile::tempdir;
+    let new_keys = session_keys
+        .into_iter()
+        .filter(|key| !state.memory_primed_sessions.contains_key(*key))
+        .collect::<Vec<_>>();
+    if new_keys.is_empty() {
+        return Some("new_sessions=0".to_string());
+    }
 
-    #[test]
-    fn load_session_source_map_uses_session_file_for_timestamp_prefixed_sessions() {
-        let tmp = tempdir().expect("tempdir");
-        let sessions_dir = tmp.path();
-        let session_path = sessions_dir
-            .join("2026-03-09T01-23-35-028Z_27715212-d3cf-4100-8a06-c2ee9de2cccc.jsonl");
-        fs::write(&session_path, "{}\n").expect("write session file");
-        fs::write(
-            sessions_dir.join("sessions.json"),
-            format!(
-                concat!(
-                    "{{\n",
-                    "  \"agent:main:discord:channel:1480375183742206035\": {{\n",
-                    "    \"sessionId\": \"27715212-d3cf-4100-8a06-c2ee9de2cccc\",\n",
-                    "    \"sessionFile\": \"{}\"\n",
-                    "  }}\n",
-                    "}}\n"
-                ),
-                session_path.display()
-            ),
-        )
-        .expect("write sessions.json");
+    // Like an empty memory, a blocked chat.send marks the sessions handled instead of
+    // failing them every cycle; they are not primed retroactively once it is allowed again.
+    let memory = fs::read_to_string(&paths.memory_file).unwrap_or_default();
+    let primer = build_memory_primer(&memory, cfg.memory.primer_max_tokens);
+    let skip_reason = if !gateway::chat_send_allowed() {
+        Some("chat-send-disabled")
+    } else if primer.is_none() {
+        Some("memory-empty")
+    } else {
+        None
+    };
+    let (Some(primer), None) = (primer, skip_reason) else {
+        for key in &new_keys {
+            state
+                .memory_primed_sessions
+                .insert((*key).to_string(), now_epoch_secs);
+        }
+        return Some(format!(
+            "new_sessions={} injected=0 reason={}",
+            new_keys.len(),
+            skip_reason.unwrap_or_default()
+        ));
+    };
 
-        let map = load_session_source_map(sessions_dir).expect("load source map");
-        assert_eq!(
-            map.get("agent:main:discord:channel:1480375183742206035"),
-            Some(&session_path)
-        );
+    let mut injected = 0usize;
+    let mut failed = 0usize;
+    let mut gave_up = 0usize;
+    for key in &new_keys {
+        match gateway::run_sessions_memory_primer(key, &primer) {
+            Ok(_) => {
+                injected += 1;
+                state.memory_primer_failures.remove(*key);
+                state
+                    .memory_primed_sessions
+                    .insert((*key).to_string(), now_epoch_secs);
+            }
+            Err(err) => {
+                failed += 1;
+                let attempts = state
+                    .memory_primer_failures
+                    .entry((*key).to_string())
+                    .or_default();
+                *attempts += 1;
+                let exhausted = *attempts >= MEMORY_PRIMER_MAX_ATTEMPTS;
+                if exhausted {
+                    gave_up += 1;
+                    state.memory_primer_failures.remove(*key);
+                    state
+                        .memory_primed_sessions
+                        .insert((*key).to_string(), now_epoch_secs);
+                }
+                warn::emit(WarnEvent {
+                    code: "MEMORY_PRIMER_FAILED",
+                    stage: "memory-primer",
+                    action: "chat-send",
+                    session: key,
+                    archive: "na",
+                    source: "na",
+                    retry: if exhausted {
+                        "none"
+                    } else {
+                        "retry-next-cycle"
+                    },
+                    reason: if exhausted {
+                        "max-attempts-reached"
+                    } else {
+                        "gateway-send-failed"
+                    },
+                    err: &format!("{err:#}"),
+                });
+            }
+        }
     }
+    Some(format!(
+        "new_sessions={} injected={injected} failed={failed} gave_up={gave_up} primer_bytes={}",
+        new_keys.len(),
+        primer.len()
+    ))
 }
 
 fn resolve_distill_source_path(
@@ -405,12 +1949,40 @@ fn is_distillable_archive_record(record: &crate::moon::archive::ArchiveRecord) -
     true
 }
 
+/// Why a cold archive must not be deleted yet, if anything is missing that would make the
+/// deletion irreversible: the projection or the distilled daily-memory summary.
+fn retention_protection_reason(
+    paths: &crate::moon::paths::MoonPaths,
+    record: &crate::moon::archive::ArchiveRecord,
+    projection_path: &Path,
+) -> Option<&'static str> {
+    if !projection_path.exists() {
+        return Some("projection-missing");
+    }
+    if !daily_memory_has_session(
+        paths,
+        &record.session_id,
+        Some(record.created_at_epoch_secs),
+    ) {
+        return Some("distilled-summary-missing");
+    }
+    None
+}
+
+/// One retention pass: the audit/summary line, its typed details and how many steps failed.
+struct RetentionSweep {
+    summary: String,
+    details: serde_json::Value,
+    failed: usize,
+}
+
 fn cleanup_expired_distilled_archives(
     paths: &crate::moon::paths::MoonPaths,
     state: &mut crate::moon::state::MoonState,
     now_epoch_secs: u64,
-    retention: &MoonRetentionConfig,
-) -> Result<Option<String>> {
+    cfg: &crate::moon::config::MoonConfig,
+) -> Result<Option<RetentionSweep>> {
+    let retention = &cfg.retention;
     let ledger = match read_ledger_records(paths) {
         Ok(records) => records,
         Err(err) => {
@@ -425,28 +1997,46 @@ fn cleanup_expired_distilled_archives(
                 reason: "ledger-read-failed",
                 err: &format!("{err:#}"),
             });
-            return Ok(Some(format!(
-                "retention_active_days={} retention_warm_days={} retention_cold_days={} removed=0 missing=0 failed=1 map_removed=0 ledger_removed=0 qmd_updated=false reason=ledger-read-failed",
-                retention.active_days, retention.warm_days, retention.cold_days
-            )));
+            return Ok(Some(RetentionSweep {
+                summary: format!(
+                    "retention_active_days={} retention_warm_days={} retention_cold_days={} removed=0 missing=0 failed=1 map_repointed=0 map_removed=0 ledger_removed=0 qmd_sync_queued=false reason=ledger-read-failed",
+                    retention.active_days, retention.warm_days, retention.cold_days
+                ),
+                details: serde_json::json!({
+                    "retention_active_days": retention.active_days,
+                    "retention_warm_days": retention.warm_days,
+                    "retention_cold_days": retention.cold_days,
+                    "failed": 1,
+                    "reason": "ledger-read-failed",
+                    "error": format!("{err:#}"),
+                }),
+                failed: 1,
+            }));
         }
     };
     let ledger_by_archive = ledger
         .into_iter()
-        .map(|r| (r.archive_path, r.created_at_epoch_secs))
+        .map(|r| (r.archive_path.clone(), r))
         .collect::<BTreeMap<_, _>>();
 
     let seconds_per_day = 86_400u64;
     let mut active_count = 0usize;
     let mut warm_count = 0usize;
     let mut cold_candidates = 0usize;
+    let mut superseded_candidates = 0usize;
     let mut purge_paths = BTreeSet::new();
+    let mut retired_map_targets = BTreeMap::new();
+    let mut vector_purge_paths = BTreeSet::new();
+    let mut purged_collections = BTreeSet::new();
     let mut removed_files = 0usize;
     let mut missing_files = 0usize;
     let mut failed = 0usize;
     let mut projection_removed = 0usize;
     let mut projection_missing = 0usize;
     let mut projection_failed = 0usize;
+    let mut protected = BTreeMap::new();
+    let mut newly_protected = Vec::new();
+    let mut forced = Vec::new();
 
     let candidates = state
         .distilled_archives
@@ -455,7 +2045,7 @@ fn cleanup_expired_distilled_archives(
         .collect::<Vec<_>>();
 
     for (archive_path, distilled_at) in candidates {
-        let Some(created_at) = ledger_by_archive.get(&archive_path).copied() else {
+        let Some(record) = ledger_by_archive.get(&archive_path) else {
             warn::emit(WarnEvent {
                 code: "LEDGER_READ_FAILED",
                 stage: "archive-retention",
@@ -471,17 +2061,29 @@ fn cleanup_expired_distilled_archives(
         };
 
         let age_days = now_epoch_secs
-            .saturating_sub(created_at)
+            .saturating_sub(record.created_at_epoch_secs)
             .saturating_div(seconds_per_day);
         if age_days <= retention.active_days {
             active_count += 1;
             continue;
         }
-        if age_days <= retention.warm_days || age_days < retention.cold_days {
+        // A superseded snapshot lives on in its newer archive, so it leaves after the active
+        // window instead of waiting for the cold window.
+        let successor = record
+            .superseded_by
+            .as_deref()
+            .filter(|newer| Path::new(newer).exists());
+        if successor.is_none()
+            && (age_days <= retention.warm_days || age_days < retention.cold_days)
+        {
             warm_count += 1;
             continue;
         }
-        cold_candidates += 1;
+        if successor.is_some() {
+            superseded_candidates += 1;
+        } else {
+            cold_candidates += 1;
+        }
 
         if now_epoch_secs.saturating_sub(distilled_at) < seconds_per_day {
             // Require at least one day from distill marker before delete to reduce race risk.
@@ -489,111 +2091,415 @@ fn cleanup_expired_distilled_archives(
         }
         let projection_path = projection_path_for_archive(&archive_path);
         let projection_path_display = projection_path.display().to_string();
+        let collection = &record.indexed_collection;
 
-        if Path::new(&archive_path).exists() {
-            match fs::remove_file(&archive_path) {
-                Ok(_) => {
-                    removed_files += 1;
-                    purge_paths.insert(archive_path.clone());
-                    state.distilled_archives.remove(&archive_path);
-                    match fs::remove_file(&projection_path) {
-                        Ok(_) => projection_removed += 1,
-                        Err(err) if err.kind() == ErrorKind::NotFound => {
-                            projection_missing += 1;
-                        }
-                        Err(err) => {
-                            projection_failed += 1;
-                            warn::emit(WarnEvent {
-                                code: "RETENTION_DELETE_FAILED",
-                                stage: "archive-retention",
-                                action: "delete-projection",
-                                session: "na",
-                                archive: &archive_path,
-                                source: &projection_path_display,
-                                retry: "retry-next-cycle",
-                                reason: "remove-projection-file-failed",
-                                err: &format!("{err:#}"),
-                            });
-                        }
-                    }
-                }
-                Err(err) => {
-                    failed += 1;
+        if successor.is_none()
+            && Path::new(&archive_path).exists()
+            && let Some(reason) = retention_protection_reason(paths, record, &projection_path)
+        {
+            if !retention.force {
+                let first_refused = state
+                    .retention_protected_archives
+                    .get(&archive_path)
+                    .copied();
+                if first_refused.is_none() {
                     warn::emit(WarnEvent {
-                        code: "RETENTION_DELETE_FAILED",
+                        code: "RETENTION_UNDISTILLED",
                         stage: "archive-retention",
-                        action: "delete-archive",
-                        session: "na",
+                        action: "skip-delete",
+                        session: &record.session_id,
                         archive: &archive_path,
-                        source: "na",
+                        source: &projection_path_display,
                         retry: "retry-next-cycle",
-                        reason: "remove-file-failed",
-                        err: &format!("{err:#}"),
+                        reason,
+                        err: "set-retention-force-to-delete",
                     });
+                    newly_protected.push(format!("{archive_path} ({reason})"));
                 }
+                protected.insert(
+                    archive_path.clone(),
+                    first_refused.unwrap_or(now_epoch_secs),
+                );
+                continue;
             }
-        } else {
-            missing_files += 1;
-            purge_paths.insert(archive_path.clone());
-            state.distilled_archives.remove(&archive_path);
-            match fs::remove_file(&projection_path) {
-                Ok(_) => projection_removed += 1,
-                Err(err) if err.kind() == ErrorKind::NotFound => {
-                    projection_missing += 1;
+            forced.push(format!("{archive_path} ({reason})"));
+        }
+
+        let origin = TrashOrigin {
+            archive_path: &archive_path,
+            reason: if successor.is_some() {
+                "retention-superseded"
+            } else {
+                "retention-cold"
+            },
+            record: Some(record),
+            distilled_at_epoch_secs: Some(distilled_at),
+        };
+        match move_to_trash(paths, Path::new(&archive_path), origin, now_epoch_secs) {
+            Ok(moved) => {
+                if let Some(entry) = &moved {
+                    removed_files += 1;
+                    hooks::fire(
+                        paths,
+                        HookEvent::RetentionDelete,
+                        &[
+                            ("session_id", &record.session_id),
+                            ("archive_path", &archive_path),
+                            ("trashed_path", &entry.trashed_path),
+                            ("reason", &entry.reason),
+                        ],
+                    );
+                } else {
+                    missing_files += 1;
                 }
-                Err(err) => {
-                    projection_failed += 1;
+                purge_paths.insert(archive_path.clone());
+                // Channels mapped to this archive fall back to what outlives it.
+                let map_target = successor.map(str::to_string).or_else(|| {
+                    daily_memory_file_for_session(
+                        paths,
+                        &record.session_id,
+                        Some(record.created_at_epoch_secs),
+                    )
+                    .map(|path| path.display().to_string())
+                });
+                retired_map_targets.insert(archive_path.clone(), map_target);
+                purged_collections.insert(collection.clone());
+                state.distilled_archives.remove(&archive_path);
+                let sidecar_path = projection_sidecar_path(&projection_path);
+                if let Err(err) = move_to_trash(paths, &sidecar_path, origin, now_epoch_secs) {
                     warn::emit(WarnEvent {
                         code: "RETENTION_DELETE_FAILED",
                         stage: "archive-retention",
-                        action: "delete-projection",
+                        action: "trash-projection-sidecar",
                         session: "na",
                         archive: &archive_path,
-                        source: &projection_path_display,
+                        source: &sidecar_path.display().to_string(),
                         retry: "retry-next-cycle",
-                        reason: "remove-projection-file-failed",
+                        reason: "move-projection-sidecar-to-trash-failed",
                         err: &format!("{err:#}"),
                     });
                 }
+                match move_to_trash(paths, &projection_path, origin, now_epoch_secs) {
+                    Ok(moved) => {
+                        if moved.is_some() {
+                            projection_removed += 1;
+                        } else {
+                            projection_missing += 1;
+                        }
+                        vector_purge_paths.insert(projection_path_display.clone());
+                    }
+                    Err(err) => {
+                        projection_failed += 1;
+                        warn::emit(WarnEvent {
+                            code: "RETENTION_DELETE_FAILED",
+                            stage: "archive-retention",
+                            action: "trash-projection",
+                            session: "na",
+                            archive: &archive_path,
+                            source: &projection_path_display,
+                            retry: "retry-next-cycle",
+                            reason: "move-projection-to-trash-failed",
+                            err: &format!("{err:#}"),
+                        });
+                    }
+                }
+            }
+            Err(err) => {
+                failed += 1;
+                warn::emit(WarnEvent {
+                    code: "RETENTION_DELETE_FAILED",
+                    stage: "archive-retention",
+                    action: "trash-archive",
+                    session: "na",
+                    archive: &archive_path,
+                    source: "na",
+                    retry: "retry-next-cycle",
+                    reason: "move-archive-to-trash-failed",
+                    err: &format!("{err:#}"),
+                });
             }
         }
     }
 
-    if purge_paths.is_empty() && failed == 0 {
+    let protected_count = protected.len();
+    state.retention_protected_archives = protected;
+    if !newly_protected.is_empty() {
+        let outcomes = notify::notify(
+            &cfg.notify,
+            NotifyEvent::RetentionUndistilled,
+            &format!(
+                "retention kept {} cold archive(s) without a distilled summary or projection: {}",
+                newly_protected.len(),
+                newly_protected.join(", ")
+            ),
+        );
+        append_notify_audit(paths, NotifyEvent::RetentionUndistilled, &outcomes);
+    }
+    if !forced.is_empty() {
+        let outcomes = notify::notify(
+            &cfg.notify,
+            NotifyEvent::RetentionUndistilled,
+            &format!(
+                "retention.force deleted {} cold archive(s) without a distilled summary or projection: {}",
+                forced.len(),
+                forced.join(", ")
+            ),
+        );
+        append_notify_audit(paths, NotifyEvent::RetentionUndistilled, &outcomes);
+    }
+
+    let trash_purge = match trash::purge_trash(
+        paths,
+        Some(retention.trash_days.saturating_mul(seconds_per_day)),
+        now_epoch_secs,
+        false,
+    ) {
+        Ok(out) => out,
+        Err(err) => {
+            warn::emit(WarnEvent {
+                code: "RETENTION_DELETE_FAILED",
+                stage: "archive-retention",
+                action: "purge-trash",
+                session: "na",
+                archive: "na",
+                source: "na",
+                retry: "retry-next-cycle",
+                reason: "trash-purge-failed",
+                err: &format!("{err:#}"),
+            });
+            trash::TrashPurgeOutcome {
+                failed: 1,
+                ..Default::default()
+            }
+        }
+    };
+
+    let distill_cache_pruned = match chunk_cache::prune_expired(
+        paths,
+        chunk_cache::ENTRY_TTL_SECS,
+        UNIX_EPOCH + Duration::from_secs(now_epoch_secs),
+    ) {
+        Ok(pruned) => pruned,
+        Err(err) => {
+            warn::emit(WarnEvent {
+                code: "RETENTION_DELETE_FAILED",
+                stage: "archive-retention",
+                action: "prune-distill-cache",
+                session: "na",
+                archive: "na",
+                source: "na",
+                retry: "retry-next-cycle",
+                reason: "distill-cache-prune-failed",
+                err: &format!("{err:#}"),
+            });
+            0
+        }
+    };
+
+    let memory_history_pruned =
+        match memory::prune_memory_history(paths, memory::MEMORY_HISTORY_KEEP_SECS, now_epoch_secs)
+        {
+            Ok(pruned) => pruned,
+            Err(err) => {
+                warn::emit(WarnEvent {
+                    code: "RETENTION_DELETE_FAILED",
+                    stage: "archive-retention",
+                    action: "prune-memory-history",
+                    session: "na",
+                    archive: "na",
+                    source: "na",
+                    retry: "retry-next-cycle",
+                    reason: "memory-history-prune-failed",
+                    err: &format!("{err:#}"),
+                });
+                0
+            }
+        };
+
+    let gateway_calls_window = cfg
+        .watcher
+        .cooldown_secs
+        .max(gateway_calls::MIN_RETENTION_SECS);
+    let gateway_calls_trimmed = match gateway_calls::trim_before(
+        paths,
+        now_epoch_secs.saturating_sub(gateway_calls_window),
+    ) {
+        Ok(trimmed) => trimmed,
+        Err(err) => {
+            warn::emit(WarnEvent {
+                code: "RETENTION_DELETE_FAILED",
+                stage: "archive-retention",
+                action: "trim-gateway-calls",
+                session: "na",
+                archive: "na",
+                source: "na",
+                retry: "retry-next-cycle",
+                reason: "gateway-calls-trim-failed",
+                err: &format!("{err:#}"),
+            });
+            0
+        }
+    };
+
+    if purge_paths.is_empty()
+        && failed == 0
+        && newly_protected.is_empty()
+        && trash_purge.purged == 0
+        && trash_purge.failed == 0
+        && distill_cache_pruned == 0
+        && memory_history_pruned == 0
+        && gateway_calls_trimmed == 0
+    {
         return Ok(None);
     }
 
-    let map_removed = channel_archive_map::remove_by_archive_paths(paths, &purge_paths)?;
+    let map_retired = channel_archive_map::retire_archive_paths(paths, &retired_map_targets)?;
     let ledger_removed = remove_ledger_records(paths, &purge_paths)?;
-    let qmd_updated = if !purge_paths.is_empty() {
-        qmd::update(&paths.qmd_bin).is_ok()
-    } else {
-        false
+    let vectors_removed = match vectors::remove_projection_vectors(paths, &vector_purge_paths) {
+        Ok(removed) => removed,
+        Err(err) => {
+            warn::emit(WarnEvent {
+                code: "RETENTION_DELETE_FAILED",
+                stage: "archive-retention",
+                action: "remove-vectors",
+                session: "na",
+                archive: "na",
+                source: "na",
+                retry: "retry-next-cycle",
+                reason: "vector-removal-failed",
+                err: &format!("{err:#}"),
+            });
+            0
+        }
     };
+    for path in &vector_purge_paths {
+        state.embedded_projections.remove(path);
+    }
+    // Purged projections leave the index with the next cycle's batched qmd sync.
+    let qmd_sync_queued = !purge_paths.is_empty();
+    for collection in &purged_collections {
+        queue_qmd_sync(paths, state, collection);
+    }
 
-    Ok(Some(format!(
-        "retention_active_days={} retention_warm_days={} retention_cold_days={} active={} warm={} cold_candidates={} removed={} missing={} failed={} projection_removed={} projection_missing={} projection_failed={} map_removed={} ledger_removed={} qmd_updated={}",
+    let collections = purged_collections.into_iter().collect::<Vec<_>>();
+    let details = serde_json::json!({
+        "retention_active_days": retention.active_days,
+        "retention_warm_days": retention.warm_days,
+        "retention_cold_days": retention.cold_days,
+        "active": active_count,
+        "warm": warm_count,
+        "cold_candidates": cold_candidates,
+        "superseded": superseded_candidates,
+        "removed": removed_files,
+        "missing": missing_files,
+        "failed": failed,
+        "projection_removed": projection_removed,
+        "projection_missing": projection_missing,
+        "projection_failed": projection_failed,
+        "vectors_removed": vectors_removed,
+        "map_repointed": map_retired.repointed,
+        "map_removed": map_retired.removed,
+        "ledger_removed": ledger_removed,
+        "qmd_sync_queued": qmd_sync_queued,
+        "collections": collections,
+        "protected": protected_count,
+        "forced": forced.len(),
+        "trash_days": retention.trash_days,
+        "trash_purged": trash_purge.purged,
+        "trash_failed": trash_purge.failed,
+        "distill_cache_pruned": distill_cache_pruned,
+        "memory_history_pruned": memory_history_pruned,
+        "gateway_calls_trimmed": gateway_calls_trimmed,
+    });
+    let summary = format!(
+        "retention_active_days={} retention_warm_days={} retention_cold_days={} active={} warm={} cold_candidates={} superseded={} removed={} missing={} failed={} projection_removed={} projection_missing={} projection_failed={} vectors_removed={} map_repointed={} map_removed={} ledger_removed={} qmd_sync_queued={} collections={} protected={} forced={} trash_days={} trash_purged={} trash_failed={} distill_cache_pruned={} memory_history_pruned={} gateway_calls_trimmed={}",
         retention.active_days,
         retention.warm_days,
         retention.cold_days,
         active_count,
         warm_count,
         cold_candidates,
+        superseded_candidates,
         removed_files,
         missing_files,
         failed,
         projection_removed,
         projection_missing,
         projection_failed,
-        map_removed,
+        vectors_removed,
+        map_retired.repointed,
+        map_retired.removed,
         ledger_removed,
-        qmd_updated
-    )))
+        qmd_sync_queued,
+        if collections.is_empty() {
+            "none".to_string()
+        } else {
+            collections.join(",")
+        },
+        protected_count,
+        forced.len(),
+        retention.trash_days,
+        trash_purge.purged,
+        trash_purge.failed,
+        distill_cache_pruned,
+        memory_history_pruned,
+        gateway_calls_trimmed
+    );
+    Ok(Some(RetentionSweep {
+        summary,
+        details,
+        failed: failed + projection_failed + trash_purge.failed,
+    }))
+}
+
+/// Undistilled, indexed archives the watcher leaves out of distillation on purpose.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IntentionalDistillSkips {
+    /// Flagged `distill_skip` in the ledger.
+    pub flagged: usize,
+    /// Excluded by `[sessions.distill]`.
+    pub session_filter: usize,
+}
+
+impl IntentionalDistillSkips {
+    pub fn total(&self) -> usize {
+        self.flagged + self.session_filter
+    }
+}
+
+/// Counts archives the next distill selection would skip by ledger flag or session filter,
+/// once per archive path.
+pub fn intentional_distill_skips(
+    paths: &crate::moon::paths::MoonPaths,
+    state: &crate::moon::state::MoonState,
+    sessions: &MoonSessionsConfig,
+) -> Result<IntentionalDistillSkips> {
+    let source_keys = session_keys_by_source(paths);
+    let mut seen = BTreeSet::new();
+    let mut skips = IntentionalDistillSkips::default();
+    for record in read_ledger_records(paths)?.into_iter().rev() {
+        if !seen.insert(record.archive_path.clone())
+            || !record.indexed
+            || state.distilled_archives.contains_key(&record.archive_path)
+        {
+            continue;
+        }
+        let session_key = source_keys
+            .get(&record.source_path)
+            .unwrap_or(&record.session_id);
+        if record.distill_skip {
+            skips.flagged += 1;
+        } else if !sessions.allows_distill(session_key) {
+            skips.session_filter += 1;
+        }
+    }
+    Ok(skips)
 }
 
 fn select_pending_distill_candidates(
     paths: &crate::moon::paths::MoonPaths,
     state: &crate::moon::state::MoonState,
+    sessions: &MoonSessionsConfig,
     max_per_cycle: u64,
 ) -> Result<DistillSelection> {
     let mut notes = Vec::new();
@@ -606,12 +2512,26 @@ fn select_pending_distill_candidates(
     }
 
     ledger.sort_by_key(|r| r.created_at_epoch_secs);
+    let source_keys = session_keys_by_source(paths);
     let mut pending = Vec::new();
     let mut skipped_non_distillable = 0usize;
+    let mut skipped_session_filter = 0usize;
+    let mut skipped_flagged = 0usize;
     for record in ledger {
         if !record.indexed || state.distilled_archives.contains_key(&record.archive_path) {
             continue;
         }
+        if record.distill_skip {
+            skipped_flagged = skipped_flagged.saturating_add(1);
+            continue;
+        }
+        let session_key = source_keys
+            .get(&record.source_path)
+            .unwrap_or(&record.session_id);
+        if !sessions.allows_distill(session_key) {
+            skipped_session_filter = skipped_session_filter.saturating_add(1);
+            continue;
+        }
 
         if !is_distillable_archive_record(&record) {
             skipped_non_distillable = skipped_non_distillable.saturating_add(1);
@@ -643,6 +2563,12 @@ fn select_pending_distill_candidates(
                 skipped_non_distillable
             ));
         }
+        if skipped_session_filter > 0 {
+            notes.push(format!("skipped_sessions_distill={skipped_session_filter}"));
+        }
+        if skipped_flagged > 0 {
+            notes.push(format!("skipped_distill_flagged={skipped_flagged}"));
+        }
         return Ok((distill_candidates, notes));
     }
 
@@ -664,6 +2590,12 @@ fn select_pending_distill_candidates(
                 skipped_non_distillable
             ));
         }
+        if skipped_session_filter > 0 {
+            notes.push(format!("skipped_sessions_distill={skipped_session_filter}"));
+        }
+        if skipped_flagged > 0 {
+            notes.push(format!("skipped_distill_flagged={skipped_flagged}"));
+        }
     } else {
         notes.push("skipped reason=no-undistilled-archives".to_string());
     }
@@ -695,6 +2627,7 @@ fn acquire_daemon_lock() -> Result<File> {
                 started_at_epoch_secs: now,
                 build_uuid: BUILD_UUID.to_string(),
                 moon_home: paths.moon_home.display().to_string(),
+                build: Some(BuildInfo::current()),
             };
             lock_file.set_len(0)?;
             lock_file.write_all(format!("{}\n", serde_json::to_string(&payload)?).as_bytes())?;
@@ -765,18 +2698,92 @@ fn extract_key_decisions(summary: &str) -> Vec<String> {
     out
 }
 
+/// Probes the OpenClaw sessions dir and tracks outages in `state`. While it is unreadable
+/// every cycle warns; the first failing cycle and the recovery are audited. Returns the
+/// cycle's note, `None` when the dir is readable and was not down before.
+fn track_sessions_dir(
+    paths: &crate::moon::paths::MoonPaths,
+    state: &mut crate::moon::state::MoonState,
+    now_epoch_secs: u64,
+    dry_run: bool,
+) -> Option<String> {
+    let dir = paths.openclaw_sessions_dir.display().to_string();
+    match fs::read_dir(&paths.openclaw_sessions_dir) {
+        Ok(_) => {
+            let since = state.sessions_dir_unavailable_since_epoch_secs.take()?;
+            let note = format!(
+                "recovered after_secs={}",
+                now_epoch_secs.saturating_sub(since)
+            );
+            if !dry_run {
+                let _ = audit::append_event(
+                    paths,
+                    "sessions-dir",
+                    "ok",
+                    &format!("{note} dir={dir}"),
+                    serde_json::json!({
+                        "after_secs": now_epoch_secs.saturating_sub(since),
+                        "dir": dir,
+                    }),
+                );
+            }
+            Some(note)
+        }
+        Err(err) => {
+            let first = state.sessions_dir_unavailable_since_epoch_secs.is_none();
+            let since = *state
+                .sessions_dir_unavailable_since_epoch_secs
+                .get_or_insert(now_epoch_secs);
+            if !dry_run {
+                warn::emit(WarnEvent {
+                    code: "SESSIONS_DIR_UNAVAILABLE",
+                    stage: "watch",
+                    action: "read-sessions-dir",
+                    session: "na",
+                    archive: "na",
+                    source: &dir,
+                    retry: "retry-next-cycle",
+                    reason: "sessions-dir-unreadable",
+                    err: &format!("{err}"),
+                });
+                if first {
+                    let code = crate::error::MoonErrorCode::E014PathMissing.as_str();
+                    let _ = audit::append_event(
+                        paths,
+                        "sessions-dir",
+                        "degraded",
+                        &format!("code={code} reason=sessions-dir-unreadable dir={dir} err={err}"),
+                        serde_json::json!({
+                            "code": code,
+                            "reason": "sessions-dir-unreadable",
+                            "dir": dir,
+                            "error": err.to_string(),
+                        }),
+                    );
+                }
+            }
+            Some(format!("unavailable since_epoch={since} error={err}"))
+        }
+    }
+}
+
 pub fn run_once() -> Result<WatchCycleOutcome> {
     run_once_with_options(WatchRunOptions::default())
 }
 
 pub fn run_once_with_options(run_opts: WatchRunOptions) -> Result<WatchCycleOutcome> {
+    // Declared first so it is dropped (and flushed) last, on every return path.
+    let audit_batch = audit::begin_cycle();
     let paths = resolve_paths()?;
     let cfg = load_config()?;
+    let watchdog = CycleWatchdog::start(&paths, cfg.watcher.max_cycle_secs);
     let mut state = load(&paths)?;
     // Legacy field retained for backward-compatible state parsing; no longer used
     // for compaction trigger decisions.
     state.compaction_hysteresis_active.clear();
-    let inbound_watch = if run_opts.dry_run {
+    watchdog_checkpoint(&watchdog, &paths, &state, "inbound", run_opts.dry_run)?;
+    let paused = read_pause(&paths);
+    let inbound_watch = if run_opts.dry_run || paused.is_some() {
         InboundWatchOutcome {
             enabled: cfg.inbound_watch.enabled,
             watched_paths: cfg.inbound_watch.watch_paths.clone(),
@@ -786,6 +2793,7 @@ pub fn run_once_with_options(run_opts: WatchRunOptions) -> Result<WatchCycleOutc
         inbound_watch::process(&paths, &cfg, &mut state)?
     };
 
+    watchdog_checkpoint(&watchdog, &paths, &state, "usage", run_opts.dry_run)?;
     let mut usage_batch_note = None;
     let usage_batch = match collect_openclaw_usage_batch() {
         Ok(batch) => Some(batch),
@@ -799,27 +2807,129 @@ pub fn run_once_with_options(run_opts: WatchRunOptions) -> Result<WatchCycleOutc
         None => collect_usage(&paths)?,
     };
     state.last_heartbeat_epoch_secs = usage.captured_at_epoch_secs;
+    state.build = Some(BuildInfo::current());
     state.last_session_id = Some(usage.session_id.clone());
     state.last_usage_ratio = Some(usage.usage_ratio);
     state.last_provider = Some(usage.provider.clone());
 
+    let usage_sessions = match &usage_batch {
+        Some(batch) => batch.sessions.clone(),
+        None => vec![usage.clone()],
+    };
+    for session in &usage_sessions {
+        record_usage_sample(
+            &mut state,
+            &session.session_id,
+            session.captured_at_epoch_secs,
+            session.usage_ratio,
+        );
+    }
+    prune_usage_trends(&mut state, usage.captured_at_epoch_secs);
+
     let context_policy = cfg.context.as_ref();
     let effective_trigger_threshold = effective_compaction_start_ratio(&cfg, context_policy);
+    let effective_archive_threshold = if cfg.thresholds.archive_ratio_trigger_enabled {
+        cfg.thresholds.archive_ratio
+    } else {
+        effective_trigger_threshold
+    };
     let compaction_authority = compaction_authority_name(context_policy);
 
+    if let Some(pause) = paused {
+        let state_file = if run_opts.dry_run {
+            state_file_path(&paths)
+        } else {
+            save(&paths, &state)?
+        };
+        return Ok(WatchCycleOutcome {
+            cycle_id: audit_batch.cycle_id().to_string(),
+            state_file: state_file.display().to_string(),
+            heartbeat_epoch_secs: state.last_heartbeat_epoch_secs,
+            poll_interval_secs: cfg.watcher.poll_interval_secs,
+            archive_threshold: effective_archive_threshold,
+            compaction_threshold: effective_trigger_threshold,
+            archive_trigger_enabled: cfg.thresholds.archive_ratio_trigger_enabled,
+            compaction_authority,
+            compaction_emergency_ratio: context_policy
+                .map(|policy| policy.compaction_emergency_ratio),
+            compaction_recover_ratio: context_policy.map(|policy| policy.compaction_recover_ratio),
+            distill_max_per_cycle: cfg.distill.max_per_cycle,
+            distill_mode: cfg.distill.mode.clone(),
+            distill_trigger: "skipped reason=paused".to_string(),
+            distill_selection: None,
+            embed_mode: cfg.embed.mode.clone(),
+            embed_idle_secs: cfg.embed.idle_secs,
+            embed_max_docs_per_cycle: cfg.embed.max_docs_per_cycle,
+            retention_active_days: cfg.retention.active_days,
+            retention_warm_days: cfg.retention.warm_days,
+            retention_cold_days: cfg.retention.cold_days,
+            usage,
+            triggers: Vec::new(),
+            inbound_watch,
+            archive: None,
+            compaction_result: None,
+            distill: None,
+            embed_result: None,
+            continuity: None,
+            archive_retention_result: None,
+            memory_primer_result: None,
+            predictive_archive_result: None,
+            idle_archive_result: None,
+            archive_skipped: None,
+            consistency_result: None,
+            compaction_anchor_result: None,
+            daily_report_result: None,
+            qmd_sync_result: None,
+            archive_plans: Vec::new(),
+            sessions_dir: None,
+            paused: Some(pause),
+        });
+    }
+
+    // Archive phases need the sessions dir; without it the cycle skips them and keeps the
+    // heartbeat, distill, embed and retention running until the dir reads again.
+    let sessions_dir = track_sessions_dir(
+        &paths,
+        &mut state,
+        usage.captured_at_epoch_secs,
+        run_opts.dry_run,
+    );
+    let sessions_dir_available = state.sessions_dir_unavailable_since_epoch_secs.is_none();
+
+    watchdog_checkpoint(&watchdog, &paths, &state, "memory-primer", run_opts.dry_run)?;
+    let memory_primer_result = if run_opts.dry_run {
+        cfg.memory
+            .inject_on_new_session
+            .then(|| "dry-run: memory primer skipped".to_string())
+    } else {
+        run_memory_primer_for_new_sessions(
+            &paths,
+            &cfg,
+            &mut state,
+            &usage_sessions,
+            usage.captured_at_epoch_secs,
+        )
+    };
+
+    watchdog_checkpoint(&watchdog, &paths, &state, "triggers", run_opts.dry_run)?;
     let triggers = if let Some(policy) = context_policy {
         match policy.compaction_authority {
             MoonContextCompactionAuthority::Moon => {
-                if usage.usage_ratio >= policy.compaction_start_ratio
-                    && (is_cooldown_ready(
-                        unified_layer1_last_trigger_epoch(&state),
+                if usage.usage_ratio >= policy.compaction_start_ratio {
+                    if is_cooldown_ready(
+                        compaction_cooldown_epoch_secs(&cfg, &state),
                         usage.captured_at_epoch_secs,
                         cfg.watcher.cooldown_secs,
-                    ) || usage.usage_ratio >= policy.compaction_emergency_ratio)
-                {
-                    vec![TriggerKind::Archive, TriggerKind::Compaction]
+                    ) || usage.usage_ratio >= policy.compaction_emergency_ratio
+                    {
+                        vec![TriggerKind::Archive, TriggerKind::Compaction]
+                    } else {
+                        Vec::new()
+                    }
                 } else {
-                    Vec::new()
+                    evaluate_archive_only(&cfg, &state, &usage)
+                        .into_iter()
+                        .collect()
                 }
             }
             MoonContextCompactionAuthority::Openclaw => Vec::new(),
@@ -827,22 +2937,57 @@ pub fn run_once_with_options(run_opts: WatchRunOptions) -> Result<WatchCycleOutc
     } else {
         evaluate(&cfg, &state, &usage)
     };
-    let trigger_names = triggers
+    let triggers = if context_policy.is_some_and(|policy| {
+        matches!(
+            policy.compaction_authority,
+            MoonContextCompactionAuthority::Openclaw
+        )
+    }) {
+        triggers
+    } else {
+        apply_policy_script(&paths, &cfg, &state, &usage, triggers)
+    };
+    let mut archive_skipped = None;
+    let mut triggers = triggers;
+    if triggers.contains(&TriggerKind::Archive) && !cfg.sessions.allows_archive(&usage.session_id) {
+        triggers.retain(|t| *t != TriggerKind::Archive);
+        archive_skipped = Some(format!("key={} reason=sessions-excluded", usage.session_id));
+    }
+    if triggers.contains(&TriggerKind::Archive) && !sessions_dir_available {
+        triggers.retain(|t| *t != TriggerKind::Archive);
+        archive_skipped = Some(format!(
+            "key={} reason=sessions-dir-unavailable",
+            usage.session_id
+        ));
+    }
+    let mut trigger_names = triggers
         .iter()
-        .map(|t| match t {
-            TriggerKind::Archive => "archive".to_string(),
-            TriggerKind::Compaction => "compaction".to_string(),
-        })
+        .map(|t| t.as_str().to_string())
         .collect::<Vec<_>>();
+    let distill_decision = evaluate_distill(
+        &cfg,
+        DistillTriggerInput {
+            now_epoch_secs: usage.captured_at_epoch_secs,
+            last_distill_epoch_secs: state.last_distill_epoch_secs(),
+            last_activity_epoch_secs: (cfg.distill.idle_secs > 0)
+                .then(|| latest_session_activity_epoch_secs(&paths, &cfg.snapshot.exclude))
+                .flatten(),
+            forced: run_opts.force_distill_now,
+        },
+    );
 
     let mut archive_out = None;
     let mut compaction_result = None;
     let mut distill_out = None;
     let mut embed_result: Option<String> = None;
-    let mut continuity_out = None;
+    let mut continuity_out = if run_opts.dry_run {
+        None
+    } else {
+        record_session_rollovers(&paths, &mut state, usage.captured_at_epoch_secs)
+    };
     let mut archive_retention_result = None;
     let compaction_cooldown_ready = is_cooldown_ready(
-        unified_layer1_last_trigger_epoch(&state),
+        compaction_cooldown_epoch_secs(&cfg, &state),
         usage.captured_at_epoch_secs,
         cfg.watcher.cooldown_secs,
     );
@@ -870,13 +3015,13 @@ pub fn run_once_with_options(run_opts: WatchRunOptions) -> Result<WatchCycleOutc
                     candidate_sessions = batch
                         .sessions
                         .iter()
-                        .filter(|s| is_compaction_channel_session(&s.session_id))
+                        .filter(|s| cfg.sessions.allows_compaction(&s.session_id))
                         .cloned()
                         .collect();
-                } else if is_compaction_channel_session(&usage.session_id) {
+                } else if cfg.sessions.allows_compaction(&usage.session_id) {
                     candidate_sessions.push(usage.clone());
                 }
-            } else if is_compaction_channel_session(&usage.session_id) {
+            } else if cfg.sessions.allows_compaction(&usage.session_id) {
                 candidate_sessions.push(usage.clone());
             }
 
@@ -919,26 +3064,85 @@ pub fn run_once_with_options(run_opts: WatchRunOptions) -> Result<WatchCycleOutc
                 .sessions
                 .iter()
                 .filter(|s| {
-                    is_compaction_channel_session(&s.session_id)
+                    cfg.sessions.allows_compaction(&s.session_id)
                         && s.usage_ratio >= cfg.thresholds.trigger_ratio
                 })
                 .cloned()
                 .collect();
         } else if usage.usage_ratio >= cfg.thresholds.trigger_ratio
-            && is_compaction_channel_session(&usage.session_id)
+            && cfg.sessions.allows_compaction(&usage.session_id)
         {
             compaction_targets.push(usage.clone());
         }
     } else if usage.usage_ratio >= cfg.thresholds.trigger_ratio
-        && is_compaction_channel_session(&usage.session_id)
+        && cfg.sessions.allows_compaction(&usage.session_id)
     {
         compaction_targets.push(usage.clone());
     }
 
+    if !sessions_dir_available && !compaction_targets.is_empty() {
+        compaction_result = Some(format!(
+            "skipped reason=sessions-dir-unavailable targets={}",
+            compaction_targets.len()
+        ));
+        compaction_targets.clear();
+    }
+
+    // Channels still under the threshold but projected to cross it before the next poll
+    // are archived now, so their history is captured before compaction can race it.
+    let mut predictive_targets = Vec::<SessionUsageSnapshot>::new();
+    if cfg.watcher.predictive_trigger && sessions_dir_available {
+        for session in &usage_sessions {
+            if !cfg.sessions.allows_compaction(&session.session_id)
+                || compaction_targets
+                    .iter()
+                    .any(|target| target.session_id == session.session_id)
+            {
+                continue;
+            }
+            let Some(trend) = state.usage_trends.get(&session.session_id) else {
+                continue;
+            };
+            if is_cooldown_ready(
+                trend.last_predictive_archive_epoch_secs,
+                usage.captured_at_epoch_secs,
+                cfg.watcher.cooldown_secs,
+            ) && predicts_threshold_crossing(
+                &trend.samples,
+                effective_trigger_threshold,
+                cfg.watcher.poll_interval_secs,
+            ) {
+                predictive_targets.push(session.clone());
+            }
+        }
+        if !predictive_targets.is_empty() {
+            trigger_names.push("predictive-archive".to_string());
+        }
+    }
+
     let mut compaction_source_map = BTreeMap::new();
-    if !compaction_targets.is_empty() {
+    let mut archive_excluded_sessions = BTreeMap::new();
+    if !compaction_targets.is_empty() || !predictive_targets.is_empty() {
         match load_session_source_map(&paths.openclaw_sessions_dir) {
-            Ok(map) => {
+            Ok(mut map) => {
+                map.retain(|session_id, source: &mut PathBuf| {
+                    // Compaction never runs without its archive, so both exclusions skip it.
+                    let excluded = if !cfg.sessions.allows_archive(session_id) {
+                        Some(("sessions-archive", "sessions-excluded"))
+                    } else if is_snapshot_excluded(source, &cfg.snapshot.exclude) {
+                        Some(("snapshot-exclude", "snapshot-excluded"))
+                    } else {
+                        None
+                    };
+                    if let Some((rule, reason)) = excluded {
+                        compaction_notes.push(format!(
+                            "excluded key={session_id} source={} reason={rule}",
+                            source.display()
+                        ));
+                        archive_excluded_sessions.insert(session_id.clone(), reason);
+                    }
+                    excluded.is_none()
+                });
                 compaction_source_map = map;
                 compaction_has_archivable_targets = compaction_targets
                     .iter()
@@ -948,7 +3152,27 @@ pub fn run_once_with_options(run_opts: WatchRunOptions) -> Result<WatchCycleOutc
         }
     }
 
+    // Sessions archived by compaction or prediction this cycle are not idle candidates.
+    let busy_sessions = compaction_targets
+        .iter()
+        .chain(&predictive_targets)
+        .map(|target| target.session_id.clone())
+        .collect::<BTreeSet<_>>();
+    let idle_selection = if sessions_dir_available {
+        select_idle_archive_targets(&paths, &cfg, &busy_sessions, usage.captured_at_epoch_secs)
+    } else {
+        Ok(Vec::new())
+    };
+    let (idle_targets, idle_selection_error) = match idle_selection {
+        Ok(targets) => (targets, None),
+        Err(err) => (Vec::new(), Some(format!("{err:#}"))),
+    };
+    if !idle_targets.is_empty() {
+        trigger_names.push("idle-archive".to_string());
+    }
+
     if run_opts.dry_run {
+        watchdog_checkpoint(&watchdog, &paths, &state, "archive-plan", true)?;
         if compaction_result.is_none() {
             compaction_result = if compaction_targets.is_empty() {
                 Some("dry-run: no compaction targets selected".to_string())
@@ -962,20 +3186,91 @@ pub fn run_once_with_options(run_opts: WatchRunOptions) -> Result<WatchCycleOutc
             compaction_result = Some(format!("dry-run: {existing}"));
         }
 
+        let mut archive_plans = Vec::new();
+        let mut planned_sources = Vec::new();
+        if compaction_has_archivable_targets {
+            for target in &compaction_targets {
+                if let Some(source) = compaction_source_map.get(&target.session_id) {
+                    planned_sources.push(source.clone());
+                }
+            }
+        } else if triggers.iter().any(|t| matches!(t, TriggerKind::Archive))
+            && let Some(source) =
+                latest_session_file(&paths.openclaw_sessions_dir, &cfg.snapshot.exclude)?
+        {
+            planned_sources.push(source);
+        }
+        for target in &predictive_targets {
+            if let Some(source) = compaction_source_map.get(&target.session_id)
+                && !planned_sources.contains(source)
+            {
+                planned_sources.push(source.clone());
+            }
+        }
+        for target in &idle_targets {
+            if !planned_sources.contains(&target.source) {
+                planned_sources.push(target.source.clone());
+            }
+        }
+        for source in planned_sources {
+            let collection = collection_for_source(&paths, &cfg.collections, &source);
+            archive_plans.push(plan_archive_and_index(&paths, &source, collection)?);
+        }
+        let idle_archive_result = idle_selection_error
+            .as_ref()
+            .map(|err| format!("failed reason=idle-selection-failed error={err}"))
+            .or_else(|| {
+                (!idle_targets.is_empty()).then(|| {
+                    format!(
+                        "dry-run: would archive {} session(s) idle for at least {}s",
+                        idle_targets.len(),
+                        cfg.watcher.idle_archive_secs
+                    )
+                })
+            });
+        let predictive_archive_result = (!predictive_targets.is_empty()).then(|| {
+            format!(
+                "dry-run: would archive {} session(s) projected to cross {:.4}",
+                predictive_targets.len(),
+                effective_trigger_threshold
+            )
+        });
+
+        let distill_selection = distill_decision.trigger.map(|_| {
+            match select_pending_distill_candidates(
+                &paths,
+                &state,
+                &cfg.sessions,
+                distill_decision.max_per_cycle,
+            ) {
+                Ok((candidates, notes)) => format!(
+                    "dry-run: would distill {} archive(s) {}",
+                    candidates.len(),
+                    notes.join(" | ")
+                ),
+                Err(err) => format!("dry-run: skipped reason=ledger-read-failed error={err:#}"),
+            }
+        });
         embed_result = Some("dry-run: embed skipped".to_string());
         archive_retention_result = Some("dry-run: archive retention skipped".to_string());
         let state_file = state_file_path(&paths);
 
         return Ok(WatchCycleOutcome {
+            cycle_id: audit_batch.cycle_id().to_string(),
             state_file: state_file.display().to_string(),
             heartbeat_epoch_secs: state.last_heartbeat_epoch_secs,
             poll_interval_secs: cfg.watcher.poll_interval_secs,
-            trigger_threshold: effective_trigger_threshold,
+            archive_threshold: effective_archive_threshold,
+            compaction_threshold: effective_trigger_threshold,
+            archive_trigger_enabled: cfg.thresholds.archive_ratio_trigger_enabled,
             compaction_authority,
             compaction_emergency_ratio: context_policy
                 .map(|policy| policy.compaction_emergency_ratio),
             compaction_recover_ratio: context_policy.map(|policy| policy.compaction_recover_ratio),
             distill_max_per_cycle: cfg.distill.max_per_cycle,
+            distill_mode: cfg.distill.mode.clone(),
+            distill_trigger: distill_decision.note,
+            distill_selection,
             embed_mode: cfg.embed.mode.clone(),
             embed_idle_secs: cfg.embed.idle_secs,
             embed_max_docs_per_cycle: cfg.embed.max_docs_per_cycle,
@@ -991,16 +3286,46 @@ pub fn run_once_with_options(run_opts: WatchRunOptions) -> Result<WatchCycleOutc
             embed_result,
             continuity: None,
             archive_retention_result,
+            memory_primer_result,
+            predictive_archive_result,
+            idle_archive_result,
+            archive_skipped,
+            consistency_result: None,
+            compaction_anchor_result: None,
+            daily_report_result: None,
+            qmd_sync_result: None,
+            archive_plans,
+            sessions_dir,
+            paused: None,
         });
     }
 
-    if let Some(archive) =
-        run_archive_if_needed(&paths, &triggers, compaction_has_archivable_targets)?
-    {
-        state.last_archive_trigger_epoch_secs = Some(usage.captured_at_epoch_secs);
+    watchdog_checkpoint(&watchdog, &paths, &state, "archive", run_opts.dry_run)?;
+    let mut new_projections = Vec::<PathBuf>::new();
+    if let Some(archive) = run_archive_if_needed(
+        &paths,
+        &mut state,
+        &cfg.collections,
+        &cfg.snapshot.exclude,
+        &triggers,
+        compaction_has_archivable_targets,
+    )? {
+        state.record_archive(&archive.record.session_id, usage.captured_at_epoch_secs);
+        new_projections.extend(archive.record.projection_path.as_deref().map(PathBuf::from));
         archive_out = Some(archive);
     }
 
+    watchdog_checkpoint(
+        &watchdog,
+        &paths,
+        &state,
+        "compaction-anchors",
+        run_opts.dry_run,
+    )?;
+    let compaction_anchor_result =
+        run_compaction_anchor_fetch(&paths, &mut state, &mut new_projections)?;
+
+    watchdog_checkpoint(&watchdog, &paths, &state, "compaction", run_opts.dry_run)?;
     if !compaction_targets.is_empty()
         && !compaction_cooldown_ready
         && !cooldown_gate_handled_during_selection
@@ -1012,122 +3337,130 @@ pub fn run_once_with_options(run_opts: WatchRunOptions) -> Result<WatchCycleOutc
         );
         compaction_result = Some(skip_note);
     } else if !compaction_targets.is_empty() {
-        state.last_compaction_trigger_epoch_secs = Some(usage.captured_at_epoch_secs);
-        state.last_archive_trigger_epoch_secs = Some(usage.captured_at_epoch_secs);
+        for target in &compaction_targets {
+            let channel = state.channel_mut(&target.session_id);
+            channel.last_compaction_epoch_secs = Some(usage.captured_at_epoch_secs);
+            channel.last_archive_epoch_secs = Some(usage.captured_at_epoch_secs);
+        }
         let mut outcomes = Vec::new();
+        let mut outcome_details = Vec::new();
         let mut failed = 0usize;
         let mut succeeded = 0usize;
 
         for note in &compaction_notes {
             outcomes.push(format!("note={note}"));
+            outcome_details.push(serde_json::json!({"status": "note", "note": note}));
         }
 
+        let mut overrun = None;
         for target in &compaction_targets {
+            if let Err(err) =
+                watchdog_checkpoint(&watchdog, &paths, &state, "compaction", run_opts.dry_run)
+            {
+                overrun = Some(err);
+                break;
+            }
+            if let Some(reason) = archive_excluded_sessions.get(&target.session_id) {
+                outcomes.push(format!(
+                    "skipped key={} ratio={:.4} reason={reason}",
+                    target.session_id, target.usage_ratio
+                ));
+                outcome_details.push(serde_json::json!({
+                    "status": "skipped",
+                    "session": target.session_id,
+                    "ratio": target.usage_ratio,
+                    "reason": reason,
+                }));
+                continue;
+            }
             let Some(source_path) = compaction_source_map.get(&target.session_id) else {
                 failed += 1;
+                state.channel_mut(&target.session_id).archive_failures += 1;
                 outcomes.push(format!(
                     "failed key={} ratio={:.4} used={} max={} reason=archive-source-not-found",
                     target.session_id, target.usage_ratio, target.used_tokens, target.max_tokens
                 ));
+                outcome_details.push(serde_json::json!({
+                    "status": "failed",
+                    "session": target.session_id,
+                    "ratio": target.usage_ratio,
+                    "used": target.used_tokens,
+                    "max": target.max_tokens,
+                    "reason": "archive-source-not-found",
+                }));
                 continue;
             };
 
-            let archived = match archive_and_index(&paths, source_path, "history") {
-                Ok(out) => out,
-                Err(err) => {
-                    failed += 1;
-                    outcomes.push(format!(
-                        "failed key={} ratio={:.4} used={} max={} reason=archive-failed error={err:#}",
-                        target.session_id, target.usage_ratio, target.used_tokens, target.max_tokens
-                    ));
-                    continue;
-                }
-            };
-
-            if !archived.record.indexed {
-                failed += 1;
-                outcomes.push(format!(
-                    "failed key={} ratio={:.4} used={} max={} reason=index-failed archive={}",
-                    target.session_id,
-                    target.usage_ratio,
-                    target.used_tokens,
-                    target.max_tokens,
-                    archived.record.archive_path
-                ));
-                continue;
-            }
-            let mapped = match channel_archive_map::upsert(
+            let collection = cfg.collections.for_session(Some(&target.session_id));
+            let (line, detail) = match archive_and_compact_session(
                 &paths,
                 &target.session_id,
-                &archived.record.source_path,
-                &archived.record.archive_path,
+                source_path,
+                collection,
+                cfg.compaction.for_session(&target.session_id),
+                cfg.watcher.cooldown_secs,
             ) {
-                Ok(record) => record,
-                Err(err) => {
-                    failed += 1;
-                    outcomes.push(format!(
-                        "failed key={} ratio={:.4} used={} max={} reason=channel-archive-map-failed archive={} error={err:#}",
-                        target.session_id,
-                        target.usage_ratio,
-                        target.used_tokens,
-                        target.max_tokens,
-                        archived.record.archive_path
-                    ));
-                    continue;
-                }
-            };
-
-            let line = match gateway::run_sessions_compact(&target.session_id) {
-                Ok(summary) => {
+                Ok(compacted) => {
                     succeeded += 1;
-                    let index_note = match gateway::run_sessions_index_note(
-                        &target.session_id,
-                        &mapped.archive_path,
-                        archived.record.projection_path.as_deref(),
-                        &archived.record.source_path,
-                        &archived.record.content_hash,
-                        &archived.record.indexed_collection,
-                    ) {
-                        Ok(note) => note,
-                        Err(err) => {
-                            warn::emit(WarnEvent {
-                                code: "INDEX_NOTE_FAILED",
-                                stage: "compaction",
-                                action: "write-index-note",
-                                session: &target.session_id,
-                                archive: &mapped.archive_path,
-                                source: &archived.record.source_path,
-                                retry: "retry-next-cycle",
-                                reason: "chat-send-index-note-failed",
-                                err: &format!("{err:#}"),
-                            });
-                            format!("index_note_failed error={err:#}")
-                        }
-                    };
-                    format!(
-                        "ok key={} ratio={:.4} used={} max={} archived={} {} {}",
+                    let channel = state.channel_mut(&target.session_id);
+                    channel.archive_failures = 0;
+                    channel.compaction_failures = 0;
+                    state.pending_compaction_anchors.insert(
+                        target.session_id.clone(),
+                        PendingCompactionAnchor {
+                            archive_path: compacted.archive_path.clone(),
+                            requested_at_epoch_secs: usage.captured_at_epoch_secs,
+                            attempts: 0,
+                        },
+                    );
+                    new_projections.extend(compacted.projection_path.as_deref().map(PathBuf::from));
+                    let line = format!(
+                        "ok key={} ratio={:.4} used={} max={} {}",
                         target.session_id,
                         target.usage_ratio,
                         target.used_tokens,
                         target.max_tokens,
-                        mapped.archive_path,
-                        summary,
-                        index_note
-                    )
+                        compacted.detail()
+                    );
+                    let detail = serde_json::json!({
+                        "status": "ok",
+                        "session": target.session_id,
+                        "ratio": target.usage_ratio,
+                        "used": target.used_tokens,
+                        "max": target.max_tokens,
+                        "archive": compacted.archive_path,
+                        "strategy": compacted.compaction_strategy,
+                    });
+                    (line, detail)
                 }
-                Err(err) => {
+                Err(failure) => {
                     failed += 1;
-                    format!(
-                        "failed key={} ratio={:.4} used={} max={} archived={} error={err:#}",
+                    let channel = state.channel_mut(&target.session_id);
+                    if failure.starts_with("reason=archive-failed") {
+                        channel.archive_failures += 1;
+                    } else {
+                        channel.compaction_failures += 1;
+                    }
+                    let line = format!(
+                        "failed key={} ratio={:.4} used={} max={} {failure}",
                         target.session_id,
                         target.usage_ratio,
                         target.used_tokens,
-                        target.max_tokens,
-                        mapped.archive_path
-                    )
+                        target.max_tokens
+                    );
+                    let detail = serde_json::json!({
+                        "status": "failed",
+                        "session": target.session_id,
+                        "ratio": target.usage_ratio,
+                        "used": target.used_tokens,
+                        "max": target.max_tokens,
+                        "error": failure,
+                    });
+                    (line, detail)
                 }
             };
             outcomes.push(line);
+            outcome_details.push(detail);
         }
 
         let compact_result = format!(
@@ -1140,7 +3473,21 @@ pub fn run_once_with_options(run_opts: WatchRunOptions) -> Result<WatchCycleOutc
 
         let status = if failed > 0 { "degraded" } else { "ok" };
 
-        audit::append_event(&paths, "compaction", status, &compact_result)?;
+        audit::append_event(
+            &paths,
+            "compaction",
+            status,
+            &compact_result,
+            serde_json::json!({
+                "targets": compaction_targets.len(),
+                "succeeded": succeeded,
+                "failed": failed,
+                "outcomes": outcome_details,
+            }),
+        )?;
+        if let Some(err) = overrun {
+            return Err(err);
+        }
         compaction_result = Some(compact_result);
     } else if compaction_result.is_none() && !compaction_notes.is_empty() {
         compaction_result = Some(format!(
@@ -1149,6 +3496,66 @@ pub fn run_once_with_options(run_opts: WatchRunOptions) -> Result<WatchCycleOutc
         ));
     }
 
+    watchdog_checkpoint(
+        &watchdog,
+        &paths,
+        &state,
+        "predictive-archive",
+        run_opts.dry_run,
+    )?;
+    let predictive_archive_result = run_predictive_archives(
+        &paths,
+        &mut state,
+        &cfg.collections,
+        &predictive_targets,
+        &compaction_source_map,
+        &archive_excluded_sessions,
+        effective_trigger_threshold,
+        cfg.watcher.poll_interval_secs,
+        &mut new_projections,
+        &watchdog,
+        run_opts.dry_run,
+    )?;
+    watchdog_checkpoint(&watchdog, &paths, &state, "idle-archive", run_opts.dry_run)?;
+    let idle_archive_result = match idle_selection_error {
+        Some(error) => {
+            let line = format!("failed reason=idle-selection-failed error={error}");
+            let _ = audit::append_event(
+                &paths,
+                "idle-archive",
+                "degraded",
+                &line,
+                serde_json::json!({"reason": "idle-selection-failed", "error": error}),
+            );
+            Some(line)
+        }
+        None => run_idle_archives(
+            &paths,
+            &mut state,
+            &cfg.collections,
+            &idle_targets,
+            cfg.watcher.idle_archive_secs,
+            &mut new_projections,
+            &watchdog,
+            run_opts.dry_run,
+        )?,
+    };
+    watchdog_checkpoint(
+        &watchdog,
+        &paths,
+        &state,
+        "incremental-embed",
+        run_opts.dry_run,
+    )?;
+    let incremental_embed_result = run_incremental_embed(
+        &paths,
+        &mut state,
+        &cfg,
+        &usage.session_id,
+        &new_projections,
+    );
+
+    watchdog_checkpoint(&watchdog, &paths, &state, "distill", run_opts.dry_run)?;
     let mut distill_notes = Vec::<String>::new();
     let mut distill_candidates = Vec::<(crate::moon::archive::ArchiveRecord, String)>::new();
 
@@ -1158,25 +3565,18 @@ pub fn run_once_with_options(run_opts: WatchRunOptions) -> Result<WatchCycleOutc
     let last_syns_day_key = state
         .last_syns_trigger_epoch_secs
         .map(|epoch| day_key_for_epoch_in_timezone(epoch, residential_tz));
-    let should_select_distill = if run_opts.force_distill_now {
-        distill_notes.push("manual_trigger=true".to_string());
-        true
-    } else if !is_cooldown_ready(
-        state.last_distill_trigger_epoch_secs,
-        usage.captured_at_epoch_secs,
-        cfg.watcher.cooldown_secs,
-    ) {
-        distill_notes.push(format!(
-            "skipped reason=cooldown cooldown_secs={}",
-            cfg.watcher.cooldown_secs
-        ));
-        false
-    } else {
-        true
-    };
+    let should_select_distill = distill_decision.trigger.is_some();
+    if distill_decision.note != "ready" {
+        distill_notes.push(distill_decision.note.clone());
+    }
 
     if should_select_distill {
-        match select_pending_distill_candidates(&paths, &state, cfg.distill.max_per_cycle) {
+        match select_pending_distill_candidates(
+            &paths,
+            &state,
+            &cfg.sessions,
+            distill_decision.max_per_cycle,
+        ) {
             Ok((candidates, notes)) => {
                 distill_candidates = candidates;
                 distill_notes.extend(notes);
@@ -1197,6 +3597,10 @@ pub fn run_once_with_options(run_opts: WatchRunOptions) -> Result<WatchCycleOutc
             }
         }
     }
+    if !distill_candidates.is_empty() {
+        trigger_names.push(TriggerKind::Distill.as_str().to_string());
+    }
+    let distill_selection = (!distill_notes.is_empty()).then(|| distill_notes.join(" | "));
 
     if !distill_candidates.is_empty() {
         if !distill_notes.is_empty() {
@@ -1212,24 +3616,49 @@ pub fn run_once_with_options(run_opts: WatchRunOptions) -> Result<WatchCycleOutc
                 "distill",
                 selection_status,
                 &format!("selection {}", distill_notes.join(" | ")),
+                serde_json::json!({"selection": distill_notes}),
             )?;
         }
 
+        let mut overrun = None;
         for (record, distill_source_path) in distill_candidates {
+            if let Err(err) =
+                watchdog_checkpoint(&watchdog, &paths, &state, "distill", run_opts.dry_run)
+            {
+                overrun = Some(err);
+                break;
+            }
             let archive_path = record.archive_path.clone();
             let input = DistillInput {
                 session_id: record.session_id.clone(),
                 archive_path: distill_source_path.clone(),
-                archive_text: String::new(),
+                archive_text: Cow::Borrowed(""),
                 archive_epoch_secs: Some(record.created_at_epoch_secs),
             };
 
             match run_distillation(&paths, &input) {
                 Ok(distill) => {
-                    state.last_distill_trigger_epoch_secs = Some(usage.captured_at_epoch_secs);
+                    state.record_distill(&record.session_id, usage.captured_at_epoch_secs);
                     state
                         .distilled_archives
                         .insert(archive_path.clone(), usage.captured_at_epoch_secs);
+                    if let Err(err) = record_distill_provenance(
+                        &paths,
+                        &archive_path,
+                        &DistillProvenance::from_output(&distill),
+                    ) {
+                        warn::emit(WarnEvent {
+                            code: "DISTILL_PROVENANCE_FAILED",
+                            stage: "distill",
+                            action: "record-provenance",
+                            session: &record.session_id,
+                            archive: &record.archive_path,
+                            source: &record.source_path,
+                            retry: "none",
+                            reason: "ledger-write-failed",
+                            err: &format!("{err:#}"),
+                        });
+                    }
 
                     match build_continuity(
                         &paths,
@@ -1281,6 +3710,13 @@ pub fn run_once_with_options(run_opts: WatchRunOptions) -> Result<WatchCycleOutc
                                 record.source_path,
                                 record.session_id
                             ),
+                            serde_json::json!({
+                                "reason": "lock-active",
+                                "archive": record.archive_path,
+                                "distill_source": distill_source_path,
+                                "source": record.source_path,
+                                "session": record.session_id,
+                            }),
                         )?;
                         break;
                     }
@@ -1295,6 +3731,13 @@ pub fn run_once_with_options(run_opts: WatchRunOptions) -> Result<WatchCycleOutc
                         reason: "distillation-failed",
                         err: &format!("{err:#}"),
                     });
+                    record_distill_failure(
+                        &paths,
+                        &cfg,
+                        &mut state,
+                        &record.session_id,
+                        &format!("norm archive={} error={err:#}", record.archive_path),
+                    );
                     audit::append_event(
                         &paths,
                         "distill",
@@ -1306,15 +3749,35 @@ pub fn run_once_with_options(run_opts: WatchRunOptions) -> Result<WatchCycleOutc
                             record.source_path,
                             record.session_id
                         ),
+                        serde_json::json!({
+                            "session": record.session_id,
+                            "archive": record.archive_path,
+                            "distill_source": distill_source_path,
+                            "source": record.source_path,
+                            "error": format!("{err:#}"),
+                        }),
                     )?;
                 }
             }
         }
+
+        // Daily memory changed this cycle; keep the `memory` collection searchable by recall.
+        if distill_out.is_some() {
+            queue_qmd_sync(&paths, &mut state, qmd::MEMORY_COLLECTION);
+        }
+        if let Some(err) = overrun {
+            return Err(err);
+        }
     }
 
+    // One qmd sync covers every archive and memory change queued so far, and it must land
+    // before embedding so new projections are visible to `qmd embed`.
+    let qmd_sync_result = flush_qmd_sync(&paths, &mut state);
+
+    watchdog_checkpoint(&watchdog, &paths, &state, "embed", run_opts.dry_run)?;
     let embed_started = Instant::now();
     let embed_run_opts = EmbedRunOptions {
-        collection_name: "history".to_string(),
+        collection_name: cfg.collections.default.clone(),
         max_docs: cfg.embed.max_docs_per_cycle as usize,
         dry_run: false,
         caller: EmbedCaller::Watcher,
@@ -1339,7 +3802,13 @@ pub fn run_once_with_options(run_opts: WatchRunOptions) -> Result<WatchCycleOutc
                     summary.skip_reason
                 );
                 let status = if summary.degraded { "degraded" } else { "ok" };
-                let _ = audit::append_event(&paths, "embed", status, &line);
+                let _ = audit::append_event(
+                    &paths,
+                    "embed",
+                    status,
+                    &line,
+                    serde_json::to_value(&summary).unwrap_or_default(),
+                );
                 embed_result = Some(line);
             }
 
@@ -1394,10 +3863,22 @@ pub fn run_once_with_options(run_opts: WatchRunOptions) -> Result<WatchCycleOutc
                 err: &format!("{err}"),
             });
             let line = format!("failed error={err}");
-            let _ = audit::append_event(&paths, "embed", "degraded", &line);
+            let _ = audit::append_event(
+                &paths,
+                "embed",
+                "degraded",
+                &line,
+                serde_json::json!({"reason": reason, "error": err.to_string()}),
+            );
             embed_result = Some(line);
         }
     }
+    if let Some(incremental) = incremental_embed_result {
+        embed_result = Some(match embed_result {
+            Some(line) => format!("{incremental} | {line}"),
+            None => incremental,
+        });
+    }
 
     if embed_started.elapsed().as_secs() > cfg.embed.max_cycle_secs {
         warn::emit(WarnEvent {
@@ -1412,7 +3893,13 @@ pub fn run_once_with_options(run_opts: WatchRunOptions) -> Result<WatchCycleOutc
             err: "embed-run-exceeded-max-cycle-secs",
         });
         let timeout_note = format!("timeout max_cycle_secs={}", cfg.embed.max_cycle_secs);
-        let _ = audit::append_event(&paths, "embed", "degraded", &timeout_note);
+        let _ = audit::append_event(
+            &paths,
+            "embed",
+            "degraded",
+            &timeout_note,
+            serde_json::json!({"reason": "timeout", "max_cycle_secs": cfg.embed.max_cycle_secs}),
+        );
         if let Some(current) = embed_result.take() {
             embed_result = Some(format!("{current} {timeout_note}"));
         } else {
@@ -1420,6 +3907,7 @@ pub fn run_once_with_options(run_opts: WatchRunOptions) -> Result<WatchCycleOutc
         }
     }
 
+    watchdog_checkpoint(&watchdog, &paths, &state, "syns", run_opts.dry_run)?;
     // Run L2 synthesis once per residential day (first watcher cycle after midnight),
     // after embed stage. Sources: yesterday daily memory + current memory.md (if present).
     if last_syns_day_key.as_deref() != Some(current_day_key.as_str()) {
@@ -1436,9 +3924,12 @@ pub fn run_once_with_options(run_opts: WatchRunOptions) -> Result<WatchCycleOutc
                 day_epoch_secs: Some(usage.captured_at_epoch_secs),
                 source_paths: syns_sources,
                 dry_run: false,
+                concurrency: cfg.distill.chunk_workers(),
+                rollup_threshold_chunks: cfg.distill.rollup_threshold_chunks as usize,
             },
         ) {
             Ok(wisdom) => {
+                state.channel_mut(GLOBAL_CHANNEL).distill_failures = 0;
                 state.last_syns_trigger_epoch_secs = Some(usage.captured_at_epoch_secs);
                 distill_out = Some(wisdom);
             }
@@ -1455,6 +3946,13 @@ pub fn run_once_with_options(run_opts: WatchRunOptions) -> Result<WatchCycleOutc
                     reason: "wisdom-distillation-failed",
                     err: &format!("{err:#}"),
                 });
+                record_distill_failure(
+                    &paths,
+                    &cfg,
+                    &mut state,
+                    GLOBAL_CHANNEL,
+                    &format!("syns error={err:#}"),
+                );
                 let _ = audit::append_event(
                     &paths,
                     "distill",
@@ -1462,37 +3960,73 @@ pub fn run_once_with_options(run_opts: WatchRunOptions) -> Result<WatchCycleOutc
                     &format!(
                         "mode=syns trigger=watcher error={err:#} fix=configure-primary-wisdom-model"
                     ),
+                    serde_json::json!({
+                        "mode": "syns",
+                        "trigger": "watcher",
+                        "error": format!("{err:#}"),
+                        "fix": "configure-primary-wisdom-model",
+                    }),
                 );
             }
         }
     }
 
-    if let Some(summary) = cleanup_expired_distilled_archives(
-        &paths,
-        &mut state,
-        usage.captured_at_epoch_secs,
-        &cfg.retention,
-    )? {
-        let status = if summary.contains("failed=") && !summary.contains("failed=0") {
-            "degraded"
-        } else {
-            "ok"
-        };
-        audit::append_event(&paths, "archive-retention", status, &summary)?;
-        archive_retention_result = Some(summary);
+    watchdog_checkpoint(&watchdog, &paths, &state, "daily-report", run_opts.dry_run)?;
+    // The digest covers the residential day that just ended, once per day.
+    let mut daily_report_result = None;
+    if cfg.report.daily {
+        let report_day_key =
+            previous_day_key_for_epoch_in_timezone(usage.captured_at_epoch_secs, residential_tz);
+        if state.last_daily_report_day.as_deref() != Some(report_day_key.as_str()) {
+            daily_report_result = Some(run_scheduled_daily_report(
+                &paths,
+                &report_day_key,
+                residential_tz,
+                cfg.report.notify,
+                cfg.distill.cost_per_million_tokens,
+            ));
+            state.last_daily_report_day = Some(report_day_key);
+        }
+    }
+
+    watchdog_checkpoint(&watchdog, &paths, &state, "retention", run_opts.dry_run)?;
+    if let Some(sweep) =
+        cleanup_expired_distilled_archives(&paths, &mut state, usage.captured_at_epoch_secs, &cfg)?
+    {
+        let status = if sweep.failed > 0 { "degraded" } else { "ok" };
+        audit::append_event(
+            &paths,
+            "archive-retention",
+            status,
+            &sweep.summary,
+            sweep.details,
+        )?;
+        archive_retention_result = Some(sweep.summary);
     }
 
+    state.watch_cycles = state.watch_cycles.saturating_add(1);
+    watchdog_checkpoint(&watchdog, &paths, &state, "consistency", run_opts.dry_run)?;
+    let consistency_result = run_consistency_check(&paths, &mut state, &cfg)?;
+
     let file = save(&paths, &state)?;
+    let cycle_id = audit_batch.cycle_id().to_string();
+    audit_batch.flush()?;
 
     Ok(WatchCycleOutcome {
+        cycle_id,
         state_file: file.display().to_string(),
         heartbeat_epoch_secs: state.last_heartbeat_epoch_secs,
         poll_interval_secs: cfg.watcher.poll_interval_secs,
-        trigger_threshold: effective_trigger_threshold,
+        archive_threshold: effective_archive_threshold,
+        compaction_threshold: effective_trigger_threshold,
+        archive_trigger_enabled: cfg.thresholds.archive_ratio_trigger_enabled,
         compaction_authority,
         compaction_emergency_ratio: context_policy.map(|policy| policy.compaction_emergency_ratio),
         compaction_recover_ratio: context_policy.map(|policy| policy.compaction_recover_ratio),
         distill_max_per_cycle: cfg.distill.max_per_cycle,
+        distill_mode: cfg.distill.mode.clone(),
+        distill_trigger: distill_decision.note,
+        distill_selection,
         embed_mode: cfg.embed.mode.clone(),
         embed_idle_secs: cfg.embed.idle_secs,
         embed_max_docs_per_cycle: cfg.embed.max_docs_per_cycle,
@@ -1508,24 +4042,39 @@ pub fn run_once_with_options(run_opts: WatchRunOptions) -> Result<WatchCycleOutc
         embed_result,
         continuity: continuity_out,
         archive_retention_result,
+        memory_primer_result,
+        predictive_archive_result,
+        idle_archive_result,
+        archive_skipped,
+        consistency_result,
+        compaction_anchor_result,
+        daily_report_result,
+        qmd_sync_result,
+        archive_plans: Vec::new(),
+        sessions_dir,
+        paused: None,
     })
 }
 
 pub fn run_daemon() -> Result<()> {
     let _daemon_lock = acquire_daemon_lock().map_err(|err| {
         if let Ok(paths) = resolve_paths() {
+            let code = crate::error::MoonErrorCode::E001Locked.as_str();
             let _ = audit::append_event(
                 &paths,
                 "daemon",
                 "failed",
-                &format!(
-                    "code={} reason=lock-acquisition-failed err={err:#}",
-                    crate::error::MoonErrorCode::E001Locked.as_str()
-                ),
+                &format!("code={code} reason=lock-acquisition-failed err={err:#}"),
+                serde_json::json!({
+                    "code": code,
+                    "reason": "lock-acquisition-failed",
+                    "error": format!("{err:#}"),
+                }),
             );
         }
         anyhow::anyhow!("failed to acquire lock: {err:#}")
     })?;
+    notify_daemon_restart();
 
     let shutdown = Arc::new(AtomicBool::new(false));
     let r = shutdown.clone();
@@ -1579,6 +4128,11 @@ pub fn run_daemon() -> Result<()> {
                             "daemon cycle failed retry_in_secs={} consecutive_failures={} error={err:#}",
                             retry_in_secs, consecutive_failures
                         ),
+                        serde_json::json!({
+                            "retry_in_secs": retry_in_secs,
+                            "consecutive_failures": consecutive_failures,
+                            "error": format!("{err:#}"),
+                        }),
                     );
                 }
 
@@ -1615,6 +4169,11 @@ pub fn run_daemon() -> Result<()> {
                             "DAEMON_PANIC consecutive_panics={} error={}",
                             consecutive_panics, panic_msg
                         ),
+                        serde_json::json!({
+                            "code": "DAEMON_PANIC",
+                            "consecutive_panics": consecutive_panics,
+                            "error": panic_msg,
+                        }),
                     );
                 }
 
@@ -1630,6 +4189,10 @@ pub fn run_daemon() -> Result<()> {
                             "watcher",
                             "alert",
                             "DAEMON_PANIC_HALT after 3 consecutive panics",
+                            serde_json::json!({
+                                "code": "DAEMON_PANIC_HALT",
+                                "consecutive_panics": consecutive_panics,
+                            }),
                         );
                     }
                     anyhow::bail!("DAEMON_PANIC_HALT: consecutive panic threshold reached");
@@ -3,10 +3,10 @@ use crate::moon::config::{
 };
 use crate::openclaw::paths::{OpenClawPaths, ensure_parent_dir};
 use anyhow::{Context, Result};
+use chrono::Utc;
 use serde_json::{Map, Value, json};
 use std::fs;
-use std::path::Path;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::path::{Path, PathBuf};
 use tempfile::NamedTempFile;
 
 pub const MIN_AGENT_CONTEXT_TOKENS: u64 = 16_000;
@@ -379,7 +379,7 @@ pub fn ensure_plugin_install_record(
     plugin_dir: &Path,
 ) -> ConfigPatchOutcome {
     let mut outcome = ConfigPatchOutcome::default();
-    let plugin_dir_value = plugin_dir.display().to_string();
+    let plugin_dir_value = crate::moon::archive::portable_path_string(plugin_dir);
 
     // Keep installs metadata aligned with the managed extension path so OpenClaw
     // can treat this plugin as provenance-tracked local code.
@@ -408,27 +408,124 @@ pub fn ensure_plugin_install_record(
     outcome
 }
 
-fn backup_path(config_path: &Path) -> Result<String> {
-    let ts = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .context("clock before unix epoch")?
-        .as_secs();
-    Ok(format!("{}.bak.{ts}", config_path.display()))
+/// Version bookkeeping kept next to the install record in `plugins.installs.<id>`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PluginVersionRecord {
+    pub version: Option<String>,
+    pub pinned_version: Option<String>,
+    pub channel: Option<String>,
+}
+
+pub fn read_plugin_version_record(root: &Value, plugin_id: &str) -> PluginVersionRecord {
+    let field = |key: &str| {
+        root.get("plugins")
+            .and_then(|v| v.get("installs"))
+            .and_then(|v| v.get(plugin_id))
+            .and_then(|v| v.get(key))
+            .and_then(Value::as_str)
+            .map(str::to_string)
+    };
+    PluginVersionRecord {
+        version: field("version"),
+        pinned_version: field("pinnedVersion"),
+        channel: field("channel"),
+    }
+}
+
+pub fn ensure_plugin_version_record(
+    root: &mut Value,
+    plugin_id: &str,
+    record: &PluginVersionRecord,
+) -> ConfigPatchOutcome {
+    let mut outcome = ConfigPatchOutcome::default();
+    for (key, value) in [
+        ("version", &record.version),
+        ("pinnedVersion", &record.pinned_version),
+        ("channel", &record.channel),
+    ] {
+        let path = ["plugins", "installs", plugin_id, key];
+        match value {
+            Some(value) => set_path_if_absent_or_forced(
+                root,
+                &path,
+                Value::from(value.clone()),
+                true,
+                &mut outcome,
+            ),
+            None => remove_path(root, &path, &mut outcome),
+        }
+    }
+    outcome
+}
+
+/// Backups of the openclaw config kept in `MOON_HOME/backups`; older ones are removed.
+pub const CONFIG_BACKUP_KEEP: usize = 10;
+const CONFIG_BACKUP_PREFIX: &str = "openclaw-";
+
+pub fn config_backups_dir() -> Result<PathBuf> {
+    Ok(crate::moon::paths::resolve_paths()?
+        .moon_home
+        .join("backups"))
+}
+
+/// Config backups, oldest first (names carry a sortable UTC timestamp).
+pub fn list_config_backups(dir: &Path) -> Result<Vec<PathBuf>> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err).with_context(|| format!("failed to read {}", dir.display())),
+    };
+    let mut backups = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| {
+                    name.starts_with(CONFIG_BACKUP_PREFIX) && name.ends_with(".json")
+                })
+        })
+        .collect::<Vec<_>>();
+    backups.sort();
+    Ok(backups)
+}
+
+/// Copies the current config to `MOON_HOME/backups/openclaw-<UTC timestamp>.json` and prunes
+/// all but the newest `CONFIG_BACKUP_KEEP`. `None` when there is no config yet.
+fn backup_config(config_path: &Path) -> Result<Option<PathBuf>> {
+    if !config_path.exists() {
+        return Ok(None);
+    }
+    let dir = config_backups_dir()?;
+    fs::create_dir_all(&dir).with_context(|| format!("failed to create {}", dir.display()))?;
+    let stamp = Utc::now().format("%Y%m%dT%H%M%S%.3fZ");
+    let backup = dir.join(format!("{CONFIG_BACKUP_PREFIX}{stamp}.json"));
+    fs::copy(config_path, &backup).with_context(|| {
+        format!(
+            "failed backing up config {} -> {}",
+            config_path.display(),
+            backup.display()
+        )
+    })?;
+
+    let backups = list_config_backups(&dir)?;
+    let excess = backups.len().saturating_sub(CONFIG_BACKUP_KEEP);
+    for old in backups.into_iter().take(excess) {
+        let _ = fs::remove_file(old);
+    }
+    Ok(Some(backup))
+}
+
+/// Reads a backup as config, so a truncated or hand-broken file is refused before restore.
+pub fn read_config_backup(path: &Path) -> Result<Value> {
+    let raw =
+        fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    parse_config_text(&raw).with_context(|| format!("invalid config backup {}", path.display()))
 }
 
 pub fn write_config_atomic(paths: &OpenClawPaths, value: &Value) -> Result<String> {
     ensure_parent_dir(&paths.config_path)?;
 
-    if paths.config_path.exists() {
-        let backup = backup_path(&paths.config_path)?;
-        fs::copy(&paths.config_path, &backup).with_context(|| {
-            format!(
-                "failed backing up config {} -> {}",
-                paths.config_path.display(),
-                backup
-            )
-        })?;
-    }
+    backup_config(&paths.config_path)?;
 
     let parent = paths
         .config_path
@@ -29,7 +29,21 @@ fn required_home_dir() -> Result<PathBuf> {
     Err(anyhow::anyhow!("HOME directory could not be resolved"))
 }
 
+impl OpenClawPaths {
+    /// Plugin directory as recorded in `plugins.installs`: `/` separators on every platform,
+    /// which OpenClaw (Node) accepts on Windows too.
+    pub fn plugin_dir_record(&self) -> String {
+        crate::moon::archive::portable_path_string(&self.plugin_dir)
+    }
+}
+
 pub fn resolve_paths() -> Result<OpenClawPaths> {
+    resolve_paths_with_prefix(None)
+}
+
+/// Paths with the plugin installed under `prefix` (or `MOON_PLUGIN_PREFIX`) instead of
+/// `<state_dir>/extensions`.
+pub fn resolve_paths_with_prefix(prefix: Option<&Path>) -> Result<OpenClawPaths> {
     let home = required_home_dir()?;
 
     let state_dir = match env::var("OPENCLAW_STATE_DIR") {
@@ -42,7 +56,13 @@ pub fn resolve_paths() -> Result<OpenClawPaths> {
         _ => state_dir.join("openclaw.json"),
     };
 
-    let extensions_dir = state_dir.join("extensions");
+    let extensions_dir = match prefix {
+        Some(prefix) => prefix.to_path_buf(),
+        None => match env::var("MOON_PLUGIN_PREFIX") {
+            Ok(v) if !v.trim().is_empty() => PathBuf::from(v.trim()),
+            _ => state_dir.join("extensions"),
+        },
+    };
     let plugin_dir = extensions_dir.join(PLUGIN_ID);
 
     Ok(OpenClawPaths {
@@ -1,9 +1,65 @@
-use crate::assets::{plugin_asset_contents, write_plugin_assets};
+use crate::assets::{asset_matches, plugin_asset_contents, write_plugin_assets};
 use crate::openclaw::gateway;
 use crate::openclaw::paths::OpenClawPaths;
 use anyhow::Result;
 use std::fs;
 
+pub const CHANNEL_STABLE: &str = "stable";
+pub const CHANNEL_BETA: &str = "beta";
+
+/// What `moon install` does with the plugin assets under the recorded pin and channel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AssetDecision {
+    /// Write the embedded assets (no-op when they already match).
+    Refresh,
+    /// Leave the installed assets in place.
+    Keep { reason: String },
+    /// Nothing acceptable to install.
+    Blocked { reason: String },
+}
+
+pub fn is_prerelease(version: &str) -> bool {
+    version.contains('-')
+}
+
+/// A pin always wins over the channel; the stable channel never installs prerelease assets.
+pub fn decide_assets(
+    embedded: &str,
+    installed: Option<&str>,
+    pin: Option<&str>,
+    channel: &str,
+) -> AssetDecision {
+    if let Some(pin) = pin {
+        if pin == embedded {
+            return AssetDecision::Refresh;
+        }
+        if installed == Some(pin) {
+            return AssetDecision::Keep {
+                reason: format!("pinned={pin} embedded={embedded}"),
+            };
+        }
+        return AssetDecision::Blocked {
+            reason: format!(
+                "pinned version {pin} is not embedded in this moon binary (embedded {embedded}); \
+                 install a moon release that ships {pin} or rerun with --unpin"
+            ),
+        };
+    }
+    if channel == CHANNEL_STABLE && is_prerelease(embedded) {
+        if installed.is_some() {
+            return AssetDecision::Keep {
+                reason: format!("channel=stable skips prerelease {embedded}"),
+            };
+        }
+        return AssetDecision::Blocked {
+            reason: format!(
+                "embedded plugin {embedded} is a prerelease; rerun with --channel beta"
+            ),
+        };
+    }
+    AssetDecision::Refresh
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct PluginInstallOutcome {
     pub changed: bool,
@@ -21,7 +77,7 @@ fn plugin_dir_matches_assets(paths: &OpenClawPaths) -> Result<bool> {
             return Ok(false);
         }
         let current = fs::read_to_string(&file)?;
-        if current != expected {
+        if !asset_matches(&current, expected) {
             return Ok(false);
         }
     }
@@ -51,3 +107,36 @@ pub fn install_plugin(paths: &OpenClawPaths, dry_run: bool) -> Result<PluginInst
         path: paths.plugin_dir.display().to_string(),
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decide_assets_honors_pin_before_channel() {
+        assert_eq!(
+            decide_assets("0.2.0", Some("0.1.0"), None, CHANNEL_STABLE),
+            AssetDecision::Refresh
+        );
+        assert!(matches!(
+            decide_assets("0.2.0", Some("0.1.0"), Some("0.1.0"), CHANNEL_STABLE),
+            AssetDecision::Keep { .. }
+        ));
+        assert!(matches!(
+            decide_assets("0.2.0", None, Some("0.1.0"), CHANNEL_STABLE),
+            AssetDecision::Blocked { .. }
+        ));
+        assert_eq!(
+            decide_assets("0.3.0-beta.1", None, Some("0.3.0-beta.1"), CHANNEL_STABLE),
+            AssetDecision::Refresh
+        );
+        assert!(matches!(
+            decide_assets("0.3.0-beta.1", Some("0.2.0"), None, CHANNEL_STABLE),
+            AssetDecision::Keep { .. }
+        ));
+        assert_eq!(
+            decide_assets("0.3.0-beta.1", Some("0.2.0"), None, CHANNEL_BETA),
+            AssetDecision::Refresh
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use crate::assets::plugin_asset_contents;
+use crate::assets::{asset_matches, installed_plugin_version, plugin_asset_contents};
 use anyhow::Result;
 use serde_json::Value;
 use std::fs;
@@ -13,6 +13,7 @@ pub struct PluginVerifyOutcome {
     pub loaded_by_openclaw: bool,
     pub assets_match_local: bool,
     pub provenance_warning_detected: bool,
+    pub installed_version: Option<String>,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -45,6 +46,7 @@ pub fn verify_plugin(paths: &OpenClawPaths) -> Result<PluginVerifyOutcome> {
         loaded_by_openclaw: list_state.loaded,
         assets_match_local,
         provenance_warning_detected: list_state.provenance_warning_detected,
+        installed_version: installed_plugin_version(&paths.plugin_dir),
     })
 }
 
@@ -54,7 +56,7 @@ fn plugin_assets_match_local(paths: &OpenClawPaths) -> bool {
         let Ok(current) = fs::read_to_string(path) else {
             return false;
         };
-        if current != expected {
+        if !asset_matches(&current, expected) {
             return false;
         }
     }
@@ -178,6 +180,13 @@ fn is_provenance_warning_message(message: &str) -> bool {
 #[cfg(test)]
 mod tests {
     use super::parse_plugins_list_state;
+    use crate::assets::asset_matches;
+
+    #[test]
+    fn asset_comparison_ignores_crlf_line_endings() {
+        assert!(asset_matches("a\r\nb\r\n", "a\nb\n"));
+        assert!(!asset_matches("a\r\nc\r\n", "a\nb\n"));
+    }
 
     #[test]
     fn parse_plugins_list_state_tolerates_preamble_before_json() {
@@ -0,0 +1,143 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const NAME: &str = env!("CARGO_PKG_NAME");
+const VERSION: &str = env!("CARGO_PKG_VERSION");
+const DESCRIPTION: &str = env!("CARGO_PKG_DESCRIPTION");
+const LICENSE: &str = env!("CARGO_PKG_LICENSE");
+const REPOSITORY: &str = env!("CARGO_PKG_REPOSITORY");
+
+/// Placeholder left in generated manifests until the release artifact is published.
+pub const SHA256_PLACEHOLDER: &str = "REPLACE_WITH_RELEASE_SHA256";
+
+fn source_tarball_url() -> String {
+    format!("{REPOSITORY}/archive/refs/tags/v{VERSION}.tar.gz")
+}
+
+fn windows_zip_url() -> String {
+    format!(
+        "{REPOSITORY}/releases/download/v{VERSION}/{NAME}-v{VERSION}-x86_64-pc-windows-msvc.zip"
+    )
+}
+
+/// Homebrew formula that builds from the tagged source tarball.
+pub fn render_homebrew_formula() -> String {
+    format!(
+        r##"class Moon < Formula
+  desc "{desc}"
+  homepage "{REPOSITORY}"
+  url "{url}"
+  sha256 "{SHA256_PLACEHOLDER}"
+  license "{LICENSE}"
+
+  depends_on "rust" => :build
+
+  def install
+    system "cargo", "install", *std_cargo_args
+  end
+
+  test do
+    assert_match "{NAME}", shell_output("#{{bin}}/{NAME} --help")
+  end
+end
+"##,
+        desc = DESCRIPTION.replace('"', "\\\""),
+        url = source_tarball_url(),
+    )
+}
+
+/// Scoop manifest for the prebuilt Windows zip.
+pub fn render_scoop_manifest() -> Result<String> {
+    let manifest = serde_json::json!({
+        "version": VERSION,
+        "description": DESCRIPTION,
+        "homepage": REPOSITORY,
+        "license": LICENSE,
+        "architecture": {
+            "64bit": {
+                "url": windows_zip_url(),
+                "hash": SHA256_PLACEHOLDER,
+            }
+        },
+        "bin": format!("{NAME}.exe"),
+        "checkver": "github",
+        "autoupdate": {
+            "architecture": {
+                "64bit": {
+                    "url": format!(
+                        "{REPOSITORY}/releases/download/v$version/{NAME}-v$version-x86_64-pc-windows-msvc.zip"
+                    )
+                }
+            }
+        }
+    });
+    let mut out = serde_json::to_string_pretty(&manifest)?;
+    out.push('\n');
+    Ok(out)
+}
+
+/// Debian `control` stanza for a binary package of the current build.
+pub fn render_deb_control() -> String {
+    let arch = match std::env::consts::ARCH {
+        "x86_64" => "amd64",
+        "aarch64" => "arm64",
+        other => other,
+    };
+    format!(
+        "Package: {NAME}\n\
+         Version: {VERSION}\n\
+         Section: utils\n\
+         Priority: optional\n\
+         Architecture: {arch}\n\
+         Maintainer: {NAME} maintainers <REPLACE_WITH_MAINTAINER_EMAIL>\n\
+         Homepage: {REPOSITORY}\n\
+         Description: {DESCRIPTION}\n"
+    )
+}
+
+/// Writes `homebrew/moon.rb`, `scoop/moon.json` and `debian/control` under `dir`.
+pub fn write_packaging_metadata(dir: &Path) -> Result<Vec<PathBuf>> {
+    let files = [
+        (
+            dir.join("homebrew").join(format!("{NAME}.rb")),
+            render_homebrew_formula(),
+        ),
+        (
+            dir.join("scoop").join(format!("{NAME}.json")),
+            render_scoop_manifest()?,
+        ),
+        (dir.join("debian").join("control"), render_deb_control()),
+    ];
+    let mut written = Vec::new();
+    for (path, payload) in files {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create {}", parent.display()))?;
+        }
+        fs::write(&path, payload).with_context(|| format!("failed to write {}", path.display()))?;
+        written.push(path);
+    }
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn manifests_carry_crate_version_and_placeholder_hash() {
+        let formula = render_homebrew_formula();
+        assert!(formula.contains(&format!("v{VERSION}.tar.gz")));
+        assert!(formula.contains(SHA256_PLACEHOLDER));
+
+        let scoop: serde_json::Value =
+            serde_json::from_str(&render_scoop_manifest().expect("scoop")).expect("json");
+        assert_eq!(scoop["version"], VERSION);
+        assert_eq!(scoop["bin"], "moon.exe");
+
+        let control = render_deb_control();
+        assert!(control.starts_with("Package: moon\nVersion: "));
+        assert!(control.ends_with('\n'));
+    }
+}
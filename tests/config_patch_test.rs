@@ -45,6 +45,7 @@ fn patch_respects_existing_values_unless_forced() {
     assert_cmd::cargo::cargo_bin_cmd!("moon")
         .current_dir(tmp.path())
         .env("OPENCLAW_STATE_DIR", &state_dir)
+        .env("HOME", &state_dir)
         .env("OPENCLAW_CONFIG_PATH", &config_path)
         .env("OPENCLAW_BIN", &fake_openclaw)
         .arg("install")
@@ -85,6 +86,7 @@ fn patch_respects_existing_values_unless_forced() {
     assert_cmd::cargo::cargo_bin_cmd!("moon")
         .current_dir(tmp.path())
         .env("OPENCLAW_STATE_DIR", &state_dir)
+        .env("HOME", &state_dir)
         .env("OPENCLAW_CONFIG_PATH", &config_path)
         .env("OPENCLAW_BIN", &fake_openclaw)
         .args(["install", "--force"])
@@ -122,3 +124,125 @@ fn patch_respects_existing_values_unless_forced() {
         None
     );
 }
+
+#[test]
+fn prune_prints_json_diff_and_writes_only_with_apply() {
+    let tmp = tempdir().expect("tempdir");
+    let state_dir = tmp.path().join("state");
+    let moon_home = tmp.path().join("moon");
+    fs::create_dir_all(&state_dir).expect("mkdir");
+    fs::create_dir_all(moon_home.join("moon/logs")).expect("mkdir logs");
+    let config_path = state_dir.join("openclaw.json");
+    let original =
+        r#"{"plugins":{"entries":{"moon":{"config":{"maxTokens":999,"maxChars":90000}}}}}"#;
+    fs::write(&config_path, original).expect("write config");
+
+    let run = |apply: bool| {
+        let mut cmd = assert_cmd::cargo::cargo_bin_cmd!("moon");
+        cmd.current_dir(tmp.path())
+            .env("MOON_HOME", &moon_home)
+            .env("OPENCLAW_STATE_DIR", &state_dir)
+            .env("OPENCLAW_CONFIG_PATH", &config_path)
+            .env("MOON_ENABLE_COMPACTION_WRITE", "true")
+            .arg("prune");
+        if apply {
+            cmd.arg("--apply");
+        }
+        let assert = cmd.assert().success();
+        String::from_utf8_lossy(&assert.get_output().stdout).to_string()
+    };
+
+    let stdout = run(false);
+    assert!(stdout.contains("changes=2"));
+    assert!(stdout.contains("change plugins.entries.moon.config.maxTokens: 999 -> 12000"));
+    assert!(stdout.contains("change plugins.entries.moon.config.maxRetainedBytes: null -> 250000"));
+    assert!(stdout.contains("config changes planned but not applied: dry-run"));
+    let diff: Value = serde_json::from_str(
+        stdout
+            .lines()
+            .find_map(|line| line.strip_prefix("- diff="))
+            .expect("diff detail"),
+    )
+    .expect("diff is json");
+    assert_eq!(
+        diff["changes"][0],
+        serde_json::json!({"path":"plugins.entries.moon.config.maxTokens","old":999,"new":12000})
+    );
+    assert_eq!(fs::read_to_string(&config_path).expect("read"), original);
+
+    let stdout = run(true);
+    assert!(stdout.contains("updated config:"));
+    let cfg: Value =
+        serde_json::from_str(&fs::read_to_string(&config_path).expect("read")).expect("parse");
+    let plugin = &cfg["plugins"]["entries"]["moon"]["config"];
+    assert_eq!(plugin["maxTokens"], 12_000);
+    assert_eq!(plugin["maxChars"], 90_000);
+    assert_eq!(plugin["maxRetainedBytes"], 250_000);
+
+    assert!(run(false).contains("unchanged (safety floors already satisfied)"));
+}
+
+#[test]
+fn prune_apply_keeps_a_backup_that_restore_rolls_back_to() {
+    let tmp = tempdir().expect("tempdir");
+    let state_dir = tmp.path().join("state");
+    let moon_home = tmp.path().join("moon");
+    fs::create_dir_all(&state_dir).expect("mkdir");
+    fs::create_dir_all(moon_home.join("moon/logs")).expect("mkdir logs");
+    let config_path = state_dir.join("openclaw.json");
+    let original = r#"{"plugins":{"entries":{"moon":{"config":{"maxTokens":999}}}}}"#;
+    fs::write(&config_path, original).expect("write config");
+
+    let moon = |args: &[&str]| {
+        let mut cmd = assert_cmd::cargo::cargo_bin_cmd!("moon");
+        cmd.current_dir(tmp.path())
+            .env("MOON_HOME", &moon_home)
+            .env("OPENCLAW_STATE_DIR", &state_dir)
+            .env("OPENCLAW_CONFIG_PATH", &config_path)
+            .env("MOON_ENABLE_COMPACTION_WRITE", "true")
+            .args(args);
+        cmd.assert()
+    };
+
+    moon(&["prune", "--apply"]).success();
+    let backups_dir = moon_home.join("backups");
+    let backups = fs::read_dir(&backups_dir)
+        .expect("backups dir")
+        .map(|entry| entry.expect("entry").path())
+        .collect::<Vec<_>>();
+    assert_eq!(backups.len(), 1);
+    let backup_name = backups[0]
+        .file_name()
+        .and_then(|name| name.to_str())
+        .expect("name")
+        .to_string();
+    assert!(backup_name.starts_with("openclaw-") && backup_name.ends_with(".json"));
+    assert_eq!(fs::read_to_string(&backups[0]).expect("read"), original);
+
+    let assert = moon(&["prune", "restore", "--from", &backup_name]).success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).to_string();
+    assert!(stdout.contains("restored_from="));
+    let cfg: Value =
+        serde_json::from_str(&fs::read_to_string(&config_path).expect("read")).expect("parse");
+    assert_eq!(
+        cfg["plugins"]["entries"]["moon"]["config"]["maxTokens"],
+        999
+    );
+    // The pruned config that was replaced is itself backed up.
+    assert_eq!(fs::read_dir(&backups_dir).expect("backups").count(), 2);
+
+    let broken = tmp.path().join("broken.json");
+    fs::write(&broken, "{ not json").expect("write broken");
+    moon(&["prune", "restore", "--from", broken.to_str().expect("utf8")])
+        .failure()
+        .stdout(predicates::str::contains("E013_INVALID_ARGUMENT"));
+    moon(&["prune", "restore", "--from", "openclaw-missing.json"])
+        .failure()
+        .stdout(predicates::str::contains("E014_PATH_MISSING"));
+    let cfg: Value =
+        serde_json::from_str(&fs::read_to_string(&config_path).expect("read")).expect("parse");
+    assert_eq!(
+        cfg["plugins"]["entries"]["moon"]["config"]["maxTokens"],
+        999
+    );
+}
@@ -61,6 +61,7 @@ fn install_applies_context_policy_and_status_reports_clean() {
     assert_cmd::cargo::cargo_bin_cmd!("moon")
         .current_dir(tmp.path())
         .env("OPENCLAW_STATE_DIR", &state_dir)
+        .env("HOME", &state_dir)
         .env("OPENCLAW_CONFIG_PATH", &config_path)
         .env("OPENCLAW_BIN", &fake_openclaw)
         .env("MOON_CONFIG_PATH", &moon_config)
@@ -94,6 +95,7 @@ fn install_applies_context_policy_and_status_reports_clean() {
     assert_cmd::cargo::cargo_bin_cmd!("moon")
         .current_dir(tmp.path())
         .env("OPENCLAW_STATE_DIR", &state_dir)
+        .env("HOME", &state_dir)
         .env("OPENCLAW_CONFIG_PATH", &config_path)
         .env("OPENCLAW_BIN", &fake_openclaw)
         .env("MOON_CONFIG_PATH", &moon_config)
@@ -120,6 +122,7 @@ fn status_fails_when_compaction_mode_drift_reenables_openclaw_authority() {
     assert_cmd::cargo::cargo_bin_cmd!("moon")
         .current_dir(tmp.path())
         .env("OPENCLAW_STATE_DIR", &state_dir)
+        .env("HOME", &state_dir)
         .env("OPENCLAW_CONFIG_PATH", &config_path)
         .env("OPENCLAW_BIN", &fake_openclaw)
         .env("MOON_CONFIG_PATH", &moon_config)
@@ -150,6 +153,7 @@ fn status_fails_when_compaction_mode_drift_reenables_openclaw_authority() {
     assert_cmd::cargo::cargo_bin_cmd!("moon")
         .current_dir(tmp.path())
         .env("OPENCLAW_STATE_DIR", &state_dir)
+        .env("HOME", &state_dir)
         .env("OPENCLAW_CONFIG_PATH", &config_path)
         .env("OPENCLAW_BIN", &fake_openclaw)
         .env("MOON_CONFIG_PATH", &moon_config)
@@ -13,6 +13,7 @@ fn verify_fails_when_openclaw_binary_missing() {
     assert_cmd::cargo::cargo_bin_cmd!("moon")
         .current_dir(tmp.path())
         .env("OPENCLAW_STATE_DIR", &state_dir)
+        .env("HOME", &state_dir)
         .env("OPENCLAW_CONFIG_PATH", &config_path)
         .env("OPENCLAW_BIN", "/definitely/not/a/real/openclaw")
         .arg("verify")
@@ -32,9 +33,61 @@ fn install_fails_when_config_invalid() {
     assert_cmd::cargo::cargo_bin_cmd!("moon")
         .current_dir(tmp.path())
         .env("OPENCLAW_STATE_DIR", &state_dir)
+        .env("HOME", &state_dir)
         .env("OPENCLAW_CONFIG_PATH", &config_path)
         .arg("install")
         .assert()
         .failure()
         .stderr(contains("failed to parse config as JSON/JSON5"));
 }
+
+/// Answers every HTTP request on a loopback port with `status_line` until the test exits.
+fn serve_http_status(status_line: &'static str) -> String {
+    use std::io::{Read, Write};
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind");
+    let addr = listener.local_addr().expect("addr");
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else { continue };
+            let mut buf = [0u8; 16 * 1024];
+            let _ = stream.read(&mut buf);
+            let _ = stream.write_all(
+                format!("HTTP/1.1 {status_line}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+                    .as_bytes(),
+            );
+        }
+    });
+    format!("http://{addr}")
+}
+
+#[test]
+fn distill_syns_names_auth_error_class_when_provider_rejects_key() {
+    let tmp = tempdir().expect("tempdir");
+    let moon_home = tmp.path().join("moon");
+    fs::create_dir_all(moon_home.join("memory")).expect("mkdir memory");
+    fs::create_dir_all(moon_home.join("moon/logs")).expect("mkdir logs");
+    let source = moon_home.join("memory/source.md");
+    fs::write(
+        &source,
+        "# Daily Memory\n\n### Rules\n- keep watcher cadence at 60 seconds\n",
+    )
+    .expect("write source");
+
+    assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("MOON_HOME", &moon_home)
+        .env("MOON_WISDOM_PROVIDER", "openai-compatible")
+        .env("MOON_WISDOM_MODEL", "qwen-test")
+        .env("AI_BASE_URL", serve_http_status("401 Unauthorized"))
+        .env("AI_API_KEY", "sk-test-revoked")
+        .args(["distill", "--mode", "syns", "--file"])
+        .arg(&source)
+        .assert()
+        .code(2)
+        .stdout(contains("error_class=auth"))
+        .stdout(contains("fix MOON_WISDOM_PROVIDER"));
+
+    let audit = fs::read_to_string(moon_home.join("moon/logs/audit.log")).expect("read audit");
+    assert!(audit.contains("\"phase\":\"distill-chunk\",\"status\":\"failed\""));
+    assert!(!audit.contains("sk-test-revoked"));
+}
@@ -33,6 +33,7 @@ fn install_creates_plugin_and_stage2_config_entries() {
     assert_cmd::cargo::cargo_bin_cmd!("moon")
         .current_dir(tmp.path())
         .env("OPENCLAW_STATE_DIR", &state_dir)
+        .env("HOME", &state_dir)
         .env("OPENCLAW_CONFIG_PATH", &config_path)
         .env("OPENCLAW_BIN", &fake_openclaw)
         .arg("install")
@@ -117,3 +118,253 @@ fn install_creates_plugin_and_stage2_config_entries() {
         None
     );
 }
+
+#[test]
+fn install_prefix_places_plugin_outside_state_extensions() {
+    let tmp = tempdir().expect("tempdir");
+    let state_dir = tmp.path().join("state");
+    fs::create_dir_all(&state_dir).expect("mkdir");
+    let config_path = state_dir.join("openclaw.json");
+    fs::write(&config_path, "{}\n").expect("write config");
+    let prefix = tmp.path().join("custom-plugins");
+
+    let fake_openclaw = tmp.path().join("openclaw");
+    let log_path = tmp.path().join("openclaw.log");
+    write_fake_openclaw(&fake_openclaw, &log_path);
+
+    let output = assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("OPENCLAW_STATE_DIR", &state_dir)
+        .env("HOME", &state_dir)
+        .env("OPENCLAW_CONFIG_PATH", &config_path)
+        .env("OPENCLAW_BIN", &fake_openclaw)
+        .env_remove("MOON_PLUGIN_PREFIX")
+        .arg("install")
+        .arg("--prefix")
+        .arg(&prefix)
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let stdout = String::from_utf8_lossy(&output);
+    assert!(stdout.contains("MOON_PLUGIN_PREFIX="));
+
+    let plugin_dir = prefix.join("moon");
+    assert!(plugin_dir.join("index.js").exists());
+    assert!(!state_dir.join("extensions").join("moon").exists());
+
+    let cfg: Value = serde_json::from_str(&fs::read_to_string(&config_path).expect("read config"))
+        .expect("parse cfg");
+    assert_eq!(
+        cfg.pointer("/plugins/installs/moon/installPath")
+            .and_then(Value::as_str),
+        Some(plugin_dir.display().to_string().as_str())
+    );
+
+    assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("OPENCLAW_STATE_DIR", &state_dir)
+        .env("HOME", &state_dir)
+        .env("OPENCLAW_CONFIG_PATH", &config_path)
+        .env("OPENCLAW_BIN", &fake_openclaw)
+        .env("MOON_PLUGIN_PREFIX", &prefix)
+        .arg("verify")
+        .assert()
+        .success();
+}
+
+#[test]
+fn install_packaging_dir_writes_manifests_without_touching_openclaw() {
+    let tmp = tempdir().expect("tempdir");
+    let out_dir = tmp.path().join("dist");
+    let fake_openclaw = tmp.path().join("openclaw");
+    let log_path = tmp.path().join("openclaw.log");
+    write_fake_openclaw(&fake_openclaw, &log_path);
+
+    assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("OPENCLAW_STATE_DIR", tmp.path().join("state"))
+        .env("HOME", tmp.path().join("state"))
+        .env("OPENCLAW_BIN", &fake_openclaw)
+        .arg("install")
+        .arg("--packaging-dir")
+        .arg(&out_dir)
+        .assert()
+        .success();
+
+    let formula = fs::read_to_string(out_dir.join("homebrew/moon.rb")).expect("formula");
+    assert!(formula.contains("class Moon < Formula"));
+    let scoop: Value =
+        serde_json::from_str(&fs::read_to_string(out_dir.join("scoop/moon.json")).expect("scoop"))
+            .expect("parse scoop");
+    assert_eq!(scoop["bin"], "moon.exe");
+    let control = fs::read_to_string(out_dir.join("debian/control")).expect("control");
+    assert!(control.contains("Package: moon"));
+    assert!(!log_path.exists());
+    assert!(!tmp.path().join("state").exists());
+}
+
+#[test]
+fn install_pin_keeps_installed_plugin_version_and_verify_reports_drift() {
+    let tmp = tempdir().expect("tempdir");
+    let state_dir = tmp.path().join("state");
+    fs::create_dir_all(&state_dir).expect("mkdir");
+    let config_path = state_dir.join("openclaw.json");
+    fs::write(&config_path, "{}\n").expect("write config");
+    let fake_openclaw = tmp.path().join("openclaw");
+    let log_path = tmp.path().join("openclaw.log");
+    write_fake_openclaw(&fake_openclaw, &log_path);
+
+    let moon = |args: &[&str]| {
+        let mut cmd = assert_cmd::cargo::cargo_bin_cmd!("moon");
+        cmd.current_dir(tmp.path())
+            .env("OPENCLAW_STATE_DIR", &state_dir)
+            .env("HOME", &state_dir)
+            .env("OPENCLAW_CONFIG_PATH", &config_path)
+            .env("OPENCLAW_BIN", &fake_openclaw)
+            .env_remove("MOON_PLUGIN_PREFIX")
+            .args(args);
+        cmd
+    };
+    let read_cfg = || -> Value {
+        serde_json::from_str(&fs::read_to_string(&config_path).expect("read config"))
+            .expect("parse cfg")
+    };
+
+    moon(&["install"]).assert().success();
+    let embedded = read_cfg()
+        .pointer("/plugins/installs/moon/version")
+        .and_then(Value::as_str)
+        .expect("recorded version")
+        .to_string();
+    assert_eq!(
+        read_cfg()
+            .pointer("/plugins/installs/moon/channel")
+            .and_then(Value::as_str),
+        Some("stable")
+    );
+
+    let package_json = state_dir.join("extensions/moon/package.json");
+    let older = fs::read_to_string(&package_json)
+        .expect("read package.json")
+        .replace(
+            &format!("\"version\": \"{embedded}\""),
+            "\"version\": \"0.0.9\"",
+        );
+    fs::write(&package_json, older).expect("write package.json");
+
+    // Version drift is a warning, so strict verify reports it without failing.
+    let drift = moon(&["verify", "--strict"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let drift = String::from_utf8_lossy(&drift);
+    assert!(drift.contains("warnings:\n- plugin version drift: installed 0.0.9"));
+
+    moon(&["install", "--pin", "0.0.9"]).assert().success();
+    assert_eq!(
+        read_cfg()
+            .pointer("/plugins/installs/moon/pinnedVersion")
+            .and_then(Value::as_str),
+        Some("0.0.9")
+    );
+    assert!(
+        fs::read_to_string(&package_json)
+            .expect("read package.json")
+            .contains("0.0.9")
+    );
+    let pinned = moon(&["verify"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    assert!(String::from_utf8_lossy(&pinned).contains("plugin_version.drift=pinned"));
+
+    moon(&["install", "--unpin"]).assert().success();
+    let cfg = read_cfg();
+    assert!(
+        cfg.pointer("/plugins/installs/moon/pinnedVersion")
+            .is_none()
+    );
+    assert_eq!(
+        cfg.pointer("/plugins/installs/moon/version")
+            .and_then(Value::as_str),
+        Some(embedded.as_str())
+    );
+
+    moon(&["install", "--channel", "nightly"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn verify_only_scopes_checks_and_strict_fails_only_on_errors() {
+    let tmp = tempdir().expect("tempdir");
+    let state_dir = tmp.path().join("state");
+    fs::create_dir_all(&state_dir).expect("mkdir");
+    let config_path = state_dir.join("openclaw.json");
+    fs::write(&config_path, "{}\n").expect("write config");
+    let fake_openclaw = tmp.path().join("openclaw");
+    let log_path = tmp.path().join("openclaw.log");
+    write_fake_openclaw(&fake_openclaw, &log_path);
+
+    let moon = |args: &[&str]| {
+        let mut cmd = assert_cmd::cargo::cargo_bin_cmd!("moon");
+        cmd.current_dir(tmp.path())
+            .env("OPENCLAW_STATE_DIR", &state_dir)
+            .env("HOME", &state_dir)
+            .env("OPENCLAW_CONFIG_PATH", &config_path)
+            .env("OPENCLAW_BIN", &fake_openclaw)
+            .env_remove("MOON_PLUGIN_PREFIX")
+            .args(args);
+        cmd
+    };
+
+    moon(&["install"]).assert().success();
+
+    // Dropping a plugin limit is a warning: strict verify still passes.
+    let mut cfg: Value =
+        serde_json::from_str(&fs::read_to_string(&config_path).expect("read config"))
+            .expect("parse cfg");
+    cfg.pointer_mut("/plugins/entries/moon/config")
+        .and_then(Value::as_object_mut)
+        .expect("plugin config")
+        .remove("maxChars");
+    // Disabling the plugin is an error.
+    cfg.pointer_mut("/plugins/entries/moon")
+        .and_then(Value::as_object_mut)
+        .expect("plugin entry")
+        .insert("enabled".to_string(), Value::from(false));
+    fs::write(
+        &config_path,
+        serde_json::to_string_pretty(&cfg).expect("json"),
+    )
+    .expect("write");
+
+    let out = moon(&["verify", "--strict", "--only", "config"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let out = String::from_utf8_lossy(&out);
+    assert!(out.contains("verify.scope=config"));
+    assert!(out.contains("- missing plugins.entries.moon.config.maxChars"));
+    assert!(!out.contains("plugin_present_on_disk="));
+
+    let out = moon(&["verify", "--strict", "--only", "plugin"])
+        .assert()
+        .code(2)
+        .get_output()
+        .stdout
+        .clone();
+    let out = String::from_utf8_lossy(&out);
+    assert!(out.contains("plugin entry is not enabled in config"));
+    assert!(out.contains("strict verify failed: 1 error(s)"));
+
+    moon(&["verify", "--only", "gateway"]).assert().failure();
+}
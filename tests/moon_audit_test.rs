@@ -0,0 +1,82 @@
+#![cfg(not(windows))]
+use predicates::str::contains;
+use std::fs;
+use tempfile::tempdir;
+
+#[test]
+fn moon_audit_filters_on_typed_details_and_skips_legacy_messages() {
+    let tmp = tempdir().expect("tempdir");
+    let moon_home = tmp.path().join("moon");
+    let logs_dir = moon_home.join("moon/logs");
+    fs::create_dir_all(&logs_dir).expect("mkdir logs");
+    fs::write(
+        logs_dir.join("audit.log"),
+        concat!(
+            "{\"at_epoch_secs\":1700000000,\"phase\":\"distill\",\"status\":\"ok\",\"message\":\"l1_normalised session=s1 source=/a/s1.jsonl target=/m/day.md\"}\n",
+            "{\"schema_version\":2,\"at_epoch_secs\":1700000100,\"phase\":\"distill\",\"status\":\"degraded\",\"message\":\"archive=/a/s2.jsonl session=s2 error=provider timed out\",\"details\":{\"session\":\"s2\",\"archive\":\"/a/s2.jsonl\",\"error\":\"provider timed out\"},\"cycle_id\":\"1700000100-7-1\"}\n",
+            "{\"schema_version\":2,\"at_epoch_secs\":1700000200,\"phase\":\"compaction\",\"status\":\"ok\",\"message\":\"manual ok key=s1\",\"details\":{\"trigger\":\"manual\",\"session\":\"s1\"}}\n",
+        ),
+    )
+    .expect("write audit log");
+
+    let moon = || {
+        let mut cmd = assert_cmd::cargo::cargo_bin_cmd!("moon");
+        cmd.current_dir(tmp.path()).env("MOON_HOME", &moon_home);
+        cmd
+    };
+
+    moon()
+        .args(["audit", "--where", "session=s1"])
+        .assert()
+        .success()
+        .stdout(contains("events_matched=1"))
+        .stdout(contains("\"trigger\":\"manual\""));
+
+    moon()
+        .args([
+            "audit",
+            "--phase",
+            "distill",
+            "--where",
+            "error=provider timed out",
+        ])
+        .assert()
+        .success()
+        .stdout(contains("events_matched=1"))
+        .stdout(contains("cycle_id=1700000100-7-1"));
+
+    moon()
+        .args([
+            "audit",
+            "--cycle",
+            "1700000100-7-1",
+            "--where",
+            "session=s1",
+        ])
+        .assert()
+        .success()
+        .stdout(contains("events_matched=0"));
+
+    moon()
+        .args(["audit", "--where", "session"])
+        .assert()
+        .code(2)
+        .stdout(contains(
+            "- [E013_INVALID_ARGUMENT] invalid --where `session`",
+        ))
+        .stdout(contains("  hint: see `moon help`"));
+
+    let out = moon()
+        .args(["--json", "audit", "--where", "session"])
+        .assert()
+        .code(2);
+    let report: serde_json::Value =
+        serde_json::from_slice(&out.get_output().stdout).expect("json report");
+    let issue = &report["issues"][0];
+    assert_eq!(issue["code"], "E013_INVALID_ARGUMENT");
+    assert_eq!(
+        issue["message"],
+        "invalid --where `session`: expected KEY=VALUE"
+    );
+    assert!(issue["hint"].as_str().is_some_and(|hint| !hint.is_empty()));
+}
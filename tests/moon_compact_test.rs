@@ -0,0 +1,375 @@
+#![cfg(not(windows))]
+use std::fs;
+use std::path::Path;
+use tempfile::tempdir;
+
+fn write_executable(path: &Path, script: &str) {
+    fs::write(path, script).expect("write script");
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mut perms = fs::metadata(path).expect("metadata").permissions();
+        perms.set_mode(0o755);
+        fs::set_permissions(path, perms).expect("chmod");
+    }
+}
+
+fn write_fake_qmd(bin_path: &Path) {
+    write_executable(bin_path, "#!/usr/bin/env bash\nexit 0\n");
+}
+
+fn write_fake_openclaw(bin_path: &Path) {
+    let script = r#"#!/usr/bin/env bash
+set -euo pipefail
+
+if [[ "${1:-}" == "sessions" && "${2:-}" == "--json" ]]; then
+  echo '{"path":"x","count":1,"sessions":[{"key":"agent:main:discord:channel:ops","totalTokens":9000,"contextTokens":10000}]}'
+  exit 0
+fi
+
+if [[ "${1:-}" == "gateway" && "${2:-}" == "call" && "${3:-}" == "chat.send" ]]; then
+  printf "%s\n" "$*" >> "${MOON_TEST_COMPACT_LOG}"
+  echo '{"status":"started","runId":"test-run"}'
+  exit 0
+fi
+
+exit 0
+"#;
+    write_executable(bin_path, script);
+}
+
+fn setup(root: &Path) -> (std::path::PathBuf, std::path::PathBuf) {
+    let moon_home = root.join("moon");
+    let sessions_dir = root.join("sessions");
+    fs::create_dir_all(moon_home.join("archives")).expect("mkdir archives");
+    fs::create_dir_all(moon_home.join("memory")).expect("mkdir memory");
+    fs::create_dir_all(moon_home.join("moon/logs")).expect("mkdir logs");
+    fs::create_dir_all(&sessions_dir).expect("mkdir sessions");
+    fs::write(
+        sessions_dir.join("sess-ops.jsonl"),
+        "{\"messages\":[\"ops channel history\"]}\n",
+    )
+    .expect("write session");
+    fs::write(
+        sessions_dir.join("sessions.json"),
+        r#"{"agent:main:discord:channel:ops": {"sessionId":"sess-ops"}}"#,
+    )
+    .expect("write sessions map");
+    (moon_home, sessions_dir)
+}
+
+#[test]
+fn moon_compact_archives_maps_and_compacts_session() {
+    let tmp = tempdir().expect("tempdir");
+    let (moon_home, sessions_dir) = setup(tmp.path());
+    let compact_log = tmp.path().join("compact.log");
+    let qmd = tmp.path().join("qmd");
+    write_fake_qmd(&qmd);
+    let openclaw = tmp.path().join("openclaw");
+    write_fake_openclaw(&openclaw);
+
+    let assert = assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("MOON_HOME", &moon_home)
+        .env("OPENCLAW_SESSIONS_DIR", &sessions_dir)
+        .env("QMD_BIN", &qmd)
+        .env("OPENCLAW_BIN", &openclaw)
+        .env("MOON_TEST_COMPACT_LOG", &compact_log)
+        .args(["compact", "agent:main:discord:channel:ops"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout.contains("verify.channel_archive_map=ok"));
+    assert!(stdout.contains("verify.usage_ratio_before=0.9000"));
+
+    let compact_calls = fs::read_to_string(&compact_log).expect("read compact log");
+    assert!(compact_calls.contains("/compact"));
+    assert!(compact_calls.contains("MOON_ARCHIVE_INDEX"));
+
+    let ledger = fs::read_to_string(moon_home.join("archives/ledger.jsonl")).expect("read ledger");
+    assert!(ledger.contains("sess-ops.jsonl"));
+    let channel_map = fs::read_to_string(moon_home.join("continuity/channel_archive_map.json"))
+        .expect("read channel archive map");
+    assert!(channel_map.contains("agent:main:discord:channel:ops"));
+    let audit = fs::read_to_string(moon_home.join("moon/logs/audit.log")).expect("read audit");
+    assert!(audit.contains("manual ok key=agent:main:discord:channel:ops"));
+
+    let continuity = fs::read_to_string(moon_home.join("continuity/records.jsonl"))
+        .expect("read continuity records");
+    assert!(continuity.contains("\"reason\":\"compaction\""));
+    assert!(continuity.contains("\"channel_key\":\"agent:main:discord:channel:ops\""));
+}
+
+#[test]
+fn moon_compact_refuses_unknown_session_without_compacting() {
+    let tmp = tempdir().expect("tempdir");
+    let (moon_home, sessions_dir) = setup(tmp.path());
+    let compact_log = tmp.path().join("compact.log");
+    let qmd = tmp.path().join("qmd");
+    write_fake_qmd(&qmd);
+    let openclaw = tmp.path().join("openclaw");
+    write_fake_openclaw(&openclaw);
+
+    assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("MOON_HOME", &moon_home)
+        .env("OPENCLAW_SESSIONS_DIR", &sessions_dir)
+        .env("QMD_BIN", &qmd)
+        .env("OPENCLAW_BIN", &openclaw)
+        .env("MOON_TEST_COMPACT_LOG", &compact_log)
+        .args(["compact", "agent:main:discord:channel:missing"])
+        .assert()
+        .code(2);
+    assert!(!compact_log.exists());
+    assert!(!moon_home.join("archives/ledger.jsonl").exists());
+}
+
+#[test]
+fn moon_compact_routes_archive_to_channel_collection() {
+    let tmp = tempdir().expect("tempdir");
+    let (moon_home, sessions_dir) = setup(tmp.path());
+    let compact_log = tmp.path().join("compact.log");
+    let qmd = tmp.path().join("qmd");
+    write_fake_qmd(&qmd);
+    let openclaw = tmp.path().join("openclaw");
+    write_fake_openclaw(&openclaw);
+    let config_path = tmp.path().join("moon.toml");
+    fs::write(
+        &config_path,
+        "[collections]\ndefault = \"history\"\n[collections.channels]\n\"agent:main:discord:\" = \"discord-history\"\n",
+    )
+    .expect("write config");
+
+    let assert = assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("MOON_HOME", &moon_home)
+        .env("MOON_CONFIG_PATH", &config_path)
+        .env("OPENCLAW_SESSIONS_DIR", &sessions_dir)
+        .env("QMD_BIN", &qmd)
+        .env("OPENCLAW_BIN", &openclaw)
+        .env("MOON_TEST_COMPACT_LOG", &compact_log)
+        .args(["compact", "agent:main:discord:channel:ops"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout.contains("collection=discord-history"));
+
+    let ledger = fs::read_to_string(moon_home.join("archives/ledger.jsonl")).expect("read ledger");
+    assert!(ledger.contains("\"indexed_collection\":\"discord-history\""));
+}
+
+#[test]
+fn moon_compact_sends_channel_strategy_and_records_it_in_channel_map() {
+    let tmp = tempdir().expect("tempdir");
+    let (moon_home, sessions_dir) = setup(tmp.path());
+    let compact_log = tmp.path().join("compact.log");
+    let qmd = tmp.path().join("qmd");
+    write_fake_qmd(&qmd);
+    let openclaw = tmp.path().join("openclaw");
+    write_fake_openclaw(&openclaw);
+    let config_path = tmp.path().join("moon.toml");
+    fs::write(
+        &config_path,
+        "[compaction.channels.\"agent:main:discord:\"]\nfocus = [\"decisions\", \"tasks\"]\nkeep_last = 20\n",
+    )
+    .expect("write config");
+
+    let assert = assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("MOON_HOME", &moon_home)
+        .env("MOON_CONFIG_PATH", &config_path)
+        .env("OPENCLAW_SESSIONS_DIR", &sessions_dir)
+        .env("QMD_BIN", &qmd)
+        .env("OPENCLAW_BIN", &openclaw)
+        .env("MOON_TEST_COMPACT_LOG", &compact_log)
+        .args(["compact", "agent:main:discord:channel:ops"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout.contains("compaction_strategy=focus=decisions,tasks keep_last=20"));
+
+    let compact_calls = fs::read_to_string(&compact_log).expect("read compact log");
+    assert!(compact_calls.contains("/compact focus=decisions,tasks keep_last=20"));
+
+    let channel_map = fs::read_to_string(moon_home.join("continuity/channel_archive_map.json"))
+        .expect("read channel archive map");
+    assert!(
+        channel_map.contains("\"compaction_strategy\": \"focus=decisions,tasks keep_last=20\"")
+    );
+}
+
+#[test]
+fn moon_compact_does_not_send_when_chat_send_disallowed() {
+    let tmp = tempdir().expect("tempdir");
+    let (moon_home, sessions_dir) = setup(tmp.path());
+    let compact_log = tmp.path().join("compact.log");
+    let qmd = tmp.path().join("qmd");
+    write_fake_qmd(&qmd);
+    let openclaw = tmp.path().join("openclaw");
+    write_fake_openclaw(&openclaw);
+
+    let assert = assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("MOON_HOME", &moon_home)
+        .env("OPENCLAW_SESSIONS_DIR", &sessions_dir)
+        .env("QMD_BIN", &qmd)
+        .env("OPENCLAW_BIN", &openclaw)
+        .env("MOON_TEST_COMPACT_LOG", &compact_log)
+        .env("MOON_ALLOW_CHAT_SEND", "false")
+        .args(["compact", "agent:main:discord:channel:ops"])
+        .assert()
+        .code(2);
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout.contains("MOON_ALLOW_CHAT_SEND is set to `false`"));
+    assert!(!compact_log.exists());
+}
+
+#[test]
+fn moon_compact_resends_on_explicit_rerun_with_the_same_idempotency_key() {
+    let tmp = tempdir().expect("tempdir");
+    let (moon_home, sessions_dir) = setup(tmp.path());
+    let compact_log = tmp.path().join("compact.log");
+    let qmd = tmp.path().join("qmd");
+    write_fake_qmd(&qmd);
+    let openclaw = tmp.path().join("openclaw");
+    write_fake_openclaw(&openclaw);
+
+    let run = || {
+        assert_cmd::cargo::cargo_bin_cmd!("moon")
+            .current_dir(tmp.path())
+            .env("MOON_HOME", &moon_home)
+            .env("OPENCLAW_SESSIONS_DIR", &sessions_dir)
+            .env("QMD_BIN", &qmd)
+            .env("OPENCLAW_BIN", &openclaw)
+            .env("MOON_TEST_COMPACT_LOG", &compact_log)
+            .args(["compact", "agent:main:discord:channel:ops"])
+            .assert()
+            .success()
+    };
+    run();
+    let second = run();
+    let stdout = String::from_utf8_lossy(&second.get_output().stdout);
+    assert!(!stdout.contains("suppressed-duplicate"));
+
+    // An explicit `moon compact` is never suppressed; the gateway dedupes on the shared key.
+    let compact_calls = fs::read_to_string(&compact_log).expect("read compact log");
+    assert_eq!(compact_calls.matches("\"message\":\"/compact\"").count(), 2);
+    assert_eq!(compact_calls.matches("MOON_ARCHIVE_INDEX").count(), 2);
+
+    let calls = fs::read_to_string(moon_home.join("continuity/gateway_calls.jsonl"))
+        .expect("read gateway call ledger");
+    assert_eq!(calls.matches("\"outcome\":\"sent\"").count(), 4);
+    assert_eq!(calls.matches("\"outcome\":\"suppressed\"").count(), 0);
+    let keys = calls
+        .lines()
+        .filter(|line| line.contains("\"kind\":\"compact\""))
+        .filter_map(|line| line.split("\"idempotency_key\":\"").nth(1))
+        .filter_map(|rest| rest.split('"').next())
+        .collect::<std::collections::BTreeSet<_>>();
+    assert_eq!(keys.len(), 1);
+}
+
+#[test]
+#[cfg(feature = "encryption")]
+fn moon_compact_keeps_private_channel_out_of_index_and_seals_archive() {
+    let tmp = tempdir().expect("tempdir");
+    let (moon_home, sessions_dir) = setup(tmp.path());
+    let compact_log = tmp.path().join("compact.log");
+    let qmd_log = tmp.path().join("qmd.log");
+    let qmd = tmp.path().join("qmd");
+    write_executable(
+        &qmd,
+        "#!/usr/bin/env bash\nprintf '%s\\n' \"$*\" >> \"${MOON_TEST_QMD_LOG}\"\necho '[]'\n",
+    );
+    let openclaw = tmp.path().join("openclaw");
+    write_fake_openclaw(&openclaw);
+    let moon = || {
+        let mut cmd = assert_cmd::cargo::cargo_bin_cmd!("moon");
+        cmd.current_dir(tmp.path())
+            .env("MOON_HOME", &moon_home)
+            .env("OPENCLAW_SESSIONS_DIR", &sessions_dir)
+            .env("QMD_BIN", &qmd)
+            .env("OPENCLAW_BIN", &openclaw)
+            .env("MOON_TEST_COMPACT_LOG", &compact_log)
+            .env("MOON_TEST_QMD_LOG", &qmd_log)
+            .env("MOON_PRIVATE_CHANNELS", "agent:main:discord:*")
+            .env("MOON_PRIVACY_ENCRYPT", "true")
+            .env(
+                "MOON_PRIVACY_KEY",
+                "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08",
+            );
+        cmd
+    };
+
+    moon()
+        .args(["compact", "agent:main:discord:channel:ops"])
+        .assert()
+        .success();
+    assert!(
+        fs::read_to_string(&compact_log)
+            .expect("read compact log")
+            .contains("/compact")
+    );
+
+    let ledger = fs::read_to_string(moon_home.join("archives/ledger.jsonl")).expect("read ledger");
+    assert!(ledger.contains("\"indexed\":false"), "ledger: {ledger}");
+    assert!(
+        ledger.contains("\"private\":true,\"encrypted\":true"),
+        "ledger: {ledger}"
+    );
+    assert!(
+        !ledger.contains("\"projection_path\":\""),
+        "ledger: {ledger}"
+    );
+    let row: serde_json::Value =
+        serde_json::from_str(ledger.lines().next().expect("row")).expect("ledger row is json");
+    let archive = row["archive_path"]
+        .as_str()
+        .expect("archive_path")
+        .to_string();
+    let sealed = fs::read(&archive).expect("read archive");
+    assert!(sealed.starts_with(b"MOONENC1"));
+    assert!(!String::from_utf8_lossy(&sealed).contains("ops channel history"));
+    let mlib = moon_home.join("archives/mlib");
+    assert!(!mlib.exists() || fs::read_dir(&mlib).expect("read mlib").next().is_none());
+    assert!(
+        !qmd_log.exists(),
+        "qmd must not be touched for private archives"
+    );
+    let audit = fs::read_to_string(moon_home.join("moon/logs/audit.log")).expect("read audit");
+    assert!(audit.contains("\"phase\":\"privacy\""));
+
+    // The channel map still points at the archive, but recall must not hand it back.
+    let assert = moon()
+        .args([
+            "recall",
+            "--query",
+            "ops",
+            "--channel-key",
+            "agent:main:discord:channel:ops",
+        ])
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout.contains("match_count=0"), "stdout: {stdout}");
+
+    let plain = tmp.path().join("plain.jsonl");
+    moon()
+        .args(["ledger", "decrypt", "--archive", &archive, "--output"])
+        .arg(&plain)
+        .assert()
+        .success();
+    assert_eq!(
+        fs::read_to_string(&plain).expect("read plaintext"),
+        "{\"messages\":[\"ops channel history\"]}\n"
+    );
+    moon()
+        .env(
+            "MOON_PRIVACY_KEY",
+            "60303ae22b998861bce3b28f33eec1be758a213c86c93c076dbe9f558c11c752",
+        )
+        .args(["ledger", "decrypt", "--archive", &archive, "--output"])
+        .arg(tmp.path().join("bad.jsonl"))
+        .assert()
+        .code(2);
+}
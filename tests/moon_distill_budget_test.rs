@@ -0,0 +1,57 @@
+use std::fs;
+use tempfile::tempdir;
+
+#[test]
+fn moon_distill_syns_stays_local_once_daily_budget_is_exhausted() {
+    let tmp = tempdir().expect("tempdir");
+    let moon_home = tmp.path().join("moon");
+    fs::create_dir_all(moon_home.join("memory")).expect("mkdir memory");
+    fs::create_dir_all(moon_home.join("moon/logs")).expect("mkdir logs");
+
+    let source = moon_home.join("memory/source.md");
+    fs::write(
+        &source,
+        "# Daily Memory\n\n### Rules\n- User prefers concise bullet summaries\n- Decision: keep watcher cadence at 60 seconds\n",
+    )
+    .expect("write source");
+
+    let today = chrono::Utc::now().format("%Y-%m-%d").to_string();
+    fs::write(
+        moon_home.join("moon/logs/distill-budget.json"),
+        format!("{{\"day_key\":\"{today}\",\"tokens_used\":1200,\"exhausted_at_epoch_secs\":1}}\n"),
+    )
+    .expect("write budget");
+
+    // No API key is configured, so any attempt to reach the remote provider would fail.
+    let assert = assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("MOON_HOME", &moon_home)
+        .env("MOON_RESIDENTIAL_TIMEZONE", "UTC")
+        .env("MOON_DISTILL_DAILY_TOKEN_BUDGET", "1000")
+        .env("MOON_WISDOM_PROVIDER", "openai")
+        .env("MOON_WISDOM_MODEL", "gpt-4.1")
+        .env_remove("OPENAI_API_KEY")
+        .env_remove("AI_API_KEY")
+        .args(["distill", "--mode", "syns", "--file"])
+        .arg(&source)
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout.contains("provider=local"));
+    assert!(stdout.contains("remote_tokens=0"));
+
+    let audit = fs::read_to_string(moon_home.join("moon/logs/audit.log")).expect("read audit");
+    assert!(audit.contains("budget=exhausted"));
+
+    let status = assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("MOON_HOME", &moon_home)
+        .env("MOON_RESIDENTIAL_TIMEZONE", "UTC")
+        .env("MOON_DISTILL_DAILY_TOKEN_BUDGET", "1000")
+        .arg("status")
+        .assert();
+    let stdout = String::from_utf8_lossy(&status.get_output().stdout);
+    assert!(stdout.contains("distill_budget.tokens_used=1200"));
+    assert!(stdout.contains("distill_budget.limit=1000"));
+    assert!(stdout.contains("distill_budget.exhausted=true"));
+}
@@ -0,0 +1,334 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tempfile::tempdir;
+
+/// One request the fake provider received: path with query, lowercased headers, JSON body.
+#[derive(Debug, Clone)]
+struct RecordedRequest {
+    path: String,
+    headers: Vec<(String, String)>,
+    body: serde_json::Value,
+}
+
+impl RecordedRequest {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+type RecordedRequests = Arc<Mutex<Vec<RecordedRequest>>>;
+
+/// Serves JSON on a loopback port, answering each request with the status and body from
+/// `respond(path, body)` and recording it in order.
+fn serve_json(
+    respond: fn(&str, &serde_json::Value) -> (u16, serde_json::Value),
+) -> (String, RecordedRequests) {
+    use std::io::{BufRead, BufReader, Read, Write};
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind");
+    let addr = listener.local_addr().expect("addr");
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let recorded = Arc::clone(&requests);
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(stream) = stream else { continue };
+            let mut reader = BufReader::new(stream);
+            let mut request_line = String::new();
+            if reader.read_line(&mut request_line).is_err() {
+                continue;
+            }
+            let path = request_line
+                .split_whitespace()
+                .nth(1)
+                .unwrap_or_default()
+                .to_string();
+            let mut headers = Vec::new();
+            loop {
+                let mut line = String::new();
+                if reader.read_line(&mut line).is_err() || line.trim().is_empty() {
+                    break;
+                }
+                if let Some((key, value)) = line.split_once(':') {
+                    headers.push((key.trim().to_ascii_lowercase(), value.trim().to_string()));
+                }
+            }
+            let content_length = headers
+                .iter()
+                .find(|(key, _)| key == "content-length")
+                .and_then(|(_, value)| value.parse().ok())
+                .unwrap_or(0usize);
+            let mut body = vec![0u8; content_length];
+            let _ = reader.read_exact(&mut body);
+            let body: serde_json::Value = serde_json::from_slice(&body).unwrap_or_default();
+            let (status, payload) = respond(&path, &body);
+            let payload = payload.to_string();
+            recorded.lock().expect("lock").push(RecordedRequest {
+                path,
+                headers,
+                body,
+            });
+            let response = format!(
+                "HTTP/1.1 {status} MOON\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{payload}",
+                payload.len()
+            );
+            let _ = reader.get_mut().write_all(response.as_bytes());
+        }
+    });
+    (format!("http://{addr}"), requests)
+}
+
+const SYNTHESIS_REPLY: &str = "## Durable Knowledge\n- Watcher cadence stays at 60 seconds\n- Archive before every compaction\n- Recall reads projections first";
+
+fn write_daily_memory(moon_home: &Path) -> PathBuf {
+    fs::create_dir_all(moon_home.join("memory")).expect("mkdir memory");
+    fs::create_dir_all(moon_home.join("moon/logs")).expect("mkdir logs");
+    let source = moon_home.join("memory/source.md");
+    fs::write(
+        &source,
+        "# Daily Memory\n\n### Rules\n- keep watcher cadence at 60 seconds\n- Decision: archive before compaction\n",
+    )
+    .expect("write source");
+    source
+}
+
+#[test]
+fn moon_distill_syns_uses_local_ollama_server() {
+    let tmp = tempdir().expect("tempdir");
+    let moon_home = tmp.path().join("moon");
+    let source = write_daily_memory(&moon_home);
+
+    let (base_url, requests) = serve_json(|path, body| {
+        let reply = if path == "/api/show" {
+            serde_json::json!({"model_info": {"llama.context_length": 32768}})
+        } else {
+            serde_json::json!({
+                "model": body["model"],
+                "message": {"role": "assistant", "content": SYNTHESIS_REPLY},
+                "done": true
+            })
+        };
+        (200, reply)
+    });
+
+    let assert = assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("MOON_HOME", &moon_home)
+        .env("MOON_RESIDENTIAL_TIMEZONE", "UTC")
+        .env("MOON_WISDOM_PROVIDER", "ollama")
+        .env("MOON_WISDOM_MODEL", "ollama:qwen2.5:7b")
+        .env(
+            "MOON_OLLAMA_BASE_URL",
+            base_url.trim_start_matches("http://"),
+        )
+        .env_remove("OPENAI_API_KEY")
+        .env_remove("AI_API_KEY")
+        .args(["distill", "--mode", "syns", "--file"])
+        .arg(&source)
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout.contains("provider=ollama"), "stdout: {stdout}");
+
+    let requests = requests.lock().expect("lock");
+    let chat = requests
+        .iter()
+        .find(|request| request.path == "/api/chat")
+        .expect("chat request");
+    assert_eq!(chat.body["model"], "qwen2.5:7b");
+    assert_eq!(chat.body["stream"], false);
+
+    let memory = fs::read_to_string(moon_home.join("MEMORY.md")).expect("read MEMORY.md");
+    assert!(memory.contains("Watcher cadence stays at 60 seconds"));
+}
+
+#[test]
+fn moon_distill_syns_calls_azure_openai_deployment() {
+    let tmp = tempdir().expect("tempdir");
+    let moon_home = tmp.path().join("moon");
+    let source = write_daily_memory(&moon_home);
+
+    let (endpoint, requests) = serve_json(|_, _| {
+        (
+            200,
+            serde_json::json!({
+                "choices": [{"message": {"role": "assistant", "content": SYNTHESIS_REPLY}}]
+            }),
+        )
+    });
+
+    let assert = assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("MOON_HOME", &moon_home)
+        .env("MOON_RESIDENTIAL_TIMEZONE", "UTC")
+        .env("MOON_WISDOM_PROVIDER", "azure")
+        .env("MOON_WISDOM_MODEL", "gpt-4.1")
+        .env("AZURE_OPENAI_ENDPOINT", format!("{endpoint}/"))
+        .env("AZURE_OPENAI_DEPLOYMENT", "moon-synthesis")
+        .env("AZURE_OPENAI_API_VERSION", "2025-01-01-preview")
+        .env("AZURE_OPENAI_API_KEY", "azure-test-key")
+        .env_remove("OPENAI_API_KEY")
+        .env_remove("AI_API_KEY")
+        .args(["distill", "--mode", "syns", "--file"])
+        .arg(&source)
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout.contains("provider=azure-openai"), "stdout: {stdout}");
+
+    let requests = requests.lock().expect("lock");
+    let chat = requests.first().expect("chat request");
+    assert_eq!(
+        chat.path,
+        "/openai/deployments/moon-synthesis/chat/completions?api-version=2025-01-01-preview"
+    );
+    assert_eq!(chat.header("api-key"), Some("azure-test-key"));
+    assert_eq!(chat.header("authorization"), None);
+    assert!(chat.body.get("model").is_none());
+}
+
+#[test]
+fn moon_config_check_keys_reports_each_configured_key() {
+    let tmp = tempdir().expect("tempdir");
+    let moon_home = tmp.path().join("moon");
+    fs::create_dir_all(moon_home.join("moon/logs")).expect("mkdir logs");
+
+    let (base_url, requests) = serve_json(|path, _| {
+        if path.starts_with("/openai/deployments/") {
+            (401, serde_json::json!({"error": {"code": "401"}}))
+        } else if path == "/v1/embeddings" {
+            (
+                429,
+                serde_json::json!({"error": {"type": "insufficient_quota"}}),
+            )
+        } else {
+            (
+                200,
+                serde_json::json!({"message": {"role": "assistant", "content": "OK"}, "done": true}),
+            )
+        }
+    });
+
+    let assert = assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("MOON_HOME", &moon_home)
+        .env("MOON_DISTILL_PROVIDER", "ollama")
+        .env("MOON_DISTILL_MODEL", "ollama:llama3.1")
+        .env("MOON_OLLAMA_BASE_URL", &base_url)
+        .env("MOON_WISDOM_PROVIDER", "azure")
+        .env("MOON_WISDOM_MODEL", "gpt-4.1")
+        .env("AZURE_OPENAI_ENDPOINT", &base_url)
+        .env("AZURE_OPENAI_API_KEY", "expired-azure-key")
+        .env("MOON_EMBED_PROVIDER", "openai-compatible")
+        .env("MOON_EMBED_BASE_URL", &base_url)
+        .env("AI_API_KEY", "embed-key-without-quota")
+        .env_remove("OPENAI_API_KEY")
+        .env_remove("GEMINI_API_KEY")
+        .env_remove("ANTHROPIC_API_KEY")
+        .args(["config", "check-keys"])
+        .assert()
+        .code(2);
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(
+        stdout.contains("key.distill provider=ollama model=llama3.1"),
+        "stdout: {stdout}"
+    );
+    assert!(stdout.contains("status=valid"), "stdout: {stdout}");
+    assert!(
+        stdout.contains("key.wisdom provider=azure-openai model=gpt-4.1 key=exp...-key"),
+        "stdout: {stdout}"
+    );
+    assert!(stdout.contains("status=rejected"), "stdout: {stdout}");
+    assert!(
+        stdout.contains("key.embed provider=openai-compatible"),
+        "stdout: {stdout}"
+    );
+    assert!(stdout.contains("status=no-quota"), "stdout: {stdout}");
+    assert!(stdout.contains("keys.valid=1"), "stdout: {stdout}");
+    assert!(!stdout.contains("expired-azure-key"), "stdout: {stdout}");
+    assert!(
+        !stdout.contains("embed-key-without-quota"),
+        "stdout: {stdout}"
+    );
+
+    // One request per key: no retries and no distillation.
+    assert_eq!(requests.lock().expect("lock").len(), 3);
+}
+
+#[test]
+fn moon_distill_syns_reuses_cached_chunks_when_the_daily_log_grows() {
+    let tmp = tempdir().expect("tempdir");
+    let moon_home = tmp.path().join("moon");
+    let source = write_daily_memory(&moon_home);
+
+    let (base_url, requests) = serve_json(|path, body| {
+        let reply = if path == "/api/show" {
+            serde_json::json!({"model_info": {"llama.context_length": 8192}})
+        } else {
+            serde_json::json!({
+                "model": body["model"],
+                "message": {"role": "assistant", "content": SYNTHESIS_REPLY},
+                "done": true
+            })
+        };
+        (200, reply)
+    });
+    let daily_log = |lines: usize| {
+        let mut text = String::from("# Daily Memory\n\n### Rules\n");
+        for idx in 0..lines {
+            text.push_str(&format!("- decision {idx:04}: {}\n", "x".repeat(980)));
+        }
+        text
+    };
+    let run = || {
+        assert_cmd::cargo::cargo_bin_cmd!("moon")
+            .current_dir(tmp.path())
+            .env("MOON_HOME", &moon_home)
+            .env("MOON_RESIDENTIAL_TIMEZONE", "UTC")
+            .env("MOON_WISDOM_PROVIDER", "ollama")
+            .env("MOON_WISDOM_MODEL", "ollama:qwen2.5:7b")
+            .env(
+                "MOON_OLLAMA_BASE_URL",
+                base_url.trim_start_matches("http://"),
+            )
+            .env_remove("OPENAI_API_KEY")
+            .env_remove("AI_API_KEY")
+            .args(["distill", "--mode", "syns", "--file"])
+            .arg(&source)
+            .assert()
+            .success();
+    };
+    let chat_calls = || {
+        requests
+            .lock()
+            .expect("lock")
+            .iter()
+            .filter(|request| request.path == "/api/chat")
+            .count()
+    };
+
+    fs::write(&source, daily_log(150)).expect("write daily log");
+    run();
+    let first_calls = chat_calls();
+    assert!(first_calls >= 3, "first run chat calls: {first_calls}");
+
+    // MEMORY.md changed during the first run; the unchanged leading chunks still hit the cache.
+    fs::write(&source, daily_log(200)).expect("grow daily log");
+    run();
+    let second_calls = chat_calls() - first_calls;
+    assert!(
+        second_calls < first_calls,
+        "second run chat calls: {second_calls}, first: {first_calls}"
+    );
+
+    let audit = fs::read_to_string(moon_home.join("moon/logs/audit.log")).expect("read audit");
+    let cached = audit
+        .lines()
+        .filter(|line| {
+            line.contains("\"phase\":\"distill-chunk\"") && line.contains("\"status\":\"cached\"")
+        })
+        .count();
+    assert!(cached >= 2, "audit: {audit}");
+}
@@ -270,3 +270,194 @@ fn moon_embed_manual_ignores_watcher_cooldown_and_keeps_watcher_clock() {
         .success()
         .stdout(contains("embed.skip_reason=cooldown"));
 }
+
+fn serve_embeddings(
+    listener: std::net::TcpListener,
+    requests: usize,
+) -> std::thread::JoinHandle<Vec<serde_json::Value>> {
+    use std::io::{BufRead, BufReader, Read, Write};
+    std::thread::spawn(move || {
+        let mut bodies = Vec::new();
+        for _ in 0..requests {
+            let (stream, _) = listener.accept().expect("accept");
+            let mut reader = BufReader::new(stream);
+            let mut content_length = 0usize;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).expect("read header");
+                if line.trim().is_empty() {
+                    break;
+                }
+                if let Some(value) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                    content_length = value.trim().parse().expect("content length");
+                }
+            }
+            let mut body = vec![0u8; content_length];
+            reader.read_exact(&mut body).expect("read body");
+            let body: serde_json::Value = serde_json::from_slice(&body).expect("json body");
+            let inputs = body["input"].as_array().map(Vec::len).unwrap_or(0);
+            let data = (0..inputs)
+                .map(|i| serde_json::json!({"index": i, "embedding": [i as f64, 1.0, 0.5]}))
+                .collect::<Vec<_>>();
+            let payload = serde_json::json!({ "data": data }).to_string();
+            let response = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{payload}",
+                payload.len()
+            );
+            reader
+                .get_mut()
+                .write_all(response.as_bytes())
+                .expect("respond");
+            bodies.push(body);
+        }
+        bodies
+    })
+}
+
+#[test]
+fn moon_embed_remote_provider_batches_sections_and_reembeds_on_model_change() {
+    let tmp = tempdir().expect("tempdir");
+    let moon_home = tmp.path().join("moon");
+    let mlib_dir = moon_home.join("archives/mlib");
+    fs::create_dir_all(&mlib_dir).expect("mkdir mlib");
+    fs::create_dir_all(moon_home.join("memory")).expect("mkdir memory");
+
+    for name in ["a", "b"] {
+        fs::write(
+            mlib_dir.join(format!("{name}.md")),
+            format!("---\nmoon_archive_projection: 2\n---\n# {name}\n\n## Timeline\n| row |\n\n## Conversation\n- [user] hello {name}\n"),
+        )
+        .expect("write projection");
+    }
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind");
+    let base_url = format!("http://{}", listener.local_addr().expect("addr"));
+    let server = serve_embeddings(listener, 4);
+
+    let run = |model: &str| {
+        assert_cmd::cargo::cargo_bin_cmd!("moon")
+            .current_dir(tmp.path())
+            .env("MOON_HOME", &moon_home)
+            .env("QMD_BIN", tmp.path().join("missing-qmd"))
+            .env("MOON_EMBED_PROVIDER", "openai-compatible")
+            .env("MOON_EMBED_MODEL", model)
+            .env("MOON_EMBED_BASE_URL", &base_url)
+            .env("MOON_EMBED_REQUESTS_PER_MINUTE", "0")
+            .env("AI_API_KEY", "test-key")
+            .arg("--json")
+            .arg("embed")
+            .args(["--max-docs", "5"])
+            .assert()
+            .success()
+    };
+
+    run("embed-a")
+        .stdout(contains("embed.provider=openai-compatible"))
+        .stdout(contains("embed.capability=remote"))
+        .stdout(contains("embed.embedded_docs=2"))
+        .stdout(contains("embed.requests=2"))
+        .stdout(contains("embed.vectors=6"))
+        .stdout(contains("embed.model_changed=false"))
+        .stdout(contains("embed.pending_after=0"));
+
+    run("embed-b")
+        .stdout(contains("embed.model_changed=true"))
+        .stdout(contains("embed.pending_before=2"))
+        .stdout(contains("embed.embedded_docs=2"))
+        .stdout(contains("embed.vectors=6"));
+
+    let bodies = server.join().expect("server");
+    assert_eq!(bodies[0]["model"], "embed-a");
+    assert_eq!(bodies[0]["input"].as_array().map(Vec::len), Some(3));
+    assert_eq!(bodies[3]["model"], "embed-b");
+
+    let meta = fs::read_to_string(moon_home.join("archives/vectors/meta.json")).expect("meta");
+    assert!(meta.contains("\"model\": \"embed-b\""));
+    assert!(meta.contains("\"dimensions\": 3"));
+    let index = fs::read_to_string(moon_home.join("archives/vectors/index.jsonl")).expect("index");
+    assert_eq!(index.lines().count(), 6);
+    assert!(!index.contains("embed-a"));
+}
+
+#[test]
+fn moon_embed_verify_reports_coverage_and_rebuild_reembeds_everything() {
+    let tmp = tempdir().expect("tempdir");
+    let moon_home = tmp.path().join("moon");
+    let mlib_dir = moon_home.join("archives/mlib");
+    let raw_dir = moon_home.join("archives/raw");
+    fs::create_dir_all(&mlib_dir).expect("mkdir mlib");
+    fs::create_dir_all(&raw_dir).expect("mkdir raw");
+    fs::create_dir_all(moon_home.join("memory")).expect("mkdir memory");
+
+    let mut ledger = String::new();
+    for name in ["a", "b"] {
+        let archive = raw_dir.join(format!("{name}.jsonl"));
+        let projection = mlib_dir.join(format!("{name}.md"));
+        fs::write(&archive, "{}\n").expect("write archive");
+        fs::write(
+            &projection,
+            format!("# {name}\n\n## Conversation\n- [user] hello {name}\n"),
+        )
+        .expect("write projection");
+        ledger.push_str(&format!(
+            "{}\n",
+            serde_json::json!({
+                "session_id": name,
+                "source_path": "/tmp/source.jsonl",
+                "archive_path": archive.display().to_string(),
+                "projection_path": projection.display().to_string(),
+                "content_hash": name,
+                "created_at_epoch_secs": 1,
+                "indexed_collection": "history",
+                "indexed": true
+            })
+        ));
+    }
+    fs::write(moon_home.join("archives/ledger.jsonl"), ledger).expect("write ledger");
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind");
+    let base_url = format!("http://{}", listener.local_addr().expect("addr"));
+    let server = serve_embeddings(listener, 3);
+
+    let embed = |args: &[&str]| {
+        assert_cmd::cargo::cargo_bin_cmd!("moon")
+            .current_dir(tmp.path())
+            .env("MOON_HOME", &moon_home)
+            .env("QMD_BIN", tmp.path().join("missing-qmd"))
+            .env("MOON_EMBED_PROVIDER", "openai-compatible")
+            .env("MOON_EMBED_MODEL", "embed-a")
+            .env("MOON_EMBED_BASE_URL", &base_url)
+            .env("MOON_EMBED_REQUESTS_PER_MINUTE", "0")
+            .env("AI_API_KEY", "test-key")
+            .arg("--json")
+            .arg("embed")
+            .args(args)
+            .assert()
+    };
+
+    embed(&["--max-docs", "1"])
+        .success()
+        .stdout(contains("embed.embedded_docs=1"));
+
+    embed(&["--verify"])
+        .code(2)
+        .stdout(contains("verify.projections=2"))
+        .stdout(contains("verify.embedded=1"))
+        .stdout(contains("verify.missing=1"))
+        .stdout(contains("verify.coverage=50.0%"))
+        .stdout(contains("verify.missing_path="));
+
+    embed(&["--rebuild", "--max-docs", "1"])
+        .success()
+        .stdout(contains("embed.rebuild=true"))
+        .stdout(contains("embed.pending_before=2"))
+        .stdout(contains("embed.embedded_docs=2"))
+        .stdout(contains("embed.vectors=4"));
+
+    embed(&["--verify"])
+        .success()
+        .stdout(contains("verify.embedded=2"))
+        .stdout(contains("verify.coverage=100.0%"));
+
+    assert_eq!(server.join().expect("server").len(), 3);
+}
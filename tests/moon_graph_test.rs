@@ -0,0 +1,31 @@
+use std::fs;
+use tempfile::tempdir;
+
+#[test]
+fn moon_graph_query_lists_edges_for_entity() {
+    let tmp = tempdir().expect("tempdir");
+    let moon_home = tmp.path().join("moon");
+    fs::create_dir_all(moon_home.join("graph")).expect("mkdir graph");
+    fs::create_dir_all(moon_home.join("moon/logs")).expect("mkdir logs");
+    fs::write(
+        moon_home.join("graph/edges.jsonl"),
+        concat!(
+            "{\"subject\":\"s1\",\"subject_kind\":\"session\",\"relation\":\"mentions\",\"object\":\"billing-api\",\"object_kind\":\"service\",\"session_id\":\"s1\",\"source_path\":\"/tmp/mlib/s1.md\",\"at_epoch_secs\":10}\n",
+            "{\"subject\":\"@alice\",\"subject_kind\":\"person\",\"relation\":\"works_on\",\"object\":\"billing-api\",\"object_kind\":\"service\",\"session_id\":\"s1\",\"source_path\":\"/tmp/mlib/s1.md\",\"at_epoch_secs\":10}\n",
+            "{\"subject\":\"s2\",\"subject_kind\":\"session\",\"relation\":\"mentions\",\"object\":\"src/lib.rs\",\"object_kind\":\"file\",\"session_id\":\"s2\",\"source_path\":\"/tmp/mlib/s2.md\",\"at_epoch_secs\":20}\n",
+        ),
+    )
+    .expect("write edges");
+
+    let assert = assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("MOON_HOME", &moon_home)
+        .args(["graph", "query", "billing"])
+        .assert()
+        .success();
+
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout.contains("edges=2"));
+    assert!(stdout.contains("@alice(person) -[works_on]-> billing-api(service)"));
+    assert!(!stdout.contains("src/lib.rs"));
+}
@@ -2,9 +2,21 @@
 
 use predicates::str::contains;
 use std::fs;
+use std::path::Path;
 use std::time::{SystemTime, UNIX_EPOCH};
 use tempfile::tempdir;
 
+fn write_fake_qmd(bin_path: &Path, collection_list: &str) {
+    let script = format!(
+        "#!/usr/bin/env bash\nif [[ \"${{1:-}}\" == \"--version\" ]]; then echo 'qmd 1.2.3'; exit 0; fi\nif [[ \"${{1:-}}\" == \"collection\" && \"${{2:-}}\" == \"list\" ]]; then cat <<'EOF'\n{collection_list}\nEOF\nexit 0; fi\nexit 0\n"
+    );
+    fs::write(bin_path, script).expect("write qmd");
+    use std::os::unix::fs::PermissionsExt;
+    let mut perms = fs::metadata(bin_path).expect("metadata").permissions();
+    perms.set_mode(0o755);
+    fs::set_permissions(bin_path, perms).expect("chmod");
+}
+
 #[test]
 fn moon_health_treats_fresh_heartbeat_as_activity_when_lock_is_missing() {
     let tmp = tempdir().expect("tempdir");
@@ -31,9 +43,14 @@ fn moon_health_treats_fresh_heartbeat_as_activity_when_lock_is_missing() {
     )
     .expect("write state");
 
+    let qmd = tmp.path().join("qmd");
+    write_fake_qmd(&qmd, "");
+
     assert_cmd::cargo::cargo_bin_cmd!("moon")
         .current_dir(tmp.path())
         .env("MOON_HOME", &moon_home)
+        .env("QMD_BIN", &qmd)
+        .env("QMD_DB", tmp.path().join("missing.sqlite"))
         .arg("health")
         .assert()
         .success()
@@ -44,3 +61,324 @@ fn moon_health_treats_fresh_heartbeat_as_activity_when_lock_is_missing() {
             "daemon may still be running without a linked lockfile",
         ));
 }
+
+#[test]
+fn moon_health_reports_qmd_version_db_and_collection_mask() {
+    let tmp = tempdir().expect("tempdir");
+    let moon_home = tmp.path().join("workspace");
+    let archives_dir = moon_home.join("archives");
+    fs::create_dir_all(&archives_dir).expect("mkdir archives");
+    fs::create_dir_all(moon_home.join("moon/logs")).expect("mkdir logs");
+    fs::write(
+        archives_dir.join("ledger.jsonl"),
+        "{\"session_id\":\"s1\",\"source_path\":\"/s1.jsonl\",\"archive_path\":\"/raw/s1.jsonl\",\"projection_path\":null,\"content_hash\":\"h\",\"created_at_epoch_secs\":1,\"indexed_collection\":\"history\",\"indexed\":true}\n",
+    )
+    .expect("write ledger");
+    let qmd_db = tmp.path().join("index.sqlite");
+    fs::write(&qmd_db, b"").expect("write qmd db");
+    let qmd = tmp.path().join("qmd");
+    write_fake_qmd(
+        &qmd,
+        "Collections (1):\n\nhistory (qmd://history/)\n  Pattern:  **/*.md\n  Files:    42\n",
+    );
+
+    let assert = assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("MOON_HOME", &moon_home)
+        .env("QMD_BIN", &qmd)
+        .env("QMD_DB", &qmd_db)
+        .arg("health")
+        .assert()
+        .code(2);
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout.contains("qmd.version=qmd 1.2.3"));
+    assert!(stdout.contains("qmd.db=ok"));
+    assert!(stdout.contains("qmd.collection.history.mask=unexpected (found **/*.md"));
+    assert!(stdout.contains("qmd.collection.history.documents=42"));
+
+    fs::remove_file(&qmd_db).expect("remove qmd db");
+    let assert = assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("MOON_HOME", &moon_home)
+        .env("QMD_BIN", &qmd)
+        .env("QMD_DB", &qmd_db)
+        .arg("health")
+        .assert()
+        .code(2);
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout.contains("qmd.db=missing"));
+}
+
+#[test]
+fn moon_health_flags_state_and_ledger_timestamps_in_the_future() {
+    let tmp = tempdir().expect("tempdir");
+    let moon_home = tmp.path().join("workspace");
+    let archives_dir = moon_home.join("archives");
+    let state_dir = moon_home.join("moon").join("state");
+    fs::create_dir_all(&archives_dir).expect("mkdir archives");
+    fs::create_dir_all(moon_home.join("moon/logs")).expect("mkdir logs");
+    fs::create_dir_all(&state_dir).expect("mkdir state");
+
+    let future = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("clock after epoch")
+        .as_secs()
+        + 86_400;
+    fs::write(
+        state_dir.join("moon_state.json"),
+        format!(
+            "{{\"last_heartbeat_epoch_secs\": {future}, \"last_compaction_trigger_epoch_secs\": {future}}}\n"
+        ),
+    )
+    .expect("write state");
+    fs::write(
+        archives_dir.join("ledger.jsonl"),
+        format!(
+            "{{\"session_id\":\"s1\",\"source_path\":\"/s1.jsonl\",\"archive_path\":\"/raw/s1.jsonl\",\"projection_path\":null,\"content_hash\":\"h\",\"created_at_epoch_secs\":{future},\"indexed_collection\":\"history\",\"indexed\":true}}\n"
+        ),
+    )
+    .expect("write ledger");
+    let qmd = tmp.path().join("qmd");
+    write_fake_qmd(&qmd, "");
+
+    let assert = assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("MOON_HOME", &moon_home)
+        .env("QMD_BIN", &qmd)
+        .arg("health")
+        .assert()
+        .code(2);
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout.contains("clock.state.last_heartbeat=future by"));
+    assert!(stdout.contains("clock.state.last_compaction_trigger=future by"));
+    assert!(stdout.contains("clock.archives=future ledger_records=1"));
+}
+
+#[test]
+fn moon_health_explains_build_mismatch_with_daemon_build_metadata() {
+    let tmp = tempdir().expect("tempdir");
+    let moon_home = tmp.path().join("moon");
+    let logs_dir = moon_home.join("moon/logs");
+    fs::create_dir_all(&logs_dir).expect("mkdir logs");
+    fs::create_dir_all(moon_home.join("archives")).expect("mkdir archives");
+    let lock = serde_json::json!({
+        "pid": std::process::id(),
+        "started_at_epoch_secs": 1,
+        "build_uuid": "old-build",
+        "moon_home": moon_home.display().to_string(),
+        "build": {"version": "0.0.1", "git_sha": "deadbeef", "build_uuid": "old-build"}
+    });
+    fs::write(logs_dir.join("moon-watch.daemon.lock"), lock.to_string()).expect("write lock");
+
+    let output = assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(&moon_home)
+        .env("MOON_HOME", &moon_home)
+        .env("QMD_BIN", tmp.path().join("missing-qmd"))
+        .arg("health")
+        .assert()
+        .code(2)
+        .get_output()
+        .stdout
+        .clone();
+    let stdout = String::from_utf8_lossy(&output);
+    assert!(stdout.contains("daemon.build=version=0.0.1 git_sha=deadbeef build_uuid=old-build"));
+    assert!(stdout.contains("daemon version=0.0.1 git_sha=deadbeef, current version="));
+
+    let version = assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .args(["version", "--json"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let info: serde_json::Value = serde_json::from_slice(&version).expect("version json");
+    assert_eq!(info["version"], env!("CARGO_PKG_VERSION"));
+    assert!(
+        info["build_uuid"]
+            .as_str()
+            .is_some_and(|uuid| !uuid.is_empty())
+    );
+    assert!(
+        info["distill_providers"]
+            .as_array()
+            .is_some_and(|providers| providers.iter().any(|p| p == "anthropic"))
+    );
+}
+
+#[test]
+fn moon_health_reports_and_repairs_dangling_references() {
+    let tmp = tempdir().expect("tempdir");
+    let moon_home = tmp.path().join("workspace");
+    let archives_dir = moon_home.join("archives");
+    let state_dir = moon_home.join("moon").join("state");
+    fs::create_dir_all(archives_dir.join("raw")).expect("mkdir raw");
+    fs::create_dir_all(moon_home.join("moon/logs")).expect("mkdir logs");
+    fs::create_dir_all(moon_home.join("continuity")).expect("mkdir continuity");
+    fs::create_dir_all(&state_dir).expect("mkdir state");
+
+    let live = archives_dir.join("raw/live.jsonl");
+    fs::write(&live, "{}\n").expect("write live archive");
+    let gone = archives_dir.join("raw/gone.jsonl");
+    let row = |archive: &Path| {
+        format!(
+            "{{\"session_id\":\"s1\",\"source_path\":\"/s1.jsonl\",\"archive_path\":\"{}\",\"projection_path\":null,\"content_hash\":\"h\",\"created_at_epoch_secs\":1,\"indexed_collection\":\"history\",\"indexed\":true}}\n",
+            archive.display()
+        )
+    };
+    fs::write(
+        archives_dir.join("ledger.jsonl"),
+        format!("{}{}", row(&live), row(&gone)),
+    )
+    .expect("write ledger");
+    fs::write(
+        moon_home.join("continuity/channel_archive_map.json"),
+        serde_json::json!({
+            "agent:main:discord:channel:ops": {
+                "channel_key": "agent:main:discord:channel:ops",
+                "source_path": "/s1.jsonl",
+                "archive_path": gone.display().to_string(),
+                "updated_at_epoch_secs": 1
+            }
+        })
+        .to_string(),
+    )
+    .expect("write channel map");
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("clock after epoch")
+        .as_secs();
+    fs::write(
+        state_dir.join("moon_state.json"),
+        serde_json::json!({
+            "last_heartbeat_epoch_secs": now,
+            "distilled_archives": { gone.display().to_string(): 1 }
+        })
+        .to_string(),
+    )
+    .expect("write state");
+    let qmd = tmp.path().join("qmd");
+    write_fake_qmd(&qmd, "");
+
+    let health = |repair: bool| {
+        let mut cmd = assert_cmd::cargo::cargo_bin_cmd!("moon");
+        cmd.current_dir(tmp.path())
+            .env("MOON_HOME", &moon_home)
+            .env("QMD_BIN", &qmd)
+            .arg("health");
+        if repair {
+            cmd.arg("--repair");
+        }
+        let assert = cmd.assert();
+        String::from_utf8_lossy(&assert.get_output().stdout).to_string()
+    };
+
+    let stdout = health(false);
+    assert!(stdout.contains(
+        "consistency.findings=ledger-archive-missing=1,state-distilled-dangling=1,map-archive-missing=1"
+    ));
+    assert!(stdout.contains("[E015_DANGLING_REFERENCE] consistency kind=map-archive-missing key=agent:main:discord:channel:ops"));
+    assert!(stdout.contains("moon health --repair"));
+
+    let stdout = health(true);
+    assert!(stdout.contains(
+        "consistency.repair=ledger_removed=1 state_removed=1 map_repointed=0 map_removed=1"
+    ));
+    let ledger = fs::read_to_string(archives_dir.join("ledger.jsonl")).expect("read ledger");
+    assert!(ledger.contains("live.jsonl"));
+    assert!(!ledger.contains("gone.jsonl"));
+    let audit = fs::read_to_string(moon_home.join("moon/logs/audit.log")).expect("read audit");
+    assert!(audit.contains("consistency"));
+
+    let stdout = health(false);
+    assert!(stdout.contains("consistency.findings=none"));
+    assert!(!stdout.contains("E015_DANGLING_REFERENCE"));
+}
+
+#[test]
+fn moon_health_flags_and_repairs_world_readable_env_with_provider_keys() {
+    use std::os::unix::fs::PermissionsExt;
+    let tmp = tempdir().expect("tempdir");
+    let moon_home = tmp.path().join("workspace");
+    fs::create_dir_all(moon_home.join("archives")).expect("mkdir archives");
+    fs::create_dir_all(moon_home.join("moon/logs")).expect("mkdir logs");
+    let env_path = moon_home.join("moon/.env");
+    fs::write(&env_path, "OPENAI_API_KEY=sk-test\n").expect("write env");
+    fs::set_permissions(&env_path, fs::Permissions::from_mode(0o644)).expect("chmod");
+    let qmd = tmp.path().join("qmd");
+    write_fake_qmd(&qmd, "");
+
+    let moon = || {
+        let mut cmd = assert_cmd::cargo::cargo_bin_cmd!("moon");
+        cmd.current_dir(tmp.path())
+            .env("MOON_HOME", &moon_home)
+            .env("QMD_BIN", &qmd)
+            .env("QMD_DB", tmp.path().join("missing.sqlite"))
+            .env_remove("MOON_ENV_FIX_PERMISSIONS");
+        cmd
+    };
+
+    moon()
+        .arg("health")
+        .assert()
+        .stdout(contains("env.permissions=exposed (0644)"))
+        .stderr(contains(
+            "holds provider keys but is readable by other users",
+        ));
+    assert_eq!(
+        fs::metadata(&env_path)
+            .expect("metadata")
+            .permissions()
+            .mode()
+            & 0o777,
+        0o644
+    );
+
+    moon()
+        .args(["health", "--repair"])
+        .assert()
+        .stdout(contains("env.permissions=restricted (0644 -> 0600)"));
+    assert_eq!(
+        fs::metadata(&env_path)
+            .expect("metadata")
+            .permissions()
+            .mode()
+            & 0o777,
+        0o600
+    );
+
+    moon()
+        .arg("health")
+        .assert()
+        .stdout(contains("env.permissions=ok (0600)"));
+}
+
+#[test]
+fn moon_env_fix_permissions_restricts_env_on_load() {
+    use std::os::unix::fs::PermissionsExt;
+    let tmp = tempdir().expect("tempdir");
+    let moon_home = tmp.path().join("workspace");
+    fs::create_dir_all(moon_home.join("moon")).expect("mkdir moon");
+    let env_path = moon_home.join("moon/.env");
+    fs::write(
+        &env_path,
+        "GEMINI_API_KEY=test-key\nMOON_ENV_FIX_PERMISSIONS=true\n",
+    )
+    .expect("write env");
+    fs::set_permissions(&env_path, fs::Permissions::from_mode(0o640)).expect("chmod");
+
+    assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("MOON_HOME", &moon_home)
+        .arg("version")
+        .assert()
+        .success()
+        .stderr(contains("restricted"));
+    assert_eq!(
+        fs::metadata(&env_path)
+            .expect("metadata")
+            .permissions()
+            .mode()
+            & 0o777,
+        0o600
+    );
+}
@@ -110,7 +110,7 @@ fn moon_index_registers_history_collection() {
     let log = fs::read_to_string(&log_path).expect("read log");
     assert!(log.contains("collection add"));
     assert!(log.contains("--name history"));
-    assert!(log.contains("--mask mlib/**/*.md"));
+    assert!(log.contains("--mask mlib/*.md"));
 }
 
 #[test]
@@ -136,7 +136,7 @@ fn moon_index_updates_when_collection_already_exists() {
 
     let log = fs::read_to_string(&log_path).expect("read log");
     assert!(log.contains("collection add"));
-    assert!(log.contains("--mask mlib/**/*.md"));
+    assert!(log.contains("--mask mlib/*.md"));
     assert!(log.contains("update"));
 }
 
@@ -166,6 +166,260 @@ fn moon_index_recreates_collection_when_mask_mismatches() {
     assert!(log.contains("collection add"));
     assert!(log.contains("collection list"));
     assert!(log.contains("collection remove history"));
-    assert!(log.contains("--mask mlib/**/*.md"));
+    assert!(log.contains("--mask mlib/*.md"));
     assert!(!log.contains("update"));
 }
+
+#[test]
+#[cfg(not(windows))]
+fn moon_index_reindex_all_recreates_collection_and_reprojects() {
+    let tmp = tempdir().expect("tempdir");
+    let archives_dir = tmp.path().join("archives");
+    fs::create_dir_all(archives_dir.join("raw")).expect("mkdir raw");
+    fs::create_dir_all(archives_dir.join("mlib")).expect("mkdir mlib");
+    let archive = archives_dir.join("raw/sess-a.jsonl");
+    fs::write(&archive, "{\"messages\":[\"reindex me\"]}\n").expect("write archive");
+    let projection = archives_dir.join("mlib/sess-a.md");
+    fs::write(&projection, "stale projection\n").expect("write projection");
+    fs::write(
+        archives_dir.join("ledger.jsonl"),
+        format!(
+            "{{\"session_id\":\"sess-a\",\"source_path\":\"{}\",\"archive_path\":\"{}\",\"projection_path\":\"{}\",\"content_hash\":\"a\",\"created_at_epoch_secs\":1700000000,\"indexed_collection\":\"history\",\"indexed\":true}}\n",
+            archive.display(),
+            archive.display(),
+            projection.display()
+        ),
+    )
+    .expect("write ledger");
+
+    let fake_qmd = tmp.path().join("qmd");
+    let log_path = tmp.path().join("qmd.log");
+    write_fake_qmd(&fake_qmd, &log_path);
+
+    let assert = assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("MOON_ARCHIVES_DIR", &archives_dir)
+        .env("QMD_BIN", &fake_qmd)
+        .args(["index", "--reindex-all", "--keep-prev"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout.contains("projection_diff.changed=1 projection_diff.unchanged=0"));
+    assert!(stdout.contains("sections_added=Timeline"));
+    let prev = archives_dir.join("mlib-prev/sess-a.prev.md");
+    assert_eq!(
+        fs::read_to_string(&prev).expect("read prev projection"),
+        "stale projection\n"
+    );
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(stderr.contains("reindex [1/3] remove collection=history removed"));
+    assert!(stderr.contains("reindex [2/3] reproject scanned=1 created=1 failed=0"));
+    assert!(stderr.contains("reindex [3/3] add collection=history"));
+
+    let log = fs::read_to_string(&log_path).expect("read log");
+    let remove_at = log
+        .find("collection remove history")
+        .expect("remove logged");
+    let add_at = log.find("collection add").expect("add logged");
+    assert!(remove_at < add_at);
+    let rebuilt = fs::read_to_string(&projection).expect("read projection");
+    assert_ne!(rebuilt, "stale projection\n");
+}
+
+#[test]
+#[cfg(not(windows))]
+fn moon_index_limit_resumes_layout_and_backfill_from_cursor() {
+    let tmp = tempdir().expect("tempdir");
+    let archives_dir = tmp.path().join("archives");
+    fs::create_dir_all(archives_dir.join("raw")).expect("mkdir raw");
+    let mut ledger = String::new();
+    for name in ["sess-a", "sess-b", "sess-c"] {
+        let archive = archives_dir.join(format!("raw/{name}.jsonl"));
+        fs::write(&archive, "{\"messages\":[\"resume me\"]}\n").expect("write archive");
+        ledger.push_str(&format!(
+            "{{\"session_id\":\"{name}\",\"source_path\":\"{}\",\"archive_path\":\"{}\",\"projection_path\":null,\"content_hash\":\"{name}\",\"created_at_epoch_secs\":1700000000,\"indexed_collection\":\"history\",\"indexed\":true}}\n",
+            archive.display(),
+            archive.display()
+        ));
+    }
+    fs::write(archives_dir.join("ledger.jsonl"), ledger).expect("write ledger");
+
+    let fake_qmd = tmp.path().join("qmd");
+    let log_path = tmp.path().join("qmd.log");
+    write_fake_qmd(&fake_qmd, &log_path);
+    let run = || {
+        let assert = assert_cmd::cargo::cargo_bin_cmd!("moon")
+            .current_dir(tmp.path())
+            .env("MOON_ARCHIVES_DIR", &archives_dir)
+            .env("QMD_BIN", &fake_qmd)
+            .args(["index", "--name", "history", "--limit", "2"])
+            .assert()
+            .success();
+        String::from_utf8_lossy(&assert.get_output().stdout).to_string()
+    };
+
+    let first = run();
+    assert!(first.contains("layout_migration.next_cursor=2"));
+    assert!(first.contains("projection_backfill=skipped reason=layout-migration-incomplete"));
+    assert!(archives_dir.join("migration-cursor.json").exists());
+
+    let second = run();
+    assert!(second.contains("layout_migration.resumed_from=2"));
+    assert!(second.contains("layout_migration.next_cursor=done"));
+    assert!(second.contains("projection_backfill.next_cursor=2"));
+    assert!(archives_dir.join("mlib/sess-a.md").exists());
+    assert!(!archives_dir.join("mlib/sess-c.md").exists());
+
+    let third = run();
+    assert!(third.contains("projection_backfill.resumed_from=2"));
+    assert!(third.contains("projection_backfill.next_cursor=done"));
+    assert!(archives_dir.join("mlib/sess-c.md").exists());
+    assert!(!archives_dir.join("migration-cursor.json").exists());
+}
+
+#[test]
+#[cfg(not(windows))]
+fn moon_index_reproject_writes_projection_json_sidecar_when_enabled() {
+    let tmp = tempdir().expect("tempdir");
+    let archives_dir = tmp.path().join("archives");
+    fs::create_dir_all(archives_dir.join("raw")).expect("mkdir raw");
+    fs::create_dir_all(archives_dir.join("mlib")).expect("mkdir mlib");
+    let archive = archives_dir.join("raw/sess-a.jsonl");
+    fs::write(
+        &archive,
+        "{\"type\":\"message\",\"timestamp\":\"2026-02-18T10:00:00Z\",\"message\":{\"role\":\"user\",\"content\":[{\"type\":\"text\",\"text\":\"sidecar decision recorded\"}]}}\n",
+    )
+    .expect("write archive");
+    let projection = archives_dir.join("mlib/sess-a.md");
+    fs::write(
+        archives_dir.join("ledger.jsonl"),
+        format!(
+            "{{\"session_id\":\"sess-a\",\"source_path\":\"{}\",\"archive_path\":\"{}\",\"projection_path\":\"{}\",\"content_hash\":\"a\",\"created_at_epoch_secs\":1700000000,\"indexed_collection\":\"history\",\"indexed\":true}}\n",
+            archive.display(),
+            archive.display(),
+            projection.display()
+        ),
+    )
+    .expect("write ledger");
+
+    let fake_qmd = tmp.path().join("qmd");
+    write_fake_qmd(&fake_qmd, &tmp.path().join("qmd.log"));
+
+    assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("MOON_ARCHIVES_DIR", &archives_dir)
+        .env("QMD_BIN", &fake_qmd)
+        .env("MOON_PROJECTION_JSON_SIDECAR", "true")
+        .args(["index", "--reindex-all"])
+        .assert()
+        .success();
+
+    assert!(projection.exists());
+    let sidecar: serde_json::Value = serde_json::from_str(
+        &fs::read_to_string(archives_dir.join("mlib/sess-a.projection.json"))
+            .expect("read sidecar"),
+    )
+    .expect("sidecar is json");
+    assert_eq!(sidecar["session_id"], "sess-a");
+    assert_eq!(sidecar["archive_path"], archive.display().to_string());
+    assert_eq!(sidecar["projection"]["message_count"], 1);
+    let entry = &sidecar["projection"]["entries"][0];
+    assert_eq!(entry["role"], "user");
+    assert_eq!(entry["content"], "sidecar decision recorded");
+    assert_eq!(entry["source_line"], 1);
+}
+
+#[test]
+fn moon_index_reproject_marks_renamed_session_archive_as_duplicate() {
+    let tmp = tempdir().expect("tempdir");
+    let archives_dir = tmp.path().join("archives");
+    fs::create_dir_all(archives_dir.join("raw")).expect("mkdir raw");
+    fs::create_dir_all(archives_dir.join("mlib")).expect("mkdir mlib");
+
+    let message = |role: &str, text: &str| {
+        format!(
+            "{{\"type\":\"message\",\"timestamp\":\"2026-02-18T10:00:00Z\",\"message\":{{\"role\":\"{role}\",\"content\":[{{\"type\":\"text\",\"text\":\"{text}\"}}]}}}}\n"
+        )
+    };
+    let conversation = [
+        message(
+            "user",
+            "Can you move the nightly backup job from the cron host to the new scheduler?",
+        ),
+        message(
+            "assistant",
+            "Sure. I moved the backup job, kept the retention at fourteen days, and added an alert when a run takes longer than an hour.",
+        ),
+        message(
+            "user",
+            "Great, also make sure the restore drill runs every Friday against the staging database.",
+        ),
+    ]
+    .concat();
+    let original = archives_dir.join("raw/sess-a.jsonl");
+    let renamed = archives_dir.join("raw/sess-b.jsonl");
+    let unrelated = archives_dir.join("raw/sess-c.jsonl");
+    fs::write(&original, &conversation).expect("write original");
+    // OpenClaw renamed the session: same transcript under a new session id.
+    fs::write(&renamed, &conversation).expect("write renamed");
+    fs::write(
+        &unrelated,
+        [
+            message(
+                "user",
+                "Draft a release note for the tokenizer upgrade and the new recall deadline option.",
+            ),
+            message(
+                "assistant",
+                "Here is a draft covering the tokenizer switch to cl100k, the recall deadline setting, and degraded results when qmd is slow.",
+            ),
+        ]
+        .concat(),
+    )
+    .expect("write unrelated");
+
+    let ledger = archives_dir.join("ledger.jsonl");
+    let row = |session: &str, archive: &Path, created: u64| {
+        format!(
+            "{{\"session_id\":\"{session}\",\"source_path\":\"{}\",\"archive_path\":\"{}\",\"projection_path\":null,\"content_hash\":\"{session}\",\"created_at_epoch_secs\":{created},\"indexed_collection\":\"history\",\"indexed\":true}}\n",
+            archive.display(),
+            archive.display()
+        )
+    };
+    fs::write(
+        &ledger,
+        [
+            row("sess-a", &original, 1_700_000_000),
+            row("sess-b", &renamed, 1_700_000_600),
+            row("sess-c", &unrelated, 1_700_001_200),
+        ]
+        .concat(),
+    )
+    .expect("write ledger");
+
+    let fake_qmd = tmp.path().join("qmd");
+    write_fake_qmd(&fake_qmd, &tmp.path().join("qmd.log"));
+
+    let assert = assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("MOON_ARCHIVES_DIR", &archives_dir)
+        .env("QMD_BIN", &fake_qmd)
+        .args(["index", "--reindex-all"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(
+        stdout.contains("projection_backfill.duplicates_marked=1"),
+        "{stdout}"
+    );
+
+    let rows = fs::read_to_string(&ledger)
+        .expect("read ledger")
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).expect("ledger row"))
+        .collect::<Vec<_>>();
+    assert!(rows.iter().all(|row| row["projection_simhash"].is_string()));
+    assert!(rows[0].get("duplicate_of").is_none());
+    assert_eq!(rows[1]["duplicate_of"], original.display().to_string());
+    assert!(rows[2].get("duplicate_of").is_none());
+}
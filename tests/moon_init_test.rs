@@ -0,0 +1,89 @@
+use std::fs;
+use std::path::Path;
+use tempfile::tempdir;
+
+fn write_fake_bin(path: &Path, body: &str) {
+    fs::write(path, format!("#!/usr/bin/env bash\n{body}\n")).expect("write fake bin");
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mut perms = fs::metadata(path).expect("metadata").permissions();
+        perms.set_mode(0o755);
+        fs::set_permissions(path, perms).expect("chmod");
+    }
+}
+
+#[test]
+fn init_creates_workspace_and_templates_without_prompting() {
+    let tmp = tempdir().expect("tempdir");
+    let moon_home = tmp.path().join("workspace");
+    let fake_openclaw = tmp.path().join("openclaw");
+    write_fake_bin(&fake_openclaw, "exit 0");
+    let fake_qmd = tmp.path().join("qmd");
+    write_fake_bin(
+        &fake_qmd,
+        "if [ \"$1\" = \"--version\" ]; then echo 'qmd 1.2.3'; fi\nexit 0",
+    );
+
+    let init = || {
+        let mut cmd = assert_cmd::cargo::cargo_bin_cmd!("moon");
+        cmd.current_dir(tmp.path())
+            .env_remove("MOON_HOME")
+            .env_remove("MOON_CONFIG_PATH")
+            .env("HOME", tmp.path())
+            .env("OPENCLAW_BIN", &fake_openclaw)
+            .env("QMD_BIN", &fake_qmd)
+            .args(["init", "--yes", "--moon-home"])
+            .arg(&moon_home);
+        cmd
+    };
+
+    let output = init().assert().success().get_output().stdout.clone();
+    let stdout = String::from_utf8_lossy(&output);
+    assert!(stdout.contains(&format!("detect.openclaw={}", fake_openclaw.display())));
+    assert!(stdout.contains(&format!("detect.qmd={}", fake_qmd.display())));
+    assert!(stdout.contains("install=skipped"));
+    assert!(stdout.contains("export MOON_HOME="));
+
+    for dir in [
+        "archives",
+        "memory",
+        "moon/logs",
+        "moon/state",
+        "continuity",
+    ] {
+        assert!(moon_home.join(dir).is_dir(), "missing {dir}");
+    }
+    let moon_toml = fs::read_to_string(moon_home.join("moon/moon.toml")).expect("moon.toml");
+    assert!(moon_toml.contains("[watcher]"));
+    let env = fs::read_to_string(moon_home.join("moon/.env")).expect(".env");
+    assert!(env.contains(&format!("OPENCLAW_BIN={}\n", fake_openclaw.display())));
+    assert!(env.contains(&format!("MOON_HOME={}\n", moon_home.display())));
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = fs::metadata(moon_home.join("moon/.env"))
+            .expect("metadata")
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    // The written config loads cleanly.
+    assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(&moon_home)
+        .env("MOON_HOME", &moon_home)
+        .env_remove("MOON_CONFIG_PATH")
+        .arg("config")
+        .assert()
+        .success();
+
+    // Rerunning keeps edited files unless --force.
+    fs::write(moon_home.join("moon/moon.toml"), "# edited\n").expect("edit");
+    let output = init().assert().success().get_output().stdout.clone();
+    assert!(String::from_utf8_lossy(&output).contains("moon.toml status=kept"));
+    assert_eq!(
+        fs::read_to_string(moon_home.join("moon/moon.toml")).expect("moon.toml"),
+        "# edited\n"
+    );
+}
@@ -0,0 +1,166 @@
+#![cfg(not(windows))]
+use std::fs;
+use std::path::Path;
+use tempfile::tempdir;
+
+fn ledger_row(session: &str, archive: &Path, hash: &str) -> String {
+    format!(
+        "{{\"session_id\":\"{session}\",\"source_path\":\"/tmp/{session}.jsonl\",\"archive_path\":\"{}\",\"projection_path\":null,\"content_hash\":\"{hash}\",\"created_at_epoch_secs\":1700000000,\"indexed_collection\":\"history\",\"indexed\":true}}\n",
+        archive.display()
+    )
+}
+
+#[test]
+fn moon_ledger_compact_keeps_latest_live_rows_and_moves_rest_to_history() {
+    let tmp = tempdir().expect("tempdir");
+    let moon_home = tmp.path().join("moon");
+    let archives_dir = moon_home.join("archives");
+    fs::create_dir_all(archives_dir.join("raw")).expect("mkdir raw");
+    fs::create_dir_all(moon_home.join("moon/logs")).expect("mkdir logs");
+    let live = archives_dir.join("raw/sess-a.jsonl");
+    fs::write(&live, "{}\n").expect("write archive");
+    let gone = archives_dir.join("raw/sess-b.jsonl");
+    let ledger_path = archives_dir.join("ledger.jsonl");
+    let original = format!(
+        "{}{}{}",
+        ledger_row("sess-a", &live, "old"),
+        ledger_row("sess-b", &gone, "b"),
+        ledger_row("sess-a", &live, "new")
+    );
+    fs::write(&ledger_path, &original).expect("write ledger");
+
+    let dry_run = assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("MOON_HOME", &moon_home)
+        .args(["ledger", "compact", "--dry-run"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&dry_run.get_output().stdout);
+    assert!(stdout.contains("records_kept=1"));
+    assert_eq!(
+        fs::read_to_string(&ledger_path).expect("read ledger"),
+        original
+    );
+
+    let assert = assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("MOON_HOME", &moon_home)
+        .args(["ledger", "compact"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout.contains("records_before=3"));
+    assert!(stdout.contains("dropped_missing=1"));
+    assert!(stdout.contains("dropped_superseded=1"));
+
+    let ledger = fs::read_to_string(&ledger_path).expect("read ledger");
+    assert_eq!(ledger.lines().count(), 1);
+    assert!(ledger.contains("\"content_hash\":\"new\""));
+    let history =
+        fs::read_to_string(archives_dir.join("ledger-history.jsonl")).expect("read history");
+    assert!(history.contains("\"content_hash\":\"old\""));
+    assert!(history.contains("sess-b"));
+}
+
+#[test]
+fn moon_ledger_compact_refuses_ledger_leased_by_another_host() {
+    let tmp = tempdir().expect("tempdir");
+    let moon_home = tmp.path().join("moon");
+    let archives_dir = moon_home.join("archives");
+    fs::create_dir_all(archives_dir.join("raw")).expect("mkdir raw");
+    fs::create_dir_all(moon_home.join("moon/logs")).expect("mkdir logs");
+    let ledger_path = archives_dir.join("ledger.jsonl");
+    let original = ledger_row("sess-b", &archives_dir.join("raw/sess-b.jsonl"), "b");
+    fs::write(&ledger_path, &original).expect("write ledger");
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .expect("epoch")
+        .as_secs();
+    fs::write(
+        archives_dir.join("ledger.jsonl.lock"),
+        format!(
+            "{{\"pid\":4242,\"host\":\"other-host\",\"operation\":\"watch\",\"acquired_at_epoch_secs\":{now}}}\n"
+        ),
+    )
+    .expect("write foreign lease");
+
+    let assert = assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("MOON_HOME", &moon_home)
+        .env("HOSTNAME", "this-host")
+        .args(["ledger", "compact"])
+        .assert()
+        .failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(stderr.contains("concurrent write detected"), "{stderr}");
+    assert!(
+        stderr.contains("`watch` (pid 4242 on host other-host"),
+        "{stderr}"
+    );
+    assert!(stderr.contains("MOON_READ_ONLY=true"), "{stderr}");
+    assert_eq!(
+        fs::read_to_string(&ledger_path).expect("read ledger"),
+        original
+    );
+}
+
+#[test]
+fn moon_ledger_skip_distill_flags_archive_and_status_counts_it() {
+    let tmp = tempdir().expect("tempdir");
+    let moon_home = tmp.path().join("moon");
+    let archives_dir = moon_home.join("archives");
+    fs::create_dir_all(archives_dir.join("raw")).expect("mkdir raw");
+    fs::create_dir_all(moon_home.join("moon/logs")).expect("mkdir logs");
+    let noisy = archives_dir.join("raw/sess-logs.jsonl");
+    fs::write(&noisy, "{}\n").expect("write archive");
+    let ledger_path = archives_dir.join("ledger.jsonl");
+    fs::write(
+        &ledger_path,
+        format!(
+            "{}{}",
+            ledger_row("sess-logs", &noisy, "a"),
+            ledger_row("sess-chat", &archives_dir.join("raw/sess-chat.jsonl"), "b")
+        ),
+    )
+    .expect("write ledger");
+    let noisy_arg = noisy.display().to_string();
+
+    let assert = assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("MOON_HOME", &moon_home)
+        .args(["ledger", "skip-distill", "--archive", &noisy_arg])
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout.contains("distill_skip=true"), "{stdout}");
+    let ledger = fs::read_to_string(&ledger_path).expect("read ledger");
+    assert_eq!(ledger.matches("\"distill_skip\":true").count(), 1);
+
+    let status = assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("MOON_HOME", &moon_home)
+        .arg("status")
+        .assert();
+    let stdout = String::from_utf8_lossy(&status.get_output().stdout);
+    assert!(stdout.contains("distill_skipped.archives=1"), "{stdout}");
+    assert!(stdout.contains("distill_skipped.flagged=1"), "{stdout}");
+
+    assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("MOON_HOME", &moon_home)
+        .args(["ledger", "skip-distill", "--archive", &noisy_arg, "--clear"])
+        .assert()
+        .success();
+    let ledger = fs::read_to_string(&ledger_path).expect("read ledger");
+    assert!(!ledger.contains("distill_skip"), "{ledger}");
+
+    let missing = assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("MOON_HOME", &moon_home)
+        .args(["ledger", "skip-distill", "--archive", "/nowhere.jsonl"])
+        .assert()
+        .failure();
+    let stdout = String::from_utf8_lossy(&missing.get_output().stdout);
+    assert!(stdout.contains("no ledger record for archive"), "{stdout}");
+}
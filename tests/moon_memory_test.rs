@@ -0,0 +1,131 @@
+use std::fs;
+use tempfile::tempdir;
+
+#[test]
+fn moon_memory_diff_reports_changes_against_history_baseline() {
+    let tmp = tempdir().expect("tempdir");
+    let moon_home = tmp.path().join("moon");
+    let history = moon_home.join("memory/.history");
+    fs::create_dir_all(&history).expect("mkdir history");
+    fs::create_dir_all(moon_home.join("moon/logs")).expect("mkdir logs");
+
+    fs::write(
+        history.join("MEMORY-1000.md"),
+        "# MEMORY\n\n## Durable Decisions & Context\n- Default branch for moon is main.\n- Legacy deploy uses ftp uploads.\n",
+    )
+    .expect("write baseline");
+    fs::write(
+        moon_home.join("MEMORY.md"),
+        "# MEMORY\n\n## Durable Decisions & Context\n- Default branch for moon is develop.\n- User prefers concise answers.\n",
+    )
+    .expect("write memory");
+
+    let assert = assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("MOON_HOME", &moon_home)
+        .args(["memory", "diff", "--since", "7d"])
+        .assert()
+        .success();
+
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout.contains("summary added=1 modified=1 consolidated=0 removed=1"));
+    assert!(stdout.contains("+ User prefers concise answers."));
+    assert!(
+        stdout
+            .contains("~ Default branch for moon is main. -> Default branch for moon is develop.")
+    );
+}
+
+#[test]
+fn moon_memory_diff_rejects_invalid_window() {
+    let tmp = tempdir().expect("tempdir");
+    let moon_home = tmp.path().join("moon");
+    fs::create_dir_all(moon_home.join("moon/logs")).expect("mkdir logs");
+
+    assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("MOON_HOME", &moon_home)
+        .args(["memory", "diff", "--since", "soon"])
+        .assert()
+        .code(2);
+}
+
+#[test]
+fn moon_memory_inject_dry_run_renders_bounded_primer() {
+    let tmp = tempdir().expect("tempdir");
+    let moon_home = tmp.path().join("moon");
+    fs::create_dir_all(moon_home.join("moon/logs")).expect("mkdir logs");
+    fs::write(
+        moon_home.join("MEMORY.md"),
+        "# MEMORY\n\n## User Preferences\n- Keep answers concise.\n",
+    )
+    .expect("write memory");
+
+    let assert = assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("MOON_HOME", &moon_home)
+        .args(["memory", "inject", "agent:main:new", "--dry-run"])
+        .assert()
+        .success();
+
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout.contains("max_tokens=800"));
+    assert!(stdout.contains("primer: - Keep answers concise."));
+}
+
+#[test]
+fn moon_memory_export_and_import_round_trip_records() {
+    let tmp = tempdir().expect("tempdir");
+    let moon_home = tmp.path().join("moon");
+    fs::create_dir_all(moon_home.join("memory")).expect("mkdir memory");
+    fs::create_dir_all(moon_home.join("moon/logs")).expect("mkdir logs");
+    fs::write(
+        moon_home.join("MEMORY.md"),
+        "# MEMORY\n\n## User Preferences\n- Keep answers concise.\n- Use metric units.\n",
+    )
+    .expect("write memory");
+    fs::write(
+        moon_home.join("memory/2026-01-02.md"),
+        "## Session s1\n### Conversation\n**User:** deploy billing-api\n",
+    )
+    .expect("write daily");
+    let export_path = tmp.path().join("export.json");
+
+    let assert = assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("MOON_HOME", &moon_home)
+        .args(["memory", "export", "--format", "json", "--output"])
+        .arg(&export_path)
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout.contains("records=3"));
+    assert!(stdout.contains("daily_records=1"));
+
+    let exported: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&export_path).expect("read export"))
+            .expect("parse export");
+    let records = exported["records"].as_array().expect("records");
+    assert_eq!(records[0]["section"], "User Preferences");
+    assert_eq!(records[0]["source"], "MEMORY.md");
+    assert_eq!(records[2]["date"], "2026-01-02");
+    assert_eq!(records[2]["section"], "Session s1 / Conversation");
+
+    fs::write(
+        moon_home.join("MEMORY.md"),
+        "# MEMORY\n\n## User Preferences\n- Keep answers concise.\n",
+    )
+    .expect("rewrite memory");
+    let assert = assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("MOON_HOME", &moon_home)
+        .args(["memory", "import"])
+        .arg(&export_path)
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout.contains("added=1"));
+    assert!(stdout.contains("skipped_daily=1"));
+    let memory = fs::read_to_string(moon_home.join("MEMORY.md")).expect("read memory");
+    assert!(memory.contains("- Keep answers concise.\n- Use metric units.\n"));
+}
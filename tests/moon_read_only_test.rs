@@ -0,0 +1,64 @@
+use std::fs;
+use tempfile::tempdir;
+
+#[test]
+fn moon_read_only_refuses_mutations_but_allows_diagnostics() {
+    let tmp = tempdir().expect("tempdir");
+    let moon_home = tmp.path().join("moon");
+    let sessions_dir = tmp.path().join("sessions");
+    fs::create_dir_all(moon_home.join("archives")).expect("mkdir archives");
+    fs::create_dir_all(moon_home.join("memory")).expect("mkdir memory");
+    fs::create_dir_all(&sessions_dir).expect("mkdir sessions");
+    fs::write(sessions_dir.join("main-session.jsonl"), "{}\n").expect("write session");
+
+    for (args, operation) in [
+        (vec!["snapshot"], "snapshot"),
+        (vec!["distill", "--mode", "norm"], "distill"),
+        (vec!["gc", "purge"], "gc purge"),
+        (vec!["embed", "--rebuild"], "embed"),
+        (vec!["health", "--repair"], "health --repair"),
+    ] {
+        let assert = assert_cmd::cargo::cargo_bin_cmd!("moon")
+            .current_dir(tmp.path())
+            .env("MOON_HOME", &moon_home)
+            .env("OPENCLAW_SESSIONS_DIR", &sessions_dir)
+            .env("MOON_READ_ONLY", "true")
+            .args(&args)
+            .assert()
+            .code(1);
+        let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+        assert!(
+            stderr.contains(&format!("MOON_READ_ONLY=true: refusing `{operation}`")),
+            "unexpected stderr for {args:?}: {stderr}"
+        );
+    }
+    assert!(!moon_home.join("archives/raw").exists());
+
+    let assert = assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("MOON_HOME", &moon_home)
+        .env("OPENCLAW_SESSIONS_DIR", &sessions_dir)
+        .env("MOON_READ_ONLY", "1")
+        .arg("status")
+        .assert();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout.contains("read_only=true"));
+
+    assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("MOON_HOME", &moon_home)
+        .env("MOON_READ_ONLY", "true")
+        .args(["embed", "--verify"])
+        .assert()
+        .success();
+
+    let assert = assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("MOON_HOME", &moon_home)
+        .env("MOON_READ_ONLY", "true")
+        .arg("health")
+        .assert();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout.contains("state.file=read-only (write probe skipped)"));
+    assert!(!moon_home.join("moon/logs/audit.log").exists());
+}
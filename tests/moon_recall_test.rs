@@ -121,3 +121,912 @@ fn moon_recall_prefers_exact_channel_archive_match() {
         deterministic_archive.display()
     )));
 }
+
+#[test]
+#[cfg(not(windows))]
+fn moon_recall_applies_configured_tool_priority_boosts() {
+    let tmp = tempdir().expect("tempdir");
+    let moon_home = tmp.path().join("moon");
+    fs::create_dir_all(moon_home.join("archives")).expect("mkdir archives");
+    fs::create_dir_all(moon_home.join("memory")).expect("mkdir memory");
+    fs::create_dir_all(moon_home.join("moon/logs")).expect("mkdir logs");
+
+    let moon_config = tmp.path().join("moon.toml");
+    fs::write(
+        &moon_config,
+        "[tool_priority]\nhigh_boost = 1.5\n\n[[tool_priority.rules]]\ntool = \"deploy_release\"\npriority = \"high\"\n",
+    )
+    .expect("write config");
+
+    let qmd = tmp.path().join("qmd");
+    write_fake_qmd(
+        &qmd,
+        r#"[{"path":"/tmp/plain.json","snippet":"talked about release","score":0.8},{"path":"/tmp/deploy.json","snippet":"toolUse deploy_release","score":0.6}]"#,
+    );
+
+    let assert = assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("MOON_HOME", &moon_home)
+        .env("MOON_CONFIG_PATH", &moon_config)
+        .env("QMD_BIN", &qmd)
+        .arg("recall")
+        .args(["--query", "release"])
+        .assert()
+        .success();
+
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout.contains("match[0].archive=/tmp/deploy.json"));
+}
+
+#[test]
+#[cfg(not(windows))]
+fn moon_recall_rpc_answers_each_request_line_within_budget() {
+    let tmp = tempdir().expect("tempdir");
+    let moon_home = tmp.path().join("moon");
+    fs::create_dir_all(moon_home.join("archives")).expect("mkdir archives");
+    fs::create_dir_all(moon_home.join("memory")).expect("mkdir memory");
+    fs::create_dir_all(moon_home.join("moon/logs")).expect("mkdir logs");
+
+    let qmd = tmp.path().join("qmd");
+    write_fake_qmd(
+        &qmd,
+        r#"[{"path":"/tmp/a.json","snippet":"rule captured","score":0.9},{"path":"/tmp/b.json","snippet":"second rule","score":0.5}]"#,
+    );
+
+    let assert = assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("MOON_HOME", &moon_home)
+        .env("QMD_BIN", &qmd)
+        .args(["recall", "--rpc"])
+        .write_stdin(concat!(
+            "{\"id\":1,\"query\":\"rule\",\"max_results\":1}\n",
+            "\n",
+            "not json\n",
+            "{\"id\":\"q3\",\"query\":\"   \"}\n",
+        ))
+        .assert()
+        .success();
+
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    let lines: Vec<serde_json::Value> = stdout
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("response line is json"))
+        .collect();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[0]["id"], 1);
+    assert_eq!(lines[0]["ok"], true);
+    assert_eq!(lines[0]["truncated"], true);
+    assert_eq!(lines[0]["matches"].as_array().expect("matches").len(), 1);
+    assert_eq!(lines[0]["matches"][0]["archive_path"], "/tmp/a.json");
+    assert_eq!(lines[1]["ok"], false);
+    assert!(
+        lines[1]["error"]
+            .as_str()
+            .expect("error")
+            .starts_with("invalid request")
+    );
+    assert_eq!(lines[2]["id"], "q3");
+    assert_eq!(lines[2]["error"], "query cannot be empty");
+}
+
+#[test]
+#[cfg(not(windows))]
+fn moon_recall_routes_channel_key_to_configured_collection() {
+    let tmp = tempdir().expect("tempdir");
+    let moon_home = tmp.path().join("moon");
+    fs::create_dir_all(moon_home.join("archives")).expect("mkdir archives");
+    fs::create_dir_all(moon_home.join("memory")).expect("mkdir memory");
+    fs::create_dir_all(moon_home.join("moon/logs")).expect("mkdir logs");
+    let config_path = tmp.path().join("moon.toml");
+    fs::write(
+        &config_path,
+        "[collections.channels]\n\"agent:main:discord:\" = \"discord-history\"\n",
+    )
+    .expect("write config");
+
+    let qmd = tmp.path().join("qmd");
+    write_fake_qmd(&qmd, "[]");
+
+    let routed = assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("MOON_HOME", &moon_home)
+        .env("MOON_CONFIG_PATH", &config_path)
+        .env("QMD_BIN", &qmd)
+        .args(["recall", "--query", "rule"])
+        .args(["--channel-key", "agent:main:discord:channel:ops"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&routed.get_output().stdout);
+    assert!(stdout.contains("collection=discord-history"));
+
+    let fallback = assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("MOON_HOME", &moon_home)
+        .env("MOON_CONFIG_PATH", &config_path)
+        .env("MOON_ARCHIVE_COLLECTION", "archive")
+        .env("QMD_BIN", &qmd)
+        .args(["recall", "--query", "rule"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&fallback.get_output().stdout);
+    assert!(stdout.contains("collection=archive"));
+}
+
+#[test]
+#[cfg(not(windows))]
+fn moon_recall_open_hydrates_raw_archive_slice_around_match() {
+    let tmp = tempdir().expect("tempdir");
+    let moon_home = tmp.path().join("moon");
+    let archives = moon_home.join("archives");
+    fs::create_dir_all(archives.join("raw")).expect("mkdir archives/raw");
+    fs::create_dir_all(moon_home.join("memory")).expect("mkdir memory");
+    fs::create_dir_all(moon_home.join("moon/logs")).expect("mkdir logs");
+
+    let raw = archives.join("raw/deep-1771470000.jsonl");
+    let mut lines = Vec::new();
+    for idx in 0..10 {
+        lines.push(format!(
+            r#"{{"timestamp":{},"message":{{"role":"user","content":[{{"type":"text","text":"filler turn {idx}"}}]}}}}"#,
+            1_771_470_000 + idx
+        ));
+    }
+    lines.push(r#"{"timestamp":1771470100,"message":{"role":"assistant","content":[{"type":"text","text":"rotate the staging database credentials"},{"type":"toolUse","name":"exec","input":{"command":"vault rotate staging-db"}}]}}"#.to_string());
+    lines.push(r#"{"timestamp":1771470101,"message":{"role":"toolResult","content":[{"type":"text","text":"rotated lease 42 for staging-db"}]}}"#.to_string());
+    fs::write(&raw, lines.join("\n")).expect("write raw archive");
+
+    let qmd = tmp.path().join("qmd");
+    write_fake_qmd(
+        &qmd,
+        r#"[{"file":"qmd://history/mlib/deep-1771470000.md","snippet":"rotate staging database credentials","score":0.8}]"#,
+    );
+
+    let assert = assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("MOON_HOME", &moon_home)
+        .env("QMD_BIN", &qmd)
+        .arg("recall")
+        .args(["--query", "credentials", "--open", "0", "--context", "1"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout.contains("open.anchor_line=11 method=snippet-terms"));
+    assert!(stdout.contains("open.lines=10-12"));
+    assert!(stdout.contains("[toolUse exec]"));
+    assert!(stdout.contains("rotated lease 42"));
+    assert!(!stdout.contains("filler turn 3"));
+
+    let export = tmp.path().join("out/match.md");
+    assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("MOON_HOME", &moon_home)
+        .env("QMD_BIN", &qmd)
+        .arg("recall")
+        .args(["--query", "credentials", "--open", "0", "--export"])
+        .arg(&export)
+        .assert()
+        .success();
+    let markdown = fs::read_to_string(&export).expect("read export");
+    assert!(markdown.contains("## L11"));
+    assert!(markdown.contains("(match)"));
+    assert!(markdown.contains("filler turn 3"));
+
+    assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("MOON_HOME", &moon_home)
+        .env("QMD_BIN", &qmd)
+        .arg("recall")
+        .args(["--query", "credentials", "--open", "5"])
+        .assert()
+        .code(2);
+}
+
+#[test]
+#[cfg(not(windows))]
+fn moon_recall_cites_projection_line_anchors() {
+    let tmp = tempdir().expect("tempdir");
+    let moon_home = tmp.path().join("moon");
+    let archives = moon_home.join("archives");
+    fs::create_dir_all(archives.join("raw")).expect("mkdir archives/raw");
+    fs::create_dir_all(archives.join("mlib")).expect("mkdir archives/mlib");
+    fs::create_dir_all(moon_home.join("memory")).expect("mkdir memory");
+    fs::create_dir_all(moon_home.join("moon/logs")).expect("mkdir logs");
+
+    let lines = [
+        r#"{"timestamp":1771470000,"message":{"role":"user","content":[{"type":"text","text":"which port does the cache use"}]}}"#,
+        r#"{"timestamp":1771470001,"message":{"role":"assistant","content":[{"type":"text","text":"the cache listens on port 6380"}]}}"#,
+        r#"{"timestamp":1771470002,"message":{"role":"user","content":[{"type":"text","text":"the cache port is documented now"}]}}"#,
+    ];
+    fs::write(archives.join("raw/cite-1771470000.jsonl"), lines.join("\n")).expect("write raw");
+    let second_offset = lines[0].len() + 1;
+    fs::write(
+        archives.join("mlib/cite-1771470000.md"),
+        format!(
+            "---\nmessage_count: 3\nline_anchors: {{\"timeline\":[],\"capsules\":[[1,0],[2,{second_offset}],[3,null]]}}\n---\n\n## Search Capsules\n- [user] which port does the cache use\n- [assistant] the cache listens on port 6380\n- [user] the cache port is documented now\n"
+        ),
+    )
+    .expect("write projection");
+
+    let qmd = tmp.path().join("qmd");
+    write_fake_qmd(
+        &qmd,
+        r#"[{"file":"qmd://history/mlib/cite-1771470000.md","snippet":"cache listens on port 6380","score":0.8}]"#,
+    );
+
+    let assert = assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("MOON_HOME", &moon_home)
+        .env("QMD_BIN", &qmd)
+        .arg("recall")
+        .args(["--query", "cache port"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout.contains(&format!("match[0].anchor=L2@{second_offset}")));
+
+    let assert = assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("MOON_HOME", &moon_home)
+        .env("QMD_BIN", &qmd)
+        .arg("recall")
+        .args(["--query", "cache port", "--open", "0", "--context", "0"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout.contains("open.anchor_line=2 method=line-anchor"));
+    assert!(stdout.contains("open.lines=2-2"));
+}
+
+#[test]
+#[cfg(not(windows))]
+fn moon_recall_redirects_superseded_archives_to_newest_version() {
+    let tmp = tempdir().expect("tempdir");
+    let moon_home = tmp.path().join("moon");
+    let archives = moon_home.join("archives");
+    fs::create_dir_all(archives.join("raw")).expect("mkdir archives/raw");
+    fs::create_dir_all(moon_home.join("memory")).expect("mkdir memory");
+    fs::create_dir_all(moon_home.join("moon/logs")).expect("mkdir logs");
+
+    let older = archives.join("raw/s1-1771470000.jsonl");
+    let newer = archives.join("raw/s1-1771473600.jsonl");
+    fs::write(
+        archives.join("ledger.jsonl"),
+        format!(
+            "{{\"session_id\":\"s1\",\"source_path\":\"/tmp/s1.jsonl\",\"archive_path\":\"{}\",\"projection_path\":null,\"content_hash\":\"a\",\"created_at_epoch_secs\":1,\"indexed_collection\":\"history\",\"indexed\":true,\"superseded_by\":\"{}\"}}\n{{\"session_id\":\"s1\",\"source_path\":\"/tmp/s1.jsonl\",\"archive_path\":\"{}\",\"projection_path\":null,\"content_hash\":\"b\",\"created_at_epoch_secs\":2,\"indexed_collection\":\"history\",\"indexed\":true}}\n",
+            older.display(),
+            newer.display(),
+            newer.display()
+        ),
+    )
+    .expect("write ledger");
+
+    let qmd = tmp.path().join("qmd");
+    write_fake_qmd(
+        &qmd,
+        r#"[{"file":"qmd://history/mlib/s1-1771470000.md","snippet":"older partial copy","score":0.9},{"file":"qmd://history/mlib/s1-1771473600.md","snippet":"newer complete copy","score":0.5}]"#,
+    );
+
+    let assert = assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("MOON_HOME", &moon_home)
+        .env("QMD_BIN", &qmd)
+        .arg("recall")
+        .args(["--query", "copy"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout.contains("match_count=1"));
+    assert!(stdout.contains(&format!("match[0].archive={}", newer.display())));
+}
+
+#[test]
+#[cfg(not(windows))]
+fn moon_recall_collapses_duplicate_session_archives_into_original() {
+    let tmp = tempdir().expect("tempdir");
+    let moon_home = tmp.path().join("moon");
+    let archives = moon_home.join("archives");
+    fs::create_dir_all(archives.join("raw")).expect("mkdir archives/raw");
+    fs::create_dir_all(moon_home.join("memory")).expect("mkdir memory");
+    fs::create_dir_all(moon_home.join("moon/logs")).expect("mkdir logs");
+
+    let original = archives.join("raw/sess-a-1771470000.jsonl");
+    let renamed = archives.join("raw/sess-b-1771473600.jsonl");
+    fs::write(
+        archives.join("ledger.jsonl"),
+        format!(
+            "{{\"session_id\":\"sess-a\",\"source_path\":\"/tmp/sess-a.jsonl\",\"archive_path\":\"{}\",\"projection_path\":null,\"content_hash\":\"a\",\"created_at_epoch_secs\":1,\"indexed_collection\":\"history\",\"indexed\":true,\"projection_simhash\":\"061cd3e480fb4c63\"}}\n{{\"session_id\":\"sess-b\",\"source_path\":\"/tmp/sess-b.jsonl\",\"archive_path\":\"{}\",\"projection_path\":null,\"content_hash\":\"b\",\"created_at_epoch_secs\":2,\"indexed_collection\":\"history\",\"indexed\":true,\"projection_simhash\":\"061cd3e480fb4c63\",\"duplicate_of\":\"{}\"}}\n",
+            original.display(),
+            renamed.display(),
+            original.display()
+        ),
+    )
+    .expect("write ledger");
+
+    let qmd = tmp.path().join("qmd");
+    write_fake_qmd(
+        &qmd,
+        r#"[{"file":"qmd://history/mlib/sess-b-1771473600.md","snippet":"backup job moved","score":0.9},{"file":"qmd://history/mlib/sess-a-1771470000.md","snippet":"backup job moved","score":0.8}]"#,
+    );
+
+    let assert = assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("MOON_HOME", &moon_home)
+        .env("QMD_BIN", &qmd)
+        .arg("recall")
+        .args(["--query", "backup"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout.contains("match_count=1"), "{stdout}");
+    assert!(stdout.contains(&format!("match[0].archive={}", original.display())));
+}
+
+#[test]
+#[cfg(not(windows))]
+fn moon_recall_max_tokens_trims_snippets_lowest_rank_first() {
+    let tmp = tempdir().expect("tempdir");
+    let moon_home = tmp.path().join("moon");
+    fs::create_dir_all(moon_home.join("archives")).expect("mkdir archives");
+    fs::create_dir_all(moon_home.join("memory")).expect("mkdir memory");
+    fs::create_dir_all(moon_home.join("moon/logs")).expect("mkdir logs");
+
+    let long = "deploy rule captured in detail ".repeat(30);
+    let qmd = tmp.path().join("qmd");
+    write_fake_qmd(
+        &qmd,
+        &format!(
+            r#"[{{"path":"/tmp/a.json","snippet":"{long}","score":0.9}},{{"path":"/tmp/b.json","snippet":"short rule","score":0.7}},{{"path":"/tmp/c.json","snippet":"{long}","score":0.5}},{{"path":"/tmp/d.json","snippet":"{long}","score":0.3}}]"#
+        ),
+    );
+
+    let assert = assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("MOON_HOME", &moon_home)
+        .env("QMD_BIN", &qmd)
+        .args(["recall", "--query", "rule", "--max-tokens", "40"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout.contains("truncated=2 dropped=1"), "stdout: {stdout}");
+    assert!(
+        stdout.contains("match[0].truncated=true"),
+        "stdout: {stdout}"
+    );
+    assert!(
+        stdout.contains("match[1].snippet=short rule"),
+        "stdout: {stdout}"
+    );
+    assert!(
+        stdout.contains("match[2].truncated=true"),
+        "stdout: {stdout}"
+    );
+    assert!(!stdout.contains("/tmp/d.json"), "stdout: {stdout}");
+    let used: usize = stdout
+        .split("used_tokens=")
+        .nth(1)
+        .and_then(|rest| rest.split_whitespace().next())
+        .and_then(|n| n.parse().ok())
+        .expect("used_tokens");
+    assert!(used <= 40, "used_tokens={used}");
+}
+
+#[test]
+#[cfg(not(windows))]
+fn moon_recall_groups_matches_under_session_headers() {
+    let tmp = tempdir().expect("tempdir");
+    let moon_home = tmp.path().join("moon");
+    let archives = moon_home.join("archives");
+    fs::create_dir_all(archives.join("raw")).expect("mkdir archives/raw");
+    fs::create_dir_all(archives.join("mlib")).expect("mkdir archives/mlib");
+    fs::create_dir_all(moon_home.join("continuity")).expect("mkdir continuity");
+    fs::create_dir_all(moon_home.join("memory")).expect("mkdir memory");
+    fs::create_dir_all(moon_home.join("moon/logs")).expect("mkdir logs");
+
+    let first = archives.join("raw/s1-1771470000.jsonl");
+    let second = archives.join("raw/s1-1771480000.jsonl");
+    let other = archives.join("raw/s2-1771475000.jsonl");
+    let ledger_row = |session: &str, archive: &Path, hash: &str| {
+        format!(
+            "{{\"session_id\":\"{session}\",\"source_path\":\"/tmp/{session}.jsonl\",\"archive_path\":\"{}\",\"projection_path\":null,\"content_hash\":\"{hash}\",\"created_at_epoch_secs\":1,\"indexed_collection\":\"history\",\"indexed\":true}}\n",
+            archive.display()
+        )
+    };
+    fs::write(
+        archives.join("ledger.jsonl"),
+        [
+            ledger_row("s1", &first, "a"),
+            ledger_row("s2", &other, "b"),
+            ledger_row("s1", &second, "c"),
+        ]
+        .concat(),
+    )
+    .expect("write ledger");
+    fs::write(
+        archives.join("mlib/s1-1771470000.md"),
+        "---\ntime_range_local: \"2026-02-19T12:00:00+09:00 — 2026-02-19T12:40:00+09:00\"\nkeywords: [\"ignored\"]\ntopics: [\"deploy\",\"cache\",\"ports\",\"extra\"]\n---\n",
+    )
+    .expect("write projection");
+    fs::write(
+        moon_home.join("continuity/channel_archive_map.json"),
+        format!(
+            "{{\"agent:main:discord:1\":{{\"channel_key\":\"agent:main:discord:1\",\"source_path\":\"/tmp/s1.jsonl\",\"archive_path\":\"{}\",\"updated_at_epoch_secs\":2}}}}",
+            second.display()
+        ),
+    )
+    .expect("write channel map");
+
+    let qmd = tmp.path().join("qmd");
+    write_fake_qmd(
+        &qmd,
+        r#"[{"file":"qmd://history/mlib/s1-1771470000.md","snippet":"deploy first","score":0.9},{"file":"qmd://history/mlib/s2-1771475000.md","snippet":"other session","score":0.7},{"file":"qmd://history/mlib/s1-1771480000.md","snippet":"deploy again","score":0.5}]"#,
+    );
+
+    let assert = assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("MOON_HOME", &moon_home)
+        .env("QMD_BIN", &qmd)
+        .args(["recall", "--query", "deploy"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout.contains("session_count=2"), "stdout: {stdout}");
+    assert!(
+        stdout.contains("session[0] id=s1 channel=agent:main:discord:1 time=2026-02-19T12:00:00+09:00 — 2026-02-19T12:40:00+09:00 topics=deploy, cache, ports matches=0,2"),
+        "stdout: {stdout}"
+    );
+    assert!(
+        stdout.contains("session[1] id=s2 channel=- time=unknown topics=- matches=1"),
+        "stdout: {stdout}"
+    );
+    let header = stdout.find("session[1]").expect("second header");
+    let third = stdout.find("match[2].archive=").expect("third match");
+    assert!(third < header, "s1 matches print under the s1 header");
+
+    let assert = assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("MOON_HOME", &moon_home)
+        .env("QMD_BIN", &qmd)
+        .args(["recall", "--rpc"])
+        .write_stdin("{\"id\":1,\"query\":\"deploy\"}\n")
+        .assert()
+        .success();
+    let response: serde_json::Value =
+        serde_json::from_str(String::from_utf8_lossy(&assert.get_output().stdout).trim())
+            .expect("rpc json");
+    assert_eq!(response["sessions"][0]["session_id"], "s1");
+    assert_eq!(
+        response["sessions"][0]["matches"],
+        serde_json::json!([0, 2])
+    );
+    assert_eq!(response["sessions"][1]["matches"], serde_json::json!([1]));
+}
+
+#[test]
+#[cfg(not(windows))]
+fn moon_recall_scope_selects_memory_and_archive_collections() {
+    let tmp = tempdir().expect("tempdir");
+    let moon_home = tmp.path().join("moon");
+    let archives = moon_home.join("archives");
+    let memory = moon_home.join("memory");
+    fs::create_dir_all(archives.join("raw")).expect("mkdir archives/raw");
+    fs::create_dir_all(&memory).expect("mkdir memory");
+    fs::create_dir_all(moon_home.join("moon/logs")).expect("mkdir logs");
+
+    // `qmd search <collection> <query> --json` answers per collection.
+    let qmd = tmp.path().join("qmd");
+    fs::write(
+        &qmd,
+        concat!(
+            "#!/usr/bin/env bash\n",
+            "if [ \"$2\" = memory ]; then\n",
+            "  echo '[{\"file\":\"qmd://memory/2026-03-01.md\",\"snippet\":\"Decision: ship the rollout\",\"score\":0.9}]'\n",
+            "else\n",
+            "  echo '[{\"file\":\"qmd://history/mlib/s1.md\",\"snippet\":\"rollout chatter\",\"score\":0.5}]'\n",
+            "fi\n",
+        ),
+    )
+    .expect("write fake qmd");
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&qmd, fs::Permissions::from_mode(0o755)).expect("chmod");
+    }
+    let recall = |scope: &str| {
+        let assert = assert_cmd::cargo::cargo_bin_cmd!("moon")
+            .current_dir(tmp.path())
+            .env("MOON_HOME", &moon_home)
+            .env("QMD_BIN", &qmd)
+            .args(["recall", "--query", "rollout", "--scope", scope])
+            .assert();
+        let output = assert.get_output().clone();
+        (
+            output.status.code(),
+            String::from_utf8_lossy(&output.stdout).to_string(),
+        )
+    };
+    let memory_file = memory.join("2026-03-01.md").display().to_string();
+    let archive_file = archives.join("raw/s1.jsonl").display().to_string();
+
+    let (code, stdout) = recall("memory");
+    assert_eq!(code, Some(0));
+    assert!(stdout.contains("scope=memory"));
+    assert!(stdout.contains("session[0] id=memory:2026-03-01"));
+    assert!(stdout.contains(&format!("match[0].archive={memory_file}")));
+    assert!(!stdout.contains(&archive_file));
+
+    let (_, stdout) = recall("archives");
+    assert!(stdout.contains(&format!("match[0].archive={archive_file}")));
+    assert!(!stdout.contains(&memory_file));
+
+    let (_, stdout) = recall("all");
+    assert!(stdout.contains("match_count=2"));
+    assert!(stdout.contains(&format!("match[0].archive={memory_file}")));
+    assert!(stdout.contains(&format!("match[1].archive={archive_file}")));
+
+    let (code, stdout) = recall("wisdom");
+    assert_eq!(code, Some(2));
+    assert!(stdout.contains("invalid --scope `wisdom`"));
+}
+
+#[test]
+#[cfg(not(windows))]
+fn moon_recall_fields_limit_emitted_match_fields() {
+    let tmp = tempdir().expect("tempdir");
+    let moon_home = tmp.path().join("moon");
+    fs::create_dir_all(moon_home.join("archives")).expect("mkdir archives");
+    fs::create_dir_all(moon_home.join("memory")).expect("mkdir memory");
+    fs::create_dir_all(moon_home.join("moon/logs")).expect("mkdir logs");
+
+    let qmd = tmp.path().join("qmd");
+    write_fake_qmd(
+        &qmd,
+        r#"[{"path":"/tmp/a.json","snippet":"rule captured","score":0.9,"docid":"abc123","context":"large blob"}]"#,
+    );
+    let moon = || {
+        let mut cmd = assert_cmd::cargo::cargo_bin_cmd!("moon");
+        cmd.current_dir(tmp.path())
+            .env("MOON_HOME", &moon_home)
+            .env("QMD_BIN", &qmd);
+        cmd
+    };
+
+    let assert = moon()
+        .args([
+            "recall",
+            "--query",
+            "rule",
+            "--fields",
+            "archive_path,score",
+        ])
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout.contains("match[0].archive=/tmp/a.json"));
+    assert!(stdout.contains("match[0].score=0.9000"));
+    assert!(!stdout.contains("match[0].snippet="));
+    assert!(!stdout.contains("docid"));
+
+    let assert = moon()
+        .args(["recall", "--query", "rule", "--fields", "metadata"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout.contains("match[0].metadata={"));
+    assert!(stdout.contains("\"docid\":\"abc123\""));
+    assert!(!stdout.contains("match[0].archive="));
+
+    moon()
+        .args(["recall", "--query", "rule", "--fields", "score,raw"])
+        .assert()
+        .code(2)
+        .stdout(predicates::str::contains("invalid --fields `raw`"));
+
+    let assert = moon()
+        .args(["recall", "--rpc"])
+        .write_stdin(concat!(
+            "{\"id\":1,\"query\":\"rule\",\"fields\":[\"snippet\"]}\n",
+            "{\"id\":2,\"query\":\"rule\"}\n",
+            "{\"id\":3,\"query\":\"rule\",\"fields\":[\"blob\"]}\n",
+        ))
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    let lines: Vec<serde_json::Value> = stdout
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("response line is json"))
+        .collect();
+    assert_eq!(
+        lines[0]["matches"][0],
+        serde_json::json!({"snippet": "rule captured"})
+    );
+    assert_eq!(
+        lines[1]["matches"][0],
+        serde_json::json!({"archive_path": "/tmp/a.json", "snippet": "rule captured", "score": 0.9})
+    );
+    assert_eq!(lines[2]["ok"], false);
+    assert!(
+        lines[2]["error"]
+            .as_str()
+            .expect("error")
+            .starts_with("invalid fields `blob`")
+    );
+}
+
+#[test]
+#[cfg(not(windows))]
+fn moon_recall_past_deadline_returns_deterministic_and_cached_matches_as_degraded() {
+    let tmp = tempdir().expect("tempdir");
+    let moon_home = tmp.path().join("moon");
+    let archives = moon_home.join("archives");
+    let continuity = moon_home.join("continuity");
+    fs::create_dir_all(&archives).expect("mkdir archives");
+    fs::create_dir_all(moon_home.join("memory")).expect("mkdir memory");
+    fs::create_dir_all(moon_home.join("moon/logs")).expect("mkdir logs");
+    fs::create_dir_all(&continuity).expect("mkdir continuity");
+
+    let mapped_archive = archives.join("mapped.jsonl");
+    fs::write(&mapped_archive, "{\"decision\":\"mapped\"}\n").expect("write archive");
+    let channel_key = "agent:main:discord:channel:ops";
+    fs::write(
+        continuity.join("channel_archive_map.json"),
+        format!(
+            "{{\"{channel_key}\":{{\"channel_key\":\"{channel_key}\",\"source_path\":\"/tmp/source.jsonl\",\"archive_path\":\"{}\",\"updated_at_epoch_secs\":1771400000}}}}\n",
+            mapped_archive.display()
+        ),
+    )
+    .expect("write channel archive map");
+
+    let qmd = tmp.path().join("qmd");
+    write_fake_qmd(
+        &qmd,
+        r#"[{"path":"/tmp/a.json","snippet":"rule captured","score":0.9}]"#,
+    );
+    let moon = || {
+        let mut cmd = assert_cmd::cargo::cargo_bin_cmd!("moon");
+        cmd.current_dir(tmp.path())
+            .env("MOON_HOME", &moon_home)
+            .env("QMD_BIN", &qmd)
+            .env("MOON_RECALL_DEADLINE_MS", "500");
+        cmd
+    };
+
+    let assert = moon()
+        .args(["recall", "--query", "rule", "--channel-key", channel_key])
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(!stdout.contains("degraded=true"));
+    assert!(stdout.contains("match_count=2"));
+
+    let qmd_pids = tmp.path().join("qmd.pids");
+    fs::write(
+        &qmd,
+        format!(
+            "#!/usr/bin/env bash\necho $$ >> {}\nexec sleep 5\n",
+            qmd_pids.display()
+        ),
+    )
+    .expect("write slow qmd");
+
+    let assert = moon()
+        .args(["recall", "--query", "rule", "--channel-key", channel_key])
+        .args(["--fields", "archive_path,metadata"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout.contains("degraded=true"));
+    assert!(stdout.contains("deadline_ms=500"));
+    assert!(stdout.contains(&format!("match[0].archive={}", mapped_archive.display())));
+    assert!(stdout.contains("match[1].archive=/tmp/a.json"));
+    assert!(stdout.contains("\"cached\":true"));
+    assert!(!stdout.contains("/tmp/late.json"));
+
+    let assert = moon()
+        .args(["recall", "--rpc"])
+        .write_stdin("{\"id\":1,\"query\":\"unseen\",\"timeout_ms\":300}\n")
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    let response: serde_json::Value =
+        serde_json::from_str(stdout.trim()).expect("response line is json");
+    assert_eq!(response["ok"], true);
+    assert_eq!(response["degraded"], true);
+    assert_eq!(response["matches"], serde_json::json!([]));
+
+    // qmd is killed at the deadline rather than left running behind the response.
+    let pids = fs::read_to_string(&qmd_pids).expect("read qmd pids");
+    assert_eq!(pids.lines().count(), 2);
+    for pid in pids.lines() {
+        let alive = std::process::Command::new("kill")
+            .args(["-0", pid.trim()])
+            .stderr(std::process::Stdio::null())
+            .status()
+            .expect("run kill -0")
+            .success();
+        assert!(!alive, "qmd {pid} still running after the deadline");
+    }
+}
+
+#[test]
+#[cfg(not(windows))]
+fn moon_recall_day_prints_daily_memory_sections_before_archive_matches() {
+    let tmp = tempdir().expect("tempdir");
+    let moon_home = tmp.path().join("moon");
+    fs::create_dir_all(moon_home.join("archives")).expect("mkdir archives");
+    fs::create_dir_all(moon_home.join("memory")).expect("mkdir memory");
+    fs::create_dir_all(moon_home.join("moon/logs")).expect("mkdir logs");
+    fs::write(
+        moon_home.join("memory/2024-05-12.md"),
+        "# Daily Memory 2024-05-12\n\n<!-- MOON_SESSION_BEGIN:s1 -->\n## Session s1\n### Conversation\n**User:** Decision: freeze deploys until Monday.\n<!-- MOON_SESSION_END:s1 -->\n",
+    )
+    .expect("write daily memory");
+
+    let qmd = tmp.path().join("qmd");
+    write_fake_qmd(
+        &qmd,
+        r#"[{"path":"/tmp/a.json","snippet":"deploy freeze","score":0.8}]"#,
+    );
+    let recall = |args: &[&str]| {
+        assert_cmd::cargo::cargo_bin_cmd!("moon")
+            .current_dir(tmp.path())
+            .env("MOON_HOME", &moon_home)
+            .env("QMD_BIN", &qmd)
+            .arg("recall")
+            .args(args)
+            .assert()
+    };
+
+    let assert = recall(&["--day", "2024-05-12", "--query", "deploy"]).success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout.contains("day.section_count=1"), "{stdout}");
+    assert!(
+        stdout.contains("day.section[0].title=Session s1"),
+        "{stdout}"
+    );
+    let section = stdout
+        .find(
+            "day.section[0].text=### Conversation **User:** Decision: freeze deploys until Monday.",
+        )
+        .expect("day section text");
+    let first_match = stdout.find("match[0].").expect("archive match");
+    assert!(section < first_match, "{stdout}");
+
+    let assert = recall(&["--day", "2024-05-13"]).success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout.contains("day.section_count=0"), "{stdout}");
+    assert!(
+        stdout.contains("no daily memory for 2024-05-13"),
+        "{stdout}"
+    );
+    assert!(!stdout.contains("match_count="), "{stdout}");
+
+    let assert = recall(&["--day", "May 12"]).failure();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout.contains("invalid --day `May 12`"), "{stdout}");
+}
+
+/// Fake qmd that keeps `collection add` registrations and answers `search` from the files the
+/// collection's mask matches, like qmd does.
+fn write_mask_aware_fake_qmd(bin_path: &Path, registry: &Path) {
+    let script = format!(
+        r#"#!/usr/bin/env bash
+set -euo pipefail
+shopt -s globstar nullglob
+registry='{registry}'
+if [[ "${{1:-}}" == "collection" && "${{2:-}}" == "add" ]]; then
+  root="$3"; name="$5"; mask="$7"
+  touch "$registry"
+  grep -v "^$name	" "$registry" > "$registry.tmp" || true
+  printf '%s\t%s\t%s\n' "$name" "$root" "$mask" >> "$registry.tmp"
+  mv "$registry.tmp" "$registry"
+  exit 0
+fi
+if [[ "${{1:-}}" == "search" ]]; then
+  line="$(grep "^$2	" "$registry" || true)"
+  [[ -z "$line" ]] && {{ echo '[]'; exit 0; }}
+  IFS=$'\t' read -r _ root mask <<< "$line"
+  out=""
+  for file in "$root"/$mask; do
+    if grep -qi -- "$3" "$file"; then
+      out="$out${{out:+,}}{{\"path\":\"$file\",\"snippet\":\"$3\",\"score\":0.8}}"
+    fi
+  done
+  echo "[$out]"
+  exit 0
+fi
+exit 0
+"#,
+        registry = registry.display()
+    );
+    fs::write(bin_path, script).expect("write fake qmd");
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mut perms = fs::metadata(bin_path).expect("metadata").permissions();
+        perms.set_mode(0o755);
+        fs::set_permissions(bin_path, perms).expect("chmod");
+    }
+}
+
+#[test]
+#[cfg(not(windows))]
+fn moon_recall_keeps_each_collection_to_its_own_archives() {
+    let tmp = tempdir().expect("tempdir");
+    let moon_home = tmp.path().join("moon");
+    let archives = moon_home.join("archives");
+    fs::create_dir_all(&archives).expect("mkdir archives");
+    fs::create_dir_all(moon_home.join("memory")).expect("mkdir memory");
+    fs::create_dir_all(moon_home.join("moon/logs")).expect("mkdir logs");
+    let sessions_dir = tmp.path().join("sessions");
+    fs::create_dir_all(&sessions_dir).expect("mkdir sessions");
+    for id in ["sess-ops", "sess-main"] {
+        fs::write(
+            sessions_dir.join(format!("{id}.jsonl")),
+            format!(
+                "{{\"type\":\"message\",\"timestamp\":\"2026-02-18T10:00:00Z\",\"message\":{{\"role\":\"user\",\"content\":[{{\"type\":\"text\",\"text\":\"deploy checklist from {id}\"}}]}}}}\n"
+            ),
+        )
+        .expect("write session");
+    }
+    fs::write(
+        sessions_dir.join("sessions.json"),
+        r#"{"agent:main:discord:channel:ops": {"sessionId":"sess-ops"}, "agent:main:main": {"sessionId":"sess-main"}}"#,
+    )
+    .expect("write sessions map");
+    let config_path = tmp.path().join("moon.toml");
+    fs::write(
+        &config_path,
+        "[collections]\ndefault = \"history\"\n[collections.channels]\n\"agent:main:discord:\" = \"discord\"\n",
+    )
+    .expect("write config");
+    let qmd = tmp.path().join("qmd");
+    write_mask_aware_fake_qmd(&qmd, &tmp.path().join("qmd-collections.tsv"));
+    let openclaw = tmp.path().join("openclaw");
+    fs::write(
+        &openclaw,
+        r#"#!/usr/bin/env bash
+if [[ "${1:-}" == "sessions" && "${2:-}" == "--json" ]]; then
+  echo '{"path":"x","count":2,"sessions":[{"key":"agent:main:discord:channel:ops","totalTokens":9000,"contextTokens":10000},{"key":"agent:main:main","totalTokens":9000,"contextTokens":10000}]}'
+  exit 0
+fi
+if [[ "${1:-}" == "gateway" ]]; then
+  echo '{"status":"started","runId":"test-run"}'
+fi
+exit 0
+"#,
+    )
+    .expect("write fake openclaw");
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&openclaw, fs::Permissions::from_mode(0o755)).expect("chmod");
+    }
+
+    let moon = || {
+        let mut cmd = assert_cmd::cargo::cargo_bin_cmd!("moon");
+        cmd.current_dir(tmp.path())
+            .env("MOON_HOME", &moon_home)
+            .env("MOON_CONFIG_PATH", &config_path)
+            .env("OPENCLAW_SESSIONS_DIR", &sessions_dir)
+            .env("QMD_BIN", &qmd)
+            .env("OPENCLAW_BIN", &openclaw);
+        cmd
+    };
+    for key in ["agent:main:discord:channel:ops", "agent:main:main"] {
+        moon().args(["compact", key]).assert().success();
+    }
+
+    let recall = |collection: &str| {
+        let assert = moon()
+            .args(["recall", "--query", "deploy", "--name", collection])
+            .assert()
+            .success();
+        String::from_utf8_lossy(&assert.get_output().stdout).to_string()
+    };
+    let history = recall("history");
+    assert!(history.contains("sess-main"), "{history}");
+    assert!(!history.contains("sess-ops"), "{history}");
+    let discord = recall("discord");
+    assert!(
+        discord.contains(&archives.join("raw/discord/sess-ops").display().to_string()),
+        "{discord}"
+    );
+    assert!(!discord.contains("sess-main"), "{discord}");
+}
@@ -0,0 +1,73 @@
+use std::fs;
+use tempfile::tempdir;
+
+#[test]
+fn moon_report_daily_writes_digest_for_the_day() {
+    let tmp = tempdir().expect("tempdir");
+    let moon_home = tmp.path().join("moon");
+    fs::create_dir_all(moon_home.join("archives")).expect("mkdir archives");
+    fs::create_dir_all(moon_home.join("memory")).expect("mkdir memory");
+    fs::create_dir_all(moon_home.join("moon/logs")).expect("mkdir logs");
+
+    // 1_700_000_000 is 2023-11-14T22:13:20Z; 1_699_000_000 falls on an earlier day.
+    fs::write(
+        moon_home.join("archives/ledger.jsonl"),
+        concat!(
+            "{\"session_id\":\"s-today\",\"source_path\":\"/tmp/s-today.jsonl\",\"archive_path\":\"/tmp/raw/s-today.jsonl\",\"projection_path\":null,\"content_hash\":\"a\",\"created_at_epoch_secs\":1700000000,\"indexed_collection\":\"history\",\"indexed\":true}\n",
+            "{\"session_id\":\"s-old\",\"source_path\":\"/tmp/s-old.jsonl\",\"archive_path\":\"/tmp/raw/s-old.jsonl\",\"projection_path\":null,\"content_hash\":\"b\",\"created_at_epoch_secs\":1699000000,\"indexed_collection\":\"history\",\"indexed\":true}\n",
+        ),
+    )
+    .expect("write ledger");
+    fs::write(
+        moon_home.join("moon/logs/audit.log"),
+        concat!(
+            "{\"at_epoch_secs\":1700000100,\"phase\":\"compaction\",\"status\":\"ok\",\"message\":\"targets=1 succeeded=1 failed=0\"}\n",
+            "{\"schema_version\":2,\"at_epoch_secs\":1700000200,\"phase\":\"distill\",\"status\":\"ok\",\"message\":\"distilled session s-today into x provider=openai topic_count=0\",\"details\":{\"session\":\"s-today\",\"provider\":\"openai\",\"topic_count\":0}}\n",
+            "{\"schema_version\":2,\"at_epoch_secs\":1700000250,\"phase\":\"distill\",\"status\":\"ok\",\"message\":\"mode=syns provider=gemini fallback_from=gemini fallback_error_class=auth\",\"details\":{\"mode\":\"syns\",\"provider\":\"gemini\",\"fallback_from\":\"gemini\",\"fallback_error_class\":\"auth\",\"fallback_failed_chunks\":2}}\n",
+            "{\"at_epoch_secs\":1700000300,\"phase\":\"embed\",\"status\":\"degraded\",\"message\":\"embed timeout\"}\n",
+            "{\"at_epoch_secs\":1699000000,\"phase\":\"embed\",\"status\":\"degraded\",\"message\":\"old failure\"}\n",
+        ),
+    )
+    .expect("write audit");
+    fs::write(
+        moon_home.join("memory/2023-11-14.md"),
+        "# Daily Memory 2023-11-14\n\n### Decisions\n- adopt the v2 parser\n\n### Open Tasks\n- backfill projections\n",
+    )
+    .expect("write daily memory");
+    // The day being reported has already rolled into the budget ledger's history.
+    fs::write(
+        moon_home.join("moon/logs/distill-budget.json"),
+        "{\"day_key\":\"2023-11-15\",\"tokens_used\":10,\"history\":{\"2023-11-14\":400000}}\n",
+    )
+    .expect("write budget ledger");
+
+    let assert = assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("MOON_HOME", &moon_home)
+        .env("MOON_RESIDENTIAL_TIMEZONE", "UTC")
+        .env("MOON_DISTILL_COST_PER_MILLION_TOKENS", "2.5")
+        .args(["report", "daily", "--day", "2023-11-14"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout.contains("archived=1"));
+    assert!(stdout.contains("warnings=1"));
+    assert!(stdout.contains("distill_tokens=400000"));
+    assert!(stdout.contains("distill_cost_estimate=1.0000"));
+
+    let digest = fs::read_to_string(moon_home.join("memory/reports/daily-2023-11-14.md"))
+        .expect("read report");
+    assert!(digest.contains("# Daily Report 2023-11-14"));
+    assert!(digest.contains("`s-today`"));
+    assert!(!digest.contains("s-old"));
+    assert!(digest.contains("ok: targets=1 succeeded=1 failed=0"));
+    assert!(digest.contains("- adopt the v2 parser"));
+    assert!(digest.contains("- backfill projections"));
+    assert!(digest.contains("- estimated tokens: 400000"));
+    assert!(digest.contains("- estimated cost: 1.0000"));
+    assert!(digest.contains("- openai: 1 run(s)"));
+    assert!(digest.contains("## Provider Fallbacks (1)"));
+    assert!(digest.contains("- auth: 1 of 2 provider run(s)"));
+    assert!(digest.contains("embed degraded: embed timeout"));
+    assert!(!digest.contains("old failure"));
+}
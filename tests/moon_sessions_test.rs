@@ -0,0 +1,161 @@
+#![cfg(not(windows))]
+use std::fs;
+use std::path::Path;
+use tempfile::tempdir;
+
+fn write_fake_openclaw(bin_path: &Path) {
+    let script = r#"#!/usr/bin/env bash
+set -euo pipefail
+
+if [[ "${1:-}" == "sessions" && "${2:-}" == "--json" ]]; then
+  echo '{"path":"x","count":2,"sessions":[{"key":"agent:main:discord:channel:ops","totalTokens":9000,"contextTokens":10000},{"key":"agent:main:main","totalTokens":1000,"contextTokens":10000}]}'
+  exit 0
+fi
+
+exit 0
+"#;
+    fs::write(bin_path, script).expect("write fake openclaw");
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mut perms = fs::metadata(bin_path).expect("metadata").permissions();
+        perms.set_mode(0o755);
+        fs::set_permissions(bin_path, perms).expect("chmod");
+    }
+}
+
+#[test]
+fn moon_sessions_lists_usage_channel_archive_and_distill_state() {
+    let tmp = tempdir().expect("tempdir");
+    let moon_home = tmp.path().join("moon");
+    let sessions_dir = tmp.path().join("sessions");
+    let state_file = tmp.path().join("state/moon_state.json");
+    fs::create_dir_all(moon_home.join("archives")).expect("mkdir archives");
+    fs::create_dir_all(moon_home.join("memory")).expect("mkdir memory");
+    fs::create_dir_all(moon_home.join("moon/logs")).expect("mkdir logs");
+    fs::create_dir_all(state_file.parent().expect("state parent")).expect("mkdir state");
+    fs::create_dir_all(&sessions_dir).expect("mkdir sessions");
+    fs::write(sessions_dir.join("sess-ops.jsonl"), "{}\n").expect("write session");
+    fs::write(
+        sessions_dir.join("sessions.json"),
+        r#"{"agent:main:discord:channel:ops": {"sessionId":"sess-ops"}}"#,
+    )
+    .expect("write sessions map");
+
+    let source = sessions_dir.join("sess-ops.jsonl");
+    fs::write(
+        moon_home.join("archives/ledger.jsonl"),
+        format!(
+            "{{\"session_id\":\"sess-ops\",\"source_path\":\"{}\",\"archive_path\":\"/tmp/raw/sess-ops.jsonl\",\"projection_path\":null,\"content_hash\":\"a\",\"created_at_epoch_secs\":1700000000,\"indexed_collection\":\"history\",\"indexed\":true}}\n",
+            source.display()
+        ),
+    )
+    .expect("write ledger");
+    fs::write(
+        &state_file,
+        "{\"distilled_archives\":{\"/tmp/raw/sess-ops.jsonl\":1700000100}}\n",
+    )
+    .expect("write state");
+
+    let openclaw = tmp.path().join("openclaw");
+    write_fake_openclaw(&openclaw);
+
+    let assert = assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("MOON_HOME", &moon_home)
+        .env("MOON_STATE_FILE", &state_file)
+        .env("OPENCLAW_SESSIONS_DIR", &sessions_dir)
+        .env("OPENCLAW_BIN", &openclaw)
+        .env("MOON_TRIGGER_RATIO", "0.85")
+        .arg("sessions")
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout.contains("sessions=2"));
+    assert!(stdout.contains(
+        "key=agent:main:discord:channel:ops ratio=0.9000 used=9000 max=10000 channel=compaction-eligible over_threshold=true last_archive=2023-11-14T22:13:20+00:00 distilled=true"
+    ));
+    assert!(stdout.contains(
+        "key=agent:main:main ratio=0.1000 used=1000 max=10000 channel=not-eligible over_threshold=false last_archive=none distilled=false source=unmapped"
+    ));
+}
+
+#[test]
+fn moon_usage_sorts_by_ratio_with_trend_sparkline_and_next_cycle_flag() {
+    let tmp = tempdir().expect("tempdir");
+    let moon_home = tmp.path().join("moon");
+    let state_file = tmp.path().join("state/moon_state.json");
+    fs::create_dir_all(moon_home.join("moon/logs")).expect("mkdir logs");
+    fs::create_dir_all(state_file.parent().expect("state parent")).expect("mkdir state");
+    fs::write(
+        &state_file,
+        r#"{"usage_trends":{"agent:main:discord:channel:ops":{"samples":[{"epoch_secs":1,"ratio":0.0},{"epoch_secs":2,"ratio":0.5},{"epoch_secs":3,"ratio":0.9}]}}}"#,
+    )
+    .expect("write state");
+
+    let openclaw = tmp.path().join("openclaw");
+    write_fake_openclaw(&openclaw);
+
+    let assert = assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("MOON_HOME", &moon_home)
+        .env("MOON_STATE_FILE", &state_file)
+        .env("OPENCLAW_BIN", &openclaw)
+        .env("MOON_TRIGGER_RATIO", "0.85")
+        .arg("usage")
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout.contains("sessions=2"));
+    assert!(stdout.contains(
+        "session[0] key=agent:main:discord:channel:ops ratio=0.9000 used=9000 max=10000 trend=▁▅▇ samples=3 next_cycle=compaction"
+    ));
+    assert!(stdout.contains(
+        "session[1] key=agent:main:main ratio=0.1000 used=1000 max=10000 trend=none samples=0 next_cycle=none"
+    ));
+    assert!(stdout.contains("next_cycle.triggering=1"));
+}
+
+#[test]
+fn moon_usage_reads_v2_payloads_and_honours_pinned_schema() {
+    let tmp = tempdir().expect("tempdir");
+    let moon_home = tmp.path().join("moon");
+    fs::create_dir_all(moon_home.join("moon/logs")).expect("mkdir logs");
+    let fixture = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/openclaw_usage/v2_sessions.json");
+    let openclaw = tmp.path().join("openclaw");
+    fs::write(
+        &openclaw,
+        format!(
+            "#!/usr/bin/env bash\nif [[ \"${{1:-}}\" == \"sessions\" && \"${{2:-}}\" == \"--json\" ]]; then\n  cat '{}'\nfi\n",
+            fixture.display()
+        ),
+    )
+    .expect("write fake openclaw");
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&openclaw, fs::Permissions::from_mode(0o755)).expect("chmod");
+    }
+
+    let run = |schema: &str| {
+        assert_cmd::cargo::cargo_bin_cmd!("moon")
+            .current_dir(tmp.path())
+            .env("MOON_HOME", &moon_home)
+            .env("OPENCLAW_BIN", &openclaw)
+            .env("MOON_USAGE_SCHEMA", schema)
+            .arg("usage")
+            .assert()
+    };
+
+    let assert = run("auto").success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout.contains("schema=v2"));
+    assert!(stdout.contains("sessions=2"));
+    assert!(
+        stdout.contains("session[0] key=agent:main:discord:channel:ops ratio=0.9000 used=9000")
+    );
+
+    let assert = run("v1").failure();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout.contains("missing used token fields (schema v1)"));
+}
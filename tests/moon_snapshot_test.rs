@@ -1,4 +1,7 @@
+use flate2::Compression;
+use flate2::write::GzEncoder;
 use std::fs;
+use std::io::Write;
 use tempfile::tempdir;
 
 #[test]
@@ -30,3 +33,113 @@ fn moon_snapshot_copies_latest_session_file_to_archives() {
     }
     assert_eq!(count, 1);
 }
+
+#[test]
+fn moon_snapshot_archives_rotated_gzip_sessions_decompressed() {
+    let tmp = tempdir().expect("tempdir");
+    let sessions_dir = tmp.path().join("sessions");
+    let archives_dir = tmp.path().join("archives");
+    fs::create_dir_all(&sessions_dir).expect("mkdir sessions");
+
+    let body = "{\"message\":{\"role\":\"user\",\"content\":\"rotated hello\"}}\n";
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(body.as_bytes()).expect("compress");
+    fs::write(
+        sessions_dir.join("main-session.jsonl.gz"),
+        encoder.finish().expect("finish gzip"),
+    )
+    .expect("write rotated session");
+
+    let assert = assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("OPENCLAW_SESSIONS_DIR", &sessions_dir)
+        .env("MOON_ARCHIVES_DIR", &archives_dir)
+        .arg("snapshot")
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout.contains("main-session.jsonl.gz"), "{stdout}");
+
+    let archived = fs::read_dir(archives_dir.join("raw"))
+        .expect("read raw archives")
+        .map(|entry| entry.expect("entry").path())
+        .collect::<Vec<_>>();
+    assert_eq!(archived.len(), 1);
+    let name = archived[0]
+        .file_name()
+        .unwrap()
+        .to_string_lossy()
+        .to_string();
+    assert!(
+        name.starts_with("main-session-") && name.ends_with(".jsonl"),
+        "{name}"
+    );
+    assert_eq!(
+        fs::read_to_string(&archived[0]).expect("read archive"),
+        body
+    );
+}
+
+#[test]
+fn moon_snapshot_skips_sessions_matching_exclude_globs() {
+    let tmp = tempdir().expect("tempdir");
+    let sessions_dir = tmp.path().join("sessions");
+    let archives_dir = tmp.path().join("archives");
+    fs::create_dir_all(&sessions_dir).expect("mkdir sessions");
+
+    fs::write(sessions_dir.join("main-session.jsonl"), "{\"a\":1}\n").expect("write main");
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    let sandbox = sessions_dir.join("scratch-sandbox.jsonl");
+    fs::write(&sandbox, "{\"b\":2}\n").expect("write sandbox");
+
+    let assert = assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("OPENCLAW_SESSIONS_DIR", &sessions_dir)
+        .env("MOON_ARCHIVES_DIR", &archives_dir)
+        .env("MOON_SNAPSHOT_EXCLUDE", "*-sandbox.jsonl")
+        .arg("snapshot")
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout.contains("main-session.jsonl"));
+    assert!(!stdout.contains("scratch-sandbox"));
+
+    assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("OPENCLAW_SESSIONS_DIR", &sessions_dir)
+        .env("MOON_ARCHIVES_DIR", &archives_dir)
+        .env("MOON_SNAPSHOT_EXCLUDE", "*-sandbox.jsonl")
+        .args(["snapshot", "--source"])
+        .arg(&sandbox)
+        .assert()
+        .code(2);
+}
+
+#[test]
+fn moon_snapshot_dry_run_reports_archive_plan_without_writing() {
+    let tmp = tempdir().expect("tempdir");
+    let sessions_dir = tmp.path().join("sessions");
+    let archives_dir = tmp.path().join("archives");
+    fs::create_dir_all(&sessions_dir).expect("mkdir sessions");
+    fs::write(
+        sessions_dir.join("main-session.jsonl"),
+        "{\"type\":\"message\",\"message\":{\"role\":\"user\",\"content\":\"ship the release\"}}\n",
+    )
+    .expect("write source");
+
+    let assert = assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("OPENCLAW_SESSIONS_DIR", &sessions_dir)
+        .env("MOON_ARCHIVES_DIR", &archives_dir)
+        .args(["snapshot", "--dry-run"])
+        .assert()
+        .success();
+
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout.contains("plan.deduped=false"));
+    assert!(stdout.contains("plan.archive_path="));
+    assert!(stdout.contains("plan.projection_bytes_estimate="));
+    assert!(stdout.contains("plan.qmd_operation="));
+    assert!(stdout.contains("collection add"));
+    assert!(!archives_dir.exists());
+}
@@ -29,3 +29,28 @@ fn status_default_state_path_uses_home_workspace_root_when_moon_home_unset() {
         legacy_nested.display()
     );
 }
+
+#[test]
+fn status_lists_per_channel_state_migrated_from_flat_fields() {
+    let tmp = tempdir().expect("tempdir");
+    let moon_home = tmp.path().join("moon");
+    let state_file = moon_home.join("moon/state/moon_state.json");
+    std::fs::create_dir_all(state_file.parent().expect("state parent")).expect("mkdir state");
+    std::fs::write(
+        &state_file,
+        "{\"schema_version\": 3, \"last_archive_trigger_epoch_secs\": 100, \"last_compaction_trigger_epoch_secs\": 120, \"consecutive_distill_failures\": 2}\n",
+    )
+    .expect("write state");
+
+    assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("MOON_HOME", &moon_home)
+        .env_remove("MOON_STATE_FILE")
+        .env_remove("MOON_STATE_DIR")
+        .arg("status")
+        .assert()
+        .stdout(contains("channels=1"))
+        .stdout(contains(
+            "channel.* last_archive=100 last_compaction=120 last_distill=- failures=archive:0,compaction:0,distill:2",
+        ));
+}
@@ -0,0 +1,150 @@
+use std::fs;
+use std::path::Path;
+use tempfile::tempdir;
+
+fn turn(timestamp: u64, role: &str, text: &str) -> String {
+    format!(
+        r#"{{"timestamp":{timestamp},"message":{{"role":"{role}","content":[{{"type":"text","text":"{text}"}}]}}}}"#
+    )
+}
+
+fn write_archive(moon_home: &Path, session_id: &str, lines: &[String]) -> String {
+    let archive = moon_home.join(format!("archives/raw/{session_id}.jsonl"));
+    fs::write(&archive, format!("{}\n", lines.join("\n"))).expect("write archive");
+    archive.display().to_string()
+}
+
+fn ledger_row(session_id: &str, archive: &str, created_at: u64) -> String {
+    format!(
+        r#"{{"session_id":"{session_id}","source_path":"/tmp/sessions/{session_id}.jsonl","archive_path":"{archive}","projection_path":null,"content_hash":"{session_id}","created_at_epoch_secs":{created_at},"indexed_collection":"history","indexed":true}}"#
+    )
+}
+
+/// 1_771_459_200 is 2026-02-19T00:00:00Z.
+fn seed_two_channels(moon_home: &Path) {
+    fs::create_dir_all(moon_home.join("archives/raw")).expect("mkdir raw");
+    fs::create_dir_all(moon_home.join("continuity")).expect("mkdir continuity");
+    let ops = write_archive(
+        moon_home,
+        "s-ops",
+        &[
+            turn(1_771_380_000, "user", "yesterday: check disk usage"),
+            turn(
+                1_771_470_000,
+                "user",
+                "rotate the staging database credentials",
+            ),
+            turn(
+                1_771_470_600,
+                "assistant",
+                "rotated lease 42 for staging-db",
+            ),
+        ],
+    );
+    let release = write_archive(
+        moon_home,
+        "s-release",
+        &[
+            turn(1_771_470_300, "user", "draft the release note for v2"),
+            turn(1_771_470_900, "assistant", "draft ready | three bullets"),
+        ],
+    );
+    fs::write(
+        moon_home.join("archives/ledger.jsonl"),
+        format!(
+            "{}\n{}\n",
+            ledger_row("s-ops", &ops, 1_771_470_700),
+            ledger_row("s-release", &release, 1_771_471_000)
+        ),
+    )
+    .expect("write ledger");
+    fs::write(
+        moon_home.join("continuity/channel_archive_map.json"),
+        format!(
+            r#"{{"agent:main:discord:ops":{{"channel_key":"agent:main:discord:ops","source_path":"/tmp/sessions/s-ops.jsonl","archive_path":"{ops}","updated_at_epoch_secs":1771470700}}}}"#
+        ),
+    )
+    .expect("write channel map");
+}
+
+#[test]
+fn moon_timeline_merges_sessions_for_a_day_in_time_order() {
+    let tmp = tempdir().expect("tempdir");
+    let moon_home = tmp.path().join("moon");
+    seed_two_channels(&moon_home);
+    let output = tmp.path().join("tuesday.md");
+
+    let assert = assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("MOON_HOME", &moon_home)
+        .env("MOON_RESIDENTIAL_TIMEZONE", "UTC")
+        .args(["timeline", "--day", "2026-02-19", "--output"])
+        .arg(&output)
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout.contains("sessions=2"), "stdout: {stdout}");
+    assert!(stdout.contains("entries=4"), "stdout: {stdout}");
+
+    let rendered = fs::read_to_string(&output).expect("read timeline");
+    assert!(rendered.starts_with("# Timeline 2026-02-19\n"));
+    assert!(!rendered.contains("check disk usage"));
+    let order = [
+        "rotate the staging database credentials",
+        "draft the release note for v2",
+        "rotated lease 42 for staging-db",
+        "draft ready \\| three bullets",
+    ]
+    .map(|text| rendered.find(text).expect(text));
+    assert!(order.windows(2).all(|pair| pair[0] < pair[1]), "{rendered}");
+    assert!(rendered.contains("| agent:main:discord:ops | `s-ops` | user |"));
+    assert!(rendered.contains("| - | `s-release` | user |"));
+}
+
+#[test]
+fn moon_timeline_filters_by_channel_as_json() {
+    let tmp = tempdir().expect("tempdir");
+    let moon_home = tmp.path().join("moon");
+    seed_two_channels(&moon_home);
+
+    let assert = assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("MOON_HOME", &moon_home)
+        .env("MOON_RESIDENTIAL_TIMEZONE", "UTC")
+        .args([
+            "timeline",
+            "--channel",
+            "agent:main:discord:ops",
+            "--format",
+            "json",
+        ])
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout.contains("entries=3"), "stdout: {stdout}");
+
+    let output = moon_home.join("moon/exports/timeline-agent-main-discord-ops.json");
+    let timeline: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&output).expect("read timeline"))
+            .expect("parse timeline");
+    assert_eq!(timeline["channel"], "agent:main:discord:ops");
+    assert!(timeline.get("day").is_none());
+    let entries = timeline["entries"].as_array().expect("entries");
+    assert_eq!(entries.len(), 3);
+    assert!(entries.iter().all(|entry| entry["session_id"] == "s-ops"));
+    assert_eq!(entries[0]["text"], "yesterday: check disk usage");
+}
+
+#[test]
+fn moon_timeline_rejects_unknown_format() {
+    let tmp = tempdir().expect("tempdir");
+    let moon_home = tmp.path().join("moon");
+    seed_two_channels(&moon_home);
+
+    assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("MOON_HOME", &moon_home)
+        .args(["timeline", "--day", "2026-02-19", "--format", "csv"])
+        .assert()
+        .code(2);
+}
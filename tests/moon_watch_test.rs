@@ -3,7 +3,7 @@ use chrono::{Duration as ChronoDuration, TimeZone, Utc};
 use predicates::str::contains;
 use serde_json::Value;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::time::{SystemTime, UNIX_EPOCH};
 use tempfile::tempdir;
 
@@ -91,6 +91,15 @@ if [[ "${1:-}" == "sessions" && "${2:-}" == "current" && "${3:-}" == "--json" ]]
   exit 0
 fi
 
+if [[ "${1:-}" == "gateway" && "${2:-}" == "call" && "${3:-}" == "chat.history" ]]; then
+  if [[ -n "${MOON_TEST_CHAT_HISTORY_JSON:-}" ]]; then
+    echo "${MOON_TEST_CHAT_HISTORY_JSON}"
+  else
+    echo '{"messages":[]}'
+  fi
+  exit 0
+fi
+
 if [[ "${1:-}" == "gateway" && "${2:-}" == "call" && "${3:-}" == "chat.send" ]]; then
   if [[ -n "${MOON_TEST_COMPACT_LOG:-}" ]]; then
     printf "%s\n" "$*" >> "${MOON_TEST_COMPACT_LOG}"
@@ -99,7 +108,7 @@ if [[ "${1:-}" == "gateway" && "${2:-}" == "call" && "${3:-}" == "chat.send" ]];
   exit 0
 fi
 
-if [[ "${1:-}" == "system" && "${2:-}" == "event" ]]; then
+if [[ "${1:-}" == "system" && "${2:-}" == "event" ]] || [[ "${1:-}" == "gateway" && "${2:-}" == "call" && "${3:-}" == "wake" ]]; then
   if [[ -n "${MOON_TEST_EVENT_LOG:-}" ]]; then
     printf "%s\n" "$*" >> "${MOON_TEST_EVENT_LOG}"
   fi
@@ -132,8 +141,15 @@ fn read_last_distill_trigger_epoch(state_file: &Path) -> Option<u64> {
     let raw = fs::read_to_string(state_file).expect("read state");
     let parsed: Value = serde_json::from_str(&raw).expect("parse state");
     parsed
-        .get("last_distill_trigger_epoch_secs")
-        .and_then(Value::as_u64)
+        .get("channels")
+        .and_then(Value::as_object)?
+        .values()
+        .filter_map(|channel| {
+            channel
+                .get("last_distill_epoch_secs")
+                .and_then(Value::as_u64)
+        })
+        .max()
 }
 
 fn write_context_policy_for_watch(moon_home: &Path, authority: &str) {
@@ -273,7 +289,7 @@ fn moon_watch_once_triggers_pipeline_with_low_thresholds() {
     let openclaw = tmp.path().join("openclaw");
     write_fake_openclaw(&openclaw);
 
-    assert_cmd::cargo::cargo_bin_cmd!("moon")
+    let assert = assert_cmd::cargo::cargo_bin_cmd!("moon")
         .current_dir(tmp.path())
         .env("MOON_HOME", &moon_home)
         .env("OPENCLAW_SESSIONS_DIR", &sessions_dir)
@@ -289,6 +305,240 @@ fn moon_watch_once_triggers_pipeline_with_low_thresholds() {
     assert!(state_file.exists());
     let ledger = moon_home.join("archives/ledger.jsonl");
     assert!(ledger.exists());
+
+    // The cycle's audit events are flushed together under one cycle id.
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    let cycle_id = stdout
+        .lines()
+        .find_map(|line| line.strip_prefix("- cycle_id="))
+        .expect("cycle_id detail");
+    let audit = fs::read_to_string(moon_home.join("moon/logs/audit.log")).expect("read audit");
+    let tag = format!("\"cycle_id\":\"{cycle_id}\"");
+    assert!(audit.lines().count() > 0);
+    assert!(audit.lines().all(|line| line.contains(&tag)));
+}
+
+#[test]
+#[cfg(not(windows))]
+fn moon_watch_once_degrades_while_sessions_dir_is_missing_and_recovers() {
+    let tmp = tempdir().expect("tempdir");
+    let moon_home = tmp.path().join("moon");
+    let sessions_dir = tmp.path().join("sessions");
+    fs::create_dir_all(moon_home.join("moon/logs")).expect("mkdir logs");
+
+    let qmd = tmp.path().join("qmd");
+    write_fake_qmd(&qmd);
+    let openclaw = tmp.path().join("openclaw");
+    write_fake_openclaw(&openclaw);
+
+    let run = || {
+        assert_cmd::cargo::cargo_bin_cmd!("moon")
+            .current_dir(tmp.path())
+            .env("MOON_HOME", &moon_home)
+            .env("OPENCLAW_SESSIONS_DIR", &sessions_dir)
+            .env("QMD_BIN", &qmd)
+            .env("OPENCLAW_BIN", &openclaw)
+            .env("MOON_TRIGGER_RATIO", "0.00002")
+            .arg("watch")
+            .arg("--once")
+            .assert()
+            .success()
+    };
+
+    run()
+        .stdout(contains("- sessions_dir=unavailable since_epoch="))
+        .stdout(contains("reason=sessions-dir-unavailable"))
+        .stderr(contains("MOON_WARN code=SESSIONS_DIR_UNAVAILABLE"));
+    let state_file = moon_home.join("moon/state/moon_state.json");
+    let state = fs::read_to_string(&state_file).expect("read state");
+    assert!(state.contains("sessions_dir_unavailable_since_epoch_secs"));
+    assert!(!moon_home.join("archives/ledger.jsonl").exists());
+    let audit = fs::read_to_string(moon_home.join("moon/logs/audit.log")).expect("read audit");
+    let degraded = audit
+        .lines()
+        .filter(|line| line.contains("\"phase\":\"sessions-dir\""))
+        .collect::<Vec<_>>();
+    assert_eq!(degraded.len(), 1);
+    assert!(degraded[0].contains("code=E014_PATH_MISSING"));
+
+    // A second failing cycle warns again but does not repeat the audit event.
+    run().stderr(contains("SESSIONS_DIR_UNAVAILABLE"));
+    let audit = fs::read_to_string(moon_home.join("moon/logs/audit.log")).expect("read audit");
+    assert_eq!(audit.matches("code=E014_PATH_MISSING").count(), 1);
+
+    fs::create_dir_all(&sessions_dir).expect("mkdir sessions");
+    fs::write(
+        sessions_dir.join("s1.json"),
+        "{\"decision\":\"dir is back\"}\n",
+    )
+    .expect("write session");
+    let assert = run().stdout(contains("- sessions_dir=recovered after_secs="));
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(!stderr.contains("SESSIONS_DIR_UNAVAILABLE"));
+    assert!(moon_home.join("archives/ledger.jsonl").exists());
+    let state = fs::read_to_string(&state_file).expect("read state");
+    assert!(!state.contains("sessions_dir_unavailable_since_epoch_secs"));
+    let audit = fs::read_to_string(moon_home.join("moon/logs/audit.log")).expect("read audit");
+    assert!(audit.contains("recovered after_secs="));
+}
+
+#[test]
+#[cfg(not(windows))]
+fn moon_watch_watchdog_aborts_cycle_that_overruns_max_cycle_secs() {
+    let tmp = tempdir().expect("tempdir");
+    let moon_home = tmp.path().join("moon");
+    let sessions_dir = tmp.path().join("sessions");
+    fs::create_dir_all(moon_home.join("moon/logs")).expect("mkdir logs");
+    fs::create_dir_all(&sessions_dir).expect("mkdir sessions");
+    fs::write(
+        sessions_dir.join("s1.json"),
+        "{\"decision\":\"slow index\"}\n",
+    )
+    .expect("write session");
+
+    let qmd = tmp.path().join("qmd");
+    fs::write(&qmd, "#!/usr/bin/env bash\nsleep 1.5\nexit 0\n").expect("write slow qmd");
+    use std::os::unix::fs::PermissionsExt;
+    let mut perms = fs::metadata(&qmd).expect("qmd metadata").permissions();
+    perms.set_mode(0o755);
+    fs::set_permissions(&qmd, perms).expect("chmod qmd");
+    let openclaw = tmp.path().join("openclaw");
+    write_fake_openclaw(&openclaw);
+
+    assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("MOON_HOME", &moon_home)
+        .env("OPENCLAW_SESSIONS_DIR", &sessions_dir)
+        .env("QMD_BIN", &qmd)
+        .env("OPENCLAW_BIN", &openclaw)
+        .env("MOON_TRIGGER_RATIO", "0.00002")
+        .env("MOON_WATCHER_MAX_CYCLE_SECS", "1")
+        .args(["watch", "--once"])
+        .assert()
+        .failure()
+        .stderr(contains("watch cycle aborted by watchdog: phase=distill"));
+
+    let state_raw =
+        fs::read_to_string(moon_home.join("moon/state/moon_state.json")).expect("read state");
+    assert!(state_raw.contains("\"last_heartbeat_epoch_secs\""));
+    let audit = fs::read_to_string(moon_home.join("moon/logs/audit.log")).expect("read audit");
+    assert!(audit.contains("cycle over budget phase=distill max_cycle_secs=1"));
+    assert!(audit.contains("\"phase\":\"watchdog\",\"status\":\"failed\""));
+}
+
+#[test]
+#[cfg(not(windows))]
+fn moon_watch_pause_records_heartbeat_but_skips_mutations_until_resume() {
+    let tmp = tempdir().expect("tempdir");
+    let moon_home = tmp.path().join("moon");
+    let sessions_dir = tmp.path().join("sessions");
+    fs::create_dir_all(moon_home.join("moon/logs")).expect("mkdir logs");
+    fs::create_dir_all(&sessions_dir).expect("mkdir sessions");
+    fs::write(sessions_dir.join("s1.json"), "{\"decision\":\"surgery\"}\n").expect("write session");
+
+    let qmd = tmp.path().join("qmd");
+    write_fake_qmd(&qmd);
+    let openclaw = tmp.path().join("openclaw");
+    write_fake_openclaw(&openclaw);
+
+    let moon = || {
+        let mut cmd = assert_cmd::cargo::cargo_bin_cmd!("moon");
+        cmd.current_dir(tmp.path())
+            .env("MOON_HOME", &moon_home)
+            .env("OPENCLAW_SESSIONS_DIR", &sessions_dir)
+            .env("QMD_BIN", &qmd)
+            .env("OPENCLAW_BIN", &openclaw)
+            .env("MOON_TRIGGER_RATIO", "0.00002");
+        cmd
+    };
+
+    moon()
+        .args(["watch", "pause", "--reason", "ledger surgery"])
+        .assert()
+        .success()
+        .stdout(contains("reason=ledger surgery"));
+    moon()
+        .arg("status")
+        .assert()
+        .stdout(contains("watch.paused=true"));
+
+    moon()
+        .args(["watch", "--once"])
+        .assert()
+        .success()
+        .stdout(contains("paused=true"))
+        .stdout(contains("distill.trigger=skipped reason=paused"));
+    let state_raw =
+        fs::read_to_string(moon_home.join("moon/state/moon_state.json")).expect("read state");
+    assert!(state_raw.contains("\"last_heartbeat_epoch_secs\""));
+    assert!(!moon_home.join("archives/ledger.jsonl").exists());
+
+    moon()
+        .args(["watch", "resume"])
+        .assert()
+        .success()
+        .stdout(contains("watcher resumed"));
+    moon().args(["watch", "--once"]).assert().success();
+    assert!(moon_home.join("archives/ledger.jsonl").exists());
+
+    let audit = fs::read_to_string(moon_home.join("moon/logs/audit.log")).expect("read audit");
+    assert!(audit.contains("\"phase\":\"watch\""));
+    assert!(audit.contains("paused paused_at_epoch_secs="));
+}
+
+#[test]
+#[cfg(not(windows))]
+fn moon_watch_once_archives_without_compacting_between_archive_and_trigger_ratios() {
+    let tmp = tempdir().expect("tempdir");
+    let moon_home = tmp.path().join("moon");
+    let sessions_dir = tmp.path().join("sessions");
+    fs::create_dir_all(moon_home.join("moon/logs")).expect("mkdir logs");
+    fs::create_dir_all(&sessions_dir).expect("mkdir sessions");
+    fs::write(
+        sessions_dir.join("s1.json"),
+        "{\"decision\":\"archive early\"}\n",
+    )
+    .expect("write session");
+
+    let qmd = tmp.path().join("qmd");
+    write_fake_qmd(&qmd);
+    let openclaw = tmp.path().join("openclaw");
+    write_fake_openclaw(&openclaw);
+
+    let run = |enabled: &str| {
+        assert_cmd::cargo::cargo_bin_cmd!("moon")
+            .current_dir(tmp.path())
+            .env("MOON_HOME", &moon_home)
+            .env("OPENCLAW_SESSIONS_DIR", &sessions_dir)
+            .env("QMD_BIN", &qmd)
+            .env("OPENCLAW_BIN", &openclaw)
+            .env("MOON_TRIGGER_RATIO", "0.9")
+            .env("MOON_THRESHOLD_ARCHIVE_RATIO", "0.00002")
+            .env("MOON_ARCHIVE_RATIO_TRIGGER_ENABLED", enabled)
+            .arg("watch")
+            .arg("--once")
+            .assert()
+            .success()
+    };
+
+    // Disabled: the trigger ratio governs both, so nothing fires.
+    run("false")
+        .stdout(contains("threshold.archive=0.9"))
+        .stdout(contains("threshold.archive_trigger_enabled=false"));
+    assert!(!moon_home.join("archives/ledger.jsonl").exists());
+
+    let assert = run("true")
+        .stdout(contains("threshold.archive=0.00002"))
+        .stdout(contains("threshold.compaction=0.9"))
+        .stdout(contains("threshold.archive_trigger_enabled=true"));
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(
+        stdout
+            .lines()
+            .filter_map(|line| line.trim().split_once("triggers="))
+            .any(|(_, names)| names.split(',').eq(["archive", "distill"]))
+    );
+    assert!(moon_home.join("archives/ledger.jsonl").exists());
 }
 
 #[test]
@@ -392,6 +642,8 @@ fn moon_watch_once_triggers_inbound_system_event_for_new_file() {
     assert!(events.contains("system event --text"));
     assert!(events.contains("Moon System inbound file detected"));
     assert!(events.contains("task.md"));
+    assert!(events.contains("size=14 mime=text/markdown"));
+    assert!(events.contains("preview: run this file"));
 
     let state_file = moon_home.join("moon/state/moon_state.json");
     let state_raw = fs::read_to_string(state_file).expect("read state");
@@ -399,137 +651,70 @@ fn moon_watch_once_triggers_inbound_system_event_for_new_file() {
 }
 
 #[test]
-#[cfg(not(windows))]
-fn moon_watch_once_compacts_all_oversized_discord_and_whatsapp_sessions() {
+#[cfg(all(not(windows), feature = "lua-policy"))]
+fn moon_watch_once_lets_policy_script_override_triggers() {
     let tmp = tempdir().expect("tempdir");
     let moon_home = tmp.path().join("moon");
     let sessions_dir = tmp.path().join("sessions");
-    let compact_log = tmp.path().join("compact.log");
-    fs::create_dir_all(moon_home.join("archives")).expect("mkdir archives");
-    fs::create_dir_all(moon_home.join("memory")).expect("mkdir memory");
     fs::create_dir_all(moon_home.join("moon/logs")).expect("mkdir logs");
     fs::create_dir_all(&sessions_dir).expect("mkdir sessions");
+    fs::write(sessions_dir.join("s1.json"), "{\"decision\":\"policy\"}\n").expect("write session");
+    let script = tmp.path().join("policy.lua");
     fs::write(
-        sessions_dir.join("s1.json"),
-        "{\"decision\":\"compact channels\"}\n",
-    )
-    .expect("write session");
-    fs::write(
-        sessions_dir.join("sess-over.jsonl"),
-        "{\"messages\":[\"discord oversized\"]}\n",
-    )
-    .expect("write over session");
-    fs::write(
-        sessions_dir.join("sess-wa.jsonl"),
-        "{\"messages\":[\"whatsapp oversized\"]}\n",
-    )
-    .expect("write wa session");
-    fs::write(
-        sessions_dir.join("sessions.json"),
-        r#"{
-            "agent:main:discord:channel:over": {"sessionId":"sess-over"},
-            "agent:main:whatsapp:+61400000000": {"sessionId":"sess-wa"},
-            "agent:main:discord:channel:small": {"sessionId":"sess-small"},
-            "agent:main:main": {"sessionId":"sess-main"}
-        }"#,
+        &script,
+        "function decide(ctx)\n  if ctx.usage.usage_ratio < 0.5 then return {} end\n  return nil\nend\n",
     )
-    .expect("write sessions map");
+    .expect("write policy");
 
     let qmd = tmp.path().join("qmd");
     write_fake_qmd(&qmd);
     let openclaw = tmp.path().join("openclaw");
     write_fake_openclaw(&openclaw);
 
-    let sessions_json = r#"{"path":"x","count":4,"sessions":[
-        {"key":"agent:main:discord:channel:over","totalTokens":29000,"contextTokens":32000},
-        {"key":"agent:main:whatsapp:+61400000000","totalTokens":70000,"contextTokens":80000},
-        {"key":"agent:main:discord:channel:small","totalTokens":1000,"contextTokens":32000},
-        {"key":"agent:main:main","totalTokens":90000,"contextTokens":100000}
-    ]}"#;
-
-    assert_cmd::cargo::cargo_bin_cmd!("moon")
+    let assert = assert_cmd::cargo::cargo_bin_cmd!("moon")
         .current_dir(tmp.path())
         .env("MOON_HOME", &moon_home)
         .env("OPENCLAW_SESSIONS_DIR", &sessions_dir)
         .env("QMD_BIN", &qmd)
         .env("OPENCLAW_BIN", &openclaw)
-        .env("MOON_TEST_SESSIONS_JSON", sessions_json)
-        .env(
-            "MOON_TEST_CURRENT_JSON",
-            r#"{"sessionId":"agent:main:main","usage":{"totalTokens":120},"limits":{"maxTokens":10000}}"#,
-        )
-        .env("MOON_TEST_COMPACT_LOG", &compact_log)
-        .env("MOON_TRIGGER_RATIO", "0.85")
-        .env("MOON_COOLDOWN_SECS", "0")
+        .env("MOON_TRIGGER_RATIO", "0.00002")
+        .env("MOON_POLICY_SCRIPT", &script)
         .arg("watch")
         .arg("--once")
         .assert()
         .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(
+        stdout
+            .lines()
+            .any(|line| line.trim_end().ends_with("triggers="))
+    );
 
-    let compact_calls = fs::read_to_string(&compact_log).expect("read compact log");
-    assert!(compact_calls.contains("agent:main:discord:channel:over"));
-    assert!(compact_calls.contains("agent:main:whatsapp:+61400000000"));
-    assert!(compact_calls.contains("MOON_ARCHIVE_INDEX"));
-    assert!(compact_calls.contains("moon-index-note"));
-    assert!(!compact_calls.contains("agent:main:discord:channel:small"));
-    assert!(!compact_calls.contains("agent:main:main"));
-
-    let ledger = fs::read_to_string(moon_home.join("archives/ledger.jsonl")).expect("read ledger");
-    assert!(ledger.contains("sess-over.jsonl"));
-    assert!(ledger.contains("sess-wa.jsonl"));
-    assert!(ledger.contains("\"projection_path\":"));
-    assert!(ledger.contains(".md"));
-
-    let mlib_archives_dir = moon_home.join("archives/mlib");
-    let projection_count = fs::read_dir(&mlib_archives_dir)
-        .expect("read mlib archives dir")
-        .flatten()
-        .map(|entry| entry.path())
-        .filter(|path| path.extension().and_then(|ext| ext.to_str()) == Some("md"))
-        .count();
-    assert!(projection_count >= 2);
-
-    let channel_map = fs::read_to_string(moon_home.join("continuity/channel_archive_map.json"))
-        .expect("read channel archive map");
-    assert!(channel_map.contains("agent:main:discord:channel:over"));
-    assert!(channel_map.contains("agent:main:whatsapp:+61400000000"));
+    let audit = fs::read_to_string(moon_home.join("moon/logs/audit.log")).expect("read audit");
+    assert!(audit.contains("\"phase\":\"policy\",\"status\":\"ok\""));
+    assert!(audit.contains("default=[archive,compaction] decided=[]"));
+    assert!(!moon_home.join("archives/ledger.jsonl").exists());
 }
 
 #[test]
 #[cfg(not(windows))]
-fn moon_watch_once_distills_oldest_pending_archive_day_first() {
+fn moon_watch_once_runs_post_archive_hook_with_event_context() {
     let tmp = tempdir().expect("tempdir");
     let moon_home = tmp.path().join("moon");
     let sessions_dir = tmp.path().join("sessions");
-    fs::create_dir_all(moon_home.join("archives/raw")).expect("mkdir archives raw");
-    fs::create_dir_all(moon_home.join("archives/mlib")).expect("mkdir archives mlib");
-    fs::create_dir_all(moon_home.join("memory")).expect("mkdir memory");
+    let hook_out = tmp.path().join("hook.out");
     fs::create_dir_all(moon_home.join("moon/logs")).expect("mkdir logs");
     fs::create_dir_all(&sessions_dir).expect("mkdir sessions");
+    fs::write(sessions_dir.join("s1.json"), "{\"decision\":\"hook\"}\n").expect("write session");
+    let config_path = tmp.path().join("moon.toml");
     fs::write(
-        sessions_dir.join("s1.json"),
-        "{\"decision\":\"distill ordering\"}\n",
-    )
-    .expect("write session");
-
-    let old_archive = moon_home.join("archives/raw/old.jsonl");
-    let new_archive = moon_home.join("archives/raw/new.jsonl");
-    let old_projection = moon_home.join("archives/mlib/old.md");
-    let new_projection = moon_home.join("archives/mlib/new.md");
-    fs::write(&old_archive, "{\"session\":\"old\"}\n").expect("write old archive");
-    fs::write(&new_archive, "{\"session\":\"new\"}\n").expect("write new archive");
-    fs::write(&old_projection, "- [user] old projection\n").expect("write old projection");
-    fs::write(&new_projection, "- [user] new projection\n").expect("write new projection");
-
-    let ledger = format!(
-        concat!(
-            "{{\"session_id\":\"old\",\"source_path\":\"/tmp/old.jsonl\",\"archive_path\":\"{}\",\"content_hash\":\"aaa\",\"created_at_epoch_secs\":86400,\"indexed_collection\":\"history\",\"indexed\":true}}\n",
-            "{{\"session_id\":\"new\",\"source_path\":\"/tmp/new.jsonl\",\"archive_path\":\"{}\",\"content_hash\":\"bbb\",\"created_at_epoch_secs\":172800,\"indexed_collection\":\"history\",\"indexed\":true}}\n"
+        &config_path,
+        format!(
+            "[hooks]\npost_archive = \"printf '%s|%s|%s' \\\"$MOON_HOOK_EVENT\\\" \\\"$MOON_HOOK_SESSION_ID\\\" \\\"$MOON_HOOK_ARCHIVE_PATH\\\" > {}\"\ntimeout_secs = 5\n",
+            hook_out.display()
         ),
-        old_archive.display(),
-        new_archive.display()
-    );
-    fs::write(moon_home.join("archives/ledger.jsonl"), ledger).expect("write ledger");
+    )
+    .expect("write config");
 
     let qmd = tmp.path().join("qmd");
     write_fake_qmd(&qmd);
@@ -539,34 +724,731 @@ fn moon_watch_once_distills_oldest_pending_archive_day_first() {
     assert_cmd::cargo::cargo_bin_cmd!("moon")
         .current_dir(tmp.path())
         .env("MOON_HOME", &moon_home)
+        .env("MOON_CONFIG_PATH", &config_path)
         .env("OPENCLAW_SESSIONS_DIR", &sessions_dir)
         .env("QMD_BIN", &qmd)
         .env("OPENCLAW_BIN", &openclaw)
-        .env("MOON_DISTILL_PROVIDER", "local")
-        .env("MOON_DISTILL_MAX_PER_CYCLE", "1")
-        .env("MOON_COOLDOWN_SECS", "0")
-        .env("MOON_RETENTION_COLD_DAYS", "99999")
+        .env("MOON_TRIGGER_RATIO", "0.00002")
         .arg("watch")
         .arg("--once")
         .assert()
         .success();
 
-    let distilled = read_distilled_archive_paths(&moon_home.join("moon/state/moon_state.json"));
-    assert_eq!(distilled.len(), 1);
-    assert!(distilled.contains(&old_archive.to_string_lossy().to_string()));
-    assert!(!distilled.contains(&new_archive.to_string_lossy().to_string()));
+    let hook = fs::read_to_string(&hook_out).expect("hook ran");
+    let fields: Vec<&str> = hook.split('|').collect();
+    assert_eq!(fields[0], "post_archive");
+    assert_eq!(fields[1], "s1");
+    assert!(fields[2].ends_with(".json"), "archive path: {hook}");
+
+    let audit = fs::read_to_string(moon_home.join("moon/logs/audit.log")).expect("read audit");
+    assert!(audit.contains("\"phase\":\"hook\",\"status\":\"ok\""));
 }
 
 #[test]
 #[cfg(not(windows))]
-fn moon_watch_once_distill_selection_skips_unindexed_missing_and_already_distilled() {
+fn moon_watch_once_sends_json_inbound_event_via_gateway_call() {
     let tmp = tempdir().expect("tempdir");
     let moon_home = tmp.path().join("moon");
     let sessions_dir = tmp.path().join("sessions");
-    fs::create_dir_all(moon_home.join("archives/raw")).expect("mkdir archives raw");
-    fs::create_dir_all(moon_home.join("archives/mlib")).expect("mkdir archives mlib");
-    fs::create_dir_all(moon_home.join("memory")).expect("mkdir memory");
-    fs::create_dir_all(moon_home.join("moon/logs")).expect("mkdir logs");
+    let inbound_dir = tmp.path().join("inbound");
+    let event_log = tmp.path().join("events.log");
+    fs::create_dir_all(moon_home.join("moon/logs")).expect("mkdir logs");
+    fs::create_dir_all(&sessions_dir).expect("mkdir sessions");
+    fs::create_dir_all(&inbound_dir).expect("mkdir inbound");
+    fs::write(sessions_dir.join("s1.json"), "{\"decision\":\"json\"}\n").expect("write session");
+    fs::write(inbound_dir.join("data.csv"), "a,b\n1,2\n").expect("write inbound file");
+
+    let qmd = tmp.path().join("qmd");
+    write_fake_qmd(&qmd);
+    let openclaw = tmp.path().join("openclaw");
+    write_fake_openclaw(&openclaw);
+
+    assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("MOON_HOME", &moon_home)
+        .env("OPENCLAW_SESSIONS_DIR", &sessions_dir)
+        .env("QMD_BIN", &qmd)
+        .env("OPENCLAW_BIN", &openclaw)
+        .env("MOON_TEST_EVENT_LOG", &event_log)
+        .env("MOON_TRIGGER_RATIO", "0.00002")
+        .env("MOON_INBOUND_WATCH_ENABLED", "true")
+        .env("MOON_INBOUND_EVENT_FORMAT", "json")
+        .env("MOON_INBOUND_WATCH_PATHS", &inbound_dir)
+        .arg("watch")
+        .arg("--once")
+        .assert()
+        .success();
+
+    let events = fs::read_to_string(&event_log).expect("read event log");
+    let line = events
+        .lines()
+        .find(|line| line.starts_with("gateway call wake --json --params "))
+        .expect("wake call");
+    let params: Value =
+        serde_json::from_str(line.trim_start_matches("gateway call wake --json --params "))
+            .expect("params json");
+    assert_eq!(params["mode"], "now");
+    let payload: Value =
+        serde_json::from_str(params["text"].as_str().expect("text")).expect("payload json");
+    assert_eq!(payload["type"], "inbound_file");
+    assert_eq!(payload["file_name"], "data.csv");
+    assert_eq!(payload["mime"], "text/csv");
+    assert_eq!(payload["size_bytes"], 8);
+    assert_eq!(payload["preview"], "a,b 1,2");
+}
+
+#[test]
+#[cfg(not(windows))]
+fn moon_watch_once_reports_content_diff_for_watched_single_file() {
+    let tmp = tempdir().expect("tempdir");
+    let moon_home = tmp.path().join("moon");
+    let sessions_dir = tmp.path().join("sessions");
+    let todo = tmp.path().join("TODO.md");
+    let later = tmp.path().join("LATER.md");
+    let event_log = tmp.path().join("events.log");
+    fs::create_dir_all(moon_home.join("archives")).expect("mkdir archives");
+    fs::create_dir_all(moon_home.join("memory")).expect("mkdir memory");
+    fs::create_dir_all(moon_home.join("moon/logs")).expect("mkdir logs");
+    fs::create_dir_all(&sessions_dir).expect("mkdir sessions");
+    fs::write(
+        sessions_dir.join("s1.json"),
+        "{\"decision\":\"watch file\"}\n",
+    )
+    .expect("write session");
+    fs::write(&todo, "- first task\n").expect("write todo");
+
+    let qmd = tmp.path().join("qmd");
+    write_fake_qmd(&qmd);
+    let openclaw = tmp.path().join("openclaw");
+    write_fake_openclaw(&openclaw);
+
+    let run_watch = || {
+        assert_cmd::cargo::cargo_bin_cmd!("moon")
+            .current_dir(tmp.path())
+            .env("MOON_HOME", &moon_home)
+            .env("OPENCLAW_SESSIONS_DIR", &sessions_dir)
+            .env("QMD_BIN", &qmd)
+            .env("OPENCLAW_BIN", &openclaw)
+            .env("MOON_TEST_EVENT_LOG", &event_log)
+            .env("MOON_TRIGGER_RATIO", "0.00002")
+            .env("MOON_INBOUND_WATCH_ENABLED", "true")
+            .env(
+                "MOON_INBOUND_WATCH_PATHS",
+                format!("{},{}", todo.display(), later.display()),
+            )
+            .arg("watch")
+            .arg("--once")
+            .assert()
+            .success();
+    };
+
+    run_watch();
+    let events = fs::read_to_string(&event_log).expect("read event log");
+    assert!(events.contains("Moon System inbound file detected: TODO.md"));
+    assert!(
+        !later.exists(),
+        "missing file watch path must not be created"
+    );
+
+    fs::remove_file(&event_log).expect("reset event log");
+    run_watch();
+    assert!(
+        !event_log.exists()
+            || !fs::read_to_string(&event_log)
+                .expect("read event log")
+                .contains("TODO.md"),
+        "unchanged file must not trigger again"
+    );
+
+    fs::write(&todo, "- first task\n- second task\n").expect("update todo");
+    run_watch();
+    let events = fs::read_to_string(&event_log).expect("read event log");
+    assert!(events.contains("Moon System watched file changed: TODO.md"));
+    assert!(events.contains("lines +1 -0"));
+    assert!(events.contains("+ - second task"));
+}
+
+#[test]
+#[cfg(not(windows))]
+fn moon_watch_once_compacts_all_oversized_discord_and_whatsapp_sessions() {
+    let tmp = tempdir().expect("tempdir");
+    let moon_home = tmp.path().join("moon");
+    let sessions_dir = tmp.path().join("sessions");
+    let compact_log = tmp.path().join("compact.log");
+    fs::create_dir_all(moon_home.join("archives")).expect("mkdir archives");
+    fs::create_dir_all(moon_home.join("memory")).expect("mkdir memory");
+    fs::create_dir_all(moon_home.join("moon/logs")).expect("mkdir logs");
+    fs::create_dir_all(&sessions_dir).expect("mkdir sessions");
+    fs::write(
+        sessions_dir.join("s1.json"),
+        "{\"decision\":\"compact channels\"}\n",
+    )
+    .expect("write session");
+    fs::write(
+        sessions_dir.join("sess-over.jsonl"),
+        "{\"messages\":[\"discord oversized\"]}\n",
+    )
+    .expect("write over session");
+    fs::write(
+        sessions_dir.join("sess-wa.jsonl"),
+        "{\"messages\":[\"whatsapp oversized\"]}\n",
+    )
+    .expect("write wa session");
+    fs::write(
+        sessions_dir.join("sessions.json"),
+        r#"{
+            "agent:main:discord:channel:over": {"sessionId":"sess-over"},
+            "agent:main:whatsapp:+61400000000": {"sessionId":"sess-wa"},
+            "agent:main:discord:channel:small": {"sessionId":"sess-small"},
+            "agent:main:main": {"sessionId":"sess-main"}
+        }"#,
+    )
+    .expect("write sessions map");
+
+    let qmd = tmp.path().join("qmd");
+    write_fake_qmd(&qmd);
+    let openclaw = tmp.path().join("openclaw");
+    write_fake_openclaw(&openclaw);
+
+    let sessions_json = r#"{"path":"x","count":4,"sessions":[
+        {"key":"agent:main:discord:channel:over","totalTokens":29000,"contextTokens":32000},
+        {"key":"agent:main:whatsapp:+61400000000","totalTokens":70000,"contextTokens":80000},
+        {"key":"agent:main:discord:channel:small","totalTokens":1000,"contextTokens":32000},
+        {"key":"agent:main:main","totalTokens":90000,"contextTokens":100000}
+    ]}"#;
+
+    assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("MOON_HOME", &moon_home)
+        .env("OPENCLAW_SESSIONS_DIR", &sessions_dir)
+        .env("QMD_BIN", &qmd)
+        .env("OPENCLAW_BIN", &openclaw)
+        .env("MOON_TEST_SESSIONS_JSON", sessions_json)
+        .env(
+            "MOON_TEST_CURRENT_JSON",
+            r#"{"sessionId":"agent:main:main","usage":{"totalTokens":120},"limits":{"maxTokens":10000}}"#,
+        )
+        .env("MOON_TEST_COMPACT_LOG", &compact_log)
+        .env("MOON_TRIGGER_RATIO", "0.85")
+        .env("MOON_COOLDOWN_SECS", "0")
+        .arg("watch")
+        .arg("--once")
+        .assert()
+        .success();
+
+    let compact_calls = fs::read_to_string(&compact_log).expect("read compact log");
+    assert!(compact_calls.contains("agent:main:discord:channel:over"));
+    assert!(compact_calls.contains("agent:main:whatsapp:+61400000000"));
+    assert!(compact_calls.contains("MOON_ARCHIVE_INDEX"));
+    assert!(compact_calls.contains("moon-index-note"));
+    assert!(!compact_calls.contains("agent:main:discord:channel:small"));
+    assert!(!compact_calls.contains("agent:main:main"));
+
+    let ledger = fs::read_to_string(moon_home.join("archives/ledger.jsonl")).expect("read ledger");
+    assert!(ledger.contains("sess-over.jsonl"));
+    assert!(ledger.contains("sess-wa.jsonl"));
+    assert!(ledger.contains("\"projection_path\":"));
+    assert!(ledger.contains(".md"));
+
+    let mlib_archives_dir = moon_home.join("archives/mlib");
+    let projection_count = fs::read_dir(&mlib_archives_dir)
+        .expect("read mlib archives dir")
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().and_then(|ext| ext.to_str()) == Some("md"))
+        .count();
+    assert!(projection_count >= 2);
+
+    let channel_map = fs::read_to_string(moon_home.join("continuity/channel_archive_map.json"))
+        .expect("read channel archive map");
+    assert!(channel_map.contains("agent:main:discord:channel:over"));
+    assert!(channel_map.contains("agent:main:whatsapp:+61400000000"));
+}
+
+#[test]
+#[cfg(not(windows))]
+fn moon_watch_records_gateway_compaction_summary_on_pre_compaction_archive() {
+    let tmp = tempdir().expect("tempdir");
+    let moon_home = tmp.path().join("moon");
+    let sessions_dir = tmp.path().join("sessions");
+    fs::create_dir_all(moon_home.join("archives")).expect("mkdir archives");
+    fs::create_dir_all(moon_home.join("memory")).expect("mkdir memory");
+    fs::create_dir_all(moon_home.join("moon/logs")).expect("mkdir logs");
+    fs::create_dir_all(&sessions_dir).expect("mkdir sessions");
+    fs::write(
+        sessions_dir.join("sess-over.jsonl"),
+        "{\"messages\":[\"discord oversized\"]}\n",
+    )
+    .expect("write over session");
+    fs::write(
+        sessions_dir.join("sessions.json"),
+        r#"{"agent:main:discord:channel:over": {"sessionId":"sess-over"}}"#,
+    )
+    .expect("write sessions map");
+
+    let qmd = tmp.path().join("qmd");
+    write_fake_qmd(&qmd);
+    let openclaw = tmp.path().join("openclaw");
+    write_fake_openclaw(&openclaw);
+    let watch = |cooldown_secs: &str, history: &str| {
+        let assert = assert_cmd::cargo::cargo_bin_cmd!("moon")
+            .current_dir(tmp.path())
+            .env("MOON_HOME", &moon_home)
+            .env("OPENCLAW_SESSIONS_DIR", &sessions_dir)
+            .env("QMD_BIN", &qmd)
+            .env("OPENCLAW_BIN", &openclaw)
+            .env(
+                "MOON_TEST_SESSIONS_JSON",
+                r#"{"path":"x","count":1,"sessions":[{"key":"agent:main:discord:channel:over","totalTokens":29000,"contextTokens":32000}]}"#,
+            )
+            .env("MOON_TEST_CHAT_HISTORY_JSON", history)
+            .env("MOON_TRIGGER_RATIO", "0.85")
+            .env("MOON_COOLDOWN_SECS", cooldown_secs)
+            .args(["watch", "--once"])
+            .assert()
+            .success();
+        String::from_utf8_lossy(&assert.get_output().stdout).to_string()
+    };
+
+    let first = watch("0", r#"{"messages":[]}"#);
+    assert!(first.contains("compaction.result=targets=1 succeeded=1"));
+    assert!(!first.contains("compaction_anchors.result="));
+
+    let second = watch(
+        "86400",
+        r#"{"messages":[
+            {"type":"compaction","id":"old","summary":"Stale summary.","timestamp":1000},
+            {"type":"compaction","id":"c42","summary":"Agreed to freeze the API.","timestamp":4102444800}
+        ]}"#,
+    );
+    assert!(second.contains("compaction_anchors.result=recorded=1 waiting=0 expired=0"));
+
+    let ledger = fs::read_to_string(moon_home.join("archives/ledger.jsonl")).expect("read ledger");
+    let row: Value =
+        serde_json::from_str(ledger.lines().next().expect("ledger row")).expect("parse ledger row");
+    assert_eq!(
+        row["compaction_anchors"],
+        serde_json::json!([{"note": "Agreed to freeze the API.", "origin_message_id": "c42"}])
+    );
+    let projection = fs::read_to_string(row["projection_path"].as_str().expect("projection"))
+        .expect("read projection");
+    assert!(projection.contains("- Agreed to freeze the API. (Origin: `c42`)"));
+    assert!(!projection.contains("Stale summary."));
+
+    let third = watch("86400", r#"{"messages":[]}"#);
+    assert!(!third.contains("compaction_anchors.result="));
+}
+
+#[test]
+fn moon_watch_once_applies_sessions_filters_to_compaction_and_archive() {
+    let tmp = tempdir().expect("tempdir");
+    let moon_home = tmp.path().join("moon");
+    let sessions_dir = tmp.path().join("sessions");
+    let compact_log = tmp.path().join("compact.log");
+    fs::create_dir_all(moon_home.join("archives")).expect("mkdir archives");
+    fs::create_dir_all(moon_home.join("memory")).expect("mkdir memory");
+    fs::create_dir_all(moon_home.join("moon/logs")).expect("mkdir logs");
+    fs::create_dir_all(&sessions_dir).expect("mkdir sessions");
+    for (name, body) in [
+        ("sess-over.jsonl", "discord oversized"),
+        ("sess-wa.jsonl", "whatsapp oversized"),
+        ("sess-main.jsonl", "main oversized"),
+    ] {
+        fs::write(
+            sessions_dir.join(name),
+            format!("{{\"messages\":[\"{body}\"]}}\n"),
+        )
+        .expect("write session");
+    }
+    fs::write(
+        sessions_dir.join("sessions.json"),
+        r#"{
+            "agent:main:discord:channel:over": {"sessionId":"sess-over"},
+            "agent:main:whatsapp:+61400000000": {"sessionId":"sess-wa"},
+            "agent:main:main": {"sessionId":"sess-main"}
+        }"#,
+    )
+    .expect("write sessions map");
+    fs::write(
+        moon_home.join("moon.toml"),
+        r#"[sessions.compaction]
+include = ["*:whatsapp:*", "agent:main:main"]
+
+[sessions.archive]
+exclude = ["*:WhatsApp:*"]
+"#,
+    )
+    .expect("write moon config");
+
+    let qmd = tmp.path().join("qmd");
+    write_fake_qmd(&qmd);
+    let openclaw = tmp.path().join("openclaw");
+    write_fake_openclaw(&openclaw);
+    let sessions_json = r#"{"path":"x","count":3,"sessions":[
+        {"key":"agent:main:discord:channel:over","totalTokens":29000,"contextTokens":32000},
+        {"key":"agent:main:whatsapp:+61400000000","totalTokens":70000,"contextTokens":80000},
+        {"key":"agent:main:main","totalTokens":90000,"contextTokens":100000}
+    ]}"#;
+
+    let assert = assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("MOON_HOME", &moon_home)
+        .env("MOON_CONFIG_PATH", moon_home.join("moon.toml"))
+        .env("OPENCLAW_SESSIONS_DIR", &sessions_dir)
+        .env("QMD_BIN", &qmd)
+        .env("OPENCLAW_BIN", &openclaw)
+        .env("MOON_TEST_SESSIONS_JSON", sessions_json)
+        .env("MOON_TEST_COMPACT_LOG", &compact_log)
+        .env("MOON_TRIGGER_RATIO", "0.85")
+        .env("MOON_COOLDOWN_SECS", "0")
+        .arg("watch")
+        .arg("--once")
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout.contains(
+        "skipped key=agent:main:whatsapp:+61400000000 ratio=0.8750 reason=sessions-excluded"
+    ));
+
+    let compact_calls = fs::read_to_string(&compact_log).expect("read compact log");
+    assert!(compact_calls.contains("agent:main:main"));
+    assert!(!compact_calls.contains("agent:main:discord:channel:over"));
+    assert!(!compact_calls.contains("agent:main:whatsapp"));
+
+    let ledger = fs::read_to_string(moon_home.join("archives/ledger.jsonl")).expect("read ledger");
+    assert!(ledger.contains("sess-main.jsonl"));
+    assert!(!ledger.contains("sess-wa.jsonl"));
+}
+
+#[test]
+fn moon_watch_once_predictively_archives_channel_projected_to_cross_threshold() {
+    let tmp = tempdir().expect("tempdir");
+    let moon_home = tmp.path().join("moon");
+    let sessions_dir = tmp.path().join("sessions");
+    let compact_log = tmp.path().join("compact.log");
+    fs::create_dir_all(moon_home.join("archives")).expect("mkdir archives");
+    fs::create_dir_all(moon_home.join("memory")).expect("mkdir memory");
+    fs::create_dir_all(moon_home.join("moon/logs")).expect("mkdir logs");
+    fs::create_dir_all(moon_home.join("moon/state")).expect("mkdir state");
+    fs::create_dir_all(&sessions_dir).expect("mkdir sessions");
+    fs::write(
+        sessions_dir.join("sess-grow.jsonl"),
+        "{\"messages\":[\"discord growing fast\"]}\n",
+    )
+    .expect("write grow session");
+    fs::write(
+        sessions_dir.join("sessions.json"),
+        r#"{"agent:main:discord:channel:grow": {"sessionId":"sess-grow"}}"#,
+    )
+    .expect("write sessions map");
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("clock")
+        .as_secs();
+    fs::write(
+        moon_home.join("moon/state/moon_state.json"),
+        serde_json::json!({
+            "schema_version": 3,
+            "usage_trends": {
+                "agent:main:discord:channel:grow": {
+                    "samples": [
+                        {"epoch_secs": now - 60, "ratio": 0.55},
+                        {"epoch_secs": now - 30, "ratio": 0.65}
+                    ]
+                }
+            }
+        })
+        .to_string(),
+    )
+    .expect("write state");
+
+    let qmd = tmp.path().join("qmd");
+    write_fake_qmd(&qmd);
+    let openclaw = tmp.path().join("openclaw");
+    write_fake_openclaw(&openclaw);
+
+    let sessions_json = r#"{"path":"x","count":1,"sessions":[
+        {"key":"agent:main:discord:channel:grow","totalTokens":24320,"contextTokens":32000}
+    ]}"#;
+
+    let assert = assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("MOON_HOME", &moon_home)
+        .env("OPENCLAW_SESSIONS_DIR", &sessions_dir)
+        .env("QMD_BIN", &qmd)
+        .env("OPENCLAW_BIN", &openclaw)
+        .env("MOON_TEST_SESSIONS_JSON", sessions_json)
+        .env("MOON_TEST_COMPACT_LOG", &compact_log)
+        .env("MOON_TRIGGER_RATIO", "0.85")
+        .env("MOON_POLL_INTERVAL_SECS", "30")
+        .env("MOON_COOLDOWN_SECS", "0")
+        .env("MOON_PREDICTIVE_TRIGGER", "true")
+        .arg("watch")
+        .arg("--once")
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout.contains("predictive-archive"));
+    assert!(stdout.contains("predictive_archive.result=threshold=0.8500 targets=1 archived=1"));
+
+    let ledger = fs::read_to_string(moon_home.join("archives/ledger.jsonl")).expect("read ledger");
+    assert!(ledger.contains("sess-grow.jsonl"));
+    assert!(!compact_log.exists());
+
+    let state: Value = serde_json::from_str(
+        &fs::read_to_string(moon_home.join("moon/state/moon_state.json")).expect("read state"),
+    )
+    .expect("parse state");
+    let trend = &state["usage_trends"]["agent:main:discord:channel:grow"];
+    assert_eq!(trend["samples"].as_array().map(Vec::len), Some(3));
+    assert!(
+        trend["last_predictive_archive_epoch_secs"]
+            .as_u64()
+            .is_some()
+    );
+}
+
+#[test]
+fn moon_watch_once_archives_sessions_idle_past_idle_archive_secs() {
+    let tmp = tempdir().expect("tempdir");
+    let moon_home = tmp.path().join("moon");
+    let sessions_dir = tmp.path().join("sessions");
+    fs::create_dir_all(moon_home.join("archives")).expect("mkdir archives");
+    fs::create_dir_all(moon_home.join("memory")).expect("mkdir memory");
+    fs::create_dir_all(moon_home.join("moon/logs")).expect("mkdir logs");
+    fs::create_dir_all(&sessions_dir).expect("mkdir sessions");
+    let quiet = sessions_dir.join("sess-quiet.jsonl");
+    fs::write(&quiet, "{\"messages\":[\"discord went quiet\"]}\n").expect("write quiet session");
+    fs::File::options()
+        .write(true)
+        .open(&quiet)
+        .expect("open quiet session")
+        .set_modified(SystemTime::now() - std::time::Duration::from_secs(600))
+        .expect("backdate quiet session");
+    fs::write(
+        sessions_dir.join("sess-busy.jsonl"),
+        "{\"messages\":[\"discord still chatting\"]}\n",
+    )
+    .expect("write busy session");
+    fs::write(
+        sessions_dir.join("sessions.json"),
+        r#"{"agent:main:discord:channel:quiet": {"sessionId":"sess-quiet"},
+            "agent:main:discord:channel:busy": {"sessionId":"sess-busy"}}"#,
+    )
+    .expect("write sessions map");
+
+    let qmd = tmp.path().join("qmd");
+    write_fake_qmd(&qmd);
+    let openclaw = tmp.path().join("openclaw");
+    write_fake_openclaw(&openclaw);
+    let sessions_json = r#"{"path":"x","count":2,"sessions":[
+        {"key":"agent:main:discord:channel:quiet","totalTokens":1000,"contextTokens":32000},
+        {"key":"agent:main:discord:channel:busy","totalTokens":1000,"contextTokens":32000}
+    ]}"#;
+    let run = || {
+        let assert = assert_cmd::cargo::cargo_bin_cmd!("moon")
+            .current_dir(tmp.path())
+            .env("MOON_HOME", &moon_home)
+            .env("OPENCLAW_SESSIONS_DIR", &sessions_dir)
+            .env("QMD_BIN", &qmd)
+            .env("OPENCLAW_BIN", &openclaw)
+            .env("MOON_TEST_SESSIONS_JSON", sessions_json)
+            .env("MOON_WATCHER_IDLE_ARCHIVE_SECS", "300")
+            .env("MOON_WATCHER_CONSISTENCY_CHECK_EVERY", "2")
+            .arg("watch")
+            .arg("--once")
+            .assert()
+            .success();
+        String::from_utf8_lossy(&assert.get_output().stdout).to_string()
+    };
+
+    let stdout = run();
+    assert!(stdout.contains("idle-archive"));
+    assert!(stdout.contains("idle_archive.result=idle_secs=300 targets=1 archived=1 failed=0"));
+    assert!(stdout.contains("key=agent:main:discord:channel:quiet"));
+    assert!(!stdout.contains("consistency.result="));
+    let ledger = fs::read_to_string(moon_home.join("archives/ledger.jsonl")).expect("read ledger");
+    assert!(ledger.contains("sess-quiet.jsonl"));
+    assert!(!ledger.contains("sess-busy.jsonl"));
+
+    let stdout = run();
+    assert!(!stdout.contains("idle_archive.result="));
+    assert!(stdout.contains("consistency.result=cycle=2 findings=none"));
+    let ledger = fs::read_to_string(moon_home.join("archives/ledger.jsonl")).expect("read ledger");
+    assert_eq!(ledger.matches("sess-quiet.jsonl").count(), 1);
+}
+
+#[test]
+#[cfg(not(windows))]
+fn moon_watch_watchdog_stops_idle_archive_loop_between_sessions() {
+    let tmp = tempdir().expect("tempdir");
+    let moon_home = tmp.path().join("moon");
+    let sessions_dir = tmp.path().join("sessions");
+    fs::create_dir_all(moon_home.join("moon/logs")).expect("mkdir logs");
+    fs::create_dir_all(&sessions_dir).expect("mkdir sessions");
+    for id in ["sess-a", "sess-b"] {
+        let path = sessions_dir.join(format!("{id}.jsonl"));
+        fs::write(&path, format!("{{\"messages\":[\"{id} went quiet\"]}}\n"))
+            .expect("write session");
+        fs::File::options()
+            .write(true)
+            .open(&path)
+            .expect("open session")
+            .set_modified(SystemTime::now() - std::time::Duration::from_secs(600))
+            .expect("backdate session");
+    }
+    fs::write(
+        sessions_dir.join("sessions.json"),
+        r#"{"agent:main:discord:channel:a": {"sessionId":"sess-a"},
+            "agent:main:discord:channel:b": {"sessionId":"sess-b"}}"#,
+    )
+    .expect("write sessions map");
+    let config_path = tmp.path().join("moon.toml");
+    fs::write(
+        &config_path,
+        "[hooks]\npost_archive = \"sleep 1.5\"\ntimeout_secs = 5\n",
+    )
+    .expect("write config");
+
+    let qmd = tmp.path().join("qmd");
+    write_fake_qmd(&qmd);
+    let openclaw = tmp.path().join("openclaw");
+    write_fake_openclaw(&openclaw);
+    let sessions_json = r#"{"path":"x","count":2,"sessions":[
+        {"key":"agent:main:discord:channel:a","totalTokens":1000,"contextTokens":32000},
+        {"key":"agent:main:discord:channel:b","totalTokens":1000,"contextTokens":32000}
+    ]}"#;
+    assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("MOON_HOME", &moon_home)
+        .env("MOON_CONFIG_PATH", &config_path)
+        .env("OPENCLAW_SESSIONS_DIR", &sessions_dir)
+        .env("QMD_BIN", &qmd)
+        .env("OPENCLAW_BIN", &openclaw)
+        .env("MOON_TEST_SESSIONS_JSON", sessions_json)
+        .env("MOON_WATCHER_IDLE_ARCHIVE_SECS", "300")
+        .env("MOON_WATCHER_MAX_CYCLE_SECS", "1")
+        .args(["watch", "--once"])
+        .assert()
+        .failure()
+        .stderr(contains(
+            "watch cycle aborted by watchdog: phase=idle-archive",
+        ));
+
+    let ledger = fs::read_to_string(moon_home.join("archives/ledger.jsonl")).expect("read ledger");
+    assert_eq!(ledger.lines().count(), 1, "ledger: {ledger}");
+    let state_raw =
+        fs::read_to_string(moon_home.join("moon/state/moon_state.json")).expect("read state");
+    assert!(state_raw.contains("\"last_heartbeat_epoch_secs\""));
+    let audit = fs::read_to_string(moon_home.join("moon/logs/audit.log")).expect("read audit");
+    assert!(audit.contains("idle_secs=300 targets=2 archived=1 failed=0"));
+    assert!(audit.contains("\"phase\":\"watchdog\",\"status\":\"failed\""));
+}
+
+#[test]
+#[cfg(not(windows))]
+fn moon_watch_once_distills_oldest_pending_archive_day_first() {
+    let tmp = tempdir().expect("tempdir");
+    let moon_home = tmp.path().join("moon");
+    let sessions_dir = tmp.path().join("sessions");
+    fs::create_dir_all(moon_home.join("archives/raw")).expect("mkdir archives raw");
+    fs::create_dir_all(moon_home.join("archives/mlib")).expect("mkdir archives mlib");
+    fs::create_dir_all(moon_home.join("memory")).expect("mkdir memory");
+    fs::create_dir_all(moon_home.join("moon/logs")).expect("mkdir logs");
+    fs::create_dir_all(&sessions_dir).expect("mkdir sessions");
+    fs::write(
+        sessions_dir.join("s1.json"),
+        "{\"decision\":\"distill ordering\"}\n",
+    )
+    .expect("write session");
+
+    let old_archive = moon_home.join("archives/raw/old.jsonl");
+    let new_archive = moon_home.join("archives/raw/new.jsonl");
+    let old_projection = moon_home.join("archives/mlib/old.md");
+    let new_projection = moon_home.join("archives/mlib/new.md");
+    fs::write(&old_archive, "{\"session\":\"old\"}\n").expect("write old archive");
+    fs::write(&new_archive, "{\"session\":\"new\"}\n").expect("write new archive");
+    fs::write(&old_projection, "- [user] old projection\n").expect("write old projection");
+    fs::write(&new_projection, "- [user] new projection\n").expect("write new projection");
+
+    let ledger = format!(
+        concat!(
+            "{{\"session_id\":\"old\",\"source_path\":\"/tmp/old.jsonl\",\"archive_path\":\"{}\",\"content_hash\":\"aaa\",\"created_at_epoch_secs\":86400,\"indexed_collection\":\"history\",\"indexed\":true}}\n",
+            "{{\"session_id\":\"new\",\"source_path\":\"/tmp/new.jsonl\",\"archive_path\":\"{}\",\"content_hash\":\"bbb\",\"created_at_epoch_secs\":172800,\"indexed_collection\":\"history\",\"indexed\":true}}\n"
+        ),
+        old_archive.display(),
+        new_archive.display()
+    );
+    fs::write(moon_home.join("archives/ledger.jsonl"), ledger).expect("write ledger");
+
+    let qmd = tmp.path().join("qmd");
+    let qmd_log = tmp.path().join("qmd.log");
+    write_fake_qmd(&qmd);
+    let openclaw = tmp.path().join("openclaw");
+    write_fake_openclaw(&openclaw);
+
+    assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("MOON_HOME", &moon_home)
+        .env("OPENCLAW_SESSIONS_DIR", &sessions_dir)
+        .env("QMD_BIN", &qmd)
+        .env("MOON_TEST_QMD_LOG", &qmd_log)
+        .env("OPENCLAW_BIN", &openclaw)
+        .env("MOON_DISTILL_PROVIDER", "local")
+        .env("MOON_DISTILL_MAX_PER_CYCLE", "1")
+        .env("MOON_COOLDOWN_SECS", "0")
+        .env("MOON_RETENTION_COLD_DAYS", "99999")
+        .arg("watch")
+        .arg("--once")
+        .assert()
+        .success();
+
+    let distilled = read_distilled_archive_paths(&moon_home.join("moon/state/moon_state.json"));
+    assert_eq!(distilled.len(), 1);
+    assert!(distilled.contains(&old_archive.to_string_lossy().to_string()));
+    assert!(!distilled.contains(&new_archive.to_string_lossy().to_string()));
+
+    let ledger = fs::read_to_string(moon_home.join("archives/ledger.jsonl")).expect("read ledger");
+    let rows = ledger
+        .lines()
+        .map(|line| serde_json::from_str::<Value>(line).expect("parse ledger row"))
+        .collect::<Vec<_>>();
+    let provenance = |session: &str| {
+        rows.iter()
+            .find(|row| row["session_id"] == session)
+            .expect("ledger row")
+            .get("distill")
+            .cloned()
+    };
+    let old = provenance("old").expect("old archive distill provenance");
+    assert_eq!(old["provider"], "l1-normaliser");
+    assert_eq!(old["chunk_count"], 1);
+    assert!(old["distilled_at_epoch_secs"].as_u64().is_some());
+    assert!(
+        old["summary_path"]
+            .as_str()
+            .is_some_and(|path| path.ends_with("1970-01-02.md"))
+    );
+    assert!(provenance("new").is_none());
+
+    let qmd_calls = fs::read_to_string(&qmd_log).expect("read qmd log");
+    assert!(qmd_calls.contains(&format!(
+        "collection add {} --name memory --mask *.md",
+        moon_home.join("memory").display()
+    )));
+}
+
+#[test]
+#[cfg(not(windows))]
+fn moon_watch_once_distill_selection_skips_unindexed_missing_flagged_and_already_distilled() {
+    let tmp = tempdir().expect("tempdir");
+    let moon_home = tmp.path().join("moon");
+    let sessions_dir = tmp.path().join("sessions");
+    fs::create_dir_all(moon_home.join("archives/raw")).expect("mkdir archives raw");
+    fs::create_dir_all(moon_home.join("archives/mlib")).expect("mkdir archives mlib");
+    fs::create_dir_all(moon_home.join("memory")).expect("mkdir memory");
+    fs::create_dir_all(moon_home.join("moon/logs")).expect("mkdir logs");
     fs::create_dir_all(moon_home.join("state")).expect("mkdir state");
     fs::create_dir_all(&sessions_dir).expect("mkdir sessions");
     fs::write(
@@ -579,7 +1461,14 @@ fn moon_watch_once_distill_selection_skips_unindexed_missing_and_already_distill
     let unindexed = moon_home.join("archives/raw/unindexed.jsonl");
     let already = moon_home.join("archives/raw/already.jsonl");
     let missing = moon_home.join("archives/raw/missing.jsonl");
+    let flagged = moon_home.join("archives/raw/flagged.jsonl");
     fs::write(&eligible, "{\"session\":\"eligible\"}\n").expect("write eligible");
+    fs::write(&flagged, "{\"session\":\"flagged\"}\n").expect("write flagged");
+    fs::write(
+        moon_home.join("archives/mlib/flagged.md"),
+        "- [user] flagged log dump\n",
+    )
+    .expect("write flagged projection");
     fs::write(&unindexed, "{\"session\":\"unindexed\"}\n").expect("write unindexed");
     fs::write(&already, "{\"session\":\"already\"}\n").expect("write already");
     fs::write(
@@ -603,12 +1492,14 @@ fn moon_watch_once_distill_selection_skips_unindexed_missing_and_already_distill
             "{{\"session_id\":\"eligible\",\"source_path\":\"/tmp/e.jsonl\",\"archive_path\":\"{}\",\"content_hash\":\"a\",\"created_at_epoch_secs\":86400,\"indexed_collection\":\"history\",\"indexed\":true}}\n",
             "{{\"session_id\":\"unindexed\",\"source_path\":\"/tmp/u.jsonl\",\"archive_path\":\"{}\",\"content_hash\":\"b\",\"created_at_epoch_secs\":86401,\"indexed_collection\":\"history\",\"indexed\":false}}\n",
             "{{\"session_id\":\"already\",\"source_path\":\"/tmp/a.jsonl\",\"archive_path\":\"{}\",\"content_hash\":\"c\",\"created_at_epoch_secs\":86402,\"indexed_collection\":\"history\",\"indexed\":true}}\n",
-            "{{\"session_id\":\"missing\",\"source_path\":\"/tmp/m.jsonl\",\"archive_path\":\"{}\",\"content_hash\":\"d\",\"created_at_epoch_secs\":86403,\"indexed_collection\":\"history\",\"indexed\":true}}\n"
+            "{{\"session_id\":\"missing\",\"source_path\":\"/tmp/m.jsonl\",\"archive_path\":\"{}\",\"content_hash\":\"d\",\"created_at_epoch_secs\":86403,\"indexed_collection\":\"history\",\"indexed\":true}}\n",
+            "{{\"session_id\":\"flagged\",\"source_path\":\"/tmp/f.jsonl\",\"archive_path\":\"{}\",\"content_hash\":\"f\",\"created_at_epoch_secs\":86404,\"indexed_collection\":\"history\",\"indexed\":true,\"distill_skip\":true}}\n"
         ),
         eligible.display(),
         unindexed.display(),
         already.display(),
-        missing.display()
+        missing.display(),
+        flagged.display()
     );
     fs::write(moon_home.join("archives/ledger.jsonl"), ledger).expect("write ledger");
 
@@ -645,6 +1536,74 @@ fn moon_watch_once_distill_selection_skips_unindexed_missing_and_already_distill
     assert!(distilled.contains(&already.to_string_lossy().to_string()));
     assert!(!distilled.contains(&unindexed.to_string_lossy().to_string()));
     assert!(!distilled.contains(&missing.to_string_lossy().to_string()));
+    assert!(!distilled.contains(&flagged.to_string_lossy().to_string()));
+}
+
+#[test]
+#[cfg(not(windows))]
+fn moon_watch_dry_run_explains_distill_trigger() {
+    let tmp = tempdir().expect("tempdir");
+    let moon_home = tmp.path().join("moon");
+    let sessions_dir = tmp.path().join("sessions");
+    fs::create_dir_all(moon_home.join("archives/raw")).expect("mkdir archives raw");
+    fs::create_dir_all(moon_home.join("archives/mlib")).expect("mkdir archives mlib");
+    fs::create_dir_all(moon_home.join("moon/logs")).expect("mkdir logs");
+    fs::create_dir_all(&sessions_dir).expect("mkdir sessions");
+    fs::write(
+        sessions_dir.join("s1.json"),
+        "{\"decision\":\"still chatting\"}\n",
+    )
+    .expect("write session");
+
+    let archive_path = moon_home.join("archives/raw/pending.jsonl");
+    fs::write(&archive_path, "{\"session\":\"pending\"}\n").expect("write archive");
+    fs::write(
+        moon_home.join("archives/mlib/pending.md"),
+        "- [user] Decision: distill when idle.\n",
+    )
+    .expect("write projection");
+    let ledger = format!(
+        "{{\"session_id\":\"pending\",\"source_path\":\"/tmp/pending.jsonl\",\"archive_path\":\"{}\",\"content_hash\":\"abc\",\"created_at_epoch_secs\":86400,\"indexed_collection\":\"history\",\"indexed\":true}}\n",
+        archive_path.display()
+    );
+    fs::write(moon_home.join("archives/ledger.jsonl"), ledger).expect("write ledger");
+
+    let qmd = tmp.path().join("qmd");
+    write_fake_qmd(&qmd);
+    let openclaw = tmp.path().join("openclaw");
+    write_fake_openclaw(&openclaw);
+
+    let run = |mode: &str, idle_secs: &str| {
+        assert_cmd::cargo::cargo_bin_cmd!("moon")
+            .current_dir(tmp.path())
+            .env("MOON_HOME", &moon_home)
+            .env("OPENCLAW_SESSIONS_DIR", &sessions_dir)
+            .env("QMD_BIN", &qmd)
+            .env("OPENCLAW_BIN", &openclaw)
+            .env("MOON_DISTILL_MODE", mode)
+            .env("MOON_DISTILL_IDLE_SECS", idle_secs)
+            .arg("watch")
+            .arg("--once")
+            .arg("--dry-run")
+            .assert()
+            .success()
+    };
+
+    run("auto", "3600").stdout(contains(
+        "distill.trigger=skipped reason=not-idle idle_secs=3600",
+    ));
+    run("manual", "0").stdout(contains("distill.trigger=skipped reason=mode-manual"));
+    run("auto", "0")
+        .stdout(contains("distill.mode=auto"))
+        .stdout(contains("distill.trigger=ready"))
+        .stdout(contains(
+            "distill.selection=dry-run: would distill 1 archive(s)",
+        ));
+
+    assert!(
+        !moon_home.join("moon/state/moon_state.json").exists(),
+        "dry-run should not record a distill"
+    );
 }
 
 #[test]
@@ -868,13 +1827,11 @@ fn moon_watch_once_emits_ai_warning_when_ledger_is_invalid() {
         .stderr(contains("action=read-ledger"));
 }
 
-#[test]
-#[cfg(not(windows))]
-fn moon_watch_once_cleans_up_expired_distilled_archives_after_grace_period() {
-    let tmp = tempdir().expect("tempdir");
-    let moon_home = tmp.path().join("moon");
-    let sessions_dir = tmp.path().join("sessions");
-    let qmd_log = tmp.path().join("qmd.log");
+/// Cold, distilled archive for `agent:main:discord:channel:retained`; `with_summary` also writes
+/// its projection and daily-memory session block so retention may delete it.
+fn write_expired_distilled_archive(root: &Path, with_summary: bool) -> (PathBuf, PathBuf, PathBuf) {
+    let moon_home = root.join("moon");
+    let sessions_dir = root.join("sessions");
     fs::create_dir_all(moon_home.join("archives")).expect("mkdir archives");
     fs::create_dir_all(moon_home.join("memory")).expect("mkdir memory");
     fs::create_dir_all(moon_home.join("moon/logs")).expect("mkdir logs");
@@ -890,6 +1847,15 @@ fn moon_watch_once_cleans_up_expired_distilled_archives_after_grace_period() {
     let archive_path = moon_home.join("archives/expired.json");
     fs::write(&archive_path, "{\"session\":\"old\"}\n").expect("write archive");
     let archive_path_str = archive_path.to_string_lossy().to_string();
+    if with_summary {
+        fs::write(moon_home.join("archives/expired.md"), "# projection\n")
+            .expect("write projection");
+        fs::write(
+            moon_home.join("memory/1970-01-01.md"),
+            "<!-- MOON_SESSION_BEGIN:agent:main:discord:channel:retained -->\n## Session\n",
+        )
+        .expect("write daily memory");
+    }
 
     let ledger_record = format!(
         "{{\"session_id\":\"agent:main:discord:channel:retained\",\"source_path\":\"/tmp/source.jsonl\",\"archive_path\":\"{}\",\"content_hash\":\"deadbeef\",\"created_at_epoch_secs\":1,\"indexed_collection\":\"history\",\"indexed\":true}}\n",
@@ -914,6 +1880,17 @@ fn moon_watch_once_cleans_up_expired_distilled_archives_after_grace_period() {
     fs::create_dir_all(moon_home.join("moon/state")).expect("mkdir state");
     fs::write(moon_home.join("moon/state/moon_state.json"), state).expect("write state");
 
+    (moon_home, sessions_dir, archive_path)
+}
+
+#[test]
+#[cfg(not(windows))]
+fn moon_watch_once_cleans_up_expired_distilled_archives_after_grace_period() {
+    let tmp = tempdir().expect("tempdir");
+    let qmd_log = tmp.path().join("qmd.log");
+    let (moon_home, sessions_dir, archive_path) = write_expired_distilled_archive(tmp.path(), true);
+    let archive_path_str = archive_path.to_string_lossy().to_string();
+
     let qmd = tmp.path().join("qmd");
     write_fake_qmd(&qmd);
     let openclaw = tmp.path().join("openclaw");
@@ -940,16 +1917,167 @@ fn moon_watch_once_cleans_up_expired_distilled_archives_after_grace_period() {
     let ledger = fs::read_to_string(moon_home.join("archives/ledger.jsonl")).expect("read ledger");
     assert!(!ledger.contains(&archive_path_str));
 
+    // The channel keeps resolving, now to the distilled summary that outlived the archive.
+    let map: Value = serde_json::from_str(
+        &fs::read_to_string(moon_home.join("continuity/channel_archive_map.json"))
+            .expect("read map"),
+    )
+    .expect("parse map");
+    let mapped = &map["agent:main:discord:channel:retained"];
+    assert_eq!(
+        mapped["archive_path"],
+        moon_home
+            .join("memory/1970-01-01.md")
+            .to_string_lossy()
+            .as_ref()
+    );
+    assert_eq!(mapped["retired_archive_path"], archive_path_str.as_str());
+
+    let state_raw =
+        fs::read_to_string(moon_home.join("moon/state/moon_state.json")).expect("state");
+    assert!(!state_raw.contains(&archive_path_str));
+
+    // The purge is queued for the next cycle's batched qmd sync and survives in state.
+    assert!(state_raw.contains("\"pending_qmd_sync\": [\n    \"history\""));
+
+    assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("MOON_HOME", &moon_home)
+        .env("OPENCLAW_SESSIONS_DIR", &sessions_dir)
+        .env("QMD_BIN", &qmd)
+        .env("OPENCLAW_BIN", &openclaw)
+        .env("MOON_TEST_QMD_LOG", &qmd_log)
+        .arg("watch")
+        .arg("--once")
+        .assert()
+        .success()
+        .stdout(contains("qmd_sync.result=ok collections=history"));
+    let state_raw =
+        fs::read_to_string(moon_home.join("moon/state/moon_state.json")).expect("state");
+    assert!(state_raw.contains("\"pending_qmd_sync\": []"));
+}
+
+#[test]
+#[cfg(not(windows))]
+fn moon_gc_restores_and_purges_retention_trash() {
+    let tmp = tempdir().expect("tempdir");
+    let (moon_home, sessions_dir, archive_path) = write_expired_distilled_archive(tmp.path(), true);
+    let archive_path_str = archive_path.to_string_lossy().to_string();
+    let projection_path = moon_home.join("archives/expired.md");
+
+    let qmd = tmp.path().join("qmd");
+    write_fake_qmd(&qmd);
+    let openclaw = tmp.path().join("openclaw");
+    write_fake_openclaw(&openclaw);
+
+    let moon = |args: &[&str]| {
+        let mut cmd = assert_cmd::cargo::cargo_bin_cmd!("moon");
+        cmd.current_dir(tmp.path())
+            .env("MOON_HOME", &moon_home)
+            .env("OPENCLAW_SESSIONS_DIR", &sessions_dir)
+            .env("QMD_BIN", &qmd)
+            .env("OPENCLAW_BIN", &openclaw)
+            .env(
+                "MOON_TEST_CURRENT_JSON",
+                r#"{"sessionId":"agent:main:main","usage":{"totalTokens":120},"limits":{"maxTokens":100000}}"#,
+            )
+            .args(args);
+        cmd
+    };
+
+    moon(&["watch", "--once"]).assert().success();
+    assert!(!archive_path.exists());
+    assert!(!projection_path.exists());
+    let manifest = fs::read_to_string(moon_home.join("archives/trash/manifest.jsonl"))
+        .expect("trash manifest");
+    assert_eq!(manifest.lines().count(), 2);
+
+    moon(&["gc", "restore", &archive_path_str])
+        .assert()
+        .success()
+        .stdout(contains("ledger_restored=true"))
+        .stdout(contains("map_restored=1"));
+    assert!(archive_path.exists());
+    assert!(projection_path.exists());
     let map = fs::read_to_string(moon_home.join("continuity/channel_archive_map.json"))
         .expect("read map");
-    assert!(!map.contains(&archive_path_str));
+    assert!(map.contains(&format!("\"archive_path\": \"{archive_path_str}\"")));
+    assert!(!map.contains("retired_archive_path"));
+    let ledger = fs::read_to_string(moon_home.join("archives/ledger.jsonl")).expect("read ledger");
+    assert!(ledger.contains(&archive_path_str));
+    let state_raw =
+        fs::read_to_string(moon_home.join("moon/state/moon_state.json")).expect("state");
+    assert!(state_raw.contains(&archive_path_str));
+    assert!(!moon_home.join("archives/trash/manifest.jsonl").exists());
+
+    moon(&["gc", "restore", &archive_path_str])
+        .assert()
+        .code(2)
+        .stdout(contains("no trashed file matches"));
+
+    moon(&["watch", "--once"]).assert().success();
+    assert!(!archive_path.exists());
+    moon(&["gc", "purge"])
+        .assert()
+        .success()
+        .stdout(contains("purged=0"))
+        .stdout(contains("kept=2"));
+    moon(&["gc", "purge", "--all"])
+        .assert()
+        .success()
+        .stdout(contains("purged=2"));
+    assert!(!moon_home.join("archives/trash/manifest.jsonl").exists());
+}
+
+#[test]
+#[cfg(not(windows))]
+fn moon_watch_once_retention_refuses_to_delete_archive_without_distilled_summary() {
+    let tmp = tempdir().expect("tempdir");
+    let (moon_home, sessions_dir, archive_path) =
+        write_expired_distilled_archive(tmp.path(), false);
+    let qmd = tmp.path().join("qmd");
+    write_fake_qmd(&qmd);
+    let openclaw = tmp.path().join("openclaw");
+    write_fake_openclaw(&openclaw);
+
+    let run = |force: &str| {
+        assert_cmd::cargo::cargo_bin_cmd!("moon")
+            .current_dir(tmp.path())
+            .env("MOON_HOME", &moon_home)
+            .env("OPENCLAW_SESSIONS_DIR", &sessions_dir)
+            .env("QMD_BIN", &qmd)
+            .env("OPENCLAW_BIN", &openclaw)
+            .env("MOON_RETENTION_FORCE", force)
+            .env(
+                "MOON_TEST_CURRENT_JSON",
+                r#"{"sessionId":"agent:main:main","usage":{"totalTokens":120},"limits":{"maxTokens":100000}}"#,
+            )
+            .args(["watch", "--once"])
+            .assert()
+            .success()
+    };
 
+    run("false")
+        .stderr(contains("MOON_WARN code=RETENTION_UNDISTILLED"))
+        .stderr(contains("reason=projection-missing"));
+    assert!(archive_path.exists());
     let state_raw =
         fs::read_to_string(moon_home.join("moon/state/moon_state.json")).expect("state");
-    assert!(!state_raw.contains(&archive_path_str));
+    assert!(state_raw.contains("retention_protected_archives"));
+    assert!(state_raw.contains(archive_path.to_string_lossy().as_ref()));
+
+    // Already-protected archives are not re-warned every cycle.
+    let second = run("false");
+    let stderr = String::from_utf8_lossy(&second.get_output().stderr);
+    assert!(!stderr.contains("RETENTION_UNDISTILLED"));
+    assert!(archive_path.exists());
 
-    let qmd_calls = fs::read_to_string(&qmd_log).expect("qmd calls");
-    assert!(qmd_calls.lines().any(|line| line.trim() == "update"));
+    // Without a summary or continuity record there is nothing to repoint the channel at.
+    run("true").stdout(contains("map_repointed=0 map_removed=1"));
+    assert!(!archive_path.exists());
+    let map = fs::read_to_string(moon_home.join("continuity/channel_archive_map.json"))
+        .expect("read map");
+    assert!(!map.contains("agent:main:discord:channel:retained"));
 }
 
 #[test]
@@ -1147,3 +2275,356 @@ fn moon_watch_context_policy_retriggers_after_cooldown_when_above_trigger_ratio(
     let fourth_count = compact_calls();
     assert_eq!(fourth_count, 3);
 }
+
+#[test]
+#[cfg(not(windows))]
+fn moon_watch_once_injects_memory_primer_into_new_sessions_only() {
+    let tmp = tempdir().expect("tempdir");
+    let moon_home = tmp.path().join("moon");
+    let sessions_dir = tmp.path().join("sessions");
+    fs::create_dir_all(moon_home.join("archives")).expect("mkdir archives");
+    fs::create_dir_all(moon_home.join("memory")).expect("mkdir memory");
+    fs::create_dir_all(moon_home.join("moon/logs")).expect("mkdir logs");
+    fs::create_dir_all(&sessions_dir).expect("mkdir sessions");
+    fs::write(
+        moon_home.join("MEMORY.md"),
+        "# MEMORY\n\n## User Preferences\n- Keep answers concise.\n",
+    )
+    .expect("write memory");
+
+    let qmd = tmp.path().join("qmd");
+    write_fake_qmd(&qmd);
+    let openclaw = tmp.path().join("openclaw");
+    write_fake_openclaw(&openclaw);
+    let send_log = tmp.path().join("chat-send.log");
+
+    let run = |sessions_json: &str| {
+        assert_cmd::cargo::cargo_bin_cmd!("moon")
+            .current_dir(tmp.path())
+            .env("MOON_HOME", &moon_home)
+            .env("OPENCLAW_SESSIONS_DIR", &sessions_dir)
+            .env("QMD_BIN", &qmd)
+            .env("OPENCLAW_BIN", &openclaw)
+            .env("MOON_MEMORY_INJECT_ON_NEW_SESSION", "true")
+            .env("MOON_TEST_SESSIONS_JSON", sessions_json)
+            .env("MOON_TEST_COMPACT_LOG", &send_log)
+            .arg("watch")
+            .arg("--once")
+            .assert()
+            .success()
+    };
+
+    run(r#"{"sessions":[{"key":"agent:main:old","totalTokens":100,"contextTokens":10000}]}"#)
+        .stdout(contains("memory_primer.result=seeded sessions=1"));
+    assert!(
+        !send_log.exists(),
+        "seeding must not prime existing sessions"
+    );
+
+    run(concat!(
+        r#"{"sessions":[{"key":"agent:main:old","totalTokens":100,"contextTokens":10000},"#,
+        r#"{"key":"agent:main:new","totalTokens":50,"contextTokens":10000}]}"#
+    ))
+    .stdout(contains(
+        "memory_primer.result=new_sessions=1 injected=1 failed=0",
+    ));
+
+    let log = fs::read_to_string(&send_log).expect("read chat.send log");
+    assert!(log.contains("agent:main:new"));
+    assert!(log.contains("MOON_MEMORY_PRIMER"));
+    assert!(!log.contains("agent:main:old"));
+}
+
+#[test]
+#[cfg(not(windows))]
+fn moon_watch_once_memory_primer_gives_up_after_failures_and_skips_blocked_chat_send() {
+    let tmp = tempdir().expect("tempdir");
+    let moon_home = tmp.path().join("moon");
+    let sessions_dir = tmp.path().join("sessions");
+    fs::create_dir_all(moon_home.join("moon/logs")).expect("mkdir logs");
+    fs::create_dir_all(&sessions_dir).expect("mkdir sessions");
+    fs::write(
+        moon_home.join("MEMORY.md"),
+        "# MEMORY\n\n## User Preferences\n- Keep answers concise.\n",
+    )
+    .expect("write memory");
+    fs::write(
+        sessions_dir.join("sessions.json"),
+        r#"{"agent:main:old": {"sessionId":"old"}, "agent:main:flaky": {"sessionId":"flaky"}}"#,
+    )
+    .expect("write sessions map");
+
+    let qmd = tmp.path().join("qmd");
+    write_fake_qmd(&qmd);
+    let openclaw = tmp.path().join("openclaw");
+    write_fake_openclaw(&openclaw);
+    let failing_openclaw = tmp.path().join("openclaw-no-send");
+    fs::write(
+        &failing_openclaw,
+        format!(
+            "#!/usr/bin/env bash\nif [[ \"${{3:-}}\" == \"chat.send\" ]]; then exit 1; fi\nexec {} \"$@\"\n",
+            openclaw.display()
+        ),
+    )
+    .expect("write failing openclaw");
+    use std::os::unix::fs::PermissionsExt;
+    let mut perms = fs::metadata(&failing_openclaw)
+        .expect("metadata")
+        .permissions();
+    perms.set_mode(0o755);
+    fs::set_permissions(&failing_openclaw, perms).expect("chmod");
+    let send_log = tmp.path().join("chat-send.log");
+    let state_file = moon_home.join("moon/state/moon_state.json");
+
+    let run = |sessions_json: &str, openclaw_bin: &Path, allow_chat_send: &str| {
+        let assert = assert_cmd::cargo::cargo_bin_cmd!("moon")
+            .current_dir(tmp.path())
+            .env("MOON_HOME", &moon_home)
+            .env("OPENCLAW_SESSIONS_DIR", &sessions_dir)
+            .env("QMD_BIN", &qmd)
+            .env("OPENCLAW_BIN", openclaw_bin)
+            .env("MOON_MEMORY_INJECT_ON_NEW_SESSION", "true")
+            .env("MOON_ALLOW_CHAT_SEND", allow_chat_send)
+            .env("MOON_TEST_SESSIONS_JSON", sessions_json)
+            .env("MOON_TEST_COMPACT_LOG", &send_log)
+            .arg("watch")
+            .arg("--once")
+            .assert()
+            .success();
+        String::from_utf8_lossy(&assert.get_output().stdout).to_string()
+    };
+    let old_only =
+        r#"{"sessions":[{"key":"agent:main:old","totalTokens":100,"contextTokens":10000}]}"#;
+    let with_flaky = concat!(
+        r#"{"sessions":[{"key":"agent:main:old","totalTokens":100,"contextTokens":10000},"#,
+        r#"{"key":"agent:main:flaky","totalTokens":50,"contextTokens":10000}]}"#
+    );
+
+    assert!(run(old_only, &openclaw, "true").contains("memory_primer.result=seeded sessions=1"));
+    for attempt in 1..=3 {
+        let stdout = run(with_flaky, &failing_openclaw, "true");
+        let gave_up = usize::from(attempt == 3);
+        assert!(
+            stdout.contains(&format!(
+                "memory_primer.result=new_sessions=1 injected=0 failed=1 gave_up={gave_up}"
+            )),
+            "attempt {attempt}: {stdout}"
+        );
+    }
+    assert!(
+        run(with_flaky, &failing_openclaw, "true").contains("memory_primer.result=new_sessions=0")
+    );
+    let state: Value =
+        serde_json::from_str(&fs::read_to_string(&state_file).expect("read state")).expect("state");
+    assert!(state["memory_primed_sessions"]["agent:main:flaky"].is_u64());
+    assert_eq!(state["memory_primer_failures"], serde_json::json!({}));
+
+    let with_blocked = concat!(
+        r#"{"sessions":[{"key":"agent:main:old","totalTokens":100,"contextTokens":10000},"#,
+        r#"{"key":"agent:main:blocked","totalTokens":50,"contextTokens":10000}]}"#
+    );
+    fs::write(
+        sessions_dir.join("sessions.json"),
+        r#"{"agent:main:old": {"sessionId":"old"}, "agent:main:blocked": {"sessionId":"blocked"}}"#,
+    )
+    .expect("rewrite sessions map");
+    let stdout = run(with_blocked, &openclaw, "false");
+    assert!(
+        stdout.contains("memory_primer.result=new_sessions=1 injected=0 reason=chat-send-disabled"),
+        "stdout: {stdout}"
+    );
+    assert!(
+        !send_log.exists(),
+        "no primer may be sent: chat.send is blocked"
+    );
+
+    // `flaky` left sessions.json, so its primed marker is dropped.
+    let state: Value =
+        serde_json::from_str(&fs::read_to_string(&state_file).expect("read state")).expect("state");
+    let primed = state["memory_primed_sessions"]
+        .as_object()
+        .expect("primed sessions");
+    assert!(primed.contains_key("agent:main:old"));
+    assert!(primed.contains_key("agent:main:blocked"));
+    assert!(!primed.contains_key("agent:main:flaky"));
+}
+
+#[test]
+#[cfg(not(windows))]
+fn moon_watch_once_records_session_rollover_continuity() {
+    let tmp = tempdir().expect("tempdir");
+    let moon_home = tmp.path().join("moon");
+    let sessions_dir = tmp.path().join("sessions");
+    let state_file = tmp.path().join("state/moon_state.json");
+    fs::create_dir_all(moon_home.join("archives")).expect("mkdir archives");
+    fs::create_dir_all(moon_home.join("memory")).expect("mkdir memory");
+    fs::create_dir_all(moon_home.join("moon/logs")).expect("mkdir logs");
+    fs::create_dir_all(moon_home.join("continuity")).expect("mkdir continuity");
+    fs::create_dir_all(state_file.parent().expect("state parent")).expect("mkdir state");
+    fs::create_dir_all(&sessions_dir).expect("mkdir sessions");
+    fs::write(sessions_dir.join("sess-new.jsonl"), "{}\n").expect("write session");
+    fs::write(
+        sessions_dir.join("sessions.json"),
+        r#"{"agent:main:discord:channel:ops": {"sessionId":"sess-new"}}"#,
+    )
+    .expect("write sessions map");
+    fs::write(
+        &state_file,
+        r#"{"session_ids":{"agent:main:discord:channel:ops":"sess-old"}}"#,
+    )
+    .expect("write state");
+    fs::write(
+        moon_home.join("continuity/channel_archive_map.json"),
+        r#"{"agent:main:discord:channel:ops":{"channel_key":"agent:main:discord:channel:ops","source_path":"/tmp/sess-old.jsonl","archive_path":"/tmp/raw/sess-old.jsonl","updated_at_epoch_secs":1700000000}}"#,
+    )
+    .expect("write channel map");
+
+    let qmd = tmp.path().join("qmd");
+    write_fake_qmd(&qmd);
+    let openclaw = tmp.path().join("openclaw");
+    write_fake_openclaw(&openclaw);
+
+    assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("MOON_HOME", &moon_home)
+        .env("MOON_STATE_FILE", &state_file)
+        .env("OPENCLAW_SESSIONS_DIR", &sessions_dir)
+        .env("QMD_BIN", &qmd)
+        .env("OPENCLAW_BIN", &openclaw)
+        .arg("watch")
+        .arg("--once")
+        .assert()
+        .success()
+        .stdout(contains("continuity.target_session_id=sess-new"));
+
+    let state: Value = serde_json::from_str(&fs::read_to_string(&state_file).expect("read state"))
+        .expect("parse state");
+    assert_eq!(
+        state["session_ids"]["agent:main:discord:channel:ops"],
+        "sess-new"
+    );
+
+    assert_cmd::cargo::cargo_bin_cmd!("moon")
+        .current_dir(tmp.path())
+        .env("MOON_HOME", &moon_home)
+        .args(["continuity", "show", "agent:main:discord:channel:ops"])
+        .assert()
+        .success()
+        .stdout(contains("records=1"))
+        .stdout(contains(
+            "reason=rollover sess-old -> sess-new archive=/tmp/raw/sess-old.jsonl summary=/tmp/raw/sess-old.jsonl",
+        ));
+}
+
+fn serve_embeddings_once(listener: std::net::TcpListener) -> std::thread::JoinHandle<Value> {
+    use std::io::{BufRead, BufReader, Read, Write};
+    std::thread::spawn(move || {
+        let (stream, _) = listener.accept().expect("accept");
+        let mut reader = BufReader::new(stream);
+        let mut content_length = 0usize;
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).expect("read header");
+            if line.trim().is_empty() {
+                break;
+            }
+            if let Some(value) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                content_length = value.trim().parse().expect("content length");
+            }
+        }
+        let mut body = vec![0u8; content_length];
+        reader.read_exact(&mut body).expect("read body");
+        let body: Value = serde_json::from_slice(&body).expect("json body");
+        let inputs = body["input"].as_array().map(Vec::len).unwrap_or(0);
+        let data = (0..inputs)
+            .map(|i| serde_json::json!({"index": i, "embedding": [1.0, 0.0, i as f64]}))
+            .collect::<Vec<_>>();
+        let payload = serde_json::json!({ "data": data }).to_string();
+        let response = format!(
+            "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{payload}",
+            payload.len()
+        );
+        reader
+            .get_mut()
+            .write_all(response.as_bytes())
+            .expect("respond");
+        body
+    })
+}
+
+#[test]
+#[cfg(not(windows))]
+fn moon_watch_once_maintains_remote_vector_index_for_new_and_retired_projections() {
+    let tmp = tempdir().expect("tempdir");
+    let (moon_home, sessions_dir, archive_path) = write_expired_distilled_archive(tmp.path(), true);
+    let retired_projection = archive_path.with_extension("md").display().to_string();
+
+    let vectors_dir = moon_home.join("archives/vectors");
+    fs::create_dir_all(&vectors_dir).expect("mkdir vectors");
+    fs::write(
+        vectors_dir.join("meta.json"),
+        r#"{"provider":"openai-compatible","model":"embed-a","dimensions":3,"updated_at_epoch_secs":1}"#,
+    )
+    .expect("write meta");
+    fs::write(
+        vectors_dir.join("index.jsonl"),
+        format!(
+            "{}\n",
+            serde_json::json!({
+                "projection_path": retired_projection,
+                "section": "preamble",
+                "content_hash": "00",
+                "model": "embed-a",
+                "dimensions": 3,
+                "vector": [0.0, 1.0, 0.0]
+            })
+        ),
+    )
+    .expect("write index");
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind");
+    let base_url = format!("http://{}", listener.local_addr().expect("addr"));
+    let server = serve_embeddings_once(listener);
+
+    let qmd = tmp.path().join("qmd");
+    write_fake_qmd(&qmd);
+    let openclaw = tmp.path().join("openclaw");
+    write_fake_openclaw(&openclaw);
+
+    let run = |trigger_ratio: &str| {
+        assert_cmd::cargo::cargo_bin_cmd!("moon")
+            .current_dir(tmp.path())
+            .env("MOON_HOME", &moon_home)
+            .env("OPENCLAW_SESSIONS_DIR", &sessions_dir)
+            .env("QMD_BIN", &qmd)
+            .env("OPENCLAW_BIN", &openclaw)
+            .env("MOON_TRIGGER_RATIO", trigger_ratio)
+            .env("MOON_EMBED_PROVIDER", "openai-compatible")
+            .env("MOON_EMBED_MODEL", "embed-a")
+            .env("MOON_EMBED_BASE_URL", &base_url)
+            .env("MOON_EMBED_REQUESTS_PER_MINUTE", "0")
+            .env("AI_API_KEY", "test-key")
+            .env(
+                "MOON_TEST_CURRENT_JSON",
+                r#"{"sessionId":"agent:main:main","usage":{"totalTokens":120},"limits":{"maxTokens":100000}}"#,
+            )
+            .arg("watch")
+            .arg("--once")
+            .assert()
+            .success()
+    };
+
+    run("0.9").stdout(contains("vectors_removed=1"));
+    let index = fs::read_to_string(vectors_dir.join("index.jsonl")).expect("index");
+    assert!(!index.contains(&retired_projection));
+
+    run("0.00002").stdout(contains(
+        "embed.result=incremental provider=openai-compatible selected=1 embedded=1",
+    ));
+
+    let body = server.join().expect("server");
+    assert_eq!(body["model"], "embed-a");
+
+    let index = fs::read_to_string(vectors_dir.join("index.jsonl")).expect("index");
+    assert!(index.contains("archives/mlib/"));
+}
@@ -32,6 +32,7 @@ fn run_install(temp_root: &Path, state_dir: &Path, config_path: &Path, openclaw_
     assert_cmd::cargo::cargo_bin_cmd!("moon")
         .current_dir(temp_root)
         .env("OPENCLAW_STATE_DIR", state_dir)
+        .env("HOME", state_dir)
         .env("OPENCLAW_CONFIG_PATH", config_path)
         .env("OPENCLAW_BIN", openclaw_bin)
         .arg("install")
@@ -69,6 +70,7 @@ fn status_fails_when_runtime_reports_untracked_provenance() {
     assert_cmd::cargo::cargo_bin_cmd!("moon")
         .current_dir(tmp.path())
         .env("OPENCLAW_STATE_DIR", &state_dir)
+        .env("HOME", &state_dir)
         .env("OPENCLAW_CONFIG_PATH", &config_path)
         .env("OPENCLAW_BIN", &fake_openclaw)
         .arg("verify")
@@ -118,6 +120,7 @@ fn status_tolerates_missing_install_record_when_diagnostics_are_clean() {
     assert_cmd::cargo::cargo_bin_cmd!("moon")
         .current_dir(tmp.path())
         .env("OPENCLAW_STATE_DIR", &state_dir)
+        .env("HOME", &state_dir)
         .env("OPENCLAW_CONFIG_PATH", &config_path)
         .env("OPENCLAW_BIN", &fake_openclaw)
         .arg("verify")
@@ -156,6 +159,7 @@ fn verify_strict_fails_when_runtime_reports_untracked_provenance() {
     assert_cmd::cargo::cargo_bin_cmd!("moon")
         .current_dir(tmp.path())
         .env("OPENCLAW_STATE_DIR", &state_dir)
+        .env("HOME", &state_dir)
         .env("OPENCLAW_CONFIG_PATH", &config_path)
         .env("OPENCLAW_BIN", &fake_openclaw)
         .args(["verify", "--strict"])